@@ -0,0 +1,810 @@
+use crate::scan::Location;
+use crate::{Definitions, Metadata, Schema, Type};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use thiserror::Error;
+
+/// An error from parsing a schema with [`Schema::from_dsl`].
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub struct DslError {
+    /// A description of the error.
+    pub message: String,
+
+    /// Where in the input the error is.
+    pub location: Location,
+}
+
+impl fmt::Display for DslError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+// Words with a meaning of their own, which must be quoted to be used as the
+// name of a definition.
+const KEYWORDS: &[&str] = &[
+    "any",
+    "boolean",
+    "enum",
+    "float32",
+    "float64",
+    "int16",
+    "int32",
+    "int8",
+    "map",
+    "string",
+    "struct",
+    "timestamp",
+    "type",
+    "uint16",
+    "uint32",
+    "uint8",
+    "union",
+];
+
+impl Schema {
+    /// Parses a schema from a compact syntax, meant for writing schemas by
+    /// hand, such as in tests.
+    ///
+    /// Each form of schema has its own syntax:
+    ///
+    /// | Syntax                         | Form                                       |
+    /// |--------------------------------|--------------------------------------------|
+    /// | `any`                          | Empty                                      |
+    /// | `string`, `uint32`, ...        | Type, with any of the RFC 8927 type names  |
+    /// | `enum { a, b }`                | Enum                                       |
+    /// | `[T]`                          | Elements                                   |
+    /// | `struct { a: T, b?: U }`       | Properties, with `b` optional              |
+    /// | `struct { a: T, .. }`          | Properties, with `additionalProperties`    |
+    /// | `map<T>`                       | Values                                     |
+    /// | `union(kind) { a: S, b: S }`   | Discriminator, with `kind` as the tag      |
+    /// | `name`                         | Ref                                        |
+    ///
+    /// A `?` after a schema makes it nullable, as in `string?`. Definitions
+    /// come before the root schema, as `type name = T;`. Names that aren't
+    /// made of letters, digits, and underscores, or that are keywords like
+    /// `string` when used as refs, are written as JSON strings, as in
+    /// `"first name": string`. Commas may trail, and `//` starts a comment.
+    ///
+    /// There's no syntax for metadata, or for the non-standard keywords of
+    /// the `extensions` feature.
+    ///
+    /// Like [`Schema::from_serde_schema`], this doesn't check that the
+    /// schema is valid, such as that its refs have definitions; see
+    /// [`Schema::validate`] for that.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_dsl("
+    ///     type tag = enum { new, used };
+    ///
+    ///     struct {
+    ///         name: string,
+    ///         age?: uint32,
+    ///         tags: [tag],
+    ///         nickname: string?, // may be null
+    ///     }
+    /// ").unwrap();
+    ///
+    /// assert_eq!(
+    ///     json!({
+    ///         "definitions": { "tag": { "enum": ["new", "used"] }},
+    ///         "properties": {
+    ///             "name": { "type": "string" },
+    ///             "tags": { "elements": { "ref": "tag" }},
+    ///             "nickname": { "type": "string", "nullable": true }
+    ///         },
+    ///         "optionalProperties": { "age": { "type": "uint32" }}
+    ///     }),
+    ///     serde_json::to_value(schema.into_serde_schema()).unwrap(),
+    /// );
+    ///
+    /// let err = Schema::from_dsl("struct { name: string, name: uint8 }").unwrap_err();
+    /// assert_eq!("1:24: duplicate property: \"name\"", err.to_string());
+    /// ```
+    pub fn from_dsl(input: &str) -> Result<Schema, DslError> {
+        Parser {
+            chars: input.chars().collect(),
+            pos: 0,
+        }
+        .parse_root()
+    }
+
+    /// Prints the schema in the syntax read by [`Schema::from_dsl`].
+    ///
+    /// Returns `None` if the schema has anything that syntax can't express:
+    /// metadata, definitions other than at the root, or, with the
+    /// `extensions` feature, any of its non-standard keywords.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    ///
+    /// let schema = Schema::from_dsl("struct { id: uint32, \"e-mail\"?: string?, .. }").unwrap();
+    /// assert_eq!(
+    ///     "struct {\n    id: uint32,\n    \"e-mail\"?: string?,\n    ..\n}\n",
+    ///     schema.to_dsl().unwrap(),
+    /// );
+    /// ```
+    pub fn to_dsl(&self) -> Option<String> {
+        if !self.metadata().is_empty() {
+            return None;
+        }
+
+        let mut out = String::new();
+        for (name, definition) in self.definitions() {
+            out.push_str("type ");
+            out.push_str(&dsl_name(name, true));
+            out.push_str(" = ");
+            write_schema(definition, 0, &mut out)?;
+            out.push_str(";\n\n");
+        }
+
+        write_form(self, 0, &mut out)?;
+        out.push('\n');
+        Some(out)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn parse_root(&mut self) -> Result<Schema, DslError> {
+        let mut definitions = Definitions::new();
+        while self.eat_keyword("type") {
+            self.skip_space();
+            let location = self.location();
+            let name = self.name()?;
+            self.expect('=')?;
+            let definition = self.parse_schema()?;
+            self.expect(';')?;
+
+            if definitions.contains_key(&name) {
+                return Err(error_at(
+                    location,
+                    format!("duplicate definition: {:?}", name),
+                ));
+            }
+
+            definitions.insert(name, definition);
+        }
+
+        let mut schema = self.parse_schema()?;
+        self.skip_space();
+        if self.pos < self.chars.len() {
+            return Err(self.error("expected end of input".to_owned()));
+        }
+
+        *schema.definitions_mut() = definitions;
+        Ok(schema)
+    }
+
+    fn parse_schema(&mut self) -> Result<Schema, DslError> {
+        self.skip_space();
+
+        let mut schema = match self.chars.get(self.pos) {
+            Some('[') => {
+                self.pos += 1;
+                let elements = self.parse_schema()?;
+                self.expect(']')?;
+
+                Schema::Elements {
+                    definitions: Definitions::new(),
+                    metadata: Metadata::new(),
+                    nullable: false,
+                    elements: Box::new(elements),
+                }
+            }
+            Some('"') => Schema::Ref {
+                definitions: Definitions::new(),
+                metadata: Metadata::new(),
+                nullable: false,
+                ref_: self.string()?,
+            },
+            Some(&c) if is_ident_start(c) => {
+                let word = self.ident();
+                match &word[..] {
+                    "any" => Schema::Empty {
+                        definitions: Definitions::new(),
+                        metadata: Metadata::new(),
+                    },
+                    "struct" => self.parse_struct()?,
+                    "enum" => self.parse_enum()?,
+                    "union" => self.parse_union()?,
+                    "map" => {
+                        self.expect('<')?;
+                        let values = self.parse_schema()?;
+                        self.expect('>')?;
+
+                        Schema::Values {
+                            definitions: Definitions::new(),
+                            metadata: Metadata::new(),
+                            nullable: false,
+                            values: Box::new(values),
+                            #[cfg(feature = "extensions")]
+                            keys: None,
+                        }
+                    }
+                    _ => match parse_type(&word) {
+                        Some(type_) => Schema::Type {
+                            definitions: Definitions::new(),
+                            metadata: Metadata::new(),
+                            nullable: false,
+                            type_,
+                        },
+                        None => Schema::Ref {
+                            definitions: Definitions::new(),
+                            metadata: Metadata::new(),
+                            nullable: false,
+                            ref_: word,
+                        },
+                    },
+                }
+            }
+            _ => return Err(self.error("expected a schema".to_owned())),
+        };
+
+        self.skip_space();
+        let location = self.location();
+        if self.eat('?') {
+            if let Schema::Empty { .. } = schema {
+                return Err(error_at(location, "`any` is always nullable".to_owned()));
+            }
+
+            crate::expand::set_nullable(&mut schema);
+        }
+
+        Ok(schema)
+    }
+
+    fn parse_struct(&mut self) -> Result<Schema, DslError> {
+        let mut properties = BTreeMap::new();
+        let mut optional_properties = BTreeMap::new();
+        let mut additional_properties = false;
+
+        self.expect('{')?;
+        loop {
+            if self.eat('}') {
+                break;
+            }
+
+            self.skip_space();
+            if self.chars[self.pos..].starts_with(&['.', '.']) {
+                self.pos += 2;
+                additional_properties = true;
+                self.eat(',');
+                self.expect('}')?;
+                break;
+            }
+
+            let location = self.location();
+            let name = self.name()?;
+            let optional = self.eat('?');
+            self.expect(':')?;
+            let sub_schema = self.parse_schema()?;
+
+            if properties.contains_key(&name) || optional_properties.contains_key(&name) {
+                return Err(error_at(
+                    location,
+                    format!("duplicate property: {:?}", name),
+                ));
+            }
+
+            if optional {
+                optional_properties.insert(name, sub_schema);
+            } else {
+                properties.insert(name, sub_schema);
+            }
+
+            if !self.eat(',') {
+                self.expect('}')?;
+                break;
+            }
+        }
+
+        Ok(Schema::Properties {
+            definitions: Definitions::new(),
+            metadata: Metadata::new(),
+            nullable: false,
+            properties_is_present: !properties.is_empty() || optional_properties.is_empty(),
+            properties,
+            optional_properties,
+            additional_properties,
+        })
+    }
+
+    fn parse_enum(&mut self) -> Result<Schema, DslError> {
+        let mut enum_ = BTreeSet::new();
+
+        self.expect('{')?;
+        loop {
+            if self.eat('}') {
+                break;
+            }
+
+            let location = self.location();
+            let value = self.name()?;
+            if !enum_.insert(value.clone()) {
+                return Err(error_at(
+                    location,
+                    format!("duplicate enum value: {:?}", value),
+                ));
+            }
+
+            if !self.eat(',') {
+                self.expect('}')?;
+                break;
+            }
+        }
+
+        Ok(Schema::Enum {
+            definitions: Definitions::new(),
+            metadata: Metadata::new(),
+            nullable: false,
+            enum_,
+        })
+    }
+
+    fn parse_union(&mut self) -> Result<Schema, DslError> {
+        let mut mapping = BTreeMap::new();
+
+        self.expect('(')?;
+        let discriminator = self.name()?;
+        self.expect(')')?;
+
+        self.expect('{')?;
+        loop {
+            if self.eat('}') {
+                break;
+            }
+
+            let location = self.location();
+            let tag = self.name()?;
+            self.expect(':')?;
+            let sub_schema = self.parse_schema()?;
+
+            if mapping.contains_key(&tag) {
+                return Err(error_at(location, format!("duplicate tag: {:?}", tag)));
+            }
+
+            mapping.insert(tag, sub_schema);
+
+            if !self.eat(',') {
+                self.expect('}')?;
+                break;
+            }
+        }
+
+        Ok(Schema::Discriminator {
+            definitions: Definitions::new(),
+            metadata: Metadata::new(),
+            nullable: false,
+            discriminator,
+            mapping,
+        })
+    }
+
+    // A name, either bare or as a JSON string.
+    fn name(&mut self) -> Result<String, DslError> {
+        self.skip_space();
+        match self.chars.get(self.pos) {
+            Some('"') => self.string(),
+            Some(&c) if is_ident_start(c) => Ok(self.ident()),
+            _ => Err(self.error("expected a name".to_owned())),
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        let start = self.pos;
+        while self.chars.get(self.pos).copied().is_some_and(is_ident) {
+            self.pos += 1;
+        }
+
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn string(&mut self) -> Result<String, DslError> {
+        let location = self.location();
+        let start = self.pos;
+
+        self.pos += 1;
+        loop {
+            match self.chars.get(self.pos) {
+                Some('"') => break,
+                Some('\\') => self.pos += 2,
+                Some(_) => self.pos += 1,
+                None => return Err(error_at(location, "unterminated string".to_owned())),
+            }
+        }
+
+        self.pos += 1;
+        let literal: String = self.chars[start..self.pos].iter().collect();
+        serde_json::from_str(&literal)
+            .map_err(|_| error_at(location, format!("invalid string: {}", literal)))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.skip_space();
+
+        let start = self.pos;
+        if self.ident() == keyword {
+            true
+        } else {
+            self.pos = start;
+            false
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_space();
+        if self.chars.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), DslError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", c)))
+        }
+    }
+
+    fn skip_space(&mut self) {
+        loop {
+            match (self.chars.get(self.pos), self.chars.get(self.pos + 1)) {
+                (Some(c), _) if c.is_whitespace() => self.pos += 1,
+                (Some('/'), Some('/')) => {
+                    while self.chars.get(self.pos).is_some_and(|&c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn location(&self) -> Location {
+        let before = &self.chars[..self.pos.min(self.chars.len())];
+        let line_start = before.iter().rposition(|&c| c == '\n').map_or(0, |i| i + 1);
+
+        Location {
+            line: before.iter().filter(|&&c| c == '\n').count() + 1,
+            column: before.len() - line_start + 1,
+        }
+    }
+
+    fn error(&self, message: String) -> DslError {
+        error_at(self.location(), message)
+    }
+}
+
+fn error_at(location: Location, message: String) -> DslError {
+    DslError { message, location }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn parse_type(name: &str) -> Option<Type> {
+    Some(match name {
+        "boolean" => Type::Boolean,
+        "int8" => Type::Int8,
+        "uint8" => Type::Uint8,
+        "int16" => Type::Int16,
+        "uint16" => Type::Uint16,
+        "int32" => Type::Int32,
+        "uint32" => Type::Uint32,
+        "float32" => Type::Float32,
+        "float64" => Type::Float64,
+        "string" => Type::String,
+        "timestamp" => Type::Timestamp,
+        _ => return None,
+    })
+}
+
+// A name as written in the syntax: bare if possible, or else as a JSON
+// string. Refs also need quoting if they're keywords.
+fn dsl_name(name: &str, is_ref: bool) -> String {
+    let is_bare = name.chars().next().is_some_and(is_ident_start)
+        && name.chars().all(is_ident)
+        && !(is_ref && KEYWORDS.contains(&name));
+
+    if is_bare {
+        name.to_owned()
+    } else {
+        serde_json::to_string(name).expect("strings always serialize")
+    }
+}
+
+fn write_schema(schema: &Schema, indent: usize, out: &mut String) -> Option<()> {
+    if !schema.metadata().is_empty() || !schema.definitions().is_empty() {
+        return None;
+    }
+
+    write_form(schema, indent, out)
+}
+
+// Writes a schema, without regard to its definitions and metadata.
+fn write_form(schema: &Schema, indent: usize, out: &mut String) -> Option<()> {
+    match schema {
+        Schema::Empty { .. } => out.push_str("any"),
+        Schema::Ref { ref_, .. } => out.push_str(&dsl_name(ref_, true)),
+        Schema::Type { type_, .. } => out.push_str(match type_ {
+            Type::Boolean => "boolean",
+            Type::Int8 => "int8",
+            Type::Uint8 => "uint8",
+            Type::Int16 => "int16",
+            Type::Uint16 => "uint16",
+            Type::Int32 => "int32",
+            Type::Uint32 => "uint32",
+            Type::Float32 => "float32",
+            Type::Float64 => "float64",
+            Type::String => "string",
+            Type::Timestamp => "timestamp",
+            #[cfg(feature = "extensions")]
+            Type::Custom(_) => return None,
+        }),
+        Schema::Enum { enum_, .. } => {
+            let values: Vec<_> = enum_.iter().map(|value| dsl_name(value, false)).collect();
+            out.push_str("enum { ");
+            out.push_str(&values.join(", "));
+            out.push_str(" }");
+        }
+        #[cfg(feature = "extensions")]
+        Schema::IntEnum { .. } => return None,
+        Schema::Elements { elements, .. } => {
+            out.push('[');
+            write_schema(elements, indent, out)?;
+            out.push(']');
+        }
+        Schema::Properties {
+            properties,
+            optional_properties,
+            additional_properties,
+            ..
+        } => {
+            if properties.is_empty() && optional_properties.is_empty() && !additional_properties {
+                out.push_str("struct {}");
+            } else {
+                out.push_str("struct {\n");
+
+                let entries = properties
+                    .iter()
+                    .map(|(name, sub_schema)| (name, "", sub_schema))
+                    .chain(
+                        optional_properties
+                            .iter()
+                            .map(|(name, sub_schema)| (name, "?", sub_schema)),
+                    );
+
+                for (name, optional, sub_schema) in entries {
+                    push_indent(indent + 1, out);
+                    out.push_str(&dsl_name(name, false));
+                    out.push_str(optional);
+                    out.push_str(": ");
+                    write_schema(sub_schema, indent + 1, out)?;
+                    out.push_str(",\n");
+                }
+
+                if *additional_properties {
+                    push_indent(indent + 1, out);
+                    out.push_str("..\n");
+                }
+
+                push_indent(indent, out);
+                out.push('}');
+            }
+        }
+        Schema::Values {
+            values,
+            #[cfg(feature = "extensions")]
+            keys,
+            ..
+        } => {
+            #[cfg(feature = "extensions")]
+            if keys.is_some() {
+                return None;
+            }
+
+            out.push_str("map<");
+            write_schema(values, indent, out)?;
+            out.push('>');
+        }
+        Schema::Discriminator {
+            discriminator,
+            mapping,
+            ..
+        } => {
+            out.push_str("union(");
+            out.push_str(&dsl_name(discriminator, false));
+            out.push_str(") {");
+
+            if !mapping.is_empty() {
+                out.push('\n');
+                for (tag, sub_schema) in mapping {
+                    push_indent(indent + 1, out);
+                    out.push_str(&dsl_name(tag, false));
+                    out.push_str(": ");
+                    write_schema(sub_schema, indent + 1, out)?;
+                    out.push_str(",\n");
+                }
+
+                push_indent(indent, out);
+            }
+
+            out.push('}');
+        }
+    }
+
+    if schema.nullable() && !matches!(schema, Schema::Empty { .. }) {
+        out.push('?');
+    }
+
+    Some(())
+}
+
+fn push_indent(indent: usize, out: &mut String) {
+    for _ in 0..indent {
+        out.push_str("    ");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scan::Location;
+    use crate::Schema;
+    use serde_json::json;
+
+    fn from_json(value: serde_json::Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn round_trips() {
+        let schema = from_json(json!({
+            "definitions": {
+                "string": { "enum": ["a b", "c"], "nullable": true },
+                "node": {
+                    "properties": { "children": { "elements": { "ref": "node" }}},
+                    "optionalProperties": { "label": { "ref": "string" }},
+                    "additionalProperties": true
+                }
+            },
+            "discriminator": "kind",
+            "mapping": {
+                "tree": { "properties": { "root": { "ref": "node", "nullable": true }}},
+                "empty": { "properties": {}},
+                "counts": {
+                    "properties": {
+                        "by-name": { "values": { "type": "uint32" }},
+                        "any": {}
+                    }
+                }
+            },
+            "nullable": true
+        }));
+
+        let dsl = schema.to_dsl().unwrap();
+        assert_eq!(
+            r#"type node = struct {
+    children: [node],
+    label?: "string",
+    ..
+};
+
+type "string" = enum { "a b", c }?;
+
+union(kind) {
+    counts: struct {
+        any: any,
+        "by-name": map<uint32>,
+    },
+    empty: struct {},
+    tree: struct {
+        root: node?,
+    },
+}?
+"#,
+            dsl
+        );
+
+        assert_eq!(schema, Schema::from_dsl(&dsl).unwrap());
+    }
+
+    #[test]
+    fn parses_all_types() {
+        let schema = Schema::from_dsl(
+            "[struct { a: boolean, b: int8, c: uint8, d: int16, e: uint16, f: int32,
+               g: uint32, h: float32, i: float64, j: string, k: timestamp }]",
+        )
+        .unwrap();
+
+        let types = ["boolean", "int8", "uint8", "int16", "uint16", "int32"]
+            .iter()
+            .chain(&["uint32", "float32", "float64", "string", "timestamp"])
+            .zip('a'..)
+            .map(|(type_, name)| (name.to_string(), json!({ "type": type_ })))
+            .collect::<serde_json::Map<_, _>>();
+
+        assert_eq!(
+            from_json(json!({ "elements": { "properties": types }})),
+            schema
+        );
+    }
+
+    #[test]
+    fn only_optional_properties() {
+        assert_eq!(
+            from_json(json!({ "optionalProperties": { "a": {} }})),
+            Schema::from_dsl("struct { a?: any, }").unwrap()
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        let error = |input| {
+            let err = Schema::from_dsl(input).unwrap_err();
+            (err.location.line, err.location.column, err.message)
+        };
+
+        assert_eq!((1, 1, "expected a schema".into()), error(""));
+        assert_eq!((1, 8, "expected `]`".into()), error("[string"));
+        assert_eq!(
+            (1, 8, "expected end of input".into()),
+            error("string string")
+        );
+        assert_eq!((1, 4, "`any` is always nullable".into()), error("any?"));
+        assert_eq!(
+            (2, 6, "duplicate definition: \"a\"".into()),
+            error("type a = any;\ntype a = any;\na")
+        );
+        assert_eq!(
+            (1, 11, "duplicate enum value: \"a\"".into()),
+            error("enum { a, \"a\" }")
+        );
+        assert_eq!(
+            (1, 26, "duplicate tag: \"a\"".into()),
+            error("union(t) { a: struct {}, a: struct {} }")
+        );
+        assert_eq!((1, 1, "unterminated string".into()), error("\"abc"));
+        assert_eq!(
+            (1, 10, "expected a name".into()),
+            error("struct { : string }")
+        );
+        assert_eq!(
+            Location {
+                line: 3,
+                column: 14
+            },
+            Schema::from_dsl("// a comment\nstruct {\n    a: strin g\n}")
+                .unwrap_err()
+                .location
+        );
+    }
+
+    #[test]
+    fn prints_only_what_it_can() {
+        assert_eq!(
+            None,
+            from_json(json!({ "type": "string", "metadata": { "a": 1 }})).to_dsl()
+        );
+        assert_eq!(
+            None,
+            from_json(json!({ "elements": { "definitions": {}, "metadata": { "a": 1 }}})).to_dsl()
+        );
+        assert_eq!(Some("any\n".into()), from_json(json!({})).to_dsl());
+    }
+}
@@ -0,0 +1,683 @@
+use crate::Schema;
+use thiserror::Error;
+
+/// Errors that may arise from the helpers for building and modifying schemas
+/// in code, such as [`Schema::add_variant`].
+///
+/// These helpers check the same invariants as [`Schema::validate`], but do so
+/// eagerly, at the point where a sub-schema is being added.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum SchemaEditError {
+    /// Indicates the helper was called on a schema of the wrong form.
+    ///
+    /// The contained string is the name of the form the helper expects.
+    ///
+    /// ```
+    /// use jtd::{Schema, SchemaEditError};
+    ///
+    /// let mut schema = Schema::Empty {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     Err(SchemaEditError::WrongForm("discriminator")),
+    ///     schema.add_variant("foo", Schema::Empty {
+    ///         definitions: Default::default(),
+    ///         metadata: Default::default(),
+    ///     }),
+    /// );
+    /// ```
+    #[error("schema is not of the {0} form")]
+    WrongForm(&'static str),
+
+    /// Indicates a variant added to a discriminator has `nullable` set to
+    /// `true`.
+    ///
+    /// This corresponds to [`SchemaValidateError::NullableMapping`][`crate::SchemaValidateError::NullableMapping`].
+    #[error("nullable schema in mapping")]
+    NullableMapping,
+
+    /// Indicates a variant added to a discriminator isn't a
+    /// [`Schema::Properties`].
+    ///
+    /// This corresponds to [`SchemaValidateError::NonPropertiesMapping`][`crate::SchemaValidateError::NonPropertiesMapping`].
+    #[error("non-properties schema in mapping")]
+    NonPropertiesMapping,
+
+    /// Indicates a variant added to a discriminator has a `properties` or
+    /// `optional_properties` that contains the discriminator.
+    ///
+    /// This corresponds to [`SchemaValidateError::RepeatedDiscriminator`][`crate::SchemaValidateError::RepeatedDiscriminator`].
+    #[error("discriminator redefined in mapping: {0:?}")]
+    RepeatedDiscriminator(String),
+
+    /// Indicates the schema has no property with the given name.
+    ///
+    /// ```
+    /// use jtd::{Schema, SchemaEditError};
+    ///
+    /// let mut schema = Schema::Properties {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    ///     nullable: false,
+    ///     properties: Default::default(),
+    ///     optional_properties: Default::default(),
+    ///     properties_is_present: true,
+    ///     additional_properties: false,
+    /// };
+    ///
+    /// assert_eq!(
+    ///     Err(SchemaEditError::NoSuchProperty("foo".into())),
+    ///     schema.make_optional("foo"),
+    /// );
+    /// ```
+    #[error("no such property: {0:?}")]
+    NoSuchProperty(String),
+
+    /// Indicates the edit would have removed the last value from an enum.
+    ///
+    /// This corresponds to [`SchemaValidateError::EmptyEnum`][`crate::SchemaValidateError::EmptyEnum`].
+    ///
+    /// ```
+    /// use jtd::{Schema, SchemaEditError};
+    /// use serde_json::json;
+    ///
+    /// let mut schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "enum": ["foo"]
+    /// })).unwrap()).unwrap();
+    ///
+    /// assert_eq!(Err(SchemaEditError::EmptyEnum), schema.remove_enum_value("foo"));
+    /// ```
+    #[error("empty enum")]
+    EmptyEnum,
+}
+
+impl Schema {
+    /// Adds a variant to a discriminator-form schema.
+    ///
+    /// The variant must satisfy the same constraints [`Schema::validate`]
+    /// places on values in `mapping`: it must be of the properties form, it
+    /// must not be nullable, and it must not define the discriminator property
+    /// itself.
+    ///
+    /// If the schema already had a variant for `tag`, that variant is replaced
+    /// and the old one is returned.
+    ///
+    /// ```
+    /// use jtd::{Schema, SchemaEditError};
+    ///
+    /// let mut schema = Schema::Discriminator {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    ///     nullable: false,
+    ///     discriminator: "type".into(),
+    ///     mapping: Default::default(),
+    /// };
+    ///
+    /// let variant = Schema::Properties {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    ///     nullable: false,
+    ///     properties: Default::default(),
+    ///     optional_properties: Default::default(),
+    ///     properties_is_present: true,
+    ///     additional_properties: false,
+    /// };
+    ///
+    /// assert_eq!(Ok(None), schema.add_variant("foo", variant.clone()));
+    /// assert_eq!(Some(&variant), schema.variant("foo"));
+    ///
+    /// // Variants that violate the mapping invariants are rejected.
+    /// assert_eq!(
+    ///     Err(SchemaEditError::NonPropertiesMapping),
+    ///     schema.add_variant("bar", Schema::Empty {
+    ///         definitions: Default::default(),
+    ///         metadata: Default::default(),
+    ///     }),
+    /// );
+    /// ```
+    pub fn add_variant(
+        &mut self,
+        tag: impl Into<String>,
+        variant: Schema,
+    ) -> Result<Option<Schema>, SchemaEditError> {
+        if let Self::Discriminator {
+            discriminator,
+            mapping,
+            ..
+        } = self
+        {
+            if let Self::Properties {
+                nullable,
+                properties,
+                optional_properties,
+                ..
+            } = &variant
+            {
+                if *nullable {
+                    return Err(SchemaEditError::NullableMapping);
+                }
+
+                if properties.contains_key(discriminator)
+                    || optional_properties.contains_key(discriminator)
+                {
+                    return Err(SchemaEditError::RepeatedDiscriminator(
+                        discriminator.clone(),
+                    ));
+                }
+            } else {
+                return Err(SchemaEditError::NonPropertiesMapping);
+            }
+
+            Ok(mapping.insert(tag.into(), variant))
+        } else {
+            Err(SchemaEditError::WrongForm("discriminator"))
+        }
+    }
+
+    /// Gets the variant of a discriminator-form schema for a given tag.
+    ///
+    /// Returns `None` if the schema isn't of the discriminator form, or if it
+    /// has no variant for `tag`.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    ///
+    /// let schema = Schema::Discriminator {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    ///     nullable: false,
+    ///     discriminator: "type".into(),
+    ///     mapping: Default::default(),
+    /// };
+    ///
+    /// assert_eq!(None, schema.variant("foo"));
+    /// ```
+    pub fn variant(&self, tag: &str) -> Option<&Schema> {
+        if let Self::Discriminator { mapping, .. } = self {
+            mapping.get(tag)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the tags of a discriminator-form schema, in sorted order.
+    ///
+    /// Returns an empty iterator if the schema isn't of the discriminator form.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    ///
+    /// let variant = Schema::Properties {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    ///     nullable: false,
+    ///     properties: Default::default(),
+    ///     optional_properties: Default::default(),
+    ///     properties_is_present: true,
+    ///     additional_properties: false,
+    /// };
+    ///
+    /// let mut schema = Schema::Discriminator {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    ///     nullable: false,
+    ///     discriminator: "type".into(),
+    ///     mapping: Default::default(),
+    /// };
+    ///
+    /// schema.add_variant("foo", variant.clone()).unwrap();
+    /// schema.add_variant("bar", variant.clone()).unwrap();
+    ///
+    /// assert_eq!(vec!["bar", "foo"], schema.tags().collect::<Vec<_>>());
+    /// ```
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        let mapping = if let Self::Discriminator { mapping, .. } = self {
+            Some(mapping)
+        } else {
+            None
+        };
+
+        mapping.into_iter().flat_map(|m| m.keys().map(|k| &k[..]))
+    }
+
+    /// Specializes a discriminator-form schema to one of its variants, as a
+    /// standalone schema.
+    ///
+    /// The result is the variant for `tag`, with the discriminator added as a
+    /// required property whose only allowed value is `tag`. It accepts exactly
+    /// the instances the discriminator accepts that have that tag, so it can
+    /// be used to validate or document each variant on its own. The
+    /// discriminator's `definitions` are copied over so that any `ref` in the
+    /// variant still resolves.
+    ///
+    /// Returns `None` if the schema isn't of the discriminator form, or if it
+    /// has no variant for `tag`.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "definitions": { "id": { "type": "uint32" }},
+    ///     "discriminator": "event",
+    ///     "mapping": {
+    ///         "click": { "properties": { "target": { "ref": "id" }}},
+    ///         "scroll": { "properties": { "offset": { "type": "int32" }}}
+    ///     }
+    /// })).unwrap()).unwrap();
+    ///
+    /// let click = schema.specialize_tag("click").unwrap();
+    /// assert_eq!(
+    ///     json!({
+    ///         "definitions": { "id": { "type": "uint32" }},
+    ///         "properties": {
+    ///             "event": { "enum": ["click"] },
+    ///             "target": { "ref": "id" }
+    ///         }
+    ///     }),
+    ///     serde_json::to_value(click.into_serde_schema()).unwrap()
+    /// );
+    ///
+    /// assert_eq!(None, schema.specialize_tag("hover"));
+    /// ```
+    pub fn specialize_tag(&self, tag: &str) -> Option<Schema> {
+        let (definitions, discriminator, variant) = match self {
+            Self::Discriminator {
+                definitions,
+                discriminator,
+                mapping,
+                ..
+            } => (definitions, discriminator, mapping.get(tag)?),
+            _ => return None,
+        };
+
+        let mut specialized = variant.clone();
+
+        // Definitions on the variant itself are nearer to its refs than the
+        // discriminator's, so they take precedence.
+        for (name, definition) in definitions {
+            specialized
+                .definitions_mut()
+                .entry(name.clone())
+                .or_insert_with(|| definition.clone());
+        }
+
+        if let Self::Properties {
+            properties,
+            properties_is_present,
+            ..
+        } = &mut specialized
+        {
+            *properties_is_present = true;
+            properties.insert(
+                discriminator.clone(),
+                Self::Enum {
+                    definitions: Default::default(),
+                    metadata: Default::default(),
+                    nullable: false,
+                    enum_: std::iter::once(tag.to_owned()).collect(),
+                },
+            );
+        }
+
+        Some(specialized)
+    }
+
+    /// Adds a required property to a properties-form schema.
+    ///
+    /// If the schema already had a property named `name`, whether required or
+    /// optional, that property is replaced and its old schema is returned. The
+    /// property is always required afterwards, so `properties` and
+    /// `optional_properties` stay disjoint.
+    ///
+    /// ```
+    /// use jtd::{Schema, Type};
+    ///
+    /// let mut schema = Schema::Properties {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    ///     nullable: false,
+    ///     properties: Default::default(),
+    ///     optional_properties: Default::default(),
+    ///     properties_is_present: false,
+    ///     additional_properties: false,
+    /// };
+    ///
+    /// let string = Schema::Type {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    ///     nullable: false,
+    ///     type_: Type::String,
+    /// };
+    ///
+    /// assert_eq!(Ok(None), schema.require("name", string.clone()));
+    ///
+    /// if let Schema::Properties { properties, properties_is_present, .. } = &schema {
+    ///     assert_eq!(Some(&string), properties.get("name"));
+    ///     assert!(properties_is_present);
+    /// }
+    /// ```
+    pub fn require(
+        &mut self,
+        name: impl Into<String>,
+        schema: Schema,
+    ) -> Result<Option<Schema>, SchemaEditError> {
+        if let Self::Properties {
+            properties,
+            optional_properties,
+            properties_is_present,
+            ..
+        } = self
+        {
+            let name = name.into();
+            let prev = optional_properties.remove(&name);
+            let prev = properties.insert(name, schema).or(prev);
+
+            *properties_is_present = true;
+            Ok(prev)
+        } else {
+            Err(SchemaEditError::WrongForm("properties"))
+        }
+    }
+
+    /// Turns a required property of a properties-form schema into an optional
+    /// one.
+    ///
+    /// Calling this on a property that is already optional does nothing.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let mut schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "properties": { "name": { "type": "string" }}
+    /// })).unwrap()).unwrap();
+    ///
+    /// schema.make_optional("name").unwrap();
+    ///
+    /// assert_eq!(
+    ///     Schema::from_serde_schema(serde_json::from_value(json!({
+    ///         "properties": {},
+    ///         "optionalProperties": { "name": { "type": "string" }}
+    ///     })).unwrap()).unwrap(),
+    ///     schema,
+    /// );
+    /// ```
+    pub fn make_optional(&mut self, name: &str) -> Result<(), SchemaEditError> {
+        if let Self::Properties {
+            properties,
+            optional_properties,
+            ..
+        } = self
+        {
+            if let Some(schema) = properties.remove(name) {
+                optional_properties.insert(name.to_owned(), schema);
+                Ok(())
+            } else if optional_properties.contains_key(name) {
+                Ok(())
+            } else {
+                Err(SchemaEditError::NoSuchProperty(name.to_owned()))
+            }
+        } else {
+            Err(SchemaEditError::WrongForm("properties"))
+        }
+    }
+
+    /// Removes a property, whether required or optional, from a
+    /// properties-form schema.
+    ///
+    /// Returns the schema of the removed property, or `None` if there was no
+    /// such property.
+    ///
+    /// If this removes the last property of the schema, then
+    /// `properties_is_present` is set to `true`, so that the schema continues
+    /// to be of the properties form when converted with
+    /// [`Schema::into_serde_schema`].
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let mut schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "optionalProperties": { "name": { "type": "string" }}
+    /// })).unwrap()).unwrap();
+    ///
+    /// assert!(schema.remove_property("name").unwrap().is_some());
+    /// assert!(schema.remove_property("name").unwrap().is_none());
+    ///
+    /// assert_eq!(
+    ///     Schema::from_serde_schema(serde_json::from_value(json!({
+    ///         "properties": {}
+    ///     })).unwrap()).unwrap(),
+    ///     schema,
+    /// );
+    /// ```
+    pub fn remove_property(&mut self, name: &str) -> Result<Option<Schema>, SchemaEditError> {
+        if let Self::Properties {
+            properties,
+            optional_properties,
+            properties_is_present,
+            ..
+        } = self
+        {
+            let prev = properties
+                .remove(name)
+                .or_else(|| optional_properties.remove(name));
+
+            if properties.is_empty() && optional_properties.is_empty() {
+                *properties_is_present = true;
+            }
+
+            Ok(prev)
+        } else {
+            Err(SchemaEditError::WrongForm("properties"))
+        }
+    }
+
+    /// Sets whether a properties-form schema permits additional properties.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let mut schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "properties": {}
+    /// })).unwrap()).unwrap();
+    ///
+    /// schema.set_additional(true).unwrap();
+    ///
+    /// assert!(jtd::validate(&schema, &json!({ "foo": "bar" }), Default::default())
+    ///     .unwrap()
+    ///     .is_empty());
+    /// ```
+    pub fn set_additional(&mut self, additional: bool) -> Result<(), SchemaEditError> {
+        if let Self::Properties {
+            additional_properties,
+            ..
+        } = self
+        {
+            *additional_properties = additional;
+            Ok(())
+        } else {
+            Err(SchemaEditError::WrongForm("properties"))
+        }
+    }
+
+    /// Adds a value to an enum-form schema.
+    ///
+    /// Returns whether the value was newly added.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let mut schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "enum": ["foo"]
+    /// })).unwrap()).unwrap();
+    ///
+    /// assert_eq!(Ok(true), schema.add_enum_value("bar"));
+    /// assert_eq!(Ok(false), schema.add_enum_value("foo"));
+    /// ```
+    pub fn add_enum_value(&mut self, value: impl Into<String>) -> Result<bool, SchemaEditError> {
+        if let Self::Enum { enum_, .. } = self {
+            Ok(enum_.insert(value.into()))
+        } else {
+            Err(SchemaEditError::WrongForm("enum"))
+        }
+    }
+
+    /// Removes a value from an enum-form schema.
+    ///
+    /// Returns whether the value was present. Enums must have at least one
+    /// value, so this refuses to remove the last one.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let mut schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "enum": ["foo", "bar"]
+    /// })).unwrap()).unwrap();
+    ///
+    /// assert_eq!(Ok(true), schema.remove_enum_value("bar"));
+    /// assert_eq!(Ok(false), schema.remove_enum_value("baz"));
+    /// ```
+    pub fn remove_enum_value(&mut self, value: &str) -> Result<bool, SchemaEditError> {
+        if let Self::Enum { enum_, .. } = self {
+            if enum_.len() == 1 && enum_.contains(value) {
+                return Err(SchemaEditError::EmptyEnum);
+            }
+
+            Ok(enum_.remove(value))
+        } else {
+            Err(SchemaEditError::WrongForm("enum"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Schema, SchemaEditError};
+    use serde_json::json;
+
+    #[test]
+    fn add_variant_checks_invariants() {
+        let mut schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "discriminator": "type",
+                "mapping": {}
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let nullable = Schema::from_serde_schema(
+            serde_json::from_value(json!({ "properties": {}, "nullable": true })).unwrap(),
+        )
+        .unwrap();
+
+        let repeated = Schema::from_serde_schema(
+            serde_json::from_value(json!({ "optionalProperties": { "type": {} }})).unwrap(),
+        )
+        .unwrap();
+
+        let ok = Schema::from_serde_schema(
+            serde_json::from_value(json!({ "properties": { "a": { "type": "string" }}})).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Err(SchemaEditError::NullableMapping),
+            schema.add_variant("a", nullable)
+        );
+
+        assert_eq!(
+            Err(SchemaEditError::RepeatedDiscriminator("type".into())),
+            schema.add_variant("a", repeated)
+        );
+
+        assert_eq!(Ok(None), schema.add_variant("a", ok.clone()));
+        assert_eq!(Ok(Some(ok.clone())), schema.add_variant("a", ok));
+        assert_eq!(vec!["a"], schema.tags().collect::<Vec<_>>());
+        assert!(schema.validate().is_ok());
+    }
+
+    #[test]
+    fn specialize_tag_accepts_only_its_variant() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": { "n": { "type": "uint8" }},
+                "discriminator": "type",
+                "mapping": {
+                    "a": { "properties": { "x": { "ref": "n" }}},
+                    "b": { "optionalProperties": { "y": {} }, "additionalProperties": true }
+                },
+                "nullable": true
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let a = schema.specialize_tag("a").unwrap();
+        let b = schema.specialize_tag("b").unwrap();
+        assert!(a.validate().is_ok());
+        assert!(b.validate().is_ok());
+
+        let valid = |schema: &Schema, instance| {
+            crate::validate(schema, &instance, Default::default())
+                .unwrap()
+                .is_empty()
+        };
+
+        for instance in [
+            json!({ "type": "a", "x": 1 }),
+            json!({ "type": "a", "x": 1000 }),
+            json!({ "type": "b", "y": 1, "z": 2 }),
+            json!({ "type": "c" }),
+            json!({ "x": 1 }),
+            json!(null),
+        ] {
+            let tag = instance["type"].as_str();
+            let whole = valid(&schema, instance.clone());
+            assert_eq!(whole && tag == Some("a"), valid(&a, instance.clone()));
+            assert_eq!(whole && tag == Some("b"), valid(&b, instance));
+        }
+
+        assert_eq!(None, schema.specialize_tag("c"));
+        assert_eq!(None, a.specialize_tag("a"));
+    }
+
+    #[test]
+    fn properties_helpers_keep_invariants() {
+        let mut schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "optionalProperties": { "a": {}, "b": {} }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let empty = Schema::Empty {
+            definitions: Default::default(),
+            metadata: Default::default(),
+        };
+
+        // Requiring an optional property moves it, rather than duplicating it.
+        assert_eq!(Ok(Some(empty.clone())), schema.require("a", empty.clone()));
+        assert!(schema.validate().is_ok());
+
+        assert_eq!(Ok(()), schema.make_optional("a"));
+        assert_eq!(Ok(()), schema.make_optional("a"));
+        assert!(schema.validate().is_ok());
+
+        assert_eq!(Ok(Some(empty.clone())), schema.remove_property("a"));
+        assert_eq!(Ok(Some(empty)), schema.remove_property("b"));
+
+        assert_eq!(
+            json!({ "properties": {} }),
+            serde_json::to_value(schema.into_serde_schema()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use crate::Schema;
+use std::collections::{BTreeMap, BTreeSet};
+
+impl Schema {
+    /// Returns a copy of the schema with refs replaced by the definitions
+    /// they refer to, for tools that can't handle refs themselves, such as
+    /// converters to other schema languages or documentation generators.
+    ///
+    /// A ref is left as it is, rather than replaced, if:
+    ///
+    /// * It's recursive, meaning it's within the definition it refers to,
+    /// * It's already within `max_depth` replaced refs, counting the
+    ///   definitions kept in the returned schema as replaced refs, or
+    /// * It refers to a definition that doesn't exist.
+    ///
+    /// So any ref in the returned schema marks where expansion stopped. The
+    /// returned schema keeps the definitions that those refs refer to,
+    /// themselves expanded, and no others, so that it's still valid.
+    ///
+    /// A replaced ref's `nullable` and `metadata` carry over to the
+    /// definition that replaces it, with the ref's metadata taking precedence.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "definitions": {
+    ///         "name": { "type": "string" },
+    ///         "node": {
+    ///             "properties": {
+    ///                 "name": { "ref": "name" },
+    ///                 "children": { "elements": { "ref": "node" }}
+    ///             }
+    ///         }
+    ///     },
+    ///     "properties": {
+    ///         "owner": { "ref": "name", "nullable": true },
+    ///         "tree": { "ref": "node" }
+    ///     }
+    /// })).unwrap()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     json!({
+    ///         "definitions": {
+    ///             "node": {
+    ///                 "properties": {
+    ///                     "name": { "type": "string" },
+    ///                     "children": { "elements": { "ref": "node" }}
+    ///                 }
+    ///             }
+    ///         },
+    ///         "properties": {
+    ///             "owner": { "type": "string", "nullable": true },
+    ///             "tree": {
+    ///                 "properties": {
+    ///                     "name": { "type": "string" },
+    ///                     "children": { "elements": { "ref": "node" }}
+    ///                 }
+    ///             }
+    ///         }
+    ///     }),
+    ///     serde_json::to_value(schema.expanded(8).into_serde_schema()).unwrap()
+    /// );
+    /// ```
+    pub fn expanded(&self, max_depth: usize) -> Schema {
+        let mut expander = Expander {
+            root: self,
+            max_depth,
+            stack: Vec::new(),
+            kept: BTreeSet::new(),
+        };
+
+        let mut expanded = self.clone();
+        expanded.definitions_mut().clear();
+        expander.expand(&mut expanded);
+
+        // Expanding a kept definition may keep refs to yet more definitions,
+        // so this goes on until there are no new ones.
+        let mut definitions = BTreeMap::new();
+        while let Some(name) = expander
+            .kept
+            .iter()
+            .find(|name| !definitions.contains_key(*name))
+            .cloned()
+        {
+            let mut definition = self.definitions()[&name].clone();
+            expander.stack.push(name.clone());
+            expander.expand(&mut definition);
+            expander.stack.pop();
+
+            definitions.insert(name, definition);
+        }
+
+        *expanded.definitions_mut() = definitions;
+        expanded
+    }
+}
+
+struct Expander<'a> {
+    root: &'a Schema,
+    max_depth: usize,
+
+    // The names of the definitions being expanded. Innermost last.
+    stack: Vec<String>,
+
+    // The names of the definitions that refs left in place refer to.
+    kept: BTreeSet<String>,
+}
+
+impl Expander<'_> {
+    fn expand(&mut self, schema: &mut Schema) {
+        match schema {
+            Schema::Ref {
+                ref_,
+                nullable,
+                metadata,
+                ..
+            } => {
+                let definition = match self.root.definitions().get(ref_) {
+                    Some(definition) => definition,
+                    None => return,
+                };
+
+                if self.stack.len() >= self.max_depth || self.stack.contains(ref_) {
+                    self.kept.insert(ref_.clone());
+                    return;
+                }
+
+                let mut expanded = definition.clone();
+                if *nullable {
+                    set_nullable(&mut expanded);
+                }
+
+                expanded.metadata_mut().append(metadata);
+
+                self.stack.push(ref_.clone());
+                self.expand(&mut expanded);
+                self.stack.pop();
+
+                *schema = expanded;
+            }
+            Schema::Elements { elements, .. } => self.expand(elements),
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            } => {
+                for sub_schema in properties.values_mut() {
+                    self.expand(sub_schema);
+                }
+
+                for sub_schema in optional_properties.values_mut() {
+                    self.expand(sub_schema);
+                }
+            }
+            Schema::Values { values, .. } => self.expand(values),
+            Schema::Discriminator { mapping, .. } => {
+                for sub_schema in mapping.values_mut() {
+                    self.expand(sub_schema);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn set_nullable(schema: &mut Schema) {
+    match schema {
+        Schema::Empty { .. } => {}
+        Schema::Ref { nullable, .. }
+        | Schema::Type { nullable, .. }
+        | Schema::Enum { nullable, .. }
+        | Schema::Elements { nullable, .. }
+        | Schema::Properties { nullable, .. }
+        | Schema::Values { nullable, .. }
+        | Schema::Discriminator { nullable, .. } => *nullable = true,
+        #[cfg(feature = "extensions")]
+        Schema::IntEnum { nullable, .. } => *nullable = true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Schema;
+    use serde_json::json;
+
+    fn expanded(schema: serde_json::Value, max_depth: usize) -> serde_json::Value {
+        let schema = Schema::from_serde_schema(serde_json::from_value(schema).unwrap()).unwrap();
+        let expanded = schema.expanded(max_depth);
+        expanded.validate().unwrap();
+
+        serde_json::to_value(expanded.into_serde_schema()).unwrap()
+    }
+
+    #[test]
+    fn stops_at_max_depth() {
+        let schema = json!({
+            "definitions": {
+                "a": { "elements": { "ref": "b" }},
+                "b": { "elements": { "ref": "c" }},
+                "c": { "type": "string", "metadata": { "description": "c" }}
+            },
+            "ref": "a",
+            "metadata": { "description": "root" }
+        });
+
+        assert_eq!(schema, expanded(schema.clone(), 0));
+
+        assert_eq!(
+            json!({
+                "definitions": {
+                    "b": { "elements": { "ref": "c" }},
+                    "c": { "type": "string", "metadata": { "description": "c" }}
+                },
+                "elements": { "ref": "b" },
+                "metadata": { "description": "root" }
+            }),
+            expanded(schema.clone(), 1)
+        );
+
+        assert_eq!(
+            json!({
+                "elements": {
+                    "elements": { "type": "string", "metadata": { "description": "c" }}
+                },
+                "metadata": { "description": "root" }
+            }),
+            expanded(schema, 3)
+        );
+    }
+
+    #[test]
+    fn keeps_mutually_recursive_refs() {
+        assert_eq!(
+            json!({
+                "definitions": {
+                    "even": { "elements": { "elements": { "ref": "even" }}, "nullable": true },
+                    "odd": { "elements": { "elements": { "ref": "odd" }, "nullable": true }}
+                },
+                "properties": {
+                    "even": { "elements": { "elements": { "ref": "even" }}, "nullable": true },
+                    "odd": { "elements": { "elements": { "ref": "odd" }, "nullable": true }}
+                }
+            }),
+            expanded(
+                json!({
+                    "definitions": {
+                        "even": { "elements": { "ref": "odd" }, "nullable": true },
+                        "odd": { "elements": { "ref": "even" }}
+                    },
+                    "properties": {
+                        "even": { "ref": "even" },
+                        "odd": { "ref": "odd" }
+                    }
+                }),
+                usize::MAX
+            )
+        );
+    }
+}
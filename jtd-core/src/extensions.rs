@@ -0,0 +1,333 @@
+//! Extensions to JSON Typedef, available with the `extensions` feature.
+//!
+//! Schemas that use these extensions are not portable: other JSON Typedef
+//! implementations will reject them as invalid.
+
+use crate::{FromSerdeSchemaError, SerdeSchema};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Arc;
+
+/// A constraint on the keys of a values-form schema.
+///
+/// In a schema, this is written as the `keys` keyword alongside `values`, and
+/// takes on one of these forms:
+///
+/// * `{ "enum": [...] }`, which requires that every key be one of the given
+///   values, or
+/// * `{ "type": "string" }`, optionally with a `"pattern"`, which requires
+///   that every key match the given regular expression.
+///
+/// ```
+/// use jtd::{Keys, Schema};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "values": { "type": "uint32" },
+///     "keys": { "type": "string", "pattern": "^[a-z]+$" }
+/// })).unwrap()).unwrap();
+///
+/// let instance = json!({ "abc": 1, "ABC": 2 });
+/// let errors = jtd::validate(&schema, &instance, Default::default()).unwrap();
+/// assert_eq!(1, errors.len());
+/// assert_eq!(vec!["ABC"], errors[0].instance_path);
+/// assert_eq!(vec!["keys", "pattern"], errors[0].schema_path);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Keys {
+    /// Keys must be one of a set of values.
+    Enum(BTreeSet<String>),
+
+    /// Keys may be any string, or, if a pattern is given, any string matching
+    /// that pattern.
+    String { pattern: Option<Pattern> },
+}
+
+/// A regular expression, as used by the `pattern` keyword.
+///
+/// Patterns use the syntax of the [`regex`](https://docs.rs/regex) crate, and
+/// are unanchored: to match an entire string, use `^` and `$`.
+#[derive(Clone)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    /// Compiles a pattern.
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(Self)
+    }
+
+    /// The source text of the pattern.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Whether a string matches the pattern.
+    pub fn is_match(&self, s: &str) -> bool {
+        self.0.is_match(s)
+    }
+}
+
+impl fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Pattern").field(&self.as_str()).finish()
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Pattern {}
+
+impl Keys {
+    /// Converts a [`SerdeSchema`] appearing under the `keys` keyword into a
+    /// [`Keys`].
+    pub fn from_serde_schema(serde_schema: SerdeSchema) -> Result<Self, FromSerdeSchemaError> {
+        match serde_schema {
+            SerdeSchema {
+                enum_: Some(enum_),
+                pattern: None,
+                ..
+            } if is_bare(&serde_schema, true) => {
+                let mut values = BTreeSet::new();
+                for value in enum_ {
+                    if values.contains(&value) {
+                        return Err(FromSerdeSchemaError::DuplicatedEnumValue(value));
+                    }
+
+                    values.insert(value);
+                }
+
+                Ok(Keys::Enum(values))
+            }
+            SerdeSchema {
+                type_: Some(ref type_),
+                ref pattern,
+                ..
+            } if type_ == "string" && is_bare(&serde_schema, false) => {
+                let pattern = match pattern {
+                    Some(pattern) => Some(Pattern::new(pattern).map_err(|err| {
+                        FromSerdeSchemaError::InvalidPattern(pattern.clone(), err.to_string())
+                    })?),
+                    None => None,
+                };
+
+                Ok(Keys::String { pattern })
+            }
+            _ => Err(FromSerdeSchemaError::InvalidKeys),
+        }
+    }
+
+    /// Converts a [`Keys`] into a [`SerdeSchema`].
+    pub fn into_serde_schema(self) -> SerdeSchema {
+        match self {
+            Keys::Enum(enum_) => SerdeSchema {
+                enum_: Some(enum_.into_iter().collect()),
+                ..Default::default()
+            },
+            Keys::String { pattern } => SerdeSchema {
+                type_: Some("string".to_owned()),
+                pattern: pattern.map(|p| p.as_str().to_owned()),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// A registry of types beyond those of RFC 8927, such as `"uuid"` or
+/// `"date"`, each with a callback that checks whether a value is of that type.
+///
+/// Schemas parsed with [`Schema::from_serde_schema_with_extensions`] may use
+/// the names of these types as values of `type`, which would otherwise be
+/// rejected with [`FromSerdeSchemaError::InvalidType`]. Such schemas are
+/// validated by passing the same registry to
+/// [`ValidateOptions::with_type_extensions`].
+///
+/// ```
+/// use jtd::{Schema, TypeExtensions, ValidateOptions};
+/// use serde_json::json;
+///
+/// let types = TypeExtensions::new().with_type("uuid", |value| {
+///     value.as_str().is_some_and(|s| {
+///         s.len() == 36 && s.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+///     })
+/// });
+///
+/// let schema = Schema::from_serde_schema_with_extensions(
+///     serde_json::from_value(json!({ "elements": { "type": "uuid" }})).unwrap(),
+///     &types,
+/// ).unwrap();
+///
+/// let instance = json!(["67e55044-10b1-426f-9247-bb680e5fe0c8", "nope"]);
+/// let options = ValidateOptions::new().with_type_extensions(types);
+/// let errors = jtd::validate(&schema, &instance, options).unwrap();
+///
+/// assert_eq!(1, errors.len());
+/// assert_eq!(vec!["1"], errors[0].instance_path);
+/// assert_eq!(vec!["elements", "type"], errors[0].schema_path);
+/// ```
+///
+/// [`Schema::from_serde_schema_with_extensions`]: crate::Schema::from_serde_schema_with_extensions
+/// [`ValidateOptions::with_type_extensions`]: crate::ValidateOptions::with_type_extensions
+#[derive(Clone, Default)]
+pub struct TypeExtensions {
+    types: BTreeMap<String, TypeCheck>,
+}
+
+type TypeCheck = Arc<dyn Fn(&Value) -> bool + Send + Sync>;
+
+impl TypeExtensions {
+    /// Constructs an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a type, with a callback that returns whether a value is of
+    /// that type.
+    ///
+    /// Registering one of the types of RFC 8927, such as `"string"`, has no
+    /// effect: those types are always checked as the RFC says.
+    pub fn with_type<F>(mut self, name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn(&Value) -> bool + Send + Sync + 'static,
+    {
+        self.types.insert(name.into(), Arc::new(check));
+        self
+    }
+
+    /// Whether a type with the given name is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.types.contains_key(name)
+    }
+
+    /// Checks whether a value is of the type with the given name, or returns
+    /// `None` if no such type is registered.
+    pub fn check(&self, name: &str, value: &Value) -> Option<bool> {
+        self.types.get(name).map(|check| check(value))
+    }
+}
+
+impl fmt::Debug for TypeExtensions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.types.keys()).finish()
+    }
+}
+
+// Like the hooks in ValidateOptions, callbacks are compared by identity.
+impl PartialEq for TypeExtensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.types.len() == other.types.len()
+            && self
+                .types
+                .iter()
+                .zip(&other.types)
+                .all(|((a, f), (b, g))| a == b && Arc::ptr_eq(f, g))
+    }
+}
+
+impl Eq for TypeExtensions {}
+
+// Whether a number is one of the values of an integer enum.
+#[doc(hidden)]
+pub fn int_enum_contains(enum_: &BTreeSet<i64>, val: f64) -> bool {
+    // i64::MAX isn't representable as an f64; the bound below is 2^63.
+    val.fract() == 0.0
+        && val >= i64::MIN as f64
+        && val < i64::MAX as f64
+        && enum_.contains(&(val as i64))
+}
+
+// Whether a keys schema uses no keywords other than enum, or other than type
+// and pattern.
+fn is_bare(serde_schema: &SerdeSchema, is_enum: bool) -> bool {
+    let expected = SerdeSchema {
+        enum_: serde_schema.enum_.clone().filter(|_| is_enum),
+        type_: serde_schema.type_.clone().filter(|_| !is_enum),
+        pattern: serde_schema.pattern.clone().filter(|_| !is_enum),
+        ..Default::default()
+    };
+
+    serde_schema == &expected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Schema;
+    use serde_json::json;
+
+    fn schema(value: serde_json::Value) -> Result<Schema, FromSerdeSchemaError> {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap())
+    }
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(
+            Err(FromSerdeSchemaError::InvalidForm),
+            schema(json!({ "elements": {}, "keys": { "type": "string" }}))
+        );
+
+        assert_eq!(
+            Err(FromSerdeSchemaError::InvalidForm),
+            schema(json!({ "type": "string", "pattern": "a" }))
+        );
+
+        for keys in [
+            json!({}),
+            json!({ "type": "uint8" }),
+            json!({ "enum": ["a"], "pattern": "a" }),
+            json!({ "type": "string", "nullable": true }),
+        ] {
+            assert_eq!(
+                Err(FromSerdeSchemaError::InvalidKeys),
+                schema(json!({ "values": {}, "keys": keys }))
+            );
+        }
+
+        assert!(matches!(
+            schema(json!({ "values": {}, "keys": { "type": "string", "pattern": "(" }})),
+            Err(FromSerdeSchemaError::InvalidPattern(..))
+        ));
+
+        for keys in [
+            json!({ "enum": ["a", "b"] }),
+            json!({ "type": "string" }),
+            json!({ "type": "string", "pattern": "^a" }),
+        ] {
+            let value = json!({ "values": {}, "keys": keys });
+            let schema = schema(value.clone()).unwrap();
+            assert_eq!(
+                value,
+                serde_json::to_value(schema.into_serde_schema()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn parse_int_enum() {
+        let value = json!({ "enum": [-1, 2, 3], "nullable": true });
+        let int_enum = schema(value.clone()).unwrap();
+        assert!(matches!(int_enum, Schema::IntEnum { .. }));
+        assert_eq!(
+            value,
+            serde_json::to_value(int_enum.into_serde_schema()).unwrap()
+        );
+
+        assert_eq!(
+            Err(FromSerdeSchemaError::DuplicatedIntEnumValue(2)),
+            schema(json!({ "enum": [2, 2] }))
+        );
+
+        assert_eq!(
+            Err(FromSerdeSchemaError::InvalidForm),
+            schema(json!({ "enum": [1], "type": "uint8" }))
+        );
+
+        assert!(serde_json::from_value::<SerdeSchema>(json!({ "enum": [1, "a"] })).is_err());
+        assert!(serde_json::from_value::<SerdeSchema>(json!({ "enum": [1.5] })).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::Schema;
+use std::collections::{BTreeMap, BTreeSet};
+
+impl Schema {
+    /// Moves any `definitions` on non-root schemas up to the root, renaming
+    /// them where needed so that every `ref` still points at the same
+    /// definition.
+    ///
+    /// RFC 8927 only permits `definitions` at the root of a schema, and
+    /// [`Schema::validate`] rejects schemas that have them anywhere else. But
+    /// it can be convenient to author schemas modularly, keeping definitions
+    /// next to the part of the schema that uses them. [`Schema::from_serde_schema`]
+    /// accepts such schemas, and this function normalizes them into a form
+    /// that [`Schema::validate`] accepts.
+    ///
+    /// Within a nested schema, a `ref` refers to the nearest definition of
+    /// that name: one on the schema itself, or on one of the schemas
+    /// containing it, or finally one at the root. A hoisted definition keeps
+    /// its name unless that name is already taken, in which case it gets a
+    /// numeric suffix, such as `foo_2`.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let mut schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "definitions": { "name": { "type": "string" }},
+    ///     "properties": {
+    ///         "user": { "ref": "name" },
+    ///         "pet": {
+    ///             "definitions": { "name": { "enum": ["cat", "dog"] }},
+    ///             "ref": "name"
+    ///         }
+    ///     }
+    /// })).unwrap()).unwrap();
+    ///
+    /// assert!(schema.validate().is_err());
+    ///
+    /// schema.hoist_definitions();
+    /// assert!(schema.validate().is_ok());
+    ///
+    /// assert_eq!(
+    ///     json!({
+    ///         "definitions": {
+    ///             "name": { "type": "string" },
+    ///             "name_2": { "enum": ["cat", "dog"] }
+    ///         },
+    ///         "properties": {
+    ///             "user": { "ref": "name" },
+    ///             "pet": { "ref": "name_2" }
+    ///         }
+    ///     }),
+    ///     serde_json::to_value(schema.into_serde_schema()).unwrap()
+    /// );
+    /// ```
+    pub fn hoist_definitions(&mut self) {
+        let mut hoister = Hoister {
+            taken: self.definitions().keys().cloned().collect(),
+            scopes: vec![self
+                .definitions()
+                .keys()
+                .map(|name| (name.clone(), name.clone()))
+                .collect()],
+            hoisted: BTreeMap::new(),
+        };
+
+        for definition in self.definitions_mut().values_mut() {
+            hoister.hoist(definition);
+        }
+
+        hoister.hoist_children(self);
+        self.definitions_mut().append(&mut hoister.hoisted);
+    }
+}
+
+struct Hoister {
+    // Every definition name in use at the root, including hoisted ones.
+    taken: BTreeSet<String>,
+
+    // For each enclosing schema with definitions, a map from the names it
+    // defines to the names those definitions have at the root. Innermost
+    // last.
+    scopes: Vec<BTreeMap<String, String>>,
+
+    // Definitions moved up so far, by their new names.
+    hoisted: BTreeMap<String, Schema>,
+}
+
+impl Hoister {
+    fn hoist(&mut self, schema: &mut Schema) {
+        let definitions = std::mem::take(schema.definitions_mut());
+        if definitions.is_empty() {
+            return self.hoist_children(schema);
+        }
+
+        let scope = definitions
+            .keys()
+            .map(|name| (name.clone(), self.fresh_name(name)))
+            .collect();
+        self.scopes.push(scope);
+
+        // Definitions can refer to one another, and to themselves, so they
+        // are hoisted with their own scope in effect.
+        for (name, mut definition) in definitions {
+            self.hoist(&mut definition);
+
+            let new_name = self.scopes.last().unwrap()[&name].clone();
+            self.hoisted.insert(new_name, definition);
+        }
+
+        self.hoist_children(schema);
+        self.scopes.pop();
+    }
+
+    fn hoist_children(&mut self, schema: &mut Schema) {
+        match schema {
+            Schema::Ref { ref_, .. } => {
+                let renamed = self.scopes.iter().rev().find_map(|scope| scope.get(ref_));
+                if let Some(renamed) = renamed {
+                    *ref_ = renamed.clone();
+                }
+            }
+            Schema::Elements { elements, .. } => self.hoist(elements),
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            } => {
+                for sub_schema in properties.values_mut() {
+                    self.hoist(sub_schema);
+                }
+
+                for sub_schema in optional_properties.values_mut() {
+                    self.hoist(sub_schema);
+                }
+            }
+            Schema::Values { values, .. } => self.hoist(values),
+            Schema::Discriminator { mapping, .. } => {
+                for sub_schema in mapping.values_mut() {
+                    self.hoist(sub_schema);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn fresh_name(&mut self, name: &str) -> String {
+        let mut candidate = name.to_owned();
+        let mut suffix = 2;
+        while self.taken.contains(&candidate) {
+            candidate = format!("{}_{}", name, suffix);
+            suffix += 1;
+        }
+
+        self.taken.insert(candidate.clone());
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Schema;
+    use serde_json::json;
+
+    fn hoist(value: serde_json::Value) -> serde_json::Value {
+        let mut schema = Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap();
+        schema.hoist_definitions();
+        assert!(schema.validate().is_ok());
+        serde_json::to_value(schema.into_serde_schema()).unwrap()
+    }
+
+    #[test]
+    fn hoists_nested_scopes() {
+        assert_eq!(
+            json!({
+                "definitions": {
+                    "a": { "elements": { "ref": "a_2" }},
+                    "a_2": { "type": "uint8" },
+                    "a_3": { "ref": "b_2" },
+                    "b": { "values": { "ref": "b" }},
+                    "b_2": { "type": "string" },
+                    "c": { "type": "boolean" }
+                },
+                "discriminator": "t",
+                "mapping": {
+                    "x": {
+                        "properties": {
+                            "a": { "ref": "a_3" },
+                            "b": { "ref": "b" },
+                            "c": { "ref": "c" }
+                        }
+                    }
+                }
+            }),
+            hoist(json!({
+                "definitions": {
+                    "a": {
+                        "definitions": { "a": { "type": "uint8" }},
+                        "elements": { "ref": "a" }
+                    },
+                    "c": { "type": "boolean" }
+                },
+                "discriminator": "t",
+                "mapping": {
+                    "x": {
+                        "definitions": {
+                            "a": {
+                                "definitions": { "b": { "type": "string" }},
+                                "ref": "b"
+                            },
+                            "b": { "values": { "ref": "b" }}
+                        },
+                        "properties": {
+                            "a": { "ref": "a" },
+                            "b": { "ref": "b" },
+                            "c": { "ref": "c" }
+                        }
+                    }
+                }
+            }))
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use serde_json::{Map, Number, Value};
+use std::borrow::Cow;
+
+/// A JSON value that can be validated against a schema.
+///
+/// [`validate()`][`crate::validate()`] works through this trait, rather than
+/// on [`serde_json::Value`] directly, so that other representations of JSON,
+/// such as those of simd-json, ijson or sonic-rs, can be validated as they
+/// are, without converting them to a [`Value`] first. This crate implements
+/// it for [`Value`]; to validate another representation, implement it for
+/// that representation's value type.
+///
+/// Each method answers a question about the kind of value `self` is, and
+/// returns [`None`], or `false`, if it's of another kind.
+///
+/// ```
+/// use jtd::{Instance, Schema};
+/// use serde_json::json;
+///
+/// // A representation of JSON that only has strings and arrays.
+/// enum Tree {
+///     Leaf(String),
+///     Branch(Vec<Tree>),
+/// }
+///
+/// impl Instance for Tree {
+///     type Elements<'a> = std::slice::Iter<'a, Tree>;
+///     type Entries<'a> = std::iter::Empty<(&'a str, &'a Tree)>;
+///
+///     fn is_null(&self) -> bool {
+///         false
+///     }
+///
+///     fn as_bool(&self) -> Option<bool> {
+///         None
+///     }
+///
+///     fn as_f64(&self) -> Option<f64> {
+///         None
+///     }
+///
+///     fn as_i64(&self) -> Option<i64> {
+///         None
+///     }
+///
+///     fn as_str(&self) -> Option<&str> {
+///         match self {
+///             Tree::Leaf(s) => Some(s),
+///             Tree::Branch(_) => None,
+///         }
+///     }
+///
+///     fn as_array(&self) -> Option<Self::Elements<'_>> {
+///         match self {
+///             Tree::Leaf(_) => None,
+///             Tree::Branch(children) => Some(children.iter()),
+///         }
+///     }
+///
+///     fn as_object(&self) -> Option<Self::Entries<'_>> {
+///         None
+///     }
+///
+///     fn get(&self, _key: &str) -> Option<&Self> {
+///         None
+///     }
+/// }
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "elements": { "elements": { "type": "string" }}
+/// })).unwrap()).unwrap();
+///
+/// let tree = Tree::Branch(vec![
+///     Tree::Branch(vec![Tree::Leaf("a".into())]),
+///     Tree::Leaf("b".into()),
+/// ]);
+///
+/// let errors = jtd::validate(&schema, &tree, Default::default()).unwrap();
+/// assert_eq!(1, errors.len());
+/// assert_eq!(vec!["1"], errors[0].instance_path);
+/// ```
+pub trait Instance {
+    /// An iterator over the elements of an array.
+    type Elements<'a>: ExactSizeIterator<Item = &'a Self>
+    where
+        Self: 'a;
+
+    /// An iterator over the properties of an object, as pairs of names and
+    /// values.
+    type Entries<'a>: ExactSizeIterator<Item = (&'a str, &'a Self)>
+    where
+        Self: 'a;
+
+    /// Whether the value is null.
+    fn is_null(&self) -> bool;
+
+    /// The value, if it's a boolean.
+    fn as_bool(&self) -> Option<bool>;
+
+    /// The value, if it's a number.
+    fn as_f64(&self) -> Option<f64>;
+
+    /// The value, if it's a number that is an integer within the range of an
+    /// `i64`. Numbers that are whole, but stored as floats, may return either
+    /// [`None`] or their value.
+    fn as_i64(&self) -> Option<i64>;
+
+    /// The value, if it's a string.
+    fn as_str(&self) -> Option<&str>;
+
+    /// The elements of the value, if it's an array.
+    fn as_array(&self) -> Option<Self::Elements<'_>>;
+
+    /// The properties of the value, if it's an object.
+    fn as_object(&self) -> Option<Self::Entries<'_>>;
+
+    /// The value of a property of the value, if it's an object with that
+    /// property.
+    fn get(&self, key: &str) -> Option<&Self>;
+
+    /// The value, as a [`serde_json::Value`].
+    ///
+    /// This is only used to check values against custom types from the
+    /// `extensions` feature, which are checked by functions of a `Value`. The
+    /// default implementation builds one from the other methods of this
+    /// trait.
+    fn to_value(&self) -> Cow<'_, Value> {
+        Cow::Owned(if let Some(b) = self.as_bool() {
+            Value::Bool(b)
+        } else if let Some(i) = self.as_i64() {
+            Value::from(i)
+        } else if let Some(f) = self.as_f64() {
+            Number::from_f64(f).map_or(Value::Null, Value::Number)
+        } else if let Some(s) = self.as_str() {
+            Value::from(s)
+        } else if let Some(elements) = self.as_array() {
+            elements
+                .map(|element| element.to_value().into_owned())
+                .collect()
+        } else if let Some(entries) = self.as_object() {
+            Value::Object(
+                entries
+                    .map(|(name, value)| (name.to_owned(), value.to_value().into_owned()))
+                    .collect::<Map<_, _>>(),
+            )
+        } else {
+            Value::Null
+        })
+    }
+}
+
+type ValueEntry<'a> = (&'a String, &'a Value);
+
+impl Instance for Value {
+    type Elements<'a> = std::slice::Iter<'a, Value>;
+    type Entries<'a> =
+        std::iter::Map<serde_json::map::Iter<'a>, fn(ValueEntry<'a>) -> (&'a str, &'a Value)>;
+
+    fn is_null(&self) -> bool {
+        Value::is_null(self)
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        Value::as_bool(self)
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        Value::as_f64(self)
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        Value::as_i64(self)
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        Value::as_str(self)
+    }
+
+    fn as_array(&self) -> Option<Self::Elements<'_>> {
+        Value::as_array(self).map(|arr| arr.iter())
+    }
+
+    fn as_object(&self) -> Option<Self::Entries<'_>> {
+        Value::as_object(self).map(|obj| obj.iter().map(entry as _))
+    }
+
+    fn get(&self, key: &str) -> Option<&Self> {
+        Value::as_object(self)?.get(key)
+    }
+
+    fn to_value(&self) -> Cow<'_, Value> {
+        Cow::Borrowed(self)
+    }
+}
+
+fn entry<'a>((name, value): ValueEntry<'a>) -> (&'a str, &'a Value) {
+    (name, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::validate;
+    use crate::Schema;
+    use serde_json::json;
+
+    // JSON kept in the order it was written, as some representations do.
+    enum Ordered {
+        Null,
+        Bool(bool),
+        Number(Number),
+        String(String),
+        Array(Vec<Ordered>),
+        Object(Vec<(String, Ordered)>),
+    }
+
+    impl From<&Value> for Ordered {
+        fn from(value: &Value) -> Self {
+            match value {
+                Value::Null => Ordered::Null,
+                Value::Bool(b) => Ordered::Bool(*b),
+                Value::Number(n) => Ordered::Number(n.clone()),
+                Value::String(s) => Ordered::String(s.clone()),
+                Value::Array(arr) => Ordered::Array(arr.iter().map(Ordered::from).collect()),
+                Value::Object(obj) => Ordered::Object(
+                    obj.iter()
+                        .map(|(name, value)| (name.clone(), value.into()))
+                        .collect(),
+                ),
+            }
+        }
+    }
+
+    impl Instance for Ordered {
+        type Elements<'a> = std::slice::Iter<'a, Ordered>;
+        type Entries<'a> = Box<dyn ExactSizeIterator<Item = (&'a str, &'a Ordered)> + 'a>;
+
+        fn is_null(&self) -> bool {
+            matches!(self, Ordered::Null)
+        }
+
+        fn as_bool(&self) -> Option<bool> {
+            match self {
+                Ordered::Bool(b) => Some(*b),
+                _ => None,
+            }
+        }
+
+        fn as_f64(&self) -> Option<f64> {
+            match self {
+                Ordered::Number(n) => n.as_f64(),
+                _ => None,
+            }
+        }
+
+        fn as_i64(&self) -> Option<i64> {
+            match self {
+                Ordered::Number(n) => n.as_i64(),
+                _ => None,
+            }
+        }
+
+        fn as_str(&self) -> Option<&str> {
+            match self {
+                Ordered::String(s) => Some(s),
+                _ => None,
+            }
+        }
+
+        fn as_array(&self) -> Option<Self::Elements<'_>> {
+            match self {
+                Ordered::Array(arr) => Some(arr.iter()),
+                _ => None,
+            }
+        }
+
+        fn as_object(&self) -> Option<Self::Entries<'_>> {
+            match self {
+                Ordered::Object(obj) => Some(Box::new(
+                    obj.iter().map(|(name, value)| (name.as_str(), value)),
+                )),
+                _ => None,
+            }
+        }
+
+        fn get(&self, key: &str) -> Option<&Self> {
+            match self {
+                Ordered::Object(obj) => obj
+                    .iter()
+                    .find(|(name, _)| name == key)
+                    .map(|(_, value)| value),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn matches_value() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "event": {
+                        "discriminator": "type",
+                        "mapping": {
+                            "a": { "properties": { "at": { "type": "timestamp" }}},
+                            "b": { "properties": { "n": { "type": "int8" }}}
+                        }
+                    }
+                },
+                "properties": {
+                    "events": { "elements": { "ref": "event" }},
+                    "counts": { "values": { "type": "float32" }},
+                    "level": { "enum": ["info", "warn"], "nullable": true }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let instance = json!({
+            "events": [
+                { "type": "a", "at": "1985-04-12T23:20:50.52Z" },
+                { "type": "a", "at": "yesterday" },
+                { "type": "b", "n": 1.0, "extra": true },
+                { "type": "c" },
+                { "n": 200 }
+            ],
+            "counts": { "x": 1.5, "y": "2" },
+            "level": null
+        });
+
+        let ordered = Ordered::from(&instance);
+        assert_eq!(
+            validate(&schema, &instance, Default::default()).unwrap(),
+            validate(&schema, &ordered, Default::default()).unwrap(),
+        );
+        assert_eq!(instance, ordered.to_value().into_owned());
+    }
+}
@@ -0,0 +1,53 @@
+//! The schema model of [`jtd`](https://docs.rs/jtd).
+//!
+//! You shouldn't need to depend on this crate directly. Everything in it is
+//! re-exported by `jtd`, and documented there. It's a crate of its own so that
+//! `jtd-macros` can check schemas at build time without depending on `jtd`,
+//! which depends on `jtd-macros`.
+
+mod dsl;
+mod edit;
+mod expand;
+#[cfg(feature = "extensions")]
+mod extensions;
+mod hoist;
+mod instance;
+mod normalize;
+mod path;
+#[cfg(feature = "quickcheck")]
+mod quickcheck;
+mod refs;
+mod roundtrip;
+mod scan;
+mod schema;
+mod serde_schema;
+mod spans;
+mod timestamp;
+mod validate;
+mod walk;
+
+pub use dsl::*;
+pub use edit::*;
+#[cfg(feature = "extensions")]
+pub use extensions::*;
+pub use instance::*;
+pub use path::*;
+pub use roundtrip::*;
+pub use schema::*;
+pub use serde_schema::*;
+pub use spans::*;
+pub use validate::*;
+pub use walk::*;
+
+// Not public API. This module exists for jtd, which is built on this crate.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::expand::set_nullable;
+    #[cfg(feature = "extensions")]
+    pub use crate::extensions::int_enum_contains;
+    pub use crate::path::resolve;
+    pub use crate::refs::resolve_ref_with;
+    pub use crate::scan::{JsonScanner, LineIndex, Location, Locations};
+    pub use crate::timestamp::is_rfc3339;
+    pub use crate::validate::internal::{type_check, ValidateOptionsExt};
+}
@@ -0,0 +1,297 @@
+use crate::{Schema, Severity};
+
+impl Schema {
+    /// Rewrites the schema into a canonical form, which accepts and rejects
+    /// the same instances, with the same severities.
+    ///
+    /// Two schemas that validate data the same way, but are written
+    /// differently, usually have the same normalized form. This is what
+    /// [`Schema::equivalent`] relies on, to tell apart changes to a schema
+    /// that change its behavior from those that don't. Normalizing:
+    ///
+    /// * Removes all metadata, except for the severities that
+    ///   [`Severity::from_metadata`] recognizes, since those change what
+    ///   validation reports.
+    /// * Replaces refs with the definitions they refer to, as
+    ///   [`Schema::expanded`] does, and removes definitions that aren't used.
+    ///   Only refs to recursive definitions are left in place.
+    /// * Removes definitions from schemas other than the root.
+    /// * Records the properties form as having `properties` only if it has
+    ///   required properties, or no properties at all.
+    /// * With the `extensions` feature, removes `keys` that allow any key.
+    ///
+    /// Enums are sets, and the empty form is always nullable, so these are
+    /// already in a canonical form.
+    ///
+    /// Expanding refs can make a schema much larger, if its definitions refer
+    /// to one another in many places.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let mut schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "definitions": {
+    ///         "id": { "type": "uint32", "metadata": { "description": "An ID" }},
+    ///         "unused": { "type": "string" }
+    ///     },
+    ///     "properties": {},
+    ///     "optionalProperties": {
+    ///         "id": { "ref": "id" }
+    ///     }
+    /// })).unwrap()).unwrap();
+    ///
+    /// schema.normalize();
+    /// assert_eq!(
+    ///     json!({ "optionalProperties": { "id": { "type": "uint32" }}}),
+    ///     serde_json::to_value(schema.into_serde_schema()).unwrap(),
+    /// );
+    /// ```
+    pub fn normalize(&mut self) {
+        let root = self.clone();
+        strip_metadata(&root, self);
+
+        *self = self.expanded(usize::MAX);
+        canonicalize(self, true);
+    }
+
+    /// Whether two schemas accept and reject the same instances, with the
+    /// same severities, as far as normalizing them can tell.
+    ///
+    /// This is true if the schemas are the same once normalized with
+    /// [`Schema::normalize`]. It's meant for telling whether a change to a
+    /// schema, such as in a pull request, changes its behavior, or only its
+    /// documentation or organization.
+    ///
+    /// Schemas that are equivalent may still give validation errors with
+    /// different schema paths: the paths go through refs that normalizing
+    /// replaces, for example.
+    ///
+    /// Schemas can be equivalent without this method being able to tell.
+    /// For instance, two recursive definitions that are the same but for
+    /// their names aren't recognized as equivalent.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema = |value| Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap();
+    ///
+    /// let before = schema(json!({
+    ///     "properties": {
+    ///         "color": { "enum": ["red", "green"] }
+    ///     }
+    /// }));
+    ///
+    /// let after = schema(json!({
+    ///     "definitions": {
+    ///         "color": {
+    ///             "metadata": { "description": "A color" },
+    ///             "enum": ["green", "red"]
+    ///         }
+    ///     },
+    ///     "properties": {
+    ///         "color": { "ref": "color" }
+    ///     }
+    /// }));
+    ///
+    /// assert!(before.equivalent(&after));
+    /// assert!(!before.equivalent(&schema(json!({
+    ///     "properties": {
+    ///         "color": { "enum": ["red", "green", "blue"] }
+    ///     }
+    /// }))));
+    /// ```
+    pub fn equivalent(&self, other: &Schema) -> bool {
+        let mut schema = self.clone();
+        let mut other = other.clone();
+        schema.normalize();
+        other.normalize();
+
+        schema == other
+    }
+}
+
+// Removes the metadata of a schema and its sub-schemas, other than
+// severities.
+//
+// When validating against a ref, the severity of the definition takes
+// precedence over the ref's own, whereas expanding the ref gives precedence
+// to the ref's metadata. So a ref's severity is removed too, if the
+// definition it leads to sets one.
+fn strip_metadata(root: &Schema, schema: &mut Schema) {
+    let metadata = schema.metadata_mut();
+    let severity = metadata.remove(Severity::METADATA_KEY);
+    metadata.clear();
+
+    if let Some(severity) = severity {
+        let overridden = match schema {
+            Schema::Ref { ref_, .. } => sets_severity(root, ref_),
+            _ => false,
+        };
+
+        if !overridden {
+            schema
+                .metadata_mut()
+                .insert(Severity::METADATA_KEY.to_owned(), severity);
+        }
+    }
+
+    for definition in schema.definitions_mut().values_mut() {
+        strip_metadata(root, definition);
+    }
+
+    for_each_sub_schema(schema, |sub_schema| strip_metadata(root, sub_schema));
+}
+
+// Whether the definition a ref leads to sets a severity, following any refs
+// it consists of.
+fn sets_severity(root: &Schema, ref_: &str) -> bool {
+    let definition = match root.definitions().get(ref_) {
+        Some(definition) => definition,
+        None => return false,
+    };
+
+    let mut sets = false;
+    let _ = crate::refs::resolve_ref_with(definition, root, |schema| {
+        sets |= Severity::from_metadata(schema.metadata()).is_some();
+    });
+
+    sets
+}
+
+// Puts what's left of the schema after expanding it into its canonical form.
+fn canonicalize(schema: &mut Schema, is_root: bool) {
+    if is_root {
+        for definition in schema.definitions_mut().values_mut() {
+            canonicalize(definition, false);
+        }
+    } else {
+        schema.definitions_mut().clear();
+    }
+
+    match schema {
+        Schema::Properties {
+            properties,
+            optional_properties,
+            properties_is_present,
+            ..
+        } => {
+            *properties_is_present = !properties.is_empty() || optional_properties.is_empty();
+        }
+        #[cfg(feature = "extensions")]
+        Schema::Values { keys, .. } => {
+            if let Some(crate::extensions::Keys::String { pattern: None }) = keys {
+                *keys = None;
+            }
+        }
+        _ => {}
+    }
+
+    for_each_sub_schema(schema, |sub_schema| canonicalize(sub_schema, false));
+}
+
+fn for_each_sub_schema(schema: &mut Schema, mut f: impl FnMut(&mut Schema)) {
+    match schema {
+        Schema::Elements { elements, .. } => f(elements),
+        Schema::Properties {
+            properties,
+            optional_properties,
+            ..
+        } => {
+            properties.values_mut().for_each(&mut f);
+            optional_properties.values_mut().for_each(f);
+        }
+        Schema::Values { values, .. } => f(values),
+        Schema::Discriminator { mapping, .. } => mapping.values_mut().for_each(f),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Schema;
+    use serde_json::json;
+
+    fn schema(value: serde_json::Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn equivalence() {
+        let equivalent = [
+            (
+                json!({ "type": "string" }),
+                json!({ "type": "string", "metadata": { "a": 1 }}),
+            ),
+            (
+                json!({ "properties": { "a": { "ref": "a" }}, "definitions": { "a": {}}}),
+                json!({ "properties": { "a": {} }, "definitions": { "b": {} }}),
+            ),
+            (
+                json!({ "ref": "a", "definitions": { "a": { "ref": "b" }, "b": { "type": "int8" }}}),
+                json!({ "type": "int8" }),
+            ),
+            (
+                json!({ "ref": "a", "nullable": true, "definitions": { "a": { "type": "int8" }}}),
+                json!({ "type": "int8", "nullable": true }),
+            ),
+            (
+                json!({
+                    "ref": "a",
+                    "definitions": {
+                        "a": { "elements": { "ref": "a" }, "metadata": { "description": "a" }}
+                    }
+                }),
+                json!({ "ref": "a", "definitions": { "a": { "elements": { "ref": "a" }}}}),
+            ),
+            (
+                json!({
+                    "ref": "a",
+                    "metadata": { "severityOnViolation": "warning" },
+                    "definitions": {
+                        "a": { "type": "int8", "metadata": { "severityOnViolation": "info" }}
+                    }
+                }),
+                json!({ "type": "int8", "metadata": { "severityOnViolation": "info" }}),
+            ),
+        ];
+
+        for (a, b) in &equivalent {
+            assert!(
+                schema(a.clone()).equivalent(&schema(b.clone())),
+                "{} {}",
+                a,
+                b
+            );
+        }
+
+        let different = [
+            (
+                json!({ "type": "string" }),
+                json!({ "type": "string", "nullable": true }),
+            ),
+            (
+                json!({ "type": "string" }),
+                json!({ "type": "string", "metadata": { "severityOnViolation": "warning" }}),
+            ),
+            (
+                json!({ "properties": { "a": {} }}),
+                json!({ "optionalProperties": { "a": {} }}),
+            ),
+            (
+                json!({ "properties": { "a": {} }}),
+                json!({ "properties": { "a": {} }, "additionalProperties": true }),
+            ),
+        ];
+
+        for (a, b) in &different {
+            assert!(
+                !schema(a.clone()).equivalent(&schema(b.clone())),
+                "{} {}",
+                a,
+                b
+            );
+        }
+    }
+}
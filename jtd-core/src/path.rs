@@ -0,0 +1,474 @@
+use crate::Schema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::Deref;
+use std::sync::Arc;
+
+macro_rules! impl_path {
+    ($name:ident) => {
+        impl<'a> $name<'a> {
+            /// Converts this path into one that doesn't borrow from the schema
+            /// or instance it came from.
+            pub fn into_owned(self) -> $name<'static> {
+                self.iter()
+                    .map(|token| Cow::Owned(token.to_string()))
+                    .collect()
+            }
+        }
+
+        impl<'a> Deref for $name<'a> {
+            type Target = [Cow<'a, str>];
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl<'a> Default for $name<'a> {
+            fn default() -> Self {
+                Self(Arc::from(Vec::new()))
+            }
+        }
+
+        // Paths display as JSON Pointers.
+        impl<'a> fmt::Display for $name<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                for token in self.iter() {
+                    write!(f, "/{}", token.replace('~', "~0").replace('/', "~1"))?;
+                }
+
+                Ok(())
+            }
+        }
+
+        // Paths are debugged as lists of tokens, just as the Vecs they
+        // replace were.
+        impl<'a> fmt::Debug for $name<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+
+        impl<'a> From<&[Cow<'a, str>]> for $name<'a> {
+            fn from(tokens: &[Cow<'a, str>]) -> Self {
+                Self(Arc::from(tokens))
+            }
+        }
+
+        impl<'a> From<Vec<Cow<'a, str>>> for $name<'a> {
+            fn from(tokens: Vec<Cow<'a, str>>) -> Self {
+                Self(Arc::from(tokens))
+            }
+        }
+
+        impl<'a> From<Vec<&'a str>> for $name<'a> {
+            fn from(tokens: Vec<&'a str>) -> Self {
+                tokens.into_iter().map(Cow::Borrowed).collect()
+            }
+        }
+
+        impl<'a> From<Vec<String>> for $name<'a> {
+            fn from(tokens: Vec<String>) -> Self {
+                tokens.into_iter().map(Cow::Owned).collect()
+            }
+        }
+
+        impl<'a> From<&'a [String]> for $name<'a> {
+            fn from(tokens: &'a [String]) -> Self {
+                tokens
+                    .iter()
+                    .map(|token| Cow::Borrowed(token.as_str()))
+                    .collect()
+            }
+        }
+
+        impl<'a> FromIterator<Cow<'a, str>> for $name<'a> {
+            fn from_iter<I: IntoIterator<Item = Cow<'a, str>>>(iter: I) -> Self {
+                Self(iter.into_iter().collect())
+            }
+        }
+
+        impl<'a, 'b> PartialEq<Vec<&'b str>> for $name<'a> {
+            fn eq(&self, other: &Vec<&'b str>) -> bool {
+                self.iter().eq(other.iter())
+            }
+        }
+
+        impl<'a, 'b> PartialEq<$name<'a>> for Vec<&'b str> {
+            fn eq(&self, other: &$name<'a>) -> bool {
+                other == self
+            }
+        }
+
+        impl<'a> PartialEq<Vec<String>> for $name<'a> {
+            fn eq(&self, other: &Vec<String>) -> bool {
+                self.iter().eq(other.iter())
+            }
+        }
+
+        impl<'a> PartialEq<$name<'a>> for Vec<String> {
+            fn eq(&self, other: &$name<'a>) -> bool {
+                other == self
+            }
+        }
+
+        impl<'a> Serialize for $name<'a> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.iter())
+            }
+        }
+
+        impl<'de, 'a> Deserialize<'de> for $name<'a> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                Vec::<String>::deserialize(deserializer).map(Self::from)
+            }
+        }
+    };
+}
+
+/// A path to part of an instance, as a list of JSON Pointer tokens.
+///
+/// This is the type of [`ValidationErrorIndicator::instance_path`][`crate::ValidationErrorIndicator::instance_path`].
+/// It dereferences to a slice of tokens, displays as a JSON Pointer, and is
+/// cheap to clone, as its tokens are shared between clones. Use
+/// [`InstancePath::get`] to find the part of the instance it points to.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "values": { "elements": { "type": "uint8" }}
+/// })).unwrap()).unwrap();
+///
+/// let instance = json!({ "a/b": [1, 300] });
+/// let errors = jtd::validate(&schema, &instance, Default::default()).unwrap();
+/// let path = &errors[0].instance_path;
+///
+/// assert_eq!(vec!["a/b", "1"], *path);
+/// assert_eq!("/a~1b/1", path.to_string());
+/// assert_eq!(Some(&json!(300)), path.get(&instance));
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InstancePath<'a>(Arc<[Cow<'a, str>]>);
+
+impl_path!(InstancePath);
+
+impl<'a> InstancePath<'a> {
+    /// The part of `instance` this path points to, if there is one.
+    ///
+    /// Tokens are property names for objects, and indices for arrays.
+    pub fn get<'v>(&self, instance: &'v Value) -> Option<&'v Value> {
+        self.iter().try_fold(instance, |value, token| match value {
+            Value::Object(object) => object.get(token.as_ref()),
+            Value::Array(array) => array.get(token.parse::<usize>().ok()?),
+            _ => None,
+        })
+    }
+}
+
+/// A path to part of a schema, as a list of JSON Pointer tokens.
+///
+/// This is the type of [`ValidationErrorIndicator::schema_path`][`crate::ValidationErrorIndicator::schema_path`].
+/// It dereferences to a slice of tokens, displays as a JSON Pointer, and is
+/// cheap to clone, as its tokens are shared between clones. Use
+/// [`SchemaPath::resolve`] to find the schema that rejected an instance.
+///
+/// ```
+/// use jtd::{Schema, Type};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "age": { "type": "uint8", "metadata": { "description": "In years" }}
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let instance = json!({ "age": -1 });
+/// let errors = jtd::validate(&schema, &instance, Default::default()).unwrap();
+/// let path = &errors[0].schema_path;
+///
+/// assert_eq!("/properties/age/type", path.to_string());
+/// assert_eq!(
+///     Some(&json!("In years")),
+///     path.resolve(&schema).unwrap().metadata().get("description"),
+/// );
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SchemaPath<'a>(Arc<[Cow<'a, str>]>);
+
+impl_path!(SchemaPath);
+
+impl<'a> SchemaPath<'a> {
+    /// The schema this path points into, given the root schema it's a path
+    /// within.
+    ///
+    /// Schema paths of errors end with the keyword that rejected the
+    /// instance, such as `type` or `elements`, or with the name of a missing
+    /// property. This returns the schema that keyword or property belongs to,
+    /// which is the schema that rejected the instance. Returns `None` if the
+    /// path leads nowhere in `root`.
+    pub fn resolve<'s>(&self, root: &'s Schema) -> Option<&'s Schema> {
+        resolve(root, self).map(|(schema, _)| schema)
+    }
+}
+
+// The keywords a path may end with that belong to a schema, rather than
+// leading to another schema.
+const KEYWORDS: [&str; 11] = [
+    "definitions",
+    "ref",
+    "type",
+    "enum",
+    "properties",
+    "optionalProperties",
+    "additionalProperties",
+    "discriminator",
+    "mapping",
+    "nullable",
+    "metadata",
+];
+
+impl Schema {
+    /// Gets the part of this schema at a path, given as a list of JSON Pointer
+    /// tokens.
+    ///
+    /// Tokens are followed as they would be in the JSON representation of the
+    /// schema: `elements` and `values` lead to those schemas, and
+    /// `properties`, `optionalProperties`, `mapping`, and `definitions` lead,
+    /// with the token after them, to the schema of a property, mapping, or
+    /// definition. A path may also end with a keyword that doesn't lead to
+    /// another schema, such as `type` or `enum`, in which case the schema the
+    /// keyword belongs to is returned. Refs aren't followed.
+    ///
+    /// Returns `None` if the path doesn't lead to a schema.
+    ///
+    /// The schema path of a validation error ends with such a keyword, so
+    /// this gets the schema that rejected the instance. For errors about
+    /// something missing, such as a required property, this gets the schema
+    /// of what's missing. See [`SchemaPath::resolve`] to always get the
+    /// schema that rejected the instance instead.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "definitions": {
+    ///         "port": {
+    ///             "type": "uint16",
+    ///             "metadata": { "errorMessage": "Ports are between 0 and 65535" }
+    ///         }
+    ///     },
+    ///     "properties": {
+    ///         "listen": { "elements": { "ref": "port" }}
+    ///     }
+    /// })).unwrap()).unwrap();
+    ///
+    /// let instance = json!({ "listen": [80, 1e6] });
+    /// let errors = jtd::validate(&schema, &instance, Default::default()).unwrap();
+    ///
+    /// let rejected_by = schema.get_at_path(&errors[0].schema_path).unwrap();
+    /// assert_eq!(
+    ///     Some(&json!("Ports are between 0 and 65535")),
+    ///     rejected_by.metadata().get("errorMessage"),
+    /// );
+    ///
+    /// assert!(schema.get_at_path(&["properties", "listen", "elements"]).is_some());
+    /// assert!(schema.get_at_path(&["properties", "port"]).is_none());
+    /// ```
+    pub fn get_at_path<S: AsRef<str>>(&self, path: &[S]) -> Option<&Schema> {
+        let mut schema = self;
+        let mut path = path;
+
+        loop {
+            let (sub_schema, rest) = match (schema, path) {
+                (_, []) => return Some(schema),
+                (_, [keyword, name, rest @ ..])
+                    if keyword.as_ref() == "definitions" && std::ptr::eq(schema, self) =>
+                {
+                    (self.definitions().get(name.as_ref())?, rest)
+                }
+                (Schema::Elements { elements, .. }, [keyword, rest @ ..])
+                    if keyword.as_ref() == "elements" =>
+                {
+                    (&**elements, rest)
+                }
+                (Schema::Values { values, .. }, [keyword, rest @ ..])
+                    if keyword.as_ref() == "values" =>
+                {
+                    (&**values, rest)
+                }
+                (Schema::Properties { properties, .. }, [keyword, name, rest @ ..])
+                    if keyword.as_ref() == "properties" =>
+                {
+                    (properties.get(name.as_ref())?, rest)
+                }
+                (
+                    Schema::Properties {
+                        optional_properties,
+                        ..
+                    },
+                    [keyword, name, rest @ ..],
+                ) if keyword.as_ref() == "optionalProperties" => {
+                    (optional_properties.get(name.as_ref())?, rest)
+                }
+                (Schema::Discriminator { mapping, .. }, [keyword, tag, rest @ ..])
+                    if keyword.as_ref() == "mapping" =>
+                {
+                    (mapping.get(tag.as_ref())?, rest)
+                }
+                // Values-form schemas reject keys with the keyword of the
+                // constraint on them, after "keys".
+                #[cfg(feature = "extensions")]
+                (Schema::Values { .. }, [keyword, _]) if keyword.as_ref() == "keys" => {
+                    return Some(schema)
+                }
+                (_, [keyword]) if KEYWORDS.contains(&keyword.as_ref()) => return Some(schema),
+                _ => return None,
+            };
+
+            schema = sub_schema;
+            path = rest;
+        }
+    }
+}
+
+// Finds the schema a schema path leads to, and the keywords left over after
+// it.
+#[doc(hidden)]
+pub fn resolve<'s, 'p, 'a>(
+    root: &'s Schema,
+    schema_path: &'p [Cow<'a, str>],
+) -> Option<(&'s Schema, &'p [Cow<'a, str>])> {
+    let (mut schema, mut schema_path) = match schema_path {
+        [keyword, name, rest @ ..] if keyword == "definitions" => {
+            (root.definitions().get(name.as_ref())?, rest)
+        }
+        _ => (root, schema_path),
+    };
+
+    loop {
+        match (schema, schema_path) {
+            (Schema::Elements { elements, .. }, [keyword, _, ..]) if keyword == "elements" => {
+                schema = elements;
+                schema_path = &schema_path[1..];
+            }
+            (Schema::Values { values, .. }, [keyword, _, ..]) if keyword == "values" => {
+                schema = values;
+                schema_path = &schema_path[1..];
+            }
+            (Schema::Properties { properties, .. }, [keyword, name, rest @ ..])
+                if keyword == "properties" && !rest.is_empty() =>
+            {
+                schema = properties.get(name.as_ref())?;
+                schema_path = rest;
+            }
+            (
+                Schema::Properties {
+                    optional_properties,
+                    ..
+                },
+                [keyword, name, rest @ ..],
+            ) if keyword == "optionalProperties" && !rest.is_empty() => {
+                schema = optional_properties.get(name.as_ref())?;
+                schema_path = rest;
+            }
+            (Schema::Discriminator { mapping, .. }, [keyword, name, rest @ ..])
+                if keyword == "mapping" =>
+            {
+                schema = mapping.get(name.as_ref())?;
+                schema_path = rest;
+            }
+            _ => return Some((schema, schema_path)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn paths() {
+        let path: InstancePath = vec!["a", "0"].into();
+        let clone = path.clone();
+        assert!(std::ptr::eq(&path[0], &clone[0]));
+
+        assert_eq!(json!(["a", "0"]), serde_json::to_value(&path).unwrap());
+        assert_eq!(
+            path,
+            serde_json::from_value::<InstancePath>(json!(["a", "0"])).unwrap()
+        );
+        assert_eq!(r#"["a", "0"]"#, format!("{:?}", path));
+
+        assert_eq!(Some(&json!(1)), path.get(&json!({ "a": [1] })));
+        assert_eq!(Some(&json!(1)), path.get(&json!({ "a": { "0": 1 }})));
+        assert_eq!(None, path.get(&json!({ "a": [] })));
+        assert_eq!(None, path.get(&json!(["a"])));
+        assert_eq!("", InstancePath::default().to_string());
+
+        let tokens = ["a/b".to_owned(), "c~d".to_owned()];
+        assert_eq!("/a~1b/c~0d", SchemaPath::from(&tokens[..]).to_string());
+    }
+
+    #[test]
+    fn get_at_path() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "a": { "values": { "type": "string" }}
+                },
+                "discriminator": "kind",
+                "mapping": {
+                    "x": {
+                        "properties": { "a": { "ref": "a" }},
+                        "optionalProperties": { "b": { "enum": ["b"] }}
+                    }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let cases: Vec<(Vec<&str>, Option<&str>)> = vec![
+            (vec![], Some("discriminator")),
+            (vec!["discriminator"], Some("discriminator")),
+            (vec!["mapping"], Some("discriminator")),
+            (vec!["mapping", "x"], Some("properties")),
+            (vec!["mapping", "x", "properties"], Some("properties")),
+            (vec!["mapping", "x", "properties", "a"], Some("ref")),
+            (vec!["mapping", "x", "properties", "a", "ref"], Some("ref")),
+            (
+                vec!["mapping", "x", "optionalProperties", "b", "enum"],
+                Some("enum"),
+            ),
+            (vec!["definitions", "a"], Some("values")),
+            (vec!["definitions", "a", "values", "type"], Some("type")),
+            (vec!["mapping", "y"], None),
+            (vec!["mapping", "x", "properties", "b"], None),
+            (vec!["mapping", "x", "definitions", "a"], None),
+            (vec!["mapping", "x", "properties", "a", "values"], None),
+            (vec!["metadata", "description"], None),
+            (vec!["bogus"], None),
+        ];
+
+        for (path, form) in cases {
+            let found = schema.get_at_path(&path).map(|schema| match schema {
+                Schema::Ref { .. } => "ref",
+                Schema::Type { .. } => "type",
+                Schema::Enum { .. } => "enum",
+                Schema::Values { .. } => "values",
+                Schema::Properties { .. } => "properties",
+                Schema::Discriminator { .. } => "discriminator",
+                _ => "other",
+            });
+
+            assert_eq!(form, found, "{:?}", path);
+        }
+    }
+}
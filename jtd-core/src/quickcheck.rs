@@ -0,0 +1,197 @@
+// Implements quickcheck's Arbitrary for schemas. The generators for instances
+// of those schemas are in jtd, along with the rest of its quickcheck module.
+
+use crate::{Definitions, Metadata, Schema, SerdeSchema, Type};
+use ::quickcheck::{Arbitrary, Gen};
+use std::collections::{BTreeMap, BTreeSet};
+
+// How deeply generated schemas nest, not counting refs.
+const MAX_DEPTH: usize = 3;
+
+// The most entries in a generated object, array, or enum.
+const MAX_LEN: usize = 4;
+
+impl Arbitrary for Schema {
+    fn arbitrary(g: &mut Gen) -> Self {
+        // Each definition may only refer to the ones before it, so that refs
+        // never form a cycle. The root may refer to any of them.
+        let mut definitions = Definitions::new();
+        let mut names = Vec::new();
+        for _ in 0..len(g) {
+            let name = String::arbitrary(g);
+            if !definitions.contains_key(&name) {
+                definitions.insert(name.clone(), schema(g, &names, 1));
+                names.push(name);
+            }
+        }
+
+        let mut root = schema(g, &names, 0);
+        *root.definitions_mut() = definitions;
+        root
+    }
+}
+
+impl Arbitrary for SerdeSchema {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Schema::arbitrary(g).into_serde_schema()
+    }
+}
+
+fn schema(g: &mut Gen, refs: &[String], depth: usize) -> Schema {
+    let definitions = Definitions::new();
+    let metadata = metadata(g);
+    let nullable = bool::arbitrary(g);
+
+    let forms: &[u8] = if depth >= MAX_DEPTH {
+        &[0, 1, 2, 3]
+    } else {
+        &[0, 1, 2, 3, 4, 5, 6, 7]
+    };
+
+    match forms[usize::arbitrary(g) % forms.len()] {
+        1 if !refs.is_empty() => Schema::Ref {
+            definitions,
+            metadata,
+            nullable,
+            ref_: g.choose(refs).unwrap().clone(),
+        },
+        0 | 1 => Schema::Empty {
+            definitions,
+            metadata,
+        },
+        2 => Schema::Type {
+            definitions,
+            metadata,
+            nullable,
+            type_: g
+                .choose(&[
+                    Type::Boolean,
+                    Type::Int8,
+                    Type::Uint8,
+                    Type::Int16,
+                    Type::Uint16,
+                    Type::Int32,
+                    Type::Uint32,
+                    Type::Float32,
+                    Type::Float64,
+                    Type::String,
+                    Type::Timestamp,
+                ])
+                .unwrap()
+                .clone(),
+        },
+        3 => {
+            let mut enum_ = BTreeSet::new();
+            enum_.insert(String::arbitrary(g));
+            for _ in 0..len(g) {
+                enum_.insert(String::arbitrary(g));
+            }
+
+            Schema::Enum {
+                definitions,
+                metadata,
+                nullable,
+                enum_,
+            }
+        }
+        4 => Schema::Elements {
+            definitions,
+            metadata,
+            nullable,
+            elements: Box::new(schema(g, refs, depth + 1)),
+        },
+        5 => properties(g, refs, depth, nullable, None),
+        6 => Schema::Values {
+            definitions,
+            metadata,
+            nullable,
+            values: Box::new(schema(g, refs, depth + 1)),
+            #[cfg(feature = "extensions")]
+            keys: None,
+        },
+        _ => {
+            // Without any mappings, there'd be no valid instances.
+            let discriminator = String::arbitrary(g);
+            let mut mapping = BTreeMap::new();
+            for _ in 0..=len(g) {
+                let schema = properties(g, refs, depth + 1, false, Some(&discriminator));
+                mapping.insert(String::arbitrary(g), schema);
+            }
+
+            Schema::Discriminator {
+                definitions,
+                metadata,
+                nullable,
+                discriminator,
+                mapping,
+            }
+        }
+    }
+}
+
+// Generates a properties-form schema. If it's in the mapping of a
+// discriminator, it must not have a property named after the discriminator.
+fn properties(
+    g: &mut Gen,
+    refs: &[String],
+    depth: usize,
+    nullable: bool,
+    discriminator: Option<&str>,
+) -> Schema {
+    let mut properties = BTreeMap::new();
+    for _ in 0..len(g) {
+        let name = String::arbitrary(g);
+        if Some(&name[..]) != discriminator {
+            properties.insert(name, schema(g, refs, depth + 1));
+        }
+    }
+
+    let mut optional_properties = BTreeMap::new();
+    for _ in 0..len(g) {
+        let name = String::arbitrary(g);
+        if Some(&name[..]) != discriminator && !properties.contains_key(&name) {
+            optional_properties.insert(name, schema(g, refs, depth + 1));
+        }
+    }
+
+    Schema::Properties {
+        definitions: Definitions::new(),
+        metadata: metadata(g),
+        nullable,
+        properties_is_present: !properties.is_empty() || bool::arbitrary(g),
+        properties,
+        optional_properties,
+        additional_properties: bool::arbitrary(g),
+    }
+}
+
+fn metadata(g: &mut Gen) -> Metadata {
+    let mut metadata = Metadata::new();
+    if bool::arbitrary(g) {
+        metadata.insert("description".to_owned(), String::arbitrary(g).into());
+    }
+
+    metadata
+}
+
+fn len(g: &mut Gen) -> usize {
+    usize::arbitrary(g) % MAX_LEN
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::quickcheck::QuickCheck;
+
+    #[test]
+    fn generates_valid_schemas() {
+        fn prop(serde_schema: SerdeSchema) -> bool {
+            let schema = Schema::from_serde_schema(serde_schema.clone()).unwrap();
+            schema.validate().is_ok() && schema.into_serde_schema() == serde_schema
+        }
+
+        QuickCheck::new()
+            .tests(500)
+            .quickcheck(prop as fn(SerdeSchema) -> bool);
+    }
+}
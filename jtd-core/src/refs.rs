@@ -0,0 +1,38 @@
+use crate::{Schema, SchemaPath, SchemaValidateError};
+
+// Follows refs from a schema to the first schema that isn't a ref, calling
+// visit with each schema along the way, from the one given to the one
+// returned. This is Schema::resolve_ref, for callers that also need to look
+// at the schemas the refs go through, such as to see if any is nullable.
+pub fn resolve_ref_with<'a, F>(
+    schema: &'a Schema,
+    root: &'a Schema,
+    mut visit: F,
+) -> Result<&'a Schema, SchemaValidateError>
+where
+    F: FnMut(&'a Schema),
+{
+    let mut schema = schema;
+    let mut visited: Vec<&str> = Vec::new();
+    visit(schema);
+
+    while let Schema::Ref { ref_, .. } = schema {
+        if let Some(index) = visited.iter().position(|name| name == ref_) {
+            return Err(SchemaValidateError::CircularRef(
+                visited[index..]
+                    .iter()
+                    .map(|&name| SchemaPath::from(vec!["definitions", name]).to_string())
+                    .collect(),
+            ));
+        }
+
+        visited.push(ref_);
+        schema = root
+            .definitions()
+            .get(ref_)
+            .ok_or_else(|| SchemaValidateError::NoSuchDefinition(ref_.clone()))?;
+        visit(schema);
+    }
+
+    Ok(schema)
+}
@@ -0,0 +1,410 @@
+use crate::{FromSerdeSchemaError, Schema, SerdeSchema};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// A way in which a schema changes when it's converted into a [`Schema`] and
+/// back, as found by [`roundtrip_check`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundtripDifference {
+    /// The path to the part of the schema that changed, as a list of JSON
+    /// object keys and array indices.
+    pub path: Vec<String>,
+
+    /// What happened to that part of the schema.
+    pub kind: RoundtripDifferenceKind,
+}
+
+/// The kinds of [`RoundtripDifference`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RoundtripDifferenceKind {
+    /// A keyword was left out, such as `"nullable": false`.
+    Dropped,
+
+    /// A keyword was added that wasn't in the original.
+    Added,
+
+    /// An array has the same values, but in a different order, such as the
+    /// values of an `enum`.
+    Reordered,
+
+    /// A value was changed.
+    Changed,
+}
+
+/// Converts a schema into a [`Schema`] and back, and reports how the result
+/// differs from the original.
+///
+/// Converting to a [`Schema`] with [`Schema::from_serde_schema`] and back
+/// with [`Schema::into_serde_schema`] preserves what a schema means, but not
+/// necessarily how it's written. This function lets tools that rewrite
+/// schemas find out what would be lost. See
+/// [`Schema::into_serde_schema_preserving`] for a conversion that loses
+/// nothing.
+///
+/// Errors if the schema can't be converted into a [`Schema`].
+///
+/// ```
+/// use jtd::{RoundtripDifference, RoundtripDifferenceKind};
+/// use serde_json::json;
+///
+/// let schema = serde_json::from_value(json!({
+///     "properties": {
+///         "status": { "enum": ["on", "off"] }
+///     },
+///     "additionalProperties": false
+/// })).unwrap();
+///
+/// assert_eq!(
+///     vec![
+///         RoundtripDifference {
+///             path: vec!["additionalProperties".into()],
+///             kind: RoundtripDifferenceKind::Dropped,
+///         },
+///         RoundtripDifference {
+///             path: vec!["properties".into(), "status".into(), "enum".into()],
+///             kind: RoundtripDifferenceKind::Reordered,
+///         },
+///     ],
+///     jtd::roundtrip_check(&schema).unwrap(),
+/// );
+/// ```
+pub fn roundtrip_check(
+    serde_schema: &SerdeSchema,
+) -> Result<Vec<RoundtripDifference>, FromSerdeSchemaError> {
+    let roundtripped = Schema::from_serde_schema(serde_schema.clone())?.into_serde_schema();
+
+    // Both of these are plain data, so they always convert into values.
+    let before = serde_json::to_value(serde_schema).unwrap_or_default();
+    let after = serde_json::to_value(roundtripped).unwrap_or_default();
+
+    let mut differences = vec![];
+    diff(&mut vec![], &before, &after, &mut differences);
+    Ok(differences)
+}
+
+fn diff(
+    path: &mut Vec<String>,
+    before: &Value,
+    after: &Value,
+    differences: &mut Vec<RoundtripDifference>,
+) {
+    if before == after {
+        return;
+    }
+
+    let kind = match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            for (key, before) in before {
+                path.push(key.clone());
+                match after.get(key) {
+                    Some(after) => diff(path, before, after, differences),
+                    None => differences.push(RoundtripDifference {
+                        path: path.clone(),
+                        kind: RoundtripDifferenceKind::Dropped,
+                    }),
+                }
+                path.pop();
+            }
+
+            for key in after.keys().filter(|key| !before.contains_key(*key)) {
+                path.push(key.clone());
+                differences.push(RoundtripDifference {
+                    path: path.clone(),
+                    kind: RoundtripDifferenceKind::Added,
+                });
+                path.pop();
+            }
+
+            return;
+        }
+        (Value::Array(before), Value::Array(after)) if is_reordering(before, after) => {
+            RoundtripDifferenceKind::Reordered
+        }
+        _ => RoundtripDifferenceKind::Changed,
+    };
+
+    differences.push(RoundtripDifference {
+        path: path.clone(),
+        kind,
+    });
+}
+
+// Whether after is a reordering of before.
+fn is_reordering(before: &[Value], after: &[Value]) -> bool {
+    let mut remaining: Vec<&Value> = after.iter().collect();
+    before.len() == after.len()
+        && before.iter().all(|value| {
+            match remaining.iter().position(|candidate| *candidate == value) {
+                Some(i) => {
+                    remaining.swap_remove(i);
+                    true
+                }
+                None => false,
+            }
+        })
+}
+
+impl Schema {
+    /// Converts a [`Schema`] into a [`SerdeSchema`], keeping the keywords the
+    /// author wrote explicitly in `original`.
+    ///
+    /// [`Schema`] doesn't keep track of keywords that have no effect, such as
+    /// `"nullable": false`, `"additionalProperties": false`, or empty
+    /// `definitions` and `metadata`, nor of the order of `enum` values. So
+    /// [`Schema::into_serde_schema`] leaves out such keywords, and sorts enum
+    /// values. This function instead carries them over from `original`, which
+    /// is usually the [`SerdeSchema`] that this schema was constructed from.
+    /// Round-tripping a schema through [`Schema::from_serde_schema`] and this
+    /// function reproduces it exactly.
+    ///
+    /// `original` is followed alongside this schema, and a keyword is only
+    /// carried over where it wouldn't change what the schema means. So if the
+    /// schema has been modified since it was constructed, the modifications
+    /// are kept.
+    ///
+    /// ```
+    /// use jtd::{Schema, SerdeSchema};
+    /// use serde_json::json;
+    ///
+    /// let original: SerdeSchema = serde_json::from_value(json!({
+    ///     "properties": {
+    ///         "status": { "enum": ["on", "off"], "nullable": false }
+    ///     },
+    ///     "additionalProperties": false
+    /// })).unwrap();
+    ///
+    /// let schema = Schema::from_serde_schema(original.clone()).unwrap();
+    /// assert_eq!(
+    ///     json!({ "properties": { "status": { "enum": ["off", "on"] }}}),
+    ///     serde_json::to_value(schema.clone().into_serde_schema()).unwrap(),
+    /// );
+    ///
+    /// assert_eq!(original, schema.into_serde_schema_preserving(&original));
+    /// ```
+    pub fn into_serde_schema_preserving(self, original: &SerdeSchema) -> SerdeSchema {
+        let mut serde_schema = self.into_serde_schema();
+        preserve(&mut serde_schema, original);
+        serde_schema
+    }
+}
+
+// Carries over from original the keywords that into_serde_schema leaves out of
+// serde_schema, where doing so doesn't change the meaning of serde_schema.
+fn preserve(serde_schema: &mut SerdeSchema, original: &SerdeSchema) {
+    let is_empty_form = *serde_schema
+        == SerdeSchema {
+            definitions: serde_schema.definitions.clone(),
+            metadata: serde_schema.metadata.clone(),
+            nullable: serde_schema.nullable,
+            ..Default::default()
+        };
+
+    // Schemas of the empty form accept null anyway, so "nullable": true can
+    // be kept on them as well.
+    if serde_schema.nullable.is_none() {
+        serde_schema.nullable = match original.nullable {
+            Some(true) if !is_empty_form => None,
+            nullable => nullable,
+        };
+    }
+
+    preserve_empty(&mut serde_schema.definitions, &original.definitions);
+    preserve_empty(&mut serde_schema.metadata, &original.metadata);
+
+    if serde_schema.properties.is_some() || serde_schema.optional_properties.is_some() {
+        if serde_schema.additional_properties.is_none()
+            && original.additional_properties == Some(false)
+        {
+            serde_schema.additional_properties = Some(false);
+        }
+
+        preserve_empty(
+            &mut serde_schema.optional_properties,
+            &original.optional_properties,
+        );
+    }
+
+    preserve_order(&mut serde_schema.enum_, &original.enum_);
+    #[cfg(feature = "extensions")]
+    preserve_order(&mut serde_schema.int_enum, &original.int_enum);
+
+    preserve_all(&mut serde_schema.definitions, &original.definitions);
+    preserve_all(&mut serde_schema.properties, &original.properties);
+    preserve_all(
+        &mut serde_schema.optional_properties,
+        &original.optional_properties,
+    );
+    preserve_all(&mut serde_schema.mapping, &original.mapping);
+
+    if let (Some(elements), Some(original)) = (&mut serde_schema.elements, &original.elements) {
+        preserve(elements, original);
+    }
+
+    if let (Some(values), Some(original)) = (&mut serde_schema.values, &original.values) {
+        preserve(values, original);
+    }
+
+    #[cfg(feature = "extensions")]
+    if let (Some(keys), Some(original)) = (&mut serde_schema.keys, &original.keys) {
+        preserve(keys, original);
+    }
+}
+
+fn preserve_empty<T>(
+    map: &mut Option<BTreeMap<String, T>>,
+    original: &Option<BTreeMap<String, T>>,
+) {
+    if map.is_none()
+        && original
+            .as_ref()
+            .is_some_and(|original| original.is_empty())
+    {
+        *map = Some(BTreeMap::new());
+    }
+}
+
+fn preserve_order<T: Ord + Clone>(values: &mut Option<Vec<T>>, original: &Option<Vec<T>>) {
+    if let (Some(values), Some(original)) = (values, original) {
+        let mut sorted = original.clone();
+        sorted.sort();
+
+        if *values == sorted {
+            values.clone_from(original);
+        }
+    }
+}
+
+fn preserve_all(
+    schemas: &mut Option<BTreeMap<String, SerdeSchema>>,
+    original: &Option<BTreeMap<String, SerdeSchema>>,
+) {
+    if let (Some(schemas), Some(original)) = (schemas, original) {
+        for (name, serde_schema) in schemas {
+            if let Some(original) = original.get(name) {
+                preserve(serde_schema, original);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Schema, SerdeSchema};
+    use serde_json::json;
+
+    fn roundtrip(original: &SerdeSchema, schema: Schema) -> serde_json::Value {
+        serde_json::to_value(schema.into_serde_schema_preserving(original)).unwrap()
+    }
+
+    #[test]
+    fn preserves_explicit_keywords() {
+        let original = json!({
+            "definitions": {
+                "a": { "optionalProperties": {}, "nullable": false },
+                "b": { "properties": {}, "optionalProperties": {}, "metadata": {} }
+            },
+            "nullable": true,
+            "metadata": {}
+        });
+
+        let serde_schema: SerdeSchema = serde_json::from_value(original.clone()).unwrap();
+        let schema = Schema::from_serde_schema(serde_schema.clone()).unwrap();
+        assert_eq!(original, roundtrip(&serde_schema, schema));
+    }
+
+    #[test]
+    fn roundtrip_check() {
+        use super::RoundtripDifferenceKind::*;
+
+        let serde_schema: SerdeSchema = serde_json::from_value(json!({
+            "definitions": {},
+            "elements": {
+                "enum": ["b", "a"],
+                "nullable": false,
+                "metadata": { "x": [2, 1] }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                (vec!["definitions"], Dropped),
+                (vec!["elements", "enum"], Reordered),
+                (vec!["elements", "nullable"], Dropped),
+            ],
+            super::roundtrip_check(&serde_schema)
+                .unwrap()
+                .iter()
+                .map(|d| (d.path.iter().map(|s| &s[..]).collect::<Vec<_>>(), d.kind))
+                .collect::<Vec<_>>()
+        );
+
+        let lossless: SerdeSchema =
+            serde_json::from_value(json!({ "values": { "type": "string" }})).unwrap();
+        assert!(super::roundtrip_check(&lossless).unwrap().is_empty());
+
+        let invalid: SerdeSchema = serde_json::from_value(json!({ "type": "nope" })).unwrap();
+        assert!(super::roundtrip_check(&invalid).is_err());
+
+        let mut differences = vec![];
+        super::diff(
+            &mut vec![],
+            &json!({ "a": [1, 2], "b": [1, 1] }),
+            &json!({ "a": [2, 1], "b": [1, 2], "c": true }),
+            &mut differences,
+        );
+        assert_eq!(
+            vec![Reordered, Changed, Added],
+            differences.iter().map(|d| d.kind).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn keeps_modifications() {
+        let serde_schema: SerdeSchema = serde_json::from_value(json!({
+            "properties": {
+                "a": { "enum": ["z", "y", "x"], "nullable": true },
+                "b": { "type": "string", "nullable": false }
+            },
+            "additionalProperties": false
+        }))
+        .unwrap();
+
+        let mut schema = Schema::from_serde_schema(serde_schema.clone()).unwrap();
+        if let Schema::Properties {
+            properties,
+            additional_properties,
+            ..
+        } = &mut schema
+        {
+            *additional_properties = true;
+
+            if let Some(Schema::Enum {
+                enum_, nullable, ..
+            }) = properties.get_mut("a")
+            {
+                enum_.insert("w".to_owned());
+                *nullable = false;
+            }
+
+            properties.insert(
+                "b".to_owned(),
+                Schema::Empty {
+                    definitions: Default::default(),
+                    metadata: Default::default(),
+                },
+            );
+        }
+
+        assert_eq!(
+            json!({
+                "properties": {
+                    "a": { "enum": ["w", "x", "y", "z"] },
+                    "b": { "nullable": false }
+                },
+                "additionalProperties": true
+            }),
+            roundtrip(&serde_schema, schema)
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+
+/// A position in a configuration file. Lines and columns both start at 1, and
+/// columns count characters, not bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    /// The line number.
+    pub line: usize,
+
+    /// The column number.
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+// Where the values at each instance path start.
+#[doc(hidden)]
+pub type Locations = BTreeMap<Vec<String>, Location>;
+
+// Converts byte offsets into locations.
+#[doc(hidden)]
+pub struct LineIndex<'a> {
+    input: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(input: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self { input, starts }
+    }
+
+    pub fn locate(&self, offset: usize) -> Location {
+        let line = match self.starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+
+        let start = self.starts[line];
+        let column = self
+            .input
+            .get(start..offset)
+            .map(|s| s.chars().count())
+            .unwrap_or(0);
+
+        Location {
+            line: line + 1,
+            column: column + 1,
+        }
+    }
+}
+
+// Finds where each value in a known-valid JSON document starts, and the
+// span of bytes it takes up. Methods return None if the input turns out not
+// to be valid after all.
+#[doc(hidden)]
+pub struct JsonScanner<'a> {
+    input: &'a str,
+    lines: LineIndex<'a>,
+    pos: usize,
+    path: Vec<String>,
+    pub locations: Locations,
+    pub spans: BTreeMap<Vec<String>, Range<usize>>,
+}
+
+impl<'a> JsonScanner<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            lines: LineIndex::new(input),
+            pos: 0,
+            path: Vec::new(),
+            locations: Locations::new(),
+            spans: BTreeMap::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    pub fn value(&mut self) -> Option<()> {
+        self.skip_whitespace();
+        self.locations
+            .insert(self.path.clone(), self.lines.locate(self.pos));
+
+        let start = self.pos;
+        self.value_contents()?;
+        self.spans.insert(self.path.clone(), start..self.pos);
+        Some(())
+    }
+
+    fn value_contents(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.peek()? == b'}' {
+                    self.pos += 1;
+                    return Some(());
+                }
+
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    if self.peek()? != b':' {
+                        return None;
+                    }
+
+                    self.pos += 1;
+                    self.path.push(serde_json::from_str(key).ok()?);
+                    self.value()?;
+                    self.path.pop();
+
+                    if !self.separator(b'}')? {
+                        return Some(());
+                    }
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.peek()? == b']' {
+                    self.pos += 1;
+                    return Some(());
+                }
+
+                for index in 0.. {
+                    self.path.push(index.to_string());
+                    self.value()?;
+                    self.path.pop();
+
+                    if !self.separator(b']')? {
+                        break;
+                    }
+                }
+
+                Some(())
+            }
+            b'"' => self.string().map(|_| ()),
+            _ => {
+                while let Some(b) = self.peek() {
+                    if matches!(b, b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r') {
+                        break;
+                    }
+
+                    self.pos += 1;
+                }
+
+                Some(())
+            }
+        }
+    }
+
+    // Consumes a string, returning its raw text, quotes included.
+    fn string(&mut self) -> Option<&'a str> {
+        let start = self.pos;
+        if self.peek()? != b'"' {
+            return None;
+        }
+
+        self.pos += 1;
+        loop {
+            match self.peek()? {
+                b'"' => break,
+                b'\\' => self.pos += 2,
+                _ => self.pos += 1,
+            }
+        }
+
+        self.pos += 1;
+        self.input.get(start..self.pos)
+    }
+
+    // Consumes either a comma, returning true, or the given closing
+    // character, returning false.
+    fn separator(&mut self, close: u8) -> Option<bool> {
+        self.skip_whitespace();
+        let b = self.peek()?;
+        self.pos += 1;
+
+        if b == b',' {
+            Some(true)
+        } else if b == close {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
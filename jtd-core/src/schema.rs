@@ -15,9 +15,10 @@ pub type Metadata = BTreeMap<String, Value>;
 /// may take on. All of the forms share the following fields:
 ///
 /// * `definitions` corresponds to the JSON Typedef keyword of the same name.
-///    This should only be non-empty on root schemas. Otherwise,
-///    [`Schema::validate`] will return
-///    [`SchemaValidateError::NonRootDefinitions`].
+///   This should only be non-empty on root schemas. Otherwise,
+///   [`Schema::validate`] will return
+///   [`SchemaValidateError::NonRootDefinitions`]. Use
+///   [`Schema::hoist_definitions`] to move them to the root.
 ///
 /// * `metadata` corresponds to the JSON Typedef keyword of the same name. Use
 ///   this to convey information not pertinent to validation, such as hints for
@@ -41,6 +42,14 @@ pub type Metadata = BTreeMap<String, Value>;
 /// If you are trying to parse a JSON Typedef schema from JSON, see
 /// [`SerdeSchema`] and [`Schema::from_serde_schema`].
 ///
+/// Cloning a schema copies all of it, which can be costly for large schemas.
+/// To share one schema between threads, tasks, or requests, wrap it in an
+/// [`Arc`][`std::sync::Arc`] instead, and clone that. Types in `jtd` that
+/// hold on to schemas take them that way too, such as
+/// [`SchemaStore`](https://docs.rs/jtd/latest/jtd/struct.SchemaStore.html)
+/// and
+/// [`ContractRecorder`](https://docs.rs/jtd/latest/jtd/struct.ContractRecorder.html).
+///
 /// ```
 /// use jtd::{SerdeSchema, Schema};
 /// use serde_json::json;
@@ -116,6 +125,21 @@ pub enum Schema {
         enum_: BTreeSet<String>,
     },
 
+    /// The integer enum form. This is an extension to JSON Typedef.
+    ///
+    /// This form is written like the enum form, but with integers instead of
+    /// strings, as in `{ "enum": [1, 2, 3] }`. It accepts JSON numbers that
+    /// are equal to one of those integers.
+    #[cfg(feature = "extensions")]
+    IntEnum {
+        definitions: Definitions,
+        metadata: Metadata,
+        nullable: bool,
+
+        /// The values the schema accepts.
+        enum_: BTreeSet<i64>,
+    },
+
     /// The [elements](https://tools.ietf.org/html/rfc8927#section-2.2.5) form.
     ///
     /// The elements form accepts JSON arrays, and each element of the array is
@@ -185,6 +209,11 @@ pub enum Schema {
 
         /// A schema for the values of the "dictionary" object.
         values: Box<Schema>,
+
+        /// A constraint on the keys of the "dictionary" object. This is an
+        /// extension to JSON Typedef.
+        #[cfg(feature = "extensions")]
+        keys: Option<crate::extensions::Keys>,
     },
 
     /// The [discriminator](https://tools.ietf.org/html/rfc8927#section-2.2.8)
@@ -246,6 +275,17 @@ pub enum Type {
     /// A JSON string encoding a [RFC3339](https://tools.ietf.org/html/rfc3339)
     /// timestamp.
     Timestamp,
+
+    /// A type registered in a [`TypeExtensions`][`crate::TypeExtensions`],
+    /// named by the string given. This is an extension to JSON Typedef.
+    ///
+    /// Schemas only contain this type if they were parsed with
+    /// [`Schema::from_serde_schema_with_extensions`], and [`validate()`]
+    /// checks it with the callback registered under its name.
+    ///
+    /// [`validate()`]: crate::validate()
+    #[cfg(feature = "extensions")]
+    Custom(String),
 }
 
 /// Errors that may arise from [`Schema::from_serde_schema`].
@@ -305,6 +345,140 @@ pub enum FromSerdeSchemaError {
     /// ```
     #[error("duplicated enum value: {0:?}")]
     DuplicatedEnumValue(String),
+
+    /// The schema is made up of more schemas than
+    /// [`ParseLimits::max_nodes`] allows.
+    #[error("too many schemas in schema")]
+    TooManyNodes,
+
+    /// The schema has schemas nested deeper than [`ParseLimits::max_depth`]
+    /// allows.
+    #[error("schema nested too deeply")]
+    MaxDepthExceeded,
+
+    /// The schema has more definitions than [`ParseLimits::max_definitions`]
+    /// allows.
+    #[error("too many definitions in schema")]
+    TooManyDefinitions,
+
+    /// A `keys` schema was not of one of the forms described in
+    /// [`Keys`][`crate::Keys`].
+    #[cfg(feature = "extensions")]
+    #[error("invalid keys schema")]
+    InvalidKeys,
+
+    /// A `pattern` was not a valid regular expression. The first field is the
+    /// pattern, and the second describes what's wrong with it.
+    #[cfg(feature = "extensions")]
+    #[error("invalid pattern {0:?}: {1}")]
+    InvalidPattern(String, String),
+
+    /// An integer enum has the same value more than once.
+    #[cfg(feature = "extensions")]
+    #[error("duplicated enum value: {0}")]
+    DuplicatedIntEnumValue(i64),
+}
+
+/// Limits on the size of a schema, for
+/// [`Schema::from_serde_schema_with_limits`].
+///
+/// A limit of zero, as all of them are by default, means no limit.
+///
+/// ```
+/// use jtd::{FromSerdeSchemaError, ParseLimits, Schema};
+/// use serde_json::json;
+///
+/// let limits = ParseLimits {
+///     max_nodes: 100,
+///     max_depth: 3,
+///     ..Default::default()
+/// };
+///
+/// // The elements of the elements of the elements are four schemas deep.
+/// let schema = serde_json::from_value(json!({
+///     "elements": { "elements": { "elements": {}}}
+/// })).unwrap();
+///
+/// assert_eq!(
+///     Err(FromSerdeSchemaError::MaxDepthExceeded),
+///     Schema::from_serde_schema_with_limits(schema, limits),
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// The most schemas the schema may be made up of, counting itself, its
+    /// definitions, and every schema within them.
+    pub max_nodes: usize,
+
+    /// The most schemas deep the schema may nest, counting itself as one
+    /// deep. Definitions are one deeper than the root.
+    pub max_depth: usize,
+
+    /// The most definitions the schema may have.
+    pub max_definitions: usize,
+}
+
+impl ParseLimits {
+    /// Checks a [`SerdeSchema`] against these limits, without constructing a
+    /// [`Schema`] from it.
+    ///
+    /// This is what [`Schema::from_serde_schema_with_limits`] does before
+    /// constructing a [`Schema`]. Use it directly to combine limits with
+    /// another way of constructing one.
+    pub fn check(&self, serde_schema: &SerdeSchema) -> Result<(), FromSerdeSchemaError> {
+        let exceeds = |len: usize, max: usize| max != 0 && len > max;
+
+        let definitions = serde_schema.definitions.as_ref().map_or(0, BTreeMap::len);
+        if exceeds(definitions, self.max_definitions) {
+            return Err(FromSerdeSchemaError::TooManyDefinitions);
+        }
+
+        // This doesn't recurse, so that it can't overflow the stack on deeply
+        // nested schemas, which are what it's meant to catch.
+        let mut nodes = 0;
+        let mut stack = vec![(serde_schema, 1)];
+        while let Some((schema, depth)) = stack.pop() {
+            nodes += 1;
+            if exceeds(nodes, self.max_nodes) {
+                return Err(FromSerdeSchemaError::TooManyNodes);
+            }
+
+            if exceeds(depth, self.max_depth) {
+                return Err(FromSerdeSchemaError::MaxDepthExceeded);
+            }
+
+            let maps = [
+                &schema.definitions,
+                &schema.properties,
+                &schema.optional_properties,
+                &schema.mapping,
+            ];
+
+            let boxes = [
+                &schema.elements,
+                &schema.values,
+                #[cfg(feature = "extensions")]
+                &schema.keys,
+            ];
+
+            let children = maps
+                .iter()
+                .copied()
+                .flatten()
+                .flat_map(BTreeMap::values)
+                .chain(
+                    boxes
+                        .iter()
+                        .copied()
+                        .flatten()
+                        .map(|sub_schema| &**sub_schema),
+                );
+
+            stack.extend(children.map(|sub_schema| (sub_schema, depth + 1)));
+        }
+
+        Ok(())
+    }
 }
 
 /// Errors that may arise from [`Schema::validate`].
@@ -514,6 +688,39 @@ pub enum SchemaValidateError {
     /// ```
     #[error("discriminator redefined in mapping: {0:?}")]
     RepeatedDiscriminator(String),
+
+    /// Indicates the schema has definitions that are refs to one another in a
+    /// cycle, with nothing but refs along the way. The paths to the
+    /// definitions, as JSON Pointers, are given in the order they refer to one
+    /// another.
+    ///
+    /// Such schemas are allowed by RFC 8927, so [`Schema::validate`] accepts
+    /// them. Only [`Schema::validate_with_cycle_check`] and
+    /// [`Schema::resolve_ref`] return this error.
+    ///
+    /// ```
+    /// use jtd::{Schema, SchemaValidateError};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "definitions": {
+    ///         "a": { "ref": "b" },
+    ///         "b": { "ref": "a" }
+    ///     },
+    ///     "ref": "a"
+    /// })).unwrap()).unwrap();
+    ///
+    /// assert_eq!(Ok(()), schema.validate());
+    /// assert_eq!(
+    ///     Err(SchemaValidateError::CircularRef(vec![
+    ///         "/definitions/b".into(),
+    ///         "/definitions/a".into(),
+    ///     ])),
+    ///     schema.validate_with_cycle_check(),
+    /// );
+    /// ```
+    #[error("circular refs between definitions at {}", .0.join(", "))]
+    CircularRef(Vec<String>),
 }
 
 // Index of valid form "signatures" -- i.e., combinations of the presence of the
@@ -628,7 +835,7 @@ impl Schema {
                 serde_schema.metadata = Self::metadata_into_serde_schema(metadata);
                 serde_schema.nullable = Self::nullable_into_serde_schema(nullable);
                 serde_schema.type_ = Some(
-                    match type_ {
+                    match &type_ {
                         Type::Boolean => "boolean",
                         Type::Int8 => "int8",
                         Type::Uint8 => "uint8",
@@ -640,6 +847,8 @@ impl Schema {
                         Type::Float64 => "float64",
                         Type::String => "string",
                         Type::Timestamp => "timestamp",
+                        #[cfg(feature = "extensions")]
+                        Type::Custom(name) => name.as_str(),
                     }
                     .to_owned(),
                 );
@@ -657,6 +866,19 @@ impl Schema {
                 serde_schema.enum_ = Some(enum_.into_iter().collect());
             }
 
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum {
+                definitions,
+                metadata,
+                nullable,
+                enum_,
+            } => {
+                serde_schema.definitions = Self::definitions_into_serde_schema(definitions);
+                serde_schema.metadata = Self::metadata_into_serde_schema(metadata);
+                serde_schema.nullable = Self::nullable_into_serde_schema(nullable);
+                serde_schema.int_enum = Some(enum_.into_iter().collect());
+            }
+
             Schema::Elements {
                 definitions,
                 metadata,
@@ -691,7 +913,9 @@ impl Schema {
                     );
                 }
 
-                if !optional_properties.is_empty() {
+                // Without properties, optionalProperties is what makes this
+                // the properties form, so it's needed even when empty.
+                if !properties_is_present || !optional_properties.is_empty() {
                     serde_schema.optional_properties = Some(
                         optional_properties
                             .into_iter()
@@ -710,11 +934,18 @@ impl Schema {
                 metadata,
                 nullable,
                 values,
+                #[cfg(feature = "extensions")]
+                keys,
             } => {
                 serde_schema.definitions = Self::definitions_into_serde_schema(definitions);
                 serde_schema.metadata = Self::metadata_into_serde_schema(metadata);
                 serde_schema.nullable = Self::nullable_into_serde_schema(nullable);
                 serde_schema.values = Some(Box::new(values.into_serde_schema()));
+
+                #[cfg(feature = "extensions")]
+                {
+                    serde_schema.keys = keys.map(|keys| Box::new(keys.into_serde_schema()));
+                }
             }
 
             Schema::Discriminator {
@@ -793,19 +1024,82 @@ impl Schema {
     /// See the documentation for [`FromSerdeSchemaError`] for examples of how
     /// this function may return an error.
     pub fn from_serde_schema(serde_schema: SerdeSchema) -> Result<Self, FromSerdeSchemaError> {
+        Self::from_serde_schema_with(serde_schema, &|_| false)
+    }
+
+    /// Constructs a [`Schema`] from a [`SerdeSchema`], allowing the types
+    /// registered in `types` as values of `type`, in addition to the types of
+    /// RFC 8927. They become [`Type::Custom`].
+    ///
+    /// See [`TypeExtensions`][`crate::TypeExtensions`] for an example.
+    ///
+    /// This function is only available with the `extensions` feature.
+    #[cfg(feature = "extensions")]
+    pub fn from_serde_schema_with_extensions(
+        serde_schema: SerdeSchema,
+        types: &crate::extensions::TypeExtensions,
+    ) -> Result<Self, FromSerdeSchemaError> {
+        Self::from_serde_schema_with(serde_schema, &|name| types.contains(name))
+    }
+
+    /// Constructs a [`Schema`] from a [`SerdeSchema`], if the schema is within
+    /// `limits`.
+    ///
+    /// Services that accept schemas from their users should bound how large
+    /// those schemas can be, as a large enough schema can use up the memory
+    /// of the service, and a deeply nested one its stack. This checks the
+    /// [`SerdeSchema`] first, so a schema over the limits is rejected before
+    /// any of the [`Schema`] is constructed. See [`ParseLimits`] for an
+    /// example.
+    ///
+    /// The limits bound the size of the schema, but not the work validating
+    /// against it can take. See [`ValidateOptions::with_max_depth`] for that.
+    ///
+    /// [`ValidateOptions::with_max_depth`]: crate::ValidateOptions::with_max_depth
+    pub fn from_serde_schema_with_limits(
+        serde_schema: SerdeSchema,
+        limits: ParseLimits,
+    ) -> Result<Self, FromSerdeSchemaError> {
+        limits.check(&serde_schema)?;
+        Self::from_serde_schema(serde_schema)
+    }
+
+    // Constructs a schema, where is_custom_type says which values of type,
+    // other than the types of RFC 8927, are allowed.
+    #[cfg_attr(
+        not(feature = "extensions"),
+        allow(unused_variables, clippy::only_used_in_recursion)
+    )]
+    fn from_serde_schema_with(
+        serde_schema: SerdeSchema,
+        is_custom_type: &dyn Fn(&str) -> bool,
+    ) -> Result<Self, FromSerdeSchemaError> {
         let mut definitions = BTreeMap::new();
         for (name, sub_schema) in serde_schema.definitions.unwrap_or_default() {
-            definitions.insert(name, Self::from_serde_schema(sub_schema)?);
+            definitions.insert(
+                name,
+                Self::from_serde_schema_with(sub_schema, is_custom_type)?,
+            );
         }
 
         let metadata = serde_schema.metadata.unwrap_or_default();
         let nullable = serde_schema.nullable.unwrap_or(false);
 
+        // An integer enum is written with the enum keyword, so it takes the
+        // same place in the form signature as a string enum does.
+        #[cfg(not(feature = "extensions"))]
+        let has_enum = serde_schema.enum_.is_some();
+        #[cfg(feature = "extensions")]
+        let has_enum = match (&serde_schema.enum_, &serde_schema.int_enum) {
+            (Some(_), Some(_)) => return Err(FromSerdeSchemaError::InvalidForm),
+            (enum_, int_enum) => enum_.is_some() || int_enum.is_some(),
+        };
+
         // Ensure the schema is using a valid combination of keywords.
         let form_signature = [
             serde_schema.ref_.is_some(),
             serde_schema.type_.is_some(),
-            serde_schema.enum_.is_some(),
+            has_enum,
             serde_schema.elements.is_some(),
             serde_schema.properties.is_some(),
             serde_schema.optional_properties.is_some(),
@@ -819,6 +1113,15 @@ impl Schema {
             return Err(FromSerdeSchemaError::InvalidForm);
         }
 
+        // The keys extension may only accompany values, and pattern may only
+        // appear within keys, where it's handled by Keys::from_serde_schema.
+        #[cfg(feature = "extensions")]
+        if (serde_schema.keys.is_some() && serde_schema.values.is_none())
+            || serde_schema.pattern.is_some()
+        {
+            return Err(FromSerdeSchemaError::InvalidForm);
+        }
+
         // From here on out, we can use the presence of certain keywords to
         // determine the form the schema takes on.
         //
@@ -846,6 +1149,8 @@ impl Schema {
                 "float64" => Type::Float64,
                 "string" => Type::String,
                 "timestamp" => Type::Timestamp,
+                #[cfg(feature = "extensions")]
+                name if is_custom_type(name) => Type::Custom(type_.clone()),
                 _ => return Err(FromSerdeSchemaError::InvalidType(type_)),
             };
 
@@ -878,12 +1183,29 @@ impl Schema {
             });
         }
 
+        #[cfg(feature = "extensions")]
+        if let Some(int_enum) = serde_schema.int_enum {
+            let mut values = BTreeSet::new();
+            for value in int_enum {
+                if !values.insert(value) {
+                    return Err(FromSerdeSchemaError::DuplicatedIntEnumValue(value));
+                }
+            }
+
+            return Ok(Schema::IntEnum {
+                definitions,
+                metadata,
+                nullable,
+                enum_: values,
+            });
+        }
+
         if let Some(elements) = serde_schema.elements {
             return Ok(Schema::Elements {
                 definitions,
                 metadata,
                 nullable,
-                elements: Box::new(Self::from_serde_schema(*elements)?),
+                elements: Box::new(Self::from_serde_schema_with(*elements, is_custom_type)?),
             });
         }
 
@@ -893,12 +1215,18 @@ impl Schema {
 
             let mut properties = BTreeMap::new();
             for (name, sub_schema) in serde_schema.properties.unwrap_or_default() {
-                properties.insert(name, Self::from_serde_schema(sub_schema)?);
+                properties.insert(
+                    name,
+                    Self::from_serde_schema_with(sub_schema, is_custom_type)?,
+                );
             }
 
             let mut optional_properties = BTreeMap::new();
             for (name, sub_schema) in serde_schema.optional_properties.unwrap_or_default() {
-                optional_properties.insert(name, Self::from_serde_schema(sub_schema)?);
+                optional_properties.insert(
+                    name,
+                    Self::from_serde_schema_with(sub_schema, is_custom_type)?,
+                );
             }
 
             return Ok(Schema::Properties {
@@ -917,7 +1245,12 @@ impl Schema {
                 definitions,
                 metadata,
                 nullable,
-                values: Box::new(Self::from_serde_schema(*values)?),
+                values: Box::new(Self::from_serde_schema_with(*values, is_custom_type)?),
+                #[cfg(feature = "extensions")]
+                keys: serde_schema
+                    .keys
+                    .map(|keys| crate::extensions::Keys::from_serde_schema(*keys))
+                    .transpose()?,
             });
         }
 
@@ -926,7 +1259,10 @@ impl Schema {
             // present if discriminator is present.
             let mut mapping = BTreeMap::new();
             for (name, sub_schema) in serde_schema.mapping.unwrap() {
-                mapping.insert(name, Self::from_serde_schema(sub_schema)?);
+                mapping.insert(
+                    name,
+                    Self::from_serde_schema_with(sub_schema, is_custom_type)?,
+                );
             }
 
             return Ok(Schema::Discriminator {
@@ -962,18 +1298,131 @@ impl Schema {
     /// See the documentation for [`SchemaValidateError`] for examples of how
     /// this function may return an error.
     pub fn validate(&self) -> Result<(), SchemaValidateError> {
-        self._validate(None)
+        self._validate(None, &mut Vec::new())
+    }
+
+    /// Ensures a [`Schema`] is well-formed, like [`Schema::validate`], but on
+    /// failure also returns where in the schema the problem is, as a path of
+    /// JSON Pointer tokens.
+    ///
+    /// ```
+    /// use jtd::{Schema, SchemaValidateError};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "properties": { "a": { "elements": { "ref": "missing" }}}
+    /// })).unwrap()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     Err((
+    ///         vec!["properties".to_owned(), "a".to_owned(), "elements".to_owned(), "ref".to_owned()],
+    ///         SchemaValidateError::NoSuchDefinition("missing".to_owned()),
+    ///     )),
+    ///     schema.validate_with_path(),
+    /// );
+    /// ```
+    pub fn validate_with_path(&self) -> Result<(), (Vec<String>, SchemaValidateError)> {
+        let mut path = Vec::new();
+        self._validate(None, &mut path).map_err(|err| (path, err))
+    }
+
+    /// Ensures a [`Schema`] is well-formed, like [`Schema::validate`], and
+    /// also that none of its definitions lead only to one another through
+    /// refs.
+    ///
+    /// RFC 8927 allows definitions such as `{ "loop": { "ref": "loop" } }`,
+    /// but there's no instance they can be checked against in a finite number
+    /// of steps: [`validate()`][`crate::validate()`] goes around them until it
+    /// reaches [`ValidateOptions::with_max_depth`][`crate::ValidateOptions::with_max_depth`],
+    /// or forever if there's no maximum depth. This function rejects them
+    /// with [`SchemaValidateError::CircularRef`], so that such schemas can be
+    /// turned away when they're loaded.
+    ///
+    /// Definitions that refer to themselves through other forms, such as
+    /// `{ "node": { "elements": { "ref": "node" } } }`, are fine, and are
+    /// accepted.
+    ///
+    /// ```
+    /// use jtd::{Schema, SchemaValidateError};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "definitions": {
+    ///         "node": { "elements": { "ref": "node" }},
+    ///         "loop": { "ref": "loop" }
+    ///     }
+    /// })).unwrap()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     Err(SchemaValidateError::CircularRef(vec!["/definitions/loop".into()])),
+    ///     schema.validate_with_cycle_check(),
+    /// );
+    /// ```
+    pub fn validate_with_cycle_check(&self) -> Result<(), SchemaValidateError> {
+        self.validate()?;
+
+        for definition in self.definitions().values() {
+            definition.resolve_ref(self)?;
+        }
+
+        Ok(())
+    }
+
+    /// Follows refs from this schema to the first schema that isn't a ref.
+    ///
+    /// Refs are looked up in the definitions of `root`, which should be the
+    /// root of the schema this one is part of. If this schema isn't a ref,
+    /// it's returned as it is.
+    ///
+    /// Returns [`SchemaValidateError::NoSuchDefinition`] if a ref is to a
+    /// definition that doesn't exist, and [`SchemaValidateError::CircularRef`]
+    /// if refs lead around in a cycle, never reaching a schema that isn't a
+    /// ref.
+    ///
+    /// ```
+    /// use jtd::{Schema, Type};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "definitions": {
+    ///         "id": { "ref": "uuid" },
+    ///         "uuid": { "type": "string" }
+    ///     },
+    ///     "elements": { "ref": "id" }
+    /// })).unwrap()).unwrap();
+    ///
+    /// let elements = match &schema {
+    ///     Schema::Elements { elements, .. } => elements,
+    ///     _ => unreachable!(),
+    /// };
+    ///
+    /// assert!(matches!(
+    ///     elements.resolve_ref(&schema),
+    ///     Ok(Schema::Type { type_: Type::String, .. }),
+    /// ));
+    /// ```
+    pub fn resolve_ref<'a>(&'a self, root: &'a Schema) -> Result<&'a Schema, SchemaValidateError> {
+        crate::refs::resolve_ref_with(self, root, |_| {})
     }
 
-    fn _validate(&self, root: Option<&Self>) -> Result<(), SchemaValidateError> {
+    // Checks the schema at path. If it fails, path is left pointing at the
+    // problem.
+    fn _validate(
+        &self,
+        root: Option<&Self>,
+        path: &mut Vec<String>,
+    ) -> Result<(), SchemaValidateError> {
         let sub_root = root.or(Some(self));
 
         if root.is_some() && !self.definitions().is_empty() {
+            path.push("definitions".to_owned());
             return Err(SchemaValidateError::NonRootDefinitions);
         }
 
-        for sub_schema in self.definitions().values() {
-            sub_schema._validate(sub_root)?;
+        for (name, sub_schema) in self.definitions() {
+            path.extend(["definitions".to_owned(), name.clone()]);
+            sub_schema._validate(sub_root, path)?;
+            path.truncate(path.len() - 2);
         }
 
         match self {
@@ -984,17 +1433,28 @@ impl Schema {
                     .unwrap()
                     .contains_key(ref_)
                 {
+                    path.push("ref".to_owned());
                     return Err(SchemaValidateError::NoSuchDefinition(ref_.clone()));
                 }
             }
             Self::Type { .. } => {}
             Self::Enum { enum_, .. } => {
                 if enum_.is_empty() {
+                    path.push("enum".to_owned());
+                    return Err(SchemaValidateError::EmptyEnum);
+                }
+            }
+            #[cfg(feature = "extensions")]
+            Self::IntEnum { enum_, .. } => {
+                if enum_.is_empty() {
+                    path.push("enum".to_owned());
                     return Err(SchemaValidateError::EmptyEnum);
                 }
             }
             Self::Elements { elements, .. } => {
-                elements._validate(sub_root)?;
+                path.push("elements".to_owned());
+                elements._validate(sub_root, path)?;
+                path.pop();
             }
             Self::Properties {
                 properties,
@@ -1003,27 +1463,36 @@ impl Schema {
             } => {
                 for key in properties.keys() {
                     if optional_properties.contains_key(key) {
+                        path.extend(["optionalProperties".to_owned(), key.clone()]);
                         return Err(SchemaValidateError::RepeatedProperty(key.clone()));
                     }
                 }
 
-                for sub_schema in properties.values() {
-                    sub_schema._validate(sub_root)?;
+                for (name, sub_schema) in properties {
+                    path.extend(["properties".to_owned(), name.clone()]);
+                    sub_schema._validate(sub_root, path)?;
+                    path.truncate(path.len() - 2);
                 }
 
-                for sub_schema in optional_properties.values() {
-                    sub_schema._validate(sub_root)?;
+                for (name, sub_schema) in optional_properties {
+                    path.extend(["optionalProperties".to_owned(), name.clone()]);
+                    sub_schema._validate(sub_root, path)?;
+                    path.truncate(path.len() - 2);
                 }
             }
             Self::Values { values, .. } => {
-                values._validate(sub_root)?;
+                path.push("values".to_owned());
+                values._validate(sub_root, path)?;
+                path.pop();
             }
             Self::Discriminator {
                 discriminator,
                 mapping,
                 ..
             } => {
-                for sub_schema in mapping.values() {
+                for (tag, sub_schema) in mapping {
+                    path.extend(["mapping".to_owned(), tag.clone()]);
+
                     if let Self::Properties {
                         nullable,
                         properties,
@@ -1032,12 +1501,19 @@ impl Schema {
                     } = sub_schema
                     {
                         if *nullable {
+                            path.push("nullable".to_owned());
                             return Err(SchemaValidateError::NullableMapping);
                         }
 
-                        if properties.contains_key(discriminator)
-                            || optional_properties.contains_key(discriminator)
-                        {
+                        if properties.contains_key(discriminator) {
+                            path.extend(["properties".to_owned(), discriminator.clone()]);
+                            return Err(SchemaValidateError::RepeatedDiscriminator(
+                                discriminator.clone(),
+                            ));
+                        }
+
+                        if optional_properties.contains_key(discriminator) {
+                            path.extend(["optionalProperties".to_owned(), discriminator.clone()]);
                             return Err(SchemaValidateError::RepeatedDiscriminator(
                                 discriminator.clone(),
                             ));
@@ -1046,7 +1522,8 @@ impl Schema {
                         return Err(SchemaValidateError::NonPropertiesMapping);
                     }
 
-                    sub_schema._validate(sub_root)?;
+                    sub_schema._validate(sub_root, path)?;
+                    path.truncate(path.len() - 2);
                 }
             }
         }
@@ -1085,6 +1562,40 @@ impl Schema {
             Self::Empty { definitions, .. } => definitions,
             Self::Ref { definitions, .. } => definitions,
             Self::Enum { definitions, .. } => definitions,
+            #[cfg(feature = "extensions")]
+            Self::IntEnum { definitions, .. } => definitions,
+            Self::Type { definitions, .. } => definitions,
+            Self::Elements { definitions, .. } => definitions,
+            Self::Properties { definitions, .. } => definitions,
+            Self::Values { definitions, .. } => definitions,
+            Self::Discriminator { definitions, .. } => definitions,
+        }
+    }
+
+    /// Gets a mutable reference to the schema's definitions.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    ///
+    /// let mut schema = Schema::Empty {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    /// };
+    ///
+    /// schema.definitions_mut().insert("foo".to_owned(), Schema::Empty {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    /// });
+    ///
+    /// assert!(schema.definitions().contains_key("foo"));
+    /// ```
+    pub fn definitions_mut(&mut self) -> &mut BTreeMap<String, Schema> {
+        match self {
+            Self::Empty { definitions, .. } => definitions,
+            Self::Ref { definitions, .. } => definitions,
+            Self::Enum { definitions, .. } => definitions,
+            #[cfg(feature = "extensions")]
+            Self::IntEnum { definitions, .. } => definitions,
             Self::Type { definitions, .. } => definitions,
             Self::Elements { definitions, .. } => definitions,
             Self::Properties { definitions, .. } => definitions,
@@ -1119,6 +1630,37 @@ impl Schema {
             Self::Empty { metadata, .. } => metadata,
             Self::Ref { metadata, .. } => metadata,
             Self::Enum { metadata, .. } => metadata,
+            #[cfg(feature = "extensions")]
+            Self::IntEnum { metadata, .. } => metadata,
+            Self::Type { metadata, .. } => metadata,
+            Self::Elements { metadata, .. } => metadata,
+            Self::Properties { metadata, .. } => metadata,
+            Self::Values { metadata, .. } => metadata,
+            Self::Discriminator { metadata, .. } => metadata,
+        }
+    }
+
+    /// Gets a mutable reference to the schema's metadata.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let mut schema = Schema::Empty {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    /// };
+    ///
+    /// schema.metadata_mut().insert("foo".to_owned(), json!("bar"));
+    /// assert_eq!(Some(&json!("bar")), schema.metadata().get("foo"));
+    /// ```
+    pub fn metadata_mut(&mut self) -> &mut BTreeMap<String, Value> {
+        match self {
+            Self::Empty { metadata, .. } => metadata,
+            Self::Ref { metadata, .. } => metadata,
+            Self::Enum { metadata, .. } => metadata,
+            #[cfg(feature = "extensions")]
+            Self::IntEnum { metadata, .. } => metadata,
             Self::Type { metadata, .. } => metadata,
             Self::Elements { metadata, .. } => metadata,
             Self::Properties { metadata, .. } => metadata,
@@ -1156,6 +1698,8 @@ impl Schema {
             Self::Empty { .. } => true,
             Self::Ref { nullable, .. } => *nullable,
             Self::Enum { nullable, .. } => *nullable,
+            #[cfg(feature = "extensions")]
+            Self::IntEnum { nullable, .. } => *nullable,
             Self::Type { nullable, .. } => *nullable,
             Self::Elements { nullable, .. } => *nullable,
             Self::Properties { nullable, .. } => *nullable,
@@ -1170,46 +1714,73 @@ mod tests {
     use crate::{Schema, SerdeSchema};
 
     #[test]
-    fn invalid_schemas() {
-        use std::collections::BTreeMap;
-
-        let test_cases: BTreeMap<String, serde_json::Value> = serde_json::from_str(include_str!(
-            "../json-typedef-spec/tests/invalid_schemas.json"
-        ))
-        .expect("parse invalid_schemas.json");
-
-        for (test_case_name, test_case) in test_cases {
-            if let Ok(serde_schema) = serde_json::from_value::<SerdeSchema>(test_case) {
-                if let Ok(schema) = Schema::from_serde_schema(serde_schema) {
-                    if schema.validate().is_ok() {
-                        panic!(
-                            "failed to detect invalid schema: {}, got: {:?}",
-                            test_case_name, schema
-                        );
-                    }
-                }
+    fn parse_limits() {
+        use super::{FromSerdeSchemaError, ParseLimits};
+        use serde_json::json;
+
+        let serde_schema: SerdeSchema = serde_json::from_value(json!({
+            "definitions": {
+                "a": { "elements": { "type": "string" }},
+                "b": { "values": { "ref": "a" }}
+            },
+            "discriminator": "type",
+            "mapping": {
+                "x": { "properties": { "y": {}}, "optionalProperties": { "z": {}}}
             }
+        }))
+        .unwrap();
+
+        let limits = |max_nodes, max_depth, max_definitions| ParseLimits {
+            max_nodes,
+            max_depth,
+            max_definitions,
+        };
+
+        for (limits, expected) in [
+            (ParseLimits::default(), Ok(())),
+            (limits(8, 3, 2), Ok(())),
+            (limits(7, 0, 0), Err(FromSerdeSchemaError::TooManyNodes)),
+            (limits(0, 2, 0), Err(FromSerdeSchemaError::MaxDepthExceeded)),
+            (
+                limits(0, 0, 1),
+                Err(FromSerdeSchemaError::TooManyDefinitions),
+            ),
+        ] {
+            assert_eq!(
+                expected,
+                Schema::from_serde_schema_with_limits(serde_schema.clone(), limits).map(|_| ())
+            );
         }
     }
 
     #[test]
-    fn valid_schemas() {
-        use std::collections::BTreeMap;
-
-        #[derive(serde::Deserialize)]
-        struct TestCase {
-            schema: serde_json::Value,
-        }
-
-        let test_cases: BTreeMap<String, TestCase> =
-            serde_json::from_str(include_str!("../json-typedef-spec/tests/validation.json"))
-                .expect("parse validation.json");
-
-        for (test_case_name, test_case) in test_cases {
-            let serde_schema =
-                serde_json::from_value::<SerdeSchema>(test_case.schema).expect(&test_case_name);
-            let schema = Schema::from_serde_schema(serde_schema).expect(&test_case_name);
-            schema.validate().expect(&test_case_name);
-        }
+    fn circular_ref_paths() {
+        use super::SchemaValidateError;
+        use serde_json::json;
+
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "a/b": { "ref": "c~d" },
+                    "c~d": { "ref": "a/b" }
+                },
+                "ref": "a/b"
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let err = schema.resolve_ref(&schema).unwrap_err();
+        assert_eq!(
+            SchemaValidateError::CircularRef(vec![
+                "/definitions/a~1b".to_owned(),
+                "/definitions/c~0d".to_owned(),
+            ]),
+            err
+        );
+        assert_eq!(
+            "circular refs between definitions at /definitions/a~1b, /definitions/c~0d",
+            err.to_string()
+        );
     }
 }
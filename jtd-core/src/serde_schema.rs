@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// A JSON representation of JSON Typedef schemas, compatible with `serde_json`.
+///
+/// To convert this into a [`Schema`][`crate::Schema`], see
+/// [`Schema::from_serde_schema`][`crate::Schema::from_serde_schema`].
+///
+/// ```
+/// use jtd::SerdeSchema;
+/// use serde_json::json;
+///
+/// assert_eq!(
+///     SerdeSchema { type_: Some("uint8".to_owned()), ..Default::default() },
+///     serde_json::from_value::<SerdeSchema>(json!({ "type": "uint8" })).unwrap()
+/// )
+/// ```
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(feature = "extensions", serde(from = "ExtendedSerdeSchema"))]
+pub struct SerdeSchema {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<BTreeMap<String, Value>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub definitions: Option<BTreeMap<String, SerdeSchema>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ref_: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enum_: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elements: Option<Box<SerdeSchema>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub properties: Option<BTreeMap<String, SerdeSchema>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optional_properties: Option<BTreeMap<String, SerdeSchema>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_properties: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Box<SerdeSchema>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discriminator: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mapping: Option<BTreeMap<String, SerdeSchema>>,
+
+    /// The `keys` extension keyword. See [`Keys`][`crate::Keys`].
+    #[cfg(feature = "extensions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Box<SerdeSchema>>,
+
+    /// The `pattern` extension keyword, which may only appear in a `keys`
+    /// schema. See [`Keys`][`crate::Keys`].
+    #[cfg(feature = "extensions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
+    /// The `enum` keyword, when it's used with the integer enum extension.
+    /// See [`Schema::IntEnum`][`crate::Schema::IntEnum`].
+    ///
+    /// At most one of this and `enum_` may be present.
+    #[cfg(feature = "extensions")]
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+    pub int_enum: Option<Vec<i64>>,
+}
+
+// With extensions enabled, the values of enum may be either strings or
+// integers, which are deserialized into different fields of SerdeSchema. This
+// type is what's really deserialized in that case.
+#[cfg(feature = "extensions")]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+struct ExtendedSerdeSchema {
+    metadata: Option<BTreeMap<String, Value>>,
+    definitions: Option<BTreeMap<String, SerdeSchema>>,
+    nullable: Option<bool>,
+    ref_: Option<String>,
+    type_: Option<String>,
+    enum_: Option<EnumValues>,
+    elements: Option<Box<SerdeSchema>>,
+    properties: Option<BTreeMap<String, SerdeSchema>>,
+    optional_properties: Option<BTreeMap<String, SerdeSchema>>,
+    additional_properties: Option<bool>,
+    values: Option<Box<SerdeSchema>>,
+    discriminator: Option<String>,
+    mapping: Option<BTreeMap<String, SerdeSchema>>,
+    keys: Option<Box<SerdeSchema>>,
+    pattern: Option<String>,
+}
+
+#[cfg(feature = "extensions")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EnumValues {
+    Strings(Vec<String>),
+    Ints(Vec<i64>),
+}
+
+#[cfg(feature = "extensions")]
+impl From<ExtendedSerdeSchema> for SerdeSchema {
+    fn from(schema: ExtendedSerdeSchema) -> Self {
+        let (enum_, int_enum) = match schema.enum_ {
+            Some(EnumValues::Strings(values)) => (Some(values), None),
+            Some(EnumValues::Ints(values)) => (None, Some(values)),
+            None => (None, None),
+        };
+
+        SerdeSchema {
+            metadata: schema.metadata,
+            definitions: schema.definitions,
+            nullable: schema.nullable,
+            ref_: schema.ref_,
+            type_: schema.type_,
+            enum_,
+            elements: schema.elements,
+            properties: schema.properties,
+            optional_properties: schema.optional_properties,
+            additional_properties: schema.additional_properties,
+            values: schema.values,
+            discriminator: schema.discriminator,
+            mapping: schema.mapping,
+            keys: schema.keys,
+            pattern: schema.pattern,
+            int_enum,
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::scan::{JsonScanner, Location};
+use crate::{FromSerdeSchemaError, Schema, SerdeSchema};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use thiserror::Error;
+
+/// Where each part of a schema is in the JSON text it was parsed from, as
+/// returned by [`Schema::from_str_spanned`].
+///
+/// Parts of the schema are identified by their path within it, as a list of
+/// JSON Pointer tokens. These are the same paths as the `schema_path` of a
+/// [`ValidationErrorIndicator`][`crate::ValidationErrorIndicator`] or a
+/// [`Lint`](https://docs.rs/jtd/latest/jtd/lint/struct.Lint.html), and as
+/// returned by [`Schema::validate_with_path`], so any of those can be pointed
+/// out in the schema's source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaSpans {
+    spans: BTreeMap<Vec<String>, (Range<usize>, Location)>,
+}
+
+/// Errors that may arise from [`Schema::from_str_spanned`].
+#[derive(Debug, Error)]
+pub enum FromStrSpannedError {
+    /// The input was not a valid JSON representation of a schema.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    /// The input was valid JSON, but not a valid schema.
+    #[error(transparent)]
+    Schema(#[from] FromSerdeSchemaError),
+}
+
+impl Schema {
+    /// Parses a schema from JSON text, keeping track of where each part of
+    /// it is in the text.
+    ///
+    /// This is for tools, such as editor integrations, that need to point at
+    /// the exact place in a schema file where there's a problem.
+    ///
+    /// ```
+    /// use jtd::config::Location;
+    /// use jtd::Schema;
+    ///
+    /// let input = r#"{
+    ///   "properties": {
+    ///     "name": { "ref": "nmae" }
+    ///   },
+    ///   "definitions": { "name": { "type": "string" } }
+    /// }"#;
+    ///
+    /// let (schema, spans) = Schema::from_str_spanned(input).unwrap();
+    /// let (path, _) = schema.validate_with_path().unwrap_err();
+    ///
+    /// assert_eq!(r#""nmae""#, &input[spans.span(&path)]);
+    /// assert_eq!(Location { line: 3, column: 22 }, spans.location(&path));
+    /// ```
+    ///
+    /// The same works for lints:
+    ///
+    /// ```
+    /// use jtd::Schema;
+    ///
+    /// let input = r#"{ "type": "string", "metadata": { "description": 1 } }"#;
+    /// let (schema, spans) = Schema::from_str_spanned(input).unwrap();
+    ///
+    /// let lints = jtd::lint::lint(&schema);
+    /// assert_eq!("1", &input[spans.span(&lints[0].schema_path)]);
+    /// ```
+    pub fn from_str_spanned(input: &str) -> Result<(Self, SchemaSpans), FromStrSpannedError> {
+        let serde_schema: SerdeSchema = serde_json::from_str(input)?;
+        let schema = Self::from_serde_schema(serde_schema)?;
+
+        // The input is known to be valid JSON at this point, so the scanner
+        // only gives up if something is badly wrong. Whatever it found before
+        // then is still correct.
+        let mut scanner = JsonScanner::new(input);
+        let _ = scanner.value();
+
+        let locations = scanner.locations;
+        let spans = scanner
+            .spans
+            .into_iter()
+            .filter_map(|(path, span)| {
+                let location = *locations.get(&path)?;
+                Some((path, (span, location)))
+            })
+            .collect();
+
+        Ok((schema, SchemaSpans { spans }))
+    }
+}
+
+impl SchemaSpans {
+    /// The span of bytes taken up by the part of the schema at `path`, or,
+    /// if there's nothing at `path`, by the nearest part that contains it.
+    ///
+    /// Paths to something missing, such as a definition that a `ref` names
+    /// but that doesn't exist, fall back to what it's missing from.
+    pub fn span<S: AsRef<str>>(&self, path: &[S]) -> Range<usize> {
+        self.nearest(path).0.clone()
+    }
+
+    /// Where the part of the schema at `path` starts, or, if there's nothing
+    /// at `path`, where the nearest part that contains it starts.
+    pub fn location<S: AsRef<str>>(&self, path: &[S]) -> Location {
+        self.nearest(path).1
+    }
+
+    /// The span of bytes taken up by the part of the schema at exactly
+    /// `path`, if there is one.
+    pub fn get<S: AsRef<str>>(&self, path: &[S]) -> Option<Range<usize>> {
+        let path: Vec<_> = path.iter().map(|token| token.as_ref().to_owned()).collect();
+        self.spans.get(&path).map(|(span, _)| span.clone())
+    }
+
+    fn nearest<S: AsRef<str>>(&self, path: &[S]) -> &(Range<usize>, Location) {
+        let mut path: Vec<_> = path.iter().map(|token| token.as_ref().to_owned()).collect();
+
+        loop {
+            if let Some(span) = self.spans.get(&path) {
+                return span;
+            }
+
+            // The root is always present, so this only runs out of path if
+            // the scanner gave up, in which case the whole input is the best
+            // that can be done.
+            if path.pop().is_none() {
+                return &ROOT;
+            }
+        }
+    }
+}
+
+static ROOT: (Range<usize>, Location) = (0..0, Location { line: 1, column: 1 });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SchemaValidateError;
+
+    #[test]
+    fn spans() {
+        let input = "{\n  \"definitions\": {\n    \"a\": { \"enum\": [] }\n  },\n  \
+                     \"elements\": {\"ref\":\"a\"}\n}";
+
+        let (schema, spans) = Schema::from_str_spanned(input).unwrap();
+        assert_eq!(0..input.len(), spans.span::<&str>(&[]));
+        assert_eq!(Some(0..input.len()), spans.get::<&str>(&[]));
+        assert_eq!(r#"{"ref":"a"}"#, &input[spans.span(&["elements"])]);
+        assert_eq!(r#""a""#, &input[spans.span(&["elements", "ref"])]);
+        assert_eq!(None, spans.get(&["elements", "nullable"]));
+        assert_eq!(
+            r#"{"ref":"a"}"#,
+            &input[spans.span(&["elements", "nullable"])]
+        );
+
+        let (path, err) = schema.validate_with_path().unwrap_err();
+        assert_eq!(SchemaValidateError::EmptyEnum, err);
+        assert_eq!(vec!["definitions", "a", "enum"], path);
+        assert_eq!("[]", &input[spans.span(&path)]);
+        assert_eq!(
+            Location {
+                line: 3,
+                column: 20
+            },
+            spans.location(&path)
+        );
+    }
+
+    #[test]
+    fn validate_with_path() {
+        let path = |value: serde_json::Value| {
+            let schema = Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap();
+            schema.validate_with_path().unwrap_err().0.join("/")
+        };
+
+        assert_eq!(
+            "properties/a/definitions",
+            path(serde_json::json!({
+                "properties": { "a": { "definitions": { "b": {} }, "type": "string" }}
+            }))
+        );
+        assert_eq!(
+            "values/optionalProperties/b",
+            path(serde_json::json!({
+                "values": {
+                    "properties": { "b": {} },
+                    "optionalProperties": { "b": {} }
+                }
+            }))
+        );
+        assert_eq!(
+            "mapping/x/optionalProperties/t",
+            path(serde_json::json!({
+                "discriminator": "t",
+                "mapping": {
+                    "x": { "optionalProperties": { "t": {} }}
+                }
+            }))
+        );
+        assert_eq!(
+            "mapping/y/nullable",
+            path(serde_json::json!({
+                "discriminator": "t",
+                "mapping": {
+                    "x": { "properties": {} },
+                    "y": { "properties": {}, "nullable": true }
+                }
+            }))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(matches!(
+            Schema::from_str_spanned("{"),
+            Err(FromStrSpannedError::Json(_))
+        ));
+        assert!(matches!(
+            Schema::from_str_spanned(r#"{ "type": "uint64" }"#),
+            Err(FromStrSpannedError::Schema(_))
+        ));
+    }
+}
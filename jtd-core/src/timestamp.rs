@@ -0,0 +1,123 @@
+// Checks that timestamps are in the format of RFC 3339, section 5.6, without
+// depending on a date and time library.
+//
+// This accepts what chrono's DateTime::parse_from_rfc3339 does: a "T", "t",
+// or space between the date and time, any number of fractional digits, and a
+// leap second at the end of any minute.
+#[doc(hidden)]
+pub fn is_rfc3339(s: &str) -> bool {
+    let b = s.as_bytes();
+    if b.len() < 20 {
+        return false;
+    }
+
+    let year = match digits(&b[0..4]) {
+        Some(year) => year,
+        None => return false,
+    };
+
+    let date_ok = b[4] == b'-'
+        && b[7] == b'-'
+        && matches!(b[10], b'T' | b't' | b' ')
+        && matches!(digits(&b[5..7]), Some(1..=12))
+        && digits(&b[8..10]).is_some_and(|day| {
+            day >= 1 && day <= days_in_month(year, digits(&b[5..7]).unwrap_or_default())
+        });
+
+    let time_ok = b[13] == b':'
+        && b[16] == b':'
+        && matches!(digits(&b[11..13]), Some(0..=23))
+        && matches!(digits(&b[14..16]), Some(0..=59))
+        && matches!(digits(&b[17..19]), Some(0..=60));
+
+    if !date_ok || !time_ok {
+        return false;
+    }
+
+    let mut rest = &b[19..];
+    if let Some((b'.', fraction)) = rest.split_first() {
+        let len = fraction.iter().take_while(|c| c.is_ascii_digit()).count();
+        if len == 0 {
+            return false;
+        }
+
+        rest = &fraction[len..];
+    }
+
+    match rest {
+        [b'Z' | b'z'] => true,
+        [b'+' | b'-', hours @ .., b':', m1, m2] if hours.len() == 2 => {
+            matches!(digits(hours), Some(0..=23)) && matches!(digits(&[*m1, *m2]), Some(0..=59))
+        }
+        _ => false,
+    }
+}
+
+fn digits(b: &[u8]) -> Option<u32> {
+    b.iter().try_fold(0, |n, c| {
+        if c.is_ascii_digit() {
+            Some(n * 10 + u32::from(c - b'0'))
+        } else {
+            None
+        }
+    })
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_rfc3339;
+
+    #[test]
+    fn rfc3339() {
+        let valid = [
+            "1985-04-12T23:20:50.52Z",
+            "1996-12-19T16:39:57-08:00",
+            "1990-12-31T23:59:60Z",
+            "1990-12-31T15:59:60-08:00",
+            "1937-01-01T12:00:27.87+00:20",
+            "2000-02-29t00:00:00z",
+            "2000-02-29 00:00:00.123456789123Z",
+        ];
+
+        let invalid = [
+            "1900-02-29T00:00:00Z",
+            "2001-02-29T00:00:00Z",
+            "2001-04-31T00:00:00Z",
+            "2001-13-01T00:00:00Z",
+            "2001-00-01T00:00:00Z",
+            "2001-01-00T00:00:00Z",
+            "2001-01-01T24:00:00Z",
+            "2001-01-01T00:60:00Z",
+            "2001-01-01T00:00:61Z",
+            "2001-01-01T00:00:00.Z",
+            "2001-01-01T00:00:00+24:00",
+            "2001-01-01T00:00:00+00:60",
+            "2001-01-01T00:00:00+0000",
+            "2001-01-01T00:00:00",
+            "2001-01-01",
+            "2001-01-01X00:00:00Z",
+            "2001-1-01T00:00:00Z",
+            "+2001-01-01T00:00:00Z",
+            "2001-01-01T00:00:00Zjunk",
+            "２００1-01-01T00:00:00Z",
+            "",
+        ];
+
+        for case in valid {
+            assert!(is_rfc3339(case), "{}", case);
+        }
+
+        for case in invalid {
+            assert!(!is_rfc3339(case), "{}", case);
+        }
+    }
+}
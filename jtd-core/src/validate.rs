@@ -0,0 +1,2727 @@
+use crate::{Instance, InstancePath, Metadata, Schema, SchemaPath, SchemaValidateError, Type};
+use internal::ValidateOptionsExt as _;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use thiserror::Error;
+use unicode_normalization::UnicodeNormalization as _;
+
+/// Options you can pass to [`validate()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidateOptions {
+    max_depth: usize,
+    max_errors: usize,
+    case_insensitive_enums: bool,
+    unicode_normalization: Option<UnicodeNormalization>,
+    cancellation: Option<Cancellation>,
+    progress: Option<Progress>,
+    min_severity: Severity,
+    ignore_missing_required: bool,
+    max_string_len: usize,
+    max_array_len: usize,
+    max_object_keys: usize,
+    max_instance_depth: usize,
+    timestamp_validator: Option<TimestampValidator>,
+    #[cfg(feature = "extensions")]
+    type_extensions: crate::TypeExtensions,
+}
+
+// A cancellation flag, as passed to ValidateOptions::with_cancellation. Flags
+// are compared by identity, rather than by their current value.
+#[derive(Clone, Debug)]
+struct Cancellation(Arc<AtomicBool>);
+
+impl PartialEq for Cancellation {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Cancellation {}
+
+// A progress hook, as passed to ValidateOptions::with_progress. Like
+// Cancellation, hooks are compared by identity.
+#[derive(Clone)]
+struct Progress {
+    interval: u64,
+    hook: Arc<dyn Fn(u64) + Send + Sync>,
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Progress {
+    fn eq(&self, other: &Self) -> bool {
+        self.interval == other.interval && Arc::ptr_eq(&self.hook, &other.hook)
+    }
+}
+
+impl Eq for Progress {}
+
+// A timestamp validator, as passed to ValidateOptions::with_timestamp_validator.
+// Validators are compared by address.
+#[derive(Clone, Copy)]
+struct TimestampValidator(fn(&str) -> bool);
+
+impl fmt::Debug for TimestampValidator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("TimestampValidator").finish()
+    }
+}
+
+impl PartialEq for TimestampValidator {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 as usize == other.0 as usize
+    }
+}
+
+impl Eq for TimestampValidator {}
+
+/// A Unicode normalization form, for use with
+/// [`ValidateOptions::with_unicode_normalization`].
+///
+/// See [Unicode Standard Annex #15](https://unicode.org/reports/tr15/) for what
+/// each form does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UnicodeNormalization {
+    /// Normalization Form C: canonical decomposition, followed by canonical
+    /// composition.
+    Nfc,
+
+    /// Normalization Form D: canonical decomposition.
+    Nfd,
+
+    /// Normalization Form KC: compatibility decomposition, followed by
+    /// canonical composition.
+    Nfkc,
+
+    /// Normalization Form KD: compatibility decomposition.
+    Nfkd,
+}
+
+/// How serious a validation error is.
+///
+/// Every constraint in a schema has the severity [`Severity::Error`], unless
+/// the schema lowers it with a `severityOnViolation` metadata property, whose
+/// value is `"error"`, `"warning"`, or `"info"`. That lets schema authors
+/// introduce a constraint gradually: for instance, a property that's about to
+/// become required can be marked as a warning first.
+///
+/// A severity applies to everything a schema checks, including what its
+/// sub-schemas check, unless they set a severity of their own. If a required
+/// property is missing, the severity set by the property's schema applies.
+///
+/// By default, [`validate()`] only reports errors. See
+/// [`ValidateOptions::with_min_severity`] to also report warnings, or
+/// warnings and info.
+///
+/// ```
+/// use jtd::{Schema, Severity, ValidateOptions};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "name": { "type": "string" },
+///         "email": {
+///             "type": "string",
+///             "metadata": { "severityOnViolation": "warning" }
+///         }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let instance = json!({ "name": "alice" });
+/// assert!(jtd::validate(&schema, &instance, ValidateOptions::new()).unwrap().is_empty());
+///
+/// let options = ValidateOptions::new().with_min_severity(Severity::Warning);
+/// let errors = jtd::validate(&schema, &instance, options).unwrap();
+/// assert_eq!(1, errors.len());
+/// assert_eq!(Severity::Warning, errors[0].severity);
+/// assert_eq!(vec!["properties", "email"], errors[0].schema_path);
+/// ```
+///
+/// Severities are an extension to JSON Typedef. Other implementations, and
+/// `jtd`'s [`Deserializer`](https://docs.rs/jtd/latest/jtd/struct.Deserializer.html)
+/// and [`Serializer`](https://docs.rs/jtd/latest/jtd/struct.Serializer.html),
+/// treat every violation as an error.
+///
+/// Severities serialize as they're written in metadata: `"error"`,
+/// `"warning"`, and `"info"`.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Worth knowing about, but not a problem.
+    Info,
+
+    /// A problem, but not one that makes the instance invalid.
+    Warning,
+
+    /// A problem that makes the instance invalid.
+    #[default]
+    Error,
+}
+
+impl Severity {
+    /// The metadata property with which schemas set a severity.
+    pub const METADATA_KEY: &'static str = "severityOnViolation";
+
+    /// The severity set by a schema's metadata, if any. Unrecognized values
+    /// are ignored.
+    pub fn from_metadata(metadata: &crate::Metadata) -> Option<Self> {
+        match metadata.get(Self::METADATA_KEY)?.as_str()? {
+            "error" => Some(Self::Error),
+            "warning" => Some(Self::Warning),
+            "info" => Some(Self::Info),
+            _ => None,
+        }
+    }
+}
+
+impl UnicodeNormalization {
+    fn normalize(self, s: &str) -> String {
+        match self {
+            Self::Nfc => s.nfc().collect(),
+            Self::Nfd => s.nfd().collect(),
+            Self::Nfkc => s.nfkc().collect(),
+            Self::Nfkd => s.nfkd().collect(),
+        }
+    }
+}
+
+impl ValidateOptions {
+    /// Construct a new set of options with all default values.
+    ///
+    /// Equivalent to [`Default::default()`] or calling `with_max_depth(0)` and
+    /// `with_max_errors(0)`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum "depth" of references to following in [`validate()`].
+    ///
+    /// This option exists to handle the possibility of an infinite loop in a
+    /// schema. For instance, this is a valid schema:
+    ///
+    /// ```json
+    /// { "ref": "loop", "definitions": { "loop": { "ref": "loop" }}}
+    /// ```
+    ///
+    /// There are good reasons to sometimes have self-referential schemas -- for
+    /// instance, to describe a recursive data structure. What `with_max_depth`
+    /// does is limit how many recursive `ref` nodes will be followed before
+    /// [`validate()`] errors with [`ValidateError::MaxDepthExceeded`].
+    ///
+    /// The default max depth of `0` indicates that no max depth should be
+    /// implemented. An infinite `ref` loop will make [`validate()`] use more
+    /// and more memory, until it runs out.
+    ///
+    /// This option doesn't limit how deeply nested the instance may be.
+    /// [`validate()`] doesn't recurse into the instance, so no nesting of the
+    /// instance can overflow the stack.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum number of validation errors to return from
+    /// [`validate()`].
+    ///
+    /// This option exists as an optimization for [`validate()`]. If all you
+    /// care about is whether an input is valid, then consider using
+    /// `set_max_errors(1)` to have [`validate()`] immediately return after
+    /// finding a validation error.
+    ///
+    /// With a max errors of `1`, [`validate()`] doesn't keep track of paths
+    /// while it looks for the error, and only goes back to find the path of
+    /// the error once there is one. This makes valid inputs cheaper to
+    /// validate, at the cost of validating invalid ones twice.
+    ///
+    /// The default max errors of `0` indicates that all errors will be
+    /// returned.
+    pub fn with_max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = max_errors;
+        self
+    }
+
+    /// Sets whether [`validate()`] should compare strings against the values
+    /// of an enum-form schema without regard to case.
+    ///
+    /// This option is an extension to JSON Typedef. RFC 8927 requires enum
+    /// values to match exactly, so enabling this option will make
+    /// [`validate()`] accept inputs that other implementations reject. It
+    /// exists for the benefit of those who have to accept data from legacy
+    /// producers that can't agree on casing.
+    ///
+    /// Comparison is done on the Unicode lowercase forms of both strings, as
+    /// computed by [`str::to_lowercase`].
+    ///
+    /// ```
+    /// use jtd::{Schema, ValidateOptions};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "enum": ["PENDING", "DONE"]
+    /// })).unwrap()).unwrap();
+    ///
+    /// let options = ValidateOptions::new().with_case_insensitive_enums(true);
+    /// assert!(jtd::validate(&schema, &json!("Pending"), options).unwrap().is_empty());
+    /// ```
+    ///
+    /// The default is `false`.
+    pub fn with_case_insensitive_enums(mut self, case_insensitive_enums: bool) -> Self {
+        self.case_insensitive_enums = case_insensitive_enums;
+        self
+    }
+
+    /// Sets a Unicode normalization form that [`validate()`] should apply to
+    /// strings before comparing them against the values of an enum-form
+    /// schema.
+    ///
+    /// Both the instance and the enum values are normalized, so that strings
+    /// that look identical but are encoded differently -- for instance, `é` as
+    /// a single code point versus `e` followed by a combining accent -- are
+    /// treated as equal.
+    ///
+    /// Like [`with_case_insensitive_enums`][`Self::with_case_insensitive_enums`],
+    /// this option is an extension to JSON Typedef, and makes [`validate()`]
+    /// accept inputs that other implementations reject. If both options are
+    /// set, strings are normalized before they are lowercased.
+    ///
+    /// ```
+    /// use jtd::{Schema, UnicodeNormalization, ValidateOptions};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "enum": ["caf\u{e9}"]
+    /// })).unwrap()).unwrap();
+    ///
+    /// let instance = json!("cafe\u{301}");
+    /// assert!(!jtd::validate(&schema, &instance, ValidateOptions::new()).unwrap().is_empty());
+    ///
+    /// let options = ValidateOptions::new().with_unicode_normalization(UnicodeNormalization::Nfc);
+    /// assert!(jtd::validate(&schema, &instance, options).unwrap().is_empty());
+    /// ```
+    ///
+    /// The default is to not normalize strings.
+    pub fn with_unicode_normalization(mut self, normalization: UnicodeNormalization) -> Self {
+        self.unicode_normalization = Some(normalization);
+        self
+    }
+
+    /// Sets the least severe validation errors that [`validate()`] should
+    /// report. Errors less severe than this are left out, and don't count
+    /// towards [`with_max_errors`][`Self::with_max_errors`].
+    ///
+    /// See [`Severity`] for how schemas set the severity of their errors, and
+    /// for an example.
+    ///
+    /// The default is [`Severity::Error`].
+    pub fn with_min_severity(mut self, min_severity: Severity) -> Self {
+        self.min_severity = min_severity;
+        self
+    }
+
+    /// Sets whether [`validate()`] should let the required properties of the
+    /// properties form be missing.
+    ///
+    /// This is for validating partial objects, such as the bodies of HTTP
+    /// `PATCH` requests, against the same schema as the full objects they
+    /// update. The properties that are present are still checked, and
+    /// properties the schema doesn't know of are still rejected, unless it
+    /// allows additional properties.
+    ///
+    /// Properties may be missing at any depth, not only at the top of the
+    /// instance, as suits partial updates to nested objects such as JSON
+    /// Merge Patch (RFC 7396) describes. The discriminator of the
+    /// discriminator form must still be present, since without it there's no
+    /// telling which properties the rest of the object should have.
+    ///
+    /// ```
+    /// use jtd::{Schema, ValidateOptions};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "properties": {
+    ///         "name": { "type": "string" },
+    ///         "age": { "type": "uint8" }
+    ///     }
+    /// })).unwrap()).unwrap();
+    ///
+    /// let patch = json!({ "age": 43 });
+    /// assert_eq!(1, jtd::validate(&schema, &patch, ValidateOptions::new()).unwrap().len());
+    ///
+    /// let options = ValidateOptions::new().with_ignore_missing_required(true);
+    /// assert!(jtd::validate(&schema, &patch, options.clone()).unwrap().is_empty());
+    ///
+    /// // Properties that are present must still be valid.
+    /// let patch = json!({ "age": "forty-three" });
+    /// assert_eq!(1, jtd::validate(&schema, &patch, options).unwrap().len());
+    /// ```
+    ///
+    /// The default is `false`.
+    pub fn with_ignore_missing_required(mut self, ignore_missing_required: bool) -> Self {
+        self.ignore_missing_required = ignore_missing_required;
+        self
+    }
+
+    /// Sets a flag that, once set to `true`, cancels validation.
+    ///
+    /// Validation checks the flag as it goes, and once it sees that the flag
+    /// is set, it stops and [`validate()`] errors with
+    /// [`ValidateError::Cancelled`]. This lets you abandon validating a large
+    /// instance from another thread, such as when the request the instance
+    /// came from is dropped.
+    ///
+    /// ```
+    /// use jtd::{Schema, ValidateError, ValidateOptions};
+    /// use serde_json::json;
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// let schema = Schema::from_serde_schema(
+    ///     serde_json::from_value(json!({ "elements": { "type": "string" }})).unwrap(),
+    /// )
+    /// .unwrap();
+    ///
+    /// let cancelled = Arc::new(AtomicBool::new(false));
+    /// let options = ValidateOptions::new().with_cancellation(Arc::clone(&cancelled));
+    /// assert!(jtd::validate(&schema, &json!(["a", "b"]), options.clone()).is_ok());
+    ///
+    /// cancelled.store(true, Ordering::Relaxed);
+    /// assert_eq!(
+    ///     ValidateError::Cancelled,
+    ///     jtd::validate(&schema, &json!(["a", "b"]), options).unwrap_err(),
+    /// );
+    /// ```
+    ///
+    /// By default, validation can't be cancelled.
+    pub fn with_cancellation(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancellation = Some(Cancellation(cancelled));
+        self
+    }
+
+    /// Sets a hook to call periodically during validation, to report
+    /// progress.
+    ///
+    /// Every time validation has visited another `interval` values in the
+    /// instance, `hook` is called with the number of values visited so far.
+    /// This lets command-line tools and user interfaces show that validation
+    /// of a very large instance is making progress. An `interval` of `0` is
+    /// treated as `1`.
+    ///
+    /// ```
+    /// use jtd::{Schema, ValidateOptions};
+    /// use serde_json::json;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let schema = Schema::from_serde_schema(
+    ///     serde_json::from_value(json!({ "elements": { "type": "string" }})).unwrap(),
+    /// )
+    /// .unwrap();
+    ///
+    /// let reports = Arc::new(Mutex::new(Vec::new()));
+    /// let hook_reports = Arc::clone(&reports);
+    /// let options = ValidateOptions::new().with_progress(2, move |visited| {
+    ///     hook_reports.lock().unwrap().push(visited);
+    /// });
+    ///
+    /// // The array itself counts as a value, as do each of its elements.
+    /// jtd::validate(&schema, &json!(["a", "b", "c", "d"]), options).unwrap();
+    /// assert_eq!(vec![2, 4], *reports.lock().unwrap());
+    /// ```
+    ///
+    /// By default, there is no progress hook.
+    pub fn with_progress<F>(mut self, interval: u64, hook: F) -> Self
+    where
+        F: Fn(u64) + Send + Sync + 'static,
+    {
+        self.progress = Some(Progress {
+            interval: interval.max(1),
+            hook: Arc::new(hook),
+        });
+        self
+    }
+
+    /// Sets the maximum length, in bytes, of any string in the instance.
+    ///
+    /// This and the other per-value limits,
+    /// [`with_max_array_len`][`Self::with_max_array_len`],
+    /// [`with_max_object_keys`][`Self::with_max_object_keys`], and
+    /// [`with_max_instance_depth`][`Self::with_max_instance_depth`], are for
+    /// services that validate untrusted input. They apply to every value in
+    /// the instance, whatever the schema says about it, including values that
+    /// an empty-form schema would accept without looking at them. The limit
+    /// on strings also applies to the keys of objects.
+    ///
+    /// The instance is checked against these limits as it's validated, so
+    /// that validation gives up as soon as a value exceeds one, rather than
+    /// after going over the rest of the instance. If a value exceeds any of
+    /// them, [`validate()`] errors with [`ValidateError::StringTooLong`],
+    /// [`ValidateError::ArrayTooLong`], or [`ValidateError::TooManyObjectKeys`],
+    /// without returning the validation errors found so far. (If
+    /// [`with_max_errors`][`Self::with_max_errors`] errors are found first,
+    /// validation stops there, and values after them aren't checked.)
+    ///
+    /// ```
+    /// use jtd::{Schema, ValidateError, ValidateOptions};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(
+    ///     serde_json::from_value(json!({ "values": {} })).unwrap(),
+    /// )
+    /// .unwrap();
+    ///
+    /// let options = ValidateOptions::new()
+    ///     .with_max_string_len(8)
+    ///     .with_max_array_len(2)
+    ///     .with_max_object_keys(2);
+    ///
+    /// assert!(jtd::validate(&schema, &json!({ "a": ["short", 1] }), options.clone()).is_ok());
+    /// assert_eq!(
+    ///     ValidateError::StringTooLong,
+    ///     jtd::validate(&schema, &json!({ "a": [{ "b": "too long!" }] }), options.clone())
+    ///         .unwrap_err(),
+    /// );
+    /// assert_eq!(
+    ///     ValidateError::ArrayTooLong,
+    ///     jtd::validate(&schema, &json!({ "a": [1, 2, 3] }), options.clone()).unwrap_err(),
+    /// );
+    /// assert_eq!(
+    ///     ValidateError::TooManyObjectKeys,
+    ///     jtd::validate(&schema, &json!({ "a": 1, "b": 2, "c": 3 }), options).unwrap_err(),
+    /// );
+    /// ```
+    ///
+    /// The default max string length of `0` indicates that there is no
+    /// limit.
+    pub fn with_max_string_len(mut self, max_string_len: usize) -> Self {
+        self.max_string_len = max_string_len;
+        self
+    }
+
+    /// Sets the maximum number of elements of any array in the instance.
+    ///
+    /// See [`with_max_string_len`][`Self::with_max_string_len`] for how
+    /// per-value limits work. If an array exceeds this limit, [`validate()`]
+    /// errors with [`ValidateError::ArrayTooLong`].
+    ///
+    /// The default max array length of `0` indicates that there is no limit.
+    pub fn with_max_array_len(mut self, max_array_len: usize) -> Self {
+        self.max_array_len = max_array_len;
+        self
+    }
+
+    /// Sets the maximum number of keys of any object in the instance.
+    ///
+    /// See [`with_max_string_len`][`Self::with_max_string_len`] for how
+    /// per-value limits work. If an object exceeds this limit, [`validate()`]
+    /// errors with [`ValidateError::TooManyObjectKeys`].
+    ///
+    /// The default max object keys of `0` indicates that there is no limit.
+    pub fn with_max_object_keys(mut self, max_object_keys: usize) -> Self {
+        self.max_object_keys = max_object_keys;
+        self
+    }
+
+    /// Sets the maximum number of arrays and objects that may be nested
+    /// inside one another in the instance.
+    ///
+    /// A scalar has no nesting, `[1]` has a nesting of one, and `{ "a": [1]
+    /// }` of two. Unlike [`with_max_depth`][`Self::with_max_depth`], which
+    /// limits how many refs are followed, this limits the instance, whatever
+    /// the schema says about it. See
+    /// [`with_max_string_len`][`Self::with_max_string_len`] for how
+    /// per-value limits work. If the instance is nested more deeply than this
+    /// limit, [`validate()`] errors with
+    /// [`ValidateError::MaxInstanceDepthExceeded`].
+    ///
+    /// ```
+    /// use jtd::{Schema, ValidateError, ValidateOptions};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({})).unwrap()).unwrap();
+    /// let options = ValidateOptions::new().with_max_instance_depth(2);
+    ///
+    /// assert!(jtd::validate(&schema, &json!({ "a": [1] }), options.clone()).is_ok());
+    /// assert_eq!(
+    ///     ValidateError::MaxInstanceDepthExceeded,
+    ///     jtd::validate(&schema, &json!({ "a": [[1]] }), options).unwrap_err(),
+    /// );
+    /// ```
+    ///
+    /// The default max instance depth of `0` indicates that there is no
+    /// limit.
+    pub fn with_max_instance_depth(mut self, max_instance_depth: usize) -> Self {
+        self.max_instance_depth = max_instance_depth;
+        self
+    }
+
+    /// Sets the function [`validate()`] uses to check that strings are
+    /// timestamps, for the `timestamp` type.
+    ///
+    /// By default, timestamps are checked by a built-in parser of the format
+    /// of RFC 3339, which accepts the same timestamps as chrono's
+    /// `DateTime::parse_from_rfc3339`. Set a validator to check timestamps
+    /// the way the rest of your program parses them, such as with the `time`
+    /// or `jiff` crates, or to be stricter, such as by rejecting leap seconds.
+    ///
+    /// ```
+    /// use jtd::{Schema, ValidateOptions};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "type": "timestamp"
+    /// })).unwrap()).unwrap();
+    ///
+    /// // Only accept timestamps like 2020-01-01T00:00:00Z, in UTC and without
+    /// // fractional seconds. A real validator would check the date exists.
+    /// let options = ValidateOptions::new().with_timestamp_validator(|s| {
+    ///     s.len() == 20
+    ///         && s.bytes().enumerate().all(|(i, c)| match i {
+    ///             4 | 7 => c == b'-',
+    ///             10 => c == b'T',
+    ///             13 | 16 => c == b':',
+    ///             19 => c == b'Z',
+    ///             _ => c.is_ascii_digit(),
+    ///         })
+    /// });
+    ///
+    /// let instance = json!("1985-04-12T23:20:50Z");
+    /// assert!(jtd::validate(&schema, &instance, options.clone()).unwrap().is_empty());
+    ///
+    /// let instance = json!("1996-12-19T16:39:57-08:00");
+    /// assert_eq!(1, jtd::validate(&schema, &instance, options).unwrap().len());
+    /// ```
+    ///
+    /// The validator is only called with strings. It applies wherever
+    /// [`ValidateOptions`] are passed, but not to `jtd`'s
+    /// [`Deserializer`](https://docs.rs/jtd/latest/jtd/struct.Deserializer.html)
+    /// or [`Serializer`](https://docs.rs/jtd/latest/jtd/struct.Serializer.html),
+    /// which always use the built-in parser.
+    pub fn with_timestamp_validator(mut self, validator: fn(&str) -> bool) -> Self {
+        self.timestamp_validator = Some(TimestampValidator(validator));
+        self
+    }
+
+    /// Sets the registry [`validate()`] uses to check types beyond those of
+    /// RFC 8927, as found in schemas parsed with
+    /// [`Schema::from_serde_schema_with_extensions`].
+    ///
+    /// See [`TypeExtensions`][`crate::TypeExtensions`] for an example. If a
+    /// schema uses a type that isn't in the registry, [`validate()`] errors
+    /// with [`ValidateError::UnknownType`].
+    ///
+    /// This method is only available with the `extensions` feature.
+    #[cfg(feature = "extensions")]
+    pub fn with_type_extensions(mut self, type_extensions: crate::TypeExtensions) -> Self {
+        self.type_extensions = type_extensions;
+        self
+    }
+}
+
+// What jtd's own validators need of ValidateOptions, which they can't get at
+// otherwise. This isn't public API; it's only reachable through
+// crate::__private.
+pub(crate) mod internal {
+    use super::*;
+
+    pub trait ValidateOptionsExt {
+        fn max_depth(&self) -> usize;
+        fn max_errors(&self) -> usize;
+        fn min_severity(&self) -> Severity;
+        fn ignore_missing_required(&self) -> bool;
+        fn max_string_len(&self) -> usize;
+        fn max_array_len(&self) -> usize;
+        fn max_object_keys(&self) -> usize;
+        fn max_instance_depth(&self) -> usize;
+        #[cfg(feature = "extensions")]
+        fn type_extensions(&self) -> &crate::TypeExtensions;
+        fn is_cancelled(&self) -> bool;
+        fn has_limits(&self) -> bool;
+        fn check_limits<I: Instance + ?Sized>(
+            &self,
+            instance: &I,
+            depth: usize,
+        ) -> Result<(), ValidateError>;
+        fn check_value_limits<I: Instance + ?Sized>(
+            &self,
+            value: &I,
+            depth: usize,
+        ) -> Result<(), ValidateError>;
+        fn type_check<I: Instance + ?Sized>(&self, type_: &Type) -> Option<fn(&I) -> bool>;
+        fn is_timestamp(&self, s: &str) -> bool;
+        fn enum_contains(&self, enum_: &BTreeSet<String>, s: &str) -> bool;
+        fn report_progress(&self, visited: u64);
+        fn without_progress(self) -> Self;
+        #[cfg(feature = "extensions")]
+        fn reject_key(&self, keys: &crate::extensions::Keys, name: &str) -> Option<&'static str>;
+        fn enum_key<'s>(&self, s: &'s str) -> Cow<'s, str>;
+    }
+
+    impl ValidateOptionsExt for ValidateOptions {
+        fn max_depth(&self) -> usize {
+            self.max_depth
+        }
+
+        fn max_errors(&self) -> usize {
+            self.max_errors
+        }
+
+        fn min_severity(&self) -> Severity {
+            self.min_severity
+        }
+
+        fn ignore_missing_required(&self) -> bool {
+            self.ignore_missing_required
+        }
+
+        fn max_string_len(&self) -> usize {
+            self.max_string_len
+        }
+
+        fn max_array_len(&self) -> usize {
+            self.max_array_len
+        }
+
+        fn max_object_keys(&self) -> usize {
+            self.max_object_keys
+        }
+
+        fn max_instance_depth(&self) -> usize {
+            self.max_instance_depth
+        }
+
+        #[cfg(feature = "extensions")]
+        fn type_extensions(&self) -> &crate::TypeExtensions {
+            &self.type_extensions
+        }
+
+        fn is_cancelled(&self) -> bool {
+            match self.cancellation {
+                Some(Cancellation(ref cancelled)) => cancelled.load(Ordering::Relaxed),
+                None => false,
+            }
+        }
+
+        // Whether these options have any per-value limits.
+        fn has_limits(&self) -> bool {
+            self.max_string_len != 0
+                || self.max_array_len != 0
+                || self.max_object_keys != 0
+                || self.max_instance_depth != 0
+        }
+
+        // Checks every value in the instance against the per-value limits of
+        // these options, given the number of arrays and objects the instance is
+        // itself nested in. This doesn't recurse, so that it can't overflow the
+        // stack on deeply nested instances.
+        fn check_limits<I: Instance + ?Sized>(
+            &self,
+            instance: &I,
+            depth: usize,
+        ) -> Result<(), ValidateError> {
+            if !self.has_limits() {
+                return Ok(());
+            }
+
+            let mut stack = vec![(instance, depth)];
+            while let Some((value, depth)) = stack.pop() {
+                if self.is_cancelled() {
+                    return Err(ValidateError::Cancelled);
+                }
+
+                self.check_value_limits(value, depth)?;
+
+                if let Some(arr) = value.as_array() {
+                    stack.extend(arr.map(|sub_value| (sub_value, depth + 1)));
+                } else if let Some(obj) = value.as_object() {
+                    stack.extend(obj.map(|(_, sub_value)| (sub_value, depth + 1)));
+                }
+            }
+
+            Ok(())
+        }
+
+        // Like check_limits, but only for the value itself: the length of a
+        // string, or the length, nesting and keys of an array or object, but
+        // not its elements or values.
+        fn check_value_limits<I: Instance + ?Sized>(
+            &self,
+            value: &I,
+            depth: usize,
+        ) -> Result<(), ValidateError> {
+            let exceeds = |len: usize, max: usize| max != 0 && len > max;
+
+            if let Some(s) = value.as_str() {
+                if exceeds(s.len(), self.max_string_len) {
+                    return Err(ValidateError::StringTooLong);
+                }
+            } else if let Some(arr) = value.as_array() {
+                if exceeds(depth + 1, self.max_instance_depth) {
+                    return Err(ValidateError::MaxInstanceDepthExceeded);
+                }
+
+                if exceeds(arr.len(), self.max_array_len) {
+                    return Err(ValidateError::ArrayTooLong);
+                }
+            } else if let Some(obj) = value.as_object() {
+                if exceeds(depth + 1, self.max_instance_depth) {
+                    return Err(ValidateError::MaxInstanceDepthExceeded);
+                }
+
+                if exceeds(obj.len(), self.max_object_keys) {
+                    return Err(ValidateError::TooManyObjectKeys);
+                }
+
+                for (key, _) in obj {
+                    if exceeds(key.len(), self.max_string_len) {
+                        return Err(ValidateError::StringTooLong);
+                    }
+                }
+            }
+
+            Ok(())
+        }
+
+        // Like type_check, but leaving timestamps to is_timestamp if these
+        // options have a timestamp validator.
+        fn type_check<I: Instance + ?Sized>(&self, type_: &Type) -> Option<fn(&I) -> bool> {
+            match type_ {
+                Type::Timestamp if self.timestamp_validator.is_some() => None,
+                _ => type_check(type_),
+            }
+        }
+
+        // Whether a string is a timestamp, according to these options.
+        fn is_timestamp(&self, s: &str) -> bool {
+            let validator = match self.timestamp_validator {
+                Some(TimestampValidator(validator)) => validator,
+                None => crate::timestamp::is_rfc3339,
+            };
+
+            validator(s)
+        }
+
+        // Whether a string is one of the values of an enum, according to these
+        // options.
+        fn enum_contains(&self, enum_: &BTreeSet<String>, s: &str) -> bool {
+            if self.case_insensitive_enums || self.unicode_normalization.is_some() {
+                let s = self.enum_key(s);
+                enum_.iter().any(|value| self.enum_key(value) == s)
+            } else {
+                enum_.contains(s)
+            }
+        }
+
+        // Calls the progress hook, if there is one and it's due, given the number
+        // of values visited so far.
+        fn report_progress(&self, visited: u64) {
+            if let Some(ref progress) = self.progress {
+                if visited % progress.interval == 0 {
+                    (progress.hook)(visited);
+                }
+            }
+        }
+
+        // The same options, without the progress hook, for going over an
+        // instance a second time.
+        fn without_progress(mut self) -> Self {
+            self.progress = None;
+            self
+        }
+
+        // The keyword of `keys` that rejects a key, if any does.
+        #[cfg(feature = "extensions")]
+        fn reject_key(&self, keys: &crate::extensions::Keys, name: &str) -> Option<&'static str> {
+            use crate::extensions::Keys;
+
+            let (keyword, ok) = match keys {
+                Keys::Enum(enum_) => ("enum", self.enum_contains(enum_, name)),
+                Keys::String { pattern: None } => return None,
+                Keys::String {
+                    pattern: Some(pattern),
+                } => {
+                    let name = match self.unicode_normalization {
+                        Some(normalization) => Cow::Owned(normalization.normalize(name)),
+                        None => Cow::Borrowed(name),
+                    };
+
+                    ("pattern", pattern.is_match(&name))
+                }
+            };
+
+            Some(keyword).filter(|_| !ok)
+        }
+
+        // Converts a string into the form in which it's compared against enum
+        // values, according to these options.
+        fn enum_key<'s>(&self, s: &'s str) -> Cow<'s, str> {
+            let s = match self.unicode_normalization {
+                Some(normalization) => Cow::Owned(normalization.normalize(s)),
+                None => Cow::Borrowed(s),
+            };
+
+            if self.case_insensitive_enums {
+                Cow::Owned(s.to_lowercase())
+            } else {
+                s
+            }
+        }
+    }
+
+    // A check of whether a value is of a type, or None for types only the
+    // options can check.
+    pub fn type_check<I: Instance + ?Sized>(type_: &Type) -> Option<fn(&I) -> bool> {
+        Some(match type_ {
+            Type::Boolean => |v| v.as_bool().is_some(),
+            Type::Float32 | Type::Float64 => |v| v.as_f64().is_some(),
+            Type::Int8 => |v| is_int_in(v, -128.0, 127.0),
+            Type::Uint8 => |v| is_int_in(v, 0.0, 255.0),
+            Type::Int16 => |v| is_int_in(v, -32768.0, 32767.0),
+            Type::Uint16 => |v| is_int_in(v, 0.0, 65535.0),
+            Type::Int32 => |v| is_int_in(v, -2147483648.0, 2147483647.0),
+            Type::Uint32 => |v| is_int_in(v, 0.0, 4294967295.0),
+            Type::String => |v| v.as_str().is_some(),
+            Type::Timestamp => |v| v.as_str().is_some_and(crate::timestamp::is_rfc3339),
+            #[cfg(feature = "extensions")]
+            Type::Custom(_) => return None,
+        })
+    }
+}
+
+/// Errors that may arise from [`validate()`].
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ValidateError {
+    /// The maximum depth, as specified by [`ValidateOptions::with_max_depth`],
+    /// was exceeded.
+    ///
+    /// ```
+    /// use serde_json::json;
+    /// use jtd::{Schema, ValidateError, ValidateOptions};
+    ///
+    /// let schema = Schema::from_serde_schema(
+    ///     serde_json::from_value(json!({
+    ///         "definitions": {
+    ///             "loop": { "ref": "loop" },
+    ///         },
+    ///         "ref": "loop",
+    ///     }))
+    ///     .unwrap(),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     ValidateError::MaxDepthExceeded,
+    ///     jtd::validate(
+    ///         &schema,
+    ///         &json!(null),
+    ///         ValidateOptions::new().with_max_depth(3)
+    ///     )
+    ///     .unwrap_err()
+    /// )
+    /// ```
+    #[error("max depth exceeded")]
+    MaxDepthExceeded,
+
+    /// Validation was cancelled, using the flag passed to
+    /// [`ValidateOptions::with_cancellation`].
+    #[error("validation cancelled")]
+    Cancelled,
+
+    /// A string in the instance, or a key of an object in it, was longer
+    /// than allowed by [`ValidateOptions::with_max_string_len`].
+    #[error("max string length exceeded")]
+    StringTooLong,
+
+    /// An array in the instance had more elements than allowed by
+    /// [`ValidateOptions::with_max_array_len`].
+    #[error("max array length exceeded")]
+    ArrayTooLong,
+
+    /// An object in the instance had more keys than allowed by
+    /// [`ValidateOptions::with_max_object_keys`].
+    #[error("max object keys exceeded")]
+    TooManyObjectKeys,
+
+    /// Arrays and objects in the instance were nested more deeply than
+    /// allowed by [`ValidateOptions::with_max_instance_depth`].
+    #[error("max instance depth exceeded")]
+    MaxInstanceDepthExceeded,
+
+    /// The schema was found to be invalid during validation.
+    ///
+    /// Schemas that pass [`Schema::validate`] never cause this error. But a
+    /// [`Schema`] can be built by hand, or edited after it's been checked,
+    /// and [`validate()`] doesn't require the schema to be checked first.
+    /// When it comes across a problem in the schema, such as a `ref` to a
+    /// definition that doesn't exist, it returns this error rather than
+    /// panicking.
+    ///
+    /// ```
+    /// use jtd::{Schema, SchemaValidateError, ValidateError, ValidateOptions};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::Ref {
+    ///     definitions: Default::default(),
+    ///     metadata: Default::default(),
+    ///     nullable: false,
+    ///     ref_: "missing".into(),
+    /// };
+    ///
+    /// assert_eq!(
+    ///     ValidateError::InvalidSchema(SchemaValidateError::NoSuchDefinition("missing".into())),
+    ///     jtd::validate(&schema, &json!(null), ValidateOptions::new()).unwrap_err(),
+    /// );
+    /// ```
+    #[error("invalid schema: {0}")]
+    InvalidSchema(SchemaValidateError),
+
+    /// The schema uses a [`Type::Custom`] that isn't registered in the
+    /// [`TypeExtensions`][`crate::TypeExtensions`] passed to
+    /// [`ValidateOptions::with_type_extensions`].
+    #[cfg(feature = "extensions")]
+    #[error("unknown type: {0:?}")]
+    UnknownType(String),
+}
+
+/// A single validation error returned by [`validate()`].
+///
+/// This type has *Indicator* at the end of its name to emphasize that it is
+/// *not* a Rust error. It is an ordinary struct, and corresponds to the concept
+/// of a validation error indicator in the JSON Typedef specification. See
+/// [RFC8927, Section 3.2](https://tools.ietf.org/html/rfc8927#section-3.2).
+///
+/// The paths are an [`InstancePath`] and a [`SchemaPath`], which display as
+/// JSON Pointers, and can be followed back to the rejected part of the
+/// instance and the schema that rejected it. In order to avoid unncessary
+/// allocations, their tokens are [`std::borrow::Cow`]s instead of [`String`]s.
+/// If you would prefer not to have to deal with that, and are OK with copying
+/// all the data out of this struct, then use
+/// [`into_owned_paths`][`ValidationErrorIndicator::into_owned_paths`] to
+/// convert instances of this type into a pair of plain old `Vec<String>`s.
+///
+/// Borrowing also means that these errors can't outlive the schema and
+/// instance they came from. To keep them around, or send them to another
+/// thread, convert them into [`OwnedValidationErrorIndicator`]s with
+/// [`into_owned`][`ValidationErrorIndicator::into_owned`], or get them that
+/// way to begin with from [`validate_owned`].
+///
+/// This type serializes in the format of RFC 8927, and of the JSON Typedef
+/// test suite, with the paths as arrays named `instancePath` and
+/// `schemaPath`. A [`Severity`] other than [`Severity::Error`] is added as
+/// `severity`, and is taken to be [`Severity::Error`] when deserializing if
+/// it's absent. This lets validation results be returned as they are, such as
+/// from an HTTP handler:
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(
+///     serde_json::from_value(json!({ "elements": { "type": "uint8" }})).unwrap(),
+/// )
+/// .unwrap();
+///
+/// let instance = json!([1, 300]);
+/// let errors = jtd::validate(&schema, &instance, Default::default()).unwrap();
+///
+/// assert_eq!(
+///     json!([{ "instancePath": ["1"], "schemaPath": ["elements", "type"] }]),
+///     serde_json::to_value(&errors).unwrap(),
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationErrorIndicator<'a> {
+    /// A path to the part of the instance that was rejected.
+    pub instance_path: InstancePath<'a>,
+
+    /// A path to the part of the schema that rejected the instance.
+    pub schema_path: SchemaPath<'a>,
+
+    /// How serious the error is. See [`Severity`].
+    #[serde(default, skip_serializing_if = "is_error")]
+    pub severity: Severity,
+}
+
+impl<'a> ValidationErrorIndicator<'a> {
+    /// Converts this struct into a `instance_path` and `schema_path` pair.
+    ///
+    /// This is a convenience function for those who don't want to manipulate
+    /// [`std::borrow::Cow`].
+    ///
+    /// ```
+    /// let indicator = jtd::ValidationErrorIndicator {
+    ///     instance_path: vec!["foo"].into(),
+    ///     schema_path: vec!["bar".to_owned()].into(),
+    ///     severity: jtd::Severity::Error,
+    /// };
+    ///
+    /// let (instance_path, schema_path) = indicator.into_owned_paths();
+    /// assert_eq!(vec!["foo".to_owned()], instance_path);
+    /// assert_eq!(vec!["bar".to_owned()], schema_path);
+    /// ```
+    pub fn into_owned_paths(self) -> (Vec<String>, Vec<String>) {
+        (
+            self.instance_path.iter().map(|c| c.to_string()).collect(),
+            self.schema_path.iter().map(|c| c.to_string()).collect(),
+        )
+    }
+
+    /// Converts this struct into one that doesn't borrow from the schema or
+    /// instance.
+    ///
+    /// ```
+    /// let indicator = jtd::ValidationErrorIndicator {
+    ///     instance_path: vec!["foo"].into(),
+    ///     schema_path: vec!["bar".to_owned()].into(),
+    ///     severity: jtd::Severity::Warning,
+    /// };
+    ///
+    /// let owned = indicator.into_owned();
+    /// assert_eq!(vec!["foo".to_owned()], owned.instance_path);
+    /// assert_eq!(vec!["bar".to_owned()], owned.schema_path);
+    /// assert_eq!(jtd::Severity::Warning, owned.severity);
+    /// ```
+    pub fn into_owned(self) -> OwnedValidationErrorIndicator {
+        let severity = self.severity;
+        let (instance_path, schema_path) = self.into_owned_paths();
+
+        OwnedValidationErrorIndicator {
+            instance_path,
+            schema_path,
+            severity,
+        }
+    }
+}
+
+/// A [`ValidationErrorIndicator`] that owns its paths, rather than borrowing
+/// them from the schema and instance.
+///
+/// This type is `'static`, so it can be stored, sent to other threads, or
+/// returned after the schema and instance are gone. See
+/// [`ValidationErrorIndicator::into_owned`] and [`validate_owned`].
+///
+/// This type serializes just as [`ValidationErrorIndicator`] does.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnedValidationErrorIndicator {
+    /// A path to the part of the instance that was rejected.
+    pub instance_path: Vec<String>,
+
+    /// A path to the part of the schema that rejected the instance.
+    pub schema_path: Vec<String>,
+
+    /// How serious the error is. See [`Severity`].
+    #[serde(default, skip_serializing_if = "is_error")]
+    pub severity: Severity,
+}
+
+// Errors are the usual severity, and the only one RFC 8927 knows, so it's left
+// out when serializing.
+fn is_error(severity: &Severity) -> bool {
+    *severity == Severity::Error
+}
+
+impl<'a> From<ValidationErrorIndicator<'a>> for OwnedValidationErrorIndicator {
+    fn from(indicator: ValidationErrorIndicator<'a>) -> Self {
+        indicator.into_owned()
+    }
+}
+
+impl From<OwnedValidationErrorIndicator> for ValidationErrorIndicator<'static> {
+    fn from(indicator: OwnedValidationErrorIndicator) -> Self {
+        Self {
+            instance_path: indicator.instance_path.into(),
+            schema_path: indicator.schema_path.into(),
+            severity: indicator.severity,
+        }
+    }
+}
+
+/// Validates a schema against an instance, returning a set of error indicators.
+///
+/// In keeping with the conventions of RFC8927, the "input" JSON -- the second
+/// argument to this function -- is called an *instance*. Instances are
+/// usually [`serde_json::Value`]s, but may be of any type that implements
+/// [`Instance`].
+///
+/// The set of error indicators returned is specified by the JSON Typedef
+/// specification. The ordering of those errors is not defined by the JSON
+/// Typedef specification, and is subject to change in a future version of this
+/// crate.
+///
+/// ```
+/// use jtd::{Schema, Severity, ValidationErrorIndicator, ValidateOptions};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(
+///     serde_json::from_value(json!({
+///         "elements": {
+///             "type": "uint8"
+///         }
+///     })).unwrap()).unwrap();
+///
+/// let instance = serde_json::json!([ "a", "b", "c" ]);
+///
+/// // By default, jtd::validate() will return all errors in the input.
+/// let validate_options = ValidateOptions::new();
+/// let errors = jtd::validate(&schema, &instance, validate_options).unwrap();
+/// assert_eq!(
+///     vec![
+///         ValidationErrorIndicator {
+///             instance_path: vec!["0"].into(),
+///             schema_path: vec!["elements", "type"].into(),
+///             severity: Severity::Error,
+///         },
+///         ValidationErrorIndicator {
+///             instance_path: vec!["1"].into(),
+///             schema_path: vec!["elements", "type"].into(),
+///             severity: Severity::Error,
+///         },
+///         ValidationErrorIndicator {
+///             instance_path: vec!["2"].into(),
+///             schema_path: vec!["elements", "type"].into(),
+///             severity: Severity::Error,
+///         },
+///     ],
+///     errors,
+/// );
+///
+/// // If you don't care about validation errors beyond a certain amount of
+/// // errors, use with_max_errors on the ValidateOptions you pass to validate.
+/// let validate_options = ValidateOptions::new().with_max_errors(1);
+/// let errors = jtd::validate(&schema, &instance, validate_options).unwrap();
+/// assert_eq!(
+///     vec![
+///         ValidationErrorIndicator {
+///             instance_path: vec!["0"].into(),
+///             schema_path: vec!["elements", "type"].into(),
+///             severity: Severity::Error,
+///         },
+///     ],
+///     errors,
+/// );
+/// ```
+///
+/// # Security considerations
+///
+/// (This note is copied from [the top-level documentation][`crate`], because
+/// it's important.)
+///
+/// If you're running [`validate()`] with untrusted schemas (untrusted inputs is
+/// fine), then be aware of this security consideration from RFC 8927:
+///
+/// > Implementations that evaluate user-inputted schemas SHOULD implement
+/// > mechanisms to detect and abort circular references that might cause a
+/// > naive implementation to go into an infinite loop.  Without such
+/// > mechanisms, implementations may be vulnerable to denial-of-service
+/// > attacks.
+///
+/// This crate supports that "detect and abort" mechanism via
+/// [`ValidateOptions::with_max_depth`]. Please see that documentation if you're
+/// validating data against untrusted schemas.
+///
+/// # Panics
+///
+/// This function does not panic, whatever the schema and instance. A schema
+/// that doesn't pass [`Schema::validate`] results in
+/// [`ValidateError::InvalidSchema`] (or, where the problem doesn't get in the
+/// way of validation, is simply tolerated), rather than a panic. Hooks passed
+/// to [`ValidateOptions::with_progress`] are the exception: if they panic, so
+/// does this function.
+///
+/// Not panicking is not the same as terminating, though: see the security
+/// considerations above about circular references.
+pub fn validate<'a, I: Instance + ?Sized>(
+    schema: &'a Schema,
+    instance: &'a I,
+    options: ValidateOptions,
+) -> Result<Vec<ValidationErrorIndicator<'a>>, ValidateError> {
+    // When only the first error is wanted, it's cheaper to look for it
+    // without keeping track of paths, and then, only if there is one, look
+    // for it again to find its path.
+    let track_paths = options.max_errors != 1;
+    let mut vm = Vm::new(schema, options, track_paths);
+    let mut result = vm.validate(schema, None, instance);
+
+    if !track_paths && matches!(result, Err(VmValidateError::MaxErrorsReached)) {
+        // Progress has already been reported for the first pass.
+        vm = Vm::new(schema, vm.options.without_progress(), true);
+        result = vm.validate(schema, None, instance);
+    }
+
+    match result {
+        Ok(()) | Err(VmValidateError::MaxErrorsReached) => Ok(vm.into_errors()),
+        Err(VmValidateError::MaxDepthExceeded) => Err(ValidateError::MaxDepthExceeded),
+        Err(VmValidateError::Cancelled) => Err(ValidateError::Cancelled),
+        Err(VmValidateError::InvalidSchema(err)) => Err(ValidateError::InvalidSchema(err)),
+        Err(VmValidateError::LimitExceeded(err)) => Err(err),
+        #[cfg(feature = "extensions")]
+        Err(VmValidateError::UnknownType(name)) => Err(ValidateError::UnknownType(name)),
+    }
+}
+
+/// Like [`validate()`], but returns errors that don't borrow from the schema
+/// or instance.
+///
+/// ```
+/// use jtd::{OwnedValidationErrorIndicator, Schema, Severity, ValidateOptions};
+/// use serde_json::json;
+///
+/// fn errors(schema: &Schema, input: &str) -> Vec<OwnedValidationErrorIndicator> {
+///     let instance: serde_json::Value = serde_json::from_str(input).unwrap();
+///
+///     // The errors outlive the instance they're about.
+///     jtd::validate_owned(schema, &instance, ValidateOptions::new()).unwrap()
+/// }
+///
+/// let schema = Schema::from_serde_schema(
+///     serde_json::from_value(json!({ "elements": { "type": "uint8" }})).unwrap(),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(
+///     vec![OwnedValidationErrorIndicator {
+///         instance_path: vec!["1".into()],
+///         schema_path: vec!["elements".into(), "type".into()],
+///         severity: Severity::Error,
+///     }],
+///     errors(&schema, "[1, 300]"),
+/// );
+/// ```
+pub fn validate_owned<I: Instance + ?Sized>(
+    schema: &Schema,
+    instance: &I,
+    options: ValidateOptions,
+) -> Result<Vec<OwnedValidationErrorIndicator>, ValidateError> {
+    Ok(validate(schema, instance, options)?
+        .into_iter()
+        .map(ValidationErrorIndicator::into_owned)
+        .collect())
+}
+
+struct Vm<'a, I: Instance + ?Sized> {
+    root: &'a Schema,
+    options: ValidateOptions,
+    instance_tokens: Vec<Cow<'a, str>>,
+    // The schema path within the current definition (or the root). Following
+    // a ref starts a new path, and the old one is restored on the way back.
+    schema_tokens: Vec<Cow<'a, str>>,
+    // The number of schema paths in play: one for the root, plus one for each
+    // ref being followed.
+    depth: usize,
+    // The number of arrays and objects the value being validated is nested
+    // in, kept up to date whether or not paths are tracked.
+    instance_depth: usize,
+    errors: Vec<ValidationErrorIndicator<'a>>,
+    visited: u64,
+    // The severity of errors found by the schema being validated against.
+    severity: Severity,
+    // Whether to keep instance_tokens and schema_tokens up to date. If not,
+    // errors are found with empty paths.
+    track_paths: bool,
+    instance: std::marker::PhantomData<&'a I>,
+}
+
+enum VmValidateError {
+    MaxErrorsReached,
+    MaxDepthExceeded,
+    Cancelled,
+    InvalidSchema(SchemaValidateError),
+    // One of the per-value limits of the options was exceeded.
+    LimitExceeded(ValidateError),
+    #[cfg(feature = "extensions")]
+    UnknownType(String),
+}
+
+// Work left to do by Vm::validate, in the order it's popped off the stack.
+enum Task<'a, I: Instance + ?Sized + 'a> {
+    // Validate an instance against a schema.
+    Validate {
+        schema: &'a Schema,
+        parent_tag: Option<&'a str>,
+        instance: &'a I,
+    },
+
+    // Validate the rest of the elements of an array.
+    Elements {
+        elements: &'a Schema,
+        instances: std::iter::Enumerate<I::Elements<'a>>,
+    },
+
+    // Validate the rest of the properties, or optional properties, of an
+    // object. Missing properties are errors only if they're required.
+    Properties {
+        obj: &'a I,
+        properties: std::collections::btree_map::Iter<'a, String, Schema>,
+        required: bool,
+    },
+
+    // Check an object for properties that aren't in its schema.
+    AdditionalProperties {
+        entries: I::Entries<'a>,
+        properties: &'a std::collections::BTreeMap<String, Schema>,
+        optional_properties: &'a std::collections::BTreeMap<String, Schema>,
+        parent_tag: Option<&'a str>,
+    },
+
+    // Validate the rest of the values of an object.
+    Values {
+        values: &'a Schema,
+        #[cfg(feature = "extensions")]
+        keys: Option<&'a crate::extensions::Keys>,
+        entries: I::Entries<'a>,
+    },
+
+    PushSchemaToken(&'a str),
+    PopSchemaToken,
+
+    // Leave a part of an instance, going back to its parent.
+    PopInstanceToken,
+
+    // Go back to the schema path from before following a ref.
+    LeaveRef(Vec<Cow<'a, str>>),
+
+    RestoreSeverity(Severity),
+}
+
+impl<'a, I: Instance + ?Sized> Vm<'a, I> {
+    pub fn new(schema: &'a Schema, options: ValidateOptions, track_paths: bool) -> Self {
+        Self {
+            root: schema,
+            options,
+            instance_tokens: vec![],
+            schema_tokens: vec![],
+            depth: 1,
+            instance_depth: 0,
+            errors: vec![],
+            visited: 0,
+            severity: Severity::Error,
+            track_paths,
+            instance: std::marker::PhantomData,
+        }
+    }
+
+    pub fn into_errors(self) -> Vec<ValidationErrorIndicator<'a>> {
+        self.errors
+    }
+
+    // Validates an instance against a schema.
+    //
+    // Rather than recursing into the parts of the instance, this keeps a stack
+    // of the work left to do, so that deeply nested instances can't overflow
+    // the call stack. Work is pushed in reverse, so that it's done, and errors
+    // are found, in the order a recursive walk would do it.
+    pub fn validate(
+        &mut self,
+        schema: &'a Schema,
+        parent_tag: Option<&'a str>,
+        instance: &'a I,
+    ) -> Result<(), VmValidateError> {
+        let mut stack = vec![Task::Validate {
+            schema,
+            parent_tag,
+            instance,
+        }];
+
+        while let Some(task) = stack.pop() {
+            match task {
+                Task::Validate {
+                    schema,
+                    parent_tag,
+                    instance,
+                } => self.validate_one(&mut stack, schema, parent_tag, instance)?,
+                Task::Elements {
+                    elements,
+                    mut instances,
+                } => {
+                    if let Some((i, sub_instance)) = instances.next() {
+                        stack.push(Task::Elements {
+                            elements,
+                            instances,
+                        });
+
+                        self.push_index_token(i);
+                        self.instance_depth += 1;
+                        stack.push(Task::PopInstanceToken);
+                        stack.push(Task::Validate {
+                            schema: elements,
+                            parent_tag: None,
+                            instance: sub_instance,
+                        });
+                    }
+                }
+                Task::Properties {
+                    obj,
+                    mut properties,
+                    required,
+                } => {
+                    if let Some((name, sub_schema)) = properties.next() {
+                        stack.push(Task::Properties {
+                            obj,
+                            properties,
+                            required,
+                        });
+
+                        self.push_schema_token(name);
+                        if let Some(sub_instance) = obj.get(name) {
+                            self.push_instance_token(name);
+                            self.instance_depth += 1;
+                            stack.push(Task::PopSchemaToken);
+                            stack.push(Task::PopInstanceToken);
+                            stack.push(Task::Validate {
+                                schema: sub_schema,
+                                parent_tag: None,
+                                instance: sub_instance,
+                            });
+                        } else {
+                            if required && !self.options.ignore_missing_required {
+                                // The property's schema says how serious it
+                                // is for the property to be missing.
+                                let saved_severity = self.severity;
+                                if let Some(severity) =
+                                    Severity::from_metadata(sub_schema.metadata())
+                                {
+                                    self.severity = severity;
+                                }
+
+                                self.push_error()?;
+                                self.severity = saved_severity;
+                            }
+
+                            self.pop_schema_token();
+                        }
+                    }
+                }
+                Task::AdditionalProperties {
+                    entries,
+                    properties,
+                    optional_properties,
+                    parent_tag,
+                } => {
+                    for (name, _) in entries {
+                        if parent_tag != Some(name)
+                            && !properties.contains_key(name)
+                            && !optional_properties.contains_key(name)
+                        {
+                            self.push_instance_token(name);
+                            self.push_error()?;
+                            self.pop_instance_token();
+                        }
+                    }
+                }
+                Task::Values {
+                    values,
+                    #[cfg(feature = "extensions")]
+                    keys,
+                    mut entries,
+                } => {
+                    if let Some((name, sub_instance)) = entries.next() {
+                        stack.push(Task::Values {
+                            values,
+                            #[cfg(feature = "extensions")]
+                            keys,
+                            entries,
+                        });
+
+                        self.push_instance_token(name);
+
+                        #[cfg(feature = "extensions")]
+                        if let Some(keys) = keys {
+                            self.validate_key(keys, name)?;
+                        }
+
+                        self.push_schema_token("values");
+                        self.instance_depth += 1;
+                        stack.push(Task::PopInstanceToken);
+                        stack.push(Task::PopSchemaToken);
+                        stack.push(Task::Validate {
+                            schema: values,
+                            parent_tag: None,
+                            instance: sub_instance,
+                        });
+                    }
+                }
+                Task::PushSchemaToken(token) => self.push_schema_token(token),
+                Task::PopSchemaToken => self.pop_schema_token(),
+                Task::PopInstanceToken => {
+                    self.pop_instance_token();
+                    self.instance_depth -= 1;
+                }
+                Task::LeaveRef(schema_tokens) => {
+                    self.schema_tokens = schema_tokens;
+                    self.depth -= 1;
+                }
+                Task::RestoreSeverity(severity) => self.severity = severity,
+            }
+        }
+
+        Ok(())
+    }
+
+    // Validates an instance against a schema, as far as can be done without
+    // looking into the parts of the instance. The rest of the work is pushed
+    // onto the stack.
+    fn validate_one(
+        &mut self,
+        stack: &mut Vec<Task<'a, I>>,
+        schema: &'a Schema,
+        parent_tag: Option<&'a str>,
+        instance: &'a I,
+    ) -> Result<(), VmValidateError> {
+        if self.options.is_cancelled() {
+            return Err(VmValidateError::Cancelled);
+        }
+
+        // A ref leads to another schema for the same value, so it's only
+        // counted once it's resolved.
+        if !matches!(schema, Schema::Ref { .. }) {
+            self.visit();
+        }
+
+        if instance.is_null() && schema.nullable() {
+            return Ok(());
+        }
+
+        self.check_limits(schema, instance)?;
+
+        // The severity is restored once everything pushed below is done.
+        let saved_severity = self.severity;
+        if let Some(severity) = Severity::from_metadata(schema.metadata()) {
+            self.severity = severity;
+        }
+        stack.push(Task::RestoreSeverity(saved_severity));
+
+        match schema {
+            Schema::Empty { .. } => {}
+            Schema::Ref { ref_, .. } => {
+                let definition = match self.root.definitions().get(ref_) {
+                    Some(definition) => definition,
+                    None => {
+                        return Err(VmValidateError::InvalidSchema(
+                            SchemaValidateError::NoSuchDefinition(ref_.clone()),
+                        ))
+                    }
+                };
+
+                self.depth += 1;
+                if self.depth == self.options.max_depth {
+                    return Err(VmValidateError::MaxDepthExceeded);
+                }
+
+                let tokens = if self.track_paths {
+                    vec!["definitions".into(), ref_.into()]
+                } else {
+                    Vec::new()
+                };
+
+                let saved = std::mem::replace(&mut self.schema_tokens, tokens);
+                stack.push(Task::LeaveRef(saved));
+                stack.push(Task::Validate {
+                    schema: definition,
+                    parent_tag: None,
+                    instance,
+                });
+            }
+            Schema::Type { type_, .. } => {
+                self.push_schema_token("type");
+
+                match self.options.type_check(type_) {
+                    Some(check) if !check(instance) => self.push_error()?,
+                    Some(_) => {}
+                    None if *type_ == Type::Timestamp
+                        && !instance
+                            .as_str()
+                            .is_some_and(|s| self.options.is_timestamp(s)) =>
+                    {
+                        self.push_error()?
+                    }
+                    #[cfg(feature = "extensions")]
+                    None => self.validate_custom_type(type_, instance)?,
+                    #[cfg(not(feature = "extensions"))]
+                    None => {}
+                };
+
+                self.pop_schema_token();
+            }
+            Schema::Enum { enum_, .. } => {
+                self.push_schema_token("enum");
+                if let Some(s) = instance.as_str() {
+                    if !self.options.enum_contains(enum_, s) {
+                        self.push_error()?;
+                    }
+                } else {
+                    self.push_error()?;
+                }
+                self.pop_schema_token();
+            }
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { enum_, .. } => {
+                self.push_schema_token("enum");
+                let ok = match instance.as_i64() {
+                    Some(i) => enum_.contains(&i),
+                    None => instance
+                        .as_f64()
+                        .map(|f| crate::extensions::int_enum_contains(enum_, f))
+                        .unwrap_or(false),
+                };
+
+                if !ok {
+                    self.push_error()?;
+                }
+                self.pop_schema_token();
+            }
+            Schema::Elements { elements, .. } => {
+                self.push_schema_token("elements");
+
+                if let Some(arr) = instance.as_array() {
+                    // Arrays of primitives, such as telemetry samples, are
+                    // common and can be long, so they get a loop of their own
+                    // that skips the bookkeeping of a task per element.
+                    if let Schema::Type {
+                        type_,
+                        metadata,
+                        nullable,
+                        ..
+                    } = &**elements
+                    {
+                        if let Some(check) = self.options.type_check(type_) {
+                            self.validate_type_elements(arr, check, metadata, *nullable)?;
+                            self.pop_schema_token();
+                            return Ok(());
+                        }
+                    }
+
+                    stack.push(Task::PopSchemaToken);
+                    stack.push(Task::Elements {
+                        elements,
+                        instances: arr.enumerate(),
+                    });
+                } else {
+                    self.push_error()?;
+                    self.pop_schema_token();
+                }
+            }
+            Schema::Properties {
+                properties,
+                optional_properties,
+                properties_is_present,
+                additional_properties,
+                ..
+            } => {
+                if let Some(entries) = instance.as_object() {
+                    if !*additional_properties {
+                        stack.push(Task::AdditionalProperties {
+                            entries,
+                            properties,
+                            optional_properties,
+                            parent_tag,
+                        });
+                    }
+
+                    stack.push(Task::PopSchemaToken);
+                    stack.push(Task::Properties {
+                        obj: instance,
+                        properties: optional_properties.iter(),
+                        required: false,
+                    });
+                    stack.push(Task::PushSchemaToken("optionalProperties"));
+
+                    stack.push(Task::PopSchemaToken);
+                    stack.push(Task::Properties {
+                        obj: instance,
+                        properties: properties.iter(),
+                        required: true,
+                    });
+                    self.push_schema_token("properties");
+                } else {
+                    self.push_schema_token(if *properties_is_present {
+                        "properties"
+                    } else {
+                        "optionalProperties"
+                    });
+                    self.push_error()?;
+                    self.pop_schema_token();
+                }
+            }
+            Schema::Values {
+                values,
+                #[cfg(feature = "extensions")]
+                keys,
+                ..
+            } => {
+                if let Some(entries) = instance.as_object() {
+                    stack.push(Task::Values {
+                        values,
+                        #[cfg(feature = "extensions")]
+                        keys: keys.as_ref(),
+                        entries,
+                    });
+                } else {
+                    self.push_schema_token("values");
+                    self.push_error()?;
+                    self.pop_schema_token();
+                }
+            }
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } => {
+                if instance.as_object().is_some() {
+                    if let Some(tag) = instance.get(discriminator) {
+                        if let Some(tag) = tag.as_str() {
+                            if let Some((tag, schema)) = mapping.get_key_value(tag) {
+                                self.push_schema_token("mapping");
+                                self.push_schema_token(tag);
+                                stack.push(Task::PopSchemaToken);
+                                stack.push(Task::PopSchemaToken);
+                                stack.push(Task::Validate {
+                                    schema,
+                                    parent_tag: Some(discriminator),
+                                    instance,
+                                });
+                            } else {
+                                self.push_schema_token("mapping");
+                                self.push_instance_token(discriminator);
+                                self.push_error()?;
+                                self.pop_instance_token();
+                                self.pop_schema_token();
+                            }
+                        } else {
+                            self.push_schema_token("discriminator");
+                            self.push_instance_token(discriminator);
+                            self.push_error()?;
+                            self.pop_instance_token();
+                            self.pop_schema_token();
+                        }
+                    } else {
+                        self.push_schema_token("discriminator");
+                        self.push_error()?;
+                        self.pop_schema_token();
+                    }
+                } else {
+                    self.push_schema_token("discriminator");
+                    self.push_error()?;
+                    self.pop_schema_token();
+                }
+            }
+        };
+
+        Ok(())
+    }
+
+    // Validates the elements of an array against a type-form schema, with the
+    // same results as validating each one in turn.
+    fn validate_type_elements(
+        &mut self,
+        arr: I::Elements<'a>,
+        check: fn(&I) -> bool,
+        metadata: &'a Metadata,
+        nullable: bool,
+    ) -> Result<(), VmValidateError> {
+        let saved_severity = self.severity;
+        if let Some(severity) = Severity::from_metadata(metadata) {
+            self.severity = severity;
+        }
+
+        for (i, sub_instance) in arr.enumerate() {
+            if self.options.is_cancelled() {
+                return Err(VmValidateError::Cancelled);
+            }
+
+            self.visit();
+            self.options
+                .check_limits(sub_instance, self.instance_depth + 1)
+                .map_err(VmValidateError::LimitExceeded)?;
+
+            if check(sub_instance) || (nullable && sub_instance.is_null()) {
+                continue;
+            }
+
+            self.push_index_token(i);
+            self.push_schema_token("type");
+            self.push_error()?;
+            self.pop_schema_token();
+            self.pop_instance_token();
+        }
+
+        self.severity = saved_severity;
+        Ok(())
+    }
+
+    #[cfg(feature = "extensions")]
+    fn validate_custom_type(&mut self, type_: &Type, instance: &I) -> Result<(), VmValidateError> {
+        if let Type::Custom(name) = type_ {
+            match self
+                .options
+                .type_extensions
+                .check(name, &instance.to_value())
+            {
+                Some(true) => {}
+                Some(false) => self.push_error()?,
+                None => return Err(VmValidateError::UnknownType(name.clone())),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Checks an instance against the per-value limits of the options. The
+    // parts of it that the schema looks into are checked as they're visited,
+    // so only the rest are checked here, in full.
+    fn check_limits(&self, schema: &'a Schema, instance: &'a I) -> Result<(), VmValidateError> {
+        if !self.options.has_limits() {
+            return Ok(());
+        }
+
+        let depth = self.instance_depth;
+        let result = match schema {
+            // The schema the ref leads to checks the instance.
+            Schema::Ref { .. } => Ok(()),
+            Schema::Elements { .. } if instance.as_array().is_some() => {
+                self.options.check_value_limits(instance, depth)
+            }
+            Schema::Values { .. } if instance.as_object().is_some() => {
+                self.options.check_value_limits(instance, depth)
+            }
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            } if instance.as_object().is_some() => {
+                self.options
+                    .check_value_limits(instance, depth)
+                    .and_then(|()| {
+                        // Additional properties, and the tag of a discriminator,
+                        // aren't looked into.
+                        instance
+                            .as_object()
+                            .into_iter()
+                            .flatten()
+                            .filter(|(name, _)| {
+                                !properties.contains_key(*name)
+                                    && !optional_properties.contains_key(*name)
+                            })
+                            .try_for_each(|(_, sub_instance)| {
+                                self.options.check_limits(sub_instance, depth + 1)
+                            })
+                    })
+            }
+            // The schema the tag leads to checks the instance.
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } if instance.as_object().is_some()
+                && instance
+                    .get(discriminator)
+                    .and_then(I::as_str)
+                    .is_some_and(|tag| mapping.contains_key(tag)) =>
+            {
+                Ok(())
+            }
+            _ => self.options.check_limits(instance, depth),
+        };
+
+        result.map_err(VmValidateError::LimitExceeded)
+    }
+
+    fn visit(&mut self) {
+        self.visited += 1;
+        self.options.report_progress(self.visited);
+    }
+
+    fn push_error(&mut self) -> Result<(), VmValidateError> {
+        if self.severity < self.options.min_severity {
+            return Ok(());
+        }
+
+        self.errors.push(ValidationErrorIndicator {
+            instance_path: self.instance_tokens[..].into(),
+            schema_path: self.schema_tokens[..].into(),
+            severity: self.severity,
+        });
+
+        if self.options.max_errors == self.errors.len() {
+            Err(VmValidateError::MaxErrorsReached)
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "extensions")]
+    fn validate_key(
+        &mut self,
+        keys: &'a crate::extensions::Keys,
+        name: &str,
+    ) -> Result<(), VmValidateError> {
+        if let Some(keyword) = self.options.reject_key(keys, name) {
+            self.push_schema_token("keys");
+            self.push_schema_token(keyword);
+            self.push_error()?;
+            self.pop_schema_token();
+            self.pop_schema_token();
+        }
+
+        Ok(())
+    }
+
+    fn push_schema_token(&mut self, token: &'a str) {
+        if self.track_paths {
+            self.schema_tokens.push(token.into());
+        }
+    }
+
+    fn pop_schema_token(&mut self) {
+        self.schema_tokens.pop();
+    }
+
+    fn push_instance_token(&mut self, token: &'a str) {
+        if self.track_paths {
+            self.instance_tokens.push(token.into());
+        }
+    }
+
+    // Array indices are the only instance tokens that aren't borrowed from
+    // the instance, so they're kept apart to keep push_instance_token simple.
+    fn push_index_token(&mut self, index: usize) {
+        if self.track_paths {
+            self.instance_tokens.push(Cow::Owned(index.to_string()));
+        }
+    }
+
+    fn pop_instance_token(&mut self) {
+        self.instance_tokens.pop();
+    }
+}
+
+fn is_int_in<I: Instance + ?Sized>(instance: &I, min: f64, max: f64) -> bool {
+    instance
+        .as_f64()
+        .is_some_and(|val| val.fract() == 0.0 && val >= min && val <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn max_depth() {
+        use serde_json::json;
+
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "loop": { "ref": "loop" },
+                },
+                "ref": "loop",
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            super::ValidateError::MaxDepthExceeded,
+            super::validate(
+                &schema,
+                &json!(null),
+                super::ValidateOptions::new().with_max_depth(3)
+            )
+            .unwrap_err()
+        )
+    }
+
+    #[test]
+    fn max_errors() {
+        use serde_json::json;
+
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "elements": { "type": "string" }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            3,
+            super::validate(
+                &schema,
+                &json!([null, null, null, null, null]),
+                super::ValidateOptions::new().with_max_errors(3)
+            )
+            .unwrap()
+            .len()
+        )
+    }
+
+    #[test]
+    fn cancellation() {
+        use serde_json::json;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "values": { "elements": { "type": "string" }}
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let options = super::ValidateOptions::new().with_cancellation(Arc::clone(&cancelled));
+
+        // Options are equal when they share the same flag.
+        assert_eq!(options, options.clone());
+        assert_ne!(
+            options,
+            super::ValidateOptions::new().with_cancellation(Arc::new(AtomicBool::new(false)))
+        );
+
+        let instance = json!({ "a": ["x", 1], "b": [2] });
+        assert_eq!(
+            2,
+            super::validate(&schema, &instance, options.clone())
+                .unwrap()
+                .len()
+        );
+
+        cancelled.store(true, Ordering::Relaxed);
+        assert_eq!(
+            super::ValidateError::Cancelled,
+            super::validate(&schema, &instance, options).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn progress() {
+        use serde_json::json;
+        use std::sync::{Arc, Mutex};
+
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": { "s": { "type": "string" }},
+                "properties": { "a": { "elements": { "ref": "s" }}}
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let reports = Arc::new(Mutex::new(vec![]));
+        let hook_reports = Arc::clone(&reports);
+        let options = super::ValidateOptions::new().with_progress(0, move |visited| {
+            hook_reports.lock().unwrap().push(visited);
+        });
+
+        // The root, the array, and its three elements, each counted once
+        // despite the refs.
+        super::validate(&schema, &json!({ "a": ["x", "y", "z"] }), options).unwrap();
+        assert_eq!(vec![1, 2, 3, 4, 5], *reports.lock().unwrap());
+    }
+
+    #[test]
+    fn value_limits() {
+        use super::{validate, ValidateError, ValidateOptions};
+        use serde_json::json;
+
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "properties": { "a": { "type": "string" }},
+                "additionalProperties": true
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let limits = |max_string_len, max_array_len, max_object_keys| {
+            ValidateOptions::new()
+                .with_max_string_len(max_string_len)
+                .with_max_array_len(max_array_len)
+                .with_max_object_keys(max_object_keys)
+        };
+
+        // Values at the limits are fine, and invalid ones still report
+        // validation errors.
+        let instance = json!({ "a": 1, "bb": [[1, 2], "cc"] });
+        assert_eq!(
+            1,
+            validate(&schema, &instance, limits(2, 2, 2)).unwrap().len()
+        );
+
+        // Limits apply to values the schema doesn't look at, and to keys.
+        assert_eq!(
+            Err(ValidateError::StringTooLong),
+            validate(&schema, &instance, limits(1, 0, 0))
+        );
+        assert_eq!(
+            Err(ValidateError::StringTooLong),
+            validate(&schema, &json!({ "a": "x", "bbb": null }), limits(2, 0, 0))
+        );
+        assert_eq!(
+            Err(ValidateError::ArrayTooLong),
+            validate(&schema, &instance, limits(0, 1, 0))
+        );
+        assert_eq!(
+            Err(ValidateError::TooManyObjectKeys),
+            validate(&schema, &instance, limits(0, 0, 1))
+        );
+
+        let depth =
+            |max_instance_depth| ValidateOptions::new().with_max_instance_depth(max_instance_depth);
+        assert_eq!(1, validate(&schema, &instance, depth(3)).unwrap().len());
+        assert_eq!(
+            Err(ValidateError::MaxInstanceDepthExceeded),
+            validate(&schema, &instance, depth(2))
+        );
+        assert!(validate(&schema, &json!("a"), depth(1)).is_ok());
+
+        // Limits are checked as the instance is validated, so validation stops
+        // at whichever comes first: a value over a limit, or the last error
+        // wanted.
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({ "elements": { "type": "uint8" }})).unwrap(),
+        )
+        .unwrap();
+
+        let instance = json!([300, "ccc"]);
+        assert_eq!(
+            Err(ValidateError::StringTooLong),
+            validate(&schema, &instance, limits(2, 0, 0))
+        );
+        assert_eq!(
+            1,
+            validate(&schema, &instance, limits(2, 0, 0).with_max_errors(1))
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[test]
+    fn first_error_paths() {
+        use serde_json::json;
+        use std::sync::{Arc, Mutex};
+
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": { "point": { "elements": { "type": "int8" }}},
+                "properties": {
+                    "a": { "type": "string" },
+                    "b": { "values": { "ref": "point" }}
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let reports = Arc::new(Mutex::new(vec![]));
+        let hook_reports = Arc::clone(&reports);
+        let options = super::ValidateOptions::new()
+            .with_max_errors(1)
+            .with_progress(0, move |visited| {
+                hook_reports.lock().unwrap().push(visited);
+            });
+
+        let instance = json!({ "a": "x", "b": { "p": [1, 2], "q": [3, 1000] }});
+        let errors = super::validate(&schema, &instance, options).unwrap();
+
+        assert_eq!(1, errors.len());
+        assert_eq!(vec!["b", "q", "1"], errors[0].instance_path);
+        assert_eq!(
+            vec!["definitions", "point", "elements", "type"],
+            errors[0].schema_path
+        );
+
+        // Progress is only reported for the search for the error, not for
+        // finding its path.
+        assert_eq!(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], *reports.lock().unwrap());
+        assert!(super::validate(
+            &schema,
+            &json!({ "a": "x", "b": { "p": [1] }}),
+            super::ValidateOptions::new().with_max_errors(1)
+        )
+        .unwrap()
+        .is_empty());
+    }
+
+    #[test]
+    fn type_elements() {
+        use serde_json::json;
+
+        // Elements behind a ref take the general path, so they show what the
+        // loop for elements of a type should do.
+        let element = json!({
+            "type": "uint8",
+            "nullable": true,
+            "metadata": { "severityOnViolation": "warning" }
+        });
+
+        let direct = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({ "elements": element })).unwrap(),
+        )
+        .unwrap();
+
+        let via_ref = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": { "e": element },
+                "elements": { "ref": "e" }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let instance = json!([1, null, -1, 2.5, "3", 255, 256]);
+        for max_errors in 0..5 {
+            let options = super::ValidateOptions::new()
+                .with_max_errors(max_errors)
+                .with_min_severity(super::Severity::Warning);
+
+            let direct_errors: Vec<_> = super::validate(&direct, &instance, options.clone())
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.instance_path, e.severity))
+                .collect();
+
+            let via_ref_errors: Vec<_> = super::validate(&via_ref, &instance, options)
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.instance_path, e.severity))
+                .collect();
+
+            assert_eq!(via_ref_errors, direct_errors);
+        }
+
+        let errors = super::validate(&direct, &instance, Default::default()).unwrap();
+        assert!(errors.is_empty());
+
+        let errors = super::validate(
+            &direct,
+            &instance,
+            super::ValidateOptions::new().with_min_severity(super::Severity::Warning),
+        )
+        .unwrap();
+        assert_eq!(4, errors.len());
+        assert_eq!(vec!["elements", "type"], errors[0].schema_path);
+        assert_eq!(vec!["2"], errors[0].instance_path);
+    }
+
+    #[test]
+    fn invalid_schemas() {
+        use crate::{Schema, SchemaValidateError};
+        use serde_json::json;
+
+        // Schemas that don't pass Schema::validate, but are tolerated where
+        // they don't get in the way.
+        for (schema, instance, errors) in [
+            (json!({ "enum": [] }), json!("a"), 1),
+            (
+                json!({ "definitions": { "a": {} }, "elements": { "definitions": { "b": {} }}}),
+                json!([1]),
+                0,
+            ),
+            (
+                json!({ "discriminator": "t", "mapping": { "a": { "type": "string" }}}),
+                json!({ "t": "a" }),
+                1,
+            ),
+            (
+                json!({ "discriminator": "t", "mapping": { "a": { "properties": { "t": {} }}}}),
+                json!({ "t": "a" }),
+                0,
+            ),
+        ] {
+            let schema =
+                Schema::from_serde_schema(serde_json::from_value(schema).unwrap()).unwrap();
+            assert!(schema.validate().is_err());
+            assert_eq!(
+                errors,
+                super::validate(&schema, &instance, super::ValidateOptions::new())
+                    .unwrap()
+                    .len()
+            );
+        }
+
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": { "a": { "elements": { "ref": "b" }}},
+                "ref": "a"
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            super::ValidateError::InvalidSchema(SchemaValidateError::NoSuchDefinition("b".into())),
+            super::validate(&schema, &json!([null]), super::ValidateOptions::new()).unwrap_err()
+        );
+
+        // A missing definition that the instance never gets to isn't a
+        // problem.
+        assert!(
+            super::validate(&schema, &json!([]), super::ValidateOptions::new())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn case_insensitive_enums() {
+        use serde_json::json;
+
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({ "enum": ["Straße", "FOO"] })).unwrap(),
+        )
+        .unwrap();
+
+        for (instance, strict, lenient) in [
+            (json!("FOO"), 0, 0),
+            (json!("foo"), 1, 0),
+            (json!("STRASSE"), 1, 1),
+            (json!("straße"), 1, 0),
+            (json!("bar"), 1, 1),
+        ] {
+            assert_eq!(
+                strict,
+                super::validate(&schema, &instance, super::ValidateOptions::new())
+                    .unwrap()
+                    .len()
+            );
+
+            assert_eq!(
+                lenient,
+                super::validate(
+                    &schema,
+                    &instance,
+                    super::ValidateOptions::new().with_case_insensitive_enums(true)
+                )
+                .unwrap()
+                .len()
+            );
+        }
+    }
+
+    #[test]
+    fn unicode_normalization() {
+        use super::UnicodeNormalization;
+        use serde_json::json;
+
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({ "enum": ["Caf\u{e9}", "\u{fb01}le"] })).unwrap(),
+        )
+        .unwrap();
+
+        let options = super::ValidateOptions::new();
+        for (instance, nfc, nfkc, nfc_insensitive) in [
+            (json!("Caf\u{e9}"), 0, 0, 0),
+            (json!("Cafe\u{301}"), 0, 0, 0),
+            (json!("cafe\u{301}"), 1, 1, 0),
+            (json!("\u{fb01}le"), 0, 0, 0),
+            (json!("file"), 1, 0, 1),
+        ] {
+            for (errors, options) in [
+                (
+                    nfc,
+                    options
+                        .clone()
+                        .with_unicode_normalization(UnicodeNormalization::Nfc),
+                ),
+                (
+                    nfkc,
+                    options
+                        .clone()
+                        .with_unicode_normalization(UnicodeNormalization::Nfkc),
+                ),
+                (
+                    nfc_insensitive,
+                    options
+                        .clone()
+                        .with_unicode_normalization(UnicodeNormalization::Nfc)
+                        .with_case_insensitive_enums(true),
+                ),
+            ] {
+                assert_eq!(
+                    errors,
+                    super::validate(&schema, &instance, options).unwrap().len(),
+                    "{}",
+                    instance
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "extensions")]
+    fn validate_keys() {
+        use super::{validate, UnicodeNormalization, ValidateOptions};
+        use serde_json::json;
+
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({
+            "values": { "type": "uint8" },
+            "keys": { "enum": ["caf\u{e9}", "tea"] }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let instance = json!({ "tea": 1, "coffee": 300, "cafe\u{301}": 1 });
+        let errors = validate(&schema, &instance, ValidateOptions::new()).unwrap();
+
+        let errors: Vec<_> = errors.into_iter().map(|e| e.into_owned_paths()).collect();
+        assert_eq!(
+            vec![
+                (
+                    vec!["cafe\u{301}".to_owned()],
+                    vec!["keys".to_owned(), "enum".to_owned()]
+                ),
+                (
+                    vec!["coffee".to_owned()],
+                    vec!["keys".to_owned(), "enum".to_owned()]
+                ),
+                (
+                    vec!["coffee".to_owned()],
+                    vec!["values".to_owned(), "type".to_owned()]
+                ),
+            ],
+            errors
+        );
+
+        let options = ValidateOptions::new().with_unicode_normalization(UnicodeNormalization::Nfc);
+        assert!(validate(&schema, &json!({ "cafe\u{301}": 1 }), options)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[cfg(feature = "extensions")]
+    #[test]
+    fn validate_int_enum() {
+        use super::{validate, ValidateOptions};
+        use serde_json::json;
+
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({ "elements": { "enum": [1, 2, -3] }})).unwrap(),
+        )
+        .unwrap();
+
+        let instance = json!([1, 2.0, -3, 4, 1.5, "1", 1e300]);
+        let errors = validate(&schema, &instance, ValidateOptions::new()).unwrap();
+
+        let errors: Vec<_> = errors.into_iter().map(|e| e.into_owned_paths()).collect();
+        let schema_path = vec!["elements".to_owned(), "enum".to_owned()];
+        assert_eq!(
+            vec![
+                (vec!["3".to_owned()], schema_path.clone()),
+                (vec!["4".to_owned()], schema_path.clone()),
+                (vec!["5".to_owned()], schema_path.clone()),
+                (vec!["6".to_owned()], schema_path),
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn deeply_nested_instances() {
+        use super::{validate, ValidateOptions};
+        use crate::Schema;
+        use serde_json::{json, Value};
+
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "nested": {
+                        "properties": { "a": { "elements": { "ref": "nested" }}},
+                        "nullable": true
+                    }
+                },
+                "ref": "nested"
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        // Far deeper than a recursive validator could go on a test thread's
+        // stack.
+        let depth = 100_000;
+        let mut instance = json!(1);
+        for _ in 0..=depth {
+            let mut obj = serde_json::Map::new();
+            obj.insert("a".to_owned(), Value::Array(vec![instance]));
+            instance = Value::Object(obj);
+        }
+
+        let errors = validate(&schema, &instance, ValidateOptions::new()).unwrap();
+        assert_eq!(1, errors.len());
+        assert_eq!(2 * depth + 2, errors[0].instance_path.len());
+        assert_eq!(
+            vec!["definitions", "nested", "properties"],
+            errors[0].schema_path
+        );
+
+        // Dropping a Value recurses, so this one is leaked instead.
+        drop(errors);
+        std::mem::forget(instance);
+    }
+
+    #[test]
+    fn serde_indicators() {
+        use super::{OwnedValidationErrorIndicator, Severity, ValidationErrorIndicator};
+        use serde_json::json;
+
+        let indicator = OwnedValidationErrorIndicator {
+            instance_path: vec!["a".into()],
+            schema_path: vec!["properties".into(), "a".into()],
+            severity: Severity::Warning,
+        };
+
+        let value = json!({
+            "instancePath": ["a"],
+            "schemaPath": ["properties", "a"],
+            "severity": "warning"
+        });
+
+        assert_eq!(value, serde_json::to_value(&indicator).unwrap());
+        assert_eq!(
+            indicator,
+            serde_json::from_value::<OwnedValidationErrorIndicator>(value).unwrap()
+        );
+
+        let indicator: ValidationErrorIndicator =
+            serde_json::from_str(r#"{ "instancePath": [], "schemaPath": ["type"] }"#).unwrap();
+        assert_eq!(Severity::Error, indicator.severity);
+    }
+
+    #[cfg(feature = "extensions")]
+    #[test]
+    fn validate_type_extensions() {
+        use super::{validate, ValidateError, ValidateOptions};
+        use crate::{FromSerdeSchemaError, Schema, Type, TypeExtensions};
+        use serde_json::json;
+
+        let types = TypeExtensions::new()
+            .with_type("date", |value| {
+                value.as_str().is_some_and(|s| s.len() == 10)
+            })
+            .with_type("bytes", |value| value.is_array());
+
+        let value = json!({ "values": { "type": "date", "nullable": true }});
+        assert_eq!(
+            Err(FromSerdeSchemaError::InvalidType("date".to_owned())),
+            Schema::from_serde_schema(serde_json::from_value(value.clone()).unwrap())
+        );
+
+        let date = Schema::from_serde_schema_with_extensions(
+            serde_json::from_value(value.clone()).unwrap(),
+            &types,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            &date,
+            Schema::Values { values, .. } if matches!(
+                &**values,
+                Schema::Type { type_: Type::Custom(name), .. } if name == "date"
+            )
+        ));
+        assert_eq!(
+            value,
+            serde_json::to_value(date.clone().into_serde_schema()).unwrap()
+        );
+
+        let instance = json!({ "a": "2020-01-01", "b": null, "c": "2020", "d": 1 });
+        let errors = validate(
+            &date,
+            &instance,
+            ValidateOptions::new().with_type_extensions(types.clone()),
+        )
+        .unwrap();
+
+        let paths: Vec<_> = errors.into_iter().map(|e| e.instance_path).collect();
+        assert_eq!(vec![vec!["c"], vec!["d"]], paths);
+
+        assert_eq!(
+            Err(ValidateError::UnknownType("date".to_owned())),
+            validate(&date, &instance, ValidateOptions::new())
+        );
+
+        assert_eq!(types, types.clone());
+        assert_ne!(types, TypeExtensions::new().with_type("date", |_| true));
+        assert_eq!(r#"{"bytes", "date"}"#, format!("{:?}", types));
+    }
+
+    #[test]
+    fn severity() {
+        use super::{validate, Severity, ValidateOptions};
+        use serde_json::json;
+
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "tag": {
+                        "type": "string",
+                        "metadata": { "severityOnViolation": "info" }
+                    }
+                },
+                "properties": {
+                    "id": { "type": "uint8" },
+                    "address": {
+                        "metadata": { "severityOnViolation": "warning" },
+                        "properties": {
+                            "city": { "type": "string" },
+                            "zip": {
+                                "type": "string",
+                                "metadata": { "severityOnViolation": "error" }
+                            }
+                        }
+                    },
+                    "tags": { "elements": { "ref": "tag" }}
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let instance = json!({ "id": 300, "address": { "zip": 1 }, "tags": [1] });
+        let errors = |options: ValidateOptions| -> Vec<_> {
+            validate(&schema, &instance, options)
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.instance_path.join("/"), e.severity))
+                .collect()
+        };
+
+        assert_eq!(
+            vec![
+                ("address/zip".to_owned(), Severity::Error),
+                ("id".to_owned(), Severity::Error),
+            ],
+            errors(ValidateOptions::new())
+        );
+
+        assert_eq!(
+            vec![
+                ("address".to_owned(), Severity::Warning),
+                ("address/zip".to_owned(), Severity::Error),
+                ("id".to_owned(), Severity::Error),
+                ("tags/0".to_owned(), Severity::Info),
+            ],
+            errors(ValidateOptions::new().with_min_severity(Severity::Info))
+        );
+
+        // Errors that aren't reported don't count towards the maximum.
+        assert_eq!(
+            vec![("address/zip".to_owned(), Severity::Error)],
+            errors(ValidateOptions::new().with_max_errors(1))
+        );
+
+        assert!(Severity::Info < Severity::Warning && Severity::Warning < Severity::Error);
+    }
+}
@@ -0,0 +1,421 @@
+use crate::Schema;
+
+/// A visitor of the schemas within a schema, for [`Schema::walk`].
+///
+/// Closures taking a schema path and a schema are visitors, so most callers
+/// don't need to implement this trait themselves.
+pub trait SchemaVisitor<'a> {
+    /// Visits a schema, given its path from the schema being walked.
+    ///
+    /// The path is made up of keywords and names, as with
+    /// [`Schema::get_at_path`]: `["properties", "name", "elements"]` is the
+    /// schema of the elements of the `name` property.
+    fn visit_schema(&mut self, path: &[&'a str], schema: &'a Schema);
+}
+
+impl<'a, F: FnMut(&[&'a str], &'a Schema)> SchemaVisitor<'a> for F {
+    fn visit_schema(&mut self, path: &[&'a str], schema: &'a Schema) {
+        self(path, schema)
+    }
+}
+
+/// A visitor of the schemas within a schema that may change them, for
+/// [`Schema::walk_mut`].
+///
+/// Closures taking a schema path and a schema are visitors, so most callers
+/// don't need to implement this trait themselves.
+pub trait SchemaVisitorMut {
+    /// Visits a schema, given its path from the schema being walked.
+    ///
+    /// The schema's sub-schemas are visited after it, as they are once this
+    /// returns, so changes made to them here are seen when they're visited.
+    fn visit_schema_mut(&mut self, path: &[String], schema: &mut Schema);
+}
+
+impl<F: FnMut(&[String], &mut Schema)> SchemaVisitorMut for F {
+    fn visit_schema_mut(&mut self, path: &[String], schema: &mut Schema) {
+        self(path, schema)
+    }
+}
+
+impl Schema {
+    /// Visits this schema, and every schema within it, with their schema
+    /// paths.
+    ///
+    /// Each schema is visited before the schemas within it, which are visited
+    /// in the order of their keywords: `definitions` first, then those of the
+    /// schema's form. Refs aren't followed, but the definitions they refer to
+    /// are visited as part of the root's `definitions`.
+    ///
+    /// This saves tools that look at every part of a schema, such as linters
+    /// and documentation generators, from each going through the eight forms
+    /// of schema themselves. It doesn't recurse, so it can't overflow the
+    /// stack on deeply nested schemas.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "definitions": {
+    ///         "id": { "type": "string", "metadata": { "description": "An ID" }}
+    ///     },
+    ///     "properties": {
+    ///         "owner": { "ref": "id" },
+    ///         "tags": { "elements": { "type": "string" }}
+    ///     }
+    /// })).unwrap()).unwrap();
+    ///
+    /// let mut paths = Vec::new();
+    /// schema.walk(&mut |path: &[&str], _: &Schema| paths.push(path.join("/")));
+    /// assert_eq!(
+    ///     vec![
+    ///         "",
+    ///         "definitions/id",
+    ///         "properties/owner",
+    ///         "properties/tags",
+    ///         "properties/tags/elements",
+    ///     ],
+    ///     paths,
+    /// );
+    ///
+    /// // Find the definitions without a description.
+    /// let mut undescribed = Vec::new();
+    /// schema.walk(&mut |path: &[&str], schema: &Schema| {
+    ///     if path.len() == 2 && path[0] == "definitions"
+    ///         && !schema.metadata().contains_key("description")
+    ///     {
+    ///         undescribed.push(path[1].to_owned());
+    ///     }
+    /// });
+    /// assert!(undescribed.is_empty());
+    /// ```
+    pub fn walk<'a>(&'a self, visitor: &mut impl SchemaVisitor<'a>) {
+        let mut stack = vec![(Vec::new(), self)];
+        while let Some((path, schema)) = stack.pop() {
+            visitor.visit_schema(&path, schema);
+
+            // Sub-schemas are pushed in reverse, so that they're popped, and
+            // visited, in order.
+            stack.extend(
+                sub_schemas(schema)
+                    .into_iter()
+                    .rev()
+                    .map(|(tokens, sub_schema)| {
+                        let mut sub_path = path.clone();
+                        sub_path.extend(tokens);
+                        (sub_path, sub_schema)
+                    }),
+            );
+        }
+    }
+
+    /// Visits this schema, and every schema within it, with their schema
+    /// paths, allowing the visitor to change them.
+    ///
+    /// Schemas are visited in the same order as by [`Schema::walk`]. Each one
+    /// is visited before the schemas within it, so a visitor that replaces a
+    /// schema goes on to visit what it was replaced with.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let mut schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "properties": {
+    ///         "name": { "type": "string" },
+    ///         "tags": { "elements": { "type": "string" }}
+    ///     }
+    /// })).unwrap()).unwrap();
+    ///
+    /// // Document where each schema is, for a documentation generator.
+    /// schema.walk_mut(&mut |path: &[String], schema: &mut Schema| {
+    ///     schema.metadata_mut().insert("path".into(), path.join(".").into());
+    /// });
+    ///
+    /// assert_eq!(
+    ///     Some(&json!("properties.tags.elements")),
+    ///     schema.get_at_path(&["properties", "tags", "elements"]).unwrap().metadata().get("path"),
+    /// );
+    /// ```
+    pub fn walk_mut(&mut self, visitor: &mut impl SchemaVisitorMut) {
+        let mut stack = vec![(Vec::new(), self)];
+        while let Some((path, schema)) = stack.pop() {
+            visitor.visit_schema_mut(&path, schema);
+
+            stack.extend(
+                sub_schemas_mut(schema)
+                    .into_iter()
+                    .rev()
+                    .map(|(tokens, sub_schema)| {
+                        let mut sub_path = path.clone();
+                        sub_path.extend(tokens.into_iter().map(str::to_owned));
+                        (sub_path, sub_schema)
+                    }),
+            );
+        }
+    }
+
+    /// Rebuilds the schema, passing each schema within it through `f`, from
+    /// the bottom up.
+    ///
+    /// The schemas within a schema, including its definitions, are passed
+    /// through `f` before it is, so when `f` is given a schema, the schemas
+    /// within it have already been rebuilt. What `f` returns takes the
+    /// schema's place. This suits changes that apply to every schema alike,
+    /// such as making every schema nullable, stripping metadata, or renaming
+    /// refs when bundling schemas together.
+    ///
+    /// ```
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "definitions": {
+    ///         "user": { "properties": { "name": { "type": "string" }}}
+    ///     },
+    ///     "elements": { "ref": "user" },
+    ///     "metadata": { "description": "Users" }
+    /// })).unwrap()).unwrap();
+    ///
+    /// // Prefix the definitions, so they don't clash with those of another
+    /// // schema, and strip metadata.
+    /// let schema = schema.map_schemas(|mut schema| {
+    ///     if let Schema::Ref { ref_, .. } = &mut schema {
+    ///         *ref_ = format!("users.{}", ref_);
+    ///     }
+    ///
+    ///     let definitions = std::mem::take(schema.definitions_mut());
+    ///     *schema.definitions_mut() = definitions
+    ///         .into_iter()
+    ///         .map(|(name, definition)| (format!("users.{}", name), definition))
+    ///         .collect();
+    ///
+    ///     schema.metadata_mut().clear();
+    ///     schema
+    /// });
+    ///
+    /// assert_eq!(
+    ///     json!({
+    ///         "definitions": {
+    ///             "users.user": { "properties": { "name": { "type": "string" }}}
+    ///         },
+    ///         "elements": { "ref": "users.user" }
+    ///     }),
+    ///     serde_json::to_value(schema.into_serde_schema()).unwrap(),
+    /// );
+    /// ```
+    pub fn map_schemas(self, mut f: impl FnMut(Schema) -> Schema) -> Schema {
+        map_schemas(self, &mut f)
+    }
+}
+
+fn map_schemas(mut schema: Schema, f: &mut dyn FnMut(Schema) -> Schema) -> Schema {
+    for (_, sub_schema) in sub_schemas_mut(&mut schema) {
+        let placeholder = Schema::Empty {
+            definitions: Default::default(),
+            metadata: Default::default(),
+        };
+
+        let taken = std::mem::replace(sub_schema, placeholder);
+        *sub_schema = map_schemas(taken, f);
+    }
+
+    f(schema)
+}
+
+// The schemas directly within a schema, with the tokens of the schema path to
+// each from it: definitions first, then those of the schema's form.
+fn sub_schemas(schema: &Schema) -> Vec<(Vec<&str>, &Schema)> {
+    let mut sub_schemas: Vec<_> = schema
+        .definitions()
+        .iter()
+        .map(|(name, sub_schema)| (vec!["definitions", name.as_str()], sub_schema))
+        .collect();
+
+    match schema {
+        Schema::Elements { elements, .. } => sub_schemas.push((vec!["elements"], elements)),
+        Schema::Properties {
+            properties,
+            optional_properties,
+            ..
+        } => {
+            for (name, sub_schema) in properties {
+                sub_schemas.push((vec!["properties", name.as_str()], sub_schema));
+            }
+
+            for (name, sub_schema) in optional_properties {
+                sub_schemas.push((vec!["optionalProperties", name.as_str()], sub_schema));
+            }
+        }
+        Schema::Values { values, .. } => sub_schemas.push((vec!["values"], values)),
+        Schema::Discriminator { mapping, .. } => {
+            for (tag, sub_schema) in mapping {
+                sub_schemas.push((vec!["mapping", tag.as_str()], sub_schema));
+            }
+        }
+        _ => {}
+    }
+
+    sub_schemas
+}
+
+// Like sub_schemas, but mutable. The definitions share each variant with the
+// rest of the schema, so they're borrowed alongside it, variant by variant.
+fn sub_schemas_mut(schema: &mut Schema) -> Vec<(Vec<&str>, &mut Schema)> {
+    let (definitions, rest) = match schema {
+        #[cfg(feature = "extensions")]
+        Schema::IntEnum { definitions, .. } => (definitions, vec![]),
+        Schema::Empty { definitions, .. }
+        | Schema::Ref { definitions, .. }
+        | Schema::Type { definitions, .. }
+        | Schema::Enum { definitions, .. } => (definitions, vec![]),
+        Schema::Elements {
+            definitions,
+            elements,
+            ..
+        } => (definitions, vec![(vec!["elements"], &mut **elements)]),
+        Schema::Properties {
+            definitions,
+            properties,
+            optional_properties,
+            ..
+        } => {
+            let properties = properties
+                .iter_mut()
+                .map(|(name, sub_schema)| (vec!["properties", name.as_str()], sub_schema));
+            let optional_properties = optional_properties
+                .iter_mut()
+                .map(|(name, sub_schema)| (vec!["optionalProperties", name.as_str()], sub_schema));
+
+            (definitions, properties.chain(optional_properties).collect())
+        }
+        Schema::Values {
+            definitions,
+            values,
+            ..
+        } => (definitions, vec![(vec!["values"], &mut **values)]),
+        Schema::Discriminator {
+            definitions,
+            mapping,
+            ..
+        } => (
+            definitions,
+            mapping
+                .iter_mut()
+                .map(|(tag, sub_schema)| (vec!["mapping", tag.as_str()], sub_schema))
+                .collect(),
+        ),
+    };
+
+    definitions
+        .iter_mut()
+        .map(|(name, sub_schema)| (vec!["definitions", name.as_str()], sub_schema))
+        .chain(rest)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn walks_every_form() {
+        let mut schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "a": { "values": { "enum": ["x"] }},
+                    "b": { "ref": "a" }
+                },
+                "discriminator": "type",
+                "mapping": {
+                    "p": {
+                        "properties": { "q": { "elements": {} }},
+                        "optionalProperties": { "r": { "type": "int8" }}
+                    },
+                    "s": { "properties": {} }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let expected = vec![
+            "",
+            "definitions/a",
+            "definitions/a/values",
+            "definitions/b",
+            "mapping/p",
+            "mapping/p/properties/q",
+            "mapping/p/properties/q/elements",
+            "mapping/p/optionalProperties/r",
+            "mapping/s",
+        ];
+
+        let mut paths = Vec::new();
+        schema.walk(&mut |path: &[&str], sub_schema: &Schema| {
+            assert_eq!(Some(sub_schema), schema.get_at_path(path));
+            paths.push(path.join("/"));
+        });
+        assert_eq!(expected, paths);
+
+        // Schemas replaced by the visitor have what they were replaced with
+        // visited instead.
+        let mut paths = Vec::new();
+        schema.walk_mut(&mut |path: &[String], sub_schema: &mut Schema| {
+            if path.len() == 2 && path[1] == "s" {
+                *sub_schema = Schema::from_serde_schema(
+                    serde_json::from_value(json!({ "elements": {} })).unwrap(),
+                )
+                .unwrap();
+            }
+
+            paths.push(path.join("/"));
+        });
+
+        let mut expected = expected;
+        expected.push("mapping/s/elements");
+        assert_eq!(expected, paths);
+    }
+
+    #[test]
+    fn maps_bottom_up() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": { "a": { "type": "string" }},
+                "properties": {
+                    "b": { "elements": { "ref": "a" }},
+                    "c": { "values": {} }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        // Number the schemas in the order they're mapped.
+        let mut count = 0;
+        let schema = schema.map_schemas(|mut schema| {
+            count += 1;
+            schema.metadata_mut().insert("n".into(), count.into());
+            schema
+        });
+
+        let mut order = Vec::new();
+        schema.walk(&mut |path: &[&str], schema: &Schema| {
+            order.push((path.join("/"), schema.metadata()["n"].clone()));
+        });
+
+        assert_eq!(
+            vec![
+                ("".to_owned(), json!(6)),
+                ("definitions/a".to_owned(), json!(1)),
+                ("properties/b".to_owned(), json!(3)),
+                ("properties/b/elements".to_owned(), json!(2)),
+                ("properties/c".to_owned(), json!(5)),
+                ("properties/c/values".to_owned(), json!(4)),
+            ],
+            order
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use jtd_core::Schema;
+use std::collections::BTreeSet;
+use syn::spanned::Spanned;
+use syn::{Attribute, Fields, ItemStruct, LitStr, Token, Type};
+
+// The serde attributes this module, and the derive module, care about.
+#[derive(Default)]
+pub(crate) struct SerdeAttrs {
+    pub(crate) rename: Option<String>,
+    pub(crate) rename_all: Option<String>,
+    pub(crate) default: bool,
+    pub(crate) skip: bool,
+    pub(crate) flatten: bool,
+    pub(crate) skip_serializing_if: bool,
+    pub(crate) tag: Option<String>,
+    pub(crate) content: bool,
+    pub(crate) untagged: bool,
+    pub(crate) transparent: bool,
+}
+
+// Checks that the named fields of item line up with the properties of schema,
+// returning an error for each discrepancy.
+pub fn check(item: &ItemStruct, path: &LitStr, schema: &Schema) -> Vec<syn::Error> {
+    let mut errors = vec![];
+
+    let target = match schema {
+        Schema::Ref { ref_, .. } => schema.definitions().get(ref_),
+        _ => Some(schema),
+    };
+
+    let (properties, optional_properties, additional_properties) = match target {
+        Some(Schema::Properties {
+            properties,
+            optional_properties,
+            additional_properties,
+            ..
+        }) => (properties, optional_properties, *additional_properties),
+        _ => {
+            errors.push(syn::Error::new(
+                path.span(),
+                "schema is not of the properties form",
+            ));
+            return errors;
+        }
+    };
+
+    let container = match parse_serde_attrs(&item.attrs) {
+        Ok(attrs) => attrs,
+        Err(err) => return vec![err],
+    };
+
+    let named = match &item.fields {
+        Fields::Named(named) => named,
+        _ => {
+            errors.push(syn::Error::new(
+                item.span(),
+                "jtd::conforms only supports structs with named fields",
+            ));
+            return errors;
+        }
+    };
+
+    let mut fields = BTreeSet::new();
+    let mut has_flatten = false;
+    for field in &named.named {
+        let attrs = match parse_serde_attrs(&field.attrs) {
+            Ok(attrs) => attrs,
+            Err(err) => {
+                errors.push(err);
+                continue;
+            }
+        };
+
+        if attrs.skip {
+            continue;
+        }
+
+        if attrs.flatten {
+            has_flatten = true;
+            continue;
+        }
+
+        let ident = field.ident.as_ref().unwrap().to_string();
+        let ident = ident.trim_start_matches("r#");
+        let json_name = match (&attrs.rename, &container.rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) => apply_rename_rule(rule, ident),
+            (None, None) => ident.to_owned(),
+        };
+
+        let optional = attrs.default || container.default || is_option(&field.ty);
+
+        if let Some(sub_schema) = properties.get(&json_name) {
+            if is_option(&field.ty) && !sub_schema.nullable() {
+                errors.push(syn::Error::new(
+                    field.span(),
+                    format!(
+                        "field {:?} is an Option, but the schema requires it and does not make it nullable",
+                        json_name
+                    ),
+                ));
+            }
+        } else if optional_properties.contains_key(&json_name) {
+            if !optional {
+                errors.push(syn::Error::new(
+                    field.span(),
+                    format!(
+                        "property {:?} is optional in the schema, but the field is neither an Option nor #[serde(default)]",
+                        json_name
+                    ),
+                ));
+            }
+        } else if !additional_properties {
+            errors.push(syn::Error::new(
+                field.span(),
+                format!("field {:?} is not a property in the schema", json_name),
+            ));
+        }
+
+        fields.insert(json_name);
+    }
+
+    // A flattened field may account for any property, so missing properties
+    // can't be reported with confidence.
+    if !has_flatten {
+        for name in properties.keys().chain(optional_properties.keys()) {
+            if !fields.contains(name) {
+                errors.push(syn::Error::new(
+                    item.ident.span(),
+                    format!("schema property {:?} has no corresponding field", name),
+                ));
+            }
+        }
+    }
+
+    errors
+}
+
+pub(crate) fn parse_serde_attrs(attrs: &[Attribute]) -> Result<SerdeAttrs, syn::Error> {
+    let mut out = SerdeAttrs::default();
+
+    for attr in attrs {
+        if !attr.path().is_ident("serde") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                out.rename = Some(parse_rename(&meta)?);
+            } else if meta.path.is_ident("rename_all") {
+                out.rename_all = Some(parse_rename(&meta)?);
+            } else if meta.path.is_ident("default") {
+                out.default = true;
+                skip_meta(&meta)?;
+            } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                out.skip = true;
+            } else if meta.path.is_ident("flatten") {
+                out.flatten = true;
+            } else if meta.path.is_ident("tag") {
+                out.tag = Some(parse_rename(&meta)?);
+            } else if meta.path.is_ident("content") {
+                out.content = true;
+                skip_meta(&meta)?;
+            } else if meta.path.is_ident("untagged") {
+                out.untagged = true;
+            } else if meta.path.is_ident("transparent") {
+                out.transparent = true;
+            } else if meta.path.is_ident("skip_serializing_if") {
+                out.skip_serializing_if = true;
+                skip_meta(&meta)?;
+            } else {
+                skip_meta(&meta)?;
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(out)
+}
+
+// Parses the argument to rename or rename_all, which may either be a string or
+// a list of serialize and deserialize names. In the latter case, the
+// deserialize name is the one that matters.
+fn parse_rename(meta: &syn::meta::ParseNestedMeta) -> Result<String, syn::Error> {
+    if meta.input.peek(Token![=]) {
+        let value: LitStr = meta.value()?.parse()?;
+        return Ok(value.value());
+    }
+
+    let mut name = None;
+    meta.parse_nested_meta(|inner| {
+        let value: LitStr = inner.value()?.parse()?;
+        if inner.path.is_ident("deserialize") || name.is_none() {
+            name = Some(value.value());
+        }
+
+        Ok(())
+    })?;
+
+    name.ok_or_else(|| meta.error("expected a name"))
+}
+
+fn skip_meta(meta: &syn::meta::ParseNestedMeta) -> Result<(), syn::Error> {
+    if meta.input.peek(Token![=]) {
+        let _: syn::Expr = meta.value()?.parse()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let _content;
+        syn::parenthesized!(_content in meta.input);
+    }
+
+    Ok(())
+}
+
+fn is_option(ty: &Type) -> bool {
+    if let Type::Path(path) = ty {
+        if let Some(segment) = path.path.segments.last() {
+            return segment.ident == "Option";
+        }
+    }
+
+    false
+}
+
+// Applies a serde rename_all rule to a snake_case field name.
+pub(crate) fn apply_rename_rule(rule: &str, name: &str) -> String {
+    let words: Vec<&str> = name.split('_').filter(|w| !w.is_empty()).collect();
+    let capitalize = |w: &str| {
+        let mut chars = w.chars();
+        match chars.next() {
+            Some(c) => c.to_uppercase().chain(chars).collect::<String>(),
+            None => String::new(),
+        }
+    };
+
+    match rule {
+        "lowercase" => name.to_lowercase(),
+        "UPPERCASE" => name.to_uppercase(),
+        "PascalCase" => words.iter().map(|w| capitalize(w)).collect(),
+        "camelCase" => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| {
+                if i == 0 {
+                    (*w).to_owned()
+                } else {
+                    capitalize(w)
+                }
+            })
+            .collect(),
+        "SCREAMING_SNAKE_CASE" => name.to_uppercase(),
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.replace('_', "-").to_uppercase(),
+        _ => name.to_owned(),
+    }
+}
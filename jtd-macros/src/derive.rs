@@ -0,0 +1,200 @@
+use crate::conforms::{apply_rename_rule, parse_serde_attrs, SerdeAttrs};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DataEnum, DeriveInput, Fields, FieldsNamed, GenericParam};
+
+// Generates an implementation of jtd::JtdSchema for input.
+pub fn derive(input: &DeriveInput) -> Result<TokenStream, syn::Error> {
+    let container = parse_serde_attrs(&input.attrs)?;
+
+    let body = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) if container.transparent => {
+                let field = named
+                    .named
+                    .iter()
+                    .find(|field| !parse_serde_attrs(&field.attrs).is_ok_and(|attrs| attrs.skip))
+                    .ok_or_else(|| syn::Error::new(input.span(), "struct has no fields"))?;
+
+                let ty = &field.ty;
+                quote! { <#ty as ::jtd::JtdSchema>::jtd_schema() }
+            }
+            Fields::Named(named) => {
+                properties(named, container.rename_all.as_deref(), container.default)?
+            }
+            Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+                let ty = &unnamed.unnamed[0].ty;
+                quote! { <#ty as ::jtd::JtdSchema>::jtd_schema() }
+            }
+            _ => return Err(syn::Error::new(
+                input.span(),
+                "JtdSchema can only be derived for structs with named fields, and newtype structs",
+            )),
+        },
+        Data::Enum(data) => enum_schema(input, data, &container)?,
+        Data::Union(_) => {
+            return Err(syn::Error::new(
+                input.span(),
+                "JtdSchema can't be derived for unions",
+            ))
+        }
+    };
+
+    let mut generics = input.generics.clone();
+    for param in &mut generics.params {
+        if let GenericParam::Type(param) = param {
+            param.bounds.push(syn::parse_quote!(::jtd::JtdSchema));
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::jtd::JtdSchema for #ident #ty_generics #where_clause {
+            fn jtd_schema() -> ::jtd::Schema {
+                #body
+            }
+        }
+    })
+}
+
+// Generates a properties-form schema for named fields.
+fn properties(
+    named: &FieldsNamed,
+    rename_all: Option<&str>,
+    default: bool,
+) -> Result<TokenStream, syn::Error> {
+    let mut properties = vec![];
+    let mut optional_properties = vec![];
+
+    for field in &named.named {
+        let attrs = parse_serde_attrs(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+
+        if attrs.flatten {
+            return Err(syn::Error::new(
+                field.span(),
+                "JtdSchema doesn't support #[serde(flatten)]",
+            ));
+        }
+
+        let ident = field.ident.as_ref().unwrap().to_string();
+        let ident = ident.trim_start_matches("r#");
+        let json_name = match (&attrs.rename, rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) => apply_rename_rule(rule, ident),
+            (None, None) => ident.to_owned(),
+        };
+
+        let ty = &field.ty;
+        let entry = quote! { (#json_name, <#ty as ::jtd::JtdSchema>::jtd_schema()) };
+
+        if attrs.default || default || attrs.skip_serializing_if {
+            optional_properties.push(entry);
+        } else {
+            properties.push(entry);
+        }
+    }
+
+    Ok(quote! {
+        ::jtd::__private::properties(
+            vec![#(#properties),*],
+            vec![#(#optional_properties),*],
+        )
+    })
+}
+
+// Generates an enum-form schema for an enum of unit variants, or a
+// discriminator-form schema for an internally tagged enum.
+fn enum_schema(
+    input: &DeriveInput,
+    data: &DataEnum,
+    container: &SerdeAttrs,
+) -> Result<TokenStream, syn::Error> {
+    if container.untagged || container.content {
+        return Err(syn::Error::new(
+            input.span(),
+            "JtdSchema only supports enums of unit variants, and internally tagged enums",
+        ));
+    }
+
+    let mut values = vec![];
+    let mut mapping = vec![];
+
+    for variant in &data.variants {
+        let attrs = parse_serde_attrs(&variant.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+
+        let name = match (&attrs.rename, &container.rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) => apply_variant_rename_rule(rule, &variant.ident.to_string()),
+            (None, None) => variant.ident.to_string(),
+        };
+
+        if container.tag.is_none() {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    "JtdSchema only supports unit variants, unless the enum has #[serde(tag = \"...\")]",
+                ));
+            }
+
+            values.push(name);
+            continue;
+        }
+
+        let schema = match &variant.fields {
+            Fields::Unit => quote! { ::jtd::__private::properties(vec![], vec![]) },
+            Fields::Named(named) => properties(named, attrs.rename_all.as_deref(), false)?,
+            Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+                let ty = &unnamed.unnamed[0].ty;
+                quote! { <#ty as ::jtd::JtdSchema>::jtd_schema() }
+            }
+            Fields::Unnamed(_) => {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    "internally tagged enums can't have tuple variants",
+                ))
+            }
+        };
+
+        mapping.push(quote! { (#name, #schema) });
+    }
+
+    Ok(match &container.tag {
+        Some(tag) => quote! { ::jtd::__private::discriminator(#tag, vec![#(#mapping),*]) },
+        None => quote! { ::jtd::__private::enum_(&[#(#values),*]) },
+    })
+}
+
+// Applies a serde rename_all rule to a PascalCase variant name.
+fn apply_variant_rename_rule(rule: &str, name: &str) -> String {
+    let mut snake_case = String::new();
+    for (i, c) in name.char_indices() {
+        if i > 0 && c.is_uppercase() {
+            snake_case.push('_');
+        }
+
+        snake_case.extend(c.to_lowercase());
+    }
+
+    match rule {
+        "lowercase" => name.to_lowercase(),
+        "UPPERCASE" => name.to_uppercase(),
+        "PascalCase" => name.to_owned(),
+        "camelCase" => {
+            let mut chars = name.chars();
+            match chars.next() {
+                Some(c) => c.to_lowercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+        "snake_case" => snake_case,
+        _ => apply_rename_rule(rule, &snake_case),
+    }
+}
@@ -0,0 +1,268 @@
+//! Procedural macros for [`jtd`](https://docs.rs/jtd).
+//!
+//! You shouldn't need to depend on this crate directly. Instead, enable the
+//! `macros` feature of `jtd`, which re-exports the macros defined here.
+
+extern crate proc_macro;
+
+mod conforms;
+mod derive;
+
+use jtd_core::{InstancePath, Schema, SchemaPath, SerdeSchema};
+use proc_macro::TokenStream;
+use quote::quote;
+use std::path::PathBuf;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, DeriveInput, ItemStruct, LitStr, Token};
+
+/// Embeds a JSON Typedef schema file into the program, checking it at build
+/// time.
+///
+/// The path is relative to the directory containing the calling crate's
+/// `Cargo.toml`. The file is parsed, converted into a `jtd::Schema`, and
+/// checked with `Schema::validate` while the calling crate is being compiled;
+/// any problem with the schema is reported as a compilation error.
+///
+/// The macro evaluates to a `&'static jtd::Schema`. The schema is constructed
+/// the first time the expression is evaluated, and reused afterwards.
+///
+/// ```ignore
+/// let schema: &'static jtd::Schema = jtd::include_schema!("schemas/user.jtd.json");
+/// ```
+#[proc_macro]
+pub fn include_schema(input: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+
+    let path = match load_schema(&path) {
+        Ok((path, _)) => path,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    // include_str! is used, rather than embedding the file's contents
+    // directly, so that Cargo knows to rebuild when the schema file changes.
+    (quote! {{
+        static SCHEMA: ::std::sync::OnceLock<::jtd::Schema> = ::std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| ::jtd::__private::schema_from_str(include_str!(#path)))
+    }})
+    .into()
+}
+
+/// Embeds a JSON fixture into the program, checking at build time that it's
+/// valid against a JSON Typedef schema.
+///
+/// Both paths are resolved the same way as with `include_schema!`. The schema
+/// is checked as with `include_schema!`, and the fixture is validated against
+/// it with default options. Each validation error is reported as a compilation
+/// error, so that test fixtures can't drift away from the schema they're
+/// meant to follow.
+///
+/// The macro evaluates to the fixture, as a `serde_json::Value`. A new value
+/// is parsed each time the expression is evaluated.
+///
+/// Custom types, from the `extensions` feature, can't be checked at build
+/// time, so a schema using them is reported as a compilation error.
+///
+/// ```ignore
+/// let user: serde_json::Value =
+///     jtd::fixture!("schemas/user.jtd.json", "tests/fixtures/user_ok.json");
+/// ```
+#[proc_macro]
+pub fn fixture(input: TokenStream) -> TokenStream {
+    let FixtureInput { schema, fixture } = parse_macro_input!(input as FixtureInput);
+
+    let (schema_path, fixture_path) = match check_fixture(&schema, &fixture) {
+        Ok(paths) => paths,
+        Err(err) => {
+            // There may be several errors, which can only be reported together
+            // as statements.
+            let err = err.to_compile_error();
+            return (quote! {{
+                #err
+                ::jtd::__private::value_from_str("null")
+            }})
+            .into();
+        }
+    };
+
+    // As with include_schema!, the include_str! calls make Cargo rebuild when
+    // either file changes.
+    (quote! {{
+        const _: &str = include_str!(#schema_path);
+        ::jtd::__private::value_from_str(include_str!(#fixture_path))
+    }})
+    .into()
+}
+
+/// Checks at build time that a struct lines up with a JSON Typedef schema.
+///
+/// The schema file path is resolved the same way as with `include_schema!`.
+/// The schema must be of the properties form, or be a `ref` to a definition of
+/// the properties form.
+///
+/// The struct's fields are compared against the schema's properties, taking
+/// into account the `rename`, `rename_all`, `default`, `skip`, and `flatten`
+/// serde attributes. Compilation fails if:
+///
+/// * A property in the schema has no corresponding field,
+/// * A field has no corresponding property, and the schema does not permit
+///   additional properties,
+/// * A property is optional, but the field is neither an `Option` nor marked
+///   `#[serde(default)]`, or
+/// * A property is required and not nullable, but the field is an `Option`.
+///   Whether `None` is serialized as `null` or, with `skip_serializing_if`,
+///   left out, the schema rejects it.
+///
+/// The types of fields are not checked. Place this attribute above any
+/// `#[derive]` attributes, so that it can see the serde attributes on the
+/// struct.
+///
+/// ```ignore
+/// #[jtd::conforms("schemas/user.jtd.json")]
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// #[serde(rename_all = "camelCase")]
+/// struct User {
+///     name: String,
+///     favorite_color: Option<String>,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn conforms(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let path = parse_macro_input!(attr as LitStr);
+    let item_tokens = proc_macro2::TokenStream::from(item.clone());
+    let item = parse_macro_input!(item as ItemStruct);
+
+    let (abs_path, errors) = match load_schema(&path) {
+        Ok((abs_path, schema)) => (Some(abs_path), conforms::check(&item, &path, &schema)),
+        Err(err) => (None, vec![err]),
+    };
+
+    // As with include_schema!, the include_str! here makes Cargo rebuild when
+    // the schema file changes.
+    let abs_path = abs_path.into_iter();
+    let errors = errors.into_iter().map(|e| e.to_compile_error());
+    (quote! {
+        #(const _: &str = include_str!(#abs_path);)*
+        #(#errors)*
+        #item_tokens
+    })
+    .into()
+}
+
+/// Derives `jtd::JtdSchema`, generating a schema for a struct or enum from its
+/// definition.
+///
+/// Structs with named fields get the properties form, and newtype structs the
+/// schema of the type they wrap. Enums of unit variants get the enum form,
+/// and enums with `#[serde(tag = "...")]` the discriminator form. The serde
+/// attributes `rename`, `rename_all`, `tag`, `transparent`, `skip`,
+/// `default`, and `skip_serializing_if` are taken into account, so that the
+/// schema describes what serde reads and writes. A field is an optional
+/// property if it's `#[serde(default)]`, or has `skip_serializing_if`.
+///
+/// Anything serde would represent in a way JSON Typedef can't describe, such
+/// as `#[serde(flatten)]` or an untagged enum, is reported as a compilation
+/// error.
+///
+/// ```ignore
+/// #[derive(serde::Serialize, serde::Deserialize, jtd::JtdSchema)]
+/// #[serde(tag = "type", rename_all = "snake_case")]
+/// enum Event {
+///     PageView { url: String },
+///     SignUp { user_id: u32 },
+/// }
+///
+/// let schema: jtd::Schema = <Event as jtd::JtdSchema>::jtd_schema();
+/// ```
+#[proc_macro_derive(JtdSchema, attributes(serde))]
+pub fn derive_jtd_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    derive::derive(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+// Reads, parses, and validates the schema at path, returning the absolute path
+// to the schema and the schema itself.
+fn load_schema(path: &LitStr) -> Result<(String, Schema), syn::Error> {
+    let abs_path = resolve_path(path)?;
+    let err = |msg: String| syn::Error::new(path.span(), msg);
+
+    let data = std::fs::read_to_string(&abs_path)
+        .map_err(|e| err(format!("failed to read {}: {}", abs_path, e)))?;
+
+    let serde_schema: SerdeSchema = serde_json::from_str(&data)
+        .map_err(|e| err(format!("failed to parse {}: {}", abs_path, e)))?;
+
+    let schema = Schema::from_serde_schema(serde_schema)
+        .map_err(|e| err(format!("invalid schema in {}: {}", abs_path, e)))?;
+
+    schema
+        .validate()
+        .map_err(|e| err(format!("invalid schema in {}: {}", abs_path, e)))?;
+
+    Ok((abs_path, schema))
+}
+
+// The arguments to fixture!: a schema path and a fixture path.
+struct FixtureInput {
+    schema: LitStr,
+    fixture: LitStr,
+}
+
+impl Parse for FixtureInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let schema = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let fixture = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+
+        Ok(Self { schema, fixture })
+    }
+}
+
+// Validates the fixture at fixture against the schema at schema, returning the
+// absolute paths to each.
+fn check_fixture(schema: &LitStr, fixture: &LitStr) -> Result<(String, String), syn::Error> {
+    let (schema_path, schema) = load_schema(schema)?;
+    let fixture_path = resolve_path(fixture)?;
+    let err = |msg: String| syn::Error::new(fixture.span(), msg);
+
+    let data = std::fs::read_to_string(&fixture_path)
+        .map_err(|e| err(format!("failed to read {}: {}", fixture_path, e)))?;
+
+    let instance: serde_json::Value = serde_json::from_str(&data)
+        .map_err(|e| err(format!("failed to parse {}: {}", fixture_path, e)))?;
+
+    let errors = jtd_core::validate(&schema, &instance, jtd_core::ValidateOptions::new())
+        .map_err(|e| err(format!("failed to validate {}: {}", fixture_path, e)))?;
+
+    let mut errors = errors.into_iter().map(|error| {
+        let (instance_path, schema_path) = error.into_owned_paths();
+        err(format!(
+            "{} does not conform to its schema: the value at {:?} is rejected by {:?}",
+            fixture_path,
+            InstancePath::from(instance_path).to_string(),
+            SchemaPath::from(schema_path).to_string(),
+        ))
+    });
+
+    match errors.next() {
+        Some(mut first) => {
+            first.extend(errors);
+            Err(first)
+        }
+        None => Ok((schema_path, fixture_path)),
+    }
+}
+
+fn resolve_path(path: &LitStr) -> Result<String, syn::Error> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| syn::Error::new(path.span(), "CARGO_MANIFEST_DIR is not set"))?;
+
+    let abs_path = PathBuf::from(manifest_dir).join(path.value());
+    abs_path
+        .to_str()
+        .map(|s| s.to_owned())
+        .ok_or_else(|| syn::Error::new(path.span(), "schema path is not valid UTF-8"))
+}
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[jtd::conforms("tests/schemas/user.jtd.json")]
+#[derive(Serialize, Deserialize)]
+struct User {
+    name: String,
+    age: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+}
+
+#[jtd::conforms("tests/schemas/user.jtd.json")]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct RenamedUser {
+    #[serde(rename = "name")]
+    full_name: String,
+
+    #[serde(rename = "age")]
+    years: u32,
+
+    #[serde(rename = "email", default)]
+    email_address: String,
+
+    #[serde(skip)]
+    _cache: (),
+}
+
+#[test]
+fn conforms() {
+    let schema = jtd::include_schema!("tests/schemas/user.jtd.json");
+
+    let user = User {
+        name: "a".into(),
+        age: 1,
+        email: None,
+    };
+
+    let renamed = RenamedUser {
+        full_name: "a".into(),
+        years: 1,
+        email_address: "a@example.com".into(),
+        _cache: (),
+    };
+
+    for instance in [
+        serde_json::to_value(&user).unwrap(),
+        serde_json::to_value(&renamed).unwrap(),
+    ] {
+        assert!(jtd::validate(schema, &instance, Default::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    let _: User = serde_json::from_value(json!({ "name": "a", "age": 1 })).unwrap();
+}
+
+// The ui tests are built in a project of trybuild's, in
+// target/tests/trybuild/jtd-macros, so the paths in them are relative to that.
+#[test]
+fn conforms_rejects_mismatches() {
+    trybuild::TestCases::new().compile_fail("tests/ui/conforms/*.rs");
+}
@@ -0,0 +1,109 @@
+use jtd::JtdSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, JtdSchema)]
+#[serde(rename_all = "camelCase")]
+struct User {
+    user_id: UserId,
+    display_name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    favorite_color: Option<Color>,
+
+    #[serde(default, rename = "tags")]
+    labels: BTreeMap<String, bool>,
+
+    #[serde(skip)]
+    _cache: (),
+
+    events: Vec<Event>,
+}
+
+#[derive(Serialize, Deserialize, JtdSchema)]
+struct UserId(u32);
+
+#[derive(Serialize, Deserialize, JtdSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+enum Color {
+    DarkRed,
+    Green,
+}
+
+#[derive(Serialize, Deserialize, JtdSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Event {
+    PageView {
+        url: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    SignUp {
+        referrer_id: Option<u32>,
+    },
+    Wrapped(Wrapper<i8>),
+    LogOut,
+}
+
+#[derive(Serialize, Deserialize, JtdSchema)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+#[test]
+fn derive() {
+    let schema = User::jtd_schema();
+    schema.validate().unwrap();
+
+    assert_eq!(
+        json!({
+            "properties": {
+                "userId": { "type": "uint32" },
+                "displayName": { "type": "string", "nullable": true },
+                "events": {
+                    "elements": {
+                        "discriminator": "type",
+                        "mapping": {
+                            "page-view": { "properties": { "url": { "type": "string" }}},
+                            "sign-up": {
+                                "properties": {
+                                    "referrerId": { "type": "uint32", "nullable": true }
+                                }
+                            },
+                            "wrapped": { "properties": { "inner": { "type": "int8" }}},
+                            "log-out": { "properties": {}}
+                        }
+                    }
+                }
+            },
+            "optionalProperties": {
+                "favoriteColor": { "enum": ["DARK_RED", "GREEN"], "nullable": true },
+                "tags": { "values": { "type": "boolean" }}
+            }
+        }),
+        serde_json::to_value(schema.clone().into_serde_schema()).unwrap()
+    );
+
+    let user = User {
+        user_id: UserId(1),
+        display_name: None,
+        favorite_color: Some(Color::DarkRed),
+        labels: BTreeMap::new(),
+        _cache: (),
+        events: vec![
+            Event::PageView { url: "/".into() },
+            Event::SignUp {
+                referrer_id: Some(2),
+            },
+            Event::Wrapped(Wrapper { inner: -1 }),
+            Event::LogOut,
+        ],
+    };
+
+    let instance = serde_json::to_value(&user).unwrap();
+    assert!(jtd::validate(&schema, &instance, Default::default())
+        .unwrap()
+        .is_empty());
+
+    let _: User = serde_json::from_value(instance).unwrap();
+}
@@ -0,0 +1,18 @@
+use serde_json::json;
+
+#[test]
+fn fixture() {
+    let user = jtd::fixture!("tests/schemas/user.jtd.json", "tests/fixtures/user_ok.json");
+
+    assert_eq!(
+        json!({ "name": "Ada", "age": 36, "email": "ada@example.com" }),
+        user
+    );
+}
+
+// The ui tests are built in a project of trybuild's, in
+// target/tests/trybuild/jtd-macros, so the paths in them are relative to that.
+#[test]
+fn fixture_rejects_nonconforming() {
+    trybuild::TestCases::new().compile_fail("tests/ui/fixture/*.rs");
+}
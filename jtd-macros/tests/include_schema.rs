@@ -0,0 +1,36 @@
+use serde_json::json;
+
+#[test]
+fn include_schema() {
+    let schema: &'static jtd::Schema = jtd::include_schema!("tests/schemas/user.jtd.json");
+
+    assert!(jtd::validate(
+        schema,
+        &json!({ "name": "a", "age": 1 }),
+        Default::default()
+    )
+    .unwrap()
+    .is_empty());
+
+    assert!(
+        !jtd::validate(schema, &json!({ "name": "a" }), Default::default())
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn include_schema_is_initialized_once() {
+    fn get() -> &'static jtd::Schema {
+        jtd::include_schema!("tests/schemas/user.jtd.json")
+    }
+
+    assert!(std::ptr::eq(get(), get()));
+}
+
+// The ui tests are built in a project of trybuild's, in
+// target/tests/trybuild/jtd-macros, so the paths in them are relative to that.
+#[test]
+fn include_schema_rejects_invalid() {
+    trybuild::TestCases::new().compile_fail("tests/ui/include_schema/*.rs");
+}
@@ -0,0 +1,10 @@
+#[jtd::conforms("../../../../jtd-macros/tests/schemas/user.jtd.json")]
+#[derive(serde::Deserialize)]
+struct User {
+    name: String,
+    age: u32,
+    email: Option<String>,
+    nickname: String,
+}
+
+fn main() {}
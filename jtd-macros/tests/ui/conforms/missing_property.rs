@@ -0,0 +1,8 @@
+#[jtd::conforms("../../../../jtd-macros/tests/schemas/user.jtd.json")]
+#[derive(serde::Deserialize)]
+struct User {
+    name: String,
+    email: Option<String>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[jtd::conforms("../../../../jtd-macros/tests/schemas/user.jtd.json")]
+#[derive(serde::Deserialize)]
+struct User {
+    name: String,
+    age: u32,
+    email: String,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+// Leaving out None doesn't help, as the schema requires the property.
+#[jtd::conforms("../../../../jtd-macros/tests/schemas/user.jtd.json")]
+#[derive(serde::Serialize)]
+struct User {
+    name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    age: Option<u32>,
+
+    email: Option<String>,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+fn main() {
+    let _ = jtd::fixture!(
+        "../../../../jtd-macros/tests/schemas/user.jtd.json",
+        "../../../../jtd-macros/tests/fixtures/user_bad.json"
+    );
+}
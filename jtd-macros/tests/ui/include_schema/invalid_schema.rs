@@ -0,0 +1,3 @@
+fn main() {
+    let _ = jtd::include_schema!("../../../../jtd-macros/tests/schemas/invalid.jtd.json");
+}
@@ -0,0 +1,339 @@
+use crate::{Definitions, Metadata, Schema, Severity};
+use std::collections::BTreeMap;
+
+/// Returns a copy of a schema with its names replaced by meaningless ones, so
+/// that it can be shared without revealing what it describes.
+///
+/// This is meant for reporting bugs: if a proprietary schema makes this crate
+/// misbehave, the anonymized schema will most likely make it misbehave in the
+/// same way, and can be attached to a bug report instead.
+///
+/// The names of definitions, properties, enum values, and discriminator tags
+/// are replaced, with each kind of name getting its own prefix: `d0`, `d1`,
+/// and so on for definitions, `p0` for properties, `e0` for enum values, and
+/// `t0` for tags. A name is always replaced in the same way, so names that
+/// were the same stay the same, and names that were different stay different.
+/// Discriminators are properties, so they're named like properties are.
+///
+/// Metadata is removed, except for the [`Severity::METADATA_KEY`] property,
+/// which changes how instances are validated. Everything else is kept as it
+/// is, including types, and, with the `extensions` feature, the patterns of
+/// `keys` and the values of integer enums.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "definitions": {
+///         "customer": {
+///             "properties": { "name": { "type": "string" }},
+///             "metadata": { "description": "A paying customer" }
+///         }
+///     },
+///     "discriminator": "plan",
+///     "mapping": {
+///         "enterprise": {
+///             "properties": {
+///                 "customer": { "ref": "customer" },
+///                 "tier": { "enum": ["gold", "platinum"] }
+///             }
+///         },
+///         "free": {
+///             "properties": { "name": { "type": "string" }}
+///         }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// assert_eq!(
+///     json!({
+///         "definitions": {
+///             "d0": { "properties": { "p0": { "type": "string" }}}
+///         },
+///         "discriminator": "p1",
+///         "mapping": {
+///             "t0": {
+///                 "properties": {
+///                     "p2": { "ref": "d0" },
+///                     "p3": { "enum": ["e0", "e1"] }
+///                 }
+///             },
+///             "t1": {
+///                 "properties": { "p0": { "type": "string" }}
+///             }
+///         }
+///     }),
+///     serde_json::to_value(jtd::anonymize(&schema).into_serde_schema()).unwrap(),
+/// );
+/// ```
+pub fn anonymize(schema: &Schema) -> Schema {
+    Anonymizer {
+        definitions: Names::new("d"),
+        properties: Names::new("p"),
+        enum_values: Names::new("e"),
+        tags: Names::new("t"),
+    }
+    .anonymize(schema)
+}
+
+struct Anonymizer {
+    definitions: Names,
+    properties: Names,
+    enum_values: Names,
+    tags: Names,
+}
+
+impl Anonymizer {
+    fn anonymize(&mut self, schema: &Schema) -> Schema {
+        match schema {
+            Schema::Empty {
+                definitions,
+                metadata,
+            } => Schema::Empty {
+                definitions: self.anonymize_definitions(definitions),
+                metadata: anonymize_metadata(metadata),
+            },
+            Schema::Ref {
+                definitions,
+                metadata,
+                nullable,
+                ref_,
+            } => Schema::Ref {
+                definitions: self.anonymize_definitions(definitions),
+                metadata: anonymize_metadata(metadata),
+                nullable: *nullable,
+                ref_: self.definitions.get(ref_),
+            },
+            Schema::Type {
+                definitions,
+                metadata,
+                nullable,
+                type_,
+            } => Schema::Type {
+                definitions: self.anonymize_definitions(definitions),
+                metadata: anonymize_metadata(metadata),
+                nullable: *nullable,
+                type_: type_.clone(),
+            },
+            Schema::Enum {
+                definitions,
+                metadata,
+                nullable,
+                enum_,
+            } => Schema::Enum {
+                definitions: self.anonymize_definitions(definitions),
+                metadata: anonymize_metadata(metadata),
+                nullable: *nullable,
+                enum_: enum_
+                    .iter()
+                    .map(|value| self.enum_values.get(value))
+                    .collect(),
+            },
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum {
+                definitions,
+                metadata,
+                nullable,
+                enum_,
+            } => Schema::IntEnum {
+                definitions: self.anonymize_definitions(definitions),
+                metadata: anonymize_metadata(metadata),
+                nullable: *nullable,
+                enum_: enum_.clone(),
+            },
+            Schema::Elements {
+                definitions,
+                metadata,
+                nullable,
+                elements,
+            } => Schema::Elements {
+                definitions: self.anonymize_definitions(definitions),
+                metadata: anonymize_metadata(metadata),
+                nullable: *nullable,
+                elements: Box::new(self.anonymize(elements)),
+            },
+            Schema::Properties {
+                definitions,
+                metadata,
+                nullable,
+                properties,
+                optional_properties,
+                properties_is_present,
+                additional_properties,
+            } => Schema::Properties {
+                definitions: self.anonymize_definitions(definitions),
+                metadata: anonymize_metadata(metadata),
+                nullable: *nullable,
+                properties: self.anonymize_properties(properties),
+                optional_properties: self.anonymize_properties(optional_properties),
+                properties_is_present: *properties_is_present,
+                additional_properties: *additional_properties,
+            },
+            Schema::Values {
+                definitions,
+                metadata,
+                nullable,
+                values,
+                #[cfg(feature = "extensions")]
+                keys,
+            } => Schema::Values {
+                definitions: self.anonymize_definitions(definitions),
+                metadata: anonymize_metadata(metadata),
+                nullable: *nullable,
+                values: Box::new(self.anonymize(values)),
+                #[cfg(feature = "extensions")]
+                keys: keys.as_ref().map(|keys| self.anonymize_keys(keys)),
+            },
+            Schema::Discriminator {
+                definitions,
+                metadata,
+                nullable,
+                discriminator,
+                mapping,
+            } => Schema::Discriminator {
+                definitions: self.anonymize_definitions(definitions),
+                metadata: anonymize_metadata(metadata),
+                nullable: *nullable,
+                discriminator: self.properties.get(discriminator),
+                mapping: mapping
+                    .iter()
+                    .map(|(tag, sub_schema)| (self.tags.get(tag), self.anonymize(sub_schema)))
+                    .collect(),
+            },
+        }
+    }
+
+    fn anonymize_definitions(&mut self, definitions: &Definitions) -> Definitions {
+        definitions
+            .iter()
+            .map(|(name, sub_schema)| (self.definitions.get(name), self.anonymize(sub_schema)))
+            .collect()
+    }
+
+    fn anonymize_properties(
+        &mut self,
+        properties: &BTreeMap<String, Schema>,
+    ) -> BTreeMap<String, Schema> {
+        properties
+            .iter()
+            .map(|(name, sub_schema)| (self.properties.get(name), self.anonymize(sub_schema)))
+            .collect()
+    }
+
+    #[cfg(feature = "extensions")]
+    fn anonymize_keys(&mut self, keys: &crate::Keys) -> crate::Keys {
+        use crate::Keys;
+
+        match keys {
+            Keys::Enum(enum_) => Keys::Enum(
+                enum_
+                    .iter()
+                    .map(|value| self.enum_values.get(value))
+                    .collect(),
+            ),
+            Keys::String { .. } => keys.clone(),
+        }
+    }
+}
+
+fn anonymize_metadata(metadata: &Metadata) -> Metadata {
+    metadata
+        .iter()
+        .filter(|(name, _)| *name == Severity::METADATA_KEY)
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+// The replacements for one kind of name, handed out in the order the names
+// are first seen.
+struct Names {
+    prefix: &'static str,
+    replacements: BTreeMap<String, String>,
+}
+
+impl Names {
+    fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix,
+            replacements: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, name: &str) -> String {
+        let next = self.replacements.len();
+        let prefix = self.prefix;
+
+        self.replacements
+            .entry(name.to_owned())
+            .or_insert_with(|| format!("{}{}", prefix, next))
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{validate, Schema, ValidateOptions};
+    use serde_json::json;
+
+    #[test]
+    fn keeps_validation_behavior() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "color": {
+                        "enum": ["red", "green"],
+                        "metadata": { "severityOnViolation": "warning", "secret": 1 }
+                    }
+                },
+                "properties": {
+                    "color": { "ref": "color" },
+                    "favorites": {
+                        "values": { "ref": "color" },
+                        "nullable": true
+                    }
+                },
+                "optionalProperties": {
+                    "nested": {
+                        "properties": { "color": { "type": "string" }},
+                        "additionalProperties": true
+                    }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let anonymized = super::anonymize(&schema);
+        anonymized.validate().unwrap();
+
+        assert_eq!(
+            json!({
+                "definitions": {
+                    "d0": {
+                        "enum": ["e0", "e1"],
+                        "metadata": { "severityOnViolation": "warning" }
+                    }
+                },
+                "properties": {
+                    "p0": { "ref": "d0" },
+                    "p1": { "values": { "ref": "d0" }, "nullable": true }
+                },
+                "optionalProperties": {
+                    "p2": {
+                        "properties": { "p0": { "type": "string" }},
+                        "additionalProperties": true
+                    }
+                }
+            }),
+            serde_json::to_value(anonymized.clone().into_serde_schema()).unwrap()
+        );
+
+        let instance = json!({ "p0": "e2", "p1": null, "p2": { "p0": 1 }});
+        let options = ValidateOptions::new().with_min_severity(crate::Severity::Info);
+        let errors = validate(&anonymized, &instance, options).unwrap();
+
+        assert_eq!(2, errors.len());
+        assert_eq!(crate::Severity::Warning, errors[0].severity);
+        assert_eq!(vec!["p2", "p0"], errors[1].instance_path);
+    }
+}
@@ -0,0 +1,600 @@
+//! Convert schemas into Apache Avro schemas.
+//!
+//! Data written to Kafka is often encoded with Avro, against a schema kept in
+//! a schema registry. [`to_avro_schema`] converts a schema into an Avro
+//! schema, so that the same definitions describe data both in JSON APIs and
+//! in Avro.
+//!
+//! ```
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": {
+//!         "id": { "type": "uint32" },
+//!         "status": { "enum": ["ACTIVE", "DISABLED"] }
+//!     },
+//!     "optionalProperties": {
+//!         "tags": { "elements": { "type": "string" }}
+//!     }
+//! })).unwrap()).unwrap();
+//!
+//! let options = jtd::avro::AvroOptions::new()
+//!     .with_namespace("com.acme.users")
+//!     .with_root_name("User");
+//!
+//! assert_eq!(
+//!     json!({
+//!         "type": "record",
+//!         "name": "User",
+//!         "namespace": "com.acme.users",
+//!         "fields": [
+//!             { "name": "id", "type": "long" },
+//!             {
+//!                 "name": "status",
+//!                 "type": {
+//!                     "type": "enum",
+//!                     "name": "UserStatus",
+//!                     "symbols": ["ACTIVE", "DISABLED"]
+//!                 }
+//!             },
+//!             {
+//!                 "name": "tags",
+//!                 "type": ["null", { "type": "array", "items": "string" }],
+//!                 "default": null
+//!             }
+//!         ]
+//!     }),
+//!     jtd::avro::to_avro_schema_with_options(&schema, options),
+//! );
+//! ```
+
+use crate::{Schema, Type};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Options you can pass to [`to_avro_schema_with_options`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AvroOptions {
+    namespace: Option<String>,
+    root_name: Option<String>,
+}
+
+impl AvroOptions {
+    /// Constructs a new set of options with all default values.
+    ///
+    /// Equivalent to [`Default::default()`] or calling `with_*` methods with
+    /// default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the namespace of the Avro schema, such as `com.acme.users`.
+    ///
+    /// By default, the schema has no namespace.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Sets the name of the record, or other named type, that the root schema
+    /// becomes. Named types nested in it are named after it.
+    ///
+    /// By default, it's `Root`.
+    pub fn with_root_name(mut self, root_name: impl Into<String>) -> Self {
+        self.root_name = Some(root_name.into());
+        self
+    }
+}
+
+/// Converts a schema into an Avro schema, with default options.
+///
+/// This is [`to_avro_schema_with_options`] with [`AvroOptions::new`].
+pub fn to_avro_schema(schema: &Schema) -> Value {
+    to_avro_schema_with_options(schema, AvroOptions::new())
+}
+
+/// Converts a schema into an Avro schema.
+///
+/// | Schema                             | Avro                                              |
+/// | ---------------------------------- | ------------------------------------------------- |
+/// | empty                              | `string`, holding the value as JSON               |
+/// | `boolean`                          | `boolean`                                         |
+/// | `int8` to `int32`, `uint8`, `uint16` | `int`                                           |
+/// | `uint32`                           | `long`                                            |
+/// | `float32`                          | `float`                                           |
+/// | `float64`                          | `double`                                          |
+/// | `string`                           | `string`                                          |
+/// | `timestamp`                        | `long`, with the `timestamp-millis` logical type  |
+/// | `enum`                             | `enum`                                            |
+/// | `elements`                         | `array`                                           |
+/// | `properties`, `optionalProperties` | `record`, with a field per property               |
+/// | `values`                           | `map`                                             |
+/// | `discriminator`                    | a union of records, one per tag                   |
+///
+/// Nullable schemas, and optional properties, become unions with `null`;
+/// optional properties default to `null`. A `description` in `metadata` is
+/// kept as the `doc` of a record, enum or field.
+///
+/// Avro's records and enums must be named, and each name defined only once.
+/// Those converted from definitions are named after the definition, and
+/// defined where they're first referred to. Those written inline are named
+/// after the record and field they're in. Each variant of a discriminator is
+/// a record named after its tag, with the discriminator as a `string` field
+/// that defaults to the tag.
+///
+/// Avro's names, of fields and enum symbols, are more restricted than
+/// property names and enum values. Property names are converted to valid
+/// names, and enums with a value that isn't a valid symbol become `string`.
+/// Definitions that refer to themselves other than through a record, which
+/// Avro can't express, become `string`, holding their value as JSON, just
+/// as the empty form does.
+pub fn to_avro_schema_with_options(schema: &Schema, options: AvroOptions) -> Value {
+    let root_name = options.root_name.as_deref().unwrap_or("Root");
+
+    let mut converter = Converter {
+        root: schema,
+        names: BTreeSet::new(),
+        definition_names: BTreeMap::new(),
+        variant_names: BTreeMap::new(),
+        defined: BTreeMap::new(),
+        refs: Vec::new(),
+    };
+
+    // Definitions are named first, so that they keep their names whenever
+    // they can.
+    converter.names.insert(root_name.to_owned());
+    for (name, definition) in schema.definitions() {
+        let avro_name = converter.unique_name(&pascal_case(name));
+        if let Schema::Discriminator { mapping, .. } = definition {
+            let variant_names = mapping
+                .keys()
+                .map(|tag| converter.unique_name(&format!("{}{}", avro_name, pascal_case(tag))))
+                .collect();
+            converter
+                .variant_names
+                .insert(avro_name.clone(), variant_names);
+        }
+
+        converter.definition_names.insert(name.clone(), avro_name);
+    }
+
+    let mut out = converter.convert(schema, root_name.to_owned());
+    if let (Some(namespace), Value::Object(object)) = (&options.namespace, &mut out) {
+        object.insert("namespace".to_owned(), namespace.clone().into());
+    }
+
+    out
+}
+
+struct Converter<'a> {
+    root: &'a Schema,
+
+    // Every name given to a named type so far.
+    names: BTreeSet<String>,
+
+    // The name of the named type for each definition.
+    definition_names: BTreeMap<String, String>,
+
+    // The names of the records for the variants of each discriminator
+    // definition, by the name of the definition.
+    variant_names: BTreeMap<String, Vec<String>>,
+
+    // How to refer to each definition whose named types have been defined,
+    // by their names.
+    defined: BTreeMap<&'a str, Value>,
+
+    // The definitions being written out in place of a ref, innermost last.
+    refs: Vec<&'a str>,
+}
+
+impl<'a> Converter<'a> {
+    // Converts a schema, naming any named type it becomes `name`.
+    fn convert(&mut self, schema: &'a Schema, name: String) -> Value {
+        let mut out = match schema {
+            Schema::Empty { .. } => return "string".into(),
+
+            Schema::Ref { ref_, .. } => self.ref_(ref_),
+
+            Schema::Type { type_, .. } => match type_ {
+                Type::Boolean => "boolean".into(),
+                Type::Int8 | Type::Uint8 | Type::Int16 | Type::Uint16 | Type::Int32 => "int".into(),
+                Type::Uint32 => "long".into(),
+                Type::Float32 => "float".into(),
+                Type::Float64 => "double".into(),
+                Type::String => "string".into(),
+                Type::Timestamp => json!({ "type": "long", "logicalType": "timestamp-millis" }),
+                #[cfg(feature = "extensions")]
+                Type::Custom(_) => "string".into(),
+            },
+
+            Schema::Enum { enum_, .. } => {
+                if enum_.iter().all(|value| is_name(value)) {
+                    let mut out = Map::new();
+                    out.insert("type".to_owned(), "enum".into());
+                    out.insert("name".to_owned(), name.into());
+                    out.insert(
+                        "symbols".to_owned(),
+                        enum_.iter().map(|value| Value::from(&value[..])).collect(),
+                    );
+                    doc(&mut out, schema);
+
+                    Value::Object(out)
+                } else {
+                    "string".into()
+                }
+            }
+
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { .. } => "long".into(),
+
+            Schema::Elements { elements, .. } => {
+                let name = self.unique_name(&format!("{}Item", name));
+                json!({ "type": "array", "items": self.convert(elements, name) })
+            }
+
+            Schema::Values { values, .. } => {
+                let name = self.unique_name(&format!("{}Value", name));
+                json!({ "type": "map", "values": self.convert(values, name) })
+            }
+
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            } => {
+                let mut fields = Vec::new();
+                let mut field_names = BTreeSet::new();
+
+                let all = properties
+                    .iter()
+                    .map(|(property, sub_schema)| (property, sub_schema, false))
+                    .chain(
+                        optional_properties
+                            .iter()
+                            .map(|(property, sub_schema)| (property, sub_schema, true)),
+                    );
+
+                for (property, sub_schema, optional) in all {
+                    let mut field_name = field_name(property);
+                    let mut n = 2;
+                    while field_names.contains(&field_name) {
+                        field_name = format!("{}{}", self::field_name(property), n);
+                        n += 1;
+                    }
+                    field_names.insert(field_name.clone());
+
+                    let type_name = self.unique_name(&format!("{}{}", name, pascal_case(property)));
+                    let mut type_ = self.convert(sub_schema, type_name);
+
+                    let mut field = Map::new();
+                    field.insert("name".to_owned(), field_name.into());
+                    if optional {
+                        nullable(&mut type_);
+                        field.insert("type".to_owned(), type_);
+                        field.insert("default".to_owned(), Value::Null);
+                    } else {
+                        field.insert("type".to_owned(), type_);
+                    }
+                    doc(&mut field, sub_schema);
+
+                    fields.push(Value::Object(field));
+                }
+
+                let mut out = Map::new();
+                out.insert("type".to_owned(), "record".into());
+                out.insert("name".to_owned(), name.into());
+                out.insert("fields".to_owned(), Value::Array(fields));
+                doc(&mut out, schema);
+
+                Value::Object(out)
+            }
+
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } => {
+                let variant_names = match self.variant_names.remove(&name) {
+                    Some(variant_names) => variant_names,
+                    None => mapping
+                        .keys()
+                        .map(|tag| self.unique_name(&format!("{}{}", name, pascal_case(tag))))
+                        .collect(),
+                };
+
+                let variants = mapping
+                    .iter()
+                    .zip(variant_names)
+                    .map(|((tag, sub_schema), variant_name)| {
+                        let mut variant = self.convert(sub_schema, variant_name);
+
+                        if let Some(Value::Array(fields)) = variant.get_mut("fields") {
+                            fields.insert(
+                                0,
+                                json!({
+                                    "name": field_name(discriminator),
+                                    "type": "string",
+                                    "default": tag,
+                                }),
+                            );
+                        }
+
+                        variant
+                    })
+                    .collect();
+
+                Value::Array(variants)
+            }
+        };
+
+        if schema.nullable() {
+            nullable(&mut out);
+        }
+
+        out
+    }
+
+    fn ref_(&mut self, ref_: &'a str) -> Value {
+        let definition = match self.root.definitions().get(ref_) {
+            Some(definition) => definition,
+            None => return "string".into(),
+        };
+
+        if let Some(reference) = self.defined.get(ref_) {
+            return reference.clone();
+        }
+
+        let name = self.definition_names[ref_].clone();
+
+        // Only the first ref to a named type defines it, and later ones,
+        // including those within it, refer to it by name.
+        let reference = match definition {
+            Schema::Properties { .. } => Some(Value::from(&name[..])),
+            Schema::Enum { enum_, .. } if enum_.iter().all(|value| is_name(value)) => {
+                Some(Value::from(&name[..]))
+            }
+            Schema::Discriminator { .. } => Some(
+                self.variant_names[&name]
+                    .iter()
+                    .map(|n| Value::from(&n[..]))
+                    .collect(),
+            ),
+            _ => None,
+        };
+
+        if let Some(mut reference) = reference {
+            if definition.nullable() {
+                nullable(&mut reference);
+            }
+
+            self.defined.insert(ref_, reference);
+            return self.convert(definition, name);
+        }
+
+        // Other types can't refer to themselves at all.
+        if self.refs.contains(&ref_) {
+            return "string".into();
+        }
+
+        self.refs.push(ref_);
+        let out = self.convert(definition, name);
+        self.refs.pop();
+
+        out
+    }
+
+    // Reserves a name for a named type, adding a number to it if it's taken.
+    fn unique_name(&mut self, name: &str) -> String {
+        let mut unique = name.to_owned();
+        let mut n = 2;
+        while self.names.contains(&unique) {
+            unique = format!("{}{}", name, n);
+            n += 1;
+        }
+
+        self.names.insert(unique.clone());
+        unique
+    }
+}
+
+// Makes a converted schema also accept null.
+fn nullable(out: &mut Value) {
+    match out {
+        Value::Array(types) => {
+            if !types.contains(&Value::from("null")) {
+                types.insert(0, "null".into());
+            }
+        }
+        _ => *out = json!(["null", std::mem::take(out)]),
+    }
+}
+
+// Keeps the description of a schema as the `doc` of what it converted to.
+fn doc(out: &mut Map<String, Value>, schema: &Schema) {
+    if let Some(description @ Value::String(_)) = schema.metadata().get("description") {
+        out.insert("doc".to_owned(), description.clone());
+    }
+}
+
+// Whether a string is a valid Avro name.
+fn is_name(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// Converts a property name into a valid Avro name.
+fn field_name(property: &str) -> String {
+    let mut out: String = property
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if !is_name(&out) {
+        out.insert(0, '_');
+    }
+
+    out
+}
+
+// Converts a name like "userId", "user_id" or "user-id" into "UserId", for
+// use in the name of a named type.
+fn pascal_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = true;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+
+    if !is_name(&out) {
+        out.insert(0, '_');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(value: Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn converts_forms() {
+        assert_eq!(
+            json!({
+                "type": "record",
+                "name": "Root",
+                "doc": "A root.",
+                "fields": [
+                    {
+                        "name": "_2d",
+                        "type": ["null", "int"],
+                        "doc": "Nullable."
+                    },
+                    {
+                        "name": "at",
+                        "type": { "type": "long", "logicalType": "timestamp-millis" }
+                    },
+                    {
+                        "name": "counts",
+                        "type": { "type": "map", "values": "double" }
+                    },
+                    {
+                        "name": "level",
+                        "type": {
+                            "type": "enum",
+                            "name": "RootLevel",
+                            "symbols": ["info", "warn"]
+                        }
+                    },
+                    { "name": "list", "type": { "type": "array", "items": "string" }},
+                    {
+                        "name": "next",
+                        "type": {
+                            "type": "record",
+                            "name": "Node",
+                            "fields": [
+                                { "name": "next", "type": ["null", "Node"], "default": null }
+                            ]
+                        }
+                    },
+                    { "name": "other", "type": "Node" },
+                    { "name": "shape", "type": ["null", {
+                        "type": "record",
+                        "name": "RootShapeCircle",
+                        "fields": [
+                            { "name": "kind", "type": "string", "default": "circle" },
+                            { "name": "r", "type": "float" }
+                        ]
+                    }, {
+                        "type": "record",
+                        "name": "RootShapeSquare",
+                        "fields": [
+                            { "name": "kind", "type": "string", "default": "square" }
+                        ]
+                    }]},
+                    { "name": "x_y", "type": "string" },
+                    { "name": "any", "type": ["null", "string"], "default": null }
+                ]
+            }),
+            to_avro_schema(&schema(json!({
+                "metadata": { "description": "A root." },
+                "definitions": {
+                    "list": { "elements": { "ref": "list" }},
+                    "node": { "optionalProperties": { "next": { "ref": "node" }}}
+                },
+                "properties": {
+                    "2d": {
+                        "metadata": { "description": "Nullable." },
+                        "type": "int8",
+                        "nullable": true
+                    },
+                    "at": { "type": "timestamp" },
+                    "counts": { "values": { "type": "float64" }},
+                    "level": { "enum": ["info", "warn"] },
+                    "list": { "ref": "list" },
+                    "next": { "ref": "node" },
+                    "other": { "ref": "node" },
+                    "shape": {
+                        "discriminator": "kind",
+                        "mapping": {
+                            "circle": { "properties": { "r": { "type": "float32" }}},
+                            "square": { "properties": {} }
+                        },
+                        "nullable": true
+                    },
+                    "x-y": { "enum": ["not a symbol"] }
+                },
+                "optionalProperties": {
+                    "any": {}
+                }
+            })))
+        );
+    }
+
+    #[test]
+    fn refers_to_definitions_by_name() {
+        assert_eq!(
+            json!({
+                "type": "record",
+                "name": "Root",
+                "fields": [
+                    { "name": "a", "type": ["null", {
+                        "type": "record",
+                        "name": "EventX",
+                        "fields": [{ "name": "t", "type": "string", "default": "x" }]
+                    }]},
+                    { "name": "b", "type": ["null", "EventX"] },
+                    { "name": "c", "type": { "type": "map", "values": ["null", "EventX"] }}
+                ]
+            }),
+            to_avro_schema(&schema(json!({
+                "definitions": {
+                    "event": {
+                        "discriminator": "t",
+                        "mapping": { "x": { "properties": {} }},
+                        "nullable": true
+                    }
+                },
+                "properties": {
+                    "a": { "ref": "event" },
+                    "b": { "ref": "event" },
+                    "c": { "values": { "ref": "event", "nullable": true }}
+                }
+            })))
+        );
+    }
+}
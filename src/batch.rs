@@ -0,0 +1,96 @@
+use crate::{validate, Schema, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde_json::Value;
+
+/// Validates each of a slice of instances against a schema.
+///
+/// This is [`validate()`][`crate::validate()`] for each instance, returning
+/// the result for each in the same order as the instances. Each instance is
+/// validated with its own copy of `options`, so limits such as
+/// [`ValidateOptions::with_max_errors`] apply to each instance on its own,
+/// and an error validating one instance doesn't stop the others from being
+/// validated.
+///
+/// With the `rayon` feature, instances are validated in parallel, on
+/// [rayon](https://docs.rs/rayon)'s global thread pool. Hooks set in
+/// `options`, such as that of [`ValidateOptions::with_progress`], may then be
+/// called from several threads at once. Cancelling `options` with
+/// [`ValidateOptions::with_cancellation`] cancels validation of every
+/// instance not yet validated.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "id": { "type": "uint32" }}
+/// })).unwrap()).unwrap();
+///
+/// let input = "{\"id\":1}\n{\"id\":-1}\n{\"id\":3}\n";
+/// let instances: Vec<serde_json::Value> = input
+///     .lines()
+///     .map(|line| serde_json::from_str(line).unwrap())
+///     .collect();
+///
+/// let results = jtd::validate_batch(&schema, &instances, Default::default());
+/// let invalid: Vec<_> = results
+///     .iter()
+///     .enumerate()
+///     .filter(|(_, result)| !result.as_ref().unwrap().is_empty())
+///     .map(|(line, _)| line)
+///     .collect();
+///
+/// assert_eq!(vec![1], invalid);
+/// ```
+pub fn validate_batch<'a>(
+    schema: &'a Schema,
+    instances: &'a [Value],
+    options: ValidateOptions,
+) -> Vec<Result<Vec<ValidationErrorIndicator<'a>>, ValidateError>> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        instances
+            .par_iter()
+            .map(|instance| validate(schema, instance, options.clone()))
+            .collect()
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    {
+        instances
+            .iter()
+            .map(|instance| validate(schema, instance, options.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn matches_validate() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "elements": { "type": "uint8" }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let instances: Vec<_> = (0..100)
+            .map(|i| json!([i, i * 3, "x"]))
+            .chain(Some(json!([[[[1]]]])))
+            .collect();
+
+        let options = ValidateOptions::new().with_max_errors(1).with_max_depth(2);
+        let results = validate_batch(&schema, &instances, options.clone());
+
+        assert_eq!(instances.len(), results.len());
+        for (instance, result) in instances.iter().zip(results) {
+            assert_eq!(validate(&schema, instance, options.clone()), result);
+        }
+    }
+}
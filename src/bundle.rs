@@ -0,0 +1,299 @@
+//! Bundle schemas spread across several files into one.
+//!
+//! JSON Typedef has no way for one schema to refer to another: a `ref` names
+//! one of the `definitions` of its own root schema. Repositories of many
+//! schemas still tend to split them into files, and share definitions between
+//! them, by a convention of their own. [`bundle`] supports one such
+//! convention, and turns a set of files that follow it into a single schema
+//! that any JSON Typedef implementation can use.
+//!
+//! By this convention, a `ref` that contains a `#` refers to another file:
+//! `"other.jtd#user"` is the `user` definition of the file named `other.jtd`,
+//! and `"other.jtd#"`, with nothing after the `#`, is the root schema of
+//! `other.jtd`. Other refs are to the definitions of the file they're in, as
+//! usual.
+//!
+//! ```
+//! use jtd::Schema;
+//! use serde_json::json;
+//! use std::collections::BTreeMap;
+//!
+//! let mut files = BTreeMap::new();
+//! files.insert("order.jtd".to_owned(), json!({
+//!     "definitions": {
+//!         "id": { "type": "uint32" }
+//!     },
+//!     "properties": {
+//!         "id": { "ref": "id" },
+//!         "customer": { "ref": "customer.jtd#" }
+//!     }
+//! }));
+//! files.insert("customer.jtd".to_owned(), json!({
+//!     "definitions": {
+//!         "id": { "type": "string" }
+//!     },
+//!     "properties": {
+//!         "id": { "ref": "id" }
+//!     }
+//! }));
+//!
+//! let files: BTreeMap<_, _> = files
+//!     .into_iter()
+//!     .map(|(name, schema)| {
+//!         let schema = serde_json::from_value(schema).unwrap();
+//!         (name, Schema::from_serde_schema(schema).unwrap())
+//!     })
+//!     .collect();
+//!
+//! // Both files have an "id" definition, so the customer's is renamed.
+//! let schema = jtd::bundle::bundle(&files, "order.jtd").unwrap();
+//! assert_eq!(
+//!     json!({
+//!         "definitions": {
+//!             "id": { "type": "uint32" },
+//!             "customer.jtd": { "properties": { "id": { "ref": "customer.jtd#id" }}},
+//!             "customer.jtd#id": { "type": "string" }
+//!         },
+//!         "properties": {
+//!             "id": { "ref": "id" },
+//!             "customer": { "ref": "customer.jtd" }
+//!         }
+//!     }),
+//!     serde_json::to_value(schema.into_serde_schema()).unwrap(),
+//! );
+//! ```
+
+use crate::Schema;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use thiserror::Error;
+
+/// Errors that may arise from [`bundle`].
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum BundleError {
+    /// A file, either the root file or one referred to by a `ref`, isn't
+    /// among the files being bundled.
+    #[error("no such file: {0:?}")]
+    NoSuchFile(String),
+
+    /// A `ref` is to a definition that its file doesn't have. The first field
+    /// is the file, and the second the definition.
+    #[error("no such definition in {0:?}: {1:?}")]
+    NoSuchDefinition(String, String),
+}
+
+// A schema that refs can refer to: a definition of a file, or, if there's no
+// definition name, the root schema of a file.
+type Target = (String, Option<String>);
+
+/// Bundles schemas spread across several files into one, starting from the
+/// file named `root`.
+///
+/// `files` maps the name of each file to its schema, and refs between them
+/// follow the convention described in [the module documentation][self].
+///
+/// The bundled schema is the root file's schema, with the definitions of
+/// every file it refers to, directly or indirectly, added to its
+/// definitions, and refs rewritten to match. Definitions of other files that
+/// aren't referred to are left out. The root file's definitions keep their
+/// names. Others keep theirs too, unless that name is already taken, in which
+/// case they're named after the ref that refers to them, such as
+/// `"other.jtd#user"`. The root schema of another file becomes a definition
+/// named after the file.
+///
+/// Definitions below the root of a file are left where they are, as
+/// [`Schema::validate`] would reject them anyway.
+pub fn bundle(files: &BTreeMap<String, Schema>, root: &str) -> Result<Schema, BundleError> {
+    let root_schema = files
+        .get(root)
+        .ok_or_else(|| BundleError::NoSuchFile(root.to_owned()))?;
+
+    let mut bundler = Bundler {
+        files,
+        names: BTreeMap::new(),
+        taken: BTreeSet::new(),
+        queue: VecDeque::new(),
+    };
+
+    // The root file's definitions are named first, so they keep their names.
+    for name in root_schema.definitions().keys() {
+        bundler.name((root.to_owned(), Some(name.clone())));
+    }
+
+    let mut schema = bundler.rewrite(root, without_definitions(root_schema))?;
+
+    let mut definitions = BTreeMap::new();
+    while let Some(target) = bundler.queue.pop_front() {
+        let definition = match &target {
+            (file, Some(name)) => files[file].definitions()[name].clone(),
+            (file, None) => without_definitions(&files[file]),
+        };
+
+        let definition = bundler.rewrite(&target.0, definition)?;
+        definitions.insert(bundler.names[&target].clone(), definition);
+    }
+
+    *schema.definitions_mut() = definitions;
+    Ok(schema)
+}
+
+struct Bundler<'a> {
+    files: &'a BTreeMap<String, Schema>,
+    // The name in the bundle of each schema that's been referred to.
+    names: BTreeMap<Target, String>,
+    taken: BTreeSet<String>,
+    // Schemas that have been named, but not yet added to the bundle.
+    queue: VecDeque<Target>,
+}
+
+impl Bundler<'_> {
+    // Rewrites the refs of a schema from the given file to the names of what
+    // they refer to in the bundle, naming anything that hasn't been yet.
+    fn rewrite(&mut self, file: &str, schema: Schema) -> Result<Schema, BundleError> {
+        let mut targets = Vec::new();
+        schema.walk(&mut |_: &[&str], sub_schema: &Schema| {
+            if let Schema::Ref { ref_, .. } = sub_schema {
+                targets.push(self.resolve(file, ref_));
+            }
+        });
+
+        for target in targets {
+            self.name(target?);
+        }
+
+        Ok(schema.map_schemas(|mut sub_schema| {
+            if let Schema::Ref { ref_, .. } = &mut sub_schema {
+                // Every ref was resolved above, so this always succeeds.
+                if let Ok(target) = self.resolve(file, ref_) {
+                    *ref_ = self.names[&target].clone();
+                }
+            }
+
+            sub_schema
+        }))
+    }
+
+    // Works out what a ref in the given file refers to.
+    fn resolve(&self, file: &str, ref_: &str) -> Result<Target, BundleError> {
+        let (file, name) = match ref_.split_once('#') {
+            Some((file, "")) => (file, None),
+            Some((file, name)) => (file, Some(name)),
+            None => (file, Some(ref_)),
+        };
+
+        let schema = self
+            .files
+            .get(file)
+            .ok_or_else(|| BundleError::NoSuchFile(file.to_owned()))?;
+
+        if let Some(name) = name {
+            if !schema.definitions().contains_key(name) {
+                return Err(BundleError::NoSuchDefinition(
+                    file.to_owned(),
+                    name.to_owned(),
+                ));
+            }
+        }
+
+        Ok((file.to_owned(), name.map(str::to_owned)))
+    }
+
+    // Gives a schema a name in the bundle, if it doesn't have one already.
+    fn name(&mut self, target: Target) {
+        if self.names.contains_key(&target) {
+            return;
+        }
+
+        let (file, name) = &target;
+        let preferred = name.clone().unwrap_or_else(|| file.clone());
+        let qualified = format!("{}#{}", file, name.as_deref().unwrap_or_default());
+
+        let mut name = preferred;
+        if self.taken.contains(&name) {
+            name = qualified.clone();
+        }
+
+        let mut n = 2;
+        while self.taken.contains(&name) {
+            name = format!("{}~{}", qualified, n);
+            n += 1;
+        }
+
+        self.taken.insert(name.clone());
+        self.names.insert(target.clone(), name);
+        self.queue.push_back(target);
+    }
+}
+
+fn without_definitions(schema: &Schema) -> Schema {
+    let mut schema = schema.clone();
+    schema.definitions_mut().clear();
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn files(files: serde_json::Value) -> BTreeMap<String, Schema> {
+        serde_json::from_value::<BTreeMap<String, crate::SerdeSchema>>(files)
+            .unwrap()
+            .into_iter()
+            .map(|(name, schema)| (name, Schema::from_serde_schema(schema).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn bundles_across_files() {
+        let files = files(json!({
+            "a": {
+                "definitions": {
+                    "node": { "elements": { "ref": "b#node" }},
+                    "b#node": { "type": "string" }
+                },
+                "ref": "node"
+            },
+            "b": {
+                "definitions": {
+                    "node": {
+                        "properties": { "next": { "ref": "node", "nullable": true }},
+                        "optionalProperties": { "root": { "ref": "a#" }}
+                    },
+                    "unused": {}
+                }
+            }
+        }));
+
+        let schema = bundle(&files, "a").unwrap();
+        assert_eq!(
+            json!({
+                "definitions": {
+                    "node": { "elements": { "ref": "b#node~2" }},
+                    "b#node": { "type": "string" },
+                    "b#node~2": {
+                        "properties": { "next": { "ref": "b#node~2", "nullable": true }},
+                        "optionalProperties": { "root": { "ref": "a" }}
+                    },
+                    "a": { "ref": "node" }
+                },
+                "ref": "node"
+            }),
+            serde_json::to_value(schema.clone().into_serde_schema()).unwrap(),
+        );
+        assert_eq!(Ok(()), schema.validate());
+
+        assert_eq!(
+            Err(BundleError::NoSuchFile("c".to_owned())),
+            bundle(&files, "c"),
+        );
+
+        let files = self::files(json!({ "a": { "elements": { "ref": "b#x" }}, "b": {} }));
+        assert_eq!(
+            Err(BundleError::NoSuchDefinition(
+                "b".to_owned(),
+                "x".to_owned()
+            )),
+            bundle(&files, "a"),
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{validate, Schema, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde_json::Value;
+use std::borrow::Cow;
+use thiserror::Error;
+
+/// Errors that may arise from [`validate_bytes`].
+#[derive(Debug, Error)]
+pub enum ValidateBytesError {
+    /// The input is in an encoding that isn't supported, such as UTF-32.
+    #[error("unsupported encoding: {0}")]
+    UnsupportedEncoding(&'static str),
+
+    /// The input is UTF-16, but isn't valid UTF-16: it has an odd number of
+    /// bytes, or an unpaired surrogate.
+    #[error("invalid UTF-16")]
+    InvalidUtf16,
+
+    /// The input, once decoded, was not valid JSON.
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// Validating the input failed.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+}
+
+/// Parses JSON from bytes in any of the encodings commonly used for it, and
+/// validates it against a schema.
+///
+/// Files produced on Windows often start with a byte order mark, or are
+/// encoded in UTF-16 rather than UTF-8. [`serde_json`] only accepts UTF-8
+/// without a byte order mark, and rejects anything else with an error that
+/// doesn't say what's wrong. This function handles:
+///
+/// * UTF-8, with or without a byte order mark, and
+/// * UTF-16, big- or little-endian, with or without a byte order mark, which
+///   it converts to UTF-8.
+///
+/// Without a byte order mark, the encoding is detected from the pattern of
+/// zero bytes at the start of the input, as described in [RFC 4627, Section
+/// 3](https://tools.ietf.org/html/rfc4627#section-3). UTF-32 is detected the
+/// same way, and rejected with [`ValidateBytesError::UnsupportedEncoding`].
+///
+/// Since the parsed instance doesn't outlive this function, the validation
+/// errors returned own their paths.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "elements": { "type": "string" }
+/// })).unwrap()).unwrap();
+///
+/// // ["a", 1] in UTF-16LE, with a byte order mark.
+/// let bytes: Vec<u8> = std::iter::once(0xFEFF)
+///     .chain(r#"["a", 1]"#.encode_utf16())
+///     .flat_map(|unit: u16| unit.to_le_bytes())
+///     .collect();
+///
+/// let errors = jtd::validate_bytes(&schema, &bytes, Default::default()).unwrap();
+/// assert_eq!(1, errors.len());
+/// assert_eq!(vec!["1"], errors[0].instance_path);
+/// ```
+pub fn validate_bytes(
+    schema: &Schema,
+    bytes: &[u8],
+    options: ValidateOptions,
+) -> Result<Vec<ValidationErrorIndicator<'static>>, ValidateBytesError> {
+    let instance: Value = serde_json::from_slice(&decode(bytes)?)?;
+
+    Ok(validate(schema, &instance, options)?
+        .into_iter()
+        .map(|error| error.into_owned().into())
+        .collect())
+}
+
+// Converts JSON text in any supported encoding into UTF-8 without a byte
+// order mark.
+fn decode(bytes: &[u8]) -> Result<Cow<'_, [u8]>, ValidateBytesError> {
+    match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => Ok(Cow::Borrowed(rest)),
+        [0xFF, 0xFE, 0, 0, ..] | [_, 0, 0, 0, ..] => {
+            Err(ValidateBytesError::UnsupportedEncoding("UTF-32LE"))
+        }
+        [0, 0, 0xFE, 0xFF, ..] | [0, 0, 0, _, ..] => {
+            Err(ValidateBytesError::UnsupportedEncoding("UTF-32BE"))
+        }
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0, _, ..] => utf16(bytes, u16::from_be_bytes),
+        [_, 0, ..] => utf16(bytes, u16::from_le_bytes),
+        _ => Ok(Cow::Borrowed(bytes)),
+    }
+}
+
+fn utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Result<Cow<'_, [u8]>, ValidateBytesError> {
+    if bytes.len() % 2 != 0 {
+        return Err(ValidateBytesError::InvalidUtf16);
+    }
+
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| unit([pair[0], pair[1]]))
+        .collect();
+
+    String::from_utf16(&units)
+        .map(|s| Cow::Owned(s.into_bytes()))
+        .map_err(|_| ValidateBytesError::InvalidUtf16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn encode(s: &str, bom: bool, unit: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        let bom = if bom { Some(0xFEFF) } else { None };
+        bom.into_iter()
+            .chain(s.encode_utf16())
+            .flat_map(unit)
+            .collect()
+    }
+
+    #[test]
+    fn decodes() {
+        let text = r#"{"é": ["😀"]}"#;
+        let inputs = [
+            text.as_bytes().to_vec(),
+            [&[0xEF, 0xBB, 0xBF], text.as_bytes()].concat(),
+            encode(text, true, u16::to_le_bytes),
+            encode(text, false, u16::to_le_bytes),
+            encode(text, true, u16::to_be_bytes),
+            encode(text, false, u16::to_be_bytes),
+        ];
+
+        for input in &inputs {
+            assert_eq!(text.as_bytes(), &decode(input).unwrap()[..]);
+        }
+
+        // Short inputs can't be mistaken for UTF-16.
+        assert_eq!(b"1", &decode(b"1").unwrap()[..]);
+        assert_eq!(b"", &decode(b"").unwrap()[..]);
+    }
+
+    #[test]
+    fn rejects_unsupported() {
+        let utf32le: Vec<u8> = "[1]"
+            .chars()
+            .flat_map(|c| (c as u32).to_le_bytes())
+            .collect();
+        let utf32be: Vec<u8> = "[1]"
+            .chars()
+            .flat_map(|c| (c as u32).to_be_bytes())
+            .collect();
+
+        assert!(matches!(
+            decode(&utf32le),
+            Err(ValidateBytesError::UnsupportedEncoding("UTF-32LE"))
+        ));
+        assert!(matches!(
+            decode(&utf32be),
+            Err(ValidateBytesError::UnsupportedEncoding("UTF-32BE"))
+        ));
+        assert!(matches!(
+            decode(&[0xFF, 0xFE, b'1']),
+            Err(ValidateBytesError::InvalidUtf16)
+        ));
+        assert!(matches!(
+            decode(&[0xFF, 0xFE, 0x00, 0xD8, b'1', 0]),
+            Err(ValidateBytesError::InvalidUtf16)
+        ));
+
+        let schema = Schema::from_serde_schema(serde_json::from_value(json!({})).unwrap()).unwrap();
+        assert!(matches!(
+            validate_bytes(&schema, b"\xEF\xBB\xBF{", Default::default()),
+            Err(ValidateBytesError::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,201 @@
+//! A process-wide cache of parsed schemas.
+//!
+//! Services that receive schemas at runtime, such as multi-tenant services
+//! where each tenant supplies its own, often receive the same schema over and
+//! over. [`get_or_parse`] parses and checks each distinct schema once, and
+//! hands out shared references to the result from then on.
+//!
+//! The cache is keyed by the SHA-256 hash of a schema's raw bytes, so looking
+//! a schema up costs one pass over its bytes, and no parsing. Byte-for-byte
+//! different spellings of the same schema are cached separately; see
+//! [`Fingerprint`][`crate::Fingerprint`] for identifying schemas by their
+//! contents instead.
+//!
+//! The cache is opt-in: nothing else in this crate uses it. It holds at most
+//! [`capacity`] schemas, 1024 by default. When it's full, adding a schema
+//! evicts the one that was added longest ago.
+
+use crate::{FromSerdeSchemaError, Schema, SchemaValidateError, SerdeSchema};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use thiserror::Error;
+
+/// Errors that may arise from [`get_or_parse`].
+///
+/// Schemas that fail to parse aren't cached, so asking for one again returns
+/// the same error again.
+#[derive(Debug, Error)]
+pub enum CacheError {
+    /// The bytes were not a JSON Typedef schema in JSON.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    /// The schema could not be converted from a [`SerdeSchema`].
+    #[error(transparent)]
+    Schema(#[from] FromSerdeSchemaError),
+
+    /// The schema did not pass [`Schema::validate`].
+    #[error(transparent)]
+    Invalid(#[from] SchemaValidateError),
+}
+
+struct Cache {
+    capacity: usize,
+    schemas: BTreeMap<[u8; 32], Arc<Schema>>,
+    // Keys in the order they were added, oldest first.
+    order: VecDeque<[u8; 32]>,
+}
+
+static CACHE: Mutex<Cache> = Mutex::new(Cache {
+    capacity: 1024,
+    schemas: BTreeMap::new(),
+    order: VecDeque::new(),
+});
+
+fn cache() -> MutexGuard<'static, Cache> {
+    // The cache is never left in an inconsistent state, so it's fine to keep
+    // using it after a panic elsewhere.
+    CACHE.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Gets the schema in `bytes`, parsing and checking it only if it isn't
+/// cached already.
+///
+/// The bytes are parsed as JSON into a [`SerdeSchema`], converted with
+/// [`Schema::from_serde_schema`], and checked with [`Schema::validate`].
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// let a = jtd::cache::get_or_parse(br#"{ "type": "string" }"#).unwrap();
+/// let b = jtd::cache::get_or_parse(br#"{ "type": "string" }"#).unwrap();
+/// assert!(Arc::ptr_eq(&a, &b));
+///
+/// assert!(jtd::cache::get_or_parse(br#"{ "ref": "missing" }"#).is_err());
+/// ```
+pub fn get_or_parse(bytes: &[u8]) -> Result<Arc<Schema>, CacheError> {
+    let key: [u8; 32] = Sha256::digest(bytes).into();
+    if let Some(schema) = cache().schemas.get(&key) {
+        return Ok(Arc::clone(schema));
+    }
+
+    // Parsing happens without holding the lock, so that other threads can
+    // use the cache in the meantime. If another thread parses the same
+    // schema first, its result is used.
+    let serde_schema: SerdeSchema = serde_json::from_slice(bytes)?;
+    let schema = Schema::from_serde_schema(serde_schema)?;
+    schema.validate()?;
+
+    let mut cache = cache();
+    if let Some(schema) = cache.schemas.get(&key) {
+        return Ok(Arc::clone(schema));
+    }
+
+    let schema = Arc::new(schema);
+    if cache.capacity > 0 {
+        cache.schemas.insert(key, Arc::clone(&schema));
+        cache.order.push_back(key);
+        cache.evict();
+    }
+
+    Ok(schema)
+}
+
+/// The most schemas the cache holds.
+pub fn capacity() -> usize {
+    cache().capacity
+}
+
+/// Sets the most schemas the cache holds, evicting schemas if it holds more
+/// than that already.
+///
+/// A capacity of zero disables caching.
+pub fn set_capacity(capacity: usize) {
+    let mut cache = cache();
+    cache.capacity = capacity;
+    cache.evict();
+}
+
+/// The number of schemas in the cache.
+pub fn len() -> usize {
+    cache().schemas.len()
+}
+
+/// Removes every schema from the cache.
+///
+/// Schemas already handed out by [`get_or_parse`] remain usable.
+pub fn clear() {
+    let mut cache = cache();
+    cache.schemas.clear();
+    cache.order.clear();
+}
+
+impl Cache {
+    fn evict(&mut self) {
+        while self.schemas.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(key) => {
+                    self.schemas.remove(&key);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    // The cache is global, so everything is tested in one test, to keep
+    // tests running in parallel from interfering with one another. Doctests
+    // run in separate processes.
+    #[test]
+    fn cache() {
+        super::clear();
+        assert_eq!(1024, super::capacity());
+
+        let a = super::get_or_parse(br#"{ "type": "uint8" }"#).unwrap();
+        let b = super::get_or_parse(br#"{ "type": "int8" }"#).unwrap();
+        assert_eq!(2, super::len());
+        assert!(Arc::ptr_eq(
+            &a,
+            &super::get_or_parse(br#"{ "type": "uint8" }"#).unwrap()
+        ));
+
+        // Spelled differently, so cached separately.
+        super::get_or_parse(br#"{"type":"uint8"}"#).unwrap();
+        assert_eq!(3, super::len());
+
+        assert!(matches!(
+            super::get_or_parse(b"{"),
+            Err(super::CacheError::Json(_))
+        ));
+        assert!(matches!(
+            super::get_or_parse(br#"{ "type": "uint128" }"#),
+            Err(super::CacheError::Schema(_))
+        ));
+        assert_eq!(3, super::len());
+
+        // The oldest schemas are evicted first.
+        super::set_capacity(1);
+        assert_eq!(1, super::len());
+        assert!(!Arc::ptr_eq(
+            &a,
+            &super::get_or_parse(br#"{ "type": "uint8" }"#).unwrap()
+        ));
+        assert!(!Arc::ptr_eq(
+            &b,
+            &super::get_or_parse(br#"{ "type": "int8" }"#).unwrap()
+        ));
+
+        super::set_capacity(0);
+        assert_eq!(0, super::len());
+        super::get_or_parse(br#"{ "type": "int8" }"#).unwrap();
+        assert_eq!(0, super::len());
+
+        super::set_capacity(1024);
+        super::clear();
+    }
+}
@@ -0,0 +1,240 @@
+//! Validate CBOR instances.
+//!
+//! A schema describes the same data whether it's sent as JSON or as CBOR, so
+//! services that use CBOR on the wire can check it against the schemas they
+//! already have. [`validate`] validates a [`ciborium::Value`] as it is, and
+//! [`validate_from_reader`] validates CBOR as it's decoded, without
+//! transcoding either to JSON first.
+//!
+//! CBOR can express more than JSON can. Tags are looked through, to the value
+//! they tag, and integers too large for an `i64` or a `u64`, including
+//! bignums, are treated as floats. Byte strings, and maps with keys other than text, have no JSON
+//! counterpart, and give a [`CborError::Unsupported`] error.
+//!
+//! This module is only available with the `cbor` feature.
+//!
+//! ```
+//! use ciborium::cbor;
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": {
+//!         "sensor": { "type": "string" },
+//!         "reading": { "type": "int16" }
+//!     }
+//! })).unwrap()).unwrap();
+//!
+//! let instance = cbor!({ "sensor" => "t1", "reading" => 70000 }).unwrap();
+//! let errors = jtd::cbor::validate(&schema, &instance, Default::default()).unwrap();
+//!
+//! assert_eq!(1, errors.len());
+//! assert_eq!(vec!["reading"], errors[0].instance_path);
+//! assert_eq!(vec!["properties", "reading", "type"], errors[0].schema_path);
+//! ```
+
+use crate::reader::validate_stream;
+use crate::{Schema, ValidateOptions, ValidateReaderError, ValidationErrorIndicator};
+use ciborium::Value;
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{self, IntoDeserializer, Visitor};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use thiserror::Error;
+
+/// Errors that may arise from reading a CBOR instance, as the
+/// [`ValidateReaderError::Deserialize`] of [`validate`] and
+/// [`validate_from_reader`].
+#[derive(Debug, Error)]
+pub enum CborError {
+    /// The input couldn't be read, or wasn't well-formed CBOR.
+    #[error(transparent)]
+    Decode(#[from] ciborium::de::Error<io::Error>),
+
+    /// The instance contains a value with no counterpart in JSON, such as a
+    /// byte string.
+    #[error("{0}")]
+    Unsupported(String),
+}
+
+impl de::Error for CborError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        CborError::Unsupported(msg.to_string())
+    }
+}
+
+/// Validates a CBOR instance against a schema.
+///
+/// This finds the same errors as [`validate()`][`crate::validate()`] would for
+/// the JSON counterpart of `instance`, though not always in the same order;
+/// see [`validate_from_reader`][`crate::validate_from_reader`], which works
+/// the same way, for the details.
+pub fn validate(
+    schema: &Schema,
+    instance: &Value,
+    options: ValidateOptions,
+) -> Result<Vec<ValidationErrorIndicator<'static>>, ValidateReaderError<CborError>> {
+    Ok(validate_stream(schema, ValueDeserializer(instance), options)?.0)
+}
+
+/// Validates CBOR read from `reader` against a schema.
+///
+/// The input is decoded into a [`ciborium::Value`], which is then passed to
+/// [`validate`].
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "elements": { "type": "uint8" }
+/// })).unwrap()).unwrap();
+///
+/// let mut input = Vec::new();
+/// ciborium::into_writer(&vec![1, 2, 300], &mut input).unwrap();
+///
+/// let errors = jtd::cbor::validate_from_reader(&schema, &input[..], Default::default()).unwrap();
+/// assert_eq!(1, errors.len());
+/// assert_eq!(vec!["2"], errors[0].instance_path);
+/// ```
+pub fn validate_from_reader<R: io::Read>(
+    schema: &Schema,
+    reader: R,
+    options: ValidateOptions,
+) -> Result<Vec<ValidationErrorIndicator<'static>>, ValidateReaderError<CborError>> {
+    let instance: Value = ciborium::from_reader(reader)
+        .map_err(|err| ValidateReaderError::Deserialize(CborError::Decode(err)))?;
+
+    validate(schema, &instance, options)
+}
+
+// The tags of bignums, which hold the bytes of an integer too large for CBOR's
+// integers.
+const BIGPOS: u64 = 2;
+const BIGNEG: u64 = 3;
+
+// Presents a CBOR value to the streaming validator as the JSON value it
+// corresponds to.
+#[derive(Clone, Copy)]
+struct ValueDeserializer<'a>(&'a Value);
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = CborError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CborError> {
+        match self.0 {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(*b),
+            Value::Integer(n) => {
+                let n = i128::from(*n);
+                if let Ok(n) = u64::try_from(n) {
+                    visitor.visit_u64(n)
+                } else if let Ok(n) = i64::try_from(n) {
+                    visitor.visit_i64(n)
+                } else {
+                    visitor.visit_f64(n as f64)
+                }
+            }
+            Value::Float(n) => visitor.visit_f64(*n),
+            Value::Text(s) => visitor.visit_str(s),
+            Value::Tag(tag, value) => match (*tag, value.as_bytes()) {
+                (BIGPOS, Some(bytes)) => visitor.visit_f64(bignum(bytes)),
+                (BIGNEG, Some(bytes)) => visitor.visit_f64(-1.0 - bignum(bytes)),
+                _ => ValueDeserializer(value).deserialize_any(visitor),
+            },
+            Value::Array(values) => {
+                visitor.visit_seq(SeqDeserializer::new(values.iter().map(ValueDeserializer)))
+            }
+            Value::Map(entries) => {
+                if entries.iter().any(|(key, _)| !key.is_text()) {
+                    return Err(CborError::Unsupported(
+                        "map with a key that isn't text".to_owned(),
+                    ));
+                }
+
+                visitor.visit_map(MapDeserializer::new(
+                    entries
+                        .iter()
+                        .map(|(key, value)| (ValueDeserializer(key), ValueDeserializer(value))),
+                ))
+            }
+            Value::Bytes(_) => Err(CborError::Unsupported("byte string".to_owned())),
+            _ => Err(CborError::Unsupported("unknown CBOR value".to_owned())),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+fn bignum(bytes: &[u8]) -> f64 {
+    bytes.iter().fold(0.0, |n, &b| n * 256.0 + f64::from(b))
+}
+
+impl<'de, 'a> IntoDeserializer<'de, CborError> for ValueDeserializer<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ciborium::cbor;
+    use serde_json::json;
+
+    #[test]
+    fn matches_json() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "properties": {
+                    "at": { "type": "timestamp" },
+                    "big": { "type": "float64" },
+                    "counts": { "values": { "type": "int8" }},
+                    "tags": { "elements": { "type": "string" }}
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let instance = cbor!({
+            "at" => Value::Tag(0, Box::new(Value::Text("1985-04-12T23:20:50.52Z".into()))),
+            "big" => u128::from(u64::MAX) + 1,
+            "counts" => { "a" => -128, "b" => -129 },
+            "tags" => ["x", 1],
+            "extra" => null
+        })
+        .unwrap();
+
+        let errors: Vec<_> = validate(&schema, &instance, Default::default())
+            .unwrap()
+            .into_iter()
+            .map(|error| error.instance_path.to_string())
+            .collect();
+        assert_eq!(vec!["/counts/b", "/tags/1", "/extra"], errors);
+
+        let instance = cbor!({ "at" => Value::Bytes(vec![1]) }).unwrap();
+        assert!(matches!(
+            validate(&schema, &instance, Default::default()),
+            Err(ValidateReaderError::Deserialize(CborError::Unsupported(_)))
+        ));
+
+        let instance = cbor!({ 1 => "a" }).unwrap();
+        assert!(matches!(
+            validate(&schema, &instance, Default::default()),
+            Err(ValidateReaderError::Deserialize(CborError::Unsupported(_)))
+        ));
+
+        assert!(matches!(
+            validate_from_reader(&schema, &[0xff][..], Default::default()),
+            Err(ValidateReaderError::Deserialize(CborError::Decode(_)))
+        ));
+    }
+}
@@ -0,0 +1,96 @@
+//! Serde wrappers that enforce a schema on the data passing through them.
+//!
+//! [`Deserializer`] wraps any [`serde::Deserializer`], and [`Serializer`] any
+//! [`serde::Serializer`], and each checks every value against a [`Schema`] as
+//! it goes by. Because they work at the level of serde's data model, rather
+//! than on a [`serde_json::Value`], the schema is enforced whatever the format
+//! -- CBOR and MessagePack as much as JSON -- and whatever type is being
+//! deserialized into.
+//!
+//! This is stricter than the `Deserialize` impl of the type alone: integers
+//! must be in the range of the schema's type, not just the Rust type's, and
+//! properties the schema doesn't know about are rejected, even where serde
+//! would ignore them.
+//!
+//! ```
+//! use jtd::checked::{DeserializeError, Deserializer};
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Reading {
+//!     sensor: String,
+//!     value: i64,
+//! }
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": {
+//!         "sensor": { "type": "string" },
+//!         "value": { "type": "int16" }
+//!     }
+//! })).unwrap()).unwrap();
+//!
+//! let mut json = serde_json::Deserializer::from_str(r#"{"sensor":"t1","value":70000}"#);
+//! match Deserializer::new(&mut json, &schema).deserialize::<Reading>() {
+//!     Err(DeserializeError::Invalid(indicator)) => {
+//!         assert_eq!(vec!["value"], indicator.instance_path);
+//!     }
+//!     _ => unreachable!(),
+//! }
+//!
+//! let mut json = serde_json::Deserializer::from_str(r#"{"sensor":"t1","value":7,"unit":"C"}"#);
+//! match Deserializer::new(&mut json, &schema).deserialize::<Reading>() {
+//!     Err(DeserializeError::Invalid(indicator)) => {
+//!         assert_eq!(vec!["unit"], indicator.instance_path);
+//!     }
+//!     _ => unreachable!(),
+//! }
+//! ```
+//!
+//! These are the same types as [`crate::Deserializer`] and
+//! [`crate::Serializer`]; this module gathers them under one name.
+//!
+//! [`Schema`]: crate::Schema
+
+pub use crate::de::{DeserializeError, Deserializer};
+pub use crate::ser::{SerializeError, Serializer};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Schema;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn enforces_schema_on_any_type() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "values": { "type": "uint8" }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let mut json = serde_json::Deserializer::from_str(r#"{"a":1,"b":255}"#);
+        let counts: HashMap<String, u64> =
+            Deserializer::new(&mut json, &schema).deserialize().unwrap();
+        assert_eq!(Some(&255), counts.get("b"));
+
+        let mut json = serde_json::Deserializer::from_str(r#"{"a":1,"b":256}"#);
+        match Deserializer::new(&mut json, &schema).deserialize::<HashMap<String, u64>>() {
+            Err(DeserializeError::Invalid(indicator)) => {
+                assert_eq!(vec!["b"], indicator.instance_path);
+                assert_eq!(vec!["values", "type"], indicator.schema_path);
+            }
+            _ => unreachable!(),
+        }
+
+        let mut out = Vec::new();
+        let counts: HashMap<_, _> = vec![("a", -1)].into_iter().collect();
+        assert!(matches!(
+            Serializer::new(&mut serde_json::Serializer::new(&mut out), &schema).serialize(&counts),
+            Err(SerializeError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+//! Accept schema-checked JSON as command-line arguments with
+//! [clap](https://docs.rs/clap).
+//!
+//! This module is only available with the `clap` feature.
+
+use crate::{validate, Schema, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use ::clap::builder::{StringValueParser, TypedValueParser};
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Constructs a clap value parser that accepts JSON satisfying `schema`.
+///
+/// The parsed value is a [`serde_json::Value`]. If the argument isn't JSON,
+/// or doesn't satisfy the schema, clap reports it the way it reports any
+/// other invalid value, with an [`ArgError`] as the reason.
+///
+/// Validation uses the default [`ValidateOptions`]; see
+/// [`schema_value_parser_with_options`] to change them.
+///
+/// ```
+/// use clap::{Arg, Command};
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "retries": { "type": "uint8" }}
+/// })).unwrap()).unwrap();
+///
+/// let command = Command::new("app").arg(
+///     Arg::new("policy")
+///         .long("policy")
+///         .value_parser(jtd::clap::schema_value_parser(schema)),
+/// );
+///
+/// let matches = command
+///     .clone()
+///     .try_get_matches_from(["app", "--policy", r#"{"retries": 3}"#])
+///     .unwrap();
+/// assert_eq!(
+///     Some(&json!({ "retries": 3 })),
+///     matches.get_one::<serde_json::Value>("policy"),
+/// );
+///
+/// let err = command
+///     .try_get_matches_from(["app", "--policy", r#"{"retries": -1}"#])
+///     .unwrap_err();
+/// assert!(err.to_string().contains("rejected by schema at \"/properties/retries/type\""));
+/// ```
+pub fn schema_value_parser(schema: impl Into<Arc<Schema>>) -> impl TypedValueParser<Value = Value> {
+    schema_value_parser_with_options(schema, ValidateOptions::new())
+}
+
+/// Like [`schema_value_parser`], but with the given options for validation.
+///
+/// Value parsers need to own everything they use, so they each keep the
+/// schema they're given. With a large schema, or many arguments, give them an
+/// `Arc<Schema>` to share, rather than a copy each.
+pub fn schema_value_parser_with_options(
+    schema: impl Into<Arc<Schema>>,
+    options: ValidateOptions,
+) -> impl TypedValueParser<Value = Value> {
+    let schema = schema.into();
+    StringValueParser::new().try_map(move |arg| {
+        let value = serde_json::from_str(&arg).map_err(ArgError::Json)?;
+        let errors = validate(&schema, &value, options.clone())?;
+        if !errors.is_empty() {
+            return Err(ArgError::Invalid(
+                errors
+                    .into_iter()
+                    .map(|error| error.into_owned().into())
+                    .collect(),
+            ));
+        }
+
+        Ok(value)
+    })
+}
+
+/// The reason an argument was rejected by a parser from
+/// [`schema_value_parser`].
+#[derive(Debug, Error)]
+pub enum ArgError {
+    /// The argument was not JSON.
+    #[error("invalid JSON: {0}")]
+    Json(serde_json::Error),
+
+    /// The argument did not satisfy the schema.
+    #[error("{}", InvalidDisplay(.0))]
+    Invalid(Vec<ValidationErrorIndicator<'static>>),
+
+    /// Validating the argument failed.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+}
+
+struct InvalidDisplay<'a>(&'a [ValidationErrorIndicator<'static>]);
+
+impl fmt::Display for InvalidDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+
+            write!(
+                f,
+                "value at {:?} rejected by schema at {:?}",
+                error.instance_path.to_string(),
+                error.schema_path.to_string()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::clap::{Arg, Command};
+    use serde_json::json;
+
+    #[test]
+    fn reports_reasons() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({ "elements": { "type": "string" }})).unwrap(),
+        )
+        .unwrap();
+
+        let command = Command::new("app").arg(
+            Arg::new("tags")
+                .long("tags")
+                .value_parser(schema_value_parser(schema)),
+        );
+
+        let err = command
+            .clone()
+            .try_get_matches_from(["app", "--tags", "[1"])
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid JSON"));
+
+        let err = command
+            .try_get_matches_from(["app", "--tags", r#"["a", 1, 2]"#])
+            .unwrap_err();
+        assert!(err.to_string().contains(
+            "value at \"/1\" rejected by schema at \"/elements/type\"; \
+             value at \"/2\" rejected by schema at \"/elements/type\""
+        ));
+    }
+}
@@ -0,0 +1,325 @@
+use crate::refs::resolve;
+use crate::{
+    validate, OwnedValidationErrorIndicator, Schema, Type, ValidateError, ValidateOptions,
+};
+use jtd_core::__private::ValidateOptionsExt as _;
+use serde_json::{Number, Value};
+use thiserror::Error;
+
+/// Errors that may arise from [`coerce()`].
+#[derive(Clone, Debug, PartialEq, Error)]
+pub enum CoerceError {
+    /// The instance is invalid, even after coercing what could be coerced.
+    #[error("instance is invalid: {} validation errors", errors.len())]
+    Invalid {
+        /// The instance, with what could be coerced coerced.
+        instance: Value,
+
+        /// The errors from validating the coerced instance.
+        errors: Vec<OwnedValidationErrorIndicator>,
+    },
+
+    /// Validating the coerced instance failed. See [`ValidateError`].
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+}
+
+/// Coerces an instance into the types its schema calls for, where that's
+/// unambiguous, and then validates it.
+///
+/// Data from query strings, form fields, spreadsheets, and the like is often
+/// all strings, or was written by hand. Coercing it fixes up what would
+/// otherwise be type errors:
+///
+/// * Strings that are numbers, like `"42"`, become numbers where the schema
+///   calls for a number and the string is a valid one. Integer types only
+///   accept strings that are integers in range.
+/// * `"true"` and `"false"` become booleans where the schema calls for a
+///   boolean.
+/// * Numbers that are integers become floating-point numbers where the
+///   schema calls for `float32` or `float64`, so that they serialize as, for
+///   instance, `1.0` rather than `1`.
+/// * Empty strings become `null` where the schema is nullable, and doesn't
+///   call for a string.
+/// * If `options` enables [case-insensitive
+///   enums][`ValidateOptions::with_case_insensitive_enums`] or [Unicode
+///   normalization][`ValidateOptions::with_unicode_normalization`], strings
+///   that match exactly one value of an enum that way become that value.
+///
+/// Values that are already valid are left as they are, and so are values
+/// that can't be coerced; the latter are then reported as validation errors.
+/// On success, this returns the coerced instance, which is valid against
+/// `schema`.
+///
+/// ```
+/// use jtd::{CoerceError, Schema, ValidateOptions};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "page": { "type": "uint32" },
+///         "sort": { "enum": ["ASC", "DESC"] },
+///         "exact": { "type": "boolean" },
+///         "min": { "type": "float64", "nullable": true }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let options = ValidateOptions::new().with_case_insensitive_enums(true);
+/// let instance = json!({ "page": "2", "sort": "desc", "exact": "true", "min": "" });
+///
+/// assert_eq!(
+///     json!({ "page": 2, "sort": "DESC", "exact": true, "min": null }),
+///     jtd::coerce(&schema, instance, options.clone()).unwrap(),
+/// );
+///
+/// let instance = json!({ "page": "-1", "sort": "asc", "exact": false, "min": 1 });
+/// match jtd::coerce(&schema, instance, options).unwrap_err() {
+///     CoerceError::Invalid { instance, errors } => {
+///         assert_eq!(json!({ "page": "-1", "sort": "ASC", "exact": false, "min": 1.0 }), instance);
+///         assert_eq!(1, errors.len());
+///         assert_eq!(vec!["page"], errors[0].instance_path);
+///     }
+///     err => panic!("{}", err),
+/// }
+/// ```
+pub fn coerce(
+    schema: &Schema,
+    instance: Value,
+    options: ValidateOptions,
+) -> Result<Value, CoerceError> {
+    // Coercing recurses into the instance, so its limits are checked first.
+    options.check_limits(&instance, 0)?;
+
+    let coercer = Coercer {
+        root: schema,
+        options: &options,
+    };
+
+    let instance = coercer.coerce(schema, None, instance);
+    let errors: Vec<_> = validate(schema, &instance, options.clone())?
+        .into_iter()
+        .map(|error| error.into_owned())
+        .collect();
+
+    if errors.is_empty() {
+        Ok(instance)
+    } else {
+        Err(CoerceError::Invalid { instance, errors })
+    }
+}
+
+struct Coercer<'a> {
+    root: &'a Schema,
+    options: &'a ValidateOptions,
+}
+
+impl<'a> Coercer<'a> {
+    fn coerce(&self, schema: &'a Schema, parent_tag: Option<&str>, instance: Value) -> Value {
+        let (schema, nullable) = resolve(self.root, schema);
+
+        match (schema, instance) {
+            (_, Value::String(s)) if s.is_empty() && nullable && !accepts_strings(schema) => {
+                Value::Null
+            }
+            (Schema::Type { type_, .. }, instance) => coerce_type(type_, instance),
+            (Schema::Enum { enum_, .. }, Value::String(s)) if !enum_.contains(&s) => {
+                let key = self.options.enum_key(&s);
+                let mut matches = enum_
+                    .iter()
+                    .filter(|value| self.options.enum_key(value) == key);
+
+                match (matches.next(), matches.next()) {
+                    (Some(value), None) => Value::String(value.clone()),
+                    _ => Value::String(s),
+                }
+            }
+            #[cfg(feature = "extensions")]
+            (Schema::IntEnum { .. }, Value::String(s)) => match s.parse::<i64>() {
+                Ok(i) => Value::from(i),
+                Err(_) => Value::String(s),
+            },
+            (Schema::Elements { elements, .. }, Value::Array(array)) => Value::Array(
+                array
+                    .into_iter()
+                    .map(|element| self.coerce(elements, None, element))
+                    .collect(),
+            ),
+            (
+                Schema::Properties {
+                    properties,
+                    optional_properties,
+                    ..
+                },
+                Value::Object(object),
+            ) => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let schema = match properties.get(&name) {
+                            Some(schema) => Some(schema),
+                            None => optional_properties.get(&name),
+                        };
+
+                        match schema {
+                            Some(schema) if parent_tag != Some(&name) => {
+                                let value = self.coerce(schema, None, value);
+                                (name, value)
+                            }
+                            _ => (name, value),
+                        }
+                    })
+                    .collect(),
+            ),
+            (Schema::Values { values, .. }, Value::Object(object)) => Value::Object(
+                object
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let value = self.coerce(values, None, value);
+                        (name, value)
+                    })
+                    .collect(),
+            ),
+            (
+                Schema::Discriminator {
+                    discriminator,
+                    mapping,
+                    ..
+                },
+                Value::Object(object),
+            ) => {
+                let variant = object
+                    .get(discriminator)
+                    .and_then(Value::as_str)
+                    .and_then(|tag| mapping.get(tag));
+
+                match variant {
+                    Some(variant) => {
+                        self.coerce(variant, Some(discriminator), Value::Object(object))
+                    }
+                    None => Value::Object(object),
+                }
+            }
+            (_, instance) => instance,
+        }
+    }
+}
+
+fn coerce_type(type_: &Type, instance: Value) -> Value {
+    let range: Option<(i64, i64)> = match type_ {
+        Type::Int8 => Some((i8::MIN.into(), i8::MAX.into())),
+        Type::Uint8 => Some((u8::MIN.into(), u8::MAX.into())),
+        Type::Int16 => Some((i16::MIN.into(), i16::MAX.into())),
+        Type::Uint16 => Some((u16::MIN.into(), u16::MAX.into())),
+        Type::Int32 => Some((i32::MIN.into(), i32::MAX.into())),
+        Type::Uint32 => Some((u32::MIN.into(), u32::MAX.into())),
+        _ => None,
+    };
+
+    match (type_, instance) {
+        (Type::Boolean, Value::String(s)) => match s.as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => Value::String(s),
+        },
+        (Type::Float32 | Type::Float64, Value::String(s)) => {
+            match s.trim().parse::<f64>().ok().and_then(Number::from_f64) {
+                Some(n) => Value::Number(n),
+                None => Value::String(s),
+            }
+        }
+        (Type::Float32 | Type::Float64, Value::Number(n)) if !n.is_f64() => {
+            match n.as_f64().and_then(Number::from_f64) {
+                Some(n) => Value::Number(n),
+                None => Value::Number(n),
+            }
+        }
+        (_, Value::String(s)) => match (range, s.trim().parse::<i64>()) {
+            (Some((min, max)), Ok(i)) if i >= min && i <= max => Value::from(i),
+            _ => Value::String(s),
+        },
+        (_, instance) => instance,
+    }
+}
+
+fn accepts_strings(schema: &Schema) -> bool {
+    matches!(
+        schema,
+        Schema::Empty { .. }
+            | Schema::Type {
+                type_: Type::String | Type::Timestamp,
+                ..
+            }
+            | Schema::Enum { .. }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn coerces_where_unambiguous() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "id": { "type": "uint8", "nullable": true }
+                },
+                "properties": {
+                    "ids": { "elements": { "ref": "id" }},
+                    "name": { "type": "string", "nullable": true },
+                    "scores": { "values": { "type": "float32" }},
+                    "event": {
+                        "discriminator": "type",
+                        "mapping": {
+                            "1": { "properties": { "at": { "type": "int16" }}}
+                        }
+                    },
+                    "color": { "enum": ["RED", "Red", "GREEN"] }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let options = ValidateOptions::new().with_case_insensitive_enums(true);
+        assert_eq!(
+            Ok(json!({
+                "ids": [1, null, 255],
+                "name": "",
+                "scores": { "a": 1.5, "b": 2.0 },
+                "event": { "type": "1", "at": -300 },
+                "color": "GREEN"
+            })),
+            coerce(
+                &schema,
+                json!({
+                    "ids": ["1", "", 255],
+                    "name": "",
+                    "scores": { "a": "1.5", "b": 2 },
+                    "event": { "type": "1", "at": " -300 " },
+                    "color": "green"
+                }),
+                options.clone()
+            )
+        );
+
+        // "red" matches two values, so there's no telling which it's meant
+        // to be, but it's valid all the same with these options.
+        let instance = json!({
+            "ids": ["256", "1.5"],
+            "name": null,
+            "scores": {},
+            "event": { "type": 1 },
+            "color": "red"
+        });
+        match coerce(&schema, instance, options) {
+            Err(CoerceError::Invalid { instance, errors }) => {
+                assert_eq!(json!(["256", "1.5"]), instance["ids"]);
+                assert_eq!(json!("red"), instance["color"]);
+                assert_eq!(3, errors.len());
+            }
+            result => panic!("{:?}", result),
+        }
+    }
+}
@@ -0,0 +1,723 @@
+use crate::{
+    Schema, SchemaValidateError, Severity, Type, ValidateError, ValidateOptions,
+    ValidationErrorIndicator,
+};
+use jtd_core::__private::{type_check, ValidateOptionsExt as _};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A schema prepared for validating many instances quickly.
+///
+/// [`validate()`][`crate::validate()`] works from the [`Schema`] as it is,
+/// looking up each `ref` by name and working out what every keyword means
+/// anew on each call. A `CompiledSchema` does that work once, up front: refs
+/// are resolved ahead of time, every schema and definition is flattened into
+/// a single array, the bounds of integer types are worked out, and the
+/// schema path of every part of the schema is precomputed, so that paths are
+/// only put together when there's an error to report.
+///
+/// [`CompiledSchema::validate`] returns exactly what [`validate()`][`crate::validate()`]
+/// does for the same schema, instance, and options. Compiling has a cost of
+/// its own, so it's worth it when the same schema validates many instances.
+///
+/// ```
+/// use jtd::{CompiledSchema, Schema, ValidateOptions};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "definitions": { "port": { "type": "uint16" }},
+///     "properties": { "ports": { "elements": { "ref": "port" }}}
+/// })).unwrap()).unwrap();
+///
+/// let compiled = CompiledSchema::new(&schema).unwrap();
+///
+/// for instance in [json!({ "ports": [80, 443] }), json!({ "ports": [80, 65536] })] {
+///     assert_eq!(
+///         jtd::validate(&schema, &instance, ValidateOptions::new()).unwrap(),
+///         compiled.validate(&instance, ValidateOptions::new()).unwrap(),
+///     );
+/// }
+///
+/// let instance = json!({ "ports": [-1] });
+/// let errors = compiled.validate(&instance, ValidateOptions::new()).unwrap();
+/// assert_eq!(vec!["ports", "0"], errors[0].instance_path);
+/// assert_eq!(vec!["definitions", "port", "type"], errors[0].schema_path);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CompiledSchema {
+    // Every schema within the compiled one. The root is first.
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    // The schema path of this schema within its definition, or the root.
+    path: Vec<String>,
+    nullable: bool,
+    severity: Option<Severity>,
+    form: Form,
+}
+
+// A schema form, with sub-schemas given by their index into the nodes of a
+// CompiledSchema.
+#[derive(Clone, Debug)]
+enum Form {
+    Empty,
+    Ref(usize),
+    Type(Check),
+    Enum(BTreeSet<String>),
+    #[cfg(feature = "extensions")]
+    IntEnum(BTreeSet<i64>),
+    Elements(usize),
+    Properties {
+        properties: BTreeMap<String, usize>,
+        optional_properties: BTreeMap<String, usize>,
+        properties_is_present: bool,
+        additional_properties: bool,
+    },
+    Values {
+        values: usize,
+        #[cfg(feature = "extensions")]
+        keys: Option<crate::Keys>,
+    },
+    Discriminator {
+        discriminator: String,
+        mapping: BTreeMap<String, usize>,
+    },
+}
+
+// How to check a value against a type.
+#[derive(Clone, Debug)]
+enum Check {
+    // An integer between the bounds, inclusive.
+    Int(f64, f64),
+    Other(fn(&Value) -> bool),
+    // Timestamps are left to the options, which may have a validator of
+    // their own.
+    Timestamp,
+    #[cfg(feature = "extensions")]
+    Custom(String),
+}
+
+impl CompiledSchema {
+    /// Compiles a schema.
+    ///
+    /// Errors if the schema isn't valid, according to [`Schema::validate`].
+    ///
+    /// ```
+    /// use jtd::{CompiledSchema, Schema, SchemaValidateError};
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(
+    ///     serde_json::from_value(json!({ "ref": "missing" })).unwrap(),
+    /// ).unwrap();
+    ///
+    /// assert_eq!(
+    ///     SchemaValidateError::NoSuchDefinition("missing".to_owned()),
+    ///     CompiledSchema::new(&schema).unwrap_err(),
+    /// );
+    /// ```
+    pub fn new(schema: &Schema) -> Result<Self, SchemaValidateError> {
+        schema.validate()?;
+
+        // Definitions are compiled after the root, so refs to them are noted
+        // down and filled in once their indices are known.
+        let mut compiler = Compiler {
+            nodes: Vec::new(),
+            refs: Vec::new(),
+        };
+
+        compiler.compile(schema, Vec::new());
+
+        let mut definitions = BTreeMap::new();
+        for (name, definition) in schema.definitions() {
+            let index = compiler.compile(definition, vec!["definitions".into(), name.clone()]);
+            definitions.insert(name, index);
+        }
+
+        for (index, name) in compiler.refs {
+            compiler.nodes[index].form = Form::Ref(definitions[&name]);
+        }
+
+        Ok(Self {
+            nodes: compiler.nodes,
+        })
+    }
+
+    /// Validates an instance against the schema, with the same results as
+    /// [`validate()`][`crate::validate()`] against the schema it was compiled
+    /// from.
+    pub fn validate<'a>(
+        &'a self,
+        instance: &'a Value,
+        options: ValidateOptions,
+    ) -> Result<Vec<ValidationErrorIndicator<'a>>, ValidateError> {
+        // As in validate(), the first error is looked for without paths, and
+        // then looked for again to find its path.
+        let track_paths = options.max_errors() != 1;
+        let mut vm = Vm::new(self, options, track_paths);
+        let mut result = vm.validate(0, None, instance);
+
+        if !track_paths && matches!(result, Err(VmValidateError::MaxErrorsReached)) {
+            vm = Vm::new(self, vm.options.without_progress(), true);
+            result = vm.validate(0, None, instance);
+        }
+
+        match result {
+            Ok(()) | Err(VmValidateError::MaxErrorsReached) => Ok(vm.errors),
+            Err(VmValidateError::MaxDepthExceeded) => Err(ValidateError::MaxDepthExceeded),
+            Err(VmValidateError::Cancelled) => Err(ValidateError::Cancelled),
+            Err(VmValidateError::LimitExceeded(err)) => Err(err),
+            #[cfg(feature = "extensions")]
+            Err(VmValidateError::UnknownType(name)) => Err(ValidateError::UnknownType(name)),
+        }
+    }
+}
+
+struct Compiler {
+    nodes: Vec<Node>,
+
+    // The refs yet to be resolved, by the index of their node.
+    refs: Vec<(usize, String)>,
+}
+
+impl Compiler {
+    // Compiles a schema at the given schema path, returning its index.
+    fn compile(&mut self, schema: &Schema, path: Vec<String>) -> usize {
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            path: path.clone(),
+            nullable: schema.nullable(),
+            severity: Severity::from_metadata(schema.metadata()),
+            form: Form::Empty,
+        });
+
+        let sub_path = |tokens: &[&str]| {
+            let mut sub_path = path.clone();
+            sub_path.extend(tokens.iter().map(|&token| token.to_owned()));
+            sub_path
+        };
+
+        let form = match schema {
+            Schema::Empty { .. } => Form::Empty,
+            Schema::Ref { ref_, .. } => {
+                self.refs.push((index, ref_.clone()));
+                Form::Empty
+            }
+            Schema::Type { type_, .. } => Form::Type(match type_ {
+                Type::Int8 => Check::Int(i8::MIN.into(), i8::MAX.into()),
+                Type::Uint8 => Check::Int(u8::MIN.into(), u8::MAX.into()),
+                Type::Int16 => Check::Int(i16::MIN.into(), i16::MAX.into()),
+                Type::Uint16 => Check::Int(u16::MIN.into(), u16::MAX.into()),
+                Type::Int32 => Check::Int(i32::MIN.into(), i32::MAX.into()),
+                Type::Uint32 => Check::Int(u32::MIN.into(), u32::MAX.into()),
+                Type::Timestamp => Check::Timestamp,
+                #[cfg(feature = "extensions")]
+                Type::Custom(name) => Check::Custom(name.clone()),
+                _ => Check::Other(type_check(type_).expect("only custom types lack checks")),
+            }),
+            Schema::Enum { enum_, .. } => Form::Enum(enum_.clone()),
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { enum_, .. } => Form::IntEnum(enum_.clone()),
+            Schema::Elements { elements, .. } => {
+                Form::Elements(self.compile(elements, sub_path(&["elements"])))
+            }
+            Schema::Properties {
+                properties,
+                optional_properties,
+                properties_is_present,
+                additional_properties,
+                ..
+            } => Form::Properties {
+                properties: properties
+                    .iter()
+                    .map(|(name, sub_schema)| {
+                        let sub_path = sub_path(&["properties", name]);
+                        (name.clone(), self.compile(sub_schema, sub_path))
+                    })
+                    .collect(),
+                optional_properties: optional_properties
+                    .iter()
+                    .map(|(name, sub_schema)| {
+                        let sub_path = sub_path(&["optionalProperties", name]);
+                        (name.clone(), self.compile(sub_schema, sub_path))
+                    })
+                    .collect(),
+                properties_is_present: *properties_is_present,
+                additional_properties: *additional_properties,
+            },
+            Schema::Values {
+                values,
+                #[cfg(feature = "extensions")]
+                keys,
+                ..
+            } => Form::Values {
+                values: self.compile(values, sub_path(&["values"])),
+                #[cfg(feature = "extensions")]
+                keys: keys.clone(),
+            },
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } => Form::Discriminator {
+                discriminator: discriminator.clone(),
+                mapping: mapping
+                    .iter()
+                    .map(|(tag, sub_schema)| {
+                        let sub_path = sub_path(&["mapping", tag]);
+                        (tag.clone(), self.compile(sub_schema, sub_path))
+                    })
+                    .collect(),
+            },
+        };
+
+        self.nodes[index].form = form;
+        index
+    }
+}
+
+struct Vm<'a> {
+    nodes: &'a [Node],
+    options: ValidateOptions,
+    instance_tokens: Vec<Cow<'a, str>>,
+    // The number of refs being followed, plus one.
+    depth: usize,
+    // The number of arrays and objects the value being validated is nested
+    // in.
+    instance_depth: usize,
+    errors: Vec<ValidationErrorIndicator<'a>>,
+    visited: u64,
+    severity: Severity,
+    track_paths: bool,
+}
+
+enum VmValidateError {
+    MaxErrorsReached,
+    MaxDepthExceeded,
+    Cancelled,
+    LimitExceeded(ValidateError),
+    #[cfg(feature = "extensions")]
+    UnknownType(String),
+}
+
+impl<'a> Vm<'a> {
+    fn new(schema: &'a CompiledSchema, options: ValidateOptions, track_paths: bool) -> Self {
+        Self {
+            nodes: &schema.nodes,
+            options,
+            instance_tokens: vec![],
+            depth: 1,
+            instance_depth: 0,
+            errors: vec![],
+            visited: 0,
+            severity: Severity::Error,
+            track_paths,
+        }
+    }
+
+    fn validate(
+        &mut self,
+        index: usize,
+        parent_tag: Option<&'a str>,
+        instance: &'a Value,
+    ) -> Result<(), VmValidateError> {
+        if self.options.is_cancelled() {
+            return Err(VmValidateError::Cancelled);
+        }
+
+        let nodes = self.nodes;
+        let node = &nodes[index];
+        if !matches!(node.form, Form::Ref(_)) {
+            self.visited += 1;
+            self.options.report_progress(self.visited);
+        }
+
+        if instance.is_null() && node.nullable {
+            return Ok(());
+        }
+
+        self.check_limits(node, instance)?;
+
+        let saved_severity = self.severity;
+        if let Some(severity) = node.severity {
+            self.severity = severity;
+        }
+
+        match &node.form {
+            Form::Empty => {}
+            Form::Ref(definition) => {
+                self.depth += 1;
+                if self.depth == self.options.max_depth() {
+                    return Err(VmValidateError::MaxDepthExceeded);
+                }
+
+                self.validate(*definition, None, instance)?;
+                self.depth -= 1;
+            }
+            Form::Type(check) => {
+                let ok = match check {
+                    Check::Int(min, max) => instance
+                        .as_f64()
+                        .is_some_and(|val| val.fract() == 0.0 && val >= *min && val <= *max),
+                    Check::Other(check) => check(instance),
+                    Check::Timestamp => instance
+                        .as_str()
+                        .is_some_and(|s| self.options.is_timestamp(s)),
+                    #[cfg(feature = "extensions")]
+                    Check::Custom(name) => {
+                        match self.options.type_extensions().check(name, instance) {
+                            Some(ok) => ok,
+                            None => return Err(VmValidateError::UnknownType(name.clone())),
+                        }
+                    }
+                };
+
+                if !ok {
+                    self.push_error(node, &["type"])?;
+                }
+            }
+            Form::Enum(enum_) => {
+                let ok = instance
+                    .as_str()
+                    .is_some_and(|s| self.options.enum_contains(enum_, s));
+
+                if !ok {
+                    self.push_error(node, &["enum"])?;
+                }
+            }
+            #[cfg(feature = "extensions")]
+            Form::IntEnum(enum_) => {
+                let ok = match instance {
+                    Value::Number(n) => match n.as_i64() {
+                        Some(i) => enum_.contains(&i),
+                        None => n
+                            .as_f64()
+                            .is_some_and(|f| jtd_core::__private::int_enum_contains(enum_, f)),
+                    },
+                    _ => false,
+                };
+
+                if !ok {
+                    self.push_error(node, &["enum"])?;
+                }
+            }
+            Form::Elements(elements) => {
+                if let Some(arr) = instance.as_array() {
+                    for (i, sub_instance) in arr.iter().enumerate() {
+                        if self.track_paths {
+                            self.instance_tokens.push(Cow::Owned(i.to_string()));
+                        }
+
+                        self.validate_part(*elements, sub_instance)?;
+                        self.instance_tokens.pop();
+                    }
+                } else {
+                    self.push_error(node, &["elements"])?;
+                }
+            }
+            Form::Properties {
+                properties,
+                optional_properties,
+                properties_is_present,
+                additional_properties,
+            } => {
+                if let Some(obj) = instance.as_object() {
+                    for (name, &sub_index) in properties {
+                        if let Some(sub_instance) = obj.get(name) {
+                            self.push_instance_token(name);
+                            self.validate_part(sub_index, sub_instance)?;
+                            self.instance_tokens.pop();
+                        } else if !self.options.ignore_missing_required() {
+                            // The property's schema says how serious it is
+                            // for the property to be missing.
+                            let saved_severity = self.severity;
+                            if let Some(severity) = self.nodes[sub_index].severity {
+                                self.severity = severity;
+                            }
+
+                            self.push_error(node, &["properties", name])?;
+                            self.severity = saved_severity;
+                        }
+                    }
+
+                    for (name, &sub_index) in optional_properties {
+                        if let Some(sub_instance) = obj.get(name) {
+                            self.push_instance_token(name);
+                            self.validate_part(sub_index, sub_instance)?;
+                            self.instance_tokens.pop();
+                        }
+                    }
+
+                    if !*additional_properties {
+                        for name in obj.keys() {
+                            if parent_tag != Some(name)
+                                && !properties.contains_key(name)
+                                && !optional_properties.contains_key(name)
+                            {
+                                self.push_instance_token(name);
+                                self.push_error(node, &[])?;
+                                self.instance_tokens.pop();
+                            }
+                        }
+                    }
+                } else if *properties_is_present {
+                    self.push_error(node, &["properties"])?;
+                } else {
+                    self.push_error(node, &["optionalProperties"])?;
+                }
+            }
+            Form::Values {
+                values,
+                #[cfg(feature = "extensions")]
+                keys,
+            } => {
+                if let Some(obj) = instance.as_object() {
+                    for (name, sub_instance) in obj {
+                        self.push_instance_token(name);
+
+                        #[cfg(feature = "extensions")]
+                        if let Some(keys) = keys {
+                            if let Some(keyword) = self.options.reject_key(keys, name) {
+                                self.push_error(node, &["keys", keyword])?;
+                            }
+                        }
+
+                        self.validate_part(*values, sub_instance)?;
+                        self.instance_tokens.pop();
+                    }
+                } else {
+                    self.push_error(node, &["values"])?;
+                }
+            }
+            Form::Discriminator {
+                discriminator,
+                mapping,
+            } => match instance.as_object() {
+                Some(obj) => match obj.get(discriminator) {
+                    Some(tag) => match tag.as_str() {
+                        Some(tag) => match mapping.get(tag) {
+                            Some(&sub_index) => {
+                                self.validate(sub_index, Some(discriminator), instance)?
+                            }
+                            None => {
+                                self.push_instance_token(discriminator);
+                                self.push_error(node, &["mapping"])?;
+                                self.instance_tokens.pop();
+                            }
+                        },
+                        None => {
+                            self.push_instance_token(discriminator);
+                            self.push_error(node, &["discriminator"])?;
+                            self.instance_tokens.pop();
+                        }
+                    },
+                    None => self.push_error(node, &["discriminator"])?,
+                },
+                None => self.push_error(node, &["discriminator"])?,
+            },
+        }
+
+        self.severity = saved_severity;
+        Ok(())
+    }
+
+    // Validates an element of an array, or a value of an object, against a
+    // node.
+    fn validate_part(&mut self, index: usize, instance: &'a Value) -> Result<(), VmValidateError> {
+        self.instance_depth += 1;
+        self.validate(index, None, instance)?;
+        self.instance_depth -= 1;
+        Ok(())
+    }
+
+    // Checks an instance against the per-value limits of the options, as in
+    // validate(): the parts of it that the node looks into are checked as
+    // they're visited, so only the rest are checked here, in full.
+    fn check_limits(&self, node: &Node, instance: &Value) -> Result<(), VmValidateError> {
+        if !self.options.has_limits() {
+            return Ok(());
+        }
+
+        let depth = self.instance_depth;
+        let result = match (&node.form, instance) {
+            (Form::Ref(_), _) => Ok(()),
+            (Form::Elements(_), Value::Array(_)) | (Form::Values { .. }, Value::Object(_)) => {
+                self.options.check_value_limits(instance, depth)
+            }
+            (
+                Form::Properties {
+                    properties,
+                    optional_properties,
+                    ..
+                },
+                Value::Object(obj),
+            ) => self
+                .options
+                .check_value_limits(instance, depth)
+                .and_then(|()| {
+                    obj.iter()
+                        .filter(|(name, _)| {
+                            !properties.contains_key(*name)
+                                && !optional_properties.contains_key(*name)
+                        })
+                        .try_for_each(|(_, sub_instance)| {
+                            self.options.check_limits(sub_instance, depth + 1)
+                        })
+                }),
+            (
+                Form::Discriminator {
+                    discriminator,
+                    mapping,
+                },
+                Value::Object(obj),
+            ) if obj
+                .get(discriminator)
+                .and_then(Value::as_str)
+                .is_some_and(|tag| mapping.contains_key(tag)) =>
+            {
+                Ok(())
+            }
+            _ => self.options.check_limits(instance, depth),
+        };
+
+        result.map_err(VmValidateError::LimitExceeded)
+    }
+
+    // Records an error found by a node, with the given tokens after the
+    // node's own schema path.
+    fn push_error(&mut self, node: &'a Node, tokens: &[&'a str]) -> Result<(), VmValidateError> {
+        if self.severity < self.options.min_severity() {
+            return Ok(());
+        }
+
+        let (instance_path, schema_path) = if self.track_paths {
+            (
+                self.instance_tokens[..].into(),
+                node.path
+                    .iter()
+                    .map(|token| token.as_str())
+                    .chain(tokens.iter().copied())
+                    .map(Cow::Borrowed)
+                    .collect(),
+            )
+        } else {
+            (Default::default(), Default::default())
+        };
+
+        self.errors.push(ValidationErrorIndicator {
+            instance_path,
+            schema_path,
+            severity: self.severity,
+        });
+
+        if self.options.max_errors() == self.errors.len() {
+            Err(VmValidateError::MaxErrorsReached)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn push_instance_token(&mut self, token: &'a str) {
+        if self.track_paths {
+            self.instance_tokens.push(Cow::Borrowed(token));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CompiledSchema;
+    use crate::{validate, Schema, SerdeSchema, Severity, ValidateError, ValidateOptions};
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn matches_validate_on_spec() {
+        #[derive(serde::Deserialize)]
+        struct TestCase {
+            schema: SerdeSchema,
+            instance: Value,
+        }
+
+        let test_cases: BTreeMap<String, TestCase> =
+            serde_json::from_str(include_str!("../json-typedef-spec/tests/validation.json"))
+                .expect("parse validation.json");
+
+        for (test_case_name, test_case) in test_cases {
+            let schema = Schema::from_serde_schema(test_case.schema).expect(&test_case_name);
+            let compiled = CompiledSchema::new(&schema).expect(&test_case_name);
+
+            for max_errors in 0..3 {
+                let options = ValidateOptions::new().with_max_errors(max_errors);
+                assert_eq!(
+                    validate(&schema, &test_case.instance, options.clone()),
+                    compiled.validate(&test_case.instance, options),
+                    "{} with max_errors {}",
+                    test_case_name,
+                    max_errors
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_validate_with_options() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "loop": { "elements": { "ref": "loop" }},
+                    "color": {
+                        "enum": ["Red", "Green"],
+                        "metadata": { "severityOnViolation": "warning" }
+                    }
+                },
+                "properties": {
+                    "loop": { "ref": "loop" },
+                    "colors": { "values": { "ref": "color" }},
+                    "note": {
+                        "type": "string",
+                        "metadata": { "severityOnViolation": "info" }
+                    },
+                    "times": { "elements": { "type": "timestamp" }}
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let compiled = CompiledSchema::new(&schema).unwrap();
+        let instance = json!({
+            "loop": [[[[]]]],
+            "colors": { "a": "red", "b": "blue" },
+            "times": ["2020-01-01T00:00:00Z", "2020-01-01T00:00:60Z"]
+        });
+
+        for options in [
+            ValidateOptions::new(),
+            ValidateOptions::new().with_min_severity(Severity::Info),
+            ValidateOptions::new().with_case_insensitive_enums(true),
+            ValidateOptions::new().with_max_depth(4),
+            ValidateOptions::new().with_max_array_len(1),
+            ValidateOptions::new().with_max_string_len(3),
+            ValidateOptions::new().with_max_object_keys(2),
+            ValidateOptions::new().with_max_instance_depth(3),
+            ValidateOptions::new()
+                .with_max_errors(1)
+                .with_max_string_len(3),
+            ValidateOptions::new().with_timestamp_validator(|s| !s.contains(":60")),
+            ValidateOptions::new().with_ignore_missing_required(true),
+        ] {
+            assert_eq!(
+                validate(&schema, &instance, options.clone()),
+                compiled.validate(&instance, options)
+            );
+        }
+
+        assert_eq!(
+            Err(ValidateError::MaxDepthExceeded),
+            compiled.validate(&instance, ValidateOptions::new().with_max_depth(4))
+        );
+    }
+}
@@ -0,0 +1,984 @@
+//! Validate configuration files against schemas.
+//!
+//! Configuration is often written in a format other than JSON, and when it's
+//! wrong, the most useful thing to tell its author is where in the file the
+//! problem is. The functions in this module parse a configuration file,
+//! convert it to JSON, validate it against a schema, and report each
+//! validation error along with the line and column of the offending value.
+//!
+//! [`validate_json_str`] is always available. `validate_jsonc_str`, for JSON
+//! with comments, requires the `jsonc` feature. [`validate_yaml_str`] requires
+//! the `yaml` feature, and [`validate_toml_str`] requires the `toml` feature.
+//!
+//! With the `figment` feature, [`validate_figment`] and [`extract_validated`]
+//! do the same for configuration layered together with
+//! [Figment](https://docs.rs/figment), reporting which layer supplied each
+//! rejected value.
+
+use crate::{validate, InstancePath, Schema, SchemaPath, ValidateError, ValidateOptions};
+use jtd_core::__private::{JsonScanner, Locations};
+use serde_json::Value;
+use std::fmt;
+use thiserror::Error;
+
+pub use jtd_core::__private::Location;
+
+/// A validation error in a configuration file.
+///
+/// This is like a [`ValidationErrorIndicator`][`crate::ValidationErrorIndicator`],
+/// with the addition of where the rejected value is in the file. For errors
+/// about something missing, such as a required property, the location is that
+/// of the object it's missing from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigValidationError {
+    /// The path to the part of the configuration that was rejected.
+    pub instance_path: Vec<String>,
+
+    /// The path to the part of the schema that rejected it.
+    pub schema_path: Vec<String>,
+
+    /// Where the rejected part of the configuration starts.
+    pub location: Location,
+}
+
+impl fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: value at {:?} rejected by schema at {:?}",
+            self.location,
+            InstancePath::from(&self.instance_path[..]).to_string(),
+            SchemaPath::from(&self.schema_path[..]).to_string()
+        )
+    }
+}
+
+/// Errors that may arise from the functions in this module.
+///
+/// These are errors that prevent validation from happening at all. Validation
+/// errors are instead returned as [`ConfigValidationError`]s.
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    /// The configuration was not valid JSON.
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The configuration was not valid YAML.
+    #[cfg(feature = "yaml")]
+    #[error("invalid YAML: {0}")]
+    Yaml(#[from] yaml_rust2::ScanError),
+
+    /// The configuration was not valid TOML.
+    #[cfg(feature = "toml")]
+    #[error("invalid TOML: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    /// The configuration contained a value with no JSON equivalent, such as an
+    /// infinite number, or a YAML mapping used as a key.
+    #[error("{0}: value has no JSON equivalent")]
+    NotJson(Location),
+
+    /// Validating the configuration failed.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+
+    /// Figment could not produce the merged configuration.
+    #[cfg(feature = "figment")]
+    #[error(transparent)]
+    Figment(Box<figment::Error>),
+
+    /// The configuration did not satisfy the schema. Returned by
+    /// [`extract_validated`].
+    #[cfg(feature = "figment")]
+    #[error("configuration rejected: {}{}", .0[0], if .0.len() > 1 { format!(", and {} more", .0.len() - 1) } else { String::new() })]
+    Rejected(Vec<FigmentValidationError>),
+}
+
+#[cfg(feature = "figment")]
+impl From<figment::Error> for ConfigError {
+    fn from(err: figment::Error) -> Self {
+        Self::Figment(Box::new(err))
+    }
+}
+
+/// Validates a JSON configuration file against a schema.
+///
+/// ```
+/// use jtd::Schema;
+/// use jtd::config::Location;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "port": { "type": "uint16" }}
+/// })).unwrap()).unwrap();
+///
+/// let config = r#"{
+///   "port": "8080"
+/// }"#;
+///
+/// let errors = jtd::config::validate_json_str(&schema, config, Default::default()).unwrap();
+/// assert_eq!(1, errors.len());
+/// assert_eq!(Location { line: 2, column: 11 }, errors[0].location);
+/// ```
+pub fn validate_json_str(
+    schema: &Schema,
+    input: &str,
+    options: ValidateOptions,
+) -> Result<Vec<ConfigValidationError>, ConfigError> {
+    let value = serde_json::from_str(input)?;
+
+    let mut scanner = JsonScanner::new(input);
+
+    // The input is known to be valid JSON at this point, so the scanner only
+    // gives up if something is badly wrong. The root location is always known,
+    // so errors are still reported, just less precisely.
+    let _ = scanner.value();
+
+    report(schema, &value, &scanner.locations, options)
+}
+
+/// Validates a JSONC configuration file against a schema.
+///
+/// This is [`validate_json_str`], applied to the output of
+/// [`jsonc::strip`][`crate::jsonc::strip`]. Locations, including those in
+/// parse errors, refer to the original input.
+///
+/// This function is only available with the `jsonc` feature.
+///
+/// ```
+/// use jtd::Schema;
+/// use jtd::config::Location;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "port": { "type": "uint16" }}
+/// })).unwrap()).unwrap();
+///
+/// let config = r#"{
+///   /* The default is 80. */ "port": "8080", // must be a number
+/// }"#;
+///
+/// let errors = jtd::config::validate_jsonc_str(&schema, config, Default::default()).unwrap();
+/// assert_eq!(1, errors.len());
+/// assert_eq!(Location { line: 2, column: 36 }, errors[0].location);
+/// ```
+#[cfg(feature = "jsonc")]
+pub fn validate_jsonc_str(
+    schema: &Schema,
+    input: &str,
+    options: ValidateOptions,
+) -> Result<Vec<ConfigValidationError>, ConfigError> {
+    validate_json_str(schema, &crate::jsonc::strip(input), options)
+}
+
+/// Validates a YAML configuration file against a schema.
+///
+/// Only the first document in the file is validated. Scalars are converted to
+/// JSON following the YAML 1.2 core schema, so unquoted `true`, `null`, `12`,
+/// and so on become booleans, nulls, and numbers, while anything quoted is a
+/// string. Aliases are expanded.
+///
+/// This function is only available with the `yaml` feature.
+///
+/// ```
+/// use jtd::Schema;
+/// use jtd::config::Location;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "servers": {
+///             "elements": { "properties": { "port": { "type": "uint16" }}}
+///         }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let config = "
+/// servers:
+///   - port: 80
+///   - port: 99999
+/// ";
+///
+/// let errors = jtd::config::validate_yaml_str(&schema, config, Default::default()).unwrap();
+/// assert_eq!(1, errors.len());
+/// assert_eq!(vec!["servers", "1", "port"], errors[0].instance_path);
+/// assert_eq!(Location { line: 4, column: 11 }, errors[0].location);
+/// ```
+#[cfg(feature = "yaml")]
+pub fn validate_yaml_str(
+    schema: &Schema,
+    input: &str,
+    options: ValidateOptions,
+) -> Result<Vec<ConfigValidationError>, ConfigError> {
+    let mut builder = yaml::Builder::default();
+    yaml_rust2::parser::Parser::new_from_str(input).load(&mut builder, false)?;
+
+    let (value, locations) = builder.finish()?;
+    report(schema, &value, &locations, options)
+}
+
+/// Validates a TOML configuration file against a schema.
+///
+/// Dates and times are converted to strings in their TOML representation,
+/// which for offset date-times is an RFC 3339 timestamp.
+///
+/// This function is only available with the `toml` feature.
+///
+/// ```
+/// use jtd::Schema;
+/// use jtd::config::Location;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "server": { "properties": { "host": { "type": "string" }}}
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let config = "
+/// [server]
+/// host = 127
+/// ";
+///
+/// let errors = jtd::config::validate_toml_str(&schema, config, Default::default()).unwrap();
+/// assert_eq!(1, errors.len());
+/// assert_eq!(Location { line: 3, column: 8 }, errors[0].location);
+/// ```
+#[cfg(feature = "toml")]
+pub fn validate_toml_str(
+    schema: &Schema,
+    input: &str,
+    options: ValidateOptions,
+) -> Result<Vec<ConfigValidationError>, ConfigError> {
+    let table: toml_support::Table = toml::from_str(input)?;
+
+    let lines = jtd_core::__private::LineIndex::new(input);
+    let mut locations = Locations::new();
+    locations.insert(Vec::new(), Location { line: 1, column: 1 });
+
+    let value = table.into_value(&lines, &mut Vec::new(), &mut locations)?;
+    report(schema, &value, &locations, options)
+}
+
+/// A validation error in a [`Figment`][`figment::Figment`] configuration.
+///
+/// This is like a [`ValidationErrorIndicator`][`crate::ValidationErrorIndicator`],
+/// with the addition of the metadata of the provider -- the layer of the
+/// configuration -- that supplied the rejected value. For errors about
+/// something missing, such as a required property, the metadata is that of
+/// the object it's missing from. It's `None` if no single provider supplied
+/// the value, which is the case for the root of the configuration.
+#[cfg(feature = "figment")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FigmentValidationError {
+    /// The path to the part of the configuration that was rejected.
+    pub instance_path: Vec<String>,
+
+    /// The path to the part of the schema that rejected it.
+    pub schema_path: Vec<String>,
+
+    /// The metadata of the provider that supplied the rejected value.
+    pub metadata: Option<figment::Metadata>,
+}
+
+#[cfg(feature = "figment")]
+impl fmt::Display for FigmentValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "value at {:?} rejected by schema at {:?}",
+            InstancePath::from(&self.instance_path[..]).to_string(),
+            SchemaPath::from(&self.schema_path[..]).to_string()
+        )?;
+
+        match &self.metadata {
+            Some(figment::Metadata {
+                name,
+                source: Some(source),
+                ..
+            }) => write!(f, " (from {} {})", name, source),
+            Some(metadata) => write!(f, " (from {})", metadata.name),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Validates the merged configuration of a [`Figment`][`figment::Figment`]
+/// against a schema.
+///
+/// The configuration is validated as it would be extracted, using the
+/// figment's selected profile. Each error records which of the figment's
+/// providers supplied the rejected value.
+///
+/// This function is only available with the `figment` feature.
+///
+/// ```
+/// use figment::{Figment, providers::Serialized};
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "port": { "type": "uint16" }}
+/// })).unwrap()).unwrap();
+///
+/// let figment = Figment::from(Serialized::defaults(json!({ "port": 8080 })))
+///     .merge(Serialized::globals(json!({ "port": -1 })));
+///
+/// let errors = jtd::config::validate_figment(&schema, &figment, Default::default()).unwrap();
+/// assert_eq!(1, errors.len());
+/// assert_eq!(vec!["port"], errors[0].instance_path);
+/// assert!(errors[0].metadata.is_some());
+/// ```
+#[cfg(feature = "figment")]
+pub fn validate_figment(
+    schema: &Schema,
+    figment: &figment::Figment,
+    options: ValidateOptions,
+) -> Result<Vec<FigmentValidationError>, ConfigError> {
+    let value: Value = figment.extract()?;
+    let root = figment.find_value("")?;
+
+    Ok(validate(schema, &value, options)?
+        .into_iter()
+        .map(|error| {
+            let (instance_path, schema_path) = error.into_owned_paths();
+            let metadata = figment_metadata(figment, &root, &instance_path).cloned();
+
+            FigmentValidationError {
+                instance_path,
+                schema_path,
+                metadata,
+            }
+        })
+        .collect())
+}
+
+/// Extracts a value from a [`Figment`][`figment::Figment`], after checking
+/// that the merged configuration satisfies a schema.
+///
+/// If the configuration doesn't satisfy the schema, this returns
+/// [`ConfigError::Rejected`] with all of the validation errors, rather than
+/// attempting extraction. That way, a misconfiguration is reported in terms
+/// of the schema, and of the providers that are to blame.
+///
+/// This function is only available with the `figment` feature.
+///
+/// ```
+/// use figment::{Figment, providers::Serialized};
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Config {
+///     port: u16,
+/// }
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "port": { "type": "uint16" }}
+/// })).unwrap()).unwrap();
+///
+/// let figment = Figment::from(Serialized::defaults(json!({ "port": 8080 })));
+/// let config: Config = jtd::config::extract_validated(&schema, &figment, Default::default()).unwrap();
+/// assert_eq!(8080, config.port);
+/// ```
+#[cfg(feature = "figment")]
+pub fn extract_validated<T: serde::de::DeserializeOwned>(
+    schema: &Schema,
+    figment: &figment::Figment,
+    options: ValidateOptions,
+) -> Result<T, ConfigError> {
+    let errors = validate_figment(schema, figment, options)?;
+    if !errors.is_empty() {
+        return Err(ConfigError::Rejected(errors));
+    }
+
+    Ok(figment.extract()?)
+}
+
+// Finds the metadata of the deepest value along path that has any.
+#[cfg(feature = "figment")]
+fn figment_metadata<'a>(
+    figment: &'a figment::Figment,
+    root: &figment::value::Value,
+    path: &[String],
+) -> Option<&'a figment::Metadata> {
+    use figment::value::Value as FigmentValue;
+
+    let mut value = root;
+    let mut metadata = None;
+    for component in path {
+        let child = match value {
+            FigmentValue::Dict(_, dict) => dict.get(component),
+            FigmentValue::Array(_, values) => component
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| values.get(index)),
+            _ => None,
+        };
+
+        value = match child {
+            Some(child) => child,
+            None => break,
+        };
+
+        metadata = figment.get_metadata(value.tag()).or(metadata);
+    }
+
+    metadata
+}
+
+fn report(
+    schema: &Schema,
+    value: &Value,
+    locations: &Locations,
+    options: ValidateOptions,
+) -> Result<Vec<ConfigValidationError>, ConfigError> {
+    let errors = validate(schema, value, options)?;
+
+    Ok(errors
+        .into_iter()
+        .map(|error| {
+            let (instance_path, schema_path) = error.into_owned_paths();
+
+            // Missing values have no location of their own, so fall back to
+            // the nearest ancestor that does.
+            let location = (0..=instance_path.len())
+                .rev()
+                .find_map(|len| locations.get(&instance_path[..len]))
+                .copied()
+                .unwrap_or(Location { line: 1, column: 1 });
+
+            ConfigValidationError {
+                instance_path,
+                schema_path,
+                location,
+            }
+        })
+        .collect())
+}
+
+#[cfg(feature = "yaml")]
+mod yaml {
+    use super::{ConfigError, Location, Locations};
+    use serde_json::{Map, Number, Value};
+    use std::collections::HashMap;
+    use yaml_rust2::parser::{Event, MarkedEventReceiver};
+    use yaml_rust2::scanner::{Marker, TScalarStyle};
+
+    // Builds up a JSON value, and the locations of its parts, from YAML
+    // parser events.
+    #[derive(Default)]
+    pub(super) struct Builder {
+        stack: Vec<Frame>,
+        path: Vec<String>,
+        locations: Locations,
+        anchors: HashMap<usize, Value>,
+        root: Option<Value>,
+        done: bool,
+        error: Option<ConfigError>,
+    }
+
+    enum Frame {
+        Sequence {
+            anchor: usize,
+            values: Vec<Value>,
+        },
+        Mapping {
+            anchor: usize,
+            values: Map<String, Value>,
+            key: Option<String>,
+        },
+    }
+
+    impl Builder {
+        pub(super) fn finish(mut self) -> Result<(Value, Locations), ConfigError> {
+            if let Some(err) = self.error {
+                return Err(err);
+            }
+
+            self.locations
+                .entry(Vec::new())
+                .or_insert(Location { line: 1, column: 1 });
+
+            Ok((self.root.unwrap_or(Value::Null), self.locations))
+        }
+
+        fn is_key(&self) -> bool {
+            matches!(self.stack.last(), Some(Frame::Mapping { key: None, .. }))
+        }
+
+        // Records the location of a value starting at mark, returning the path
+        // component for it, if it's not the root.
+        fn start(&mut self, mark: Marker) -> Option<String> {
+            let component = match self.stack.last() {
+                Some(Frame::Sequence { values, .. }) => Some(values.len().to_string()),
+                Some(Frame::Mapping { key, .. }) => key.clone(),
+                None => None,
+            };
+
+            let mut path = self.path.clone();
+            path.extend(component.clone());
+            self.locations.insert(path, location(mark));
+
+            component
+        }
+
+        fn finish_value(&mut self, value: Value, anchor: usize) {
+            if anchor != 0 {
+                self.anchors.insert(anchor, value.clone());
+            }
+
+            match self.stack.last_mut() {
+                Some(Frame::Sequence { values, .. }) => values.push(value),
+                Some(Frame::Mapping { values, key, .. }) => {
+                    values.insert(key.take().unwrap_or_default(), value);
+                }
+                None => {
+                    self.root = Some(value);
+                    self.done = true;
+                }
+            }
+        }
+
+        fn set_key(&mut self, key: String) {
+            if let Some(Frame::Mapping { key: slot, .. }) = self.stack.last_mut() {
+                *slot = Some(key);
+            }
+        }
+
+        fn on_event_inner(&mut self, event: Event, mark: Marker) -> Result<(), ConfigError> {
+            match event {
+                Event::Scalar(s, style, anchor, tag) => {
+                    if self.is_key() {
+                        self.set_key(s);
+                        return Ok(());
+                    }
+
+                    let is_str = matches!(&tag, Some(tag) if tag.suffix == "str");
+                    let value = if style == TScalarStyle::Plain && !is_str {
+                        resolve_plain(s).ok_or(ConfigError::NotJson(location(mark)))?
+                    } else {
+                        Value::String(s)
+                    };
+
+                    self.start(mark);
+                    self.finish_value(value, anchor);
+                }
+                Event::Alias(anchor) => {
+                    let value = self.anchors.get(&anchor).cloned().unwrap_or(Value::Null);
+                    if self.is_key() {
+                        match value {
+                            Value::String(s) => self.set_key(s),
+                            _ => return Err(ConfigError::NotJson(location(mark))),
+                        }
+
+                        return Ok(());
+                    }
+
+                    self.start(mark);
+                    self.finish_value(value, 0);
+                }
+                Event::SequenceStart(anchor, _) | Event::MappingStart(anchor, _) => {
+                    if self.is_key() {
+                        return Err(ConfigError::NotJson(location(mark)));
+                    }
+
+                    let component = self.start(mark);
+                    self.path.extend(component);
+                    self.stack.push(match event {
+                        Event::SequenceStart(..) => Frame::Sequence {
+                            anchor,
+                            values: Vec::new(),
+                        },
+                        _ => Frame::Mapping {
+                            anchor,
+                            values: Map::new(),
+                            key: None,
+                        },
+                    });
+                }
+                Event::SequenceEnd | Event::MappingEnd => {
+                    let (anchor, value) = match self.stack.pop() {
+                        Some(Frame::Sequence { anchor, values }) => (anchor, Value::Array(values)),
+                        Some(Frame::Mapping { anchor, values, .. }) => {
+                            (anchor, Value::Object(values))
+                        }
+                        None => return Ok(()),
+                    };
+
+                    if !self.stack.is_empty() {
+                        self.path.pop();
+                    }
+
+                    self.finish_value(value, anchor);
+                }
+                _ => {}
+            }
+
+            Ok(())
+        }
+    }
+
+    impl MarkedEventReceiver for Builder {
+        fn on_event(&mut self, event: Event, mark: Marker) {
+            if self.done || self.error.is_some() {
+                return;
+            }
+
+            if let Err(err) = self.on_event_inner(event, mark) {
+                self.error = Some(err);
+            }
+        }
+    }
+
+    fn location(mark: Marker) -> Location {
+        Location {
+            line: mark.line(),
+            column: mark.col() + 1,
+        }
+    }
+
+    // Resolves a plain scalar according to the YAML 1.2 core schema. Returns
+    // None for values with no JSON equivalent, namely infinities and NaN.
+    fn resolve_plain(s: String) -> Option<Value> {
+        match &s[..] {
+            "" | "~" | "null" | "Null" | "NULL" => return Some(Value::Null),
+            "true" | "True" | "TRUE" => return Some(Value::Bool(true)),
+            "false" | "False" | "FALSE" => return Some(Value::Bool(false)),
+            ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" | "-.inf" | "-.Inf"
+            | "-.INF" | ".nan" | ".NaN" | ".NAN" => return None,
+            _ => {}
+        }
+
+        let int = if let Some(hex) = s.strip_prefix("0x") {
+            i64::from_str_radix(hex, 16).ok()
+        } else if let Some(oct) = s.strip_prefix("0o") {
+            i64::from_str_radix(oct, 8).ok()
+        } else {
+            s.strip_prefix('+').unwrap_or(&s).parse().ok()
+        };
+
+        if let Some(int) = int {
+            return Some(Value::Number(int.into()));
+        }
+
+        if let Ok(uint) = s.parse::<u64>() {
+            return Some(Value::Number(uint.into()));
+        }
+
+        // Rust accepts some spellings of floats, like "inf", that YAML
+        // doesn't, so only things that look like YAML floats are parsed.
+        let is_float = s.bytes().any(|b| b.is_ascii_digit())
+            && s.bytes()
+                .all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'+' | b'-'));
+
+        if is_float {
+            if let Some(number) = s.parse().ok().and_then(Number::from_f64) {
+                return Some(Value::Number(number));
+            }
+        }
+
+        Some(Value::String(s))
+    }
+}
+
+#[cfg(feature = "toml")]
+mod toml_support {
+    use super::{ConfigError, Locations};
+    use jtd_core::__private::LineIndex;
+    use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+    use serde_json::{Map, Number, Value};
+    use std::fmt;
+    use toml::Spanned;
+
+    // How the toml crate represents dates and times through serde.
+    const DATETIME_KEY: &str = "$__toml_private_datetime";
+
+    // A TOML value, with the location of each of its parts.
+    pub(super) enum Node {
+        Bool(bool),
+        Int(i64),
+        Float(f64),
+        String(String),
+        Array(Vec<Spanned<Node>>),
+        Table(Table),
+    }
+
+    pub(super) struct Table(Vec<(String, Spanned<Node>)>);
+
+    impl Table {
+        pub(super) fn into_value(
+            self,
+            lines: &LineIndex,
+            path: &mut Vec<String>,
+            locations: &mut Locations,
+        ) -> Result<Value, ConfigError> {
+            let mut values = Map::new();
+            for (key, node) in self.0 {
+                path.push(key);
+                let value = into_value(node, lines, path, locations)?;
+                values.insert(path.pop().unwrap(), value);
+            }
+
+            Ok(Value::Object(values))
+        }
+    }
+
+    fn into_value(
+        node: Spanned<Node>,
+        lines: &LineIndex,
+        path: &mut Vec<String>,
+        locations: &mut Locations,
+    ) -> Result<Value, ConfigError> {
+        let location = lines.locate(node.span().start);
+        locations.insert(path.clone(), location);
+
+        Ok(match node.into_inner() {
+            Node::Bool(b) => Value::Bool(b),
+            Node::Int(i) => Value::Number(i.into()),
+            Node::Float(f) => {
+                Value::Number(Number::from_f64(f).ok_or(ConfigError::NotJson(location))?)
+            }
+            Node::String(s) => Value::String(s),
+            Node::Array(nodes) => {
+                let mut values = Vec::new();
+                for (index, node) in nodes.into_iter().enumerate() {
+                    path.push(index.to_string());
+                    values.push(into_value(node, lines, path, locations)?);
+                    path.pop();
+                }
+
+                Value::Array(values)
+            }
+            Node::Table(table) => table.into_value(lines, path, locations)?,
+        })
+    }
+
+    impl<'de> Deserialize<'de> for Node {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(NodeVisitor)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Table {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            match deserializer.deserialize_any(NodeVisitor)? {
+                Node::Table(table) => Ok(table),
+                _ => Err(serde::de::Error::custom("expected a table")),
+            }
+        }
+    }
+
+    struct NodeVisitor;
+
+    impl<'de> Visitor<'de> for NodeVisitor {
+        type Value = Node;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a TOML value")
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Node, E> {
+            Ok(Node::Bool(v))
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<Node, E> {
+            Ok(Node::Int(v))
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<Node, E> {
+            Ok(Node::Float(v))
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Node, E> {
+            Ok(Node::String(v.to_owned()))
+        }
+
+        fn visit_string<E>(self, v: String) -> Result<Node, E> {
+            Ok(Node::String(v))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+            let mut nodes = Vec::new();
+            while let Some(node) = seq.next_element()? {
+                nodes.push(node);
+            }
+
+            Ok(Node::Array(nodes))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+            let mut entries = Vec::new();
+            while let Some(key) = map.next_key::<String>()? {
+                if key == DATETIME_KEY {
+                    return Ok(Node::String(map.next_value()?));
+                }
+
+                entries.push((key, map.next_value()?));
+            }
+
+            Ok(Node::Table(Table(entries)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "properties": {
+                    "name": { "type": "string" },
+                    "ports": { "elements": { "type": "uint16" }},
+                    "limits": { "values": { "type": "uint8" }}
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn locations(errors: Vec<ConfigValidationError>) -> Vec<(String, usize, usize)> {
+        let mut locations: Vec<_> = errors
+            .into_iter()
+            .map(|e| {
+                (
+                    e.instance_path.join("/"),
+                    e.location.line,
+                    e.location.column,
+                )
+            })
+            .collect();
+
+        locations.sort();
+        locations
+    }
+
+    #[test]
+    fn json_locations() {
+        let input = "{\n  \"ports\": [80, \"x\"],\n  \"limits\": {\"a\\\"b\": 300}\n}";
+        let errors = validate_json_str(&schema(), input, Default::default()).unwrap();
+
+        assert_eq!(
+            vec![
+                ("".to_owned(), 1, 1),
+                ("limits/a\"b".to_owned(), 3, 22),
+                ("ports/1".to_owned(), 2, 17),
+            ],
+            locations(errors)
+        );
+
+        assert!(matches!(
+            validate_json_str(&schema(), "{", Default::default()),
+            Err(ConfigError::Json(_))
+        ));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_locations() {
+        let input = "\
+name: &n 'x'
+ports: [80, 0x10, \"443\"]
+limits:
+  a: *n
+  b: 1.5
+  c: ~
+";
+        let errors = validate_yaml_str(&schema(), input, Default::default()).unwrap();
+
+        assert_eq!(
+            vec![
+                ("limits/a".to_owned(), 4, 6),
+                ("limits/b".to_owned(), 5, 6),
+                ("limits/c".to_owned(), 6, 6),
+                ("ports/2".to_owned(), 2, 19),
+            ],
+            locations(errors)
+        );
+
+        assert!(matches!(
+            validate_yaml_str(&schema(), "a: .inf", Default::default()),
+            Err(ConfigError::NotJson(Location { line: 1, column: 4 }))
+        ));
+
+        assert!(matches!(
+            validate_yaml_str(&schema(), "[a]: 1", Default::default()),
+            Err(ConfigError::NotJson(_))
+        ));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_locations() {
+        let input = "\
+name = 1979-05-27T07:32:00Z
+ports = [80, 65536]
+
+[limits]
+a = 1
+b = \"2\"
+";
+        let errors = validate_toml_str(&schema(), input, Default::default()).unwrap();
+
+        assert_eq!(
+            vec![("limits/b".to_owned(), 6, 5), ("ports/1".to_owned(), 2, 14)],
+            locations(errors)
+        );
+    }
+
+    #[cfg(feature = "figment")]
+    #[test]
+    fn figment_layers() {
+        use figment::providers::Serialized;
+        use figment::value::{Dict, Map};
+        use figment::{Figment, Metadata, Profile, Provider};
+
+        struct Layer(&'static str, Value);
+
+        impl Provider for Layer {
+            fn metadata(&self) -> Metadata {
+                Metadata::named(self.0)
+            }
+
+            fn data(&self) -> Result<Map<Profile, Dict>, figment::Error> {
+                Serialized::defaults(&self.1).data()
+            }
+        }
+
+        let figment = Figment::from(Layer(
+            "defaults",
+            json!({ "name": "app", "ports": [80, 443], "limits": { "a": 1 }}),
+        ))
+        .merge(Layer("overrides", json!({ "ports": [80, -1] })))
+        .merge(Layer("env", json!({ "limits": { "b": 1000 }})));
+
+        let errors = validate_figment(&schema(), &figment, Default::default()).unwrap();
+        let mut blamed: Vec<_> = errors
+            .iter()
+            .map(|e| {
+                (
+                    e.instance_path.join("/"),
+                    e.metadata.as_ref().unwrap().name.clone(),
+                )
+            })
+            .collect();
+        blamed.sort();
+
+        assert_eq!(
+            vec![
+                ("limits/b".to_owned(), "env".into()),
+                ("ports/1".to_owned(), "overrides".into()),
+            ],
+            blamed
+        );
+
+        match extract_validated::<Value>(&schema(), &figment, Default::default()) {
+            Err(ConfigError::Rejected(rejected)) => assert_eq!(errors, rejected),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{validate, Schema, ValidateError, ValidationErrorIndicator};
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+/// Errors that may arise from [`JtdValidate::validate_self`].
+#[derive(Debug, Error)]
+pub enum ValidateSelfError {
+    /// The value couldn't be serialized to JSON.
+    #[error("failed to serialize: {0}")]
+    Serialize(#[from] serde_json::Error),
+
+    /// Validating the serialized value failed.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+}
+
+/// A type that carries a JSON Typedef schema describing its JSON
+/// representation.
+///
+/// Implementing this trait lets a type act as the single source of truth for
+/// its contract: every place that needs to check data against that contract
+/// can go through the same entry point, rather than each call site looking up
+/// (and possibly disagreeing about) which schema to use.
+///
+/// Only [`schema`][`JtdValidate::schema`] needs to be implemented. The other
+/// methods are provided, and validate using the default [`ValidateOptions`][`crate::ValidateOptions`].
+///
+/// ```
+/// use jtd::{JtdValidate, Schema};
+/// use serde_json::json;
+/// use std::sync::OnceLock;
+///
+/// #[derive(serde::Serialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// impl JtdValidate for User {
+///     fn schema() -> &'static Schema {
+///         static SCHEMA: OnceLock<Schema> = OnceLock::new();
+///         SCHEMA.get_or_init(|| {
+///             Schema::from_serde_schema(serde_json::from_value(json!({
+///                 "properties": { "name": { "type": "string" }}
+///             })).unwrap()).unwrap()
+///         })
+///     }
+/// }
+///
+/// assert!(User::is_valid_value(&json!({ "name": "John" })));
+/// assert_eq!(1, User::validate_value(&json!({ "name": 3 })).unwrap().len());
+///
+/// let user = User { name: "John".into() };
+/// assert!(user.validate_self().unwrap().is_empty());
+/// ```
+///
+/// With the `macros` feature enabled, `include_schema!` is a convenient way to
+/// implement [`schema`][`JtdValidate::schema`].
+pub trait JtdValidate {
+    /// The schema describing this type's JSON representation.
+    fn schema() -> &'static Schema;
+
+    /// Validates a JSON value against [`schema`][`JtdValidate::schema`].
+    ///
+    /// This only errors if the schema itself is at fault, as with
+    /// [`ValidateError::InvalidSchema`]. Schemas that pass
+    /// [`Schema::validate`] never cause an error here.
+    fn validate_value(value: &Value) -> Result<Vec<ValidationErrorIndicator<'_>>, ValidateError> {
+        validate(Self::schema(), value, Default::default())
+    }
+
+    /// Returns whether a JSON value satisfies [`schema`][`JtdValidate::schema`].
+    ///
+    /// If the value can't be validated, because of a problem with the schema,
+    /// then it doesn't satisfy the schema, and this returns `false`.
+    fn is_valid_value(value: &Value) -> bool {
+        validate(
+            Self::schema(),
+            value,
+            crate::ValidateOptions::new().with_max_errors(1),
+        )
+        .map(|errors| errors.is_empty())
+        .unwrap_or(false)
+    }
+
+    /// Serializes `self` to JSON, and validates the result against
+    /// [`schema`][`JtdValidate::schema`].
+    ///
+    /// This is useful for checking that a type's [`Serialize`] implementation
+    /// actually honors the type's contract.
+    ///
+    /// Because the serialized JSON doesn't outlive this call, the returned
+    /// error indicators own their paths.
+    fn validate_self(&self) -> Result<Vec<ValidationErrorIndicator<'static>>, ValidateSelfError>
+    where
+        Self: Serialize,
+    {
+        let value = serde_json::to_value(self)?;
+        Ok(Self::validate_value(&value)?
+            .into_iter()
+            .map(|error| error.into_owned().into())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JtdValidate, ValidateSelfError};
+    use crate::{Schema, SchemaValidateError, ValidateError};
+    use serde_json::json;
+    use std::sync::OnceLock;
+
+    #[derive(serde::Serialize)]
+    struct Broken;
+
+    impl JtdValidate for Broken {
+        // A schema built by hand, and never checked with Schema::validate.
+        fn schema() -> &'static Schema {
+            static SCHEMA: OnceLock<Schema> = OnceLock::new();
+            SCHEMA.get_or_init(|| Schema::Ref {
+                definitions: Default::default(),
+                metadata: Default::default(),
+                nullable: false,
+                ref_: "missing".into(),
+            })
+        }
+    }
+
+    #[test]
+    fn invalid_schema() {
+        let error =
+            ValidateError::InvalidSchema(SchemaValidateError::NoSuchDefinition("missing".into()));
+
+        assert_eq!(Err(error.clone()), Broken::validate_value(&json!(null)));
+        assert!(!Broken::is_valid_value(&json!(null)));
+
+        match Broken.validate_self() {
+            Err(ValidateSelfError::Validate(e)) => assert_eq!(error, e),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! Convert schemas into the schema languages of other systems.
+//!
+//! Each submodule targets one system. Conversions are one-way, and lossy:
+//! they keep as much of the schema as the target can express.
+
+pub mod mongodb;
+pub mod sql;
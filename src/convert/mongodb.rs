@@ -0,0 +1,463 @@
+//! Convert schemas into MongoDB's `$jsonSchema` validators.
+//!
+//! MongoDB can validate documents as they're written to a collection, against
+//! a schema in a dialect of JSON Schema draft 4 that it calls `$jsonSchema`.
+//! [`validator`] converts a schema into that dialect, so that the database
+//! enforces what services validate in Rust.
+//!
+//! `$jsonSchema` has no way to refer to definitions, so each `ref` is
+//! replaced with a copy of the definition it refers to. Schemas whose
+//! definitions refer to themselves, directly or not, can't be converted.
+//!
+//! MongoDB gives every document an `_id`. If the root schema doesn't allow
+//! additional properties, and doesn't say what `_id` is, the validator allows
+//! an `_id` of any type.
+//!
+//! ```
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": {
+//!         "name": { "type": "string" },
+//!         "role": { "enum": ["admin", "user"], "nullable": true }
+//!     }
+//! })).unwrap()).unwrap();
+//!
+//! assert_eq!(
+//!     json!({
+//!         "$jsonSchema": {
+//!             "bsonType": "object",
+//!             "required": ["name", "role"],
+//!             "properties": {
+//!                 "_id": {},
+//!                 "name": { "bsonType": "string" },
+//!                 "role": { "enum": ["admin", "user", null] }
+//!             },
+//!             "additionalProperties": false
+//!         }
+//!     }),
+//!     jtd::convert::mongodb::validator(&schema).unwrap(),
+//! );
+//! ```
+
+use crate::{Schema, Type};
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+/// Errors that may arise from [`validator`] and [`json_schema`].
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum MongoDbError {
+    /// The schema has a definition that refers to itself, directly or through
+    /// other definitions, so it can't be copied in place of a `ref`.
+    ///
+    /// The contained string is the name of the definition.
+    #[error("definition refers to itself: {0:?}")]
+    RecursiveRef(String),
+
+    /// The schema has a `ref` to a definition that doesn't exist.
+    #[error("no such definition: {0:?}")]
+    NoSuchDefinition(String),
+}
+
+/// Makes a validator for a MongoDB collection, of the form `{ "$jsonSchema":
+/// ... }`, as passed to the `validator` option of `createCollection` or
+/// `collMod`.
+pub fn validator(schema: &Schema) -> Result<Value, MongoDbError> {
+    Ok(json!({ "$jsonSchema": json_schema(schema)? }))
+}
+
+/// Converts a schema into MongoDB's `$jsonSchema` dialect.
+///
+/// | Schema                                    | `$jsonSchema`                                   |
+/// | ----------------------------------------- | ----------------------------------------------- |
+/// | empty                                     | `{}`                                            |
+/// | `boolean`                                 | `"bsonType": "bool"`                            |
+/// | `int8` to `uint32`                        | `"bsonType": "number"`, `multipleOf`, `minimum`, `maximum` |
+/// | `float32`, `float64`                      | `"bsonType": "number"`                          |
+/// | `string`, `timestamp`                     | `"bsonType": "string"`                          |
+/// | `enum`                                    | `enum`                                          |
+/// | `elements`                                | `"bsonType": "array"`, `items`                  |
+/// | `properties`, `optionalProperties`        | `"bsonType": "object"`, `required`, `properties`, `additionalProperties` |
+/// | `values`                                  | `"bsonType": "object"`, `additionalProperties`  |
+/// | `discriminator`                           | `"bsonType": "object"`, `oneOf` with a schema per tag |
+///
+/// Integers are checked as numbers of any BSON type, since documents written
+/// from JSON or the MongoDB shell often store integers as doubles. Timestamps
+/// are checked as strings, the way they are in JSON; the format of a
+/// timestamp isn't checked. `nullable` adds `"null"` to the `bsonType`, or
+/// `null` to the `enum`. A `description` in `metadata` is kept as the
+/// `description` of the `$jsonSchema`.
+pub fn json_schema(schema: &Schema) -> Result<Value, MongoDbError> {
+    let mut converter = Converter {
+        root: schema,
+        refs: Vec::new(),
+    };
+
+    let mut out = converter.convert(schema)?;
+    allow_id(&mut out);
+    Ok(out)
+}
+
+struct Converter<'a> {
+    root: &'a Schema,
+
+    // The definitions being copied in place of a ref, innermost last.
+    refs: Vec<&'a str>,
+}
+
+impl<'a> Converter<'a> {
+    fn convert(&mut self, schema: &'a Schema) -> Result<Value, MongoDbError> {
+        let mut out = Map::new();
+
+        match schema {
+            Schema::Empty { .. } => {}
+
+            Schema::Ref { ref_, .. } => {
+                let definition = self
+                    .root
+                    .definitions()
+                    .get(ref_)
+                    .ok_or_else(|| MongoDbError::NoSuchDefinition(ref_.clone()))?;
+
+                if self.refs.contains(&ref_.as_str()) {
+                    return Err(MongoDbError::RecursiveRef(ref_.clone()));
+                }
+
+                self.refs.push(ref_);
+                let mut definition = self.convert(definition)?;
+                self.refs.pop();
+
+                if schema.nullable() {
+                    nullable(&mut definition);
+                }
+
+                return Ok(definition);
+            }
+
+            Schema::Type { type_, .. } => match type_ {
+                Type::Boolean => {
+                    out.insert("bsonType".to_owned(), "bool".into());
+                }
+                Type::Float32 | Type::Float64 => {
+                    out.insert("bsonType".to_owned(), "number".into());
+                }
+                Type::String | Type::Timestamp => {
+                    out.insert("bsonType".to_owned(), "string".into());
+                }
+                // Only the registered callback knows what a custom type
+                // accepts, so anything is allowed.
+                #[cfg(feature = "extensions")]
+                Type::Custom(_) => {}
+                _ => {
+                    let (min, max) = range(type_);
+                    out.insert("bsonType".to_owned(), "number".into());
+                    out.insert("multipleOf".to_owned(), 1.into());
+                    out.insert("minimum".to_owned(), min.into());
+                    out.insert("maximum".to_owned(), max.into());
+                }
+            },
+
+            Schema::Enum { enum_, .. } => {
+                out.insert(
+                    "enum".to_owned(),
+                    enum_.iter().map(|value| Value::from(&value[..])).collect(),
+                );
+            }
+
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { enum_, .. } => {
+                out.insert(
+                    "enum".to_owned(),
+                    enum_.iter().map(|&value| Value::from(value)).collect(),
+                );
+            }
+
+            Schema::Elements { elements, .. } => {
+                out.insert("bsonType".to_owned(), "array".into());
+                out.insert("items".to_owned(), self.convert(elements)?);
+            }
+
+            Schema::Properties {
+                properties,
+                optional_properties,
+                additional_properties,
+                ..
+            } => {
+                out.insert("bsonType".to_owned(), "object".into());
+
+                // Draft 4 requires `required` to be non-empty.
+                if !properties.is_empty() {
+                    out.insert(
+                        "required".to_owned(),
+                        properties
+                            .keys()
+                            .map(|name| Value::from(&name[..]))
+                            .collect(),
+                    );
+                }
+
+                let mut all = Map::new();
+                for (name, sub_schema) in properties.iter().chain(optional_properties) {
+                    all.insert(name.clone(), self.convert(sub_schema)?);
+                }
+
+                if !all.is_empty() {
+                    out.insert("properties".to_owned(), Value::Object(all));
+                }
+
+                if !additional_properties {
+                    out.insert("additionalProperties".to_owned(), false.into());
+                }
+            }
+
+            // `keys` isn't converted, so any keys are allowed.
+            Schema::Values { values, .. } => {
+                out.insert("bsonType".to_owned(), "object".into());
+                out.insert("additionalProperties".to_owned(), self.convert(values)?);
+            }
+
+            Schema::Discriminator { mapping, .. } => {
+                let mut variants = Vec::new();
+                for tag in mapping.keys() {
+                    // The specialized variant holds its own copy of the
+                    // definitions, but refs are still resolved against the
+                    // root, so only its properties need converting.
+                    let variant = schema.specialize_tag(tag).expect("tag is in mapping");
+                    variants.push(self.convert_owned(variant)?);
+                }
+
+                out.insert("bsonType".to_owned(), "object".into());
+                out.insert("oneOf".to_owned(), Value::Array(variants));
+            }
+        }
+
+        if let Some(description) = schema.metadata().get("description") {
+            if description.is_string() {
+                out.insert("description".to_owned(), description.clone());
+            }
+        }
+
+        let mut out = Value::Object(out);
+        if schema.nullable() && !matches!(schema, Schema::Empty { .. }) {
+            nullable(&mut out);
+        }
+
+        Ok(out)
+    }
+
+    fn convert_owned(&mut self, schema: Schema) -> Result<Value, MongoDbError> {
+        // Sub-schemas of the owned schema are only borrowed for as long as
+        // this call, and the refs they push are popped before it returns.
+        let mut converter = Converter {
+            root: self.root,
+            refs: self.refs.clone(),
+        };
+
+        converter.convert(&schema)
+    }
+}
+
+// Makes a converted schema also accept null.
+fn nullable(out: &mut Value) {
+    let object = match out {
+        Value::Object(object) => object,
+        _ => return,
+    };
+
+    if object.is_empty() {
+        return;
+    }
+
+    if let Some(Value::Array(values)) = object.get_mut("enum") {
+        if !values.contains(&Value::Null) {
+            values.push(Value::Null);
+        }
+
+        return;
+    }
+
+    // A null would match none of the variants of a `oneOf`.
+    let bson_type = if object.contains_key("oneOf") {
+        None
+    } else {
+        object.get_mut("bsonType")
+    };
+
+    match bson_type {
+        Some(Value::String(bson_type)) => {
+            let bson_type = std::mem::take(bson_type);
+            object.insert("bsonType".to_owned(), json!([bson_type, "null"]));
+        }
+        Some(Value::Array(bson_types)) => {
+            if !bson_types.contains(&Value::from("null")) {
+                bson_types.push("null".into());
+            }
+        }
+        _ => {
+            let inner = std::mem::take(object);
+            object.insert(
+                "anyOf".to_owned(),
+                json!([{ "bsonType": "null" }, Value::Object(inner)]),
+            );
+        }
+    }
+}
+
+// Allows the `_id` every MongoDB document has, if the root schema would
+// otherwise reject it.
+fn allow_id(out: &mut Value) {
+    let object = match out {
+        Value::Object(object) => object,
+        _ => return,
+    };
+
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(Value::Array(schemas)) = object.get_mut(keyword) {
+            schemas.iter_mut().for_each(allow_id);
+        }
+    }
+
+    if object.get("additionalProperties") == Some(&Value::Bool(false)) {
+        let properties = object
+            .entry("properties")
+            .or_insert_with(|| Value::Object(Map::new()));
+
+        if let Value::Object(properties) = properties {
+            properties
+                .entry("_id")
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+    }
+}
+
+// The range of an integer type.
+fn range(type_: &Type) -> (i64, i64) {
+    match type_ {
+        Type::Int8 => (i8::MIN.into(), i8::MAX.into()),
+        Type::Uint8 => (u8::MIN.into(), u8::MAX.into()),
+        Type::Int16 => (i16::MIN.into(), i16::MAX.into()),
+        Type::Uint16 => (u16::MIN.into(), u16::MAX.into()),
+        Type::Int32 => (i32::MIN.into(), i32::MAX.into()),
+        Type::Uint32 => (u32::MIN.into(), u32::MAX.into()),
+        _ => (i64::MIN, i64::MAX),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(value: Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn converts_forms() {
+        assert_eq!(
+            json!({
+                "bsonType": "object",
+                "required": ["at", "n", "tags"],
+                "properties": {
+                    "any": {},
+                    "at": { "bsonType": ["string", "null"], "description": "When." },
+                    "counts": {
+                        "bsonType": "object",
+                        "additionalProperties": { "bsonType": "number" }
+                    },
+                    "n": {
+                        "bsonType": "number",
+                        "multipleOf": 1,
+                        "minimum": 0,
+                        "maximum": 255
+                    },
+                    "point": {
+                        "bsonType": ["object", "null"],
+                        "required": ["x"],
+                        "properties": { "x": { "bsonType": "bool" }},
+                        "additionalProperties": false
+                    },
+                    "tags": { "bsonType": "array", "items": { "enum": ["a", "b"] }}
+                }
+            }),
+            json_schema(&schema(json!({
+                "definitions": {
+                    "point": { "properties": { "x": { "type": "boolean" }}}
+                },
+                "properties": {
+                    "at": {
+                        "metadata": { "description": "When." },
+                        "type": "timestamp",
+                        "nullable": true
+                    },
+                    "n": { "type": "uint8" },
+                    "tags": { "elements": { "enum": ["a", "b"] }}
+                },
+                "optionalProperties": {
+                    "any": {},
+                    "counts": { "values": { "type": "float64" }},
+                    "point": { "ref": "point", "nullable": true }
+                },
+                "additionalProperties": true
+            })))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn converts_discriminators() {
+        assert_eq!(
+            json!({
+                "anyOf": [{ "bsonType": "null" }, {
+                "bsonType": "object",
+                "oneOf": [
+                    {
+                        "bsonType": "object",
+                        "required": ["t"],
+                        "properties": { "_id": {}, "t": { "enum": ["a"] }},
+                        "additionalProperties": false
+                    },
+                    {
+                        "bsonType": "object",
+                        "required": ["t", "x"],
+                        "properties": {
+                            "_id": {},
+                            "t": { "enum": ["b"] },
+                            "x": { "bsonType": "string" }
+                        },
+                        "additionalProperties": false
+                    }
+                ]
+                }]
+            }),
+            json_schema(&schema(json!({
+                "definitions": { "s": { "type": "string" }},
+                "discriminator": "t",
+                "mapping": {
+                    "a": { "properties": {} },
+                    "b": { "properties": { "x": { "ref": "s" }}}
+                },
+                "nullable": true
+            })))
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_recursive_refs() {
+        assert_eq!(
+            Err(MongoDbError::RecursiveRef("node".into())),
+            json_schema(&schema(json!({
+                "definitions": {
+                    "node": { "optionalProperties": { "next": { "ref": "node" }}}
+                },
+                "ref": "node"
+            })))
+        );
+
+        assert_eq!(
+            Err(MongoDbError::NoSuchDefinition("x".into())),
+            json_schema(&schema(json!({ "ref": "x" })))
+        );
+    }
+}
@@ -0,0 +1,582 @@
+//! Convert properties-form schemas into SQL.
+//!
+//! Records that pass validation are often loaded into a database afterwards.
+//! This module keeps the database in line with the schema, in one of two
+//! ways:
+//!
+//! * [`create_table`] makes a table with a column for each property, of the
+//!   SQL type that best fits it. Properties that aren't scalars, such as
+//!   arrays and nested objects, become JSON columns.
+//! * [`check_constraints`] makes `CHECK` expressions for a single JSON
+//!   column, that hold when the column's value has the properties the schema
+//!   requires, of the right types.
+//!
+//! Both only look at the top-level properties of the schema. Constraints that
+//! SQL types can't express on their own, such as the range of a `uint8` or
+//! the values of an `enum`, are expressed with `CHECK`. The format of a
+//! `timestamp` inside JSON isn't checked.
+//!
+//! ```
+//! use jtd::convert::sql::{self, Dialect};
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": {
+//!         "id": { "type": "string" },
+//!         "age": { "type": "uint8" }
+//!     },
+//!     "optionalProperties": {
+//!         "tags": { "elements": { "type": "string" }}
+//!     }
+//! })).unwrap()).unwrap();
+//!
+//! assert_eq!(
+//!     r#"CREATE TABLE "users" (
+//!     "age" SMALLINT NOT NULL CHECK ("age" BETWEEN 0 AND 255),
+//!     "id" TEXT NOT NULL,
+//!     "tags" JSONB
+//! );"#,
+//!     sql::create_table(&schema, "users", Dialect::Postgres).unwrap(),
+//! );
+//! ```
+
+use crate::refs::resolve;
+use crate::{Schema, Type};
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+/// The dialects of SQL that [`create_table`] and [`check_constraints`] can
+/// produce.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// PostgreSQL. JSON columns are of type `JSONB`.
+    Postgres,
+
+    /// MySQL 8.0.16 or later, the first version to enforce `CHECK`.
+    MySql,
+
+    /// SQLite. JSON columns are `TEXT` holding valid JSON.
+    Sqlite,
+}
+
+/// Errors that may arise from [`create_table`] and [`check_constraints`].
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum SqlError {
+    /// The schema is not of the properties form, even after following any
+    /// `ref`, so it has no properties to make columns from.
+    #[error("schema is not of the properties form")]
+    NotProperties,
+}
+
+/// Makes a `CREATE TABLE` statement for a table with a column for each
+/// property of `schema`.
+///
+/// Columns are named after properties, and are listed in alphabetical order.
+/// A column is `NOT NULL` if its property is required, and not nullable.
+///
+/// | Schema                       | Postgres           | MySQL               | SQLite    |
+/// | ---------------------------- | ------------------ | ------------------- | --------- |
+/// | `boolean`                    | `BOOLEAN`          | `BOOLEAN`           | `INTEGER` |
+/// | `int8`                       | `SMALLINT`         | `TINYINT`           | `INTEGER` |
+/// | `uint8`                      | `SMALLINT`         | `TINYINT UNSIGNED`  | `INTEGER` |
+/// | `int16`                      | `SMALLINT`         | `SMALLINT`          | `INTEGER` |
+/// | `uint16`                     | `INTEGER`          | `SMALLINT UNSIGNED` | `INTEGER` |
+/// | `int32`                      | `INTEGER`          | `INT`               | `INTEGER` |
+/// | `uint32`                     | `BIGINT`           | `INT UNSIGNED`      | `INTEGER` |
+/// | `float32`                    | `REAL`             | `FLOAT`             | `REAL`    |
+/// | `float64`                    | `DOUBLE PRECISION` | `DOUBLE`            | `REAL`    |
+/// | `string`, `enum`             | `TEXT`             | `TEXT`              | `TEXT`    |
+/// | `timestamp`                  | `TIMESTAMPTZ`      | `DATETIME(6)`       | `TEXT`    |
+/// | anything else                | `JSONB`            | `JSON`              | `TEXT`    |
+///
+/// Integers get a `CHECK` on their range where the SQL type is wider than
+/// the schema's type. Enums get a `CHECK` on their values.
+pub fn create_table(schema: &Schema, table: &str, dialect: Dialect) -> Result<String, SqlError> {
+    let columns: Vec<String> = properties(schema)?
+        .into_iter()
+        .map(|(name, required, sub_schema)| {
+            let (kind, nullable) = Kind::of(schema, sub_schema);
+            let name = dialect.identifier(name);
+
+            let mut column = format!("    {} {}", name, dialect.column_type(&kind));
+            if required && !nullable {
+                column.push_str(" NOT NULL");
+            }
+
+            if let Some(check) = dialect.column_check(&name, &kind) {
+                column.push_str(&format!(" CHECK ({})", check));
+            }
+
+            column
+        })
+        .collect();
+
+    Ok(format!(
+        "CREATE TABLE {} (\n{}\n);",
+        dialect.identifier(table),
+        columns.join(",\n")
+    ))
+}
+
+/// Makes `CHECK` expressions that hold when a JSON column satisfies the
+/// top-level properties of `schema`.
+///
+/// The first expression checks that the column holds an object. Each one
+/// after that checks one property, in alphabetical order: that it's present
+/// if it's required, and that it's of the right type. Like other `CHECK`
+/// constraints, they hold when the column is `NULL`.
+///
+/// ```
+/// use jtd::convert::sql::{self, Dialect};
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "id": { "type": "string" }}
+/// })).unwrap()).unwrap();
+///
+/// assert_eq!(
+///     vec![
+///         r#"json_type("doc") = 'object'"#,
+///         r#"COALESCE(json_type("doc", '$."id"') = 'text', "doc" IS NULL)"#,
+///     ],
+///     sql::check_constraints(&schema, "doc", Dialect::Sqlite).unwrap(),
+/// );
+/// ```
+pub fn check_constraints(
+    schema: &Schema,
+    column: &str,
+    dialect: Dialect,
+) -> Result<Vec<String>, SqlError> {
+    let properties = properties(schema)?;
+    let column = dialect.identifier(column);
+
+    let mut checks = vec![dialect.is(&dialect.json_type(&column, None), Json::Object)];
+    for (name, required, sub_schema) in properties {
+        let (kind, nullable) = Kind::of(schema, sub_schema);
+        let type_ = dialect.json_type(&column, Some(name));
+
+        let check = dialect.json_check(&column, name, &kind).map(|check| {
+            if nullable {
+                format!("({} OR {})", dialect.is(&type_, Json::Null), check)
+            } else {
+                check
+            }
+        });
+
+        checks.push(match (required, check) {
+            (true, Some(check)) => format!("COALESCE({}, {} IS NULL)", check, column),
+            (true, None) => format!("({} IS NULL OR {} IS NOT NULL)", column, type_),
+            (false, Some(check)) => format!("({} IS NULL OR COALESCE({}, FALSE))", type_, check),
+            (false, None) => continue,
+        });
+    }
+
+    Ok(checks)
+}
+
+// The properties of a schema, in alphabetical order, with whether each is
+// required.
+fn properties(schema: &Schema) -> Result<Vec<(&str, bool, &Schema)>, SqlError> {
+    match resolve(schema, schema).0 {
+        Schema::Properties {
+            properties,
+            optional_properties,
+            ..
+        } => {
+            let mut all: Vec<_> = properties
+                .iter()
+                .map(|(name, sub_schema)| (name.as_str(), true, sub_schema))
+                .chain(
+                    optional_properties
+                        .iter()
+                        .map(|(name, sub_schema)| (name.as_str(), false, sub_schema)),
+                )
+                .collect();
+
+            all.sort_by_key(|&(name, _, _)| name);
+            Ok(all)
+        }
+        _ => Err(SqlError::NotProperties),
+    }
+}
+
+// What a property holds, as far as SQL is concerned.
+enum Kind<'a> {
+    Boolean,
+    Integer(&'a Type),
+    Float(&'a Type),
+    String,
+    Timestamp,
+    Enum(&'a BTreeSet<String>),
+    #[cfg(feature = "extensions")]
+    IntEnum(&'a BTreeSet<i64>),
+    Array,
+    Object,
+    Any,
+}
+
+impl<'a> Kind<'a> {
+    fn of(root: &'a Schema, schema: &'a Schema) -> (Self, bool) {
+        let (schema, nullable) = resolve(root, schema);
+        let kind = match schema {
+            Schema::Empty { .. } | Schema::Ref { .. } => Kind::Any,
+            Schema::Type { type_, .. } => match type_ {
+                Type::Boolean => Kind::Boolean,
+                Type::Float32 | Type::Float64 => Kind::Float(type_),
+                Type::String => Kind::String,
+                Type::Timestamp => Kind::Timestamp,
+                // Only the registered callback knows what a custom type
+                // accepts, so it's stored as JSON, unchecked.
+                #[cfg(feature = "extensions")]
+                Type::Custom(_) => Kind::Any,
+                _ => Kind::Integer(type_),
+            },
+            Schema::Enum { enum_, .. } => Kind::Enum(enum_),
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { enum_, .. } => Kind::IntEnum(enum_),
+            Schema::Elements { .. } => Kind::Array,
+            Schema::Properties { .. } | Schema::Values { .. } | Schema::Discriminator { .. } => {
+                Kind::Object
+            }
+        };
+
+        (kind, nullable)
+    }
+}
+
+// The range of an integer type.
+fn range(type_: &Type) -> (i64, i64) {
+    match type_ {
+        Type::Int8 => (i8::MIN.into(), i8::MAX.into()),
+        Type::Uint8 => (u8::MIN.into(), u8::MAX.into()),
+        Type::Int16 => (i16::MIN.into(), i16::MAX.into()),
+        Type::Uint16 => (u16::MIN.into(), u16::MAX.into()),
+        Type::Int32 => (i32::MIN.into(), i32::MAX.into()),
+        Type::Uint32 => (u32::MIN.into(), u32::MAX.into()),
+        _ => (i64::MIN, i64::MAX),
+    }
+}
+
+// The types of JSON values, as far as telling them apart in SQL goes.
+#[derive(Clone, Copy)]
+enum Json {
+    Null,
+    Boolean,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl Dialect {
+    fn identifier(self, name: &str) -> String {
+        match self {
+            Dialect::Postgres | Dialect::Sqlite => format!("\"{}\"", name.replace('"', "\"\"")),
+            Dialect::MySql => format!("`{}`", name.replace('`', "``")),
+        }
+    }
+
+    fn string(self, s: &str) -> String {
+        let s = s.replace('\'', "''");
+        match self {
+            Dialect::Postgres | Dialect::Sqlite => format!("'{}'", s),
+            // MySQL treats backslashes in strings as escapes by default.
+            Dialect::MySql => format!("'{}'", s.replace('\\', "\\\\")),
+        }
+    }
+
+    fn column_type(self, kind: &Kind) -> &'static str {
+        match (self, kind) {
+            (Dialect::Sqlite, Kind::Boolean | Kind::Integer(_)) => "INTEGER",
+            (Dialect::Sqlite, Kind::Float(_)) => "REAL",
+            (Dialect::Sqlite, _) => "TEXT",
+            (_, Kind::Boolean) => "BOOLEAN",
+            (Dialect::Postgres, Kind::Integer(Type::Int8 | Type::Uint8 | Type::Int16)) => {
+                "SMALLINT"
+            }
+            (Dialect::Postgres, Kind::Integer(Type::Uint16 | Type::Int32)) => "INTEGER",
+            (Dialect::Postgres, Kind::Integer(_)) => "BIGINT",
+            (Dialect::MySql, Kind::Integer(Type::Int8)) => "TINYINT",
+            (Dialect::MySql, Kind::Integer(Type::Uint8)) => "TINYINT UNSIGNED",
+            (Dialect::MySql, Kind::Integer(Type::Int16)) => "SMALLINT",
+            (Dialect::MySql, Kind::Integer(Type::Uint16)) => "SMALLINT UNSIGNED",
+            (Dialect::MySql, Kind::Integer(Type::Int32)) => "INT",
+            (Dialect::MySql, Kind::Integer(_)) => "INT UNSIGNED",
+            (Dialect::Postgres, Kind::Float(Type::Float32)) => "REAL",
+            (Dialect::Postgres, Kind::Float(_)) => "DOUBLE PRECISION",
+            (Dialect::MySql, Kind::Float(Type::Float32)) => "FLOAT",
+            (Dialect::MySql, Kind::Float(_)) => "DOUBLE",
+            (_, Kind::String | Kind::Enum(_)) => "TEXT",
+            (Dialect::Postgres, Kind::Timestamp) => "TIMESTAMPTZ",
+            (Dialect::MySql, Kind::Timestamp) => "DATETIME(6)",
+            #[cfg(feature = "extensions")]
+            (_, Kind::IntEnum(_)) => "BIGINT",
+            (Dialect::Postgres, _) => "JSONB",
+            (Dialect::MySql, _) => "JSON",
+        }
+    }
+
+    // A check on a column of the type given by column_type, for whatever that
+    // type doesn't enforce by itself.
+    fn column_check(self, column: &str, kind: &Kind) -> Option<String> {
+        match (self, kind) {
+            (Dialect::Sqlite, Kind::Boolean) => Some(format!("{} IN (0, 1)", column)),
+            (Dialect::Sqlite, Kind::Array | Kind::Object | Kind::Any) => {
+                Some(format!("json_valid({})", column))
+            }
+            (Dialect::Sqlite, Kind::Integer(type_))
+            | (Dialect::Postgres, Kind::Integer(type_ @ (Type::Int8 | Type::Uint8)))
+            | (Dialect::Postgres, Kind::Integer(type_ @ (Type::Uint16 | Type::Uint32))) => {
+                let (min, max) = range(type_);
+                Some(format!("{} BETWEEN {} AND {}", column, min, max))
+            }
+            (_, Kind::Enum(values)) => Some(format!(
+                "{} IN ({})",
+                column,
+                self.list(values.iter().map(|value| self.string(value)))
+            )),
+            #[cfg(feature = "extensions")]
+            (_, Kind::IntEnum(values)) => Some(format!(
+                "{} IN ({})",
+                column,
+                self.list(values.iter().map(|value| value.to_string()))
+            )),
+            _ => None,
+        }
+    }
+
+    fn list(self, items: impl Iterator<Item = String>) -> String {
+        items.collect::<Vec<_>>().join(", ")
+    }
+
+    // An expression for the JSON type of a column, or of a property of it.
+    // It's NULL if the property is missing.
+    fn json_type(self, column: &str, property: Option<&str>) -> String {
+        match (self, property) {
+            (Dialect::Postgres, None) => format!("jsonb_typeof({})", column),
+            (Dialect::Postgres, Some(property)) => {
+                format!("jsonb_typeof({} -> {})", column, self.string(property))
+            }
+            (Dialect::MySql, None) => format!("JSON_TYPE({})", column),
+            (Dialect::MySql, Some(property)) => format!(
+                "JSON_TYPE(JSON_EXTRACT({}, {}))",
+                column,
+                self.json_path(property)
+            ),
+            (Dialect::Sqlite, None) => format!("json_type({})", column),
+            (Dialect::Sqlite, Some(property)) => {
+                format!("json_type({}, {})", column, self.json_path(property))
+            }
+        }
+    }
+
+    // An expression for the value of a property of a column, as a SQL number
+    // or string.
+    fn json_value(self, column: &str, property: &str, number: bool) -> String {
+        match self {
+            Dialect::Postgres if number => {
+                format!("({} ->> {})::numeric", column, self.string(property))
+            }
+            Dialect::Postgres => format!("{} ->> {}", column, self.string(property)),
+            Dialect::MySql if number => {
+                format!("JSON_EXTRACT({}, {})", column, self.json_path(property))
+            }
+            Dialect::MySql => format!(
+                "JSON_UNQUOTE(JSON_EXTRACT({}, {}))",
+                column,
+                self.json_path(property)
+            ),
+            Dialect::Sqlite => format!("json_extract({}, {})", column, self.json_path(property)),
+        }
+    }
+
+    fn json_path(self, property: &str) -> String {
+        let property = property.replace('\\', "\\\\").replace('"', "\\\"");
+        self.string(&format!("$.\"{}\"", property))
+    }
+
+    // Tests whether a JSON type expression is of the given type.
+    fn is(self, type_: &str, json: Json) -> String {
+        let names: &[&str] = match (self, json) {
+            (Dialect::MySql, Json::Null) => &["NULL"],
+            (Dialect::MySql, Json::Boolean) => &["BOOLEAN"],
+            (Dialect::MySql, Json::Number) => &["INTEGER", "UNSIGNED INTEGER", "DOUBLE", "DECIMAL"],
+            (Dialect::MySql, Json::String) => &["STRING"],
+            (Dialect::MySql, Json::Array) => &["ARRAY"],
+            (Dialect::MySql, Json::Object) => &["OBJECT"],
+            (_, Json::Null) => &["null"],
+            (Dialect::Postgres, Json::Boolean) => &["boolean"],
+            (Dialect::Postgres, Json::Number) => &["number"],
+            (Dialect::Postgres, Json::String) => &["string"],
+            (Dialect::Sqlite, Json::Boolean) => &["true", "false"],
+            (Dialect::Sqlite, Json::Number) => &["integer", "real"],
+            (Dialect::Sqlite, Json::String) => &["text"],
+            (_, Json::Array) => &["array"],
+            (_, Json::Object) => &["object"],
+        };
+
+        match names {
+            [name] => format!("{} = '{}'", type_, name),
+            _ => format!(
+                "{} IN ({})",
+                type_,
+                self.list(names.iter().map(|name| format!("'{}'", name)))
+            ),
+        }
+    }
+
+    // A check that a property of a JSON column, if present and not null, is
+    // of the given kind. Checks are NULL or FALSE if the property is missing.
+    fn json_check(self, column: &str, property: &str, kind: &Kind) -> Option<String> {
+        let type_ = self.json_type(column, Some(property));
+
+        // Numbers are only converted once they're known to be numbers, as
+        // converting anything else is an error in some dialects.
+        let number = |check: String| {
+            format!(
+                "CASE WHEN {} THEN {} ELSE FALSE END",
+                self.is(&type_, Json::Number),
+                check
+            )
+        };
+
+        let value = |number| self.json_value(column, property, number);
+
+        Some(match kind {
+            Kind::Any => return None,
+            Kind::Boolean => self.is(&type_, Json::Boolean),
+            Kind::Integer(type_) => {
+                let (min, max) = range(type_);
+                let value = value(true);
+                number(format!(
+                    "{} = ROUND({}) AND {} BETWEEN {} AND {}",
+                    value, value, value, min, max
+                ))
+            }
+            Kind::Float(_) => self.is(&type_, Json::Number),
+            Kind::String | Kind::Timestamp => self.is(&type_, Json::String),
+            Kind::Enum(values) => format!(
+                "{} AND {} IN ({})",
+                self.is(&type_, Json::String),
+                value(false),
+                self.list(values.iter().map(|value| self.string(value)))
+            ),
+            #[cfg(feature = "extensions")]
+            Kind::IntEnum(values) => number(format!(
+                "{} IN ({})",
+                value(true),
+                self.list(values.iter().map(|value| value.to_string()))
+            )),
+            Kind::Array => self.is(&type_, Json::Array),
+            Kind::Object => self.is(&type_, Json::Object),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: serde_json::Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    fn example() -> Schema {
+        schema(json!({
+            "definitions": {
+                "status": { "enum": ["on", "it's off"] }
+            },
+            "properties": {
+                "flag": { "type": "boolean" },
+                "n": { "type": "int8" },
+                "status": { "ref": "status", "nullable": true }
+            },
+            "optionalProperties": {
+                "at": { "type": "timestamp" },
+                "any": {},
+                "x": { "type": "float64" },
+                "map": { "values": { "type": "string" }}
+            }
+        }))
+    }
+
+    #[test]
+    fn create_table() {
+        assert_eq!(
+            "CREATE TABLE `t``s` (\n    \
+                 `any` JSON,\n    \
+                 `at` DATETIME(6),\n    \
+                 `flag` BOOLEAN NOT NULL,\n    \
+                 `map` JSON,\n    \
+                 `n` TINYINT NOT NULL,\n    \
+                 `status` TEXT CHECK (`status` IN ('it''s off', 'on')),\n    \
+                 `x` DOUBLE\n\
+             );",
+            super::create_table(&example(), "t`s", Dialect::MySql).unwrap()
+        );
+
+        assert_eq!(
+            "CREATE TABLE \"t\" (\n    \
+                 \"any\" TEXT CHECK (json_valid(\"any\")),\n    \
+                 \"at\" TEXT,\n    \
+                 \"flag\" INTEGER NOT NULL CHECK (\"flag\" IN (0, 1)),\n    \
+                 \"map\" TEXT CHECK (json_valid(\"map\")),\n    \
+                 \"n\" INTEGER NOT NULL CHECK (\"n\" BETWEEN -128 AND 127),\n    \
+                 \"status\" TEXT CHECK (\"status\" IN ('it''s off', 'on')),\n    \
+                 \"x\" REAL\n\
+             );",
+            super::create_table(&example(), "t", Dialect::Sqlite).unwrap()
+        );
+
+        assert_eq!(
+            Err(SqlError::NotProperties),
+            super::create_table(&schema(json!({ "type": "string" })), "t", Dialect::Postgres)
+        );
+    }
+
+    #[test]
+    fn check_constraints() {
+        assert_eq!(
+            vec![
+                "jsonb_typeof(\"d\") = 'object'",
+                "(jsonb_typeof(\"d\" -> 'at') IS NULL OR COALESCE(jsonb_typeof(\"d\" -> 'at') = 'string', FALSE))",
+                "COALESCE(jsonb_typeof(\"d\" -> 'flag') = 'boolean', \"d\" IS NULL)",
+                "(jsonb_typeof(\"d\" -> 'map') IS NULL OR COALESCE(jsonb_typeof(\"d\" -> 'map') = 'object', FALSE))",
+                "COALESCE(CASE WHEN jsonb_typeof(\"d\" -> 'n') = 'number' \
+                 THEN (\"d\" ->> 'n')::numeric = ROUND((\"d\" ->> 'n')::numeric) \
+                 AND (\"d\" ->> 'n')::numeric BETWEEN -128 AND 127 ELSE FALSE END, \"d\" IS NULL)",
+                "COALESCE((jsonb_typeof(\"d\" -> 'status') = 'null' OR jsonb_typeof(\"d\" -> 'status') = 'string' \
+                 AND \"d\" ->> 'status' IN ('it''s off', 'on')), \"d\" IS NULL)",
+                "(jsonb_typeof(\"d\" -> 'x') IS NULL OR COALESCE(jsonb_typeof(\"d\" -> 'x') = 'number', FALSE))",
+            ],
+            super::check_constraints(&example(), "d", Dialect::Postgres).unwrap()
+        );
+
+        let checks = super::check_constraints(
+            &schema(json!({
+                "properties": {
+                    "a\"b": { "type": "uint8" },
+                    "c": {}
+                }
+            })),
+            "d",
+            Dialect::MySql,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                "JSON_TYPE(`d`) = 'OBJECT'",
+                "COALESCE(CASE WHEN JSON_TYPE(JSON_EXTRACT(`d`, '$.\"a\\\\\"b\"')) \
+                 IN ('INTEGER', 'UNSIGNED INTEGER', 'DOUBLE', 'DECIMAL') \
+                 THEN JSON_EXTRACT(`d`, '$.\"a\\\\\"b\"') = ROUND(JSON_EXTRACT(`d`, '$.\"a\\\\\"b\"')) \
+                 AND JSON_EXTRACT(`d`, '$.\"a\\\\\"b\"') BETWEEN 0 AND 255 ELSE FALSE END, `d` IS NULL)",
+                "(`d` IS NULL OR JSON_TYPE(JSON_EXTRACT(`d`, '$.\"c\"')) IS NOT NULL)",
+            ],
+            checks
+        );
+    }
+}
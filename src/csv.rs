@@ -0,0 +1,387 @@
+//! Validate CSV files against properties-form schemas.
+//!
+//! Plenty of data that's shaped like a list of JSON objects arrives as CSV
+//! instead. [`validate`] reads CSV with a header row, turns each row into a
+//! JSON object, with a property for each column, and validates it against a
+//! properties-form schema.
+//!
+//! Cells in CSV are all strings, so each is converted to the type its
+//! property's schema expects:
+//!
+//! * For `boolean`, `true` and `false`, in any case, become booleans.
+//! * For numeric types, and integer enums, numbers become numbers.
+//! * For arrays and objects, cells are parsed as JSON.
+//! * Anything else, or a cell that can't be converted, stays a string, and
+//!   so fails validation if a string isn't allowed.
+//!
+//! An empty cell means the property is missing if it's optional, and `null`
+//! if it's required but nullable. Otherwise, it's an empty string.
+//!
+//! This module is only available with the `csv` feature.
+//!
+//! ```
+//! use jtd::csv::MappingOptions;
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": {
+//!         "name": { "type": "string" },
+//!         "age": { "type": "uint8" }
+//!     },
+//!     "optionalProperties": {
+//!         "admin": { "type": "boolean" }
+//!     }
+//! })).unwrap()).unwrap();
+//!
+//! let csv = "name,age,admin\nalice,43,TRUE\nbob,forty,\n";
+//! let errors = jtd::csv::validate(csv.as_bytes(), &schema, MappingOptions::new()).unwrap();
+//!
+//! assert_eq!(1, errors.len());
+//! assert_eq!(3, errors[0].line);
+//! assert_eq!(Some("age".to_owned()), errors[0].column);
+//! assert_eq!(vec!["properties", "age", "type"], errors[0].error.schema_path);
+//! ```
+
+use crate::refs::resolve;
+use crate::{Schema, Type, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde_json::{Map, Number, Value};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Read;
+use thiserror::Error;
+
+/// How [`validate`] reads CSV, and maps its columns onto properties.
+#[derive(Clone, Debug)]
+pub struct MappingOptions {
+    delimiter: u8,
+    columns: BTreeMap<String, String>,
+    ignore_unknown_columns: bool,
+    validate_options: ValidateOptions,
+}
+
+impl Default for MappingOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MappingOptions {
+    /// Constructs options for comma-separated values, with each column
+    /// mapped to the property of the same name, and no columns ignored.
+    pub fn new() -> Self {
+        Self {
+            delimiter: b',',
+            columns: BTreeMap::new(),
+            ignore_unknown_columns: false,
+            validate_options: ValidateOptions::new(),
+        }
+    }
+
+    /// Sets the character that separates cells, such as `b'\t'` for
+    /// tab-separated values.
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Maps the column with the header `column` onto the property `property`,
+    /// instead of the property with the same name as the column.
+    pub fn with_column(mut self, column: impl Into<String>, property: impl Into<String>) -> Self {
+        self.columns.insert(column.into(), property.into());
+        self
+    }
+
+    /// Sets whether to ignore columns that aren't mapped onto any property
+    /// of the schema.
+    ///
+    /// By default, they're kept as string properties, and so fail validation
+    /// unless the schema allows additional properties.
+    pub fn with_ignore_unknown_columns(mut self, ignore_unknown_columns: bool) -> Self {
+        self.ignore_unknown_columns = ignore_unknown_columns;
+        self
+    }
+
+    /// Sets the options used to validate each row.
+    pub fn with_validate_options(mut self, validate_options: ValidateOptions) -> Self {
+        self.validate_options = validate_options;
+        self
+    }
+}
+
+/// A validation error in a row of CSV.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RowError {
+    /// The line the row starts on, counting from 1, with the header on line
+    /// 1.
+    pub line: u64,
+
+    /// The header of the column the error is in, if it's in a cell. Errors
+    /// about the row as a whole, such as a missing column, aren't in a cell.
+    pub column: Option<String>,
+
+    /// The validation error, for the row as a JSON object.
+    pub error: ValidationErrorIndicator<'static>,
+}
+
+/// Errors that may arise from [`validate`].
+#[derive(Debug, Error)]
+pub enum CsvError {
+    /// The schema is not of the properties form, even after following any
+    /// `ref`, so there are no properties to map columns onto.
+    #[error("schema is not of the properties form")]
+    NotProperties,
+
+    /// The CSV couldn't be read.
+    #[error(transparent)]
+    Csv(#[from] ::csv::Error),
+
+    /// Validating a row failed.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+}
+
+/// Validates each row of CSV against a properties-form schema.
+///
+/// The first row of the CSV must be a header, naming each column. See the
+/// [module documentation][`crate::csv`] for how rows are converted to JSON.
+pub fn validate<R: Read>(
+    reader: R,
+    schema: &Schema,
+    options: MappingOptions,
+) -> Result<Vec<RowError>, CsvError> {
+    let (properties, optional_properties) = match resolve(schema, schema).0 {
+        Schema::Properties {
+            properties,
+            optional_properties,
+            ..
+        } => (properties, optional_properties),
+        _ => return Err(CsvError::NotProperties),
+    };
+
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .from_reader(reader);
+
+    // For each column, its header, and the property it's mapped onto, if
+    // any. For properties in the schema, also whether they're required, and
+    // their schema.
+    let columns: Vec<_> = reader
+        .headers()?
+        .iter()
+        .map(|header| {
+            let property = options
+                .columns
+                .get(header)
+                .map_or(header, String::as_str)
+                .to_owned();
+
+            let known = match (
+                properties.get(&property),
+                optional_properties.get(&property),
+            ) {
+                (Some(sub_schema), _) => Some((true, resolve(schema, sub_schema))),
+                (None, Some(sub_schema)) => Some((false, resolve(schema, sub_schema))),
+                (None, None) => None,
+            };
+
+            let property = if known.is_none() && options.ignore_unknown_columns {
+                None
+            } else {
+                Some(property)
+            };
+
+            (header.to_owned(), property, known)
+        })
+        .collect();
+
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |position| position.line());
+
+        let mut row = Map::new();
+        for (cell, (_, property, known)) in record.iter().zip(&columns) {
+            let property = match property {
+                Some(property) => property,
+                None => continue,
+            };
+
+            let value = match known {
+                Some((required, (sub_schema, nullable))) => {
+                    coerce(sub_schema, *required, *nullable, cell)
+                }
+                None => Some(cell.into()),
+            };
+
+            if let Some(value) = value {
+                row.insert(property.clone(), value);
+            }
+        }
+
+        let row = Value::Object(row);
+        for error in crate::validate(schema, &row, options.validate_options.clone())? {
+            let column = error.instance_path.first().and_then(|property| {
+                columns
+                    .iter()
+                    .find(|(_, p, _)| p.as_deref() == Some(property.as_ref()))
+                    .map(|(header, _, _)| header.clone())
+            });
+
+            let severity = error.severity;
+            let (instance_path, schema_path) = error.into_owned_paths();
+            errors.push(RowError {
+                line,
+                column,
+                error: ValidationErrorIndicator {
+                    instance_path: instance_path.into_iter().map(Cow::Owned).collect(),
+                    schema_path: schema_path.into_iter().map(Cow::Owned).collect(),
+                    severity,
+                },
+            });
+        }
+    }
+
+    Ok(errors)
+}
+
+// Converts a cell into the value of a property with the given schema, or
+// None if the property should be left out.
+fn coerce(schema: &Schema, required: bool, nullable: bool, cell: &str) -> Option<Value> {
+    if cell.is_empty() {
+        if !required {
+            return None;
+        }
+
+        if nullable {
+            return Some(Value::Null);
+        }
+    }
+
+    let number = || {
+        cell.parse::<i64>()
+            .ok()
+            .map(Number::from)
+            .or_else(|| cell.parse::<f64>().ok().and_then(Number::from_f64))
+            .map(Value::Number)
+    };
+
+    let value = match schema {
+        Schema::Type {
+            type_: Type::Boolean,
+            ..
+        } => match cell.to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Schema::Type {
+            type_: Type::String | Type::Timestamp,
+            ..
+        } => None,
+        Schema::Type { .. } => number(),
+        #[cfg(feature = "extensions")]
+        Schema::IntEnum { .. } => number(),
+        Schema::Elements { .. }
+        | Schema::Properties { .. }
+        | Schema::Values { .. }
+        | Schema::Discriminator { .. } => serde_json::from_str(cell).ok(),
+        _ => None,
+    };
+
+    Some(value.unwrap_or_else(|| cell.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "score": { "type": "float64", "nullable": true }
+                },
+                "properties": {
+                    "id": { "type": "uint32" },
+                    "score": { "ref": "score" },
+                    "tags": { "elements": { "type": "string" }}
+                },
+                "optionalProperties": {
+                    "active": { "type": "boolean" },
+                    "note": { "type": "string" }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn errors(csv: &str, options: MappingOptions) -> Vec<(u64, Option<String>, String)> {
+        validate(csv.as_bytes(), &schema(), options)
+            .unwrap()
+            .into_iter()
+            .map(|error| {
+                let schema_path = error.error.schema_path.to_string();
+                (error.line, error.column, schema_path)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn validates_rows() {
+        let csv = "ID\tscore\ttags\tactive\textra\n\
+                   1\t0.5\t[\"a\"]\tfalse\tx\n\
+                   2\t\t[]\t\t\n\
+                   \"3\n\"\t1e3\t\"[1]\"\tyes\t\n\
+                   \t\tnot json\t\t\n";
+
+        let options = MappingOptions::new()
+            .with_delimiter(b'\t')
+            .with_column("ID", "id")
+            .with_ignore_unknown_columns(true);
+
+        assert_eq!(
+            vec![
+                (4, Some("ID".into()), "/properties/id/type".into()),
+                (
+                    4,
+                    Some("tags".into()),
+                    "/properties/tags/elements/type".into()
+                ),
+                (
+                    4,
+                    Some("active".into()),
+                    "/optionalProperties/active/type".into()
+                ),
+                (6, Some("ID".into()), "/properties/id/type".into()),
+                (6, Some("tags".into()), "/properties/tags/elements".into()),
+            ],
+            errors(csv, options)
+        );
+
+        // Without the mapping, "id" is missing, and "ID" is unknown, as is
+        // "extra" without ignoring unknown columns.
+        assert_eq!(
+            vec![
+                (2, None, "/properties/id".into()),
+                (2, Some("ID".into()), "".into()),
+                (2, Some("extra".into()), "".into()),
+            ],
+            errors("ID,score,tags,extra\n1,,[],x\n", MappingOptions::new())
+        );
+    }
+
+    #[test]
+    fn rejects_non_properties() {
+        let schema =
+            Schema::from_serde_schema(serde_json::from_value(json!({ "elements": {} })).unwrap())
+                .unwrap();
+
+        assert!(matches!(
+            validate("a\n".as_bytes(), &schema, MappingOptions::new()),
+            Err(CsvError::NotProperties)
+        ));
+    }
+}
@@ -0,0 +1,1495 @@
+use crate::stream::{Ctx, Object, Property};
+use crate::{validate, Schema, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde::de::{self, Deserialize, DeserializeSeed, Visitor};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use thiserror::Error;
+
+/// A [`serde::Deserializer`] that validates data against a schema as it is
+/// being deserialized.
+///
+/// This wraps any other deserializer, and checks each value that deserializer
+/// produces against a [`Schema`] before passing it along. This lets you
+/// validate and decode data in a single pass, in any self-describing format
+/// serde supports -- JSON, CBOR, MessagePack, and so on -- without first
+/// building a [`serde_json::Value`].
+///
+/// As soon as a value is found that the schema rejects, deserialization is
+/// aborted. Use [`Deserializer::deserialize`] to get the corresponding
+/// [`ValidationErrorIndicator`] back, in a [`DeserializeError::Invalid`].
+///
+/// ```
+/// use jtd::{DeserializeError, Schema};
+/// use serde_json::json;
+///
+/// #[derive(serde::Deserialize, Debug)]
+/// struct User {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "name": { "type": "string" },
+///         "age": { "type": "uint8" }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let mut json = serde_json::Deserializer::from_str(r#"{"name":"John","age":43}"#);
+/// let user: User = jtd::Deserializer::new(&mut json, &schema).deserialize().unwrap();
+/// assert_eq!(43, user.age);
+///
+/// // age is valid for the Rust type, but not for the schema.
+/// let mut json = serde_json::Deserializer::from_str(r#"{"name":"John","age":300}"#);
+/// match jtd::Deserializer::new(&mut json, &schema).deserialize::<User>() {
+///     Err(DeserializeError::Invalid(indicator)) => {
+///         assert_eq!(vec!["age"], indicator.instance_path);
+///         assert_eq!(vec!["properties", "age", "type"], indicator.schema_path);
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+///
+/// # Differences from [`validate()`][`crate::validate()`]
+///
+/// Because this type sees the input only once, and in order, it can't always
+/// do everything [`validate()`][`crate::validate()`] does:
+///
+/// * Only the first validation error is reported.
+///
+/// * In a discriminator-form schema, the schema for the rest of an object
+///   depends on the value of the discriminator. The values of properties that
+///   come before the discriminator in the input are buffered, as a
+///   [`serde_json::Value`], until the discriminator is found and they can be
+///   checked. As with serde's internally tagged enums, those values can't be
+///   borrowed from the input.
+///
+/// * Formats that are not self-describing, which deserialize structs as
+///   sequences, are not supported.
+pub struct Deserializer<'s, D> {
+    inner: D,
+    ctx: Ctx<'s>,
+}
+
+/// Errors that may arise from [`Deserializer::deserialize`].
+#[derive(Debug, Error)]
+pub enum DeserializeError<E> {
+    /// The input was rejected by the schema.
+    #[error(
+        "instance at {} rejected by schema at {}",
+        .0.instance_path,
+        .0.schema_path
+    )]
+    Invalid(ValidationErrorIndicator<'static>),
+
+    /// The input exceeded one of the per-value limits of the options, such as
+    /// [`ValidateOptions::with_max_string_len`].
+    #[error(transparent)]
+    Validate(ValidateError),
+
+    /// The wrapped deserializer returned an error, or the input was rejected
+    /// by the type being deserialized into.
+    #[error(transparent)]
+    Inner(E),
+}
+
+/// Errors that may arise from [`deserialize()`].
+#[derive(Debug, Error)]
+pub enum DeserializeValueError<'a> {
+    /// The value was rejected by the schema, with these errors.
+    #[error("value rejected by schema, with {} errors", .0.len())]
+    Invalid(Vec<ValidationErrorIndicator<'a>>),
+
+    /// The schema couldn't be used to validate the value.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+
+    /// The value was valid, but rejected by the type being deserialized into.
+    #[error(transparent)]
+    Deserialize(serde_json::Error),
+}
+
+/// Validates a value against a schema and deserializes it into a `T`, going
+/// over the value once when it's valid.
+///
+/// This is equivalent to [`validate()`][`crate::validate()`], with default
+/// options, followed by [`serde_json::from_value`], but doesn't need a
+/// second pass over valid values. If the value is invalid, every validation
+/// error is returned, exactly as [`validate()`][`crate::validate()`] would
+/// return them.
+///
+/// Values that fail to deserialize into `T`, but satisfy the schema, give a
+/// [`DeserializeValueError::Deserialize`].
+///
+/// ```
+/// use jtd::{DeserializeValueError, Schema};
+/// use serde_json::json;
+///
+/// #[derive(serde::Deserialize, Debug)]
+/// struct User<'a> {
+///     name: &'a str,
+///     age: u32,
+/// }
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "name": { "type": "string" },
+///         "age": { "type": "uint8" }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let value = json!({ "name": "John", "age": 43 });
+/// let user: User = jtd::deserialize(&schema, &value).unwrap();
+/// assert_eq!("John", user.name);
+///
+/// let value = json!({ "name": 1, "age": 300 });
+/// match jtd::deserialize::<User>(&schema, &value) {
+///     Err(DeserializeValueError::Invalid(errors)) => {
+///         assert_eq!(vec!["age"], errors[0].instance_path);
+///         assert_eq!(vec!["name"], errors[1].instance_path);
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+///
+/// # Performance
+///
+/// The single pass is done with a [`Deserializer`], which checks values as
+/// `T` asks for them. When it finds an error, the value is also validated with
+/// [`validate()`][`crate::validate()`], to find every error.
+pub fn deserialize<'a, T>(
+    schema: &'a Schema,
+    value: &'a Value,
+) -> Result<T, DeserializeValueError<'a>>
+where
+    T: de::Deserialize<'a>,
+{
+    let result = match Deserializer::new(value, schema).deserialize::<T>() {
+        Ok(t) => return Ok(t),
+        Err(err) => err,
+    };
+
+    let errors = validate(schema, value, ValidateOptions::new())?;
+    if !errors.is_empty() {
+        return Err(DeserializeValueError::Invalid(errors));
+    }
+
+    match result {
+        DeserializeError::Inner(err) => Err(DeserializeValueError::Deserialize(err)),
+        DeserializeError::Validate(err) => Err(DeserializeValueError::Validate(err)),
+
+        // The Deserializer treats every violation as an error, whatever its
+        // severity, so it may have rejected a value that's valid after all.
+        DeserializeError::Invalid(_) => {
+            T::deserialize(value).map_err(DeserializeValueError::Deserialize)
+        }
+    }
+}
+
+impl<'s, D> Deserializer<'s, D> {
+    /// Wraps a deserializer, so that its data is checked against `schema`.
+    ///
+    /// `schema` is expected to be a root schema; any refs in it are resolved
+    /// against its definitions.
+    pub fn new(inner: D, schema: &'s Schema) -> Self {
+        Self {
+            inner,
+            ctx: Ctx::new(schema),
+        }
+    }
+
+    /// Sets the options that decide which values are valid, such as
+    /// [`ValidateOptions::with_case_insensitive_enums`] and
+    /// [`ValidateOptions::with_timestamp_validator`].
+    ///
+    /// The per-value limits, such as [`ValidateOptions::with_max_string_len`],
+    /// are checked as the input is read, and give a
+    /// [`DeserializeError::Validate`] if it exceeds them. Options about which
+    /// errors are reported, such as [`ValidateOptions::with_max_errors`], have
+    /// no effect: the first error always aborts deserialization.
+    pub fn with_options(self, options: ValidateOptions) -> Self {
+        self.ctx.state.borrow_mut().options = options;
+        self
+    }
+
+    /// Deserializes a `T`, checking the input against the schema along the
+    /// way.
+    pub fn deserialize<'de, T>(self) -> Result<T, DeserializeError<D::Error>>
+    where
+        D: de::Deserializer<'de>,
+        T: de::Deserialize<'de>,
+    {
+        let state = self.ctx.state.clone();
+        T::deserialize(self).map_err(|err| {
+            let mut state = state.borrow_mut();
+            match (state.error.take(), state.limit.take()) {
+                (Some(indicator), _) => DeserializeError::Invalid(indicator),
+                (None, Some(limit)) => DeserializeError::Validate(limit),
+                (None, None) => DeserializeError::Inner(err),
+            }
+        })
+    }
+}
+
+macro_rules! forward_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                let resolved = self.ctx.resolve().map_err(de::Error::custom)?;
+                let result = self.inner.$method($($arg,)* Wrap {
+                    inner: visitor,
+                    ctx: resolved.ctx.clone(),
+                    nullable: resolved.nullable,
+                });
+
+                resolved.finish();
+                result
+            }
+        )*
+    };
+}
+
+impl<'de, 's, D> de::Deserializer<'de> for Deserializer<'s, D>
+where
+    D: de::Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    forward_deserialize! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+    }
+
+    // Values the type ignores are still checked against the schema, which
+    // means visiting them.
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+// A visitor that checks values against the schema before passing them to the
+// wrapped visitor.
+struct Wrap<'s, V> {
+    inner: V,
+    ctx: Ctx<'s>,
+    nullable: bool,
+}
+
+macro_rules! visit_int {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method<E: de::Error>(self, v: $ty) -> Result<V::Value, E> {
+                self.ctx.check_int(v as f64).map_err(E::custom)?;
+                self.inner.$method(v)
+            }
+        )*
+    };
+}
+
+impl<'de, 's, V> Visitor<'de> for Wrap<'s, V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<V::Value, E> {
+        self.ctx.check_bool().map_err(E::custom)?;
+        self.inner.visit_bool(v)
+    }
+
+    visit_int! {
+        visit_i8(i8);
+        visit_i16(i16);
+        visit_i32(i32);
+        visit_i64(i64);
+        visit_i128(i128);
+        visit_u8(u8);
+        visit_u16(u16);
+        visit_u32(u32);
+        visit_u64(u64);
+        visit_u128(u128);
+        visit_f32(f32);
+        visit_f64(f64);
+    }
+
+    fn visit_char<E: de::Error>(self, v: char) -> Result<V::Value, E> {
+        self.ctx
+            .check_str(v.encode_utf8(&mut [0; 4]))
+            .map_err(E::custom)?;
+        self.inner.visit_char(v)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<V::Value, E> {
+        self.ctx.check_str(v).map_err(E::custom)?;
+        self.inner.visit_str(v)
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<V::Value, E> {
+        self.ctx.check_str(v).map_err(E::custom)?;
+        self.inner.visit_borrowed_str(v)
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<V::Value, E> {
+        self.ctx.check_str(&v).map_err(E::custom)?;
+        self.inner.visit_string(v)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<V::Value, E> {
+        self.ctx.check_bytes().map_err(E::custom)?;
+        self.inner.visit_bytes(v)
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(self, v: &'de [u8]) -> Result<V::Value, E> {
+        self.ctx.check_bytes().map_err(E::custom)?;
+        self.inner.visit_borrowed_bytes(v)
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<V::Value, E> {
+        self.ctx.check_bytes().map_err(E::custom)?;
+        self.inner.visit_byte_buf(v)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.ctx.check_null(self.nullable).map_err(E::custom)?;
+        self.inner.visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.inner.visit_some(Deserializer {
+            inner: deserializer,
+            ctx: self.ctx,
+        })
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.ctx.check_null(self.nullable).map_err(E::custom)?;
+        self.inner.visit_unit()
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.inner.visit_newtype_struct(Deserializer {
+            inner: deserializer,
+            ctx: self.ctx,
+        })
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<V::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        self.ctx.enter_nested().map_err(de::Error::custom)?;
+        let result = match self.ctx.schema {
+            Schema::Empty { .. } if !self.ctx.has_limits() => self.inner.visit_seq(seq),
+            Schema::Empty { .. } => self.inner.visit_seq(WrapSeq {
+                inner: seq,
+                ctx: self.ctx.clone(),
+                index: 0,
+            }),
+            Schema::Elements { elements, .. } => {
+                self.ctx.push_schema_token("elements");
+                let result = self.inner.visit_seq(WrapSeq {
+                    inner: seq,
+                    ctx: self.ctx.child(elements),
+                    index: 0,
+                });
+                self.ctx.pop_schema_token();
+                result
+            }
+            _ => Err(de::Error::custom(self.ctx.wrong_type())),
+        };
+
+        self.ctx.leave_nested();
+        result
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<V::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        self.ctx.enter_nested().map_err(de::Error::custom)?;
+        let ctx = self.ctx.clone();
+        let result = match self.ctx.schema {
+            Schema::Empty { .. } if !self.ctx.has_limits() => self.inner.visit_map(map),
+            Schema::Empty { .. }
+            | Schema::Properties { .. }
+            | Schema::Values { .. }
+            | Schema::Discriminator { .. } => self.inner.visit_map(WrapMap {
+                inner: map,
+                object: Object::new(self.ctx),
+                key: None,
+            }),
+            _ => Err(de::Error::custom(self.ctx.wrong_type())),
+        };
+
+        ctx.leave_nested();
+        result
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<V::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        match self.ctx.schema {
+            Schema::Empty { .. } => self.inner.visit_enum(data),
+            _ => self.inner.visit_enum(WrapEnum {
+                inner: data,
+                ctx: self.ctx,
+            }),
+        }
+    }
+}
+
+struct WrapSeq<'s, A> {
+    inner: A,
+    ctx: Ctx<'s>,
+    index: usize,
+}
+
+impl<'de, 's, A> de::SeqAccess<'de> for WrapSeq<'s, A>
+where
+    A: de::SeqAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.ctx.push_instance_token(&self.index.to_string());
+        let result = self
+            .inner
+            .next_element_seed(Seed::new(seed, self.ctx.clone()));
+        self.ctx.pop_instance_token();
+
+        self.index += 1;
+        if let Ok(Some(_)) = result {
+            self.ctx
+                .check_len(self.index, true)
+                .map_err(de::Error::custom)?;
+        }
+
+        result
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+// A seed that wraps the deserializer it's given in a validating one.
+//
+// If it has a buffer, it instead deserializes a Value, keeps a copy of it in
+// the buffer, and passes it along unchecked.
+struct Seed<'s, T> {
+    inner: T,
+    ctx: Ctx<'s>,
+    buffer: Option<Rc<RefCell<Option<Value>>>>,
+}
+
+impl<'s, T> Seed<'s, T> {
+    fn new(inner: T, ctx: Ctx<'s>) -> Self {
+        Self {
+            inner,
+            ctx,
+            buffer: None,
+        }
+    }
+}
+
+impl<'de, 's, T> DeserializeSeed<'de> for Seed<'s, T>
+where
+    T: DeserializeSeed<'de>,
+{
+    type Value = T::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<T::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        match self.buffer {
+            None => self.inner.deserialize(Deserializer {
+                inner: deserializer,
+                ctx: self.ctx,
+            }),
+            Some(buffer) => {
+                // The value is checked against the per-value limits of the
+                // options as it's buffered.
+                let value = Value::deserialize(Deserializer {
+                    inner: deserializer,
+                    ctx: self.ctx,
+                })?;
+                *buffer.borrow_mut() = Some(value.clone());
+                self.inner.deserialize(value).map_err(de::Error::custom)
+            }
+        }
+    }
+}
+
+impl<'s> Object<'s> {
+    // Deserializes the value of a property with a given seed.
+    fn value<'de, T, F, E>(&mut self, key: &str, seed: T, deserialize: F) -> Result<T::Value, E>
+    where
+        T: DeserializeSeed<'de>,
+        F: FnOnce(Seed<'s, Capture<T>>) -> Result<T::Value, E>,
+        E: de::Error,
+    {
+        let ctx = match self.property(key).map_err(E::custom)? {
+            Property::Check(tokens, sub_schema) => {
+                for token in &tokens {
+                    self.ctx.push_schema_token(token);
+                }
+
+                self.ctx.push_instance_token(key);
+                let result = deserialize(Seed::new(Capture::new(seed), self.ctx.child(sub_schema)));
+                self.ctx.pop_instance_token();
+
+                for _ in &tokens {
+                    self.ctx.pop_schema_token();
+                }
+
+                return result;
+            }
+            Property::Skip => self.ctx.child(self.ctx.root).with_empty(),
+            Property::BeforeTag => {
+                let buffer = Rc::new(RefCell::new(None));
+                let result = deserialize(Seed {
+                    inner: Capture::new(seed),
+                    ctx: self.ctx.child(self.ctx.root).with_empty(),
+                    buffer: Some(buffer.clone()),
+                })?;
+
+                if let Some(value) = buffer.borrow_mut().take() {
+                    self.buffered.push((key.to_owned(), value));
+                }
+
+                return Ok(result);
+            }
+            Property::Tag => {
+                let captured = Rc::new(RefCell::new(None));
+                let result = deserialize(Seed::new(
+                    Capture {
+                        inner: seed,
+                        captured: captured.clone(),
+                    },
+                    self.ctx.child(self.ctx.root).with_empty(),
+                ))?;
+
+                let tag = captured.borrow_mut().take();
+                self.tag(tag.as_deref()).map_err(E::custom)?;
+                self.check_buffered()?;
+                return Ok(result);
+            }
+        };
+
+        deserialize(Seed::new(Capture::new(seed), ctx))
+    }
+
+    // Checks the values of properties that came before the tag, now that the
+    // variant they belong to is known.
+    fn check_buffered<E: de::Error>(&mut self) -> Result<(), E> {
+        for (key, value) in std::mem::take(&mut self.buffered) {
+            if let Property::Check(tokens, sub_schema) = self.property(&key).map_err(E::custom)? {
+                for token in &tokens {
+                    self.ctx.push_schema_token(token);
+                }
+
+                self.ctx.push_instance_token(&key);
+                let result = de::IgnoredAny::deserialize(Deserializer {
+                    inner: value,
+                    ctx: self.ctx.child(sub_schema),
+                });
+                self.ctx.pop_instance_token();
+
+                for _ in &tokens {
+                    self.ctx.pop_schema_token();
+                }
+
+                result.map_err(E::custom)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct WrapMap<'s, A> {
+    inner: A,
+    object: Object<'s>,
+    key: Option<String>,
+}
+
+impl<'de, 's, A> de::MapAccess<'de> for WrapMap<'s, A>
+where
+    A: de::MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let captured = Rc::new(RefCell::new(None));
+        let key = self.inner.next_key_seed(Capture {
+            inner: seed,
+            captured: captured.clone(),
+        })?;
+
+        if key.is_none() {
+            self.object.end().map_err(de::Error::custom)?;
+        }
+
+        self.key = captured.borrow_mut().take();
+        Ok(key)
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let key = self.key.take().unwrap_or_default();
+        let inner = &mut self.inner;
+        self.object
+            .value(&key, seed, |seed| inner.next_value_seed(seed))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+struct WrapEnum<'s, A> {
+    inner: A,
+    ctx: Ctx<'s>,
+}
+
+impl<'de, 's, A> de::EnumAccess<'de> for WrapEnum<'s, A>
+where
+    A: de::EnumAccess<'de>,
+{
+    type Error = A::Error;
+    type Variant = WrapVariant<'s, A::Variant>;
+
+    fn variant_seed<T>(self, seed: T) -> Result<(T::Value, Self::Variant), A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let captured = Rc::new(RefCell::new(None));
+        let (value, variant) = self.inner.variant_seed(Capture {
+            inner: seed,
+            captured: captured.clone(),
+        })?;
+
+        let name = captured.borrow_mut().take().unwrap_or_default();
+        Ok((
+            value,
+            WrapVariant {
+                inner: variant,
+                ctx: self.ctx,
+                name,
+            },
+        ))
+    }
+}
+
+// An enum variant. Unit variants are represented as strings, and all others
+// as objects with a single property, named after the variant.
+struct WrapVariant<'s, A> {
+    inner: A,
+    ctx: Ctx<'s>,
+    name: String,
+}
+
+impl<'s, A> WrapVariant<'s, A> {
+    fn content<F, E>(self, f: F) -> Result<(), E>
+    where
+        F: FnOnce(Ctx<'s>, A) -> Result<(), E>,
+        E: de::Error,
+    {
+        match self.ctx.schema {
+            Schema::Properties { .. } | Schema::Values { .. } | Schema::Discriminator { .. } => {}
+            _ => return Err(de::Error::custom(self.ctx.wrong_type())),
+        }
+
+        self.ctx.enter_nested().map_err(E::custom)?;
+        let mut object = Object::new(self.ctx.clone());
+        let inner = self.inner;
+        object.value(&self.name, PhantomSeed, |seed| f(seed.ctx, inner))?;
+        object.end().map_err(E::custom)?;
+        self.ctx.leave_nested();
+        Ok(())
+    }
+}
+
+// A placeholder seed, used where Object::value is driven by a variant rather
+// than a map.
+struct PhantomSeed;
+
+impl<'de> DeserializeSeed<'de> for PhantomSeed {
+    type Value = ();
+
+    fn deserialize<D>(self, _: D) -> Result<(), D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        Ok(())
+    }
+}
+
+impl<'de, 's, A> de::VariantAccess<'de> for WrapVariant<'s, A>
+where
+    A: de::VariantAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.ctx
+            .clone()
+            .resolve()
+            .and_then(|resolved| {
+                let result = resolved.ctx.check_str(&self.name);
+                resolved.finish();
+                result
+            })
+            .map_err(de::Error::custom)?;
+
+        self.inner.unit_variant()
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, A::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let mut out = None;
+        self.content(|ctx, inner: A| {
+            out = Some(inner.newtype_variant_seed(Seed::new(seed, ctx))?);
+            Ok(())
+        })?;
+
+        Ok(out.unwrap())
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut out = None;
+        self.content(|ctx, inner: A| {
+            let resolved = ctx.resolve().map_err(de::Error::custom)?;
+            let result = inner.tuple_variant(
+                len,
+                Wrap {
+                    inner: visitor,
+                    ctx: resolved.ctx.clone(),
+                    nullable: resolved.nullable,
+                },
+            );
+            resolved.finish();
+            out = Some(result?);
+            Ok(())
+        })?;
+
+        Ok(out.unwrap())
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut out = None;
+        self.content(|ctx, inner: A| {
+            let resolved = ctx.resolve().map_err(de::Error::custom)?;
+            let result = inner.struct_variant(
+                fields,
+                Wrap {
+                    inner: visitor,
+                    ctx: resolved.ctx.clone(),
+                    nullable: resolved.nullable,
+                },
+            );
+            resolved.finish();
+            out = Some(result?);
+            Ok(())
+        })?;
+
+        Ok(out.unwrap())
+    }
+}
+
+// A seed that records the string its deserializer produces, if any. Used to
+// find out the names of keys and the values of discriminator tags, whatever
+// type the caller is deserializing them into.
+struct Capture<T> {
+    inner: T,
+    captured: Rc<RefCell<Option<String>>>,
+}
+
+impl<T> Capture<T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            captured: Rc::new(RefCell::new(None)),
+        }
+    }
+}
+
+impl<'de, T> DeserializeSeed<'de> for Capture<T>
+where
+    T: DeserializeSeed<'de>,
+{
+    type Value = T::Value;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<T::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.inner.deserialize(CaptureDeserializer {
+            inner: deserializer,
+            captured: self.captured,
+        })
+    }
+}
+
+struct CaptureDeserializer<D> {
+    inner: D,
+    captured: Rc<RefCell<Option<String>>>,
+}
+
+macro_rules! forward_capture {
+    ($($method:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            fn $method<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.inner.$method($($arg,)* CaptureVisitor {
+                    inner: visitor,
+                    captured: self.captured,
+                })
+            }
+        )*
+    };
+}
+
+impl<'de, D> de::Deserializer<'de> for CaptureDeserializer<D>
+where
+    D: de::Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    forward_capture! {
+        deserialize_any();
+        deserialize_bool();
+        deserialize_i8();
+        deserialize_i16();
+        deserialize_i32();
+        deserialize_i64();
+        deserialize_i128();
+        deserialize_u8();
+        deserialize_u16();
+        deserialize_u32();
+        deserialize_u64();
+        deserialize_u128();
+        deserialize_f32();
+        deserialize_f64();
+        deserialize_char();
+        deserialize_str();
+        deserialize_string();
+        deserialize_bytes();
+        deserialize_byte_buf();
+        deserialize_option();
+        deserialize_unit();
+        deserialize_unit_struct(name: &'static str);
+        deserialize_newtype_struct(name: &'static str);
+        deserialize_seq();
+        deserialize_tuple(len: usize);
+        deserialize_tuple_struct(name: &'static str, len: usize);
+        deserialize_map();
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]);
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]);
+        deserialize_identifier();
+        deserialize_ignored_any();
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+struct CaptureVisitor<V> {
+    inner: V,
+    captured: Rc<RefCell<Option<String>>>,
+}
+
+macro_rules! visit_forward {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method<E: de::Error>(self, v: $ty) -> Result<V::Value, E> {
+                self.inner.$method(v)
+            }
+        )*
+    };
+}
+
+impl<'de, V> Visitor<'de> for CaptureVisitor<V>
+where
+    V: Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    // Only strings are captured. Other kinds of keys can't be the name of a
+    // property, and other kinds of tags can't be in a mapping.
+    fn visit_char<E: de::Error>(self, v: char) -> Result<V::Value, E> {
+        *self.captured.borrow_mut() = Some(v.to_string());
+        self.inner.visit_char(v)
+    }
+
+    visit_forward! {
+        visit_bool(bool);
+        visit_i64(i64);
+        visit_i128(i128);
+        visit_u64(u64);
+        visit_u128(u128);
+        visit_f64(f64);
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<V::Value, E> {
+        *self.captured.borrow_mut() = Some(v.to_owned());
+        self.inner.visit_str(v)
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<V::Value, E> {
+        *self.captured.borrow_mut() = Some(v.to_owned());
+        self.inner.visit_borrowed_str(v)
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<V::Value, E> {
+        *self.captured.borrow_mut() = Some(v.clone());
+        self.inner.visit_string(v)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<V::Value, E> {
+        self.inner.visit_bytes(v)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        self.inner.visit_none()
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.inner.visit_some(deserializer)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        self.inner.visit_unit()
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<V::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.inner.visit_newtype_struct(deserializer)
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<V::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        self.inner.visit_seq(seq)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<V::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        self.inner.visit_map(map)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<V::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        self.inner.visit_enum(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: serde_json::Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    fn deserialize<T: de::DeserializeOwned>(
+        schema: &Schema,
+        input: &str,
+    ) -> Result<T, DeserializeError<serde_json::Error>> {
+        let mut json = serde_json::Deserializer::from_str(input);
+        Deserializer::new(&mut json, schema).deserialize()
+    }
+
+    #[test]
+    fn agrees_with_validate() {
+        let schema = schema(json!({
+            "definitions": {
+                "point": {
+                    "properties": {
+                        "x": { "type": "int8" },
+                        "y": { "type": "float64" }
+                    }
+                }
+            },
+            "properties": {
+                "points": { "elements": { "ref": "point", "nullable": true }},
+                "tags": { "values": { "enum": ["a", "b"] }},
+                "shape": {
+                    "discriminator": "kind",
+                    "mapping": {
+                        "circle": { "properties": { "r": { "type": "uint8" }}},
+                        "square": { "optionalProperties": { "side": { "type": "uint8" }}}
+                    }
+                }
+            },
+            "optionalProperties": {
+                "at": { "type": "timestamp" }
+            }
+        }));
+
+        let instances = [
+            json!({ "points": [], "tags": {}, "shape": { "kind": "square" }}),
+            json!({ "points": [null, { "x": 1, "y": 1.5 }], "tags": { "x": "a" }, "shape": { "kind": "circle", "r": 3 }, "at": "2020-01-01T00:00:00Z" }),
+            json!({ "points": [{ "x": 128, "y": 0 }], "tags": {}, "shape": { "kind": "square" }}),
+            json!({ "points": [{ "x": 1 }], "tags": {}, "shape": { "kind": "square" }}),
+            json!({ "points": [], "tags": { "x": "c" }, "shape": { "kind": "square" }}),
+            json!({ "points": [], "tags": {}, "shape": { "kind": "triangle" }}),
+            json!({ "points": [], "tags": {}, "shape": { "kind": 3 }}),
+            json!({ "points": [], "tags": {}, "shape": {}}),
+            json!({ "points": [], "tags": {}, "shape": { "kind": "circle", "r": -1 }}),
+            json!({ "points": [], "tags": {}, "shape": { "kind": "square", "r": 1 }}),
+            json!({ "points": [], "tags": {}, "shape": { "kind": "square" }, "at": "yesterday" }),
+            json!({ "points": [], "tags": {}, "shape": { "kind": "square" }, "extra": true }),
+            json!({ "points": {}, "tags": {}, "shape": { "kind": "square" }}),
+            json!(null),
+        ];
+
+        for instance in &instances {
+            let expected = crate::validate(
+                &schema,
+                instance,
+                crate::ValidateOptions::new().with_max_errors(1),
+            )
+            .unwrap()
+            .into_iter()
+            .next();
+
+            let actual = match deserialize::<serde_json::Value>(&schema, &instance.to_string()) {
+                Ok(value) => {
+                    assert_eq!(instance, &value);
+                    None
+                }
+                Err(DeserializeError::Invalid(indicator)) => Some(indicator),
+                Err(DeserializeError::Validate(err)) => panic!("{}", err),
+                Err(DeserializeError::Inner(err)) => panic!("{}", err),
+            };
+
+            assert_eq!(expected, actual, "{}", instance);
+        }
+    }
+
+    #[test]
+    fn tag_after_properties() {
+        let schema = schema(json!({
+            "definitions": { "r": { "type": "uint8" }},
+            "discriminator": "kind",
+            "mapping": {
+                "circle": {
+                    "properties": { "r": { "ref": "r" }},
+                    "optionalProperties": { "at": { "elements": { "type": "timestamp" }}}
+                }
+            }
+        }));
+
+        assert_eq!(
+            json!({ "r": 3, "kind": "circle" }),
+            deserialize::<serde_json::Value>(&schema, r#"{"r":3,"kind":"circle"}"#).unwrap()
+        );
+
+        match deserialize::<serde_json::Value>(&schema, r#"{"r":-1,"kind":"circle"}"#) {
+            Err(DeserializeError::Invalid(indicator)) => {
+                assert_eq!(vec!["r"], indicator.instance_path);
+                assert_eq!(vec!["definitions", "r", "type"], indicator.schema_path);
+            }
+            other => panic!("{:?}", other),
+        }
+
+        match deserialize::<serde_json::Value>(&schema, r#"{"at":["x"],"kind":"circle","r":1}"#) {
+            Err(DeserializeError::Invalid(indicator)) => {
+                assert_eq!(vec!["at", "0"], indicator.instance_path);
+                assert_eq!(
+                    vec![
+                        "mapping",
+                        "circle",
+                        "optionalProperties",
+                        "at",
+                        "elements",
+                        "type"
+                    ],
+                    indicator.schema_path
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+
+        match deserialize::<serde_json::Value>(&schema, r#"{"side":3,"kind":"circle"}"#) {
+            Err(DeserializeError::Invalid(indicator)) => {
+                assert_eq!(vec!["side"], indicator.instance_path);
+                assert_eq!(vec!["mapping", "circle"], indicator.schema_path);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn rust_types() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum Color {
+            Red,
+            Green,
+        }
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Paint {
+            color: Color,
+            coats: Option<u8>,
+        }
+
+        let schema = schema(json!({
+            "properties": {
+                "color": { "enum": ["red", "green"] },
+                "coats": { "type": "uint8", "nullable": true }
+            }
+        }));
+
+        assert_eq!(
+            Paint {
+                color: Color::Red,
+                coats: None
+            },
+            deserialize(&schema, r#"{"color":"red","coats":null}"#).unwrap()
+        );
+
+        match deserialize::<Paint>(&schema, r#"{"color":"green"}"#) {
+            Err(DeserializeError::Invalid(indicator)) => {
+                assert!(indicator.instance_path.is_empty());
+                assert_eq!(vec!["properties", "coats"], indicator.schema_path);
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // Errors from the Rust type, rather than the schema, are passed along.
+        assert!(matches!(
+            deserialize::<Paint>(&schema, r#"{"color":"red","coats":"x"}"#),
+            Err(DeserializeError::Inner(_))
+        ));
+        assert!(matches!(
+            deserialize::<Paint>(&schema, r#"{"color":"red""#),
+            Err(DeserializeError::Inner(_))
+        ));
+    }
+
+    #[test]
+    fn checks_ignored_values() {
+        #[derive(serde::Deserialize)]
+        struct Empty {}
+
+        let schema = schema(json!({
+            "optionalProperties": { "a": { "type": "string", "nullable": true }}
+        }));
+
+        match deserialize::<Empty>(&schema, r#"{"a":[1]}"#) {
+            Err(DeserializeError::Invalid(indicator)) => {
+                assert_eq!(vec!["a"], indicator.instance_path);
+            }
+            other => panic!("{:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn deserialize_value() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Shape<'a> {
+            kind: &'a str,
+            side: u8,
+        }
+
+        let schema = schema(json!({
+            "discriminator": "kind",
+            "mapping": {
+                "square": {
+                    "properties": {
+                        "side": {
+                            "type": "uint8",
+                            "metadata": { "severityOnViolation": "warning" }
+                        }
+                    },
+                    "optionalProperties": { "a": { "type": "string" }}
+                }
+            }
+        }));
+
+        let value = json!({ "kind": "square", "side": 2 });
+        assert_eq!(
+            Shape {
+                kind: "square",
+                side: 2
+            },
+            super::deserialize(&schema, &value).unwrap()
+        );
+
+        // "a" comes before the tag, so it's checked once the tag is found.
+        let value = json!({ "kind": "square", "side": 2, "a": 1 });
+        match super::deserialize::<Shape>(&schema, &value) {
+            Err(DeserializeValueError::Invalid(errors)) => {
+                assert_eq!(1, errors.len());
+                assert_eq!(vec!["a"], errors[0].instance_path);
+            }
+            other => panic!("{:?}", other),
+        }
+
+        // Warnings don't make the value invalid, but 2.5 still isn't a u8.
+        let value = json!({ "kind": "square", "side": 2.5 });
+        assert!(matches!(
+            super::deserialize::<Shape>(&schema, &value),
+            Err(DeserializeValueError::Deserialize(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "extensions")]
+    fn key_options_agree_with_validate() {
+        use crate::UnicodeNormalization;
+
+        let schema = schema(json!({
+            "properties": {
+                "a": {
+                    "values": { "enum": ["caf\u{e9}", "Tea"] },
+                    "keys": { "enum": ["caf\u{e9}", "Tea"] }
+                },
+                "b": {
+                    "values": {},
+                    "keys": { "type": "string", "pattern": "^caf\u{e9}$" }
+                }
+            }
+        }));
+
+        let instances = [
+            json!({ "a": { "Tea": "Tea" }, "b": { "caf\u{e9}": 1 }}),
+            json!({ "a": { "TEA": "tea" }, "b": {}}),
+            json!({ "a": { "cafe\u{301}": "CAF\u{c9}" }, "b": {}}),
+            json!({ "a": {}, "b": { "cafe\u{301}": 1 }}),
+            json!({ "a": { "coffee": "Tea" }, "b": {}}),
+            json!({ "a": {}, "b": { "tea": 1 }}),
+        ];
+
+        let options = [
+            crate::ValidateOptions::new(),
+            crate::ValidateOptions::new().with_case_insensitive_enums(true),
+            crate::ValidateOptions::new().with_unicode_normalization(UnicodeNormalization::Nfc),
+            crate::ValidateOptions::new()
+                .with_case_insensitive_enums(true)
+                .with_unicode_normalization(UnicodeNormalization::Nfc),
+        ];
+
+        for options in &options {
+            for instance in &instances {
+                let input = instance.to_string();
+                let valid = crate::validate(&schema, instance, options.clone())
+                    .unwrap()
+                    .is_empty();
+
+                let errors =
+                    crate::validate_from_reader(&schema, input.as_bytes(), options.clone())
+                        .unwrap();
+                assert_eq!(valid, errors.is_empty(), "{}", input);
+
+                let errors = crate::validate_str(&schema, &input, options.clone()).unwrap();
+                assert_eq!(valid, errors.is_empty(), "{}", input);
+
+                let mut json = serde_json::Deserializer::from_str(&input);
+                let result = Deserializer::new(&mut json, &schema)
+                    .with_options(options.clone())
+                    .deserialize::<serde_json::Value>();
+                assert_eq!(valid, result.is_ok(), "{}", input);
+
+                let mut out = vec![];
+                let mut json = serde_json::Serializer::new(&mut out);
+                let result = crate::Serializer::new(&mut json, &schema)
+                    .with_options(options.clone())
+                    .serialize(instance);
+                assert_eq!(valid, result.is_ok(), "{}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn limits_agree_with_validate() {
+        let schema = schema(json!({
+            "definitions": {
+                "node": {
+                    "properties": { "children": { "elements": { "ref": "node" }}},
+                    "additionalProperties": true
+                }
+            },
+            "properties": {
+                "tree": { "ref": "node" },
+                "any": {},
+                "tags": { "values": { "type": "string" }},
+                "shape": {
+                    "discriminator": "kind",
+                    "mapping": {
+                        "box": { "properties": { "size": { "elements": { "type": "uint8" }}}}
+                    }
+                }
+            },
+            "additionalProperties": true
+        }));
+
+        // The size of the box comes before its tag, so it's buffered.
+        let inputs = [
+            r#"{
+                "tree": { "children": [{ "children": [] }] },
+                "any": { "x": [["deep"]] },
+                "tags": { "a": "bb" },
+                "shape": { "size": [1, 2], "kind": "box" },
+                "extra": "long string"
+            }"#,
+            r#"{
+                "tree": { "children": [] },
+                "any": null,
+                "tags": { "a": 1 },
+                "shape": { "kind": "box", "size": [] }
+            }"#,
+        ];
+
+        let options = [
+            crate::ValidateOptions::new().with_max_string_len(10),
+            crate::ValidateOptions::new().with_max_string_len(11),
+            crate::ValidateOptions::new().with_max_array_len(1),
+            crate::ValidateOptions::new().with_max_array_len(2),
+            crate::ValidateOptions::new().with_max_object_keys(4),
+            crate::ValidateOptions::new().with_max_object_keys(5),
+            crate::ValidateOptions::new().with_max_instance_depth(4),
+            crate::ValidateOptions::new().with_max_instance_depth(5),
+        ];
+
+        let compiled = crate::CompiledSchema::new(&schema).unwrap();
+        for options in &options {
+            for input in inputs {
+                let instance: serde_json::Value = serde_json::from_str(input).unwrap();
+                let expected = crate::validate(&schema, &instance, options.clone())
+                    .map(|errors| errors.is_empty());
+
+                let actual = compiled
+                    .validate(&instance, options.clone())
+                    .map(|errors| errors.is_empty());
+                assert_eq!(expected, actual, "{}", input);
+
+                let actual =
+                    match crate::validate_from_reader(&schema, input.as_bytes(), options.clone()) {
+                        Ok(errors) => Ok(errors.is_empty()),
+                        Err(crate::ValidateReaderError::Validate(err)) => Err(err),
+                        Err(crate::ValidateReaderError::Deserialize(err)) => panic!("{}", err),
+                    };
+                assert_eq!(expected, actual, "{}", input);
+
+                let mut json = serde_json::Deserializer::from_str(input);
+                let actual = match Deserializer::new(&mut json, &schema)
+                    .with_options(options.clone())
+                    .deserialize::<serde_json::Value>()
+                {
+                    Ok(_) => Ok(true),
+                    Err(DeserializeError::Invalid(_)) => Ok(false),
+                    Err(DeserializeError::Validate(err)) => Err(err),
+                    Err(DeserializeError::Inner(err)) => panic!("{}", err),
+                };
+                assert_eq!(expected, actual, "{}", input);
+
+                let mut out = vec![];
+                let mut json = serde_json::Serializer::new(&mut out);
+                let actual = match crate::Serializer::new(&mut json, &schema)
+                    .with_options(options.clone())
+                    .serialize(&instance)
+                {
+                    Ok(_) => Ok(true),
+                    Err(crate::SerializeError::Invalid(_)) => Ok(false),
+                    Err(crate::SerializeError::Validate(err)) => Err(err),
+                    Err(crate::SerializeError::Inner(err)) => panic!("{}", err),
+                };
+                assert_eq!(expected, actual, "{}", input);
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use crate::refs::resolve;
+use crate::Schema;
+use jtd_core::__private::resolve_ref_with;
+use serde_json::Value;
+
+/// The metadata property with which schemas set a default, for
+/// [`apply_defaults`].
+pub const DEFAULT_METADATA_KEY: &str = "default";
+
+/// Fills in missing optional properties of an instance with the defaults
+/// their schemas set.
+///
+/// A schema sets a default with the `default` property of its metadata, and
+/// the default applies wherever that schema is used as an optional property.
+/// If an optional property's schema is a ref, the default may also be set on
+/// the definition it refers to; a default set on the property's own schema
+/// takes precedence.
+///
+/// This goes through the whole instance, filling in defaults in nested
+/// properties, elements, values, and discriminator variants, and in the
+/// defaults it fills in. Parts of the instance that don't have the shape the
+/// schema calls for are left alone, so this can be called before or after
+/// validating. Defaults aren't validated; they're copied into the instance as
+/// they are.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "name": { "type": "string" }
+///     },
+///     "optionalProperties": {
+///         "retries": { "type": "uint8", "metadata": { "default": 3 }},
+///         "tags": {
+///             "elements": { "type": "string" },
+///             "metadata": { "default": [] }
+///         },
+///         "comment": { "type": "string" }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let mut instance = json!({ "name": "job", "retries": 5 });
+/// jtd::apply_defaults(&schema, &mut instance);
+///
+/// assert_eq!(json!({ "name": "job", "retries": 5, "tags": [] }), instance);
+/// ```
+pub fn apply_defaults(schema: &Schema, instance: &mut Value) {
+    apply(schema, schema, None, instance);
+}
+
+fn apply(root: &Schema, schema: &Schema, parent_tag: Option<&str>, instance: &mut Value) {
+    let (schema, _) = resolve(root, schema);
+
+    match (schema, instance) {
+        (Schema::Elements { elements, .. }, Value::Array(array)) => {
+            for element in array {
+                apply(root, elements, None, element);
+            }
+        }
+        (
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            },
+            Value::Object(object),
+        ) => {
+            for (name, schema) in optional_properties {
+                if object.contains_key(name) || parent_tag == Some(name.as_str()) {
+                    continue;
+                }
+
+                if let Some(default) = default(root, schema) {
+                    object.insert(name.clone(), default.clone());
+                }
+            }
+
+            for (name, value) in object {
+                if parent_tag == Some(name.as_str()) {
+                    continue;
+                }
+
+                let schema = match properties.get(name) {
+                    Some(schema) => schema,
+                    None => match optional_properties.get(name) {
+                        Some(schema) => schema,
+                        None => continue,
+                    },
+                };
+
+                apply(root, schema, None, value);
+            }
+        }
+        (Schema::Values { values, .. }, Value::Object(object)) => {
+            for value in object.values_mut() {
+                apply(root, values, None, value);
+            }
+        }
+        (
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            },
+            instance,
+        ) => {
+            let variant = instance
+                .get(discriminator)
+                .and_then(Value::as_str)
+                .and_then(|tag| mapping.get(tag));
+
+            if let Some(variant) = variant {
+                apply(root, variant, Some(discriminator), instance);
+            }
+        }
+        _ => {}
+    }
+}
+
+// The default for a schema, looking through refs until one is found.
+fn default<'a>(root: &'a Schema, schema: &'a Schema) -> Option<&'a Value> {
+    let mut default = None;
+    let _ = resolve_ref_with(schema, root, |schema| {
+        default = default.or_else(|| schema.metadata().get(DEFAULT_METADATA_KEY));
+    });
+
+    default
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn nested_defaults() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "limits": {
+                        "optionalProperties": {
+                            "cpu": { "type": "float64", "metadata": { "default": 1 }},
+                            "memory": { "type": "string" }
+                        },
+                        "metadata": { "default": {}}
+                    },
+                    "queue": { "type": "string", "metadata": { "default": "low" }},
+                    "loop": { "ref": "loop" }
+                },
+                "elements": {
+                    "discriminator": "kind",
+                    "mapping": {
+                        "worker": {
+                            "optionalProperties": {
+                                "limits": { "ref": "limits" },
+                                "queue": { "ref": "queue", "metadata": { "default": "main" }},
+                                "fallback": { "ref": "queue" },
+                                "env": { "values": { "ref": "limits" }},
+                                "loop": { "ref": "loop" }
+                            }
+                        }
+                    }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let mut instance = json!([
+            { "kind": "worker" },
+            { "kind": "worker", "limits": { "memory": "1G" }, "env": { "a": {}}},
+            { "kind": "web" },
+            { "limits": 3 },
+            "worker",
+        ]);
+        apply_defaults(&schema, &mut instance);
+
+        assert_eq!(
+            json!([
+                {
+                    "kind": "worker",
+                    "limits": { "cpu": 1 },
+                    "queue": "main",
+                    "fallback": "low",
+                },
+                {
+                    "kind": "worker",
+                    "limits": { "memory": "1G", "cpu": 1 },
+                    "queue": "main",
+                    "fallback": "low",
+                    "env": { "a": { "cpu": 1 }},
+                },
+                { "kind": "web" },
+                { "limits": 3 },
+                "worker",
+            ]),
+            instance
+        );
+    }
+}
@@ -0,0 +1,331 @@
+use crate::report::describe_type;
+use crate::{
+    validate, InstancePath, Schema, ValidateError, ValidateOptions, ValidationErrorIndicator,
+};
+use jtd_core::__private::resolve;
+use serde_json::Value;
+use std::fmt;
+
+/// A validation error, along with a description of it for people to read.
+///
+/// A [`ValidationErrorIndicator`] says where a value was rejected, and which
+/// part of the schema rejected it, in the form RFC 8927 specifies. That's
+/// what programs need, but people fixing their data are better served by a
+/// sentence saying what was expected, and what was found instead. This type
+/// adds that sentence, as well as the value that was rejected.
+///
+/// Get these from [`validate_detailed`], or from errors found some other way
+/// with [`DetailedError::new`].
+///
+/// ```
+/// use jtd::{DetailedError, Schema};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "name": { "type": "string" },
+///         "age": { "type": "uint8" }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let instance = json!({ "age": 300 });
+/// let errors = jtd::validate_detailed(&schema, &instance, Default::default()).unwrap();
+///
+/// assert_eq!("expected a uint8, got 300", errors[0].message);
+/// assert_eq!(Some(&json!(300)), errors[0].actual);
+/// assert_eq!("missing required property 'name'", errors[1].message);
+///
+/// // Displaying an error says where it is, too.
+/// assert_eq!("expected a uint8, got 300 at /age", errors[0].to_string());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DetailedError<'a> {
+    /// The error being described.
+    pub error: ValidationErrorIndicator<'a>,
+
+    /// What's wrong, in words, such as `"expected a uint8, got \"abc\""`, or
+    /// `"missing required property 'name'"`.
+    ///
+    /// The message doesn't say where the error is: see
+    /// [`error.instance_path`][`ValidationErrorIndicator::instance_path`] for
+    /// that, or display the [`DetailedError`] to get both.
+    pub message: String,
+
+    /// What the schema expected in place of the rejected value, such as `"a
+    /// uint8"`, or `"one of \"a\", \"b\""`, if there's a way to say so.
+    pub expected: Option<String>,
+
+    /// The value at the error's instance path, if there is one in the
+    /// instance. For errors about the properties of an object, such as
+    /// missing properties, this is the object.
+    pub actual: Option<&'a Value>,
+}
+
+impl<'a> DetailedError<'a> {
+    /// Describes an error found by validating `instance` against `schema`.
+    ///
+    /// `error` should have come from validating `instance` against `schema`;
+    /// if it didn't, the description may not make sense, but this function
+    /// doesn't panic.
+    pub fn new(schema: &Schema, instance: &'a Value, error: ValidationErrorIndicator<'a>) -> Self {
+        let actual = error.instance_path.get(instance);
+        let (message, expected) = describe(schema, instance, &error, actual).unwrap_or_else(|| {
+            // Errors this function doesn't understand, such as those from
+            // extension keywords it doesn't know about, are described by
+            // where they are in the schema.
+            let message = format!("rejected by {}", error.schema_path);
+            (message, None)
+        });
+
+        Self {
+            error,
+            message,
+            expected,
+            actual,
+        }
+    }
+}
+
+impl fmt::Display for DetailedError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.error.instance_path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{} at {}", self.message, self.error.instance_path)
+        }
+    }
+}
+
+/// Validates an instance against a schema, and describes each error found.
+///
+/// This is [`validate()`][`crate::validate()`], followed by
+/// [`DetailedError::new`] for each error. See [`DetailedError`] for an
+/// example.
+pub fn validate_detailed<'a>(
+    schema: &'a Schema,
+    instance: &'a Value,
+    options: ValidateOptions,
+) -> Result<Vec<DetailedError<'a>>, ValidateError> {
+    Ok(validate(schema, instance, options)?
+        .into_iter()
+        .map(|error| DetailedError::new(schema, instance, error))
+        .collect())
+}
+
+// Describes an error as a message, and what was expected, if the error is of
+// a kind this function knows.
+fn describe(
+    root: &Schema,
+    instance: &Value,
+    error: &ValidationErrorIndicator,
+    actual: Option<&Value>,
+) -> Option<(String, Option<String>)> {
+    let (schema, keywords) = resolve(root, &error.schema_path)?;
+    let last_token = error.instance_path.last().map(|token| token.as_ref());
+    let keywords: Vec<&str> = keywords.iter().map(|keyword| keyword.as_ref()).collect();
+
+    let expected = match (schema, &keywords[..]) {
+        (Schema::Properties { .. }, []) => {
+            return Some((format!("unexpected property '{}'", last_token?), None));
+        }
+        (Schema::Properties { .. }, ["properties", name]) => {
+            return Some((
+                format!("missing required property '{}'", name),
+                Some(format!("property '{}'", name)),
+            ));
+        }
+        (Schema::Type { type_, .. }, ["type"]) => describe_type(type_).into_owned(),
+        (Schema::Enum { enum_, .. }, ["enum"]) => one_of(enum_.iter().map(|value| quote(value))),
+        #[cfg(feature = "extensions")]
+        (Schema::IntEnum { enum_, .. }, ["enum"]) => one_of(enum_.iter().map(i64::to_string)),
+        (Schema::Elements { .. }, ["elements"]) => "an array".to_owned(),
+        (Schema::Values { .. }, ["values"]) => "an object".to_owned(),
+        (Schema::Properties { .. }, ["properties"]) => "an object".to_owned(),
+        (Schema::Properties { .. }, ["optionalProperties"]) => "an object".to_owned(),
+        #[cfg(feature = "extensions")]
+        (
+            Schema::Values {
+                keys: Some(keys), ..
+            },
+            ["keys", _],
+        ) => {
+            let expected = match keys {
+                crate::Keys::Enum(enum_) => one_of(enum_.iter().map(|key| quote(key))),
+                crate::Keys::String {
+                    pattern: Some(pattern),
+                } => format!("a key matching /{}/", pattern.as_str()),
+                crate::Keys::String { pattern: None } => return None,
+            };
+
+            return Some((
+                format!("expected {}, got key {}", expected, quote(last_token?)),
+                Some(expected),
+            ));
+        }
+        (Schema::Discriminator { discriminator, .. }, ["discriminator"]) => {
+            // The error is at the discriminator if it isn't a string, or at
+            // the object if it's missing or the object isn't an object.
+            if let Some(Value::Object(_)) = actual {
+                return Some((
+                    format!("missing discriminator '{}'", discriminator),
+                    Some(format!("property '{}'", discriminator)),
+                ));
+            }
+
+            if last_token == Some(discriminator.as_str()) && is_tag(instance, error) {
+                let expected = "a string".to_owned();
+                return Some((
+                    format!(
+                        "expected discriminator '{}' to be {}, got {}",
+                        discriminator,
+                        expected,
+                        describe_value(actual?)
+                    ),
+                    Some(expected),
+                ));
+            }
+
+            "an object".to_owned()
+        }
+        (
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            },
+            ["mapping"],
+        ) => {
+            let expected = one_of(mapping.keys().map(|tag| quote(tag)));
+            return Some((
+                format!(
+                    "expected discriminator '{}' to be {}, got {}",
+                    discriminator,
+                    expected,
+                    describe_value(actual?)
+                ),
+                Some(expected),
+            ));
+        }
+        _ => return None,
+    };
+
+    let expected = if schema.nullable() {
+        format!("{} or null", expected)
+    } else {
+        expected
+    };
+
+    let message = format!("expected {}, got {}", expected, describe_value(actual?));
+    Some((message, Some(expected)))
+}
+
+// Whether an error's instance path leads to the discriminator of an object,
+// rather than to an object under a property named like the discriminator.
+fn is_tag(instance: &Value, error: &ValidationErrorIndicator) -> bool {
+    let parent = &error.instance_path[..error.instance_path.len() - 1];
+    matches!(
+        InstancePath::from(parent).get(instance),
+        Some(Value::Object(_))
+    )
+}
+
+fn one_of(values: impl Iterator<Item = String>) -> String {
+    format!("one of {}", values.collect::<Vec<_>>().join(", "))
+}
+
+fn quote(s: &str) -> String {
+    Value::from(s).to_string()
+}
+
+// Describes a value that was rejected. Values that are short enough are
+// shown as they are, and longer ones by their kind.
+fn describe_value(value: &Value) -> String {
+    const MAX_LEN: usize = 40;
+
+    match value {
+        Value::Array(_) => "an array".to_owned(),
+        Value::Object(_) => "an object".to_owned(),
+        Value::String(s) if s.chars().count() > MAX_LEN => "a long string".to_owned(),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn messages(schema: Value, instance: Value) -> Vec<String> {
+        let schema = Schema::from_serde_schema(serde_json::from_value(schema).unwrap()).unwrap();
+        validate_detailed(&schema, &instance, ValidateOptions::new())
+            .unwrap()
+            .iter()
+            .map(DetailedError::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn describes_errors() {
+        let schema = json!({
+            "definitions": {
+                "event": {
+                    "discriminator": "type",
+                    "mapping": {
+                        "a": { "properties": { "x": { "enum": ["x", "y"] }}},
+                        "b": { "properties": {}}
+                    }
+                }
+            },
+            "properties": {
+                "name": { "type": "timestamp", "nullable": true },
+                "tags": { "elements": { "type": "string" }},
+                "events": { "values": { "ref": "event" }}
+            }
+        });
+
+        assert_eq!(
+            vec!["expected an object, got an array"],
+            messages(schema.clone(), json!([1])),
+        );
+
+        assert_eq!(
+            vec![
+                "expected one of \"x\", \"y\", got \"z\" at /events/e0/x",
+                "expected discriminator 'type' to be one of \"a\", \"b\", got \"c\" at /events/e1/type",
+                "expected discriminator 'type' to be a string, got 1 at /events/e2/type",
+                "missing discriminator 'type' at /events/e3",
+                "expected an object, got \"a\" at /events/e4",
+                "expected an RFC 3339 timestamp or null, got \"yesterday\" at /name",
+                "expected a string, got null at /tags/0",
+                "expected a string, got an object at /tags/1",
+                "unexpected property 'extra' at /extra",
+            ],
+            messages(
+                schema.clone(),
+                json!({
+                    "name": "yesterday",
+                    "tags": [null, {}, "ok"],
+                    "events": {
+                        "e0": { "type": "a", "x": "z" },
+                        "e1": { "type": "c" },
+                        "e2": { "type": 1 },
+                        "e3": {},
+                        "e4": "a"
+                    },
+                    "extra": true
+                })
+            ),
+        );
+
+        assert_eq!(
+            vec![
+                "missing required property 'name'",
+                "expected an array, got a long string at /tags",
+            ],
+            messages(
+                schema,
+                json!({ "events": {}, "tags": "a long string, longer than forty characters" })
+            ),
+        );
+    }
+}
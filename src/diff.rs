@@ -0,0 +1,1041 @@
+//! Compare two versions of a schema.
+//!
+//! [`changes`] walks two versions of a schema side by side, and lists each
+//! structural change between them: properties, enum values, and mapping tags
+//! that were added or removed, types that were changed, and so on. Each change
+//! says whether it's compatible with data written against the other version.
+//!
+//! [`diff()`] collects those changes into a [`SchemaDiff`], which classifies
+//! the two versions as backward compatible, forward compatible, both, or
+//! neither, for gating schema changes in CI.
+//!
+//! [`to_changelog`] renders those changes as a Markdown changelog, with
+//! breaking changes first, for the release notes of a package of schemas:
+//!
+//! ```
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = |value| Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap();
+//! let old = schema(json!({
+//!     "properties": {
+//!         "name": { "type": "string" },
+//!         "age": { "type": "uint8" }
+//!     }
+//! }));
+//! let new = schema(json!({
+//!     "properties": {
+//!         "name": { "type": "string" },
+//!         "age": { "type": "uint16" },
+//!         "email": { "type": "string" }
+//!     }
+//! }));
+//!
+//! assert_eq!(
+//!     vec![
+//!         "## Breaking changes",
+//!         "",
+//!         "- Added required property \"email\" at `/properties/email`",
+//!         "",
+//!         "## Non-breaking changes",
+//!         "",
+//!         "- Changed type from uint8 to uint16 at `/properties/age/type`",
+//!     ],
+//!     jtd::diff::to_changelog(&old, &new).lines().collect::<Vec<_>>(),
+//! );
+//! ```
+
+use crate::{Schema, SchemaPath, Type};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fmt::Write;
+
+/// A change between two versions of a schema, as found by [`changes`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// The path to the part of the schema that changed, as a list of JSON
+    /// object keys. This is a path in the new version of the schema, unless
+    /// the change is a removal, in which case it's a path in the old version.
+    pub schema_path: Vec<String>,
+
+    /// What the change is.
+    pub kind: ChangeKind,
+
+    /// Whether every instance the old version accepts, the new version
+    /// accepts too. That is, whether data written against the old version
+    /// can still be read by those using the new one.
+    pub backward_compatible: bool,
+
+    /// Whether every instance the new version accepts, the old version
+    /// accepts too. That is, whether data written against the new version
+    /// can be read by those still using the old one.
+    pub forward_compatible: bool,
+}
+
+/// The kinds of [`Change`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// A definition was added.
+    DefinitionAdded(String),
+
+    /// A definition was removed.
+    DefinitionRemoved(String),
+
+    /// The schema was changed to a different form, such as from the type form
+    /// to the empty form.
+    FormChanged {
+        old: &'static str,
+        new: &'static str,
+    },
+
+    /// The `nullable` keyword was changed to the given value.
+    NullableChanged(bool),
+
+    /// A `ref` was changed to refer to a different definition.
+    RefChanged { old: String, new: String },
+
+    /// A `type` was changed.
+    TypeChanged { old: Type, new: Type },
+
+    /// A value was added to an `enum`.
+    EnumValueAdded(String),
+
+    /// A value was removed from an `enum`.
+    EnumValueRemoved(String),
+
+    /// A property was added.
+    PropertyAdded { name: String, required: bool },
+
+    /// A property was removed.
+    PropertyRemoved { name: String, required: bool },
+
+    /// A required property was made optional.
+    PropertyMadeOptional(String),
+
+    /// An optional property was made required.
+    PropertyMadeRequired(String),
+
+    /// The `additionalProperties` keyword was changed to the given value.
+    AdditionalPropertiesChanged(bool),
+
+    /// The `keys` keyword of a values-form schema was changed. This is an
+    /// extension to JSON Typedef.
+    #[cfg(feature = "extensions")]
+    KeysChanged,
+
+    /// The `discriminator` keyword was changed to a different property.
+    DiscriminatorChanged { old: String, new: String },
+
+    /// A tag was added to a `mapping`.
+    MappingAdded(String),
+
+    /// A tag was removed from a `mapping`.
+    MappingRemoved(String),
+}
+
+impl Change {
+    /// Whether the change is a breaking one for the users of a schema: one
+    /// that makes it reject some instance it used to accept.
+    pub fn is_breaking(&self) -> bool {
+        !self.backward_compatible
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}",
+            SchemaPath::from(&self.schema_path[..]),
+            self.kind
+        )
+    }
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let required = |required: &bool| if *required { "required" } else { "optional" };
+
+        match self {
+            ChangeKind::DefinitionAdded(name) => write!(f, "added definition {:?}", name),
+            ChangeKind::DefinitionRemoved(name) => write!(f, "removed definition {:?}", name),
+            ChangeKind::FormChanged { old, new } => {
+                write!(f, "changed form from {} to {}", old, new)
+            }
+            ChangeKind::NullableChanged(true) => f.write_str("made nullable"),
+            ChangeKind::NullableChanged(false) => f.write_str("made not nullable"),
+            ChangeKind::RefChanged { old, new } => {
+                write!(f, "changed ref from {:?} to {:?}", old, new)
+            }
+            ChangeKind::TypeChanged { old, new } => write!(
+                f,
+                "changed type from {} to {}",
+                type_keyword(old),
+                type_keyword(new)
+            ),
+            ChangeKind::EnumValueAdded(value) => write!(f, "added enum value {:?}", value),
+            ChangeKind::EnumValueRemoved(value) => write!(f, "removed enum value {:?}", value),
+            ChangeKind::PropertyAdded { name, required: r } => {
+                write!(f, "added {} property {:?}", required(r), name)
+            }
+            ChangeKind::PropertyRemoved { name, required: r } => {
+                write!(f, "removed {} property {:?}", required(r), name)
+            }
+            ChangeKind::PropertyMadeOptional(name) => {
+                write!(f, "made property {:?} optional", name)
+            }
+            ChangeKind::PropertyMadeRequired(name) => {
+                write!(f, "made property {:?} required", name)
+            }
+            ChangeKind::AdditionalPropertiesChanged(true) => {
+                f.write_str("allowed additional properties")
+            }
+            ChangeKind::AdditionalPropertiesChanged(false) => {
+                f.write_str("disallowed additional properties")
+            }
+            #[cfg(feature = "extensions")]
+            ChangeKind::KeysChanged => f.write_str("changed keys"),
+            ChangeKind::DiscriminatorChanged { old, new } => {
+                write!(f, "changed discriminator from {:?} to {:?}", old, new)
+            }
+            ChangeKind::MappingAdded(tag) => write!(f, "added mapping for {:?}", tag),
+            ChangeKind::MappingRemoved(tag) => write!(f, "removed mapping for {:?}", tag),
+        }
+    }
+}
+
+/// Lists the changes from one version of a schema to another.
+///
+/// Changes are returned in the order they're found, with definitions
+/// compared before the root schema, and the parts of each schema in the
+/// order they're written in. An empty result means the two versions are the
+/// same, except perhaps for their metadata.
+///
+/// Compatibility is judged one change at a time, and errs on the side of
+/// caution. For instance, changing a `ref` is never judged compatible, even
+/// if the two definitions happen to accept the same instances.
+pub fn changes(old: &Schema, new: &Schema) -> Vec<Change> {
+    let mut differ = Differ {
+        path: Vec::new(),
+        changes: Vec::new(),
+    };
+
+    differ.diff(old, new);
+    differ.changes
+}
+
+/// Renders the changes from one version of a schema to another as a
+/// Markdown changelog.
+///
+/// Changes are listed in the order returned by [`changes`], under the
+/// heading "Breaking changes" if they're breaking, as defined by
+/// [`Change::is_breaking`], or "Non-breaking changes" otherwise. Headings
+/// with no changes under them are left out.
+pub fn to_changelog(old: &Schema, new: &Schema) -> String {
+    let (breaking, non_breaking): (Vec<_>, Vec<_>) =
+        changes(old, new).into_iter().partition(Change::is_breaking);
+
+    if breaking.is_empty() && non_breaking.is_empty() {
+        return "No changes.\n".to_owned();
+    }
+
+    let mut out = String::new();
+    for (heading, changes) in [
+        ("Breaking changes", breaking),
+        ("Non-breaking changes", non_breaking),
+    ] {
+        if changes.is_empty() {
+            continue;
+        }
+
+        if !out.is_empty() {
+            out.push('\n');
+        }
+
+        writeln!(out, "## {}\n", heading).unwrap();
+        for change in changes {
+            let kind = change.kind.to_string();
+            let mut chars = kind.chars();
+            let capitalized: String = chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+                .collect();
+
+            if change.schema_path.is_empty() {
+                writeln!(out, "- {} at the root", capitalized).unwrap();
+            } else {
+                writeln!(
+                    out,
+                    "- {} at `{}`",
+                    capitalized,
+                    SchemaPath::from(&change.schema_path[..])
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    out
+}
+
+/// The changes from one version of a schema to another, as found by
+/// [`diff()`], along with how compatible the two versions are overall.
+///
+/// This is meant for gating changes to schemas in CI: fail the build if the
+/// [`compatibility`][`SchemaDiff::compatibility`] of a change is less than
+/// what the schema promises its users.
+///
+/// ```
+/// use jtd::diff::Compatibility;
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = |value| Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap();
+/// let old = schema(json!({ "enum": ["a", "b"] }));
+///
+/// let added = jtd::diff(&old, &schema(json!({ "enum": ["a", "b", "c"] })));
+/// assert_eq!(Compatibility::Backward, added.compatibility());
+///
+/// let removed = jtd::diff(&old, &schema(json!({ "enum": ["a"] })));
+/// assert_eq!(Compatibility::Forward, removed.compatibility());
+/// assert_eq!(1, removed.breaking().count());
+///
+/// let unchanged = jtd::diff(&old, &old);
+/// assert!(unchanged.is_empty());
+/// assert_eq!(Compatibility::Full, unchanged.compatibility());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    /// The changes, in the order [`changes`] returns them.
+    pub changes: Vec<Change>,
+}
+
+/// How compatible two versions of a schema are, as judged by
+/// [`SchemaDiff::compatibility`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Compatibility {
+    /// Each version accepts every instance the other accepts.
+    Full,
+
+    /// The new version accepts every instance the old version accepts, but
+    /// not the other way around.
+    Backward,
+
+    /// The old version accepts every instance the new version accepts, but
+    /// not the other way around.
+    Forward,
+
+    /// Neither version accepts every instance the other accepts.
+    None,
+}
+
+impl SchemaDiff {
+    /// Whether there are no changes, except perhaps to metadata.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Whether every change is backward compatible, as defined by
+    /// [`Change::backward_compatible`].
+    pub fn is_backward_compatible(&self) -> bool {
+        self.changes.iter().all(|change| change.backward_compatible)
+    }
+
+    /// Whether every change is forward compatible, as defined by
+    /// [`Change::forward_compatible`].
+    pub fn is_forward_compatible(&self) -> bool {
+        self.changes.iter().all(|change| change.forward_compatible)
+    }
+
+    /// How compatible the two versions are, taking every change into
+    /// account.
+    pub fn compatibility(&self) -> Compatibility {
+        match (self.is_backward_compatible(), self.is_forward_compatible()) {
+            (true, true) => Compatibility::Full,
+            (true, false) => Compatibility::Backward,
+            (false, true) => Compatibility::Forward,
+            (false, false) => Compatibility::None,
+        }
+    }
+
+    /// The changes that are breaking, as defined by [`Change::is_breaking`].
+    pub fn breaking(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+}
+
+impl fmt::Display for SchemaDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares two versions of a schema, and judges how compatible they are.
+///
+/// This is [`changes`], wrapped in a [`SchemaDiff`]. This function is also
+/// available as `jtd::diff`.
+pub fn diff(old: &Schema, new: &Schema) -> SchemaDiff {
+    SchemaDiff {
+        changes: changes(old, new),
+    }
+}
+
+struct Differ {
+    path: Vec<String>,
+    changes: Vec<Change>,
+}
+
+impl Differ {
+    fn diff(&mut self, old: &Schema, new: &Schema) {
+        self.diff_definitions(old.definitions(), new.definitions());
+
+        match (old, new) {
+            (Schema::Empty { .. }, Schema::Empty { .. }) => {}
+
+            (Schema::Ref { ref_: old_ref, .. }, Schema::Ref { ref_: new_ref, .. }) => {
+                self.diff_nullable(old, new);
+                if old_ref != new_ref {
+                    let kind = ChangeKind::RefChanged {
+                        old: old_ref.clone(),
+                        new: new_ref.clone(),
+                    };
+
+                    self.push(&["ref"], kind, false, false);
+                }
+            }
+
+            (
+                Schema::Type {
+                    type_: old_type, ..
+                },
+                Schema::Type {
+                    type_: new_type, ..
+                },
+            ) => {
+                self.diff_nullable(old, new);
+                if old_type != new_type {
+                    let kind = ChangeKind::TypeChanged {
+                        old: old_type.clone(),
+                        new: new_type.clone(),
+                    };
+
+                    let backward = type_accepts(new_type, old_type);
+                    let forward = type_accepts(old_type, new_type);
+                    self.push(&["type"], kind, backward, forward);
+                }
+            }
+
+            (
+                Schema::Enum {
+                    enum_: old_enum, ..
+                },
+                Schema::Enum {
+                    enum_: new_enum, ..
+                },
+            ) => {
+                self.diff_nullable(old, new);
+                self.diff_enum(old_enum, new_enum);
+            }
+
+            #[cfg(feature = "extensions")]
+            (
+                Schema::IntEnum {
+                    enum_: old_enum, ..
+                },
+                Schema::IntEnum {
+                    enum_: new_enum, ..
+                },
+            ) => {
+                self.diff_nullable(old, new);
+                self.diff_enum(old_enum, new_enum);
+            }
+
+            (
+                Schema::Elements {
+                    elements: old_elements,
+                    ..
+                },
+                Schema::Elements {
+                    elements: new_elements,
+                    ..
+                },
+            ) => {
+                self.diff_nullable(old, new);
+                self.diff_child(&["elements"], old_elements, new_elements);
+            }
+
+            (
+                Schema::Properties {
+                    properties: old_required,
+                    optional_properties: old_optional,
+                    additional_properties: old_additional,
+                    ..
+                },
+                Schema::Properties {
+                    properties: new_required,
+                    optional_properties: new_optional,
+                    additional_properties: new_additional,
+                    ..
+                },
+            ) => {
+                self.diff_nullable(old, new);
+
+                if old_additional != new_additional {
+                    let kind = ChangeKind::AdditionalPropertiesChanged(*new_additional);
+                    self.push(
+                        &["additionalProperties"],
+                        kind,
+                        *new_additional,
+                        !new_additional,
+                    );
+                }
+
+                let names: BTreeSet<_> = old_required
+                    .keys()
+                    .chain(old_optional.keys())
+                    .chain(new_required.keys())
+                    .chain(new_optional.keys())
+                    .collect();
+
+                for name in names {
+                    let old_property = property(old_required, old_optional, name);
+                    let new_property = property(new_required, new_optional, name);
+
+                    match (old_property, new_property) {
+                        (Some((old_schema, old_required)), Some((new_schema, new_required))) => {
+                            let keyword = properties_keyword(new_required);
+                            if old_required && !new_required {
+                                let kind = ChangeKind::PropertyMadeOptional(name.clone());
+                                self.push(&[keyword, name], kind, true, false);
+                            } else if !old_required && new_required {
+                                let kind = ChangeKind::PropertyMadeRequired(name.clone());
+                                self.push(&[keyword, name], kind, false, true);
+                            }
+
+                            self.diff_child(&[keyword, name], old_schema, new_schema);
+                        }
+
+                        // Old instances never have a property that wasn't
+                        // allowed, but if additional properties were allowed,
+                        // they could have it with any value at all.
+                        (None, Some((_, required))) => {
+                            let kind = ChangeKind::PropertyAdded {
+                                name: name.clone(),
+                                required,
+                            };
+
+                            let backward = !required && !old_additional;
+                            let forward = *old_additional;
+                            self.push(
+                                &[properties_keyword(required), name],
+                                kind,
+                                backward,
+                                forward,
+                            );
+                        }
+
+                        (Some((_, required)), None) => {
+                            let kind = ChangeKind::PropertyRemoved {
+                                name: name.clone(),
+                                required,
+                            };
+
+                            let backward = *new_additional;
+                            let forward = !required && !new_additional;
+                            self.push(
+                                &[properties_keyword(required), name],
+                                kind,
+                                backward,
+                                forward,
+                            );
+                        }
+
+                        (None, None) => unreachable!(),
+                    }
+                }
+            }
+
+            (
+                Schema::Values {
+                    values: old_values,
+                    #[cfg(feature = "extensions")]
+                        keys: old_keys,
+                    ..
+                },
+                Schema::Values {
+                    values: new_values,
+                    #[cfg(feature = "extensions")]
+                        keys: new_keys,
+                    ..
+                },
+            ) => {
+                self.diff_nullable(old, new);
+
+                #[cfg(feature = "extensions")]
+                if old_keys != new_keys {
+                    self.push(&["keys"], ChangeKind::KeysChanged, false, false);
+                }
+
+                self.diff_child(&["values"], old_values, new_values);
+            }
+
+            (
+                Schema::Discriminator {
+                    discriminator: old_discriminator,
+                    mapping: old_mapping,
+                    ..
+                },
+                Schema::Discriminator {
+                    discriminator: new_discriminator,
+                    mapping: new_mapping,
+                    ..
+                },
+            ) => {
+                self.diff_nullable(old, new);
+
+                if old_discriminator != new_discriminator {
+                    let kind = ChangeKind::DiscriminatorChanged {
+                        old: old_discriminator.clone(),
+                        new: new_discriminator.clone(),
+                    };
+
+                    self.push(&["discriminator"], kind, false, false);
+                }
+
+                let tags: BTreeSet<_> = old_mapping.keys().chain(new_mapping.keys()).collect();
+                for tag in tags {
+                    match (old_mapping.get(tag), new_mapping.get(tag)) {
+                        (Some(old_variant), Some(new_variant)) => {
+                            self.diff_child(&["mapping", tag], old_variant, new_variant);
+                        }
+                        (None, Some(_)) => {
+                            let kind = ChangeKind::MappingAdded(tag.clone());
+                            self.push(&["mapping", tag], kind, true, false);
+                        }
+                        (Some(_), None) => {
+                            let kind = ChangeKind::MappingRemoved(tag.clone());
+                            self.push(&["mapping", tag], kind, false, true);
+                        }
+                        (None, None) => unreachable!(),
+                    }
+                }
+            }
+
+            _ => {
+                let kind = ChangeKind::FormChanged {
+                    old: form_name(old),
+                    new: form_name(new),
+                };
+
+                self.push(&[], kind, form_accepts(new, old), form_accepts(old, new));
+            }
+        }
+    }
+
+    fn diff_definitions(&mut self, old: &BTreeMap<String, Schema>, new: &BTreeMap<String, Schema>) {
+        // A definition on its own doesn't change what a schema accepts. Only
+        // changes to the refs to it, or to the definition itself, do.
+        let names: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+        for name in names {
+            match (old.get(name), new.get(name)) {
+                (Some(old_definition), Some(new_definition)) => {
+                    self.diff_child(&["definitions", name], old_definition, new_definition);
+                }
+                (None, Some(_)) => {
+                    let kind = ChangeKind::DefinitionAdded(name.clone());
+                    self.push(&["definitions", name], kind, true, true);
+                }
+                (Some(_), None) => {
+                    let kind = ChangeKind::DefinitionRemoved(name.clone());
+                    self.push(&["definitions", name], kind, true, true);
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+    }
+
+    fn diff_nullable(&mut self, old: &Schema, new: &Schema) {
+        if old.nullable() != new.nullable() {
+            let kind = ChangeKind::NullableChanged(new.nullable());
+            self.push(&["nullable"], kind, new.nullable(), old.nullable());
+        }
+    }
+
+    fn diff_enum<T: Ord + ToString>(&mut self, old: &BTreeSet<T>, new: &BTreeSet<T>) {
+        for value in old.difference(new) {
+            let kind = ChangeKind::EnumValueRemoved(value.to_string());
+            self.push(&["enum"], kind, false, true);
+        }
+
+        for value in new.difference(old) {
+            let kind = ChangeKind::EnumValueAdded(value.to_string());
+            self.push(&["enum"], kind, true, false);
+        }
+    }
+
+    fn diff_child(&mut self, tokens: &[&str], old: &Schema, new: &Schema) {
+        let len = self.path.len();
+        self.path.extend(tokens.iter().map(|&t| t.to_owned()));
+        self.diff(old, new);
+        self.path.truncate(len);
+    }
+
+    fn push(&mut self, tokens: &[&str], kind: ChangeKind, backward: bool, forward: bool) {
+        let mut schema_path = self.path.clone();
+        schema_path.extend(tokens.iter().map(|&t| t.to_owned()));
+        self.changes.push(Change {
+            schema_path,
+            kind,
+            backward_compatible: backward,
+            forward_compatible: forward,
+        });
+    }
+}
+
+// Looks up a property, and whether it's required.
+fn property<'a>(
+    required: &'a BTreeMap<String, Schema>,
+    optional: &'a BTreeMap<String, Schema>,
+    name: &str,
+) -> Option<(&'a Schema, bool)> {
+    required
+        .get(name)
+        .map(|schema| (schema, true))
+        .or_else(|| optional.get(name).map(|schema| (schema, false)))
+}
+
+fn properties_keyword(required: bool) -> &'static str {
+    if required {
+        "properties"
+    } else {
+        "optionalProperties"
+    }
+}
+
+fn form_name(schema: &Schema) -> &'static str {
+    match schema {
+        Schema::Empty { .. } => "empty",
+        Schema::Ref { .. } => "ref",
+        Schema::Type { .. } => "type",
+        Schema::Enum { .. } => "enum",
+        #[cfg(feature = "extensions")]
+        Schema::IntEnum { .. } => "enum",
+        Schema::Elements { .. } => "elements",
+        Schema::Properties { .. } => "properties",
+        Schema::Values { .. } => "values",
+        Schema::Discriminator { .. } => "discriminator",
+    }
+}
+
+// Whether a schema accepts everything another one of a different form does.
+fn form_accepts(outer: &Schema, inner: &Schema) -> bool {
+    if inner.nullable() && !outer.nullable() {
+        return false;
+    }
+
+    matches!(
+        (outer, inner),
+        (Schema::Empty { .. }, _)
+            | (
+                Schema::Type {
+                    type_: Type::String,
+                    ..
+                },
+                Schema::Enum { .. }
+            )
+    )
+}
+
+// Whether a type accepts everything another one does.
+fn type_accepts(outer: &Type, inner: &Type) -> bool {
+    if outer == inner {
+        return true;
+    }
+
+    match (outer, inner) {
+        (Type::Float32 | Type::Float64, inner) => {
+            int_range(inner).is_some() || matches!(inner, Type::Float32 | Type::Float64)
+        }
+        (Type::String, Type::Timestamp) => true,
+        (outer, inner) => match (int_range(outer), int_range(inner)) {
+            (Some((outer_min, outer_max)), Some((inner_min, inner_max))) => {
+                outer_min <= inner_min && inner_max <= outer_max
+            }
+            _ => false,
+        },
+    }
+}
+
+fn int_range(type_: &Type) -> Option<(i64, i64)> {
+    match type_ {
+        Type::Int8 => Some((i8::MIN.into(), i8::MAX.into())),
+        Type::Uint8 => Some((u8::MIN.into(), u8::MAX.into())),
+        Type::Int16 => Some((i16::MIN.into(), i16::MAX.into())),
+        Type::Uint16 => Some((u16::MIN.into(), u16::MAX.into())),
+        Type::Int32 => Some((i32::MIN.into(), i32::MAX.into())),
+        Type::Uint32 => Some((u32::MIN.into(), u32::MAX.into())),
+        _ => None,
+    }
+}
+
+fn type_keyword(type_: &Type) -> &str {
+    match type_ {
+        Type::Boolean => "boolean",
+        Type::Int8 => "int8",
+        Type::Uint8 => "uint8",
+        Type::Int16 => "int16",
+        Type::Uint16 => "uint16",
+        Type::Int32 => "int32",
+        Type::Uint32 => "uint32",
+        Type::Float32 => "float32",
+        Type::Float64 => "float64",
+        Type::String => "string",
+        Type::Timestamp => "timestamp",
+        #[cfg(feature = "extensions")]
+        Type::Custom(name) => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: serde_json::Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    fn diff(old: serde_json::Value, new: serde_json::Value) -> Vec<(String, bool, bool)> {
+        changes(&schema(old), &schema(new))
+            .into_iter()
+            .map(|c| (c.to_string(), c.backward_compatible, c.forward_compatible))
+            .collect()
+    }
+
+    #[test]
+    fn finds_changes() {
+        assert_eq!(
+            Vec::<(String, bool, bool)>::new(),
+            diff(
+                json!({ "metadata": { "description": "a" }, "type": "string" }),
+                json!({ "type": "string" })
+            )
+        );
+
+        assert_eq!(
+            vec![
+                (
+                    r#"/definitions/a: removed definition "a""#.to_owned(),
+                    true,
+                    true
+                ),
+                (
+                    r#"/definitions/b: added definition "b""#.to_owned(),
+                    true,
+                    true
+                ),
+                (
+                    r#"/ref: changed ref from "a" to "b""#.to_owned(),
+                    false,
+                    false
+                ),
+            ],
+            diff(
+                json!({ "definitions": { "a": {} }, "ref": "a" }),
+                json!({ "definitions": { "b": {} }, "ref": "b" })
+            )
+        );
+
+        assert_eq!(
+            vec![
+                ("/nullable: made nullable".to_owned(), true, false),
+                (r#"/enum: removed enum value "A""#.to_owned(), false, true),
+                (r#"/enum: added enum value "C""#.to_owned(), true, false),
+            ],
+            diff(
+                json!({ "enum": ["A", "B"] }),
+                json!({ "enum": ["B", "C"], "nullable": true })
+            )
+        );
+
+        assert_eq!(
+            vec![
+                (
+                    "/additionalProperties: allowed additional properties".to_owned(),
+                    true,
+                    false
+                ),
+                (
+                    r#"/optionalProperties/a: made property "a" optional"#.to_owned(),
+                    true,
+                    false
+                ),
+                (
+                    r#"/properties/b: made property "b" required"#.to_owned(),
+                    false,
+                    true
+                ),
+                (
+                    "/properties/b/elements/type: changed type from int32 to float64".to_owned(),
+                    true,
+                    false
+                ),
+                (
+                    r#"/properties/c: removed required property "c""#.to_owned(),
+                    true,
+                    false
+                ),
+                (
+                    r#"/optionalProperties/d: added optional property "d""#.to_owned(),
+                    true,
+                    false
+                ),
+            ],
+            diff(
+                json!({
+                    "properties": { "a": {}, "c": {} },
+                    "optionalProperties": { "b": { "elements": { "type": "int32" }}}
+                }),
+                json!({
+                    "properties": { "b": { "elements": { "type": "float64" }}},
+                    "optionalProperties": { "a": {}, "d": {} },
+                    "additionalProperties": true
+                })
+            )
+        );
+
+        assert_eq!(
+            vec![
+                (
+                    r#"/discriminator: changed discriminator from "t" to "type""#.to_owned(),
+                    false,
+                    false
+                ),
+                (
+                    r#"/mapping/a: removed mapping for "a""#.to_owned(),
+                    false,
+                    true
+                ),
+                (
+                    "/mapping/b/properties/x/type: changed type from uint8 to int8".to_owned(),
+                    false,
+                    false
+                ),
+                (
+                    r#"/mapping/c: added mapping for "c""#.to_owned(),
+                    true,
+                    false
+                ),
+            ],
+            diff(
+                json!({
+                    "discriminator": "t",
+                    "mapping": {
+                        "a": { "properties": {} },
+                        "b": { "properties": { "x": { "type": "uint8" }}}
+                    }
+                }),
+                json!({
+                    "discriminator": "type",
+                    "mapping": {
+                        "b": { "properties": { "x": { "type": "int8" }}},
+                        "c": { "properties": {} }
+                    }
+                })
+            )
+        );
+
+        assert_eq!(
+            vec![(
+                "/values: changed form from enum to type".to_owned(),
+                true,
+                false
+            )],
+            diff(
+                json!({ "values": { "enum": ["a"] }}),
+                json!({ "values": { "type": "string" }})
+            )
+        );
+
+        assert_eq!(
+            vec![(": changed form from type to empty".to_owned(), true, false)],
+            diff(json!({ "type": "string", "nullable": true }), json!({}))
+        );
+    }
+
+    #[test]
+    fn type_compatibility() {
+        assert!(type_accepts(&Type::Int16, &Type::Uint8));
+        assert!(!type_accepts(&Type::Uint16, &Type::Int8));
+        assert!(type_accepts(&Type::Float32, &Type::Uint32));
+        assert!(type_accepts(&Type::String, &Type::Timestamp));
+        assert!(!type_accepts(&Type::Timestamp, &Type::String));
+        assert!(!type_accepts(&Type::Boolean, &Type::Int8));
+    }
+
+    #[test]
+    fn changelog() {
+        assert_eq!(
+            "No changes.\n",
+            to_changelog(&schema(json!({})), &schema(json!({})))
+        );
+
+        assert_eq!(
+            "## Non-breaking changes\n\n- Changed form from type to empty at the root\n",
+            to_changelog(&schema(json!({ "type": "string" })), &schema(json!({})))
+        );
+
+        assert_eq!(
+            "## Breaking changes\n\n- Made not nullable at `/elements/nullable`\n",
+            to_changelog(
+                &schema(json!({ "elements": { "type": "string", "nullable": true }})),
+                &schema(json!({ "elements": { "type": "string" }}))
+            )
+        );
+    }
+
+    #[test]
+    fn compatibility() {
+        let old = schema(json!({
+            "properties": { "a": { "type": "uint8" }},
+            "optionalProperties": { "b": { "type": "string" }}
+        }));
+
+        let widened = super::diff(
+            &old,
+            &schema(json!({
+                "properties": { "a": { "type": "uint16" }},
+                "optionalProperties": { "b": { "type": "string", "nullable": true }}
+            })),
+        );
+        assert_eq!(2, widened.changes.len());
+        assert_eq!(Compatibility::Backward, widened.compatibility());
+        assert_eq!(0, widened.breaking().count());
+
+        let mixed = super::diff(
+            &old,
+            &schema(json!({
+                "properties": { "a": { "type": "uint16" }},
+                "optionalProperties": { "b": { "type": "timestamp" }}
+            })),
+        );
+        assert_eq!(Compatibility::None, mixed.compatibility());
+        assert_eq!(
+            vec![&mixed.changes[1]],
+            mixed.breaking().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "/properties/a/type: changed type from uint8 to uint16\n\
+             /optionalProperties/b/type: changed type from string to timestamp\n",
+            mixed.to_string()
+        );
+
+        assert_eq!(Compatibility::Full, super::diff(&old, &old).compatibility());
+    }
+}
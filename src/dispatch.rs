@@ -0,0 +1,172 @@
+use crate::{validate, Schema, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde_json::Value;
+
+/// The result of [`dispatch`]: how an instance fared against each of several
+/// candidate schemas, and which of them it matches best.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dispatch<'a> {
+    /// The index into [`candidates`][`Dispatch::candidates`] of the best
+    /// match, or `None` if there were no candidates.
+    pub best: Option<usize>,
+
+    /// A report for each candidate, in the order they were given.
+    pub candidates: Vec<Candidate<'a>>,
+}
+
+/// How an instance fared against one of the schemas passed to [`dispatch`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate<'a> {
+    /// The name the schema was given.
+    pub name: &'a str,
+
+    /// The errors from validating the instance against the schema.
+    pub errors: Vec<ValidationErrorIndicator<'a>>,
+}
+
+impl<'a> Dispatch<'a> {
+    /// The report for the best match, if there were any candidates.
+    pub fn best(&self) -> Option<&Candidate<'a>> {
+        self.best.map(|index| &self.candidates[index])
+    }
+
+    /// The name of the best match, if the instance is valid against it.
+    ///
+    /// This is `None` if the instance isn't valid against any candidate, even
+    /// though there may still be a best match.
+    pub fn matched(&self) -> Option<&'a str> {
+        self.best()
+            .filter(|candidate| candidate.errors.is_empty())
+            .map(|candidate| candidate.name)
+    }
+}
+
+/// Validates an instance against several named candidate schemas, and picks
+/// the one it matches best.
+///
+/// This is for ingesting streams of data that come in several shapes, without
+/// a tag that says which shape each instance has. If the instance is valid
+/// against some candidate, that's the best match. Otherwise, the best match is
+/// the one it's closest to being valid against, so that the errors reported
+/// for it are the ones most likely to be useful. Candidates are ranked by:
+///
+/// 1. The number of errors, fewest first.
+/// 2. How deep in the instance the shallowest error is, deepest first. An
+///    instance that's only wrong about some nested property fits a schema
+///    better than one that's of the wrong type entirely.
+/// 3. The order in which the candidates were given, earliest first.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = |value| Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap();
+/// let click = schema(json!({ "properties": { "x": { "type": "uint16" }, "y": { "type": "uint16" }}}));
+/// let key = schema(json!({ "properties": { "key": { "type": "string" }}}));
+/// let candidates = [("click", &click), ("key", &key)];
+///
+/// let instance = json!({ "key": "a" });
+/// assert_eq!(Some("key"), jtd::dispatch(&candidates, &instance).unwrap().matched());
+///
+/// let instance = json!({ "x": 1, "y": -1 });
+/// let dispatch = jtd::dispatch(&candidates, &instance).unwrap();
+/// assert_eq!(None, dispatch.matched());
+/// assert_eq!("click", dispatch.best().unwrap().name);
+/// assert_eq!(vec!["y"], dispatch.best().unwrap().errors[0].instance_path);
+/// ```
+pub fn dispatch<'a>(
+    candidates: &[(&'a str, &'a Schema)],
+    instance: &'a Value,
+) -> Result<Dispatch<'a>, ValidateError> {
+    dispatch_with_options(candidates, instance, ValidateOptions::new())
+}
+
+/// Like [`dispatch`], but validates using the given options.
+///
+/// Setting [`ValidateOptions::with_max_errors`] makes dispatch faster, but
+/// also makes candidates with many errors look as good as those with few, so
+/// the best match among invalid candidates may be a worse one.
+pub fn dispatch_with_options<'a>(
+    candidates: &[(&'a str, &'a Schema)],
+    instance: &'a Value,
+    options: ValidateOptions,
+) -> Result<Dispatch<'a>, ValidateError> {
+    let candidates = candidates
+        .iter()
+        .map(|&(name, schema)| {
+            Ok(Candidate {
+                name,
+                errors: validate(schema, instance, options.clone())?,
+            })
+        })
+        .collect::<Result<Vec<_>, ValidateError>>()?;
+
+    // min_by_key returns the first of equal elements, so ties go to the
+    // earliest candidate.
+    let best = candidates
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, candidate)| {
+            let shallowest = candidate
+                .errors
+                .iter()
+                .map(|error| error.instance_path.len())
+                .min()
+                .unwrap_or(0);
+
+            (candidate.errors.len(), std::cmp::Reverse(shallowest))
+        })
+        .map(|(index, _)| index);
+
+    Ok(Dispatch { best, candidates })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn picks_best_match() {
+        let string = schema(json!({ "type": "string" }));
+        let point = schema(json!({
+            "properties": { "x": { "type": "int8" }, "y": { "type": "int8" }}
+        }));
+        let named = schema(json!({
+            "properties": { "name": { "type": "string" }},
+            "additionalProperties": true
+        }));
+        let candidates = [("string", &string), ("point", &point), ("named", &named)];
+
+        let best = |instance: Value| {
+            let dispatch = dispatch(&candidates, &instance).unwrap();
+            assert_eq!(3, dispatch.candidates.len());
+            let name = dispatch.best().unwrap().name.to_owned();
+            (name, dispatch.matched().is_some())
+        };
+
+        assert_eq!(("string".to_owned(), true), best(json!("a")));
+        assert_eq!(("point".to_owned(), true), best(json!({ "x": 1, "y": 2 })));
+        assert_eq!(
+            ("named".to_owned(), true),
+            best(json!({ "x": 1, "name": "a" }))
+        );
+
+        // With one error each, an error inside the object beats one about
+        // the object itself.
+        assert_eq!(
+            ("point".to_owned(), false),
+            best(json!({ "x": 1, "y": 200 }))
+        );
+
+        // With the same number of errors at the same depth, the first wins.
+        assert_eq!(("string".to_owned(), false), best(json!(null)));
+
+        let dispatch = dispatch(&[], &Value::Null).unwrap();
+        assert_eq!(None, dispatch.best);
+        assert_eq!(None, dispatch.matched());
+    }
+}
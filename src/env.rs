@@ -0,0 +1,596 @@
+//! Validate configuration in environment variables.
+//!
+//! Services that keep their configuration in the environment tend to use
+//! variables like `APP_DB_HOST` and `APP_DB_PORT`: a prefix, then a path of
+//! names separated by underscores. This module turns such variables into a
+//! JSON object, so that they can be validated against a schema, and reports
+//! any problems by variable name.
+//!
+//! Variables are matched to properties by converting property names to
+//! upper snake case, so `APP_DB_HOST` can set a property `host`, inside a
+//! property `db`, while `APP_MAX_CONNECTIONS` can set `maxConnections` or
+//! `max_connections`. Where the schema is of the values form, the next name
+//! is a key, in lower case, or the rest of the name is, if the values are
+//! neither objects nor arrays. Where it's of the elements form, the next name
+//! is an index, or, for a variable that sets the whole array, the value is
+//! a JSON array or a comma-separated list.
+//!
+//! Values become booleans or numbers where the schema calls for them. An
+//! empty value becomes `null` where the schema is nullable, and doesn't call
+//! for a string. Variables that don't match any property are kept, under
+//! their name in lower case, so that schemas that don't allow additional
+//! properties catch misspelled variables.
+//!
+//! ```
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": {
+//!         "db": {
+//!             "properties": {
+//!                 "host": { "type": "string" },
+//!                 "port": { "type": "uint16" }
+//!             }
+//!         },
+//!         "maxConnections": { "type": "uint32" }
+//!     }
+//! })).unwrap()).unwrap();
+//!
+//! let vars = vec![
+//!     ("APP_DB_HOST", "localhost"),
+//!     ("APP_DB_PORT", "5432"),
+//!     ("APP_MAX_CONNECTIONS", "lots"),
+//!     ("HOME", "/root"),
+//! ];
+//!
+//! assert_eq!(
+//!     json!({ "db": { "host": "localhost", "port": 5432 }, "maxConnections": "lots" }),
+//!     jtd::env::parse("APP", vars.clone(), &schema),
+//! );
+//!
+//! let errors = jtd::env::validate_vars("APP", vars, &schema, Default::default()).unwrap();
+//! assert_eq!(1, errors.len());
+//! assert_eq!("APP_MAX_CONNECTIONS", errors[0].variable);
+//! ```
+
+use crate::refs::resolve;
+use crate::{Schema, Type, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde_json::{Map, Number, Value};
+use std::borrow::Cow;
+
+/// A validation error in environment variables.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VariableError {
+    /// The name of the variable the error is about. For errors about a
+    /// missing property, it's the name of the variable that would set it.
+    pub variable: String,
+
+    /// The validation error, for the variables as a JSON object.
+    pub error: ValidationErrorIndicator<'static>,
+}
+
+/// Validates the environment variables of this process that start with
+/// `prefix` and an underscore.
+///
+/// Variables whose names or values aren't valid Unicode are ignored. See
+/// [`validate_vars`] to validate some other set of variables, or to use
+/// options other than the defaults.
+pub fn validate(prefix: &str, schema: &Schema) -> Result<Vec<VariableError>, ValidateError> {
+    let vars = std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+
+    validate_vars(prefix, vars, schema, ValidateOptions::new())
+}
+
+/// Validates the variables in `vars` that start with `prefix` and an
+/// underscore.
+pub fn validate_vars<I, K, V>(
+    prefix: &str,
+    vars: I,
+    schema: &Schema,
+    options: ValidateOptions,
+) -> Result<Vec<VariableError>, ValidateError>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let prefix = prefix.trim_end_matches('_');
+    let (value, variables) = parse_with_variables(prefix, vars, schema);
+
+    Ok(crate::validate(schema, &value, options)?
+        .into_iter()
+        .map(|error| {
+            let severity = error.severity;
+            let (instance_path, schema_path) = error.into_owned_paths();
+
+            // Errors about a missing property are about the variable that
+            // would have set it. Errors about something nested, such as an
+            // object that should have been a string, are about the first
+            // variable inside it.
+            let variable = match &schema_path[..] {
+                [.., keyword, property] if keyword == "properties" => {
+                    let mut path = instance_path.clone();
+                    path.push(property.clone());
+                    name(prefix, &path)
+                }
+                _ => variables
+                    .iter()
+                    .find(|(path, _)| *path == instance_path)
+                    .or_else(|| {
+                        variables
+                            .iter()
+                            .find(|(path, _)| path.starts_with(&instance_path))
+                    })
+                    .map_or_else(|| name(prefix, &instance_path), |(_, name)| name.clone()),
+            };
+
+            VariableError {
+                variable,
+                error: ValidationErrorIndicator {
+                    instance_path: instance_path.into_iter().map(Cow::Owned).collect(),
+                    schema_path: schema_path.into_iter().map(Cow::Owned).collect(),
+                    severity,
+                },
+            }
+        })
+        .collect())
+}
+
+/// Converts the variables in `vars` that start with `prefix` and an
+/// underscore into a JSON object, using `schema` to decide where each goes,
+/// and what type it should be.
+///
+/// See the [module documentation][`crate::env`] for how variables are
+/// converted.
+pub fn parse<I, K, V>(prefix: &str, vars: I, schema: &Schema) -> Value
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    parse_with_variables(prefix.trim_end_matches('_'), vars, schema).0
+}
+
+// A variable, and its value.
+struct Variable {
+    name: String,
+    value: String,
+}
+
+// Variables, arranged by where they go.
+enum Node {
+    Leaf(Variable),
+    Branch(Vec<(String, Node)>),
+}
+
+// Converts variables, returning the instance path of each one, and its name.
+fn parse_with_variables<I, K, V>(
+    prefix: &str,
+    vars: I,
+    schema: &Schema,
+) -> (Value, Vec<(Vec<String>, String)>)
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut converter = Converter {
+        root: schema,
+        path: Vec::new(),
+        variables: Vec::new(),
+    };
+
+    let mut vars: Vec<_> = vars
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let rest = name.as_ref().strip_prefix(prefix)?.strip_prefix('_')?;
+            let keys = converter.keys(rest);
+            let variable = Variable {
+                name: name.as_ref().to_owned(),
+                value: value.as_ref().to_owned(),
+            };
+
+            Some((keys, variable))
+        })
+        .collect();
+
+    // The environment is in no particular order, so variables are sorted, so
+    // that conflicting variables are resolved the same way every time.
+    vars.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name));
+
+    let mut root = Vec::new();
+    for (keys, variable) in vars {
+        insert(&mut root, &keys, variable);
+    }
+
+    let value = converter.convert(Node::Branch(root), Some(schema));
+    (value, converter.variables)
+}
+
+fn insert(children: &mut Vec<(String, Node)>, keys: &[String], variable: Variable) {
+    let (key, rest) = match keys.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+
+    let index = match children.iter().position(|(k, _)| k == key) {
+        Some(index) => index,
+        None => {
+            children.push((key.clone(), Node::Branch(Vec::new())));
+            children.len() - 1
+        }
+    };
+
+    // A variable that conflicts with another, as in APP_DB=x and
+    // APP_DB_HOST=y, replaces it.
+    let node = &mut children[index].1;
+    match (node, rest.is_empty()) {
+        (node, true) => *node = Node::Leaf(variable),
+        (Node::Branch(children), false) => insert(children, rest, variable),
+        (node, false) => {
+            let mut children = Vec::new();
+            insert(&mut children, rest, variable);
+            *node = Node::Branch(children);
+        }
+    }
+}
+
+// The name of the variable for an instance path.
+fn name(prefix: &str, path: &[String]) -> String {
+    std::iter::once(prefix.to_owned())
+        .chain(path.iter().map(|key| upper_snake_case(key)))
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+// Converts a name like "maxConnections" or "max-connections" into
+// "MAX_CONNECTIONS".
+fn upper_snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut prev: Option<char> = None;
+    for c in name.chars() {
+        if !c.is_alphanumeric() {
+            out.push('_');
+        } else {
+            if c.is_uppercase() && prev.is_some_and(|prev| prev.is_lowercase() || prev.is_numeric())
+            {
+                out.push('_');
+            }
+
+            out.extend(c.to_uppercase());
+        }
+
+        prev = Some(c);
+    }
+
+    out
+}
+
+struct Converter<'a> {
+    root: &'a Schema,
+    path: Vec<String>,
+    variables: Vec<(Vec<String>, String)>,
+}
+
+impl<'a> Converter<'a> {
+    // Works out where a variable goes, from its name after the prefix.
+    fn keys(&self, name: &str) -> Vec<String> {
+        let mut keys = Vec::new();
+        let mut schema = Some(self.root);
+        let mut segments: Vec<&str> = name.split('_').collect();
+
+        while !segments.is_empty() {
+            let (key, len, next) = match schema.map(|schema| resolve(self.root, schema).0) {
+                Some(Schema::Properties {
+                    properties,
+                    optional_properties,
+                    ..
+                }) => {
+                    // The longest property name that matches wins, so that
+                    // APP_DB_HOST can set "db_host", even if there's a "db".
+                    let matched = properties
+                        .iter()
+                        .chain(optional_properties)
+                        .filter_map(|(property, sub_schema)| {
+                            let property_segments = upper_snake_case(property).split('_').count();
+                            let candidate = segments.get(..property_segments)?.join("_");
+                            if candidate == upper_snake_case(property) {
+                                Some((property, property_segments, sub_schema))
+                            } else {
+                                None
+                            }
+                        })
+                        .max_by_key(|(_, len, _)| *len);
+
+                    match matched {
+                        Some((property, len, sub_schema)) => {
+                            (property.clone(), len, Some(sub_schema))
+                        }
+                        None => (segments.join("_").to_lowercase(), segments.len(), None),
+                    }
+                }
+                Some(Schema::Values { values, .. }) => {
+                    // Keys can only be followed by more names if the values
+                    // have something inside them to name.
+                    let len = match resolve(self.root, values).0 {
+                        Schema::Properties { .. }
+                        | Schema::Values { .. }
+                        | Schema::Elements { .. } => 1,
+                        _ => segments.len(),
+                    };
+
+                    (
+                        segments[..len].join("_").to_lowercase(),
+                        len,
+                        Some(&**values),
+                    )
+                }
+                Some(Schema::Elements { elements, .. }) if segments[0].parse::<usize>().is_ok() => {
+                    (segments[0].to_owned(), 1, Some(&**elements))
+                }
+                _ => (segments.join("_").to_lowercase(), segments.len(), None),
+            };
+
+            keys.push(key);
+            segments.drain(..len);
+            schema = next;
+        }
+
+        keys
+    }
+
+    fn convert(&mut self, node: Node, schema: Option<&'a Schema>) -> Value {
+        let (schema, nullable) = match schema {
+            Some(schema) => {
+                let (schema, nullable) = resolve(self.root, schema);
+                (Some(schema), nullable)
+            }
+            None => (None, false),
+        };
+
+        match (node, schema) {
+            (Node::Leaf(variable), Some(Schema::Elements { elements, .. })) => {
+                self.variables
+                    .push((self.path.clone(), variable.name.clone()));
+
+                if let Ok(Value::Array(values)) = serde_json::from_str(&variable.value) {
+                    return Value::Array(values);
+                }
+
+                if variable.value.is_empty() {
+                    return if nullable {
+                        Value::Null
+                    } else {
+                        Value::Array(Vec::new())
+                    };
+                }
+
+                let elements = resolve(self.root, elements).0;
+                let values = variable
+                    .value
+                    .split(',')
+                    .enumerate()
+                    .map(|(i, value)| {
+                        let mut path = self.path.clone();
+                        path.push(i.to_string());
+                        self.variables.push((path, variable.name.clone()));
+                        coerce(value, Some(elements))
+                    })
+                    .collect();
+
+                Value::Array(values)
+            }
+            (Node::Leaf(variable), schema) => {
+                self.variables.push((self.path.clone(), variable.name));
+                if variable.value.is_empty() && nullable && !is_string(schema) {
+                    return Value::Null;
+                }
+
+                coerce(&variable.value, schema)
+            }
+            (Node::Branch(mut children), Some(Schema::Elements { elements, .. })) => {
+                // Indices are put in order, and any gaps between them closed.
+                children.sort_by_key(|(key, _)| key.parse::<usize>().unwrap_or(usize::MAX));
+
+                let values = children
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (_, child))| {
+                        self.path.push(i.to_string());
+                        let value = self.convert(child, Some(elements));
+                        self.path.pop();
+                        value
+                    })
+                    .collect();
+
+                Value::Array(values)
+            }
+            (Node::Branch(children), schema) => {
+                let mut object = Map::new();
+                for (key, child) in children {
+                    let sub_schema = match schema {
+                        Some(Schema::Values { values, .. }) => Some(&**values),
+                        Some(Schema::Properties {
+                            properties,
+                            optional_properties,
+                            ..
+                        }) => properties
+                            .get(&key)
+                            .or_else(|| optional_properties.get(&key)),
+                        _ => None,
+                    };
+
+                    self.path.push(key.clone());
+                    let value = self.convert(child, sub_schema);
+                    self.path.pop();
+                    object.insert(key, value);
+                }
+
+                Value::Object(object)
+            }
+        }
+    }
+}
+
+fn coerce(value: &str, schema: Option<&Schema>) -> Value {
+    let number = || {
+        value
+            .parse::<i64>()
+            .ok()
+            .map(Number::from)
+            .or_else(|| value.parse::<f64>().ok().and_then(Number::from_f64))
+            .map(Value::Number)
+    };
+
+    let coerced = match schema {
+        Some(Schema::Type {
+            type_: Type::Boolean,
+            ..
+        }) => match value.to_ascii_lowercase().as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        Some(Schema::Type {
+            type_: Type::String | Type::Timestamp,
+            ..
+        }) => None,
+        Some(Schema::Type { .. }) => number(),
+        #[cfg(feature = "extensions")]
+        Some(Schema::IntEnum { .. }) => number(),
+        Some(Schema::Properties { .. })
+        | Some(Schema::Values { .. })
+        | Some(Schema::Discriminator { .. }) => serde_json::from_str(value).ok(),
+        _ => None,
+    };
+
+    coerced.unwrap_or_else(|| value.into())
+}
+
+fn is_string(schema: Option<&Schema>) -> bool {
+    matches!(
+        schema,
+        Some(Schema::Type {
+            type_: Type::String | Type::Timestamp,
+            ..
+        }) | Some(Schema::Enum { .. })
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "server": {
+                        "properties": {
+                            "host": { "type": "string" },
+                            "port": { "type": "uint16" }
+                        }
+                    }
+                },
+                "properties": {
+                    "db": { "ref": "server" },
+                    "db_pool": { "type": "uint8" },
+                    "debug": { "type": "boolean" }
+                },
+                "optionalProperties": {
+                    "hosts": { "elements": { "type": "string" }},
+                    "ports": { "elements": { "type": "uint16" }},
+                    "labels": { "values": { "type": "string" }},
+                    "timeoutMs": { "type": "uint32", "nullable": true }
+                },
+                "additionalProperties": false
+            }))
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses() {
+        assert_eq!(
+            json!({
+                "db": { "host": "h", "port": 1 },
+                "db_pool": 8,
+                "debug": true,
+                "hosts": ["a", "b"],
+                "ports": [3, 1, 2],
+                "labels": { "team": "x", "env_name": "prod" },
+                "timeoutMs": null
+            }),
+            parse(
+                "APP_",
+                vec![
+                    ("APP_DB_HOST", "h"),
+                    ("APP_DB_PORT", "1"),
+                    ("APP_DB_POOL", "8"),
+                    ("APP_DEBUG", "TRUE"),
+                    ("APP_HOSTS", "a,b"),
+                    ("APP_PORTS_10", "2"),
+                    ("APP_PORTS_2", "1"),
+                    ("APP_PORTS_0", "3"),
+                    ("APP_LABELS_TEAM", "x"),
+                    ("APP_LABELS_ENV_NAME", "prod"),
+                    ("APP_TIMEOUT_MS", ""),
+                    ("APPLE", "no"),
+                    ("OTHER_DEBUG", "no"),
+                ],
+                &schema()
+            )
+        );
+
+        assert_eq!(
+            json!({ "hosts": ["a", 1] }),
+            parse("APP", vec![("APP_HOSTS", r#"["a", 1]"#)], &schema())
+        );
+    }
+
+    #[test]
+    fn validates() {
+        let errors = validate_vars(
+            "APP",
+            vec![
+                ("APP_DB_HOST", "h"),
+                ("APP_DB_PORT", "99999"),
+                ("APP_DEBUG", "yes"),
+                ("APP_PORTS", "1,x"),
+                ("APP_DEBUGG", "true"),
+            ],
+            &schema(),
+            Default::default(),
+        )
+        .unwrap();
+
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|error| (error.variable.as_str(), error.error.schema_path.to_string()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (
+                    "APP_DB_PORT",
+                    "/definitions/server/properties/port/type".to_owned()
+                ),
+                ("APP_DB_POOL", "/properties/db_pool".to_owned()),
+                ("APP_DEBUG", "/properties/debug/type".to_owned()),
+                (
+                    "APP_PORTS",
+                    "/optionalProperties/ports/elements/type".to_owned()
+                ),
+                ("APP_DEBUGG", "".to_owned()),
+            ],
+            errors
+        );
+
+        assert_eq!("MAX_CONNECTIONS2_X", upper_snake_case("maxConnections2X"));
+        assert_eq!("DB_HOST", upper_snake_case("db-host"));
+    }
+}
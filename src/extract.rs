@@ -0,0 +1,500 @@
+//! Pick out a few typed values from an instance, without deserializing all
+//! of it.
+//!
+//! Applications often need only a handful of fields from a large payload,
+//! such as an ID and a timestamp to route it by. Deserializing the whole
+//! payload into Rust types is wasteful for that, but reading fields out of a
+//! [`serde_json::Value`] by hand loses what the schema says about them.
+//! [`extract`] finds a value by its path in the instance, follows the schema
+//! down to it, checks the value against the schema there, and converts it to
+//! the matching Rust type, as an [`Extracted`]:
+//!
+//! ```
+//! use jtd::extract::Extracted;
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": {
+//!         "id": { "type": "uint32" },
+//!         "sentAt": { "type": "timestamp" },
+//!         "event": {
+//!             "discriminator": "kind",
+//!             "mapping": {
+//!                 "click": { "properties": { "button": { "enum": ["left", "right"] }}},
+//!                 "scroll": { "properties": { "delta": { "type": "float64" }}}
+//!             }
+//!         }
+//!     },
+//!     "optionalProperties": { "note": { "type": "string" }}
+//! })).unwrap()).unwrap();
+//!
+//! let instance = json!({
+//!     "id": 7,
+//!     "sentAt": "2024-01-02T03:04:05Z",
+//!     "event": { "kind": "click", "button": "right" }
+//! });
+//!
+//! assert_eq!(Some(Extracted::Uint(7)), jtd::extract::extract(&schema, &instance, "/id").unwrap());
+//! assert_eq!(
+//!     Some(Extracted::Enum { value: "right", index: 1 }),
+//!     jtd::extract::extract(&schema, &instance, "/event/button").unwrap(),
+//! );
+//! assert_eq!(None, jtd::extract::extract(&schema, &instance, "/note").unwrap());
+//!
+//! let values = jtd::extract::extract_all(&schema, &instance, ["/sentAt", "/event/kind"]).unwrap();
+//! assert_eq!(
+//!     Some(&Extracted::Enum { value: "click", index: 0 }),
+//!     values.get("/event/kind"),
+//! );
+//! match values["/sentAt"] {
+//!     Extracted::Timestamp(t) => assert_eq!(1704164645, t.timestamp()),
+//!     ref other => panic!("{:?}", other),
+//! }
+//! ```
+//!
+//! Only the values along the path are checked against the schema, so the
+//! rest of the instance may be invalid without getting in the way.
+
+use crate::{InstancePath, Schema, Type};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// A value extracted from an instance, converted to the Rust type its schema
+/// calls for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Extracted<'v> {
+    /// A `null`, where the schema is nullable.
+    Null,
+
+    /// A value of type `boolean`.
+    Boolean(bool),
+
+    /// A value of type `int8`, `int16`, or `int32`, or, with the `extensions`
+    /// feature, of an enum of integers.
+    Int(i64),
+
+    /// A value of type `uint8`, `uint16`, or `uint32`.
+    Uint(u64),
+
+    /// A value of type `float32` or `float64`.
+    Float(f64),
+
+    /// A value of type `string`.
+    String(&'v str),
+
+    /// A value of type `timestamp`.
+    ///
+    /// This variant is only available with the `chrono` feature. Without it,
+    /// timestamps are extracted as [`Extracted::String`].
+    #[cfg(feature = "chrono")]
+    Timestamp(chrono::DateTime<chrono::FixedOffset>),
+
+    /// A value of the enum form, or the tag of a discriminator.
+    Enum {
+        /// The value itself.
+        value: &'v str,
+
+        /// The position of the value among those of the enum, or the tags of
+        /// the discriminator's `mapping`, in sorted order.
+        index: usize,
+    },
+
+    /// A value whose schema doesn't say what type it should have: one of the
+    /// empty form, or, with the `extensions` feature, of a custom type. Such
+    /// values aren't checked.
+    Json(&'v Value),
+}
+
+/// Errors that may arise from [`extract`].
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ExtractError {
+    /// The path isn't a JSON Pointer.
+    #[error("invalid JSON pointer: {0:?}")]
+    InvalidPointer(String),
+
+    /// The schema doesn't describe anything at the path, so there's no
+    /// telling what type the value there would have.
+    #[error("no such path in schema: {0:?}")]
+    NoSuchPath(String),
+
+    /// The schema describes an array or object at the path, rather than a
+    /// single value.
+    #[error("not a primitive value: {0:?}")]
+    NotPrimitive(String),
+
+    /// The instance doesn't satisfy the schema somewhere along the path. The
+    /// pointer is to the value that doesn't.
+    #[error("invalid value at {0:?}")]
+    Invalid(String),
+}
+
+/// Extracts the value at a path in an instance.
+///
+/// The path is a [JSON Pointer](https://tools.ietf.org/html/rfc6901), such as
+/// `/users/0/name`. Returns `None` if there's nothing at the path, but the
+/// schema allows for that, such as for an optional property, an array
+/// element past the end of the array, or anything inside a `null`.
+///
+/// See the [module documentation][`crate::extract`] for an example.
+pub fn extract<'v>(
+    schema: &Schema,
+    instance: &'v Value,
+    pointer: &str,
+) -> Result<Option<Extracted<'v>>, ExtractError> {
+    let tokens = parse_pointer(pointer)?;
+    Extractor {
+        root: schema,
+        pointer,
+    }
+    .extract(schema, instance, &tokens, 0)
+}
+
+/// Extracts the values at several paths in an instance, with [`extract`].
+///
+/// The values are keyed by their paths. Paths with nothing at them are left
+/// out.
+pub fn extract_all<'v, 'p, I>(
+    schema: &Schema,
+    instance: &'v Value,
+    pointers: I,
+) -> Result<BTreeMap<&'p str, Extracted<'v>>, ExtractError>
+where
+    I: IntoIterator<Item = &'p str>,
+{
+    let mut values = BTreeMap::new();
+    for pointer in pointers {
+        if let Some(value) = extract(schema, instance, pointer)? {
+            values.insert(pointer, value);
+        }
+    }
+
+    Ok(values)
+}
+
+struct Extractor<'a> {
+    root: &'a Schema,
+    pointer: &'a str,
+}
+
+impl<'a> Extractor<'a> {
+    // Extracts the value at tokens[depth..] of instance, which is at
+    // tokens[..depth] of the root instance.
+    fn extract<'v>(
+        &self,
+        schema: &'a Schema,
+        instance: &'v Value,
+        tokens: &[String],
+        depth: usize,
+    ) -> Result<Option<Extracted<'v>>, ExtractError> {
+        let schema = self.resolve(schema)?;
+
+        if instance.is_null() && schema.nullable() {
+            if depth == tokens.len() {
+                return Ok(Some(Extracted::Null));
+            }
+
+            self.check_path(schema, tokens, depth)?;
+            return Ok(None);
+        }
+
+        let token = match tokens.get(depth) {
+            Some(token) => token,
+            None => return self.convert(schema, instance, tokens, depth).map(Some),
+        };
+
+        let (sub_schema, sub_instance) = match schema {
+            Schema::Elements { elements, .. } => {
+                let arr = instance.as_array().ok_or_else(|| invalid(tokens, depth))?;
+                let index: usize = token.parse().map_err(|_| self.no_such_path())?;
+                (&**elements, arr.get(index))
+            }
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            } => {
+                let obj = instance.as_object().ok_or_else(|| invalid(tokens, depth))?;
+                match (properties.get(token), optional_properties.get(token)) {
+                    (Some(sub_schema), _) => match obj.get(token) {
+                        Some(sub_instance) => (sub_schema, Some(sub_instance)),
+                        None => return Err(invalid(tokens, depth)),
+                    },
+                    (None, Some(sub_schema)) => (sub_schema, obj.get(token)),
+                    (None, None) => return Err(self.no_such_path()),
+                }
+            }
+            Schema::Values { values, .. } => {
+                let obj = instance.as_object().ok_or_else(|| invalid(tokens, depth))?;
+                (&**values, obj.get(token))
+            }
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } => {
+                let obj = instance.as_object().ok_or_else(|| invalid(tokens, depth))?;
+                let tag = obj
+                    .get(discriminator)
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| invalid(tokens, depth))?;
+
+                let index = mapping.keys().position(|key| key == tag).ok_or_else(|| {
+                    let mut tag_tokens = tokens[..depth].to_vec();
+                    tag_tokens.push(discriminator.clone());
+                    invalid(&tag_tokens, depth + 1)
+                })?;
+
+                if token == discriminator {
+                    if depth + 1 != tokens.len() {
+                        return Err(self.no_such_path());
+                    }
+
+                    return Ok(Some(Extracted::Enum { value: tag, index }));
+                }
+
+                // The token is looked up in the variant, which describes the
+                // same object.
+                return self.extract(&mapping[tag], instance, tokens, depth);
+            }
+            _ => return Err(self.no_such_path()),
+        };
+
+        match sub_instance {
+            Some(sub_instance) => self.extract(sub_schema, sub_instance, tokens, depth + 1),
+            None => {
+                // There's nothing here, but the path must still make sense.
+                self.check_path(sub_schema, tokens, depth + 1)?;
+                Ok(None)
+            }
+        }
+    }
+
+    fn convert<'v>(
+        &self,
+        schema: &'a Schema,
+        instance: &'v Value,
+        tokens: &[String],
+        depth: usize,
+    ) -> Result<Extracted<'v>, ExtractError> {
+        let invalid = || invalid(tokens, depth);
+
+        match schema {
+            Schema::Empty { .. } => Ok(Extracted::Json(instance)),
+            Schema::Type { type_, .. } => {
+                let check = match jtd_core::__private::type_check(type_) {
+                    Some(check) => check,
+                    None => return Ok(Extracted::Json(instance)),
+                };
+
+                if !check(instance) {
+                    return Err(invalid());
+                }
+
+                // Integer types accept numbers like 1.0, which aren't stored
+                // as integers, and so need converting from floats.
+                let float = instance.as_f64().unwrap_or_default();
+                Ok(match type_ {
+                    Type::Boolean => Extracted::Boolean(instance.as_bool().unwrap_or_default()),
+                    Type::Float32 | Type::Float64 => Extracted::Float(float),
+                    Type::Int8 | Type::Int16 | Type::Int32 => {
+                        Extracted::Int(instance.as_i64().unwrap_or(float as i64))
+                    }
+                    Type::Uint8 | Type::Uint16 | Type::Uint32 => {
+                        Extracted::Uint(instance.as_u64().unwrap_or(float as u64))
+                    }
+                    Type::String => Extracted::String(instance.as_str().unwrap_or_default()),
+                    #[cfg(feature = "chrono")]
+                    Type::Timestamp => {
+                        let s = instance.as_str().unwrap_or_default();
+                        Extracted::Timestamp(
+                            chrono::DateTime::parse_from_rfc3339(s).map_err(|_| invalid())?,
+                        )
+                    }
+                    #[cfg(not(feature = "chrono"))]
+                    Type::Timestamp => Extracted::String(instance.as_str().unwrap_or_default()),
+                    #[cfg(feature = "extensions")]
+                    Type::Custom(_) => Extracted::Json(instance),
+                })
+            }
+            Schema::Enum { enum_, .. } => {
+                let value = instance.as_str().ok_or_else(invalid)?;
+                let index = enum_.iter().position(|v| v == value).ok_or_else(invalid)?;
+                Ok(Extracted::Enum { value, index })
+            }
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { enum_, .. } => {
+                let value = instance.as_f64().ok_or_else(invalid)?;
+                if !jtd_core::__private::int_enum_contains(enum_, value) {
+                    return Err(invalid());
+                }
+
+                Ok(Extracted::Int(instance.as_i64().unwrap_or(value as i64)))
+            }
+            _ => Err(ExtractError::NotPrimitive(self.pointer.to_owned())),
+        }
+    }
+
+    // Checks that tokens[depth..] leads to a primitive in schema, for when
+    // there's no instance to follow.
+    fn check_path(
+        &self,
+        schema: &'a Schema,
+        tokens: &[String],
+        depth: usize,
+    ) -> Result<(), ExtractError> {
+        let schema = self.resolve(schema)?;
+        let token = match tokens.get(depth) {
+            Some(token) => token,
+            None => {
+                return match schema {
+                    Schema::Elements { .. }
+                    | Schema::Properties { .. }
+                    | Schema::Values { .. }
+                    | Schema::Discriminator { .. } => {
+                        Err(ExtractError::NotPrimitive(self.pointer.to_owned()))
+                    }
+                    _ => Ok(()),
+                }
+            }
+        };
+
+        match schema {
+            Schema::Elements { elements, .. } if token.parse::<usize>().is_ok() => {
+                self.check_path(elements, tokens, depth + 1)
+            }
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            } => match properties
+                .get(token)
+                .or_else(|| optional_properties.get(token))
+            {
+                Some(sub_schema) => self.check_path(sub_schema, tokens, depth + 1),
+                None => Err(self.no_such_path()),
+            },
+            Schema::Values { values, .. } => self.check_path(values, tokens, depth + 1),
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } => {
+                // Without an instance, there's no telling which variant
+                // applies, so any of them will do.
+                if token == discriminator && depth + 1 == tokens.len() {
+                    return Ok(());
+                }
+
+                if mapping
+                    .values()
+                    .any(|variant| self.check_path(variant, tokens, depth).is_ok())
+                {
+                    Ok(())
+                } else {
+                    Err(self.no_such_path())
+                }
+            }
+            _ => Err(self.no_such_path()),
+        }
+    }
+
+    // Follows refs, returning the schema they lead to.
+    fn resolve(&self, schema: &'a Schema) -> Result<&'a Schema, ExtractError> {
+        schema
+            .resolve_ref(self.root)
+            .map_err(|_| self.no_such_path())
+    }
+
+    fn no_such_path(&self) -> ExtractError {
+        ExtractError::NoSuchPath(self.pointer.to_owned())
+    }
+}
+
+// An Invalid error about the value at tokens[..depth].
+fn invalid(tokens: &[String], depth: usize) -> ExtractError {
+    ExtractError::Invalid(InstancePath::from(&tokens[..depth]).to_string())
+}
+
+// Splits a JSON Pointer into its unescaped tokens.
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, ExtractError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    match pointer.strip_prefix('/') {
+        Some(rest) => Ok(rest
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect()),
+        None => Err(ExtractError::InvalidPointer(pointer.to_owned())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn extracts_along_paths() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "point": {
+                        "properties": { "x": { "type": "int16" }, "y": { "type": "int16" }},
+                        "nullable": true
+                    }
+                },
+                "properties": {
+                    "points": { "elements": { "ref": "point" }},
+                    "tags": { "values": { "type": "boolean" }},
+                    "a/b": { "type": "float32" },
+                    "extra": {}
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let instance = json!({
+            "points": [{ "x": 1.0, "y": -2 }, null, { "x": "bad", "y": 0 }],
+            "tags": { "big": true },
+            "a/b": 1.5,
+            "extra": [1, 2]
+        });
+
+        let extract = |pointer| super::extract(&schema, &instance, pointer);
+
+        assert_eq!(Ok(Some(Extracted::Int(1))), extract("/points/0/x"));
+        assert_eq!(Ok(Some(Extracted::Int(-2))), extract("/points/0/y"));
+        assert_eq!(Ok(Some(Extracted::Null)), extract("/points/1"));
+        assert_eq!(Ok(None), extract("/points/1/x"));
+        assert_eq!(Ok(None), extract("/points/9/x"));
+        assert_eq!(Ok(Some(Extracted::Boolean(true))), extract("/tags/big"));
+        assert_eq!(Ok(None), extract("/tags/small"));
+        assert_eq!(Ok(Some(Extracted::Float(1.5))), extract("/a~1b"));
+        assert_eq!(Ok(Some(Extracted::Json(&json!([1, 2])))), extract("/extra"));
+
+        // The rest of the instance doesn't have to be valid.
+        assert_eq!(
+            Err(ExtractError::Invalid("/points/2/x".into())),
+            extract("/points/2/x")
+        );
+        assert_eq!(Ok(Some(Extracted::Int(0))), extract("/points/2/y"));
+
+        assert_eq!(
+            Err(ExtractError::NoSuchPath("/points/1/z".into())),
+            extract("/points/1/z")
+        );
+        assert_eq!(
+            Err(ExtractError::NotPrimitive("/points/0".into())),
+            extract("/points/0")
+        );
+        assert_eq!(
+            Err(ExtractError::InvalidPointer("points".into())),
+            extract("points")
+        );
+    }
+}
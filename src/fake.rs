@@ -0,0 +1,410 @@
+//! Generate random instances of a schema.
+//!
+//! A [`Faker`] produces a stream of random values that a schema accepts, for
+//! fuzzing the programs that consume them, or for filling in examples in
+//! documentation. Values are meant to look plausible rather than to probe
+//! edge cases: strings are words, timestamps are dates in this century, and
+//! so on. For edge cases, and for values that are almost but not quite valid,
+//! see [`fuzzing::seed_corpus`][`crate::fuzzing::seed_corpus`].
+//!
+//! Generation is reproducible: the same schema, options, and seed always
+//! give the same instances.
+//!
+//! ```
+//! use jtd::fake::{FakeOptions, Faker};
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": {
+//!         "id": { "type": "uint32" },
+//!         "tags": { "elements": { "type": "string" }}
+//!     }
+//! })).unwrap()).unwrap();
+//!
+//! let options = FakeOptions::new().with_seed(42).with_elements_len(1, 3);
+//! let instances: Vec<_> = Faker::new(&schema, options.clone()).take(10).collect();
+//!
+//! for instance in &instances {
+//!     assert!(jtd::validate(&schema, instance, Default::default()).unwrap().is_empty());
+//!
+//!     let tags = instance["tags"].as_array().unwrap();
+//!     assert!((1..=3).contains(&tags.len()));
+//! }
+//!
+//! // The same seed gives the same instances.
+//! assert_eq!(instances, Faker::new(&schema, options).take(10).collect::<Vec<_>>());
+//! ```
+
+use crate::rng::Rng;
+use crate::{Schema, Type};
+use serde_json::{json, Map, Value};
+
+// How much deeper than the maximum depth generation may go, following
+// required properties and refs, before it gives up. Schemas that need to go
+// deeper than this don't have instances of a reasonable size, if any.
+const MAX_EXTRA_DEPTH: usize = 64;
+
+const WORDS: [&str; 16] = [
+    "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india", "juliett",
+    "kilo", "lima", "mike", "november", "oscar", "papa",
+];
+
+/// Options you can pass to [`Faker::new`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FakeOptions {
+    seed: u64,
+    elements_len: (usize, usize),
+    values_len: (usize, usize),
+    max_depth: usize,
+}
+
+impl Default for FakeOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            elements_len: (0, 4),
+            values_len: (0, 4),
+            max_depth: 8,
+        }
+    }
+}
+
+impl FakeOptions {
+    /// Constructs a new set of options with all default values.
+    ///
+    /// Equivalent to [`Default::default()`] or calling `with_*` methods with
+    /// default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the seed of the random number generator.
+    ///
+    /// By default, the seed is zero.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the least and most elements in a generated array, inclusive.
+    ///
+    /// If `min` is greater than `max`, arrays have `min` elements.
+    ///
+    /// By default, arrays have between 0 and 4 elements.
+    pub fn with_elements_len(mut self, min: usize, max: usize) -> Self {
+        self.elements_len = (min, max.max(min));
+        self
+    }
+
+    /// Sets the least and most entries in an object generated for the values
+    /// form, inclusive.
+    ///
+    /// If `min` is greater than `max`, objects have `min` entries. With the
+    /// `extensions` feature, objects may have fewer entries than `min`, if
+    /// their `keys` don't allow that many.
+    ///
+    /// By default, objects have between 0 and 4 entries.
+    pub fn with_values_len(mut self, min: usize, max: usize) -> Self {
+        self.values_len = (min, max.max(min));
+        self
+    }
+
+    /// Sets how deeply instances may nest before generation favors the
+    /// smallest values it can: null for nullable schemas, empty arrays and
+    /// objects, and no optional properties.
+    ///
+    /// This keeps instances of recursive schemas finite. Instances may still
+    /// nest more deeply than this, where a schema requires it.
+    ///
+    /// By default, the maximum depth is 8.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+/// Generates random instances of a schema.
+///
+/// A `Faker` is an endless [`Iterator`] of instances. See the
+/// [module documentation][`crate::fake`] for an example.
+///
+/// Instances are valid against the schema, with a few exceptions:
+///
+/// * Schemas that have no finite instances, such as a definition that
+///   requires a property of its own type, give up after a while and generate
+///   `null`.
+/// * A discriminator with an empty mapping, or an empty enum, has no
+///   instances at all, and generates `null`.
+/// * With the `extensions` feature, a custom type generates a string,
+///   which the type may not accept, and `keys` with a `pattern` generate an
+///   empty object.
+#[derive(Clone, Debug)]
+pub struct Faker<'a> {
+    root: &'a Schema,
+    options: FakeOptions,
+    rng: Rng,
+}
+
+impl<'a> Faker<'a> {
+    /// Constructs a generator of instances of `schema`.
+    pub fn new(schema: &'a Schema, options: FakeOptions) -> Self {
+        Self {
+            root: schema,
+            rng: Rng::new(options.seed),
+            options,
+        }
+    }
+
+    /// Generates an instance.
+    pub fn instance(&mut self) -> Value {
+        self.generate(self.root, None, 0)
+    }
+
+    fn generate(&mut self, schema: &'a Schema, parent_tag: Option<&str>, depth: usize) -> Value {
+        let shallow = depth >= self.options.max_depth;
+        if depth > self.options.max_depth + MAX_EXTRA_DEPTH
+            || schema.nullable() && (shallow || self.rng.below(8) == 0)
+        {
+            return Value::Null;
+        }
+
+        match schema {
+            Schema::Empty { .. } => match self.rng.below(4) {
+                0 => json!(self.rng.below(2) == 0),
+                1 => json!(self.rng.below(1000)),
+                _ => json!(self.word()),
+            },
+            Schema::Ref { ref_, .. } => match self.root.definitions().get(ref_) {
+                Some(definition) => self.generate(definition, None, depth + 1),
+                None => Value::Null,
+            },
+            Schema::Type { type_, .. } => self.type_(type_),
+            Schema::Enum { enum_, .. } => {
+                let values: Vec<_> = enum_.iter().collect();
+                if values.is_empty() {
+                    Value::Null
+                } else {
+                    json!(self.rng.choose(&values))
+                }
+            }
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { enum_, .. } => {
+                let values: Vec<_> = enum_.iter().collect();
+                if values.is_empty() {
+                    Value::Null
+                } else {
+                    json!(self.rng.choose(&values))
+                }
+            }
+            Schema::Elements { elements, .. } => {
+                let len = self.len(self.options.elements_len, shallow);
+                (0..len)
+                    .map(|_| self.generate(elements, None, depth + 1))
+                    .collect()
+            }
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            } => {
+                let mut object = Map::new();
+                for (name, schema) in properties {
+                    object.insert(name.clone(), self.generate(schema, None, depth + 1));
+                }
+
+                for (name, schema) in optional_properties {
+                    if !shallow && self.rng.below(2) == 0 {
+                        object.insert(name.clone(), self.generate(schema, None, depth + 1));
+                    }
+                }
+
+                if let Some(tag) = parent_tag {
+                    object.remove(tag);
+                }
+
+                Value::Object(object)
+            }
+            Schema::Values { values, .. } => {
+                let len = self.len(self.options.values_len, shallow);
+
+                #[cfg(feature = "extensions")]
+                let keys: Vec<String> = match schema {
+                    Schema::Values {
+                        keys: Some(crate::Keys::Enum(keys)),
+                        ..
+                    } => keys.iter().cloned().collect(),
+                    Schema::Values {
+                        keys:
+                            Some(crate::Keys::String {
+                                pattern: Some(_), ..
+                            }),
+                        ..
+                    } => vec![],
+                    _ => self.keys(len),
+                };
+
+                #[cfg(not(feature = "extensions"))]
+                let keys = self.keys(len);
+
+                keys.into_iter()
+                    .take(len)
+                    .map(|key| (key, self.generate(values, None, depth + 1)))
+                    .collect()
+            }
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } => {
+                let tags: Vec<_> = mapping.keys().collect();
+                if tags.is_empty() {
+                    return Value::Null;
+                }
+
+                let tag = self.rng.choose(&tags).to_string();
+                let mut object = match self.generate(&mapping[&tag], Some(discriminator), depth + 1)
+                {
+                    Value::Object(object) => object,
+                    _ => Map::new(),
+                };
+
+                object.insert(discriminator.clone(), json!(tag));
+                Value::Object(object)
+            }
+        }
+    }
+
+    fn type_(&mut self, type_: &Type) -> Value {
+        match type_ {
+            Type::Boolean => json!(self.rng.below(2) == 0),
+            Type::Int8 => self.int(-128, 127),
+            Type::Uint8 => self.int(0, 255),
+            Type::Int16 => self.int(-32768, 32767),
+            Type::Uint16 => self.int(0, 65535),
+            Type::Int32 => self.int(-2147483648, 2147483647),
+            Type::Uint32 => self.int(0, 4294967295),
+            // Floats are kept to a few decimal places, as they would be in
+            // most examples.
+            Type::Float32 | Type::Float64 => {
+                let float = (self.rng.next_f64() - 0.5) * 2000.0;
+                json!((float * 100.0).round() / 100.0)
+            }
+            Type::String => json!(self.word()),
+            Type::Timestamp => json!(format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+                2000 + self.rng.below(50),
+                1 + self.rng.below(12),
+                1 + self.rng.below(28),
+                self.rng.below(24),
+                self.rng.below(60),
+                self.rng.below(60),
+            )),
+            #[cfg(feature = "extensions")]
+            Type::Custom(_) => json!(self.word()),
+        }
+    }
+
+    fn int(&mut self, min: i64, max: i64) -> Value {
+        json!(min + self.rng.below((max - min + 1) as u64) as i64)
+    }
+
+    fn len(&mut self, (min, max): (usize, usize), shallow: bool) -> usize {
+        if shallow {
+            0
+        } else {
+            min + self.rng.below((max - min + 1) as u64) as usize
+        }
+    }
+
+    fn word(&mut self) -> String {
+        self.rng.choose(&WORDS).to_string()
+    }
+
+    // Generates n distinct keys for a values-form object.
+    fn keys(&mut self, n: usize) -> Vec<String> {
+        let offset = self.rng.below(WORDS.len() as u64) as usize;
+        (0..n)
+            .map(|i| {
+                let word = WORDS[(offset + i) % WORDS.len()];
+                match i / WORDS.len() {
+                    0 => word.to_owned(),
+                    round => format!("{}{}", word, round + 1),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Iterator for Faker<'_> {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Value> {
+        Some(self.instance())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_instances() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "node": {
+                        "properties": {
+                            "children": { "elements": { "ref": "node" }}
+                        },
+                        "optionalProperties": {
+                            "at": { "type": "timestamp", "nullable": true },
+                            "weight": { "type": "float32" }
+                        }
+                    }
+                },
+                "discriminator": "kind",
+                "mapping": {
+                    "tree": { "properties": { "root": { "ref": "node" }}},
+                    "list": {
+                        "properties": {
+                            "items": { "values": { "type": "int8" }},
+                            "tags": { "elements": { "enum": ["a", "b"] }}
+                        }
+                    },
+                    "any": { "properties": { "value": {} }}
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let options = FakeOptions::new().with_seed(7).with_values_len(20, 20);
+        for instance in Faker::new(&schema, options).take(200) {
+            let errors = crate::validate(&schema, &instance, Default::default()).unwrap();
+            assert!(errors.is_empty(), "{}", instance);
+
+            if instance["kind"] == "list" {
+                assert_eq!(20, instance["items"].as_object().unwrap().len());
+            }
+        }
+    }
+
+    #[test]
+    fn no_finite_instances() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "a": { "properties": { "a": { "ref": "a" }}}
+                },
+                "ref": "a"
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let instance = Faker::new(&schema, FakeOptions::new().with_max_depth(0)).instance();
+        let errors = crate::validate(&schema, &instance, Default::default()).unwrap();
+        assert_eq!(1, errors.len());
+    }
+}
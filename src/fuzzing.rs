@@ -0,0 +1,491 @@
+//! Generate inputs for fuzzing services that consume schema-conformant data.
+//!
+//! Coverage-guided fuzzers such as libFuzzer and AFL start from a corpus of
+//! example inputs. For a service that expects JSON satisfying a schema, the
+//! most useful examples are ones that get past its input validation, along
+//! with ones that only just fail it. [`seed_corpus`] generates both.
+
+use crate::rng::Rng;
+use crate::{validate, Schema, Type, ValidateOptions};
+use serde_json::{json, Map, Value};
+
+// How deep to go before generating only the smallest possible values, so that
+// generation terminates for recursive schemas.
+const MAX_DEPTH: usize = 8;
+
+// The most entries in a generated array or values-form object.
+const MAX_LEN: u64 = 4;
+
+const STRINGS: [&str; 8] = [
+    "",
+    "a",
+    "hello world",
+    "ünïcödé",
+    "😀",
+    "with \"quotes\" and \\",
+    "line\nbreak",
+    "\u{0}",
+];
+
+/// Generates `n` serialized JSON instances of a schema, for seeding a fuzzing
+/// corpus.
+///
+/// Instances alternate between valid and near-valid ones, starting with a
+/// valid one. A near-valid instance is a valid one with exactly one thing
+/// wrong with it, such as a missing required property, an unknown enum value,
+/// an integer out of range, or a value of the wrong type. If a schema accepts
+/// anything, so that nothing can be wrong, a valid instance is generated in
+/// place of a near-valid one.
+///
+/// Generated values favor edge cases: the minimum and maximum of integer
+/// types, empty and non-ASCII strings, and so on. The output depends only on
+/// the schema, `n`, and `seed`, so a corpus can be regenerated exactly.
+///
+/// For recursive schemas, generation stops recursing after a while, which
+/// might make an instance that's meant to be valid invalid.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "id": { "type": "uint32" },
+///         "role": { "enum": ["admin", "member"] }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let corpus = jtd::fuzzing::seed_corpus(&schema, 10, 42);
+/// assert_eq!(10, corpus.len());
+/// assert_eq!(corpus, jtd::fuzzing::seed_corpus(&schema, 10, 42));
+///
+/// for (i, input) in corpus.iter().enumerate() {
+///     let instance: serde_json::Value = serde_json::from_slice(input).unwrap();
+///     let errors = jtd::validate(&schema, &instance, Default::default()).unwrap();
+///     assert_eq!(i % 2 == 1, errors.len() == 1);
+/// }
+/// ```
+pub fn seed_corpus(schema: &Schema, n: usize, seed: u64) -> Vec<Vec<u8>> {
+    let mut generator = Generator {
+        root: schema,
+        rng: Rng::new(seed),
+    };
+
+    (0..n)
+        .map(|i| {
+            let mut instance = generator.instance(schema, None, 0);
+            if i % 2 == 1 {
+                generator.mutate(&mut instance);
+            }
+
+            // Values built up by the generator always serialize.
+            serde_json::to_vec(&instance).unwrap_or_default()
+        })
+        .collect()
+}
+
+struct Generator<'a> {
+    root: &'a Schema,
+    rng: Rng,
+}
+
+// A way to make an instance invalid, at the value with the given path.
+struct Mutation {
+    path: Vec<String>,
+    kind: MutationKind,
+}
+
+enum MutationKind {
+    Replace(Value),
+    Remove(String),
+    Insert(String, Value),
+}
+
+impl<'a> Generator<'a> {
+    fn instance(&mut self, schema: &'a Schema, parent_tag: Option<&str>, depth: usize) -> Value {
+        let shallow = depth >= MAX_DEPTH;
+        if schema.nullable() && (shallow || self.rng.below(4) == 0) {
+            return Value::Null;
+        }
+
+        match schema {
+            Schema::Empty { .. } => self
+                .rng
+                .choose(&[
+                    Value::Null,
+                    json!(true),
+                    json!(0),
+                    json!(-1.5),
+                    json!("a"),
+                    json!([]),
+                    json!({}),
+                ])
+                .clone(),
+            Schema::Ref { ref_, .. } => match self.root.definitions().get(ref_) {
+                Some(definition) => self.instance(definition, None, depth + 1),
+                None => Value::Null,
+            },
+            Schema::Type { type_, .. } => match type_ {
+                Type::Boolean => json!(self.rng.below(2) == 0),
+                Type::Int8 => self.int(-128, 127),
+                Type::Uint8 => self.int(0, 255),
+                Type::Int16 => self.int(-32768, 32767),
+                Type::Uint16 => self.int(0, 65535),
+                Type::Int32 => self.int(-2147483648, 2147483647),
+                Type::Uint32 => self.int(0, 4294967295),
+                Type::Float32 | Type::Float64 => self
+                    .rng
+                    .choose(&[
+                        json!(0),
+                        json!(-1.5),
+                        json!(3.25),
+                        json!(1e30),
+                        json!(-1e-30),
+                    ])
+                    .clone(),
+                Type::String => json!(self.string()),
+                Type::Timestamp => json!(self.rng.choose(&[
+                    "1970-01-01T00:00:00Z",
+                    "1985-04-12T23:20:50.52Z",
+                    "1996-12-19T16:39:57-08:00",
+                    "2016-12-31T23:59:60Z",
+                    "9999-12-31T23:59:59.999999999+14:00",
+                ])),
+                // There's no telling what a custom type accepts, but they're
+                // most often strings with some format.
+                #[cfg(feature = "extensions")]
+                Type::Custom(_) => json!(self.string()),
+            },
+            Schema::Enum { enum_, .. } => {
+                let values: Vec<_> = enum_.iter().collect();
+                if values.is_empty() {
+                    Value::Null
+                } else {
+                    json!(self.rng.choose(&values))
+                }
+            }
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { enum_, .. } => {
+                let values: Vec<_> = enum_.iter().collect();
+                if values.is_empty() {
+                    Value::Null
+                } else {
+                    json!(self.rng.choose(&values))
+                }
+            }
+            Schema::Elements { elements, .. } => {
+                let len = if shallow { 0 } else { self.rng.below(MAX_LEN) };
+                (0..len)
+                    .map(|_| self.instance(elements, None, depth + 1))
+                    .collect()
+            }
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            } => {
+                let mut object = Map::new();
+                for (name, schema) in properties {
+                    object.insert(name.clone(), self.instance(schema, None, depth + 1));
+                }
+
+                for (name, schema) in optional_properties {
+                    if !shallow && self.rng.below(2) == 0 {
+                        object.insert(name.clone(), self.instance(schema, None, depth + 1));
+                    }
+                }
+
+                if let Some(tag) = parent_tag {
+                    object.remove(tag);
+                }
+
+                Value::Object(object)
+            }
+            Schema::Values { values, .. } => {
+                let len = if shallow { 0 } else { self.rng.below(MAX_LEN) };
+                let mut object = Map::new();
+                for _ in 0..len {
+                    // Keys constrained by the keys extension are left out.
+                    #[cfg(feature = "extensions")]
+                    if let Schema::Values { keys: Some(_), .. } = schema {
+                        break;
+                    }
+
+                    let name = self.string();
+                    object.insert(name, self.instance(values, None, depth + 1));
+                }
+
+                Value::Object(object)
+            }
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } => {
+                let tags: Vec<_> = mapping.keys().collect();
+                if tags.is_empty() {
+                    return Value::Null;
+                }
+
+                let tag = self.rng.choose(&tags).to_string();
+                let mut object = match self.instance(&mapping[&tag], Some(discriminator), depth + 1)
+                {
+                    Value::Object(object) => object,
+                    _ => Map::new(),
+                };
+
+                object.insert(discriminator.clone(), json!(tag));
+                Value::Object(object)
+            }
+        }
+    }
+
+    // Makes a valid instance invalid, in exactly one way, if possible.
+    fn mutate(&mut self, instance: &mut Value) {
+        let mut mutations = Vec::new();
+        collect_mutations(
+            self.root,
+            self.root,
+            None,
+            instance,
+            &mut vec![],
+            &mut mutations,
+        );
+
+        // Try mutations in a random order, keeping the first that results in
+        // exactly one validation error. Some don't, such as replacing a value
+        // in a way another part of the schema makes up for.
+        while !mutations.is_empty() {
+            let i = self.rng.below(mutations.len() as u64) as usize;
+            let mutation = mutations.swap_remove(i);
+
+            let mut mutated = instance.clone();
+            apply(&mut mutated, mutation);
+
+            let options = ValidateOptions::new().with_max_depth(64);
+            if let Ok(errors) = validate(self.root, &mutated, options) {
+                if errors.len() == 1 {
+                    *instance = mutated;
+                    return;
+                }
+            }
+        }
+    }
+
+    fn int(&mut self, min: i64, max: i64) -> Value {
+        match self.rng.below(4) {
+            0 => json!(min),
+            1 => json!(max),
+            2 => json!(0.max(min)),
+            _ => json!(min + self.rng.below((max - min + 1) as u64) as i64),
+        }
+    }
+
+    fn string(&mut self) -> String {
+        self.rng.choose(&STRINGS).to_string()
+    }
+}
+
+fn collect_mutations(
+    root: &Schema,
+    schema: &Schema,
+    parent_tag: Option<&str>,
+    instance: &Value,
+    path: &mut Vec<String>,
+    mutations: &mut Vec<Mutation>,
+) {
+    if let Schema::Ref { ref_, .. } = schema {
+        if let Some(definition) = root.definitions().get(ref_) {
+            if path.len() < MAX_DEPTH * 4 {
+                collect_mutations(root, definition, None, instance, path, mutations);
+            }
+        }
+
+        return;
+    }
+
+    if let Schema::Empty { .. } = schema {
+        return;
+    }
+
+    let mut push = |kind| {
+        mutations.push(Mutation {
+            path: path.clone(),
+            kind,
+        })
+    };
+
+    // Every form other than the empty form rejects a value of the wrong type.
+    push(MutationKind::Replace(match instance {
+        Value::String(_) => json!(true),
+        _ => json!("not the expected type"),
+    }));
+
+    match (schema, instance) {
+        (Schema::Type { type_, .. }, _) => match type_ {
+            Type::Int8 => push(MutationKind::Replace(json!(128))),
+            Type::Uint8 | Type::Uint16 | Type::Uint32 => {
+                push(MutationKind::Replace(json!(-1)));
+                push(MutationKind::Replace(json!(0.5)));
+            }
+            Type::Int16 => push(MutationKind::Replace(json!(-32769))),
+            Type::Int32 => push(MutationKind::Replace(json!(2147483648i64))),
+            Type::Timestamp => push(MutationKind::Replace(json!("2020-13-45T25:61:61Z"))),
+            _ => {}
+        },
+        (Schema::Enum { enum_, .. }, _) => {
+            let mut value = "unknown".to_owned();
+            while enum_.contains(&value) {
+                value.push('_');
+            }
+
+            push(MutationKind::Replace(json!(value)));
+        }
+        (Schema::Elements { elements, .. }, Value::Array(array)) => {
+            for (i, element) in array.iter().enumerate() {
+                path.push(i.to_string());
+                collect_mutations(root, elements, None, element, path, mutations);
+                path.pop();
+            }
+        }
+        (
+            Schema::Properties {
+                properties,
+                optional_properties,
+                additional_properties,
+                ..
+            },
+            Value::Object(object),
+        ) => {
+            for name in properties.keys() {
+                push(MutationKind::Remove(name.clone()));
+            }
+
+            if !additional_properties {
+                let mut name = "unexpected".to_owned();
+                while properties.contains_key(&name)
+                    || optional_properties.contains_key(&name)
+                    || Some(&name[..]) == parent_tag
+                {
+                    name.push('_');
+                }
+
+                push(MutationKind::Insert(name, json!(null)));
+            }
+
+            for (name, value) in object {
+                let schema = properties
+                    .get(name)
+                    .or_else(|| optional_properties.get(name));
+                if let Some(schema) = schema {
+                    path.push(name.clone());
+                    collect_mutations(root, schema, None, value, path, mutations);
+                    path.pop();
+                }
+            }
+        }
+        (Schema::Values { values, .. }, Value::Object(object)) => {
+            for (name, value) in object {
+                path.push(name.clone());
+                collect_mutations(root, values, None, value, path, mutations);
+                path.pop();
+            }
+        }
+        (
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            },
+            Value::Object(object),
+        ) => {
+            push(MutationKind::Remove(discriminator.clone()));
+
+            let mut tag = "unknown".to_owned();
+            while mapping.contains_key(&tag) {
+                tag.push('_');
+            }
+
+            push(MutationKind::Insert(discriminator.clone(), json!(tag)));
+
+            let schema = object
+                .get(discriminator)
+                .and_then(Value::as_str)
+                .and_then(|tag| mapping.get(tag));
+
+            if let Some(schema) = schema {
+                collect_mutations(root, schema, Some(discriminator), instance, path, mutations);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn apply(instance: &mut Value, mutation: Mutation) {
+    let mut target = Some(instance);
+    for token in &mutation.path {
+        target = match target {
+            Some(Value::Array(array)) => match token.parse::<usize>() {
+                Ok(i) => array.get_mut(i),
+                Err(_) => None,
+            },
+            Some(Value::Object(object)) => object.get_mut(token),
+            _ => None,
+        };
+    }
+
+    match (target, mutation.kind) {
+        (Some(target), MutationKind::Replace(value)) => *target = value,
+        (Some(Value::Object(object)), MutationKind::Remove(name)) => {
+            object.remove(&name);
+        }
+        (Some(Value::Object(object)), MutationKind::Insert(name, value)) => {
+            object.insert(name, value);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Schema;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn seed_corpus() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "node": {
+                        "properties": {
+                            "children": { "elements": { "ref": "node" }}
+                        },
+                        "optionalProperties": {
+                            "at": { "type": "timestamp", "nullable": true }
+                        }
+                    }
+                },
+                "discriminator": "kind",
+                "mapping": {
+                    "tree": { "properties": { "root": { "ref": "node" }}},
+                    "list": { "properties": { "items": { "values": { "type": "int8" }}}}
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let corpus = super::seed_corpus(&schema, 200, 7);
+        assert_ne!(corpus, super::seed_corpus(&schema, 200, 8));
+
+        for (i, input) in corpus.iter().enumerate() {
+            let instance: Value = serde_json::from_slice(input).unwrap();
+            let errors = crate::validate(&schema, &instance, Default::default()).unwrap();
+            assert_eq!(i % 2, errors.len(), "{}", instance);
+        }
+
+        // Nothing can be wrong with an instance of the empty form.
+        let empty = Schema::from_serde_schema(serde_json::from_value(json!({})).unwrap()).unwrap();
+        assert_eq!(3, super::seed_corpus(&empty, 3, 0).len());
+    }
+}
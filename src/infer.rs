@@ -0,0 +1,1542 @@
+//! Infer and evolve schemas from example data.
+//!
+//! The main entry points of this module are [`Widener`], which takes an
+//! existing schema and widens it just enough to accept newly observed
+//! instances, and [`infer()`], which infers a schema from a batch of examples
+//! and recognizes tagged unions among them. [`infer_with_options`] takes hints
+//! about where to infer enums, dictionaries, and tagged unions, like the
+//! `jtd-infer` command-line tool does.
+//! [`suggest_properties`] instead uses examples to find places where an
+//! existing schema could be tightened.
+
+use crate::{Definitions, Schema, Type};
+use jtd_core::__private::{is_rfc3339, resolve_ref_with};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Widens a schema so that it accepts observed instances.
+///
+/// A `Widener` starts from an existing schema (see [`Widener::new`]) or from
+/// nothing at all (see [`Widener::default`]). Each call to
+/// [`observe`][`Widener::observe`] makes the smallest change to the schema
+/// that makes it accept the observed instance:
+///
+/// * Properties that were required but are missing become optional, and
+///   properties that weren't in the schema before are added as optional
+///   properties.
+/// * Integer types are widened to the smallest type that holds both the old
+///   range and the new value. Non-integers turn integer types into `float64`.
+/// * Strings that aren't in an enum are added to the enum.
+/// * Timestamps that turn out not to be RFC3339 strings become `string`.
+/// * `null` makes a schema nullable. Until something other than `null` is
+///   observed for a part of the schema, that part is the empty form; the first
+///   value that isn't `null` replaces it with a nullable schema for that value.
+/// * Discriminator tags that weren't in `mapping` are added as new variants.
+///
+/// If no such change exists -- for instance, if a string is observed where the
+/// schema expects an array -- then that part of the schema is widened to the
+/// empty form, which accepts anything. Metadata is preserved throughout.
+///
+/// ```
+/// use jtd::{Schema, infer::Widener};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "name": { "type": "string" },
+///         "age": { "type": "uint8" },
+///         "status": { "enum": ["ACTIVE"] }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let mut widener = Widener::new(schema);
+/// widener.observe(&json!({ "name": "John", "age": 300, "status": "BANNED" }));
+/// widener.observe(&json!({ "age": 43, "status": "ACTIVE", "email": "j@example.com" }));
+///
+/// assert_eq!(
+///     Schema::from_serde_schema(serde_json::from_value(json!({
+///         "properties": {
+///             "age": { "type": "uint16" },
+///             "status": { "enum": ["ACTIVE", "BANNED"] }
+///         },
+///         "optionalProperties": {
+///             "name": { "type": "string" },
+///             "email": { "type": "string" }
+///         }
+///     })).unwrap()).unwrap(),
+///     widener.into_schema(),
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Widener {
+    schema: Option<Schema>,
+
+    // Paths, as constructed by WidenState, to the parts of the schema that are
+    // the empty form only because nothing but null has been observed there.
+    nulls: BTreeSet<Vec<String>>,
+}
+
+impl Widener {
+    /// Constructs a widener starting from an existing schema.
+    ///
+    /// Refs in `schema` are followed, and widening an instance through a ref
+    /// widens the definition being referred to. It's therefore important that
+    /// `schema` be a root schema that passes [`Schema::validate`].
+    pub fn new(schema: Schema) -> Self {
+        Self {
+            schema: Some(schema),
+            nulls: BTreeSet::new(),
+        }
+    }
+
+    /// Widens the schema, if necessary, so that it accepts `instance`.
+    pub fn observe(&mut self, instance: &Value) {
+        let mut state = WidenState {
+            definitions: Default::default(),
+            in_progress: BTreeSet::new(),
+            deferred: Vec::new(),
+            done: BTreeSet::new(),
+            nulls: &mut self.nulls,
+            path: Vec::new(),
+        };
+
+        match self.schema {
+            None => self.schema = Some(state.infer_fresh(instance)),
+            Some(ref mut schema) => {
+                state.definitions = std::mem::take(schema.definitions_mut());
+
+                state.widen(schema, None, instance);
+                state.widen_deferred();
+
+                *schema.definitions_mut() = state.definitions;
+            }
+        }
+    }
+
+    /// Returns the schema as widened so far.
+    ///
+    /// If the widener was constructed with [`Widener::default`] and never
+    /// observed any instances, this returns `None`.
+    pub fn schema(&self) -> Option<&Schema> {
+        self.schema.as_ref()
+    }
+
+    /// Consumes the widener, returning the widened schema.
+    ///
+    /// If the widener was constructed with [`Widener::default`] and never
+    /// observed any instances, this returns the empty form.
+    pub fn into_schema(self) -> Schema {
+        self.schema.unwrap_or_else(empty)
+    }
+}
+
+/// Infers a schema from a batch of instances, detecting tagged unions.
+///
+/// Where a [`Widener`] looks at instances one at a time, this function looks at
+/// all of them together. That lets it notice when a set of objects is really a
+/// tagged union: when they share a string property whose value determines what
+/// other properties the objects have. Such objects are inferred as a
+/// discriminator-form schema, with one variant per tag, instead of being
+/// collapsed into a single properties-form schema where every property is
+/// optional.
+///
+/// A property is considered a discriminator when:
+///
+/// * It is present in every object, and is always a string.
+/// * Objects with different values for it have different sets of properties,
+///   ignoring properties that only some objects with the same value have.
+/// * At least one of its values appears more than once. This keeps
+///   identifier-like properties, whose values are all unique, from being
+///   mistaken for discriminators.
+///
+/// If several properties qualify, the one whose variants are most consistent
+/// in shape is chosen, with ties going to the property with the fewest
+/// distinct values and then to the alphabetically first property. Detection
+/// happens at every level of the instances, not just the top.
+///
+/// Everything else follows the rules of the `jtd-infer` command-line tool:
+///
+/// * Types are kept narrow: `uint8` for small non-negative integers,
+///   `timestamp` for strings that are all RFC 3339 timestamps, and so on.
+/// * Properties that every object has are required, and the rest are
+///   optional.
+/// * `null` makes a schema nullable, and values of different kinds at the
+///   same place give the empty form.
+///
+/// Strings are only inferred as enums, and objects as dictionaries, where
+/// [`infer_with_options`] is told to.
+///
+/// This function is also available as `jtd::infer`.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let instances = vec![
+///     json!({ "name": "John", "age": 43, "joined": "2021-01-01T00:00:00Z" }),
+///     json!({ "name": "Jane", "age": 7, "email": null }),
+/// ];
+///
+/// assert_eq!(
+///     Schema::from_serde_schema(serde_json::from_value(json!({
+///         "properties": {
+///             "name": { "type": "string" },
+///             "age": { "type": "uint8" }
+///         },
+///         "optionalProperties": {
+///             "email": {},
+///             "joined": { "type": "timestamp" }
+///         }
+///     })).unwrap()).unwrap(),
+///     jtd::infer(&instances),
+/// );
+///
+/// let instances = vec![
+///     json!({ "type": "circle", "radius": 1.5 }),
+///     json!({ "type": "square", "side": 2 }),
+///     json!({ "type": "circle", "radius": 3 }),
+/// ];
+///
+/// assert_eq!(
+///     Schema::from_serde_schema(serde_json::from_value(json!({
+///         "discriminator": "type",
+///         "mapping": {
+///             "circle": { "properties": { "radius": { "type": "float64" }}},
+///             "square": { "properties": { "side": { "type": "uint8" }}}
+///         }
+///     })).unwrap()).unwrap(),
+///     jtd::infer(&instances),
+/// );
+/// ```
+pub fn infer<'a, I>(instances: I) -> Schema
+where
+    I: IntoIterator<Item = &'a Value>,
+{
+    infer_with_options(instances, InferOptions::new())
+}
+
+/// Options you can pass to [`infer_with_options`].
+///
+/// Each hint is a JSON Pointer into the instances, in which `-` stands for
+/// any element of an array, or any value of an object, as in the
+/// `--enum-hint`, `--values-hint`, and `--discriminator-hint` options of
+/// `jtd-infer`. A hint that doesn't apply to what's found where it points is
+/// ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InferOptions {
+    enum_hints: Vec<Vec<String>>,
+    values_hints: Vec<Vec<String>>,
+    discriminator_hints: Vec<Vec<String>>,
+}
+
+impl InferOptions {
+    /// Constructs a new set of options with all default values.
+    ///
+    /// Equivalent to [`Default::default()`] or calling `with_*` methods with
+    /// default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Infers the strings at `pointer` as an enum of the values found there.
+    ///
+    /// By default, strings are inferred as the `string` or `timestamp` type.
+    pub fn with_enum_hint(mut self, pointer: &str) -> Self {
+        self.enum_hints.push(parse_pointer(pointer));
+        self
+    }
+
+    /// Infers the objects at `pointer` as the values form, rather than the
+    /// properties form.
+    ///
+    /// This suits objects used as dictionaries, whose keys are data rather
+    /// than a fixed set of names.
+    pub fn with_values_hint(mut self, pointer: &str) -> Self {
+        self.values_hints.push(parse_pointer(pointer));
+        self
+    }
+
+    /// Infers the objects containing `pointer` as a tagged union, with the
+    /// string at `pointer` as their discriminator.
+    ///
+    /// For instance, a hint of `/-/type` infers the elements of an array as a
+    /// discriminator-form schema with a discriminator of `type`. Objects are
+    /// only inferred this way if each has a string at `pointer`. Tagged unions
+    /// are detected without hints too, but a hint decides which property is
+    /// the discriminator, where detection might pick another, or none.
+    pub fn with_discriminator_hint(mut self, pointer: &str) -> Self {
+        self.discriminator_hints.push(parse_pointer(pointer));
+        self
+    }
+}
+
+/// Infers a schema from a batch of instances, following hints.
+///
+/// This is [`infer()`], except that [`InferOptions`] can say where to infer
+/// enums, dictionaries, and tagged unions.
+///
+/// ```
+/// use jtd::infer::InferOptions;
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let instances = vec![
+///     json!({ "status": "active", "scores": { "math": 90 }}),
+///     json!({ "status": "banned", "scores": { "art": 75, "math": 80 }}),
+/// ];
+///
+/// let options = InferOptions::new()
+///     .with_enum_hint("/status")
+///     .with_values_hint("/scores");
+///
+/// assert_eq!(
+///     Schema::from_serde_schema(serde_json::from_value(json!({
+///         "properties": {
+///             "status": { "enum": ["active", "banned"] },
+///             "scores": { "values": { "type": "uint8" }}
+///         }
+///     })).unwrap()).unwrap(),
+///     jtd::infer::infer_with_options(&instances, options),
+/// );
+/// ```
+pub fn infer_with_options<'a, I>(instances: I, options: InferOptions) -> Schema
+where
+    I: IntoIterator<Item = &'a Value>,
+{
+    let instances: Vec<_> = instances.into_iter().collect();
+    infer_batch(&instances, None, &options, &mut Vec::new())
+}
+
+fn parse_pointer(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+fn is_hinted(hints: &[Vec<String>], path: &[Option<&str>]) -> bool {
+    hints.iter().any(|hint| points_at(hint, path))
+}
+
+// Whether a hint points at the given path. In a path, None is an element of
+// an array, or a value of a dictionary, which only "-" matches.
+fn points_at(hint: &[String], path: &[Option<&str>]) -> bool {
+    hint.len() == path.len()
+        && hint
+            .iter()
+            .zip(path)
+            .all(|(hint, token)| hint == "-" || Some(hint.as_str()) == *token)
+}
+
+// The discriminator hinted for the objects at path, if every object has a
+// string there.
+fn hinted_discriminator(
+    options: &InferOptions,
+    path: &[Option<&str>],
+    instances: &[&Value],
+) -> Option<String> {
+    options.discriminator_hints.iter().find_map(|hint| {
+        let (name, parent) = hint.split_last()?;
+        let is_tag = |instance: &&Value| instance.get(name).is_some_and(Value::is_string);
+        if points_at(parent, path) && instances.iter().all(is_tag) {
+            Some(name.clone())
+        } else {
+            None
+        }
+    })
+}
+
+fn infer_batch<'a>(
+    instances: &[&'a Value],
+    parent_tag: Option<&str>,
+    options: &InferOptions,
+    path: &mut Vec<Option<&'a str>>,
+) -> Schema {
+    let non_null: Vec<&Value> = instances.iter().copied().filter(|v| !v.is_null()).collect();
+
+    let mut schema = if non_null.is_empty() {
+        empty()
+    } else if non_null.iter().all(|v| v.is_string()) && is_hinted(&options.enum_hints, path) {
+        Schema::Enum {
+            definitions: Default::default(),
+            metadata: Default::default(),
+            nullable: false,
+            enum_: non_null
+                .iter()
+                .map(|v| v.as_str().unwrap().to_owned())
+                .collect(),
+        }
+    } else if non_null.iter().all(|v| v.is_object()) && is_hinted(&options.values_hints, path) {
+        let values: Vec<&Value> = non_null
+            .iter()
+            .flat_map(|v| v.as_object().unwrap().values())
+            .collect();
+
+        path.push(None);
+        let values = infer_batch(&values, None, options, path);
+        path.pop();
+
+        Schema::Values {
+            definitions: Default::default(),
+            metadata: Default::default(),
+            nullable: false,
+            values: Box::new(values),
+            #[cfg(feature = "extensions")]
+            keys: None,
+        }
+    } else if non_null.iter().all(|v| v.is_object()) {
+        let discriminator = hinted_discriminator(options, path, &non_null)
+            .or_else(|| find_discriminator(&non_null, parent_tag));
+
+        match discriminator {
+            Some(discriminator) => {
+                let mut groups: BTreeMap<&str, Vec<&Value>> = BTreeMap::new();
+                for instance in &non_null {
+                    let tag = instance[&discriminator[..]].as_str().unwrap();
+                    groups.entry(tag).or_default().push(instance);
+                }
+
+                let mapping = groups
+                    .into_iter()
+                    .map(|(tag, group)| {
+                        (
+                            tag.to_owned(),
+                            infer_batch_properties(&group, Some(&discriminator), options, path),
+                        )
+                    })
+                    .collect();
+
+                Schema::Discriminator {
+                    definitions: Default::default(),
+                    metadata: Default::default(),
+                    nullable: false,
+                    discriminator,
+                    mapping,
+                }
+            }
+            None => infer_batch_properties(&non_null, parent_tag, options, path),
+        }
+    } else if non_null.iter().all(|v| v.is_array()) {
+        let elements: Vec<&Value> = non_null
+            .iter()
+            .flat_map(|v| v.as_array().unwrap())
+            .collect();
+
+        path.push(None);
+        let elements = infer_batch(&elements, None, options, path);
+        path.pop();
+
+        Schema::Elements {
+            definitions: Default::default(),
+            metadata: Default::default(),
+            nullable: false,
+            elements: Box::new(elements),
+        }
+    } else {
+        let mut widener = Widener::default();
+        for instance in &non_null {
+            widener.observe(instance);
+        }
+
+        widener.into_schema()
+    };
+
+    if non_null.len() != instances.len() {
+        set_nullable(&mut schema);
+    }
+
+    schema
+}
+
+// Infers a properties-form schema from a set of objects. Properties present in
+// every object are required; the rest are optional.
+fn infer_batch_properties<'a>(
+    instances: &[&'a Value],
+    parent_tag: Option<&str>,
+    options: &InferOptions,
+    path: &mut Vec<Option<&'a str>>,
+) -> Schema {
+    let mut values: BTreeMap<&str, Vec<&Value>> = BTreeMap::new();
+    for instance in instances {
+        for (name, value) in instance.as_object().unwrap() {
+            if parent_tag != Some(name) {
+                values.entry(name).or_default().push(value);
+            }
+        }
+    }
+
+    let mut properties = BTreeMap::new();
+    let mut optional_properties = BTreeMap::new();
+    for (name, values) in values {
+        path.push(Some(name));
+        let sub_schema = infer_batch(&values, None, options, path);
+        path.pop();
+
+        if values.len() == instances.len() {
+            properties.insert(name.to_owned(), sub_schema);
+        } else {
+            optional_properties.insert(name.to_owned(), sub_schema);
+        }
+    }
+
+    Schema::Properties {
+        definitions: Default::default(),
+        metadata: Default::default(),
+        nullable: false,
+        properties_is_present: !properties.is_empty() || optional_properties.is_empty(),
+        properties,
+        optional_properties,
+        additional_properties: false,
+    }
+}
+
+fn find_discriminator(instances: &[&Value], parent_tag: Option<&str>) -> Option<String> {
+    let first = instances.first()?.as_object()?;
+
+    let mut best: Option<((usize, usize), &str)> = None;
+    for candidate in first.keys() {
+        if parent_tag == Some(candidate) {
+            continue;
+        }
+
+        // Group the sets of property names by the value of the candidate.
+        let mut groups: BTreeMap<&str, Vec<BTreeSet<&str>>> = BTreeMap::new();
+        for instance in instances {
+            let obj = instance.as_object().unwrap();
+            let tag = match obj.get(candidate).and_then(Value::as_str) {
+                Some(tag) => tag,
+                None => break,
+            };
+
+            let keys = obj
+                .keys()
+                .map(|k| &k[..])
+                .filter(|k| k != candidate && Some(*k) != parent_tag)
+                .collect();
+
+            groups.entry(tag).or_default().push(keys);
+        }
+
+        let group_count = groups.len();
+        let member_count: usize = groups.values().map(Vec::len).sum();
+        if member_count != instances.len() || group_count < 2 || group_count == member_count {
+            continue;
+        }
+
+        // A group's shape is the set of properties shared by all its members.
+        // Its inconsistency is how many properties only some members have.
+        let mut shapes = BTreeSet::new();
+        let mut inconsistency = 0;
+        for members in groups.values() {
+            let union: BTreeSet<&str> = members.iter().flatten().copied().collect();
+            let shared: BTreeSet<&str> = union
+                .iter()
+                .copied()
+                .filter(|k| members.iter().all(|m| m.contains(k)))
+                .collect();
+
+            inconsistency += union.len() - shared.len();
+            shapes.insert(shared);
+        }
+
+        if shapes.len() < 2 {
+            continue;
+        }
+
+        let score = (inconsistency, group_count);
+        best = match best {
+            Some((best_score, _)) if best_score <= score => best,
+            _ => Some((score, candidate)),
+        };
+    }
+
+    best.map(|(_, name)| name.to_owned())
+}
+
+/// A proposal to replace a values-form schema with a properties-form one.
+///
+/// See [`suggest_properties`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertiesSuggestion {
+    /// The path to the values-form schema, from the root of the schema.
+    pub schema_path: Vec<String>,
+
+    /// The number of objects in the corpus that the values-form schema
+    /// applied to.
+    pub observed: usize,
+
+    /// The properties-form schema proposed in its place.
+    pub schema: Schema,
+}
+
+/// Finds values-form schemas that could be tightened into properties-form
+/// ones, based on a corpus of instances.
+///
+/// A values-form schema accepts objects with any keys at all. Often that's
+/// looser than necessary: the objects in practice always have the same small
+/// set of keys, and a properties-form schema would describe them better. This
+/// function validates each instance against `schema`, and for every
+/// values-form schema it encounters along the way, looks at the keys of the
+/// objects it applied to. A properties-form schema is proposed when:
+///
+/// * At least two objects were observed,
+/// * There are no more than `max_keys` distinct keys among them, and
+/// * The key set is stable: every key was already seen in the first half of
+///   the observed objects.
+///
+/// In the proposed schema, each key becomes a property whose schema is the
+/// values-form schema's `values`. Keys present in every observed object are
+/// required; the rest are optional. `nullable` and `metadata` carry over.
+///
+/// Suggestions are returned in order of their schema paths. Instances, or
+/// parts of instances, that don't match `schema` are skipped over.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "name": { "type": "string" },
+///         "scores": { "values": { "type": "uint8" }}
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let instances = vec![
+///     json!({ "name": "a", "scores": { "math": 1, "art": 2 }}),
+///     json!({ "name": "b", "scores": { "math": 3 }}),
+///     json!({ "name": "c", "scores": { "math": 5, "art": 6 }}),
+/// ];
+///
+/// let suggestions = jtd::infer::suggest_properties(&schema, &instances, 10);
+/// assert_eq!(1, suggestions.len());
+/// assert_eq!(vec!["properties", "scores"], suggestions[0].schema_path);
+/// assert_eq!(3, suggestions[0].observed);
+/// assert_eq!(
+///     json!({
+///         "properties": { "math": { "type": "uint8" }},
+///         "optionalProperties": { "art": { "type": "uint8" }}
+///     }),
+///     serde_json::to_value(suggestions[0].schema.clone().into_serde_schema()).unwrap(),
+/// );
+/// ```
+pub fn suggest_properties<'a, I>(
+    schema: &Schema,
+    instances: I,
+    max_keys: usize,
+) -> Vec<PropertiesSuggestion>
+where
+    I: IntoIterator<Item = &'a Value>,
+{
+    let mut observed = BTreeMap::new();
+    for instance in instances {
+        collect_values_objects(schema, schema, &mut Vec::new(), instance, &mut observed);
+    }
+
+    observed
+        .into_iter()
+        .filter_map(|(schema_path, (values_schema, objects))| {
+            suggest_for(values_schema, &objects, max_keys).map(|schema| PropertiesSuggestion {
+                schema_path,
+                observed: objects.len(),
+                schema,
+            })
+        })
+        .collect()
+}
+
+type ValuesObjects<'s, 'v> = BTreeMap<Vec<String>, (&'s Schema, Vec<&'v Map<String, Value>>)>;
+
+// Walks instance alongside schema, recording, for each values-form schema, the
+// objects it applies to.
+fn collect_values_objects<'s, 'v>(
+    root: &'s Schema,
+    schema: &'s Schema,
+    path: &mut Vec<String>,
+    instance: &'v Value,
+    out: &mut ValuesObjects<'s, 'v>,
+) {
+    // Refs are followed up front, and the path restarts at the definition
+    // they lead to.
+    if let Schema::Ref { .. } = schema {
+        let mut name = "";
+        let resolved = resolve_ref_with(schema, root, |schema| {
+            if let Schema::Ref { ref_, .. } = schema {
+                name = ref_;
+            }
+        });
+
+        if let Ok(schema) = resolved {
+            let mut path = vec!["definitions".to_owned(), name.to_owned()];
+            collect_values_objects(root, schema, &mut path, instance, out);
+        }
+
+        return;
+    }
+
+    match (schema, instance) {
+        (Schema::Elements { elements, .. }, Value::Array(arr)) => {
+            path.push("elements".to_owned());
+            for sub_instance in arr {
+                collect_values_objects(root, elements, path, sub_instance, out);
+            }
+            path.pop();
+        }
+        (
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            },
+            Value::Object(obj),
+        ) => {
+            for (name, sub_instance) in obj {
+                let (keyword, sub_schema) = match properties.get(name) {
+                    Some(sub_schema) => ("properties", sub_schema),
+                    None => match optional_properties.get(name) {
+                        Some(sub_schema) => ("optionalProperties", sub_schema),
+                        None => continue,
+                    },
+                };
+
+                path.push(keyword.to_owned());
+                path.push(name.clone());
+                collect_values_objects(root, sub_schema, path, sub_instance, out);
+                path.truncate(path.len() - 2);
+            }
+        }
+        (Schema::Values { values, .. }, Value::Object(obj)) => {
+            out.entry(path.clone())
+                .or_insert_with(|| (schema, Vec::new()))
+                .1
+                .push(obj);
+
+            path.push("values".to_owned());
+            for sub_instance in obj.values() {
+                collect_values_objects(root, values, path, sub_instance, out);
+            }
+            path.pop();
+        }
+        (
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            },
+            Value::Object(obj),
+        ) => {
+            let sub_schema = obj
+                .get(discriminator)
+                .and_then(Value::as_str)
+                .and_then(|tag| mapping.get_key_value(tag));
+
+            if let Some((tag, sub_schema)) = sub_schema {
+                path.push("mapping".to_owned());
+                path.push(tag.clone());
+                collect_values_objects(root, sub_schema, path, instance, out);
+                path.truncate(path.len() - 2);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn suggest_for(
+    values_schema: &Schema,
+    objects: &[&Map<String, Value>],
+    max_keys: usize,
+) -> Option<Schema> {
+    let (metadata, nullable, values) = match values_schema {
+        Schema::Values {
+            metadata,
+            nullable,
+            values,
+            ..
+        } => (metadata, *nullable, values),
+        _ => return None,
+    };
+
+    if objects.len() < 2 {
+        return None;
+    }
+
+    // Count how many objects have each key, noting whether any key first
+    // shows up in the second half of the objects.
+    let half = objects.len().div_ceil(2);
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for (i, obj) in objects.iter().enumerate() {
+        for key in obj.keys() {
+            let count = counts.entry(key).or_insert(0);
+            if *count == 0 && i >= half {
+                return None;
+            }
+
+            *count += 1;
+        }
+
+        if counts.len() > max_keys {
+            return None;
+        }
+    }
+
+    let mut properties = BTreeMap::new();
+    let mut optional_properties = BTreeMap::new();
+    for (key, count) in counts {
+        let sub_schema = values.as_ref().clone();
+        if count == objects.len() {
+            properties.insert(key.to_owned(), sub_schema);
+        } else {
+            optional_properties.insert(key.to_owned(), sub_schema);
+        }
+    }
+
+    Some(Schema::Properties {
+        definitions: Default::default(),
+        metadata: metadata.clone(),
+        nullable,
+        properties_is_present: !properties.is_empty() || optional_properties.is_empty(),
+        properties,
+        optional_properties,
+        additional_properties: false,
+    })
+}
+
+struct WidenState<'v, 'n> {
+    // The definitions of the root schema. These are held separately from the
+    // root schema while widening, so that refs can be widened in place.
+    definitions: Definitions,
+
+    // Names of definitions that are currently removed from definitions
+    // because they are being widened further up the stack.
+    in_progress: BTreeSet<String>,
+
+    // Instances that need to be widened into an in-progress definition, once
+    // that definition is available again.
+    deferred: Vec<(String, &'v Value)>,
+
+    // Deferred widenings that have already been done, identified by
+    // definition name and instance address. Without this, a definition like
+    // { "ref": "loop" } named "loop" would defer itself forever.
+    done: BTreeSet<(String, usize)>,
+
+    // The Widener's record of which empty forms have only seen nulls.
+    nulls: &'n mut BTreeSet<Vec<String>>,
+
+    // The path to the schema being widened. Required and optional properties
+    // share a path, so that a property becoming optional doesn't move it.
+    path: Vec<String>,
+}
+
+impl<'v> WidenState<'v, '_> {
+    fn widen(&mut self, schema: &mut Schema, parent_tag: Option<&str>, instance: &'v Value) {
+        if instance.is_null() {
+            set_nullable(schema);
+            return;
+        }
+
+        match schema {
+            Schema::Empty { .. } => {
+                if self.nulls.remove(&self.path) {
+                    *schema = self.infer_fresh(instance);
+                    set_nullable(schema);
+                }
+            }
+            Schema::Ref { ref_, .. } => {
+                if let Some(mut definition) = self.definitions.remove(ref_) {
+                    self.in_progress.insert(ref_.clone());
+                    self.widen_definition(&mut definition, ref_, instance);
+                    self.in_progress.remove(ref_);
+                    self.definitions.insert(ref_.clone(), definition);
+                } else if self.in_progress.contains(ref_) {
+                    self.deferred.push((ref_.clone(), instance));
+                }
+            }
+            Schema::Type { type_, .. } => {
+                if let Some(widened) = widen_type(type_, instance) {
+                    *type_ = widened;
+                } else {
+                    self.set_empty(schema);
+                }
+            }
+            Schema::Enum { enum_, .. } => {
+                if let Some(s) = instance.as_str() {
+                    if !enum_.contains(s) {
+                        enum_.insert(s.to_owned());
+                    }
+                } else {
+                    self.set_empty(schema);
+                }
+            }
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { enum_, .. } => {
+                if let Some(i) = instance.as_i64() {
+                    enum_.insert(i);
+                } else {
+                    self.set_empty(schema);
+                }
+            }
+            Schema::Elements { elements, .. } => {
+                if let Some(arr) = instance.as_array() {
+                    self.path.push("elements".to_owned());
+                    for sub_instance in arr {
+                        self.widen(elements, None, sub_instance);
+                    }
+                    self.path.pop();
+                } else {
+                    self.set_empty(schema);
+                }
+            }
+            Schema::Properties {
+                properties,
+                optional_properties,
+                additional_properties,
+                ..
+            } => {
+                if let Some(obj) = instance.as_object() {
+                    let missing: Vec<_> = properties
+                        .keys()
+                        .filter(|name| !obj.contains_key(*name))
+                        .cloned()
+                        .collect();
+
+                    for name in missing {
+                        let sub_schema = properties.remove(&name).unwrap();
+                        optional_properties.insert(name, sub_schema);
+                    }
+
+                    for (name, sub_instance) in obj {
+                        if parent_tag == Some(name) {
+                            continue;
+                        }
+
+                        self.path.push("properties".to_owned());
+                        self.path.push(name.clone());
+                        if let Some(sub_schema) = properties.get_mut(name) {
+                            self.widen(sub_schema, None, sub_instance);
+                        } else if let Some(sub_schema) = optional_properties.get_mut(name) {
+                            self.widen(sub_schema, None, sub_instance);
+                        } else if !*additional_properties {
+                            let sub_schema = self.infer_fresh(sub_instance);
+                            optional_properties.insert(name.clone(), sub_schema);
+                        }
+                        self.path.truncate(self.path.len() - 2);
+                    }
+                } else {
+                    self.set_empty(schema);
+                }
+            }
+            Schema::Values { values, .. } => {
+                if let Some(obj) = instance.as_object() {
+                    self.path.push("values".to_owned());
+                    for sub_instance in obj.values() {
+                        self.widen(values, None, sub_instance);
+                    }
+                    self.path.pop();
+                } else {
+                    self.set_empty(schema);
+                }
+            }
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } => {
+                let tag = instance
+                    .as_object()
+                    .and_then(|obj| obj.get(discriminator))
+                    .and_then(|tag| tag.as_str());
+
+                if let Some(tag) = tag {
+                    self.path.push("mapping".to_owned());
+                    self.path.push(tag.to_owned());
+                    if let Some(variant) = mapping.get_mut(tag) {
+                        self.widen(variant, Some(discriminator), instance);
+                    } else {
+                        let variant = self.infer_fresh_properties(instance, Some(discriminator));
+                        mapping.insert(tag.to_owned(), variant);
+                    }
+                    self.path.truncate(self.path.len() - 2);
+                } else {
+                    self.set_empty(schema);
+                }
+            }
+        }
+    }
+
+    fn widen_deferred(&mut self) {
+        while let Some((name, instance)) = self.deferred.pop() {
+            if !self
+                .done
+                .insert((name.clone(), instance as *const Value as usize))
+            {
+                continue;
+            }
+
+            if let Some(mut definition) = self.definitions.remove(&name) {
+                self.in_progress.insert(name.clone());
+                self.widen_definition(&mut definition, &name, instance);
+                self.in_progress.remove(&name);
+                self.definitions.insert(name, definition);
+            }
+        }
+    }
+
+    fn widen_definition(&mut self, definition: &mut Schema, name: &str, instance: &'v Value) {
+        let path = std::mem::replace(
+            &mut self.path,
+            vec!["definitions".to_owned(), name.to_owned()],
+        );
+        self.widen(definition, None, instance);
+        self.path = path;
+    }
+
+    fn set_empty(&mut self, schema: &mut Schema) {
+        // Nothing below an empty form gets widened, so there's no longer any
+        // need to track nulls there.
+        let path = &self.path;
+        self.nulls.retain(|p| !p.starts_with(path));
+
+        set_empty(schema);
+    }
+
+    // Constructs the narrowest schema that accepts instance. Where instance is
+    // null, this is the empty form, and its path is recorded in nulls.
+    fn infer_fresh(&mut self, instance: &'v Value) -> Schema {
+        match instance {
+            Value::Null => {
+                self.nulls.insert(self.path.clone());
+                empty()
+            }
+            Value::Bool(_) => infer_type(Type::Boolean),
+            Value::Number(n) => {
+                let val = n.as_f64().unwrap_or(f64::NAN);
+                if val.fract() == 0.0 {
+                    infer_type(smallest_int_type(val, val))
+                } else {
+                    infer_type(Type::Float64)
+                }
+            }
+            Value::String(s) => {
+                if is_rfc3339(s) {
+                    infer_type(Type::Timestamp)
+                } else {
+                    infer_type(Type::String)
+                }
+            }
+            Value::Array(arr) => {
+                // A fresh schema has no refs, so the elements are widened
+                // without needing any definitions.
+                self.path.push("elements".to_owned());
+                let mut elements: Option<Schema> = None;
+                for sub_instance in arr {
+                    match elements {
+                        None => elements = Some(self.infer_fresh(sub_instance)),
+                        Some(ref mut elements) => self.widen(elements, None, sub_instance),
+                    }
+                }
+                self.path.pop();
+
+                Schema::Elements {
+                    definitions: Default::default(),
+                    metadata: Default::default(),
+                    nullable: false,
+                    elements: Box::new(elements.unwrap_or_else(empty)),
+                }
+            }
+            Value::Object(_) => self.infer_fresh_properties(instance, None),
+        }
+    }
+
+    fn infer_fresh_properties(&mut self, instance: &'v Value, parent_tag: Option<&str>) -> Schema {
+        let mut properties = BTreeMap::new();
+        if let Some(obj) = instance.as_object() {
+            for (name, sub_instance) in obj {
+                if parent_tag != Some(name) {
+                    self.path.push("properties".to_owned());
+                    self.path.push(name.clone());
+                    properties.insert(name.clone(), self.infer_fresh(sub_instance));
+                    self.path.truncate(self.path.len() - 2);
+                }
+            }
+        }
+
+        Schema::Properties {
+            definitions: Default::default(),
+            metadata: Default::default(),
+            nullable: false,
+            properties,
+            optional_properties: Default::default(),
+            properties_is_present: true,
+            additional_properties: false,
+        }
+    }
+}
+
+// Integer types, in the order in which they're preferred when inferring or
+// widening.
+const INT_TYPES: [(Type, f64, f64); 6] = [
+    (Type::Uint8, 0.0, 255.0),
+    (Type::Int8, -128.0, 127.0),
+    (Type::Uint16, 0.0, 65535.0),
+    (Type::Int16, -32768.0, 32767.0),
+    (Type::Uint32, 0.0, 4294967295.0),
+    (Type::Int32, -2147483648.0, 2147483647.0),
+];
+
+fn int_range(type_: &Type) -> Option<(f64, f64)> {
+    INT_TYPES
+        .iter()
+        .find(|(t, _, _)| t == type_)
+        .map(|(_, min, max)| (*min, *max))
+}
+
+fn smallest_int_type(min: f64, max: f64) -> Type {
+    INT_TYPES
+        .iter()
+        .find(|(_, t_min, t_max)| *t_min <= min && max <= *t_max)
+        .map(|(t, _, _)| t.clone())
+        .unwrap_or(Type::Float64)
+}
+
+// Returns the type that accepts everything type_ does, plus instance, or None
+// if there is no such type.
+fn widen_type(type_: &Type, instance: &Value) -> Option<Type> {
+    match type_ {
+        Type::Boolean if instance.is_boolean() => Some(Type::Boolean),
+        Type::Float32 | Type::Float64 if instance.is_number() => Some(type_.clone()),
+        Type::String if instance.is_string() => Some(Type::String),
+        Type::Timestamp => match instance.as_str() {
+            Some(s) if is_rfc3339(s) => Some(Type::Timestamp),
+            Some(_) => Some(Type::String),
+            None => None,
+        },
+        _ => {
+            let (min, max) = int_range(type_)?;
+            let val = instance.as_f64()?;
+
+            if val.fract() != 0.0 {
+                Some(Type::Float64)
+            } else {
+                Some(smallest_int_type(min.min(val), max.max(val)))
+            }
+        }
+    }
+}
+
+fn empty() -> Schema {
+    Schema::Empty {
+        definitions: Default::default(),
+        metadata: Default::default(),
+    }
+}
+
+fn set_empty(schema: &mut Schema) {
+    let metadata = std::mem::take(schema.metadata_mut());
+    let definitions = std::mem::take(schema.definitions_mut());
+
+    *schema = Schema::Empty {
+        definitions,
+        metadata,
+    };
+}
+
+fn set_nullable(schema: &mut Schema) {
+    match schema {
+        Schema::Empty { .. } => {}
+        Schema::Ref { nullable, .. }
+        | Schema::Type { nullable, .. }
+        | Schema::Enum { nullable, .. }
+        | Schema::Elements { nullable, .. }
+        | Schema::Properties { nullable, .. }
+        | Schema::Values { nullable, .. }
+        | Schema::Discriminator { nullable, .. } => *nullable = true,
+        #[cfg(feature = "extensions")]
+        Schema::IntEnum { nullable, .. } => *nullable = true,
+    }
+}
+
+fn infer_type(type_: Type) -> Schema {
+    Schema::Type {
+        definitions: Default::default(),
+        metadata: Default::default(),
+        nullable: false,
+        type_,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{infer, infer_with_options, InferOptions, Widener};
+    use crate::Schema;
+    use serde_json::{json, Value};
+
+    fn widen(schema: Value, instances: Vec<Value>) -> Value {
+        let mut widener = Widener::new(
+            Schema::from_serde_schema(serde_json::from_value(schema).unwrap()).unwrap(),
+        );
+
+        for instance in &instances {
+            widener.observe(instance);
+        }
+
+        let schema = widener.into_schema();
+        schema.validate().unwrap();
+        for instance in &instances {
+            // The max depth here is only to cope with schemas that loop
+            // forever, which accept no instances whatsoever.
+            let options = crate::ValidateOptions::new().with_max_depth(32);
+            if let Ok(errors) = crate::validate(&schema, instance, options) {
+                assert!(errors.is_empty());
+            }
+        }
+
+        serde_json::to_value(schema.into_serde_schema()).unwrap()
+    }
+
+    #[test]
+    fn widen_types() {
+        assert_eq!(
+            json!({ "type": "int16" }),
+            widen(json!({ "type": "uint8" }), vec![json!(-1)])
+        );
+
+        assert_eq!(
+            json!({ "type": "float64" }),
+            widen(json!({ "type": "int32" }), vec![json!(1.5)])
+        );
+
+        assert_eq!(
+            json!({ "type": "string", "nullable": true }),
+            widen(
+                json!({ "type": "timestamp" }),
+                vec![json!("1985-04-12T23:20:50.52Z"), json!("foo"), json!(null)]
+            )
+        );
+
+        assert_eq!(
+            json!({ "metadata": { "foo": "bar" }}),
+            widen(
+                json!({ "type": "boolean", "metadata": { "foo": "bar" }}),
+                vec![json!("true")]
+            )
+        );
+    }
+
+    #[test]
+    fn widen_refs() {
+        assert_eq!(
+            json!({
+                "definitions": {
+                    "node": {
+                        "properties": {
+                            "value": { "type": "uint16" },
+                        },
+                        "optionalProperties": {
+                            "children": { "elements": { "ref": "node" }}
+                        }
+                    }
+                },
+                "ref": "node"
+            }),
+            widen(
+                json!({
+                    "definitions": {
+                        "node": {
+                            "properties": {
+                                "value": { "type": "uint8" },
+                                "children": { "elements": { "ref": "node" }}
+                            }
+                        }
+                    },
+                    "ref": "node"
+                }),
+                vec![json!({ "value": 1, "children": [{ "value": 1000 }]})]
+            )
+        );
+
+        assert_eq!(
+            json!({ "definitions": { "loop": { "ref": "loop" }}, "ref": "loop" }),
+            widen(
+                json!({ "definitions": { "loop": { "ref": "loop" }}, "ref": "loop" }),
+                vec![json!(1)]
+            )
+        );
+    }
+
+    #[test]
+    fn widen_discriminator() {
+        assert_eq!(
+            json!({
+                "discriminator": "type",
+                "mapping": {
+                    "a": { "properties": {}, "optionalProperties": { "x": { "type": "uint8" }}},
+                    "b": { "properties": { "y": { "type": "boolean" }}}
+                }
+            }),
+            widen(
+                json!({
+                    "discriminator": "type",
+                    "mapping": { "a": { "properties": {} }}
+                }),
+                vec![
+                    json!({ "type": "a", "x": 1 }),
+                    json!({ "type": "b", "y": true }),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn infer_nested_discriminators() {
+        let instances = vec![
+            json!({ "id": "1", "events": [{ "kind": "click", "x": 1 }, { "kind": "key", "code": "a" }] }),
+            json!({ "id": "2", "events": [{ "kind": "click", "x": 2 }], "note": null }),
+            json!({ "id": "3", "events": [] }),
+        ];
+
+        let expected = json!({
+            "properties": {
+                "id": { "type": "string" },
+                "events": {
+                    "elements": {
+                        "discriminator": "kind",
+                        "mapping": {
+                            "click": { "properties": { "x": { "type": "uint8" }}},
+                            "key": { "properties": { "code": { "type": "string" }}}
+                        }
+                    }
+                }
+            },
+            "optionalProperties": {
+                "note": {}
+            }
+        });
+
+        assert_eq!(
+            expected,
+            serde_json::to_value(infer(&instances).into_serde_schema()).unwrap()
+        );
+
+        // A hint picks the same discriminator that was detected.
+        let options = InferOptions::new().with_discriminator_hint("/events/-/kind");
+        assert_eq!(
+            expected,
+            serde_json::to_value(infer_with_options(&instances, options).into_serde_schema())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn infer_without_discriminators() {
+        // Every value of "id" is unique, and "kind" doesn't affect the shape of
+        // the objects, so neither are discriminators.
+        let instances = vec![
+            json!({ "id": "a", "kind": "x", "foo": 1 }),
+            json!({ "id": "b", "kind": "y", "foo": 1 }),
+            json!({ "id": "c", "kind": "x", "foo": 1, "bar": true }),
+        ];
+
+        assert_eq!(
+            json!({
+                "properties": {
+                    "id": { "type": "string" },
+                    "kind": { "type": "string" },
+                    "foo": { "type": "uint8" }
+                },
+                "optionalProperties": {
+                    "bar": { "type": "boolean" }
+                }
+            }),
+            serde_json::to_value(infer(&instances).into_serde_schema()).unwrap()
+        );
+    }
+
+    #[test]
+    fn infer_from_nothing() {
+        let mut widener = Widener::default();
+        assert_eq!(None, widener.schema());
+
+        widener.observe(&json!({ "a": [1, 2, 300], "b": "x" }));
+        widener.observe(&json!({ "a": [], "b": null }));
+
+        assert_eq!(
+            json!({
+                "properties": {
+                    "a": { "elements": { "type": "uint16" }},
+                    "b": { "type": "string", "nullable": true }
+                }
+            }),
+            serde_json::to_value(widener.into_schema().into_serde_schema()).unwrap()
+        );
+    }
+
+    #[test]
+    fn infer_from_nulls() {
+        fn infer(instances: Vec<Value>) -> Value {
+            let mut widener = Widener::default();
+            for instance in &instances {
+                widener.observe(instance);
+            }
+
+            serde_json::to_value(widener.into_schema().into_serde_schema()).unwrap()
+        }
+
+        assert_eq!(json!({}), infer(vec![json!(null), json!(null)]));
+
+        assert_eq!(
+            json!({ "type": "uint8", "nullable": true }),
+            infer(vec![json!(null), json!(5)])
+        );
+
+        assert_eq!(
+            json!({ "type": "uint8", "nullable": true }),
+            infer(vec![json!(5), json!(null)])
+        );
+
+        assert_eq!(
+            json!({
+                "properties": {
+                    "a": { "elements": { "type": "uint8", "nullable": true }}
+                }
+            }),
+            infer(vec![json!({ "a": [null, 1] })])
+        );
+
+        assert_eq!(
+            json!({
+                "properties": {
+                    "a": { "type": "string", "nullable": true },
+                    "b": { "elements": { "type": "uint8", "nullable": true }}
+                },
+                "optionalProperties": {
+                    "c": { "properties": { "x": { "type": "boolean", "nullable": true }}}
+                }
+            }),
+            infer(vec![
+                json!({ "a": null, "b": [null] }),
+                json!({ "a": "x", "b": [], "c": { "x": null }}),
+                json!({ "a": null, "b": [1], "c": { "x": true }}),
+            ])
+        );
+
+        // Once part of a schema is widened to the empty form because of a
+        // conflict, later values don't narrow it.
+        assert_eq!(
+            json!({ "elements": {} }),
+            infer(vec![json!([null]), json!([1, "x"]), json!([true])])
+        );
+    }
+
+    #[test]
+    fn infer_with_hints() {
+        let instances = vec![
+            json!({ "events": [{ "kind": "a", "id": "x1" }, { "kind": "b", "id": "x2" }]}),
+            json!({ "events": [{ "kind": "a", "id": "x3", "tags": { "k": "v" }}]}),
+            json!({ "events": [], "labels": { "/": null }}),
+        ];
+
+        let options = InferOptions::new()
+            .with_discriminator_hint("/events/-/kind")
+            .with_enum_hint("/events/-/tags/-")
+            .with_values_hint("/events/-/tags")
+            .with_values_hint("/labels~1")
+            .with_enum_hint("/labels/~1");
+
+        assert_eq!(
+            json!({
+                "properties": {
+                    "events": {
+                        "elements": {
+                            "discriminator": "kind",
+                            "mapping": {
+                                "a": {
+                                    "properties": { "id": { "type": "string" }},
+                                    "optionalProperties": {
+                                        "tags": { "values": { "enum": ["v"] }}
+                                    }
+                                },
+                                "b": { "properties": { "id": { "type": "string" }}}
+                            }
+                        }
+                    }
+                },
+                "optionalProperties": {
+                    "labels": { "properties": { "/": {} }}
+                }
+            }),
+            serde_json::to_value(infer_with_options(&instances, options).into_serde_schema())
+                .unwrap()
+        );
+
+        // Without hints, the ids keep the events from looking like a tagged
+        // union, and the tags are objects with a property "k".
+        assert_eq!(
+            json!({
+                "properties": {
+                    "id": { "type": "string" },
+                    "kind": { "type": "string" }
+                },
+                "optionalProperties": {
+                    "tags": { "properties": { "k": { "type": "string" }}}
+                }
+            }),
+            serde_json::to_value(infer(&instances).into_serde_schema()).unwrap()["properties"]
+                ["events"]["elements"]
+        );
+    }
+
+    #[test]
+    fn suggest_properties_through_refs() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "labels": { "values": { "type": "string" }, "nullable": true }
+                },
+                "discriminator": "kind",
+                "mapping": {
+                    "a": {
+                        "properties": {
+                            "labels": { "ref": "labels" },
+                            "tags": { "elements": { "values": {} }}
+                        }
+                    }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        // The keys of the tags grow throughout, so they aren't stable.
+        let instances: Vec<Value> = (0..4)
+            .map(|i| {
+                json!({
+                    "kind": "a",
+                    "labels": { "env": "prod", "team": "x" },
+                    "tags": [{ format!("t{}", i): 1 }]
+                })
+            })
+            .chain(vec![
+                json!({ "kind": "b" }),
+                json!({ "kind": "a", "labels": {}, "tags": [] }),
+            ])
+            .collect();
+
+        let suggestions = super::suggest_properties(&schema, &instances, 5);
+        assert_eq!(1, suggestions.len());
+        assert_eq!(vec!["definitions", "labels"], suggestions[0].schema_path);
+        assert_eq!(5, suggestions[0].observed);
+        assert_eq!(
+            json!({
+                "optionalProperties": {
+                    "env": { "type": "string" },
+                    "team": { "type": "string" }
+                },
+                "nullable": true
+            }),
+            serde_json::to_value(suggestions[0].schema.clone().into_serde_schema()).unwrap()
+        );
+
+        assert!(super::suggest_properties(&schema, &instances, 1).is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+//! Read JSON5.
+//!
+//! [JSON5](https://json5.org) extends JSON with features meant for files
+//! written by hand: comments, trailing commas, unquoted object keys,
+//! single-quoted strings, hexadecimal numbers, and more. It's a superset of
+//! the JSONC accepted by the `jsonc` module, so this module reads JSONC as
+//! well.
+//!
+//! [`from_str`] parses JSON5 into anything that implements
+//! [`Deserialize`][`serde::Deserialize`], such as a [`SerdeSchema`][`crate::SerdeSchema`]
+//! or a [`serde_json::Value`] to validate.
+//!
+//! This module is only available with the `json5` feature.
+//!
+//! ```
+//! use jtd::{Schema, SerdeSchema};
+//! use serde_json::json;
+//!
+//! let schema: SerdeSchema = jtd::json5::from_str("{
+//!     // Ports below 1024 are reserved.
+//!     type: 'uint16',
+//! }").unwrap();
+//!
+//! let schema = Schema::from_serde_schema(schema).unwrap();
+//! let instance: serde_json::Value = jtd::json5::from_str("0x1F90").unwrap();
+//! assert_eq!(json!(8080), instance);
+//! assert!(jtd::validate(&schema, &instance, Default::default()).unwrap().is_empty());
+//! ```
+
+use crate::config::Location;
+use serde::de::DeserializeOwned;
+use std::fmt;
+use thiserror::Error;
+
+/// An error from parsing JSON5.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub struct Json5Error {
+    /// A description of the error.
+    pub message: String,
+
+    /// Where in the input the error is, if known.
+    pub location: Option<Location>,
+}
+
+impl fmt::Display for Json5Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location {
+            Some(location) => write!(f, "{}: {}", location, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Deserializes an instance of `T` from JSON5.
+///
+/// ```
+/// let err = jtd::json5::from_str::<serde_json::Value>("{\n  a: [1, 2,, 3],\n}").unwrap_err();
+/// assert_eq!(Some(jtd::config::Location { line: 2, column: 12 }), err.location);
+/// ```
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T, Json5Error> {
+    json5::from_str(input).map_err(|err| match err {
+        json5::Error::Message { msg, location } => Json5Error {
+            message: msg,
+            location: location.map(|location| Location {
+                line: location.line,
+                column: location.column,
+            }),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Location;
+    use crate::{Schema, SerdeSchema};
+    use serde_json::json;
+
+    #[test]
+    fn from_str() {
+        let schema: SerdeSchema = super::from_str(
+            r#"{
+                /* Where the service listens. */
+                properties: {
+                    host: { type: "string" },
+                    'port': { type: 'uint16', },
+                },
+                additionalProperties: true,
+            }"#,
+        )
+        .unwrap();
+
+        let schema = Schema::from_serde_schema(schema).unwrap();
+        let instance: serde_json::Value =
+            super::from_str("{ host: 'localhost', port: +8080, debug: Infinity }").unwrap();
+
+        assert!(crate::validate(&schema, &instance, Default::default())
+            .unwrap()
+            .is_empty());
+
+        // JSON has no infinite numbers, so Infinity becomes null.
+        assert_eq!(json!(null), instance["debug"]);
+
+        // Errors from deserializing into SerdeSchema have locations too.
+        let err = super::from_str::<SerdeSchema>("{ nope: {} }").unwrap_err();
+        assert_eq!(Some(Location { line: 1, column: 3 }), err.location);
+    }
+}
@@ -0,0 +1,1245 @@
+//! Convert schemas to and from JSON Schema.
+//!
+//! Much tooling, such as OpenAPI, speaks JSON Schema rather than JSON Typedef.
+//! [`to_json_schema`] converts a schema into an equivalent [JSON Schema
+//! draft 2020-12](https://json-schema.org/draft/2020-12/json-schema-core)
+//! schema, so that definitions written once in JSON Typedef can be published
+//! there too. [`from_json_schema`] goes the other way, for the part of JSON
+//! Schema that JSON Typedef can express, to help move existing schemas over.
+//!
+//! ```
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "definitions": {
+//!         "name": { "type": "string" }
+//!     },
+//!     "properties": {
+//!         "name": { "ref": "name" },
+//!         "age": { "type": "uint8", "nullable": true }
+//!     }
+//! })).unwrap()).unwrap();
+//!
+//! assert_eq!(
+//!     json!({
+//!         "$schema": "https://json-schema.org/draft/2020-12/schema",
+//!         "$defs": {
+//!             "name": { "type": "string" }
+//!         },
+//!         "type": "object",
+//!         "required": ["age", "name"],
+//!         "properties": {
+//!             "age": { "type": ["integer", "null"], "minimum": 0, "maximum": 255 },
+//!             "name": { "$ref": "#/$defs/name" }
+//!         },
+//!         "additionalProperties": false
+//!     }),
+//!     jtd::json_schema::to_json_schema(&schema),
+//! );
+//! ```
+
+use crate::{Schema, SchemaPath, Type};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use thiserror::Error;
+
+/// The URI of the dialect of JSON Schema that [`to_json_schema`] produces.
+pub const DRAFT_2020_12: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Converts a schema into JSON Schema draft 2020-12.
+///
+/// | Schema                             | JSON Schema                                            |
+/// | ---------------------------------- | ------------------------------------------------------ |
+/// | `definitions`                      | `$defs`                                                |
+/// | empty                              | `{}`                                                   |
+/// | `ref`                              | `$ref` to `#/$defs/...`                                |
+/// | `boolean`                          | `"type": "boolean"`                                    |
+/// | `int8` to `uint32`                 | `"type": "integer"`, `minimum`, `maximum`              |
+/// | `float32`, `float64`               | `"type": "number"`                                     |
+/// | `string`                           | `"type": "string"`                                     |
+/// | `timestamp`                        | `"type": "string"`, `"format": "date-time"`            |
+/// | `enum`                             | `enum`                                                 |
+/// | `elements`                         | `"type": "array"`, `items`                             |
+/// | `properties`, `optionalProperties` | `"type": "object"`, `required`, `properties`, `additionalProperties` |
+/// | `values`                           | `"type": "object"`, `additionalProperties`             |
+/// | `discriminator`                    | `"type": "object"`, `oneOf` with a schema per tag, whose tag property is a `const` |
+///
+/// `nullable` adds `"null"` to the `type`, or `null` to the `enum`; a
+/// nullable `ref` or `discriminator` becomes an `anyOf` of `null` and the
+/// schema. A `description` in `metadata` is kept as the `description` of the
+/// JSON Schema.
+///
+/// JSON Schema doesn't check `format` by default, so timestamps are only
+/// checked to be strings, unless the validator is asked to check formats.
+/// Likewise, custom types from the `extensions` feature can only be checked by
+/// their callbacks, so they become `{}`, which allows anything.
+pub fn to_json_schema(schema: &Schema) -> Value {
+    let mut out = convert(schema);
+
+    let mut root = Map::new();
+    root.insert("$schema".to_owned(), DRAFT_2020_12.into());
+
+    if !schema.definitions().is_empty() {
+        root.insert(
+            "$defs".to_owned(),
+            Value::Object(
+                schema
+                    .definitions()
+                    .iter()
+                    .map(|(name, definition)| (name.clone(), Value::Object(convert(definition))))
+                    .collect(),
+            ),
+        );
+    }
+
+    root.append(&mut out);
+    Value::Object(root)
+}
+
+fn convert(schema: &Schema) -> Map<String, Value> {
+    let mut out = Map::new();
+
+    match schema {
+        Schema::Empty { .. } => {}
+
+        Schema::Ref { ref_, .. } => {
+            out.insert("$ref".to_owned(), definition_uri(ref_).into());
+        }
+
+        Schema::Type { type_, .. } => match type_ {
+            Type::Boolean => {
+                out.insert("type".to_owned(), "boolean".into());
+            }
+            Type::Float32 | Type::Float64 => {
+                out.insert("type".to_owned(), "number".into());
+            }
+            Type::String => {
+                out.insert("type".to_owned(), "string".into());
+            }
+            Type::Timestamp => {
+                out.insert("type".to_owned(), "string".into());
+                out.insert("format".to_owned(), "date-time".into());
+            }
+            #[cfg(feature = "extensions")]
+            Type::Custom(_) => {}
+            _ => {
+                let (min, max) = range(type_);
+                out.insert("type".to_owned(), "integer".into());
+                out.insert("minimum".to_owned(), min.into());
+                out.insert("maximum".to_owned(), max.into());
+            }
+        },
+
+        Schema::Enum { enum_, .. } => {
+            out.insert(
+                "enum".to_owned(),
+                enum_.iter().map(|value| Value::from(&value[..])).collect(),
+            );
+        }
+
+        #[cfg(feature = "extensions")]
+        Schema::IntEnum { enum_, .. } => {
+            out.insert(
+                "enum".to_owned(),
+                enum_.iter().map(|&value| Value::from(value)).collect(),
+            );
+        }
+
+        Schema::Elements { elements, .. } => {
+            out.insert("type".to_owned(), "array".into());
+            out.insert("items".to_owned(), Value::Object(convert(elements)));
+        }
+
+        Schema::Properties {
+            properties,
+            optional_properties,
+            additional_properties,
+            ..
+        } => {
+            out.insert("type".to_owned(), "object".into());
+
+            if !properties.is_empty() {
+                out.insert(
+                    "required".to_owned(),
+                    properties
+                        .keys()
+                        .map(|name| Value::from(&name[..]))
+                        .collect(),
+                );
+            }
+
+            if !properties.is_empty() || !optional_properties.is_empty() {
+                out.insert(
+                    "properties".to_owned(),
+                    Value::Object(
+                        properties
+                            .iter()
+                            .chain(optional_properties)
+                            .map(|(name, sub_schema)| {
+                                (name.clone(), Value::Object(convert(sub_schema)))
+                            })
+                            .collect(),
+                    ),
+                );
+            }
+
+            if !additional_properties {
+                out.insert("additionalProperties".to_owned(), false.into());
+            }
+        }
+
+        Schema::Values { values, .. } => {
+            out.insert("type".to_owned(), "object".into());
+            out.insert(
+                "additionalProperties".to_owned(),
+                Value::Object(convert(values)),
+            );
+
+            #[cfg(feature = "extensions")]
+            if let Schema::Values {
+                keys: Some(keys), ..
+            } = schema
+            {
+                out.insert("propertyNames".to_owned(), property_names(keys));
+            }
+        }
+
+        Schema::Discriminator {
+            discriminator,
+            mapping,
+            ..
+        } => {
+            let variants = mapping
+                .iter()
+                .map(|(tag, sub_schema)| {
+                    let mut variant = convert(sub_schema);
+
+                    // The tag is the first required property, and the only
+                    // one that tells the variants apart.
+                    let mut required = vec![Value::from(&discriminator[..])];
+                    if let Some(Value::Array(rest)) = variant.remove("required") {
+                        required.extend(rest);
+                    }
+                    variant.insert("required".to_owned(), Value::Array(required));
+
+                    if let Value::Object(properties) = variant
+                        .entry("properties")
+                        .or_insert_with(|| Value::Object(Map::new()))
+                    {
+                        properties.insert(discriminator.clone(), json!({ "const": tag }));
+                    }
+
+                    Value::Object(variant)
+                })
+                .collect();
+
+            out.insert("type".to_owned(), "object".into());
+            out.insert("oneOf".to_owned(), Value::Array(variants));
+        }
+    }
+
+    if let Some(description) = schema.metadata().get("description") {
+        if description.is_string() {
+            out.insert("description".to_owned(), description.clone());
+        }
+    }
+
+    if schema.nullable() && !matches!(schema, Schema::Empty { .. }) {
+        nullable(&mut out);
+    }
+
+    out
+}
+
+// Makes a converted schema also accept null.
+fn nullable(out: &mut Map<String, Value>) {
+    if let Some(Value::Array(values)) = out.get_mut("enum") {
+        values.push(Value::Null);
+        return;
+    }
+
+    // A null would match none of the variants of a `oneOf`, and a `$ref`
+    // can't be given another type.
+    let type_ = if out.contains_key("oneOf") {
+        None
+    } else {
+        out.get_mut("type")
+    };
+
+    if let Some(Value::String(type_)) = type_ {
+        let type_ = std::mem::take(type_);
+        out.insert("type".to_owned(), json!([type_, "null"]));
+        return;
+    }
+
+    let inner = std::mem::take(out);
+    out.insert(
+        "anyOf".to_owned(),
+        json!([{ "type": "null" }, Value::Object(inner)]),
+    );
+}
+
+#[cfg(feature = "extensions")]
+fn property_names(keys: &crate::Keys) -> Value {
+    use crate::Keys;
+
+    match keys {
+        Keys::Enum(values) => json!({ "enum": values }),
+        Keys::String { pattern: None } => json!({ "type": "string" }),
+        Keys::String {
+            pattern: Some(pattern),
+        } => json!({ "type": "string", "pattern": pattern.as_str() }),
+    }
+}
+
+// The URI of a definition, as a `$ref` to it: a JSON Pointer into `$defs`, as
+// a URI fragment.
+fn definition_uri(name: &str) -> String {
+    let mut uri = "#".to_owned();
+    for byte in SchemaPath::from(vec!["$defs", name]).to_string().bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/".contains(&byte) {
+            uri.push(char::from(byte));
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    uri
+}
+
+// The range of an integer type.
+fn range(type_: &Type) -> (i64, i64) {
+    match type_ {
+        Type::Int8 => (i8::MIN.into(), i8::MAX.into()),
+        Type::Uint8 => (u8::MIN.into(), u8::MAX.into()),
+        Type::Int16 => (i16::MIN.into(), i16::MAX.into()),
+        Type::Uint16 => (u16::MIN.into(), u16::MAX.into()),
+        Type::Int32 => (i32::MIN.into(), i32::MAX.into()),
+        Type::Uint32 => (u32::MIN.into(), u32::MAX.into()),
+        _ => (i64::MIN, i64::MAX),
+    }
+}
+
+/// A JSON Schema that [`from_json_schema`] couldn't convert exactly.
+///
+/// This holds the schema it converted the JSON Schema to anyway, and a list of
+/// the keywords that schema doesn't honor.
+#[derive(Clone, Debug, PartialEq, Error)]
+#[error("{} keywords of the JSON Schema couldn't be converted exactly", .issues.len())]
+pub struct ConversionReport {
+    /// The closest schema to the JSON Schema that could be made.
+    pub schema: Box<Schema>,
+
+    /// The keywords of the JSON Schema that [`schema`][`Self::schema`]
+    /// doesn't honor, in the order they were found.
+    pub issues: Vec<ConversionIssue>,
+}
+
+/// A keyword of a JSON Schema that [`from_json_schema`] couldn't convert
+/// exactly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConversionIssue {
+    /// The path to the keyword in the JSON Schema, as a list of JSON object
+    /// keys and array indices.
+    pub schema_path: Vec<String>,
+
+    /// What became of the keyword.
+    pub kind: ConversionIssueKind,
+}
+
+/// The kinds of [`ConversionIssue`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConversionIssueKind {
+    /// The keyword has no counterpart in JSON Typedef, and was left out. The
+    /// converted schema accepts values that the keyword would have rejected.
+    Dropped,
+
+    /// The keyword was converted to something close to it, but not the same,
+    /// such as a `minimum` and `maximum` to the smallest integer type that
+    /// holds them. The converted schema may accept, or reject, values that
+    /// the JSON Schema wouldn't.
+    Approximated,
+}
+
+impl fmt::Display for ConversionIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}",
+            SchemaPath::from(&self.schema_path[..]),
+            self.kind
+        )
+    }
+}
+
+impl fmt::Display for ConversionIssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConversionIssueKind::Dropped => "keyword was dropped",
+            ConversionIssueKind::Approximated => "keyword was approximated",
+        })
+    }
+}
+
+/// Converts a JSON Schema into a schema, as far as JSON Typedef can express
+/// it.
+///
+/// If every keyword of the JSON Schema was converted exactly, this returns
+/// the converted schema. Otherwise, it returns a [`ConversionReport`] with
+/// the closest schema it could make, and the keywords that schema doesn't
+/// honor. Drafts 4 to 2020-12 are supported, and conversion works the other
+/// way around to [`to_json_schema`]:
+///
+/// * `type` picks the form. `"null"` among the types makes the schema
+///   nullable, and an `anyOf` or `oneOf` of `{ "type": "null" }` and one other
+///   schema makes that schema nullable.
+/// * `"integer"` becomes the smallest integer type that holds its `minimum`
+///   and `maximum`, or `float64` if there is no such type.
+/// * `enum` and `const` of strings become the enum form.
+/// * `properties`, `required` and `additionalProperties` become the
+///   properties form, and `additionalProperties` alone the values form.
+/// * A `oneOf` or `anyOf` of objects that each have a property with a
+///   different `const` string becomes the discriminator form.
+/// * `$defs` and `definitions` of the root become definitions, and a `$ref`
+///   to one of them a `ref`.
+///
+/// A `description` is kept in `metadata`, and other annotations, such as
+/// `title` and `examples`, are ignored. Anything else, such as `pattern` or
+/// `minItems`, is reported as [`ConversionIssueKind::Dropped`].
+///
+/// ```
+/// use jtd::json_schema::{ConversionIssueKind, ConversionReport};
+/// use serde_json::json;
+///
+/// let json_schema = json!({
+///     "type": "object",
+///     "required": ["id"],
+///     "properties": {
+///         "id": { "type": "integer", "minimum": 1, "maximum": 100 },
+///         "email": { "type": "string", "pattern": "@" }
+///     }
+/// });
+///
+/// let ConversionReport { schema, issues } = jtd::from_json_schema(&json_schema).unwrap_err();
+/// assert_eq!(
+///     json!({
+///         "properties": { "id": { "type": "uint8" }},
+///         "optionalProperties": { "email": { "type": "string" }},
+///         "additionalProperties": true
+///     }),
+///     serde_json::to_value(schema.into_serde_schema()).unwrap(),
+/// );
+///
+/// let issues: Vec<_> = issues.iter().map(ToString::to_string).collect();
+/// assert_eq!(
+///     vec![
+///         "/properties/email/pattern: keyword was dropped",
+///         "/properties/id/minimum: keyword was approximated",
+///         "/properties/id/maximum: keyword was approximated",
+///     ],
+///     issues,
+/// );
+/// ```
+pub fn from_json_schema(json_schema: &Value) -> Result<Schema, ConversionReport> {
+    let mut importer = Importer {
+        definitions: BTreeSet::new(),
+        path: Vec::new(),
+        issues: Vec::new(),
+    };
+
+    for keyword in DEFINITIONS {
+        if let Some(Value::Object(definitions)) = json_schema.get(keyword) {
+            importer.definitions.extend(definitions.keys().cloned());
+        }
+    }
+
+    let mut schema = importer.convert(json_schema);
+
+    let mut definitions = BTreeMap::new();
+    for keyword in DEFINITIONS {
+        if let Some(Value::Object(json_definitions)) = json_schema.get(keyword) {
+            for (name, definition) in json_definitions {
+                let definition = importer.convert_at(&[keyword, name], definition);
+                definitions.insert(name.clone(), definition);
+            }
+        }
+    }
+
+    *schema.definitions_mut() = definitions;
+
+    if importer.issues.is_empty() {
+        Ok(schema)
+    } else {
+        Err(ConversionReport {
+            schema: Box::new(schema),
+            issues: importer.issues,
+        })
+    }
+}
+
+// The keywords that hold definitions, in draft 2019-09 onwards and before it.
+const DEFINITIONS: [&str; 2] = ["$defs", "definitions"];
+
+// Keywords that don't affect what a JSON Schema accepts.
+const ANNOTATIONS: [&str; 11] = [
+    "$schema",
+    "$id",
+    "$anchor",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "deprecated",
+    "readOnly",
+    "writeOnly",
+];
+
+// The integer types, smallest first.
+const INT_TYPES: [Type; 6] = [
+    Type::Uint8,
+    Type::Int8,
+    Type::Uint16,
+    Type::Int16,
+    Type::Uint32,
+    Type::Int32,
+];
+
+struct Importer {
+    // The names of the definitions of the root.
+    definitions: BTreeSet<String>,
+
+    // The path to the JSON Schema being converted.
+    path: Vec<String>,
+    issues: Vec<ConversionIssue>,
+}
+
+impl Importer {
+    fn issue(&mut self, tokens: &[&str], kind: ConversionIssueKind) {
+        let mut schema_path = self.path.clone();
+        schema_path.extend(tokens.iter().map(|&token| token.to_owned()));
+        self.issues.push(ConversionIssue { schema_path, kind });
+    }
+
+    fn convert_at(&mut self, tokens: &[&str], json_schema: &Value) -> Schema {
+        let len = self.path.len();
+        self.path
+            .extend(tokens.iter().map(|&token| token.to_owned()));
+        let schema = self.convert(json_schema);
+        self.path.truncate(len);
+        schema
+    }
+
+    fn convert(&mut self, json_schema: &Value) -> Schema {
+        let object = match json_schema {
+            Value::Bool(true) => return empty(),
+            Value::Object(object) => object,
+
+            // Neither `false`, which rejects everything, nor anything that
+            // isn't a JSON Schema at all, has a counterpart.
+            _ => {
+                self.issue(&[], ConversionIssueKind::Approximated);
+                return empty();
+            }
+        };
+
+        let mut used: BTreeSet<&str> = ANNOTATIONS.iter().copied().collect();
+        if self.path.is_empty() {
+            used.extend(DEFINITIONS);
+        }
+
+        let mut nullable = false;
+        let mut schema = self.form(object, &mut used, &mut nullable);
+
+        if nullable {
+            set_nullable(&mut schema);
+        }
+
+        if let Some(description @ Value::String(_)) = object.get("description") {
+            schema
+                .metadata_mut()
+                .insert("description".to_owned(), description.clone());
+        }
+
+        for keyword in object.keys() {
+            if !used.contains(keyword.as_str()) {
+                self.issue(&[keyword], ConversionIssueKind::Dropped);
+            }
+        }
+
+        schema
+    }
+
+    // Converts the keywords of a JSON Schema that decide the form of the
+    // schema, adding each one it honors to `used`.
+    fn form(
+        &mut self,
+        object: &Map<String, Value>,
+        used: &mut BTreeSet<&str>,
+        nullable: &mut bool,
+    ) -> Schema {
+        let mut types: Vec<&str> = match object.get("type") {
+            Some(Value::String(type_)) => vec![type_],
+            Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+
+        if types.contains(&"null") {
+            *nullable = true;
+            types.retain(|&type_| type_ != "null");
+
+            if types.is_empty() {
+                used.insert("type");
+                self.issue(&["type"], ConversionIssueKind::Approximated);
+                return empty();
+            }
+        }
+
+        // Every integer is a number.
+        if types.contains(&"number") {
+            types.retain(|&type_| type_ != "integer");
+        }
+
+        if let Some(ref_) = object.get("$ref") {
+            used.insert("$ref");
+            return self.ref_(ref_);
+        }
+
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(Value::Array(variants)) = object.get(keyword) {
+                used.insert(keyword);
+                return self.union(keyword, variants, &types, used, nullable);
+            }
+        }
+
+        if let Some(Value::Array(variants)) = object.get("allOf") {
+            if variants.len() == 1 {
+                used.insert("allOf");
+                return self.convert_at(&["allOf", "0"], &variants[0]);
+            }
+        }
+
+        let values = match (object.get("enum"), object.get("const")) {
+            (Some(Value::Array(values)), _) => Some(("enum", values.iter().collect())),
+            (None, Some(value)) => Some(("const", vec![value])),
+            _ => None,
+        };
+
+        if let Some((keyword, values)) = values {
+            if types.iter().all(|&type_| type_ == "string")
+                && values
+                    .iter()
+                    .all(|value| value.is_string() || value.is_null())
+            {
+                used.insert(keyword);
+                used.insert("type");
+
+                if values.iter().any(|value| value.is_null()) {
+                    *nullable = true;
+                }
+
+                let enum_: BTreeSet<_> = values
+                    .iter()
+                    .filter_map(|value| value.as_str())
+                    .map(str::to_owned)
+                    .collect();
+
+                if enum_.is_empty() {
+                    self.issue(&[keyword], ConversionIssueKind::Approximated);
+                    return empty();
+                }
+
+                return Schema::Enum {
+                    definitions: Default::default(),
+                    metadata: Default::default(),
+                    nullable: false,
+                    enum_,
+                };
+            }
+        }
+
+        if types.len() > 1 {
+            used.insert("type");
+            self.issue(&["type"], ConversionIssueKind::Approximated);
+            return empty();
+        }
+
+        let type_ = types.first().copied().or_else(|| {
+            if ["properties", "required", "additionalProperties"]
+                .iter()
+                .any(|keyword| object.contains_key(*keyword))
+            {
+                Some("object")
+            } else if object.contains_key("items") {
+                Some("array")
+            } else {
+                None
+            }
+        });
+
+        let type_ = match type_ {
+            Some("boolean") => Type::Boolean,
+            Some("number") => Type::Float64,
+            Some("string") => {
+                if object.get("format") == Some(&Value::from("date-time")) {
+                    used.insert("format");
+                    Type::Timestamp
+                } else {
+                    Type::String
+                }
+            }
+            Some("integer") => self.integer(object, used),
+            Some("array") => {
+                used.insert("type");
+                return self.elements(object, used);
+            }
+            Some("object") => {
+                used.insert("type");
+                return self.object(object, used);
+            }
+            _ => return empty(),
+        };
+
+        used.insert("type");
+        Schema::Type {
+            definitions: Default::default(),
+            metadata: Default::default(),
+            nullable: false,
+            type_,
+        }
+    }
+
+    fn ref_(&mut self, ref_: &Value) -> Schema {
+        let name = ref_
+            .as_str()
+            .and_then(|ref_| {
+                DEFINITIONS
+                    .iter()
+                    .find_map(|keyword| ref_.strip_prefix(&format!("#/{}/", keyword)))
+            })
+            .filter(|token| !token.contains('/'))
+            .map(|token| percent_decode(token).replace("~1", "/").replace("~0", "~"));
+
+        match name {
+            Some(name) if self.definitions.contains(&name) => Schema::Ref {
+                definitions: Default::default(),
+                metadata: Default::default(),
+                nullable: false,
+                ref_: name,
+            },
+
+            // Refs to other documents, or into the middle of a definition,
+            // can't be followed.
+            _ => {
+                self.issue(&["$ref"], ConversionIssueKind::Approximated);
+                empty()
+            }
+        }
+    }
+
+    fn union(
+        &mut self,
+        keyword: &str,
+        variants: &[Value],
+        types: &[&str],
+        used: &mut BTreeSet<&str>,
+        nullable: &mut bool,
+    ) -> Schema {
+        let mut rest = Vec::new();
+        for (index, variant) in variants.iter().enumerate() {
+            if is_null(variant) {
+                *nullable = true;
+            } else {
+                rest.push((index.to_string(), variant));
+            }
+        }
+
+        if let [(index, variant)] = &rest[..] {
+            if types.is_empty() {
+                used.insert("type");
+            }
+
+            return self.convert_at(&[keyword, index], variant);
+        }
+
+        if types.iter().all(|&type_| type_ == "object") {
+            if let Some(schema) = self.discriminator(keyword, &rest) {
+                used.insert("type");
+                return schema;
+            }
+        }
+
+        self.issue(&[keyword], ConversionIssueKind::Approximated);
+        empty()
+    }
+
+    // Converts the variants of a union to the discriminator form, if they're
+    // all objects with a property that tells them apart.
+    fn discriminator(&mut self, keyword: &str, variants: &[(String, &Value)]) -> Option<Schema> {
+        let (_, first) = variants.first()?;
+        let discriminator = first
+            .get("properties")?
+            .as_object()?
+            .keys()
+            .find(|property| {
+                let tags: BTreeSet<_> = variants
+                    .iter()
+                    .filter_map(|(_, variant)| tag(variant, property))
+                    .collect();
+
+                tags.len() == variants.len()
+            })?;
+
+        // The variants are converted without the discriminator, which is
+        // implied by the mapping. If any of them isn't of the properties form
+        // after all, what they reported is discarded.
+        let issues = self.issues.len();
+        let mut mapping = BTreeMap::new();
+        for (index, json_variant) in variants {
+            let tag = tag(json_variant, discriminator)?.to_owned();
+            let mut variant = json_variant.as_object()?.clone();
+            if let Some(Value::Object(properties)) = variant.get_mut("properties") {
+                properties.remove(discriminator);
+            }
+
+            if let Some(Value::Array(required)) = variant.get_mut("required") {
+                required.retain(|property| property != &Value::from(&discriminator[..]));
+            }
+
+            let schema = self.convert_at(&[keyword, index], &Value::Object(variant));
+            if !matches!(
+                schema,
+                Schema::Properties {
+                    nullable: false,
+                    ..
+                }
+            ) {
+                self.issues.truncate(issues);
+                return None;
+            }
+
+            mapping.insert(tag, schema);
+        }
+
+        Some(Schema::Discriminator {
+            definitions: Default::default(),
+            metadata: Default::default(),
+            nullable: false,
+            discriminator: discriminator.clone(),
+            mapping,
+        })
+    }
+
+    fn integer(&mut self, object: &Map<String, Value>, used: &mut BTreeSet<&str>) -> Type {
+        let number = |keyword: &str| object.get(keyword).and_then(Value::as_f64);
+
+        let min = [
+            number("minimum").map(f64::ceil),
+            number("exclusiveMinimum").map(|min| min.floor() + 1.0),
+        ];
+        let max = [
+            number("maximum").map(f64::floor),
+            number("exclusiveMaximum").map(|max| max.ceil() - 1.0),
+        ];
+
+        let lo = min.iter().flatten().copied().reduce(f64::max);
+        let hi = max.iter().flatten().copied().reduce(f64::min);
+
+        let type_ = match (lo, hi) {
+            (Some(lo), Some(hi)) => INT_TYPES.iter().find(|type_| {
+                let (min, max) = range(type_);
+                min as f64 <= lo && hi <= max as f64
+            }),
+            _ => None,
+        };
+
+        let type_ = match type_ {
+            Some(type_) => type_.clone(),
+
+            // JSON Typedef has no integer type for this range, so only being a
+            // number is checked.
+            None => {
+                self.issue(&["type"], ConversionIssueKind::Approximated);
+                return Type::Float64;
+            }
+        };
+
+        let (type_min, type_max) = range(&type_);
+        let bounds = [
+            ("minimum", lo == Some(type_min as f64)),
+            ("exclusiveMinimum", lo == Some(type_min as f64)),
+            ("maximum", hi == Some(type_max as f64)),
+            ("exclusiveMaximum", hi == Some(type_max as f64)),
+        ];
+
+        for (keyword, exact) in bounds {
+            if number(keyword).is_some() {
+                used.insert(keyword);
+                if !exact {
+                    self.issue(&[keyword], ConversionIssueKind::Approximated);
+                }
+            }
+        }
+
+        type_
+    }
+
+    fn elements(&mut self, object: &Map<String, Value>, used: &mut BTreeSet<&str>) -> Schema {
+        // Draft 4's tuples, with an array of `items`, aren't converted.
+        let elements = match object.get("items") {
+            Some(items @ (Value::Object(_) | Value::Bool(_))) => {
+                used.insert("items");
+                self.convert_at(&["items"], items)
+            }
+            _ => empty(),
+        };
+
+        Schema::Elements {
+            definitions: Default::default(),
+            metadata: Default::default(),
+            nullable: false,
+            elements: Box::new(elements),
+        }
+    }
+
+    fn object(&mut self, object: &Map<String, Value>, used: &mut BTreeSet<&str>) -> Schema {
+        let json_properties = object.get("properties").and_then(Value::as_object);
+        let required: BTreeSet<&str> = object
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        let additional = object.get("additionalProperties");
+        if additional.is_some() {
+            used.insert("additionalProperties");
+        }
+
+        if json_properties.is_none() && required.is_empty() {
+            let values = match additional {
+                Some(Value::Bool(false)) => {
+                    return Schema::Properties {
+                        definitions: Default::default(),
+                        metadata: Default::default(),
+                        nullable: false,
+                        properties: Default::default(),
+                        optional_properties: Default::default(),
+                        properties_is_present: true,
+                        additional_properties: false,
+                    };
+                }
+                Some(values) => self.convert_at(&["additionalProperties"], values),
+                None => empty(),
+            };
+
+            return Schema::Values {
+                definitions: Default::default(),
+                metadata: Default::default(),
+                nullable: false,
+                values: Box::new(values),
+                #[cfg(feature = "extensions")]
+                keys: None,
+            };
+        }
+
+        used.insert("properties");
+        used.insert("required");
+
+        let mut properties = BTreeMap::new();
+        let mut optional_properties = BTreeMap::new();
+        for (name, json_schema) in json_properties.into_iter().flatten() {
+            let schema = self.convert_at(&["properties", name], json_schema);
+            if required.contains(name.as_str()) {
+                properties.insert(name.clone(), schema);
+            } else {
+                optional_properties.insert(name.clone(), schema);
+            }
+        }
+
+        for name in required {
+            properties.entry(name.to_owned()).or_insert_with(empty);
+        }
+
+        let additional_properties = match additional {
+            Some(Value::Bool(false)) => false,
+            None | Some(Value::Bool(true)) => true,
+
+            // JSON Typedef can't say what additional properties must be, only
+            // whether they're allowed.
+            Some(_) => {
+                self.issue(&["additionalProperties"], ConversionIssueKind::Approximated);
+                true
+            }
+        };
+
+        Schema::Properties {
+            definitions: Default::default(),
+            metadata: Default::default(),
+            nullable: false,
+            properties_is_present: !properties.is_empty() || optional_properties.is_empty(),
+            properties,
+            optional_properties,
+            additional_properties,
+        }
+    }
+}
+
+// Whether a JSON Schema accepts only null.
+fn is_null(json_schema: &Value) -> bool {
+    let object = match json_schema.as_object() {
+        Some(object) if object.len() == 1 => object,
+        _ => return false,
+    };
+
+    object.get("type") == Some(&Value::from("null"))
+        || object.get("const") == Some(&Value::Null)
+        || object.get("enum") == Some(&json!([null]))
+}
+
+// The value a variant of a union requires a property to have, if it requires
+// it to be a single string.
+fn tag<'a>(variant: &'a Value, property: &str) -> Option<&'a str> {
+    let json_schema = variant.get("properties")?.get(property)?;
+    match (json_schema.get("const"), json_schema.get("enum")) {
+        (Some(Value::String(tag)), _) => Some(tag),
+        (_, Some(Value::Array(tags))) if tags.len() == 1 => tags[0].as_str(),
+        _ => None,
+    }
+}
+
+fn set_nullable(schema: &mut Schema) {
+    match schema {
+        Schema::Empty { .. } => {}
+        Schema::Ref { nullable, .. }
+        | Schema::Type { nullable, .. }
+        | Schema::Enum { nullable, .. }
+        | Schema::Elements { nullable, .. }
+        | Schema::Properties { nullable, .. }
+        | Schema::Values { nullable, .. }
+        | Schema::Discriminator { nullable, .. } => *nullable = true,
+        #[cfg(feature = "extensions")]
+        Schema::IntEnum { nullable, .. } => *nullable = true,
+    }
+}
+
+fn empty() -> Schema {
+    Schema::Empty {
+        definitions: Default::default(),
+        metadata: Default::default(),
+    }
+}
+
+// Decodes the percent-encoding of a URI fragment.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = s
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match byte {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(value: Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn converts_forms() {
+        assert_eq!(
+            json!({
+                "$schema": DRAFT_2020_12,
+                "$defs": {
+                    "a/b c": { "type": "boolean" }
+                },
+                "type": ["object", "null"],
+                "required": ["at", "n", "tags"],
+                "properties": {
+                    "any": {},
+                    "at": { "type": "string", "format": "date-time", "description": "When." },
+                    "counts": {
+                        "type": "object",
+                        "additionalProperties": { "type": "number" }
+                    },
+                    "n": { "type": "integer", "minimum": -128, "maximum": 127 },
+                    "flag": {
+                        "anyOf": [{ "type": "null" }, { "$ref": "#/$defs/a~1b%20c" }]
+                    },
+                    "tags": {
+                        "type": "array",
+                        "items": { "enum": ["a", "b", null] }
+                    }
+                }
+            }),
+            to_json_schema(&schema(json!({
+                "definitions": {
+                    "a/b c": { "type": "boolean" }
+                },
+                "properties": {
+                    "at": { "metadata": { "description": "When." }, "type": "timestamp" },
+                    "n": { "type": "int8" },
+                    "tags": { "elements": { "enum": ["a", "b"], "nullable": true }}
+                },
+                "optionalProperties": {
+                    "any": {},
+                    "counts": { "values": { "type": "float64" }},
+                    "flag": { "ref": "a/b c", "nullable": true }
+                },
+                "additionalProperties": true,
+                "nullable": true
+            })))
+        );
+    }
+
+    #[test]
+    fn converts_discriminators() {
+        assert_eq!(
+            json!({
+                "$schema": DRAFT_2020_12,
+                "type": "object",
+                "oneOf": [
+                    {
+                        "type": "object",
+                        "required": ["t"],
+                        "properties": { "t": { "const": "a" }},
+                        "additionalProperties": false
+                    },
+                    {
+                        "type": "object",
+                        "required": ["t", "x"],
+                        "properties": {
+                            "t": { "const": "b" },
+                            "x": { "type": "string" },
+                            "y": { "type": "number" }
+                        }
+                    }
+                ]
+            }),
+            to_json_schema(&schema(json!({
+                "discriminator": "t",
+                "mapping": {
+                    "a": { "properties": {} },
+                    "b": {
+                        "properties": { "x": { "type": "string" }},
+                        "optionalProperties": { "y": { "type": "float32" }},
+                        "additionalProperties": true
+                    }
+                }
+            })))
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let schema = schema(json!({
+            "definitions": {
+                "a/b c": { "type": "boolean", "nullable": true },
+                "event": {
+                    "discriminator": "t",
+                    "mapping": {
+                        "a": { "properties": {} },
+                        "b": {
+                            "properties": { "x": { "ref": "a/b c", "nullable": true }},
+                            "optionalProperties": { "at": { "type": "timestamp" }},
+                            "additionalProperties": true
+                        }
+                    },
+                    "nullable": true
+                }
+            },
+            "properties": {
+                "events": { "elements": { "ref": "event" }},
+                "n": { "type": "int16", "metadata": { "description": "N." }},
+                "level": { "enum": ["info", "warn"], "nullable": true }
+            },
+            "optionalProperties": {
+                "any": {},
+                "counts": { "values": { "type": "uint32" }},
+                "ratio": { "type": "float64" },
+                "name": { "type": "string" }
+            }
+        }));
+
+        assert_eq!(
+            Ok(schema.clone()),
+            from_json_schema(&to_json_schema(&schema))
+        );
+    }
+
+    #[test]
+    fn reports_what_is_lost() {
+        let report = from_json_schema(&json!({
+            "$defs": { "id": { "type": "integer" }},
+            "type": "object",
+            "properties": {
+                "id": { "$ref": "#/$defs/id" },
+                "other": { "$ref": "other.json" },
+                "tags": {
+                    "type": "array",
+                    "items": { "type": ["string", "number"] },
+                    "minItems": 1
+                },
+                "shape": {
+                    "oneOf": [
+                        { "properties": { "kind": { "const": "circle" }}},
+                        { "properties": { "kind": { "const": "circle" }}}
+                    ]
+                },
+                "never": false
+            },
+            "additionalProperties": { "type": "string" }
+        }))
+        .unwrap_err();
+
+        assert_eq!(
+            json!({
+                "definitions": { "id": { "type": "float64" }},
+                "optionalProperties": {
+                    "id": { "ref": "id" },
+                    "never": {},
+                    "other": {},
+                    "shape": {},
+                    "tags": { "elements": {} }
+                },
+                "additionalProperties": true
+            }),
+            serde_json::to_value(report.schema.into_serde_schema()).unwrap(),
+        );
+
+        let issues: Vec<_> = report.issues.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            vec![
+                "/properties/never: keyword was approximated",
+                "/properties/other/$ref: keyword was approximated",
+                "/properties/shape/oneOf: keyword was approximated",
+                "/properties/tags/items/type: keyword was approximated",
+                "/properties/tags/minItems: keyword was dropped",
+                "/additionalProperties: keyword was approximated",
+                "/$defs/id/type: keyword was approximated",
+            ],
+            issues,
+        );
+    }
+}
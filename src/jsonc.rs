@@ -0,0 +1,155 @@
+//! Read JSON with comments and trailing commas.
+//!
+//! Schema files, and configuration files, are often maintained by hand, and
+//! benefit from comments. JSONC is JSON extended with `//` and `/* */`
+//! comments, and with trailing commas in objects and arrays, as used by many
+//! editors for their settings files.
+//!
+//! [`strip`] turns JSONC into plain JSON by blanking out comments and trailing
+//! commas. Everything else stays on the same line and column, so errors from
+//! parsing or validating the result point at the right place in the original
+//! text. [`from_str`] parses JSONC with `serde_json`, and
+//! [`validate_jsonc_str`][`crate::config::validate_jsonc_str`] validates it
+//! with the locations of any errors.
+//!
+//! This module is only available with the `jsonc` feature.
+//!
+//! ```
+//! use jtd::{Schema, SerdeSchema};
+//! use serde_json::json;
+//!
+//! let schema: SerdeSchema = jtd::jsonc::from_str(r#"{
+//!     // Ports below 1024 are reserved.
+//!     "type": "uint16",
+//! }"#).unwrap();
+//!
+//! let schema = Schema::from_serde_schema(schema).unwrap();
+//! let instance: serde_json::Value = jtd::jsonc::from_str("8080 /* the default */").unwrap();
+//! assert!(jtd::validate(&schema, &instance, Default::default()).unwrap().is_empty());
+//! ```
+
+use serde::de::DeserializeOwned;
+
+/// Converts JSONC into JSON, by replacing comments and trailing commas with
+/// spaces.
+///
+/// Each character removed is replaced by a single space, except for line
+/// breaks within block comments, which are kept. So every other character
+/// keeps its line and column.
+///
+/// Input that isn't valid JSONC is converted as far as possible, so that
+/// parsing the output reports the problem at the right place. In particular,
+/// an unterminated block comment is left in place.
+///
+/// ```
+/// assert_eq!(
+///     "[1, 2  ]        \n",
+///     jtd::jsonc::strip("[1, 2, ] // nums\n"),
+/// );
+/// ```
+pub fn strip(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut output = chars.clone();
+
+    // Blank out comments, and note where the commas outside strings are.
+    let mut commas = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1)) {
+            ('"', _) => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+            }
+            ('/', Some('/')) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    output[i] = ' ';
+                    i += 1;
+                }
+
+                continue;
+            }
+            ('/', Some('*')) => {
+                let end = (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == '*' && chars[j + 1] == '/');
+
+                match end {
+                    Some(end) => {
+                        for c in &mut output[i..end + 2] {
+                            if !matches!(*c, '\n' | '\r') {
+                                *c = ' ';
+                            }
+                        }
+
+                        i = end + 2;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            (',', _) => commas.push(i),
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    // A comma is trailing if, now that comments are gone, it follows a value
+    // and the next thing after it closes an object or array.
+    for comma in commas {
+        let prev = output[..comma].iter().rev().find(|c| !c.is_whitespace());
+        let next = output[comma + 1..].iter().find(|c| !c.is_whitespace());
+        if !matches!(prev, Some('{' | '[' | ',')) && matches!(next, Some('}' | ']')) {
+            output[comma] = ' ';
+        }
+    }
+
+    output.into_iter().collect()
+}
+
+/// Deserializes an instance of `T` from JSONC.
+///
+/// This is [`serde_json::from_str`], applied to the output of [`strip`]. The
+/// line and column of any error refer to the original input.
+///
+/// ```
+/// let err = jtd::jsonc::from_str::<serde_json::Value>("/* a */ [1,, 2]").unwrap_err();
+/// assert_eq!((1, 12), (err.line(), err.column()));
+/// ```
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T, serde_json::Error> {
+    serde_json::from_str(&strip(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    #[test]
+    fn strip() {
+        let input = r#"{
+            // "a": 1,
+            "b": "not // a comment, }", /* multi
+            line */ "c": [1, 2, /* three */ ],
+            "d\"": { "e": true, },
+        }"#;
+
+        let output = super::strip(input);
+        assert_eq!(input.lines().count(), output.lines().count());
+        for (input, output) in input.lines().zip(output.lines()) {
+            assert_eq!(input.chars().count(), output.chars().count());
+        }
+
+        assert_eq!(
+            json!({ "b": "not // a comment, }", "c": [1, 2], "d\"": { "e": true }}),
+            serde_json::from_str::<serde_json::Value>(&output).unwrap()
+        );
+
+        // Malformed input is left for the JSON parser to reject.
+        assert_eq!("1 /* 2", super::strip("1 /* 2"));
+        assert_eq!("[, ]", super::strip("[, ]"));
+        assert_eq!("[1,, ]", super::strip("[1,, ]"));
+        assert!(super::from_str::<serde_json::Value>("[, ]").is_err());
+        assert_eq!("\"é, ]", super::strip("\"é, ]"));
+    }
+}
@@ -0,0 +1,308 @@
+use crate::{Definitions, Metadata, Schema, Type};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A Rust type with a JSON Typedef schema, describing how the type is
+/// represented in JSON by serde.
+///
+/// This lets Rust types be the source of truth for a schema that's published
+/// for others, such as the consumers of an API. Call
+/// [`Schema::into_serde_schema`] on the result to get it as JSON.
+///
+/// This trait is implemented for the Rust types with an obvious JSON Typedef
+/// counterpart: `bool`, `String`, `f32` and `f64`, the integer types that
+/// JSON Typedef has (`i8` to `i32`, and `u8` to `u32`, but not `i64`, `u64`,
+/// or wider), `chrono::DateTime` (with the `chrono` feature), collections of
+/// these, `Option` (as a nullable schema), and `serde_json::Value` (as the
+/// empty form).
+///
+/// With the `macros` feature, it can be derived with `#[derive(JtdSchema)]`,
+/// like [`Serialize`][`serde::Serialize`] and
+/// [`Deserialize`][`serde::Deserialize`], for structs with named fields,
+/// newtype structs, enums of unit variants, and internally tagged enums. The
+/// derived schema follows the serde attributes `rename`, `rename_all`, `tag`,
+/// `transparent`, `skip`, `default`, and `skip_serializing_if`: fields that
+/// are `#[serde(default)]`, or may be left out by `skip_serializing_if`, are
+/// optional properties. Other serde attributes, like `flatten` and
+/// `untagged`, are rejected at compile time. Recursive types aren't
+/// supported, since schemas are built without definitions.
+///
+/// ```
+/// use jtd::JtdSchema;
+/// use serde_json::json;
+/// use std::collections::BTreeMap;
+///
+/// assert_eq!(
+///     json!({ "values": { "elements": { "type": "uint8", "nullable": true }}}),
+///     serde_json::to_value(
+///         <BTreeMap<String, Vec<Option<u8>>>>::jtd_schema().into_serde_schema()
+///     ).unwrap(),
+/// );
+/// ```
+///
+/// Deriving this trait for a struct gives the properties form, with a
+/// property for each field:
+///
+/// ```ignore
+/// #[derive(serde::Serialize, serde::Deserialize, jtd::JtdSchema)]
+/// #[serde(rename_all = "camelCase")]
+/// struct User {
+///     name: String,
+///     #[serde(skip_serializing_if = "Option::is_none")]
+///     favorite_color: Option<Color>,
+/// }
+///
+/// #[derive(serde::Serialize, serde::Deserialize, jtd::JtdSchema)]
+/// #[serde(rename_all = "lowercase")]
+/// enum Color {
+///     Red,
+///     Green,
+/// }
+///
+/// // {
+/// //   "properties": { "name": { "type": "string" }},
+/// //   "optionalProperties": {
+/// //     "favoriteColor": { "enum": ["green", "red"], "nullable": true }
+/// //   }
+/// // }
+/// let schema = User::jtd_schema().into_serde_schema();
+/// ```
+pub trait JtdSchema {
+    /// The schema of the type.
+    fn jtd_schema() -> Schema;
+}
+
+macro_rules! impl_type {
+    ($($ty:ty => $type_:expr,)*) => {
+        $(
+            impl JtdSchema for $ty {
+                fn jtd_schema() -> Schema {
+                    type_schema($type_)
+                }
+            }
+        )*
+    };
+}
+
+impl_type! {
+    bool => Type::Boolean,
+    i8 => Type::Int8,
+    u8 => Type::Uint8,
+    i16 => Type::Int16,
+    u16 => Type::Uint16,
+    i32 => Type::Int32,
+    u32 => Type::Uint32,
+    f32 => Type::Float32,
+    f64 => Type::Float64,
+    char => Type::String,
+    str => Type::String,
+    String => Type::String,
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> JtdSchema for chrono::DateTime<Tz> {
+    fn jtd_schema() -> Schema {
+        type_schema(Type::Timestamp)
+    }
+}
+
+impl JtdSchema for serde_json::Value {
+    fn jtd_schema() -> Schema {
+        Schema::Empty {
+            definitions: Definitions::new(),
+            metadata: Metadata::new(),
+        }
+    }
+}
+
+impl<T: JtdSchema> JtdSchema for Option<T> {
+    fn jtd_schema() -> Schema {
+        let mut schema = T::jtd_schema();
+        jtd_core::__private::set_nullable(&mut schema);
+        schema
+    }
+}
+
+// Types that are represented just like the type they wrap.
+macro_rules! impl_wrapper {
+    ($($ty:ty,)*) => {
+        $(
+            impl<T: JtdSchema + ?Sized> JtdSchema for $ty {
+                fn jtd_schema() -> Schema {
+                    T::jtd_schema()
+                }
+            }
+        )*
+    };
+}
+
+impl_wrapper! {
+    &T,
+    Box<T>,
+    Rc<T>,
+    Arc<T>,
+}
+
+impl<T: JtdSchema + ToOwned + ?Sized> JtdSchema for Cow<'_, T> {
+    fn jtd_schema() -> Schema {
+        T::jtd_schema()
+    }
+}
+
+macro_rules! impl_elements {
+    ($($ty:ty,)*) => {
+        $(
+            impl<T: JtdSchema> JtdSchema for $ty {
+                fn jtd_schema() -> Schema {
+                    elements_schema(T::jtd_schema())
+                }
+            }
+        )*
+    };
+}
+
+impl_elements! {
+    [T],
+    Vec<T>,
+    VecDeque<T>,
+    BTreeSet<T>,
+}
+
+impl<T: JtdSchema, S> JtdSchema for HashSet<T, S> {
+    fn jtd_schema() -> Schema {
+        elements_schema(T::jtd_schema())
+    }
+}
+
+impl<T: JtdSchema, const N: usize> JtdSchema for [T; N] {
+    fn jtd_schema() -> Schema {
+        elements_schema(T::jtd_schema())
+    }
+}
+
+impl<V: JtdSchema> JtdSchema for BTreeMap<String, V> {
+    fn jtd_schema() -> Schema {
+        values_schema(V::jtd_schema())
+    }
+}
+
+impl<V: JtdSchema, S> JtdSchema for HashMap<String, V, S> {
+    fn jtd_schema() -> Schema {
+        values_schema(V::jtd_schema())
+    }
+}
+
+impl JtdSchema for serde_json::Map<String, serde_json::Value> {
+    fn jtd_schema() -> Schema {
+        values_schema(serde_json::Value::jtd_schema())
+    }
+}
+
+fn type_schema(type_: Type) -> Schema {
+    Schema::Type {
+        definitions: Definitions::new(),
+        metadata: Metadata::new(),
+        nullable: false,
+        type_,
+    }
+}
+
+fn elements_schema(elements: Schema) -> Schema {
+    Schema::Elements {
+        definitions: Definitions::new(),
+        metadata: Metadata::new(),
+        nullable: false,
+        elements: Box::new(elements),
+    }
+}
+
+fn values_schema(values: Schema) -> Schema {
+    Schema::Values {
+        definitions: Definitions::new(),
+        metadata: Metadata::new(),
+        nullable: false,
+        values: Box::new(values),
+        #[cfg(feature = "extensions")]
+        keys: None,
+    }
+}
+
+// Used by the code generated by #[derive(JtdSchema)], through __private.
+pub(crate) fn properties_schema(
+    properties: Vec<(&str, Schema)>,
+    optional_properties: Vec<(&str, Schema)>,
+) -> Schema {
+    let collect = |properties: Vec<(&str, Schema)>| {
+        properties
+            .into_iter()
+            .map(|(name, schema)| (name.to_owned(), schema))
+            .collect()
+    };
+
+    Schema::Properties {
+        definitions: Definitions::new(),
+        metadata: Metadata::new(),
+        nullable: false,
+        properties: collect(properties),
+        optional_properties: collect(optional_properties),
+        properties_is_present: true,
+        additional_properties: false,
+    }
+}
+
+pub(crate) fn enum_schema(values: &[&str]) -> Schema {
+    Schema::Enum {
+        definitions: Definitions::new(),
+        metadata: Metadata::new(),
+        nullable: false,
+        enum_: values.iter().map(|&value| value.to_owned()).collect(),
+    }
+}
+
+pub(crate) fn discriminator_schema(discriminator: &str, mapping: Vec<(&str, Schema)>) -> Schema {
+    Schema::Discriminator {
+        definitions: Definitions::new(),
+        metadata: Metadata::new(),
+        nullable: false,
+        discriminator: discriminator.to_owned(),
+        mapping: mapping
+            .into_iter()
+            .map(|(tag, schema)| (tag.to_owned(), schema))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JtdSchema;
+    use serde_json::json;
+    use std::collections::{BTreeMap, HashSet};
+
+    fn schema_json<T: JtdSchema + ?Sized>() -> serde_json::Value {
+        let schema = T::jtd_schema();
+        schema.validate().unwrap();
+        serde_json::to_value(schema.into_serde_schema()).unwrap()
+    }
+
+    #[test]
+    fn std_types() {
+        assert_eq!(json!({ "type": "uint16" }), schema_json::<u16>());
+        assert_eq!(json!({ "type": "string" }), schema_json::<str>());
+        #[cfg(feature = "chrono")]
+        assert_eq!(
+            json!({ "type": "timestamp" }),
+            schema_json::<chrono::DateTime<chrono::Utc>>()
+        );
+        assert_eq!(
+            json!({ "elements": { "type": "boolean", "nullable": true }}),
+            schema_json::<Box<[Option<bool>]>>()
+        );
+        assert_eq!(
+            json!({ "elements": { "values": { "type": "float64" }}}),
+            schema_json::<HashSet<BTreeMap<String, f64>>>()
+        );
+        assert_eq!(json!({}), schema_json::<Option<serde_json::Value>>());
+    }
+}
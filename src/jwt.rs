@@ -0,0 +1,229 @@
+//! Validate the claims of JSON Web Tokens.
+//!
+//! Checking a token's signature says who issued it, but not that its claims
+//! are shaped the way the rest of a service expects. [`validate_claims`]
+//! checks decoded claims against a schema, and reports each problem by the
+//! name of the claim it's about, so that authentication middleware can
+//! enforce a contract for claims declaratively.
+//!
+//! With the `jsonwebtoken` feature, `decode` does the same for tokens
+//! decoded by the [`jsonwebtoken`](https://docs.rs/jsonwebtoken) crate. For
+//! other crates, decode the claims as a [`serde_json::Value`], and pass them
+//! to [`validate_claims`].
+//!
+//! ```
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": {
+//!         "sub": { "type": "string" },
+//!         "roles": { "elements": { "enum": ["admin", "user"] }}
+//!     },
+//!     "additionalProperties": true
+//! })).unwrap()).unwrap();
+//!
+//! let claims = json!({ "sub": "alice", "roles": ["root"], "iat": 1516239022 });
+//! let errors = jtd::jwt::validate_claims(&schema, &claims).unwrap();
+//!
+//! assert_eq!(1, errors.len());
+//! assert_eq!(Some("roles".to_owned()), errors[0].claim);
+//! assert_eq!(vec!["roles", "0"], errors[0].error.instance_path);
+//! ```
+
+use crate::{Schema, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde_json::Value;
+use std::borrow::Cow;
+use thiserror::Error;
+
+/// A validation error in the claims of a token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClaimError {
+    /// The name of the claim the error is about, including claims that are
+    /// missing. Errors about the claims as a whole, such as claims that
+    /// aren't an object, aren't about any one claim.
+    pub claim: Option<String>,
+
+    /// The validation error, for the claims as a JSON object.
+    pub error: ValidationErrorIndicator<'static>,
+}
+
+/// Errors that may arise from `decode`.
+#[derive(Debug, Error)]
+pub enum JwtError {
+    /// The token couldn't be decoded, or its signature or registered claims,
+    /// such as `exp`, didn't pass the checks of the `jsonwebtoken` crate.
+    #[cfg(feature = "jsonwebtoken")]
+    #[error(transparent)]
+    Jsonwebtoken(#[from] jsonwebtoken::errors::Error),
+
+    /// The claims failed validation against the schema.
+    #[error("token has {} invalid claims", .0.len())]
+    Invalid(Vec<ClaimError>),
+
+    /// The claims passed validation, but couldn't be converted to the
+    /// requested type.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    /// Validating the claims failed.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+}
+
+/// Validates decoded claims against a schema, with default options.
+///
+/// See [`validate_claims_with_options`] to use other options, such as a limit
+/// on the number of errors.
+pub fn validate_claims(schema: &Schema, claims: &Value) -> Result<Vec<ClaimError>, ValidateError> {
+    validate_claims_with_options(schema, claims, ValidateOptions::new())
+}
+
+/// Validates decoded claims against a schema.
+pub fn validate_claims_with_options(
+    schema: &Schema,
+    claims: &Value,
+    options: ValidateOptions,
+) -> Result<Vec<ClaimError>, ValidateError> {
+    Ok(crate::validate(schema, claims, options)?
+        .into_iter()
+        .map(|error| {
+            let severity = error.severity;
+            let (instance_path, schema_path) = error.into_owned_paths();
+
+            // Errors about a missing claim have an empty instance path, but
+            // name the claim at the end of their schema path.
+            let claim = match (&instance_path[..], &schema_path[..]) {
+                ([claim, ..], _) => Some(claim.clone()),
+                ([], [.., keyword, claim]) if keyword == "properties" => Some(claim.clone()),
+                _ => None,
+            };
+
+            ClaimError {
+                claim,
+                error: ValidationErrorIndicator {
+                    instance_path: instance_path.into_iter().map(Cow::Owned).collect(),
+                    schema_path: schema_path.into_iter().map(Cow::Owned).collect(),
+                    severity,
+                },
+            }
+        })
+        .collect())
+}
+
+/// Decodes a token with the `jsonwebtoken` crate, and validates its claims
+/// against a schema.
+///
+/// The token's signature and registered claims are checked as by
+/// [`jsonwebtoken::decode`] first. Then, its claims are validated, and
+/// converted to `T` only if they're valid.
+///
+/// This function is only available with the `jsonwebtoken` feature.
+#[cfg(feature = "jsonwebtoken")]
+pub fn decode<T: serde::de::DeserializeOwned>(
+    token: &str,
+    key: &jsonwebtoken::DecodingKey,
+    validation: &jsonwebtoken::Validation,
+    schema: &Schema,
+) -> Result<jsonwebtoken::TokenData<T>, JwtError> {
+    let data = jsonwebtoken::decode::<Value>(token, key, validation)?;
+
+    let errors = validate_claims(schema, &data.claims)?;
+    if !errors.is_empty() {
+        return Err(JwtError::Invalid(errors));
+    }
+
+    Ok(jsonwebtoken::TokenData {
+        header: data.header,
+        claims: serde_json::from_value(data.claims)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "properties": {
+                    "sub": { "type": "string" },
+                    "exp": { "type": "uint32" }
+                },
+                "optionalProperties": {
+                    "scope": { "type": "string" }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn claims(claims: Value) -> Vec<(Option<String>, String)> {
+        validate_claims(&schema(), &claims)
+            .unwrap()
+            .into_iter()
+            .map(|error| {
+                let schema_path = error.error.schema_path.to_string();
+                (error.claim, schema_path)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn maps_errors_to_claims() {
+        assert!(claims(json!({ "sub": "a", "exp": 1 })).is_empty());
+
+        assert_eq!(
+            vec![
+                (Some("exp".to_owned()), "/properties/exp/type".to_owned()),
+                (Some("sub".to_owned()), "/properties/sub".to_owned()),
+                (
+                    Some("scope".to_owned()),
+                    "/optionalProperties/scope/type".to_owned()
+                ),
+                (Some("admin".to_owned()), "".to_owned()),
+            ],
+            claims(json!({ "exp": -1, "scope": 1, "admin": true }))
+        );
+
+        assert_eq!(vec![(None, "/properties".to_owned())], claims(json!("sub")));
+    }
+
+    #[cfg(feature = "jsonwebtoken")]
+    #[test]
+    fn decodes() {
+        use jsonwebtoken::{encode, DecodingKey, EncodingKey, Header, Validation};
+
+        let key = b"secret";
+        let token = |claims: Value| {
+            encode(&Header::default(), &claims, &EncodingKey::from_secret(key)).unwrap()
+        };
+
+        let decode = |token: &str| {
+            decode::<Value>(
+                token,
+                &DecodingKey::from_secret(key),
+                &Validation::default(),
+                &schema(),
+            )
+        };
+
+        let claims = json!({ "sub": "a", "exp": 4_000_000_000_u32 });
+        assert_eq!(claims, decode(&token(claims.clone())).unwrap().claims);
+
+        match decode(&token(json!({ "sub": 1, "exp": 4_000_000_000_u32 }))) {
+            Err(JwtError::Invalid(errors)) => {
+                assert_eq!(1, errors.len());
+                assert_eq!(Some("sub".to_owned()), errors[0].claim);
+            }
+            other => panic!("{:?}", other),
+        }
+
+        assert!(matches!(
+            decode(&token(json!({ "sub": "a", "exp": 1 }))),
+            Err(JwtError::Jsonwebtoken(_))
+        ));
+    }
+}
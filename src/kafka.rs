@@ -0,0 +1,327 @@
+//! Validate Kafka messages as they're produced and consumed.
+//!
+//! [`Serializer`] and [`Deserializer`] turn values into JSON message payloads
+//! and back, validating each payload against the schema for its topic. They
+//! work with payloads as bytes, so they fit any Kafka client, such as
+//! [`rdkafka`](https://docs.rs/rdkafka): pass the output of
+//! [`Serializer::serialize`] to `FutureRecord::payload`, and the result of
+//! `Message::payload` to [`Deserializer::deserialize`].
+//!
+//! What happens to an invalid payload is up to [`OnInvalid`]: it can be
+//! rejected with an error, or logged with the [`log`](https://docs.rs/log)
+//! crate and let through.
+//!
+//! This module is only available with the `kafka` feature.
+//!
+//! ```
+//! use jtd::kafka::{Deserializer, KafkaError, Serializer, Topics};
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": { "user": { "type": "string" }}
+//! })).unwrap()).unwrap();
+//!
+//! let topics = Topics::new().with_schema("logins", schema);
+//! let serializer = Serializer::new(topics.clone());
+//! let deserializer = Deserializer::new(topics);
+//!
+//! let payload = serializer.serialize("logins", &json!({ "user": "alice" })).unwrap();
+//! let login: serde_json::Value = deserializer.deserialize("logins", &payload).unwrap();
+//! assert_eq!(json!({ "user": "alice" }), login);
+//!
+//! assert!(matches!(
+//!     serializer.serialize("logins", &json!({ "user": 1 })),
+//!     Err(KafkaError::Invalid { .. }),
+//! ));
+//! ```
+
+use crate::{validate, Schema, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// The schemas that messages on each topic must satisfy.
+///
+/// This is cheap to clone, so the same topics can be shared between a
+/// [`Serializer`] and a [`Deserializer`].
+#[derive(Clone, Debug, Default)]
+pub struct Topics {
+    schemas: BTreeMap<String, Arc<Schema>>,
+}
+
+impl Topics {
+    /// Constructs an empty set of topics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the schema for messages on `topic`.
+    ///
+    /// The schema may be given as an `Arc<Schema>`, to share it with other
+    /// topics, or other parts of the program, without copying it.
+    pub fn with_schema(mut self, topic: impl Into<String>, schema: impl Into<Arc<Schema>>) -> Self {
+        self.schemas.insert(topic.into(), schema.into());
+        self
+    }
+
+    /// Gets the schema for messages on `topic`, if any.
+    pub fn get(&self, topic: &str) -> Option<&Arc<Schema>> {
+        self.schemas.get(topic)
+    }
+}
+
+/// What to do with a message that fails validation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OnInvalid {
+    /// Return [`KafkaError::Invalid`].
+    Reject,
+
+    /// Log a warning, with target `jtd::kafka`, and carry on as if the
+    /// message was valid.
+    Warn,
+}
+
+/// Errors that may arise from [`Serializer`] and [`Deserializer`].
+#[derive(Debug, Error)]
+pub enum KafkaError {
+    /// There is no schema for the topic. Messages on topics without a schema
+    /// are always rejected, so that a missing schema doesn't go unnoticed.
+    #[error("no schema for topic {0:?}")]
+    UnknownTopic(String),
+
+    /// The message failed validation, and [`OnInvalid::Reject`] was in
+    /// effect.
+    #[error("message on topic {topic:?} has {} validation errors", errors.len())]
+    Invalid {
+        /// The topic of the message.
+        topic: String,
+
+        /// The message's validation errors.
+        errors: Vec<ValidationErrorIndicator<'static>>,
+    },
+
+    /// The value could not be converted to or from JSON.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    /// Validating the message failed.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+}
+
+/// Serializes values into JSON message payloads, validating them first.
+///
+/// By default, invalid messages are rejected.
+#[derive(Clone, Debug)]
+pub struct Serializer {
+    checker: Checker,
+}
+
+impl Serializer {
+    /// Constructs a serializer that validates messages against the schemas
+    /// for their topics.
+    pub fn new(topics: Topics) -> Self {
+        Self {
+            checker: Checker::new(topics),
+        }
+    }
+
+    /// Sets what to do with messages that fail validation.
+    pub fn with_on_invalid(mut self, on_invalid: OnInvalid) -> Self {
+        self.checker.on_invalid = on_invalid;
+        self
+    }
+
+    /// Sets the options used to validate messages.
+    pub fn with_validate_options(mut self, options: ValidateOptions) -> Self {
+        self.checker.options = options;
+        self
+    }
+
+    /// Serializes a value to be produced on `topic`.
+    pub fn serialize<T: Serialize + ?Sized>(
+        &self,
+        topic: &str,
+        value: &T,
+    ) -> Result<Vec<u8>, KafkaError> {
+        let value = serde_json::to_value(value)?;
+        self.checker.check(topic, &value)?;
+        Ok(serde_json::to_vec(&value)?)
+    }
+}
+
+/// Deserializes values from JSON message payloads, validating them first.
+///
+/// By default, invalid messages are rejected.
+#[derive(Clone, Debug)]
+pub struct Deserializer {
+    checker: Checker,
+}
+
+impl Deserializer {
+    /// Constructs a deserializer that validates messages against the schemas
+    /// for their topics.
+    pub fn new(topics: Topics) -> Self {
+        Self {
+            checker: Checker::new(topics),
+        }
+    }
+
+    /// Sets what to do with messages that fail validation.
+    pub fn with_on_invalid(mut self, on_invalid: OnInvalid) -> Self {
+        self.checker.on_invalid = on_invalid;
+        self
+    }
+
+    /// Sets the options used to validate messages.
+    pub fn with_validate_options(mut self, options: ValidateOptions) -> Self {
+        self.checker.options = options;
+        self
+    }
+
+    /// Deserializes the payload of a message consumed from `topic`.
+    pub fn deserialize<T: DeserializeOwned>(
+        &self,
+        topic: &str,
+        payload: &[u8],
+    ) -> Result<T, KafkaError> {
+        let value: Value = serde_json::from_slice(payload)?;
+        self.checker.check(topic, &value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Checker {
+    topics: Topics,
+    on_invalid: OnInvalid,
+    options: ValidateOptions,
+}
+
+impl Checker {
+    fn new(topics: Topics) -> Self {
+        Self {
+            topics,
+            on_invalid: OnInvalid::Reject,
+            options: ValidateOptions::new(),
+        }
+    }
+
+    fn check(&self, topic: &str, value: &Value) -> Result<(), KafkaError> {
+        let schema = self
+            .topics
+            .get(topic)
+            .ok_or_else(|| KafkaError::UnknownTopic(topic.to_owned()))?;
+
+        let errors = validate(schema, value, self.options.clone())?;
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        match self.on_invalid {
+            OnInvalid::Reject => Err(KafkaError::Invalid {
+                topic: topic.to_owned(),
+                errors: errors
+                    .into_iter()
+                    .map(|error| error.into_owned().into())
+                    .collect(),
+            }),
+            OnInvalid::Warn => {
+                let paths: Vec<_> = errors
+                    .iter()
+                    .map(|error| error.instance_path.to_string())
+                    .collect();
+
+                log::warn!(
+                    target: "jtd::kafka",
+                    "message on topic {:?} is invalid at {}",
+                    topic,
+                    paths.join(", ")
+                );
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn topics() -> Topics {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({ "elements": { "type": "uint8" }})).unwrap(),
+        )
+        .unwrap();
+
+        Topics::new().with_schema("bytes", schema)
+    }
+
+    #[test]
+    fn serializer() {
+        let serializer = Serializer::new(topics());
+        assert_eq!(
+            b"[1,2]".to_vec(),
+            serializer.serialize("bytes", &[1, 2]).unwrap()
+        );
+
+        match serializer.serialize("bytes", &[1, 300, -1]) {
+            Err(KafkaError::Invalid { topic, errors }) => {
+                assert_eq!("bytes", topic);
+                assert_eq!(2, errors.len());
+                assert_eq!(vec!["1"], errors[0].instance_path);
+            }
+            other => panic!("{:?}", other),
+        }
+
+        assert!(matches!(
+            serializer.serialize("other", &[1]),
+            Err(KafkaError::UnknownTopic(topic)) if topic == "other"
+        ));
+
+        let serializer = serializer.with_on_invalid(OnInvalid::Warn);
+        assert_eq!(
+            b"[300]".to_vec(),
+            serializer.serialize("bytes", &[300]).unwrap()
+        );
+    }
+
+    #[test]
+    fn deserializer() {
+        let deserializer = Deserializer::new(topics());
+        assert_eq!(
+            vec![1, 2],
+            deserializer
+                .deserialize::<Vec<u8>>("bytes", b"[1, 2]")
+                .unwrap()
+        );
+
+        assert!(matches!(
+            deserializer.deserialize::<Vec<u8>>("bytes", b"[1, 2"),
+            Err(KafkaError::Json(_))
+        ));
+        assert!(matches!(
+            deserializer.deserialize::<Vec<u16>>("bytes", b"[300]"),
+            Err(KafkaError::Invalid { .. })
+        ));
+
+        // Letting an invalid message through can still fail to deserialize.
+        let deserializer = deserializer.with_on_invalid(OnInvalid::Warn);
+        assert_eq!(
+            vec![300],
+            deserializer
+                .deserialize::<Vec<u16>>("bytes", b"[300]")
+                .unwrap()
+        );
+        assert!(matches!(
+            deserializer.deserialize::<Vec<u8>>("bytes", b"[300]"),
+            Err(KafkaError::Json(_))
+        ));
+    }
+}
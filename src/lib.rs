@@ -58,7 +58,7 @@
 //! and how you can access them with this crate.
 //!
 //! ```
-//! use jtd::{Schema, ValidationErrorIndicator};
+//! use jtd::{Schema, Severity, ValidationErrorIndicator};
 //! use serde_json::json;
 //!
 //! let schema = Schema::from_serde_schema(
@@ -102,9 +102,10 @@
 //!     "phones": ["+44 1234567", 442345678]
 //! });
 //!
-//! // Each error indicator has two pieces of information: the path to the part
-//! // of the input that was rejected (the "instance path"), and the part of the
-//! // schema that rejected it (the "schema path").
+//! // Each error indicator has three pieces of information: the path to the part
+//! // of the input that was rejected (the "instance path"), the part of the
+//! // schema that rejected it (the "schema path"), and how serious the problem
+//! // is (the "severity").
 //! //
 //! // The exact values of the instance path and schema path is specified in the
 //! // JSON Type Definition spec.
@@ -112,25 +113,23 @@
 //!     vec![
 //!         // "age" has the wrong type (required by "/properties/age/type")
 //!         ValidationErrorIndicator {
-//!             instance_path: vec!["age".into()],
-//!             schema_path: vec!["properties".into(), "age".into(), "type".into()],
+//!             instance_path: vec!["age"].into(),
+//!             schema_path: vec!["properties", "age", "type"].into(),
+//!             severity: Severity::Error,
 //!         },
 //!
 //!         // "name" is missing (required by "/properties/name")
 //!         ValidationErrorIndicator {
-//!             instance_path: vec![],
-//!             schema_path: vec!["properties".into(), "name".into()],
+//!             instance_path: Default::default(),
+//!             schema_path: vec!["properties", "name"].into(),
+//!             severity: Severity::Error,
 //!         },
 //!
 //!         // "phones/1" has the wrong type (required by "/properties/phones/elements/type")
 //!         ValidationErrorIndicator {
-//!             instance_path: vec!["phones".into(), "1".into()],
-//!             schema_path: vec![
-//!                 "properties".into(),
-//!                 "phones".into(),
-//!                 "elements".into(),
-//!                 "type".into()
-//!             ],
+//!             instance_path: vec!["phones", "1"].into(),
+//!             schema_path: vec!["properties", "phones", "elements", "type"].into(),
+//!             severity: Severity::Error,
 //!         },
 //!     ],
 //!     jtd::validate(&schema, &input_bad, Default::default()).unwrap(),
@@ -146,8 +145,210 @@
 //!   validate whether a schema is valid, see [`SerdeSchema`],
 //!   [`Schema::from_serde_schema`], and [`Schema::validate`].
 //!
+//! * If you're validating data in bulk and want a machine-readable record of
+//!   the results, see [`report::ReportWriter`]. To summarize the results
+//!   across a whole corpus, see [`stats::aggregate`], or for a summary to
+//!   show people, see [`report::summarize`].
+//!   To turn those statistics into a Markdown or HTML document, enable the
+//!   `render` feature and see the `render` module.
+//!
+//! * If you show validation errors to people, see [`validate_detailed`] to
+//!   describe each one with what was expected and what was found instead.
+//!   To word messages yourself, put them in your schemas' metadata as
+//!   `errorMessage`, and see [`validate_with_messages`].
+//!
+//! * If you need the part of the instance an error is about, or the schema
+//!   that rejected it, see [`InstancePath::get`] and [`SchemaPath::resolve`].
+//!
+//! * If you validate values only to deserialize them afterwards, see
+//!   [`deserialize()`] to do both at once. To enforce a schema while
+//!   deserializing from any serde format, such as CBOR or MessagePack, see the
+//!   [`checked`] module.
+//!
+//! * If you ingest data that comes in several shapes, with nothing to say
+//!   which shape each instance has, see [`dispatch()`] to find the schema an
+//!   instance matches best.
+//!
+//! * If you're reading JSON from files that may have come from Windows, with
+//!   a byte order mark or in UTF-16, see [`validate_bytes`].
+//!
+//! * If you validate large payloads, and would rather not hold all of one in
+//!   memory as a [`serde_json::Value`], see [`validate_from_reader`].
+//!
+//! * If you validate many small records, such as lines of a log, see
+//!   [`validate_str`] to skip building a [`serde_json::Value`] of each, and
+//!   get the byte span of each error. The `raw_value` feature adds
+//!   `validate_raw_value`, for serde_json's `RawValue`.
+//!   To point out errors by line and column instead, such as in an editor,
+//!   see [`validate_str_located`].
+//!
+//! * If your JSON isn't a [`serde_json::Value`], but comes from another
+//!   library, such as simd-json or sonic-rs, implement [`Instance`] for it
+//!   to pass it to [`validate()`] as it is.
+//!
+//! * If you validate many instances at once, see [`validate_batch`]. With the
+//!   `rayon` feature, it validates them in parallel.
+//!
+//! * If you generate code from your schemas, see [`lint::lint`] to check
+//!   that their `description` and `enumDescription` metadata are consistent.
+//!   To keep metadata to keys you've agreed on, see
+//!   [`lint::LintOptions::with_metadata_allowlist`].
+//!
+//! * If you're writing a tool of your own that looks at every part of a
+//!   schema, such as a linter or a documentation generator, see
+//!   [`Schema::walk`], or [`Schema::walk_mut`] to change the schema as you go.
+//!   To rebuild a schema with the same change made to every part of it, see
+//!   [`Schema::map_schemas`].
+//!
 //! * If you want better performance out of [`validate()`], see
-//!   [`ValidateOptions`] to see how you can make validation faster.
+//!   [`ValidateOptions`] to see how you can make validation faster. If you
+//!   validate many instances against the same schema, see [`CompiledSchema`].
+//!
+//! * If you want to phase in a new constraint, see [`Severity`] for how a
+//!   schema can mark it as a warning until data has caught up.
+//!
+//! * If you keep schemas in global variables, see [`static_schema!`].
+//!
+//! * If you review changes to schemas, see [`Schema::equivalent`] to tell
+//!   whether a change affects validation, or only documentation and
+//!   organization.
+//!
+//! * If you're reporting a bug in this crate, but can't share the schema that
+//!   triggers it, see [`anonymize()`] to make a copy of it that's safe to
+//!   share.
+//!
+//! * If you're testing code that turns data of one schema into data of
+//!   another, such as an ETL job, see [`pipeline::check`] to check it against
+//!   both.
+//!
+//! * If you only need a few fields of a large payload, see [`extract`] to
+//!   read them as typed values, checked against the schema, without
+//!   deserializing the rest.
+//!
+//! * If you write schemas by hand, such as in tests, see [`Schema::from_dsl`]
+//!   for a syntax far terser than JSON, like `struct { name: string, age?:
+//!   uint32 }`.
+//!
+//! * If you're writing a tool that can't follow refs, such as a converter to
+//!   another schema language, see [`Schema::expanded`] to substitute them
+//!   with the definitions they refer to.
+//!
+//! * If your schemas are split across files that refer to one another, see
+//!   [`bundle::bundle`] to merge them into a single schema, with the refs
+//!   between files rewritten to refs to definitions.
+//!
+//! * If you want to embed schemas into your program and have them checked at
+//!   build time, enable the `macros` feature and see `include_schema!`. The
+//!   same feature provides `#[conforms]`, which checks at build time that a
+//!   Rust struct matches a schema, and `fixture!`, which does the same for a
+//!   JSON test fixture.
+//!
+//! * If your Rust types are the source of truth, and you want to publish
+//!   schemas for them, see [`JtdSchema`]. With the `macros` feature, it can
+//!   be derived.
+//!
+//! * If you need to express things RFC 8927 can't, enable the `extensions`
+//!   feature. It adds non-standard keywords, such as `keys` for constraining
+//!   the keys of a values-form schema, and allows `enum` to list integers.
+//!   With a `TypeExtensions` registry, `type` can also name your own types,
+//!   such as `"uuid"`. Schemas using them won't work with other JSON Typedef
+//!   implementations.
+//!
+//! * If you want to validate configuration files, and point their authors at
+//!   the exact line and column of any problems, see the [`config`] module. It
+//!   handles JSON, and YAML and TOML with the `yaml` and `toml` features.
+//!
+//! * If your configuration comes in layers, such as defaults overridden by a
+//!   file, see [`merge()`] to combine them the way the schema says they fit
+//!   together.
+//!
+//! * If you're building editor support for schema files, parse them with
+//!   [`Schema::from_str_spanned`] to find where in the file a lint or invalid
+//!   part of the schema is.
+//!
+//! * If your service is configured with environment variables, like
+//!   `APP_DB_HOST`, see the [`env`][mod@env] module to validate them against a schema,
+//!   with problems reported by variable name.
+//!
+//! * If your schemas or data are written by hand, and you'd like to allow
+//!   comments in them, enable the `jsonc` feature for JSON with comments and
+//!   trailing commas, or the `json5` feature for JSON5.
+//!
+//! * If your services send CBOR rather than JSON, enable the `cbor` feature
+//!   and see the `cbor` module to validate it against the same schemas,
+//!   without transcoding it to JSON first.
+//!
+//! * If your data arrives as CSV, enable the `csv` feature and see the `csv`
+//!   module to validate each row against a properties-form schema.
+//!
+//! * If you want to validate query strings or form data with the same schemas
+//!   as JSON request bodies, see the [`query`] module. To fix up an instance
+//!   whose numbers and booleans arrived as strings, see [`coerce()`].
+//!
+//! * If you authenticate requests with JSON Web Tokens, see the [`jwt`] module
+//!   to hold their claims to a schema. The `jsonwebtoken` feature adds support
+//!   for tokens decoded by that crate.
+//!
+//! * If you'd like mobile apps to validate payloads the same way your Rust
+//!   backend does, enable the `uniffi` feature and see the `uniffi` module
+//!   for Kotlin and Swift bindings.
+//!
+//! * If your command-line tool takes JSON arguments, enable the `clap` feature
+//!   and see the `clap` module for a schema-checking value parser.
+//!
+//! * If you load validated records into a database, see [`convert::sql`] to
+//!   keep its tables in line with your schema.
+//!
+//! * If you store documents in MongoDB, see [`convert::mongodb`] to have the
+//!   database enforce your schema too.
+//!
+//! * If you publish OpenAPI documents, or otherwise need JSON Schema, see
+//!   [`json_schema::to_json_schema`] to convert your schemas rather than
+//!   maintain both. If you're moving from JSON Schema, see
+//!   [`from_json_schema`] to convert what JSON Typedef can express, and find
+//!   out what it can't.
+//!
+//! * If you also serve gRPC, see the experimental [`proto`] module to generate
+//!   `.proto` messages from your schemas.
+//!
+//! * If you produce or consume Kafka messages, enable the `kafka` feature and
+//!   see the `kafka` module to validate them against a schema per topic. If
+//!   those messages are encoded with Avro, see [`avro::to_avro_schema`] to
+//!   register an Avro schema made from the same definitions.
+//!
+//! * If your service receives the same schemas over and over, see the
+//!   [`cache`] module to avoid parsing them each time.
+//!
+//! * If you want a long-running service to pick up schema changes without a
+//!   restart, enable the `watch` feature and see `WatchedSchemas`.
+//!
+//! * If you publish schemas as a package, see the [`diff`][mod@diff] module to find
+//!   the changes between two versions of a schema, and write them up as a
+//!   changelog with breaking changes called out. [`diff()`] classifies how
+//!   compatible the two versions are, for gating schema changes in CI.
+//!
+//! * If you fuzz services that consume schema-conformant data, see
+//!   [`fuzzing::seed_corpus`] for generating a starting corpus. To generate
+//!   plausible-looking instances, such as for documentation examples, see the
+//!   [`fake`] module.
+//!
+//! * If you property-test with quickcheck, enable the `quickcheck` feature to
+//!   generate arbitrary schemas, and instances of them.
+//!
+//! * If you'd rather not depend on `chrono`, disable the default `chrono`
+//!   feature. Timestamps are checked by a built-in parser either way, and
+//!   [`ValidateOptions::with_timestamp_validator`] lets you check them with
+//!   whichever date and time crate you use.
+//!
+//! * If you have example data but no schema yet, see [`infer()`] to infer one
+//!   from the examples, as the `jtd-infer` command-line tool does.
+//!
+//! * If you'd like optional properties to have defaults, set them with a
+//!   `default` in each property's metadata, and see [`apply_defaults`] to
+//!   fill them in.
+//!
+//! * If you accept lenient input, but only want to keep the fields your schema
+//!   knows about, see [`prune`].
 //!
 //! # Security considerations
 //!
@@ -162,12 +363,204 @@
 //!
 //! This crate supports that "detect and abort" mechanism via
 //! [`ValidateOptions::with_max_depth`]. Please see that documentation if you're
-//! validating data against untrusted schemas.
+//! validating data against untrusted schemas. To turn away schemas whose refs
+//! loop among themselves when they're loaded, before validating anything, see
+//! [`Schema::validate_with_cycle_check`]. To bound the size of the schemas
+//! themselves, see [`Schema::from_serde_schema_with_limits`].
+//!
+//! Untrusted inputs can still be large. To bound how much work validating one
+//! takes, whatever the schema, see [`ValidateOptions::with_max_string_len`]
+//! and the other per-value limits.
+
+mod anonymize;
+pub mod avro;
+mod batch;
+pub mod bundle;
+mod bytes;
+pub mod cache;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod checked;
+#[cfg(feature = "clap")]
+pub mod clap;
+mod coerce;
+mod compiled;
+pub mod config;
+mod contract;
+pub mod convert;
+#[cfg(feature = "csv")]
+pub mod csv;
+mod de;
+mod defaults;
+mod detailed;
+pub mod diff;
+mod dispatch;
+pub mod env;
+pub mod extract;
+pub mod fake;
+pub mod fuzzing;
+pub mod infer;
+#[cfg(feature = "json5")]
+pub mod json5;
+pub mod json_schema;
+#[cfg(feature = "jsonc")]
+pub mod jsonc;
+mod jtd_schema;
+pub mod jwt;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod lint;
+mod merge;
+mod messages;
+pub mod pipeline;
+pub mod proto;
+mod prune;
+pub mod query;
+#[cfg(feature = "quickcheck")]
+pub mod quickcheck;
+mod raw;
+mod reader;
+mod recorder;
+mod refs;
+#[cfg(feature = "render")]
+pub mod render;
+pub mod report;
+mod rng;
+mod ser;
+pub mod stats;
+mod store;
+mod stream;
+#[cfg(feature = "uniffi")]
+pub mod uniffi;
+#[cfg(feature = "watch")]
+mod watch;
+
+pub use anonymize::*;
+pub use batch::*;
+pub use bytes::*;
+pub use coerce::*;
+pub use compiled::*;
+pub use contract::*;
+pub use de::{deserialize, DeserializeError, DeserializeValueError, Deserializer};
+pub use defaults::*;
+pub use detailed::*;
+pub use diff::diff;
+pub use dispatch::*;
+pub use infer::infer;
+pub use json_schema::from_json_schema;
+pub use jtd_schema::*;
+pub use merge::*;
+pub use messages::*;
+pub use prune::*;
+pub use raw::*;
+pub use reader::*;
+pub use recorder::*;
+pub use ser::{SerializeError, Serializer};
+pub use store::*;
+#[cfg(feature = "watch")]
+pub use watch::*;
+
+// The schema model is in the jtd-core crate, so that jtd-macros can use it to
+// check schemas at build time.
+pub use jtd_core::*;
+
+#[cfg(feature = "macros")]
+pub use jtd_macros::{conforms, fixture, include_schema, JtdSchema};
+
+// UniFFI needs a few items at the root of the crate to tie the exported
+// items of the `uniffi` module together.
+#[cfg(feature = "uniffi")]
+::uniffi::setup_scaffolding!();
+
+/// Declares a global schema, parsed and checked the first time it's used.
+///
+/// `static_schema!(NAME, json)` declares a static named `NAME`, of type
+/// [`LazyLock<Schema>`][`std::sync::LazyLock`]. The first time it's
+/// dereferenced, `json` is parsed as a [`SerdeSchema`], converted with
+/// [`Schema::from_serde_schema`], and checked with [`Schema::validate`]. If any
+/// of those fail, it panics with a message naming the static and saying what
+/// went wrong.
+///
+/// `json` is any expression of type `&'static str`, such as a string literal,
+/// or `include_str!("schema.json")`. The static may be preceded by attributes,
+/// such as doc comments, and a visibility, such as `pub`.
+///
+/// ```
+/// use serde_json::json;
+///
+/// jtd::static_schema!(
+///     /// The schema for users.
+///     pub USER_SCHEMA,
+///     r#"{ "properties": { "name": { "type": "string" }}}"#
+/// );
+///
+/// let instance = json!({ "name": 1 });
+/// let errors = jtd::validate(&USER_SCHEMA, &instance, Default::default()).unwrap();
+/// assert_eq!(1, errors.len());
+/// ```
+///
+/// ```should_panic
+/// jtd::static_schema!(BROKEN, r#"{ "ref": "missing" }"#);
+///
+/// // Panics with: static_schema! BROKEN is invalid: no such definition: "missing"
+/// let _ = &*BROKEN;
+/// ```
+///
+/// If you'd rather find out about a broken schema when compiling, enable the
+/// `macros` feature, and see `include_schema!`.
+#[macro_export]
+macro_rules! static_schema {
+    ($(#[$attr:meta])* $vis:vis $name:ident, $json:expr $(,)?) => {
+        $(#[$attr])*
+        $vis static $name: ::std::sync::LazyLock<$crate::Schema> =
+            ::std::sync::LazyLock::new(|| $crate::__private::static_schema(stringify!($name), $json));
+    };
+}
+
+// Not public API. This module exists for the code generated by jtd's macros.
+#[doc(hidden)]
+pub mod __private {
+    use crate::Schema;
+
+    pub fn schema_from_str(s: &str) -> Schema {
+        Schema::from_serde_schema(serde_json::from_str(s).expect("parse embedded schema"))
+            .expect("construct embedded schema")
+    }
+
+    pub fn static_schema(name: &str, s: &str) -> Schema {
+        let serde_schema = serde_json::from_str(s)
+            .unwrap_or_else(|err| panic!("static_schema! {} is not valid JSON: {}", name, err));
+
+        let schema = Schema::from_serde_schema(serde_schema).unwrap_or_else(|err| {
+            panic!(
+                "static_schema! {} is not a JSON Typedef schema: {}",
+                name, err
+            )
+        });
+
+        if let Err(err) = schema.validate() {
+            panic!("static_schema! {} is invalid: {}", name, err);
+        }
+
+        schema
+    }
+
+    pub fn value_from_str(s: &str) -> serde_json::Value {
+        serde_json::from_str(s).expect("parse embedded fixture")
+    }
+
+    pub fn properties(
+        properties: Vec<(&str, Schema)>,
+        optional_properties: Vec<(&str, Schema)>,
+    ) -> Schema {
+        crate::jtd_schema::properties_schema(properties, optional_properties)
+    }
 
-mod schema;
-mod serde_schema;
-mod validate;
+    pub fn enum_(values: &[&str]) -> Schema {
+        crate::jtd_schema::enum_schema(values)
+    }
 
-pub use schema::*;
-pub use serde_schema::*;
-pub use validate::*;
+    pub fn discriminator(discriminator: &str, mapping: Vec<(&str, Schema)>) -> Schema {
+        crate::jtd_schema::discriminator_schema(discriminator, mapping)
+    }
+}
@@ -0,0 +1,476 @@
+//! Check the metadata that code generators rely on.
+//!
+//! JSON Typedef doesn't say what goes in `metadata`, but code generators such
+//! as [`jtd-codegen`](https://github.com/jsontypedef/json-typedef-codegen)
+//! look for a few keywords there: `description`, which documents a schema,
+//! and `enumDescription`, which documents each value of an enum. Nothing
+//! stops those from drifting out of sync with the rest of the schema, and
+//! code generators silently ignore metadata they can't use.
+//!
+//! [`lint`] finds such problems, so that they can be caught in CI. With
+//! [`lint_with_options`], it can also hold metadata to a vocabulary agreed on
+//! across many schemas; see [`LintOptions::with_metadata_allowlist`].
+//!
+//! ```
+//! use jtd::lint::{Lint, LintKind};
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "metadata": {
+//!         "description": "The state of a light switch.",
+//!         "enumDescription": { "ON": "Switched on.", "DIMMED": "Half on." }
+//!     },
+//!     "enum": ["ON", "OFF"]
+//! })).unwrap()).unwrap();
+//!
+//! let lints = jtd::lint::lint(&schema);
+//! assert_eq!(
+//!     vec![Lint {
+//!         schema_path: vec!["metadata".into(), "enumDescription".into(), "DIMMED".into()],
+//!         kind: LintKind::EnumDescriptionUnknownValue,
+//!     }],
+//!     lints,
+//! );
+//!
+//! assert_eq!(
+//!     "/metadata/enumDescription/DIMMED: enumDescription describes a value not in the enum",
+//!     lints[0].to_string(),
+//! );
+//! ```
+
+use crate::{Schema, SchemaPath};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Options you can pass to [`lint_with_options`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LintOptions {
+    metadata_allowlist: Option<BTreeSet<String>>,
+}
+
+impl LintOptions {
+    /// Constructs a new set of options with all default values.
+    ///
+    /// Equivalent to [`Default::default()`] or calling `with_*` methods with
+    /// default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the only keys that metadata may have.
+    ///
+    /// Organizations with many schemas often agree on what goes in their
+    /// metadata, such as `description` and a few keys of their own. With an
+    /// allowlist, any other key, anywhere in a schema, is reported as a
+    /// [`LintKind::MetadataKeyNotAllowed`], so that a misspelled or
+    /// made-up key gets caught. Whether that rejects the schema or only warns
+    /// about it is up to the caller.
+    ///
+    /// ```
+    /// use jtd::lint::{LintKind, LintOptions};
+    /// use jtd::Schema;
+    /// use serde_json::json;
+    ///
+    /// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+    ///     "metadata": { "description": "A user." },
+    ///     "properties": {
+    ///         "name": {
+    ///             "metadata": { "descripton": "Their name." },
+    ///             "type": "string"
+    ///         }
+    ///     }
+    /// })).unwrap()).unwrap();
+    ///
+    /// let options = LintOptions::new().with_metadata_allowlist(["description", "owner"]);
+    /// let lints = jtd::lint::lint_with_options(&schema, options);
+    ///
+    /// assert_eq!(1, lints.len());
+    /// assert_eq!(LintKind::MetadataKeyNotAllowed, lints[0].kind);
+    /// assert_eq!(
+    ///     "/properties/name/metadata/descripton: metadata key is not allowed",
+    ///     lints[0].to_string(),
+    /// );
+    /// ```
+    ///
+    /// By default, there is no allowlist, and metadata may have any keys.
+    pub fn with_metadata_allowlist<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.metadata_allowlist = Some(keys.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+/// A problem with a schema's metadata, as found by [`lint`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lint {
+    /// The path to the part of the schema with the problem, as a list of JSON
+    /// object keys.
+    pub schema_path: Vec<String>,
+
+    /// What the problem is.
+    pub kind: LintKind,
+}
+
+/// The kinds of [`Lint`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LintKind {
+    /// A `description` isn't a string.
+    DescriptionNotString,
+
+    /// An `enumDescription` is on a schema that isn't of the enum form.
+    EnumDescriptionWithoutEnum,
+
+    /// An `enumDescription` isn't an object.
+    EnumDescriptionNotObject,
+
+    /// An `enumDescription` describes a value that isn't in the enum.
+    EnumDescriptionUnknownValue,
+
+    /// An `enumDescription` describes a value with something other than a
+    /// string.
+    EnumDescriptionNotString,
+
+    /// A value of a discriminator's `mapping` has no `description`.
+    MappingWithoutDescription,
+
+    /// A key of `metadata` isn't in the allowlist passed to
+    /// [`LintOptions::with_metadata_allowlist`].
+    MetadataKeyNotAllowed,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}",
+            SchemaPath::from(&self.schema_path[..]),
+            self.kind
+        )
+    }
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LintKind::DescriptionNotString => "description is not a string",
+            LintKind::EnumDescriptionWithoutEnum => "enumDescription is not on an enum",
+            LintKind::EnumDescriptionNotObject => "enumDescription is not an object",
+            LintKind::EnumDescriptionUnknownValue => {
+                "enumDescription describes a value not in the enum"
+            }
+            LintKind::EnumDescriptionNotString => "enumDescription value is not a string",
+            LintKind::MappingWithoutDescription => "mapping value has no description",
+            LintKind::MetadataKeyNotAllowed => "metadata key is not allowed",
+        })
+    }
+}
+
+/// Checks a schema, its definitions, and all of their sub-schemas for
+/// problems with their metadata.
+///
+/// The checks are:
+///
+/// * A `description` must be a string.
+/// * An `enumDescription` must be on an enum-form schema, and must be an
+///   object whose keys are values of the enum and whose values are strings.
+/// * Each value of a discriminator's `mapping` must have a `description`,
+///   since code generators turn each one into its own type.
+///
+/// Lints are returned in the order they're found, with definitions checked
+/// before the root schema. An empty result means no problems were found.
+pub fn lint(schema: &Schema) -> Vec<Lint> {
+    lint_with_options(schema, LintOptions::new())
+}
+
+/// Like [`lint`], but with additional checks as given by the options.
+pub fn lint_with_options(schema: &Schema, options: LintOptions) -> Vec<Lint> {
+    let mut linter = Linter {
+        options,
+        path: Vec::new(),
+        lints: Vec::new(),
+    };
+
+    for (name, definition) in schema.definitions() {
+        linter.path.push("definitions".to_owned());
+        linter.path.push(name.clone());
+        linter.lint(definition);
+        linter.path.truncate(linter.path.len() - 2);
+    }
+
+    linter.lint(schema);
+    linter.lints
+}
+
+struct Linter {
+    options: LintOptions,
+    path: Vec<String>,
+    lints: Vec<Lint>,
+}
+
+impl Linter {
+    fn lint(&mut self, schema: &Schema) {
+        let metadata = schema.metadata();
+
+        if let Some(ref allowlist) = self.options.metadata_allowlist {
+            let disallowed: Vec<_> = metadata
+                .keys()
+                .filter(|key| !allowlist.contains(*key))
+                .cloned()
+                .collect();
+
+            for key in disallowed {
+                self.push(&["metadata", &key], LintKind::MetadataKeyNotAllowed);
+            }
+        }
+
+        if let Some(description) = metadata.get("description") {
+            if !description.is_string() {
+                self.push(&["metadata", "description"], LintKind::DescriptionNotString);
+            }
+        }
+
+        if let Some(enum_description) = metadata.get("enumDescription") {
+            self.lint_enum_description(schema, enum_description);
+        }
+
+        match schema {
+            Schema::Elements { elements, .. } => self.lint_child(&["elements"], elements),
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            } => {
+                for (name, sub_schema) in properties {
+                    self.lint_child(&["properties", name], sub_schema);
+                }
+
+                for (name, sub_schema) in optional_properties {
+                    self.lint_child(&["optionalProperties", name], sub_schema);
+                }
+            }
+            Schema::Values { values, .. } => self.lint_child(&["values"], values),
+            Schema::Discriminator { mapping, .. } => {
+                for (name, sub_schema) in mapping {
+                    if !sub_schema.metadata().contains_key("description") {
+                        self.push(&["mapping", name], LintKind::MappingWithoutDescription);
+                    }
+
+                    self.lint_child(&["mapping", name], sub_schema);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lint_enum_description(&mut self, schema: &Schema, enum_description: &Value) {
+        let is_member: Box<dyn Fn(&str) -> bool + '_> = match schema {
+            Schema::Enum { enum_, .. } => Box::new(move |value| enum_.contains(value)),
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { enum_, .. } => Box::new(move |value| {
+                value
+                    .parse::<i64>()
+                    .is_ok_and(|value| enum_.contains(&value))
+            }),
+            _ => {
+                return self.push(
+                    &["metadata", "enumDescription"],
+                    LintKind::EnumDescriptionWithoutEnum,
+                )
+            }
+        };
+
+        let descriptions = match enum_description {
+            Value::Object(descriptions) => descriptions,
+            _ => {
+                return self.push(
+                    &["metadata", "enumDescription"],
+                    LintKind::EnumDescriptionNotObject,
+                )
+            }
+        };
+
+        for (value, description) in descriptions {
+            if !is_member(value) {
+                self.push(
+                    &["metadata", "enumDescription", value],
+                    LintKind::EnumDescriptionUnknownValue,
+                );
+            } else if !description.is_string() {
+                self.push(
+                    &["metadata", "enumDescription", value],
+                    LintKind::EnumDescriptionNotString,
+                );
+            }
+        }
+    }
+
+    fn lint_child(&mut self, tokens: &[&str], schema: &Schema) {
+        let len = self.path.len();
+        self.path.extend(tokens.iter().map(|&t| t.to_owned()));
+        self.lint(schema);
+        self.path.truncate(len);
+    }
+
+    fn push(&mut self, tokens: &[&str], kind: LintKind) {
+        let mut schema_path = self.path.clone();
+        schema_path.extend(tokens.iter().map(|&t| t.to_owned()));
+        self.lints.push(Lint { schema_path, kind });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, Lint, LintKind};
+    use crate::Schema;
+    use serde_json::json;
+
+    fn lints(value: serde_json::Value) -> Vec<(String, LintKind)> {
+        let schema = Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap();
+        lint(&schema)
+            .into_iter()
+            .map(|lint| (lint.to_string(), lint.kind))
+            .collect()
+    }
+
+    #[test]
+    fn lints_metadata() {
+        assert_eq!(
+            vec![
+                (
+                    "/definitions/color/metadata/enumDescription/BLUE: enumDescription value is not a string".to_owned(),
+                    LintKind::EnumDescriptionNotString
+                ),
+                (
+                    "/metadata/description: description is not a string".to_owned(),
+                    LintKind::DescriptionNotString
+                ),
+                (
+                    "/mapping/circle: mapping value has no description".to_owned(),
+                    LintKind::MappingWithoutDescription
+                ),
+                (
+                    "/mapping/circle/properties/r/metadata/enumDescription: enumDescription is not on an enum".to_owned(),
+                    LintKind::EnumDescriptionWithoutEnum
+                ),
+                (
+                    "/mapping/square/optionalProperties/c/metadata/enumDescription: enumDescription is not an object".to_owned(),
+                    LintKind::EnumDescriptionNotObject
+                ),
+            ],
+            lints(json!({
+                "definitions": {
+                    "color": {
+                        "metadata": {
+                            "enumDescription": { "RED": "Red.", "BLUE": 1 }
+                        },
+                        "enum": ["RED", "BLUE"]
+                    }
+                },
+                "metadata": { "description": ["A shape."] },
+                "discriminator": "kind",
+                "mapping": {
+                    "circle": {
+                        "properties": {
+                            "r": {
+                                "metadata": { "enumDescription": {} },
+                                "type": "float64"
+                            }
+                        }
+                    },
+                    "square": {
+                        "metadata": { "description": "A square." },
+                        "optionalProperties": {
+                            "c": {
+                                "metadata": { "enumDescription": ["RED"] },
+                                "enum": ["RED"]
+                            }
+                        }
+                    }
+                }
+            }))
+        );
+
+        assert!(lints(json!({
+            "metadata": {
+                "description": "A color.",
+                "enumDescription": { "RED": "Red." }
+            },
+            "enum": ["RED", "BLUE"]
+        }))
+        .is_empty());
+
+        assert_eq!(
+            "/metadata/enumDescription/a~1b: enumDescription describes a value not in the enum",
+            Lint {
+                schema_path: vec!["metadata".into(), "enumDescription".into(), "a/b".into()],
+                kind: LintKind::EnumDescriptionUnknownValue,
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn lints_metadata_allowlist() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "id": { "metadata": { "format": "uuid" }, "type": "string" }
+                },
+                "metadata": { "description": "An order.", "owner": "sales" },
+                "discriminator": "kind",
+                "mapping": {
+                    "retail": {
+                        "metadata": { "description": "A retail order.", "x-legacy": true },
+                        "properties": { "id": { "ref": "id" }}
+                    }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let lints = |options| -> Vec<String> {
+            super::lint_with_options(&schema, options)
+                .into_iter()
+                .map(|lint| lint.to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            vec![
+                "/definitions/id/metadata/format: metadata key is not allowed",
+                "/mapping/retail/metadata/x-legacy: metadata key is not allowed",
+            ],
+            lints(super::LintOptions::new().with_metadata_allowlist(["description", "owner"]))
+        );
+
+        assert_eq!(
+            5,
+            lints(super::LintOptions::new().with_metadata_allowlist(Vec::<String>::new())).len()
+        );
+        assert!(lints(super::LintOptions::new()).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "extensions")]
+    fn lints_int_enums() {
+        assert_eq!(
+            vec![(
+                "/metadata/enumDescription/3: enumDescription describes a value not in the enum"
+                    .to_owned(),
+                LintKind::EnumDescriptionUnknownValue
+            )],
+            lints(json!({
+                "metadata": {
+                    "enumDescription": { "1": "One.", "3": "Three." }
+                },
+                "enum": [1, 2]
+            }))
+        );
+    }
+}
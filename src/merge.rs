@@ -0,0 +1,318 @@
+use crate::refs::resolve;
+use crate::{validate, Schema, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+/// Errors that may arise from [`merge`].
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MergeError {
+    /// The base and the overlay have different tags for the same
+    /// discriminator, so there's no one variant to merge them as.
+    #[error("discriminator tags differ at {instance_path:?}: {base:?} and {overlay:?}")]
+    TagMismatch {
+        /// The path to the object whose tags differ.
+        instance_path: Vec<String>,
+
+        /// The tag of the base.
+        base: String,
+
+        /// The tag of the overlay.
+        overlay: String,
+    },
+
+    /// The merged value failed validation against the schema.
+    #[error("merged value has {} validation errors", .0.len())]
+    Invalid(Vec<ValidationErrorIndicator<'static>>),
+
+    /// Validating the merged value failed.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+}
+
+/// Deep-merges two instances of a schema, with `overlay` taking precedence
+/// over `base`, and validates the result.
+///
+/// This is the building block for layered configuration, such as defaults
+/// overridden by a file, overridden in turn by command-line flags. The schema
+/// decides how each part of the two instances is merged:
+///
+/// * Objects of the properties form are merged property by property. A
+///   property in only one of them is kept as is.
+/// * Objects of the values form are merged key by key, in the same way.
+/// * Objects of the discriminator form are merged as the properties form of
+///   their variant, but only if they have the same tag. Otherwise, this
+///   returns [`MergeError::TagMismatch`].
+/// * Everything else, including arrays, is replaced by the overlay. That
+///   includes `null`, so an overlay can clear a nullable value.
+///
+/// The merged value is then validated against the schema with default
+/// options. Neither `base` nor `overlay` need be valid on their own, such as
+/// when each has only some of the required properties.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "server": {
+///             "properties": {
+///                 "host": { "type": "string" },
+///                 "port": { "type": "uint16" }
+///             }
+///         },
+///         "plugins": { "elements": { "type": "string" }}
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let defaults = json!({
+///     "server": { "host": "localhost", "port": 8080 },
+///     "plugins": ["auth", "log"]
+/// });
+///
+/// let file = json!({ "server": { "port": 80 }, "plugins": ["log"] });
+///
+/// assert_eq!(
+///     json!({
+///         "server": { "host": "localhost", "port": 80 },
+///         "plugins": ["log"]
+///     }),
+///     jtd::merge(&schema, &defaults, &file).unwrap(),
+/// );
+/// ```
+pub fn merge(schema: &Schema, base: &Value, overlay: &Value) -> Result<Value, MergeError> {
+    merge_with_options(schema, base, overlay, ValidateOptions::new())
+}
+
+/// Like [`merge`], but validates the merged value using the given options.
+pub fn merge_with_options(
+    schema: &Schema,
+    base: &Value,
+    overlay: &Value,
+    options: ValidateOptions,
+) -> Result<Value, MergeError> {
+    let mut merger = Merger {
+        root: schema,
+        path: Vec::new(),
+    };
+
+    let merged = merger.merge(schema, base, overlay)?;
+    let errors = validate(schema, &merged, options)?;
+    if !errors.is_empty() {
+        return Err(MergeError::Invalid(
+            errors
+                .into_iter()
+                .map(|error| error.into_owned().into())
+                .collect(),
+        ));
+    }
+
+    Ok(merged)
+}
+
+struct Merger<'a> {
+    root: &'a Schema,
+    path: Vec<String>,
+}
+
+impl<'a> Merger<'a> {
+    fn merge(
+        &mut self,
+        schema: &'a Schema,
+        base: &Value,
+        overlay: &Value,
+    ) -> Result<Value, MergeError> {
+        match (base, overlay) {
+            (Value::Object(base), Value::Object(overlay)) => {
+                self.merge_object(schema, base, overlay)
+            }
+            _ => Ok(overlay.clone()),
+        }
+    }
+
+    fn merge_object(
+        &mut self,
+        schema: &'a Schema,
+        base: &Map<String, Value>,
+        overlay: &Map<String, Value>,
+    ) -> Result<Value, MergeError> {
+        match resolve(self.root, schema).0 {
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            } => self.merge_properties(base, overlay, |name| {
+                properties
+                    .get(name)
+                    .or_else(|| optional_properties.get(name))
+            }),
+
+            Schema::Values { values, .. } => self.merge_properties(base, overlay, |_| Some(values)),
+
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } => {
+                let base_tag = base.get(discriminator).and_then(Value::as_str);
+                let overlay_tag = overlay.get(discriminator).and_then(Value::as_str);
+
+                let tag = match (base_tag, overlay_tag) {
+                    (Some(base_tag), Some(overlay_tag)) if base_tag != overlay_tag => {
+                        return Err(MergeError::TagMismatch {
+                            instance_path: self.path.clone(),
+                            base: base_tag.to_owned(),
+                            overlay: overlay_tag.to_owned(),
+                        });
+                    }
+                    (base_tag, overlay_tag) => overlay_tag.or(base_tag),
+                };
+
+                // The discriminator isn't among the variant's properties, so
+                // it's merged like any other property that isn't, by taking
+                // the overlay's.
+                match tag.and_then(|tag| mapping.get(tag)) {
+                    Some(variant) => self.merge_object(variant, base, overlay),
+                    None => Ok(Value::Object(overlay.clone())),
+                }
+            }
+
+            _ => Ok(Value::Object(overlay.clone())),
+        }
+    }
+
+    fn merge_properties(
+        &mut self,
+        base: &Map<String, Value>,
+        overlay: &Map<String, Value>,
+        sub_schema: impl Fn(&str) -> Option<&'a Schema>,
+    ) -> Result<Value, MergeError> {
+        let mut merged = base.clone();
+        for (name, overlay_value) in overlay {
+            let value = match (merged.get(name), sub_schema(name)) {
+                (Some(base_value), Some(sub_schema)) => {
+                    self.path.push(name.clone());
+                    let value = self.merge(sub_schema, base_value, overlay_value)?;
+                    self.path.pop();
+                    value
+                }
+                _ => overlay_value.clone(),
+            };
+
+            merged.insert(name.clone(), value);
+        }
+
+        Ok(Value::Object(merged))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "limits": {
+                        "optionalProperties": {
+                            "cpu": { "type": "float64" },
+                            "memory": { "type": "uint32" }
+                        }
+                    }
+                },
+                "properties": {
+                    "services": {
+                        "values": {
+                            "discriminator": "kind",
+                            "mapping": {
+                                "web": {
+                                    "properties": { "port": { "type": "uint16" }},
+                                    "optionalProperties": { "limits": { "ref": "limits" }}
+                                },
+                                "worker": {
+                                    "properties": { "queue": { "type": "string" }}
+                                }
+                            }
+                        }
+                    }
+                },
+                "optionalProperties": {
+                    "owner": { "type": "string", "nullable": true }
+                },
+                "additionalProperties": true
+            }))
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn merges_by_schema() {
+        let base = json!({
+            "services": {
+                "api": { "kind": "web", "port": 80, "limits": { "cpu": 1, "memory": 512 }},
+                "jobs": { "kind": "worker", "queue": "default" }
+            },
+            "owner": "ops",
+            "extra": { "a": 1 }
+        });
+
+        let overlay = json!({
+            "services": {
+                "api": { "port": 8080, "limits": { "memory": 1024 }},
+                "mail": { "kind": "worker", "queue": "mail" }
+            },
+            "owner": null,
+            "extra": { "b": 2 }
+        });
+
+        assert_eq!(
+            json!({
+                "services": {
+                    "api": {
+                        "kind": "web",
+                        "port": 8080,
+                        "limits": { "cpu": 1, "memory": 1024 }
+                    },
+                    "jobs": { "kind": "worker", "queue": "default" },
+                    "mail": { "kind": "worker", "queue": "mail" }
+                },
+                "owner": null,
+                "extra": { "b": 2 }
+            }),
+            merge(&schema(), &base, &overlay).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_tags_and_invalid_results() {
+        let base = json!({ "services": { "api": { "kind": "web", "port": 80 }}});
+
+        assert_eq!(
+            Err(MergeError::TagMismatch {
+                instance_path: vec!["services".into(), "api".into()],
+                base: "web".into(),
+                overlay: "worker".into(),
+            }),
+            merge(
+                &schema(),
+                &base,
+                &json!({ "services": { "api": { "kind": "worker", "queue": "a" }}})
+            )
+        );
+
+        match merge(
+            &schema(),
+            &base,
+            &json!({ "services": { "api": { "port": -1 }}}),
+        ) {
+            Err(MergeError::Invalid(errors)) => {
+                assert_eq!(1, errors.len());
+                assert_eq!(vec!["services", "api", "port"], errors[0].instance_path);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+}
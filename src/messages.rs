@@ -0,0 +1,169 @@
+use crate::{validate, Schema, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use jtd_core::__private::resolve;
+use serde_json::Value;
+use std::fmt;
+
+/// A validation error, along with the message the schema gives for it, if
+/// any.
+///
+/// Schemas give messages for the errors they find with the `errorMessage`
+/// property of their metadata. The message for an error is that of the
+/// schema that rejected the instance, or, if that schema doesn't have one,
+/// that of the nearest schema above it along the error's schema path. Errors
+/// about missing properties are found by the schema the property is missing
+/// from, not by the property's own schema, so messages for them come from the
+/// former.
+///
+/// Get these from [`validate_with_messages`], or from errors found some other
+/// way with [`ErrorWithMessage::new`].
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "email": {
+///             "type": "string",
+///             "metadata": { "errorMessage": "Enter an email address" }
+///         },
+///         "tags": {
+///             "elements": { "type": "string" }
+///         }
+///     },
+///     "metadata": { "errorMessage": "Enter a user" }
+/// })).unwrap()).unwrap();
+///
+/// let instance = json!({ "email": 1, "tags": [2] });
+/// let errors = jtd::validate_with_messages(&schema, &instance, Default::default()).unwrap();
+///
+/// assert_eq!(Some("Enter an email address"), errors[0].message);
+/// assert_eq!(Some("Enter a user"), errors[1].message);
+///
+/// // Displaying an error says where it is, too.
+/// assert_eq!("Enter an email address at /email", errors[0].to_string());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorWithMessage<'a> {
+    /// The error the message is for.
+    pub error: ValidationErrorIndicator<'a>,
+
+    /// The message the schema gives for the error, if any.
+    pub message: Option<&'a str>,
+}
+
+impl<'a> ErrorWithMessage<'a> {
+    /// The metadata property with which schemas give messages for errors.
+    pub const METADATA_KEY: &'static str = "errorMessage";
+
+    /// Finds the message `schema` gives for an error.
+    ///
+    /// `error` should have come from validating an instance against `schema`;
+    /// if it didn't, the message found may not make sense, but this function
+    /// doesn't panic. Values of `errorMessage` that aren't strings are
+    /// ignored.
+    pub fn new(schema: &'a Schema, error: ValidationErrorIndicator<'a>) -> Self {
+        // The schema that rejected the instance is where the schema path stops
+        // leading to other schemas. From there, look back up the path.
+        let depth = match resolve(schema, &error.schema_path) {
+            Some((_, keywords)) => error.schema_path.len() - keywords.len(),
+            None => 0,
+        };
+
+        let message = (0..=depth).rev().find_map(|len| {
+            schema
+                .get_at_path(&error.schema_path[..len])?
+                .metadata()
+                .get(Self::METADATA_KEY)
+                .and_then(Value::as_str)
+        });
+
+        Self { error, message }
+    }
+}
+
+impl fmt::Display for ErrorWithMessage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.message {
+            Some(message) if self.error.instance_path.is_empty() => f.write_str(message),
+            Some(message) => write!(f, "{} at {}", message, self.error.instance_path),
+            None => write!(
+                f,
+                "value at {:?} rejected by schema at {:?}",
+                self.error.instance_path.to_string(),
+                self.error.schema_path.to_string()
+            ),
+        }
+    }
+}
+
+/// Validates an instance against a schema, and finds the message the schema
+/// gives for each error found.
+///
+/// This is [`validate()`][`crate::validate()`], followed by
+/// [`ErrorWithMessage::new`] for each error. See [`ErrorWithMessage`] for an
+/// example.
+pub fn validate_with_messages<'a>(
+    schema: &'a Schema,
+    instance: &'a Value,
+    options: ValidateOptions,
+) -> Result<Vec<ErrorWithMessage<'a>>, ValidateError> {
+    Ok(validate(schema, instance, options)?
+        .into_iter()
+        .map(|error| ErrorWithMessage::new(schema, error))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn nearest_message() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "port": {
+                        "type": "uint16",
+                        "metadata": { "errorMessage": "Not a port" }
+                    }
+                },
+                "properties": {
+                    "listen": {
+                        "elements": { "ref": "port" },
+                        "metadata": { "errorMessage": "Not a list of ports" }
+                    },
+                    "host": {
+                        "type": "string",
+                        "metadata": { "errorMessage": 1 }
+                    },
+                    "name": {
+                        "type": "string",
+                        "metadata": { "errorMessage": "Not a name" }
+                    }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let instance = json!({ "listen": [80, -1], "host": 1, "extra": true });
+        let errors = validate_with_messages(&schema, &instance, Default::default()).unwrap();
+        let messages: Vec<_> = errors.iter().map(|error| error.to_string()).collect();
+
+        assert_eq!(
+            vec![
+                r#"value at "/host" rejected by schema at "/properties/host/type""#,
+                "Not a port at /listen/1",
+                r#"value at "" rejected by schema at "/properties/name""#,
+                r#"value at "/extra" rejected by schema at """#,
+            ],
+            messages
+        );
+
+        let instance = json!({ "listen": 80, "host": "", "name": "" });
+        let errors = validate_with_messages(&schema, &instance, Default::default()).unwrap();
+        assert_eq!(Some("Not a list of ports"), errors[0].message);
+    }
+}
@@ -0,0 +1,244 @@
+//! Test a transformation against the contracts on either side of it.
+//!
+//! ETL jobs, message translators, and API adapters all take data satisfying
+//! one schema and turn it into data that should satisfy another. [`check`]
+//! runs such a transformation over a corpus of inputs, and reports every
+//! input that breaks either contract, along with which side it broke:
+//!
+//! ```
+//! use jtd::pipeline::Stage;
+//! use jtd::Schema;
+//! use serde_json::{json, Value};
+//!
+//! let input_schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": { "first": { "type": "string" }, "last": { "type": "string" }}
+//! })).unwrap()).unwrap();
+//!
+//! let output_schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": { "name": { "type": "string" }}
+//! })).unwrap()).unwrap();
+//!
+//! // A buggy transformation, which drops the name if there's no last name.
+//! let transform = |input: &Value| match (input["first"].as_str(), input["last"].as_str()) {
+//!     (Some(first), Some(last)) if !last.is_empty() => {
+//!         json!({ "name": format!("{} {}", first, last) })
+//!     }
+//!     _ => json!({}),
+//! };
+//!
+//! let corpus = vec![
+//!     json!({ "first": "Ada", "last": "Lovelace" }),
+//!     json!({ "first": "Plato", "last": "" }),
+//!     json!({ "first": "Nobody" }),
+//! ];
+//!
+//! let report = jtd::pipeline::check(&input_schema, &output_schema, transform, &corpus).unwrap();
+//! assert_eq!(3, report.checked);
+//! assert_eq!(2, report.failures.len());
+//!
+//! assert_eq!(1, report.failures[0].index);
+//! assert_eq!(Stage::Output, report.failures[0].stage);
+//! assert_eq!(
+//!     "output of input 1: value at \"\" rejected by schema at \"/properties/name\"",
+//!     report.failures[0].to_string(),
+//! );
+//!
+//! // Inputs that break the input contract aren't transformed.
+//! assert_eq!(2, report.failures[1].index);
+//! assert_eq!(Stage::Input, report.failures[1].stage);
+//! ```
+
+use crate::{
+    validate_owned, InstancePath, OwnedValidationErrorIndicator, Schema, SchemaPath, ValidateError,
+    ValidateOptions,
+};
+use serde_json::Value;
+use std::fmt;
+
+/// The side of a transformation on which a [`Failure`] happened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// The input didn't satisfy the input schema. The transformation wasn't
+    /// applied to it.
+    Input,
+
+    /// The input satisfied the input schema, but the transformation's output
+    /// didn't satisfy the output schema.
+    Output,
+}
+
+/// An input for which a transformation broke a contract, as found by
+/// [`check`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+    /// The position of the input in the corpus, starting from zero.
+    pub index: usize,
+
+    /// Which contract was broken.
+    pub stage: Stage,
+
+    /// The validation errors in the input or output, depending on `stage`.
+    pub errors: Vec<OwnedValidationErrorIndicator>,
+}
+
+/// The results of [`check`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The number of inputs checked.
+    pub checked: usize,
+
+    /// The inputs that broke a contract, in the order they were checked.
+    pub failures: Vec<Failure>,
+}
+
+impl Report {
+    /// Whether every input satisfied the input schema, and was transformed
+    /// into an output that satisfied the output schema.
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.stage {
+            Stage::Input => write!(f, "input {}: ", self.index)?,
+            Stage::Output => write!(f, "output of input {}: ", self.index)?,
+        }
+
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+
+            write!(
+                f,
+                "value at {:?} rejected by schema at {:?}",
+                InstancePath::from(&error.instance_path[..]).to_string(),
+                SchemaPath::from(&error.schema_path[..]).to_string()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks a transformation against the schemas of its inputs and outputs.
+///
+/// Each input in `corpus` is validated against `input_schema`. If it's
+/// invalid, that's reported as a [`Stage::Input`] failure. Otherwise, it's
+/// passed to `transform`, and the output is validated against
+/// `output_schema`, with any errors reported as a [`Stage::Output`] failure.
+///
+/// Validation uses the default [`ValidateOptions`]; see
+/// [`check_with_options`] to change them. See the [module
+/// documentation][`crate::pipeline`] for an example.
+pub fn check<'c, F, I>(
+    input_schema: &Schema,
+    output_schema: &Schema,
+    transform: F,
+    corpus: I,
+) -> Result<Report, ValidateError>
+where
+    F: Fn(&Value) -> Value,
+    I: IntoIterator<Item = &'c Value>,
+{
+    check_with_options(
+        input_schema,
+        output_schema,
+        transform,
+        corpus,
+        ValidateOptions::new(),
+    )
+}
+
+/// Like [`check`], but with the given options for validating both inputs and
+/// outputs.
+pub fn check_with_options<'c, F, I>(
+    input_schema: &Schema,
+    output_schema: &Schema,
+    transform: F,
+    corpus: I,
+    options: ValidateOptions,
+) -> Result<Report, ValidateError>
+where
+    F: Fn(&Value) -> Value,
+    I: IntoIterator<Item = &'c Value>,
+{
+    let mut report = Report::default();
+
+    for (index, input) in corpus.into_iter().enumerate() {
+        report.checked += 1;
+
+        let errors = validate_owned(input_schema, input, options.clone())?;
+        let (stage, errors) = if errors.is_empty() {
+            let output = transform(input);
+            (
+                Stage::Output,
+                validate_owned(output_schema, &output, options.clone())?,
+            )
+        } else {
+            (Stage::Input, errors)
+        };
+
+        if !errors.is_empty() {
+            report.failures.push(Failure {
+                index,
+                stage,
+                errors,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn attributes_failures_to_stages() {
+        let input_schema = schema(json!({ "elements": { "type": "uint8" }}));
+        let output_schema = schema(json!({ "values": { "type": "uint8" }}));
+
+        // Outputs each element under its index, doubled.
+        let transform = |input: &Value| {
+            Value::Object(
+                input
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, n)| (i.to_string(), json!(n.as_u64().unwrap() * 2)))
+                    .collect(),
+            )
+        };
+
+        let corpus = vec![json!([1, 2]), json!(["a"]), json!([100, 200])];
+        let report = check(&input_schema, &output_schema, transform, &corpus).unwrap();
+
+        assert!(!report.is_ok());
+        assert_eq!(3, report.checked);
+        assert_eq!(
+            vec![
+                "input 1: value at \"/0\" rejected by schema at \"/elements/type\"",
+                "output of input 2: value at \"/1\" rejected by schema at \"/values/type\"",
+            ],
+            report
+                .failures
+                .iter()
+                .map(|failure| failure.to_string())
+                .collect::<Vec<_>>()
+        );
+
+        let report = check(&input_schema, &output_schema, transform, &corpus[..1]).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(1, report.checked);
+    }
+}
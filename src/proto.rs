@@ -0,0 +1,663 @@
+//! Convert schemas into Protocol Buffers.
+//!
+//! **This module is experimental.** What it generates may change in minor
+//! releases, as it's tried against more schemas.
+//!
+//! Services that speak gRPC describe their messages with Protocol Buffers.
+//! [`to_proto`] generates a `.proto` file, in proto3 syntax, with the messages
+//! and enums that a schema describes, so that JSON Typedef can remain the
+//! source of truth for both JSON and gRPC APIs.
+//!
+//! ```
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "definitions": {
+//!         "role": { "enum": ["admin", "member"] }
+//!     },
+//!     "properties": {
+//!         "userId": { "type": "string" },
+//!         "role": { "ref": "role" }
+//!     },
+//!     "optionalProperties": {
+//!         "tags": { "elements": { "type": "string" }}
+//!     }
+//! })).unwrap()).unwrap();
+//!
+//! let options = jtd::proto::ProtoOptions::new()
+//!     .with_package("acme.users.v1")
+//!     .with_root_name("User");
+//!
+//! assert_eq!(
+//!     r#"syntax = "proto3";
+//!
+//! package acme.users.v1;
+//!
+//! message User {
+//!   Role role = 1;
+//!   string user_id = 2;
+//!   repeated string tags = 3;
+//! }
+//!
+//! enum Role {
+//!   ROLE_UNSPECIFIED = 0;
+//!   ROLE_ADMIN = 1;
+//!   ROLE_MEMBER = 2;
+//! }
+//! "#,
+//!     jtd::proto::to_proto_with_options(&schema, options).unwrap(),
+//! );
+//! ```
+
+use crate::{Schema, Type};
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+/// Options you can pass to [`to_proto_with_options`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtoOptions {
+    package: Option<String>,
+    root_name: Option<String>,
+}
+
+impl ProtoOptions {
+    /// Constructs a new set of options with all default values.
+    ///
+    /// Equivalent to [`Default::default()`] or calling `with_*` methods with
+    /// default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the package of the `.proto` file, such as `acme.users.v1`.
+    ///
+    /// By default, the file has no `package` statement.
+    pub fn with_package(mut self, package: impl Into<String>) -> Self {
+        self.package = Some(package.into());
+        self
+    }
+
+    /// Sets the name of the message generated for the root schema.
+    ///
+    /// By default, it's `Root`.
+    pub fn with_root_name(mut self, root_name: impl Into<String>) -> Self {
+        self.root_name = Some(root_name.into());
+        self
+    }
+}
+
+/// Errors that may arise from [`to_proto`] and [`to_proto_with_options`].
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum ProtoError {
+    /// The root schema is not of the properties or discriminator form, even
+    /// after following any `ref`, so it can't be a message.
+    #[error("root schema is not of the properties or discriminator form")]
+    RootNotMessage,
+
+    /// The schema has a definition that refers to itself, without a message
+    /// in between, so its type would be infinitely large.
+    ///
+    /// The contained string is the name of the definition.
+    #[error("definition refers to itself: {0:?}")]
+    RecursiveRef(String),
+
+    /// The schema has a `ref` to a definition that doesn't exist.
+    #[error("no such definition: {0:?}")]
+    NoSuchDefinition(String),
+}
+
+/// Generates a `.proto` file from a schema, with default options.
+///
+/// This is [`to_proto_with_options`] with [`ProtoOptions::new`].
+pub fn to_proto(schema: &Schema) -> Result<String, ProtoError> {
+    to_proto_with_options(schema, ProtoOptions::new())
+}
+
+/// Generates a `.proto` file from a schema.
+///
+/// The root schema becomes a message, and each definition of the properties
+/// or discriminator form becomes a message of its own, as does each definition
+/// of the enum form an enum. Other definitions are written out wherever
+/// they're referred to.
+///
+/// | Schema                             | Protocol Buffers                                  |
+/// | ---------------------------------- | ------------------------------------------------- |
+/// | empty                              | `google.protobuf.Value`                           |
+/// | `boolean`                          | `bool`                                            |
+/// | `int8`, `int16`, `int32`           | `int32`                                           |
+/// | `uint8`, `uint16`, `uint32`        | `uint32`                                          |
+/// | `float32`                          | `float`                                           |
+/// | `float64`                          | `double`                                          |
+/// | `string`                           | `string`                                          |
+/// | `timestamp`                        | `google.protobuf.Timestamp`                       |
+/// | `enum`                             | an `enum`, with a zero `UNSPECIFIED` value first  |
+/// | `elements`                         | `repeated`                                        |
+/// | `properties`, `optionalProperties` | a `message`, with a field per property            |
+/// | `values`                           | `map<string, ...>`                                |
+/// | `discriminator`                    | a `message` with a `oneof`, and a message per tag |
+///
+/// Fields are numbered in order, required properties first, in alphabetical
+/// order. Adding a property to a schema therefore renumbers the fields after
+/// it, which breaks compatibility with messages encoded before; pin the
+/// output in version control to notice when that happens. Optional
+/// properties, and nullable ones other than arrays and maps, become
+/// `optional` fields.
+///
+/// Names are converted to the case Protocol Buffers expect, and fields whose
+/// JSON name would then differ from the property are given a `json_name`.
+/// Protocol Buffers' JSON mapping doesn't always match the schema's otherwise:
+/// enum values are written in their converted case, and a discriminator's
+/// variant is nested under a field named after its tag, rather than beside
+/// the tag. Arrays of arrays, and the like, which Protocol Buffers can't
+/// express, are nested in a message with a single `value` field.
+pub fn to_proto_with_options(schema: &Schema, options: ProtoOptions) -> Result<String, ProtoError> {
+    let mut generator = Generator {
+        root: schema,
+        imports: BTreeSet::new(),
+        refs: Vec::new(),
+    };
+
+    let root_name = options.root_name.as_deref().unwrap_or("Root");
+    let root = match schema {
+        Schema::Ref { ref_, .. } => generator.definition(ref_)?,
+        _ => schema,
+    };
+
+    if !matches!(
+        root,
+        Schema::Properties { .. } | Schema::Discriminator { .. }
+    ) {
+        return Err(ProtoError::RootNotMessage);
+    }
+
+    let mut decls = vec![generator.decl(root_name, schema, root)?];
+    for (name, definition) in schema.definitions() {
+        if is_named(definition) {
+            decls.push(generator.decl(&pascal_case(name), definition, definition)?);
+        }
+    }
+
+    let mut out = "syntax = \"proto3\";\n".to_owned();
+    if let Some(package) = &options.package {
+        out.push_str(&format!("\npackage {};\n", package));
+    }
+
+    if !generator.imports.is_empty() {
+        out.push('\n');
+        for import in &generator.imports {
+            out.push_str(&format!("import \"{}\";\n", import));
+        }
+    }
+
+    for decl in decls {
+        out.push('\n');
+        out.push_str(&decl);
+    }
+
+    Ok(out)
+}
+
+// Whether a schema becomes a message or enum of its own, rather than the type
+// of a field.
+fn is_named(schema: &Schema) -> bool {
+    matches!(
+        schema,
+        Schema::Enum { .. } | Schema::Properties { .. } | Schema::Discriminator { .. }
+    )
+}
+
+struct Generator<'a> {
+    root: &'a Schema,
+    imports: BTreeSet<&'static str>,
+
+    // The definitions being written out in place of a ref, innermost last.
+    refs: Vec<&'a str>,
+}
+
+impl<'a> Generator<'a> {
+    fn definition(&self, ref_: &str) -> Result<&'a Schema, ProtoError> {
+        self.root
+            .definitions()
+            .get(ref_)
+            .ok_or_else(|| ProtoError::NoSuchDefinition(ref_.to_owned()))
+    }
+
+    // Declares a message or enum for a schema of one of the forms for which
+    // `is_named` holds. `described` is the schema whose description, if any,
+    // becomes a comment.
+    fn decl(
+        &mut self,
+        name: &str,
+        described: &Schema,
+        schema: &'a Schema,
+    ) -> Result<String, ProtoError> {
+        let mut lines = Vec::new();
+        let mut nested = Vec::new();
+
+        let keyword = match schema {
+            Schema::Enum { enum_, .. } => {
+                let prefix = upper_snake_case(name);
+                lines.push(format!("{}_UNSPECIFIED = 0;", prefix));
+                for (i, value) in enum_.iter().enumerate() {
+                    lines.push(format!(
+                        "{}_{} = {};",
+                        prefix,
+                        upper_snake_case(value),
+                        i + 1
+                    ));
+                }
+
+                "enum"
+            }
+
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            } => {
+                let fields = properties
+                    .iter()
+                    .map(|(name, schema)| (name, schema, false))
+                    .chain(
+                        optional_properties
+                            .iter()
+                            .map(|(name, schema)| (name, schema, true)),
+                    );
+
+                for (i, (property, sub_schema, optional)) in fields.enumerate() {
+                    let field = snake_case(property);
+                    let type_ =
+                        self.field_type(&pascal_case(property), sub_schema, &mut nested, true)?;
+
+                    let label = if type_.starts_with("repeated ") || type_.starts_with("map<") {
+                        ""
+                    } else if optional || sub_schema.nullable() {
+                        "optional "
+                    } else {
+                        ""
+                    };
+
+                    let json_name = if json_name(&field) == *property {
+                        String::new()
+                    } else {
+                        format!(" [json_name = {}]", quote(property))
+                    };
+
+                    lines.extend(comment(sub_schema));
+                    lines.push(format!(
+                        "{}{} {} = {}{};",
+                        label,
+                        type_,
+                        field,
+                        i + 1,
+                        json_name
+                    ));
+                }
+
+                "message"
+            }
+
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } => {
+                lines.push(format!("oneof {} {{", snake_case(discriminator)));
+                for (i, (tag, sub_schema)) in mapping.iter().enumerate() {
+                    let variant = pascal_case(tag);
+                    nested.push(self.decl(&variant, sub_schema, sub_schema)?);
+                    lines.push(format!("  {} {} = {};", variant, snake_case(tag), i + 1));
+                }
+                lines.push("}".to_owned());
+
+                "message"
+            }
+
+            _ => unreachable!("only named schemas are declared"),
+        };
+
+        let mut out: String = comment(described)
+            .into_iter()
+            .map(|line| line + "\n")
+            .collect();
+
+        out.push_str(&format!("{} {} {{\n", keyword, name));
+        for line in lines {
+            out.push_str(&format!("  {}\n", line));
+        }
+
+        for decl in nested {
+            out.push('\n');
+            out.push_str(&indent(&decl));
+        }
+
+        out.push_str("}\n");
+        Ok(out)
+    }
+
+    // The type of a field for a schema, declaring any messages or enums it
+    // needs in `nested`. `hint` names those declarations, and `repeatable` is
+    // whether the field can be repeated or a map.
+    fn field_type(
+        &mut self,
+        hint: &str,
+        schema: &'a Schema,
+        nested: &mut Vec<String>,
+        repeatable: bool,
+    ) -> Result<String, ProtoError> {
+        Ok(match schema {
+            Schema::Empty { .. } => {
+                self.imports.insert("google/protobuf/struct.proto");
+                "google.protobuf.Value".to_owned()
+            }
+
+            Schema::Ref { ref_, .. } => {
+                let definition = self.definition(ref_)?;
+                if is_named(definition) {
+                    return Ok(pascal_case(ref_));
+                }
+
+                if self.refs.contains(&ref_.as_str()) {
+                    return Err(ProtoError::RecursiveRef(ref_.clone()));
+                }
+
+                self.refs.push(ref_);
+                let type_ = self.field_type(hint, definition, nested, repeatable)?;
+                self.refs.pop();
+
+                type_
+            }
+
+            Schema::Type { type_, .. } => match type_ {
+                Type::Boolean => "bool",
+                Type::Int8 | Type::Int16 | Type::Int32 => "int32",
+                Type::Uint8 | Type::Uint16 | Type::Uint32 => "uint32",
+                Type::Float32 => "float",
+                Type::Float64 => "double",
+                Type::String => "string",
+                Type::Timestamp => {
+                    self.imports.insert("google/protobuf/timestamp.proto");
+                    "google.protobuf.Timestamp"
+                }
+                #[cfg(feature = "extensions")]
+                Type::Custom(_) => {
+                    self.imports.insert("google/protobuf/struct.proto");
+                    "google.protobuf.Value"
+                }
+            }
+            .to_owned(),
+
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { .. } => "int64".to_owned(),
+
+            Schema::Elements { .. } | Schema::Values { .. } if !repeatable => {
+                let mut wrapper_nested = Vec::new();
+                let type_ = self.field_type(hint, schema, &mut wrapper_nested, true)?;
+
+                let mut decl = format!("message {} {{\n  {} value = 1;\n", hint, type_);
+                for wrapper_decl in wrapper_nested {
+                    decl.push('\n');
+                    decl.push_str(&indent(&wrapper_decl));
+                }
+                decl.push_str("}\n");
+
+                nested.push(decl);
+                hint.to_owned()
+            }
+
+            Schema::Elements { elements, .. } => format!(
+                "repeated {}",
+                self.field_type(&format!("{}Item", hint), elements, nested, false)?
+            ),
+
+            Schema::Values { values, .. } => format!(
+                "map<string, {}>",
+                self.field_type(&format!("{}Value", hint), values, nested, false)?
+            ),
+
+            Schema::Enum { .. } | Schema::Properties { .. } | Schema::Discriminator { .. } => {
+                nested.push(self.decl(hint, schema, schema)?);
+                hint.to_owned()
+            }
+        })
+    }
+}
+
+// Indents a declaration to nest it in another.
+fn indent(decl: &str) -> String {
+    decl.lines()
+        .map(|line| {
+            if line.is_empty() {
+                "\n".to_owned()
+            } else {
+                format!("  {}\n", line)
+            }
+        })
+        .collect()
+}
+
+// The lines of a comment holding the description of a schema, if it has one.
+fn comment(schema: &Schema) -> Vec<String> {
+    match schema
+        .metadata()
+        .get("description")
+        .and_then(|d| d.as_str())
+    {
+        Some(description) => description
+            .lines()
+            .map(|line| format!("// {}", line).trim_end().to_owned())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// Splits a name like "userId", "user_id" or "user-id" into lowercase words.
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut prev: Option<char> = None;
+    for c in name.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+        } else {
+            if c.is_ascii_uppercase()
+                && !word.is_empty()
+                && prev.is_some_and(|prev| prev.is_ascii_lowercase() || prev.is_ascii_digit())
+            {
+                words.push(std::mem::take(&mut word));
+            }
+
+            word.push(c.to_ascii_lowercase());
+        }
+
+        prev = Some(c);
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    // Identifiers must start with a letter.
+    match words.first() {
+        None => words.push("x".to_owned()),
+        Some(first) if first.starts_with(|c: char| c.is_ascii_digit()) => {
+            words.insert(0, "x".to_owned())
+        }
+        _ => {}
+    }
+
+    words
+}
+
+fn pascal_case(name: &str) -> String {
+    words(name)
+        .iter()
+        .map(|word| word[..1].to_ascii_uppercase() + &word[1..])
+        .collect()
+}
+
+fn snake_case(name: &str) -> String {
+    words(name).join("_")
+}
+
+fn upper_snake_case(name: &str) -> String {
+    snake_case(name).to_ascii_uppercase()
+}
+
+// The name Protocol Buffers gives a field in JSON, unless told otherwise.
+fn json_name(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut upper = false;
+    for c in field.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+
+    out
+}
+
+// Quotes a string as a Protocol Buffers string literal.
+fn quote(s: &str) -> String {
+    let mut out = "\"".to_owned();
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: serde_json::Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn converts_forms() {
+        let schema = schema(json!({
+            "definitions": {
+                "matrix": { "elements": { "elements": { "type": "float64" }}},
+                "shape": {
+                    "metadata": { "description": "A shape." },
+                    "discriminator": "kind",
+                    "mapping": {
+                        "circle": { "properties": { "radius": { "type": "float32" }}},
+                        "square": { "properties": {} }
+                    }
+                }
+            },
+            "properties": {
+                "at": {
+                    "metadata": { "description": "When.\nIn UTC." },
+                    "type": "timestamp"
+                },
+                "grid": { "ref": "matrix" },
+                "level": { "enum": ["info", "very-bad"], "nullable": true },
+                "point": { "properties": { "x": { "type": "int8" }}},
+                "shapes": { "values": { "ref": "shape" }}
+            },
+            "optionalProperties": {
+                "any": {},
+                "flag": { "type": "boolean" },
+                "2d": { "type": "uint16" }
+            }
+        }));
+
+        assert_eq!(
+            r#"syntax = "proto3";
+
+import "google/protobuf/struct.proto";
+import "google/protobuf/timestamp.proto";
+
+message Root {
+  // When.
+  // In UTC.
+  google.protobuf.Timestamp at = 1;
+  repeated GridItem grid = 2;
+  optional Level level = 3;
+  Point point = 4;
+  map<string, Shape> shapes = 5;
+  optional uint32 x_2d = 6 [json_name = "2d"];
+  optional google.protobuf.Value any = 7;
+  optional bool flag = 8;
+
+  message GridItem {
+    repeated double value = 1;
+  }
+
+  enum Level {
+    LEVEL_UNSPECIFIED = 0;
+    LEVEL_INFO = 1;
+    LEVEL_VERY_BAD = 2;
+  }
+
+  message Point {
+    int32 x = 1;
+  }
+}
+
+// A shape.
+message Shape {
+  oneof kind {
+    Circle circle = 1;
+    Square square = 2;
+  }
+
+  message Circle {
+    float radius = 1;
+  }
+
+  message Square {
+  }
+}
+"#,
+            to_proto(&schema).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_unrepresentable_schemas() {
+        assert_eq!(
+            Err(ProtoError::RootNotMessage),
+            to_proto(&schema(json!({ "elements": {} })))
+        );
+
+        assert_eq!(
+            Err(ProtoError::RootNotMessage),
+            to_proto(&schema(json!({ "enum": ["a"] })))
+        );
+
+        assert_eq!(
+            Err(ProtoError::RecursiveRef("list".into())),
+            to_proto(&schema(json!({
+                "definitions": { "list": { "elements": { "ref": "list" }}},
+                "properties": { "x": { "ref": "list" }}
+            })))
+        );
+
+        assert_eq!(
+            Err(ProtoError::NoSuchDefinition("x".into())),
+            to_proto(&schema(json!({ "properties": { "x": { "ref": "x" }}})))
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::refs::resolve;
+use crate::Schema;
+use serde_json::Value;
+
+/// Removes properties of an instance that its schema doesn't declare.
+///
+/// This goes through the whole instance, and removes from each object that
+/// the schema calls for with `properties` or `optionalProperties` whatever
+/// properties aren't declared there. The discriminator of a discriminator
+/// variant is kept. Properties are removed even if the schema sets
+/// `additionalProperties`, so that what's left holds only fields the schema
+/// knows about.
+///
+/// Parts of the instance that don't have the shape the schema calls for are
+/// left alone, so this can be called before or after validating.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "discriminator": "type",
+///     "mapping": {
+///         "user": {
+///             "properties": {
+///                 "name": { "type": "string" },
+///                 "emails": {
+///                     "elements": {
+///                         "properties": { "address": { "type": "string" }}
+///                     }
+///                 }
+///             },
+///             "additionalProperties": true
+///         }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let mut instance = json!({
+///     "type": "user",
+///     "name": "alice",
+///     "emails": [{ "address": "alice@example.com", "verified": true }],
+///     "csrf": "abc123"
+/// });
+/// jtd::prune(&schema, &mut instance);
+///
+/// assert_eq!(json!({
+///     "type": "user",
+///     "name": "alice",
+///     "emails": [{ "address": "alice@example.com" }]
+/// }), instance);
+/// ```
+pub fn prune(schema: &Schema, instance: &mut Value) {
+    prune_at(schema, schema, None, instance);
+}
+
+fn prune_at(root: &Schema, schema: &Schema, parent_tag: Option<&str>, instance: &mut Value) {
+    let (schema, _) = resolve(root, schema);
+
+    match (schema, instance) {
+        (Schema::Elements { elements, .. }, Value::Array(array)) => {
+            for element in array {
+                prune_at(root, elements, None, element);
+            }
+        }
+        (
+            Schema::Properties {
+                properties,
+                optional_properties,
+                ..
+            },
+            Value::Object(object),
+        ) => {
+            object.retain(|name, _| {
+                parent_tag == Some(name.as_str())
+                    || properties.contains_key(name)
+                    || optional_properties.contains_key(name)
+            });
+
+            for (name, value) in object {
+                let schema = match properties.get(name) {
+                    Some(schema) => schema,
+                    None => match optional_properties.get(name) {
+                        Some(schema) => schema,
+                        None => continue,
+                    },
+                };
+
+                prune_at(root, schema, None, value);
+            }
+        }
+        (Schema::Values { values, .. }, Value::Object(object)) => {
+            for value in object.values_mut() {
+                prune_at(root, values, None, value);
+            }
+        }
+        (
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            },
+            instance,
+        ) => {
+            let variant = instance
+                .get(discriminator)
+                .and_then(Value::as_str)
+                .and_then(|tag| mapping.get(tag));
+
+            if let Some(variant) = variant {
+                prune_at(root, variant, Some(discriminator), instance);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn prunes_through_refs() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "node": {
+                        "properties": {
+                            "value": { "type": "string" }
+                        },
+                        "optionalProperties": {
+                            "children": { "elements": { "ref": "node" }},
+                            "attrs": { "values": { "ref": "attr" }}
+                        }
+                    },
+                    "attr": {
+                        "optionalProperties": { "name": {}},
+                        "nullable": true
+                    }
+                },
+                "ref": "node"
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let mut instance = json!({
+            "value": "a",
+            "x": 1,
+            "children": [
+                { "value": "b", "y": 2, "children": [{ "value": "c", "z": 3 }]},
+                "not a node",
+            ],
+            "attrs": {
+                "a": { "name": { "anything": "goes" }, "w": 4 },
+                "b": null,
+            },
+        });
+        prune(&schema, &mut instance);
+
+        assert_eq!(
+            json!({
+                "value": "a",
+                "children": [
+                    { "value": "b", "children": [{ "value": "c" }]},
+                    "not a node",
+                ],
+                "attrs": {
+                    "a": { "name": { "anything": "goes" }},
+                    "b": null,
+                },
+            }),
+            instance
+        );
+    }
+}
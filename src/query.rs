@@ -0,0 +1,509 @@
+//! Validate query strings and form data.
+//!
+//! A query string, like `name=alice&tags[0]=a&tags[1]=b`, or a body of type
+//! `application/x-www-form-urlencoded`, is a flat list of parameters, and
+//! every value in it is a string. This module turns one into a JSON object,
+//! so that it can be validated against the same schema as a JSON request
+//! body:
+//!
+//! * Parameter names with brackets nest: `a[b][c]=1` is `{"a":{"b":{"c":1}}}`.
+//!   Brackets with nothing in them, as in `a[]=1&a[]=2`, append.
+//! * Repeating a parameter whose schema is of the elements form, as in
+//!   `a=1&a=2`, makes an array. For any other schema, the last one wins.
+//! * Values become booleans or numbers where the schema calls for them. An
+//!   empty value becomes `null` where the schema is nullable, and doesn't
+//!   call for a string.
+//!
+//! Validation errors point at the parameter they're about, by the name it
+//! was given in the query string.
+//!
+//! ```
+//! use jtd::Schema;
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": {
+//!         "page": { "type": "uint32" },
+//!         "tags": { "elements": { "enum": ["new", "sale"] }}
+//!     }
+//! })).unwrap()).unwrap();
+//!
+//! assert_eq!(
+//!     json!({ "page": 2, "tags": ["new", "old"] }),
+//!     jtd::query::parse("?page=2&tags[0]=new&tags[1]=old", &schema),
+//! );
+//!
+//! let errors = jtd::query::validate("page=2&tags[0]=new&tags[1]=old", &schema, Default::default()).unwrap();
+//! assert_eq!(1, errors.len());
+//! assert_eq!(Some("tags[1]".to_owned()), errors[0].parameter);
+//! assert_eq!(vec!["tags", "1"], errors[0].error.instance_path);
+//! ```
+
+use crate::refs::resolve;
+use crate::{Schema, Type, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde_json::{Map, Number, Value};
+use std::borrow::Cow;
+
+/// A validation error in a query string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParameterError {
+    /// The name of the parameter the error is about, as it was written in the
+    /// query string, but with percent-encoding decoded. Errors about
+    /// something missing aren't about any parameter.
+    pub parameter: Option<String>,
+
+    /// The validation error, for the query string as a JSON object.
+    pub error: ValidationErrorIndicator<'static>,
+}
+
+/// Parses a query string into a JSON object, using `schema` to decide what
+/// type each value should be.
+///
+/// A leading `?` is ignored. See the [module documentation][`crate::query`]
+/// for how parameters are converted.
+pub fn parse(query: &str, schema: &Schema) -> Value {
+    let (value, _) = parse_with_parameters(query, schema);
+    value
+}
+
+/// Parses a query string with [`parse`], and validates the result against
+/// `schema`.
+pub fn validate(
+    query: &str,
+    schema: &Schema,
+    options: ValidateOptions,
+) -> Result<Vec<ParameterError>, ValidateError> {
+    let (value, parameters) = parse_with_parameters(query, schema);
+
+    Ok(crate::validate(schema, &value, options)?
+        .into_iter()
+        .map(|error| {
+            let severity = error.severity;
+            let (instance_path, schema_path) = error.into_owned_paths();
+
+            // Errors about a parameter are at its instance path. Errors about
+            // something nested, such as an object that should have been a
+            // string, are attributed to the first parameter inside it, unless
+            // they're about something missing from it.
+            let missing = matches!(
+                &schema_path[..],
+                [.., keyword, _] if keyword == "properties"
+            ) || schema_path.last().map(String::as_str) == Some("discriminator");
+
+            let parameter = parameters
+                .iter()
+                .find(|(path, _)| *path == instance_path)
+                .or_else(|| {
+                    parameters.iter().find(|(path, _)| {
+                        !missing && !instance_path.is_empty() && path.starts_with(&instance_path)
+                    })
+                })
+                .map(|(_, name)| name.clone());
+
+            ParameterError {
+                parameter,
+                error: ValidationErrorIndicator {
+                    instance_path: instance_path.into_iter().map(Cow::Owned).collect(),
+                    schema_path: schema_path.into_iter().map(Cow::Owned).collect(),
+                    severity,
+                },
+            }
+        })
+        .collect())
+}
+
+// A parameter, with its name and value decoded.
+struct Parameter {
+    name: String,
+    value: String,
+}
+
+// Parameters, arranged by the keys in their names.
+enum Node {
+    Leaf(Vec<Parameter>),
+    Branch(Vec<(String, Node)>),
+}
+
+// Parses a query string, returning the instance path of each parameter, and
+// its name.
+fn parse_with_parameters(query: &str, schema: &Schema) -> (Value, Vec<(Vec<String>, String)>) {
+    let query = query.strip_prefix('?').unwrap_or(query);
+
+    let mut root = Vec::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = match pair.find('=') {
+            Some(i) => (&pair[..i], &pair[i + 1..]),
+            None => (pair, ""),
+        };
+
+        let name = decode(name);
+        let keys = keys(&name);
+        let parameter = Parameter {
+            value: decode(value),
+            name,
+        };
+
+        insert(&mut root, &keys, false, parameter);
+    }
+
+    let mut converter = Converter {
+        root: schema,
+        path: Vec::new(),
+        parameters: Vec::new(),
+    };
+
+    let value = converter.convert(Node::Branch(root), Some(schema));
+    (value, converter.parameters)
+}
+
+// Decodes percent-encoding, and "+" for space. Invalid escapes are kept
+// as-is.
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+                match hex {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// Splits a parameter name like "a[b][]" into its keys: "a", "b", and "".
+// Names that don't fit that pattern are a single key.
+fn keys(name: &str) -> Vec<String> {
+    let start = match name.find('[') {
+        Some(start) if start > 0 => start,
+        _ => return vec![name.to_owned()],
+    };
+
+    let mut keys = vec![name[..start].to_owned()];
+    let mut rest = &name[start..];
+    while let Some(inner) = rest.strip_prefix('[') {
+        match inner.find(']') {
+            Some(end) => {
+                keys.push(inner[..end].to_owned());
+                rest = &inner[end + 1..];
+            }
+            None => return vec![name.to_owned()],
+        }
+    }
+
+    if rest.is_empty() {
+        keys
+    } else {
+        vec![name.to_owned()]
+    }
+}
+
+fn insert(children: &mut Vec<(String, Node)>, keys: &[String], nested: bool, parameter: Parameter) {
+    let (key, rest) = match keys.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+
+    // An empty key in brackets appends, after the highest index so far.
+    let key = if key.is_empty() && nested {
+        children
+            .iter()
+            .filter_map(|(key, _)| key.parse::<usize>().ok())
+            .max()
+            .map_or(0, |max| max + 1)
+            .to_string()
+    } else {
+        key.clone()
+    };
+
+    let index = match children.iter().position(|(k, _)| *k == key) {
+        Some(index) => index,
+        None => {
+            children.push((key, Node::Leaf(Vec::new())));
+            children.len() - 1
+        }
+    };
+
+    // A parameter that conflicts with an earlier one, as in "a=1&a[b]=2",
+    // replaces it.
+    let node = &mut children[index].1;
+    match (node, rest.is_empty()) {
+        (Node::Leaf(parameters), true) => parameters.push(parameter),
+        (Node::Branch(children), false) => insert(children, rest, true, parameter),
+        (node, true) => *node = Node::Leaf(vec![parameter]),
+        (node, false) => {
+            let mut children = Vec::new();
+            insert(&mut children, rest, true, parameter);
+            *node = Node::Branch(children);
+        }
+    }
+}
+
+struct Converter<'a> {
+    root: &'a Schema,
+    path: Vec<String>,
+    parameters: Vec<(Vec<String>, String)>,
+}
+
+impl<'a> Converter<'a> {
+    fn convert(&mut self, node: Node, schema: Option<&'a Schema>) -> Value {
+        let (schema, nullable) = match schema {
+            Some(schema) => {
+                let (schema, nullable) = resolve(self.root, schema);
+                (Some(schema), nullable)
+            }
+            None => (None, false),
+        };
+
+        match (node, schema) {
+            (Node::Leaf(parameters), Some(Schema::Elements { elements, .. })) => {
+                let values = parameters
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, parameter)| {
+                        self.path.push(i.to_string());
+                        let value = self.leaf(parameter, Some(elements));
+                        self.path.pop();
+                        value
+                    })
+                    .collect();
+
+                Value::Array(values)
+            }
+            (Node::Leaf(mut parameters), schema) => {
+                let parameter = parameters.pop().expect("leaves have parameters");
+                if parameter.value.is_empty() && nullable && !is_string(schema) {
+                    self.parameters.push((self.path.clone(), parameter.name));
+                    return Value::Null;
+                }
+
+                self.leaf(parameter, schema)
+            }
+            (Node::Branch(mut children), Some(Schema::Elements { elements, .. })) => {
+                // Indices are put in order, and any gaps between them closed.
+                if children.iter().all(|(key, _)| key.parse::<usize>().is_ok()) {
+                    children.sort_by_key(|(key, _)| key.parse::<usize>().unwrap_or(0));
+                }
+
+                let values = children
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (_, child))| {
+                        self.path.push(i.to_string());
+                        let value = self.convert(child, Some(elements));
+                        self.path.pop();
+                        value
+                    })
+                    .collect();
+
+                Value::Array(values)
+            }
+            (Node::Branch(children), schema) => {
+                // In a discriminator, the tag decides the schemas of the rest
+                // of the properties.
+                let mapping = match schema {
+                    Some(Schema::Discriminator {
+                        discriminator,
+                        mapping,
+                        ..
+                    }) => children
+                        .iter()
+                        .find_map(|(key, child)| match child {
+                            Node::Leaf(parameters) if key == discriminator => parameters.last(),
+                            _ => None,
+                        })
+                        .and_then(|tag| mapping.get(&tag.value))
+                        .map(|mapping| resolve(self.root, mapping).0),
+                    _ => None,
+                };
+
+                let mut object = Map::new();
+                for (key, child) in children {
+                    let sub_schema = match (schema, mapping) {
+                        (Some(Schema::Values { values, .. }), _) => Some(&**values),
+                        (Some(Schema::Properties { .. }), _) => property(schema, &key),
+                        (_, Some(mapping)) => property(Some(mapping), &key),
+                        _ => None,
+                    };
+
+                    self.path.push(key.clone());
+                    let value = self.convert(child, sub_schema);
+                    self.path.pop();
+                    object.insert(key, value);
+                }
+
+                Value::Object(object)
+            }
+        }
+    }
+
+    fn leaf(&mut self, parameter: Parameter, schema: Option<&'a Schema>) -> Value {
+        self.parameters.push((self.path.clone(), parameter.name));
+        let schema = schema.map(|schema| resolve(self.root, schema).0);
+        let value = parameter.value;
+
+        let number = || {
+            value
+                .parse::<i64>()
+                .ok()
+                .map(Number::from)
+                .or_else(|| value.parse::<f64>().ok().and_then(Number::from_f64))
+                .map(Value::Number)
+        };
+
+        let coerced = match schema {
+            Some(Schema::Type {
+                type_: Type::Boolean,
+                ..
+            }) => match value.as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            Some(Schema::Type {
+                type_: Type::String | Type::Timestamp,
+                ..
+            }) => None,
+            Some(Schema::Type { .. }) => number(),
+            #[cfg(feature = "extensions")]
+            Some(Schema::IntEnum { .. }) => number(),
+            _ => None,
+        };
+
+        coerced.unwrap_or(Value::String(value))
+    }
+}
+
+fn property<'a>(schema: Option<&'a Schema>, key: &str) -> Option<&'a Schema> {
+    match schema {
+        Some(Schema::Properties {
+            properties,
+            optional_properties,
+            ..
+        }) => properties.get(key).or_else(|| optional_properties.get(key)),
+        _ => None,
+    }
+}
+
+fn is_string(schema: Option<&Schema>) -> bool {
+    matches!(
+        schema,
+        Some(Schema::Type {
+            type_: Type::String | Type::Timestamp,
+            ..
+        }) | Some(Schema::Enum { .. })
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Schema {
+        Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "limit": { "type": "uint8", "nullable": true }
+                },
+                "properties": {
+                    "q": { "type": "string" },
+                    "ids": { "elements": { "type": "uint32" }},
+                    "filter": {
+                        "discriminator": "kind",
+                        "mapping": {
+                            "range": {
+                                "properties": {
+                                    "from": { "type": "float64" },
+                                    "to": { "type": "float64" }
+                                }
+                            }
+                        }
+                    }
+                },
+                "optionalProperties": {
+                    "limit": { "ref": "limit" },
+                    "exact": { "type": "boolean" },
+                    "meta": { "values": { "type": "int8" }}
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses() {
+        assert_eq!(
+            json!({
+                "q": "a b&c",
+                "ids": [1, 2, 3],
+                "filter": { "kind": "range", "from": 1.5, "to": -1 },
+                "limit": null,
+                "exact": "yes",
+                "meta": { "x": 1, "y[z": "%zz" }
+            }),
+            parse(
+                "q=a+b%26c&ids=1&ids=2&ids=3&filter[kind]=range&filter[from]=1.5&filter[to]=-1\
+                 &limit=&exact=yes&meta[x]=1&meta[y[z]=%zz&",
+                &schema()
+            )
+        );
+
+        // Sparse and appended indices, and conflicting parameters.
+        assert_eq!(
+            json!({ "ids": [7, 5, 6], "q": { "x": "1" }}),
+            parse("ids[5]=5&ids[]=6&ids[0]=7&q=a&q[x]=1", &schema())
+        );
+
+        assert_eq!(json!({ "": "" }), parse("=&", &schema()));
+    }
+
+    #[test]
+    fn validates() {
+        let errors = validate(
+            "ids[0]=1&ids[1]=x&q[a]=1&filter[kind]=range&filter[from]=a&exact=1",
+            &schema(),
+            Default::default(),
+        )
+        .unwrap();
+
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|error| {
+                (
+                    error.parameter.as_deref(),
+                    error.error.instance_path.to_string(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                (Some("filter[from]"), "/filter/from".to_owned()),
+                (None, "/filter".to_owned()),
+                (Some("ids[1]"), "/ids/1".to_owned()),
+                (Some("q[a]"), "/q".to_owned()),
+                (Some("exact"), "/exact".to_owned()),
+            ],
+            errors
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Property-test with [quickcheck](https://docs.rs/quickcheck).
+//!
+//! With this feature, [`Schema`] and [`SerdeSchema`][`crate::SerdeSchema`]
+//! implement [`Arbitrary`]. This module provides [`ValidInstance`], an
+//! arbitrary schema along with an instance it accepts. To generate instances
+//! for a schema of your own, see [`arbitrary_instance`].
+//!
+//! Generated schemas always pass [`Schema::validate`]. They use only the
+//! keywords of RFC 8927, and not those of the `extensions` feature. Their
+//! definitions may refer to one another, but never in a cycle, so that there
+//! are always finite instances to generate.
+//!
+//! This module is only available with the `quickcheck` feature.
+//!
+//! ```
+//! use jtd::quickcheck::ValidInstance;
+//! use quickcheck::quickcheck;
+//!
+//! fn accepts_generated_instances(valid: ValidInstance) -> bool {
+//!     jtd::validate(&valid.schema, &valid.instance, Default::default())
+//!         .unwrap()
+//!         .is_empty()
+//! }
+//!
+//! quickcheck(accepts_generated_instances as fn(ValidInstance) -> bool);
+//! ```
+
+use crate::{Schema, Type};
+use ::quickcheck::{Arbitrary, Gen};
+use serde_json::{Map, Value};
+
+// The most entries in a generated object, array, or enum.
+const MAX_LEN: usize = 4;
+
+/// An arbitrary schema, and an arbitrary instance that it accepts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidInstance {
+    /// The schema.
+    pub schema: Schema,
+
+    /// An instance of the schema.
+    pub instance: Value,
+}
+
+impl Arbitrary for ValidInstance {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let schema = Schema::arbitrary(g);
+        let instance = arbitrary_instance(&schema, g);
+        Self { schema, instance }
+    }
+}
+
+/// Generates an arbitrary instance of a schema.
+///
+/// If the schema passes [`Schema::validate`], and its definitions don't refer
+/// to one another in a cycle, then the instance is valid. Otherwise, this
+/// function generates something, but it may be invalid, and for cyclic
+/// definitions it may not terminate.
+///
+/// Schemas using the `extensions` feature are supported, except that for a
+/// `keys` schema with a `pattern`, this always generates an empty object.
+///
+/// ```
+/// use jtd::Schema;
+/// use quickcheck::Gen;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "id": { "type": "uint8" },
+///         "tags": { "elements": { "enum": ["a", "b"] }}
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let mut g = Gen::new(10);
+/// for _ in 0..100 {
+///     let instance = jtd::quickcheck::arbitrary_instance(&schema, &mut g);
+///     assert!(jtd::validate(&schema, &instance, Default::default()).unwrap().is_empty());
+/// }
+/// ```
+pub fn arbitrary_instance(schema: &Schema, g: &mut Gen) -> Value {
+    instance(schema, schema, None, g)
+}
+
+fn instance(root: &Schema, schema: &Schema, parent_tag: Option<&str>, g: &mut Gen) -> Value {
+    if schema.nullable() && bool::arbitrary(g) {
+        return Value::Null;
+    }
+
+    match schema {
+        Schema::Empty { .. } => match usize::arbitrary(g) % 6 {
+            0 => Value::Null,
+            1 => bool::arbitrary(g).into(),
+            2 => i32::arbitrary(g).into(),
+            3 => String::arbitrary(g).into(),
+            4 => Value::Array(vec![]),
+            _ => Value::Object(Map::new()),
+        },
+        Schema::Ref { ref_, .. } => match root.definitions().get(ref_) {
+            Some(definition) => instance(root, definition, None, g),
+            None => Value::Null,
+        },
+        Schema::Type { type_, .. } => match type_ {
+            Type::Boolean => bool::arbitrary(g).into(),
+            Type::Int8 => int(g, -128, 127),
+            Type::Uint8 => int(g, 0, 255),
+            Type::Int16 => int(g, -32768, 32767),
+            Type::Uint16 => int(g, 0, 65535),
+            Type::Int32 => int(g, -2147483648, 2147483647),
+            Type::Uint32 => int(g, 0, 4294967295),
+            Type::Float32 | Type::Float64 => (f64::from(i32::arbitrary(g)) / 8.0).into(),
+            Type::String => String::arbitrary(g).into(),
+            Type::Timestamp => format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}",
+                1970 + u32::arbitrary(g) % 100,
+                1 + u32::arbitrary(g) % 12,
+                1 + u32::arbitrary(g) % 28,
+                u32::arbitrary(g) % 24,
+                u32::arbitrary(g) % 60,
+                u32::arbitrary(g) % 60,
+                g.choose(&["Z", ".5Z", "+05:30", "-08:00"]).unwrap(),
+            )
+            .into(),
+            // There's no telling what a custom type accepts, but they're most
+            // often strings with some format.
+            #[cfg(feature = "extensions")]
+            Type::Custom(_) => String::arbitrary(g).into(),
+        },
+        Schema::Enum { enum_, .. } => {
+            let values: Vec<_> = enum_.iter().collect();
+            g.choose(&values)
+                .map(|value| Value::String((*value).clone()))
+                .unwrap_or_default()
+        }
+        #[cfg(feature = "extensions")]
+        Schema::IntEnum { enum_, .. } => {
+            let values: Vec<_> = enum_.iter().collect();
+            g.choose(&values)
+                .map(|value| Value::from(**value))
+                .unwrap_or_default()
+        }
+        Schema::Elements { elements, .. } => (0..len(g))
+            .map(|_| instance(root, elements, None, g))
+            .collect(),
+        Schema::Properties {
+            properties,
+            optional_properties,
+            ..
+        } => {
+            let mut object = Map::new();
+            for (name, schema) in properties {
+                object.insert(name.clone(), instance(root, schema, None, g));
+            }
+
+            for (name, schema) in optional_properties {
+                if bool::arbitrary(g) {
+                    object.insert(name.clone(), instance(root, schema, None, g));
+                }
+            }
+
+            // The discriminator's mapping can't define the tag itself, but
+            // it's allowed anyway.
+            if let Some(tag) = parent_tag {
+                object.remove(tag);
+            }
+
+            Value::Object(object)
+        }
+        Schema::Values {
+            values,
+            #[cfg(feature = "extensions")]
+            keys,
+            ..
+        } => {
+            #[cfg(feature = "extensions")]
+            let names: Option<Vec<&String>> = match keys {
+                None | Some(crate::Keys::String { pattern: None }) => None,
+                Some(crate::Keys::Enum(enum_)) => Some(enum_.iter().collect()),
+                Some(crate::Keys::String { pattern: Some(_) }) => Some(vec![]),
+            };
+
+            let mut object = Map::new();
+            for _ in 0..len(g) {
+                #[cfg(feature = "extensions")]
+                let name = match names {
+                    Some(ref names) => match g.choose(names) {
+                        Some(name) => (*name).clone(),
+                        None => break,
+                    },
+                    None => String::arbitrary(g),
+                };
+                #[cfg(not(feature = "extensions"))]
+                let name = String::arbitrary(g);
+
+                object.insert(name, instance(root, values, None, g));
+            }
+
+            Value::Object(object)
+        }
+        Schema::Discriminator {
+            discriminator,
+            mapping,
+            ..
+        } => {
+            let tags: Vec<_> = mapping.keys().collect();
+            let tag = match g.choose(&tags) {
+                Some(tag) => (*tag).clone(),
+                None => return Value::Null,
+            };
+
+            let mut object = match instance(root, &mapping[&tag], Some(discriminator), g) {
+                Value::Object(object) => object,
+                _ => Map::new(),
+            };
+
+            object.insert(discriminator.clone(), tag.into());
+            Value::Object(object)
+        }
+    }
+}
+
+fn int(g: &mut Gen, min: i64, max: i64) -> Value {
+    (min + (u64::arbitrary(g) % (max - min + 1) as u64) as i64).into()
+}
+
+fn len(g: &mut Gen) -> usize {
+    usize::arbitrary(g) % MAX_LEN
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::quickcheck::QuickCheck;
+
+    #[test]
+    fn generates_valid_instances() {
+        fn prop(valid: ValidInstance) -> bool {
+            crate::validate(&valid.schema, &valid.instance, Default::default())
+                .unwrap()
+                .is_empty()
+        }
+
+        QuickCheck::new()
+            .tests(500)
+            .quickcheck(prop as fn(ValidInstance) -> bool);
+    }
+}
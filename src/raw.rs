@@ -0,0 +1,326 @@
+use crate::config::Location;
+use crate::reader::validate_stream;
+use crate::{Schema, ValidateOptions, ValidateReaderError, ValidationErrorIndicator};
+use jtd_core::__private::{JsonScanner, LineIndex};
+use serde::de::Error as _;
+use std::fmt;
+use std::ops::Range;
+
+/// A validation error in JSON text, along with where the rejected value is in
+/// the text.
+///
+/// Returned by [`validate_str`] and [`validate_slice`]. For errors about
+/// something missing, such as a required property, the span is that of the
+/// object it's missing from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SpannedValidationError {
+    /// The error itself.
+    pub error: ValidationErrorIndicator<'static>,
+
+    /// The span of bytes the rejected value takes up in the input.
+    pub span: Range<usize>,
+}
+
+/// A validation error in JSON text, along with the lines and columns where
+/// the rejected value starts and ends.
+///
+/// Returned by [`validate_str_located`]. This is a [`SpannedValidationError`]
+/// with the span converted to [`Location`]s, for editors and other tools that
+/// point out problems by line and column. For errors about something missing,
+/// such as a required property, the location is that of the object it's
+/// missing from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocatedValidationError {
+    /// The error itself.
+    pub error: ValidationErrorIndicator<'static>,
+
+    /// The span of bytes the rejected value takes up in the input.
+    pub span: Range<usize>,
+
+    /// Where the rejected value starts.
+    pub start: Location,
+
+    /// Where the rejected value ends. This is the position just after its
+    /// last character.
+    pub end: Location,
+}
+
+impl fmt::Display for LocatedValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: value at {:?} rejected by schema at {:?}",
+            self.start,
+            self.error.instance_path.to_string(),
+            self.error.schema_path.to_string()
+        )
+    }
+}
+
+/// Validates JSON text against a schema, without building a
+/// [`serde_json::Value`] of it, and returns where in the text each error is.
+///
+/// This is for pipelines that validate many records, such as lines of a log,
+/// where building a [`Value`][`serde_json::Value`] of each record would take
+/// longer than validating it. The input is checked as it's parsed, as with
+/// [`validate_from_reader`][`crate::validate_from_reader`], and the same
+/// errors are found.
+///
+/// Working out where errors are takes a second pass over the input, which is
+/// only made if there are errors to report, so valid records go through in a
+/// single pass.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "level": { "enum": ["info", "warn", "error"] },
+///         "status": { "type": "uint16" }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let input = r#"{"level":"debug","status":200}"#;
+/// let errors = jtd::validate_str(&schema, input, Default::default()).unwrap();
+///
+/// assert_eq!(1, errors.len());
+/// assert_eq!(vec!["level"], errors[0].error.instance_path);
+/// assert_eq!(r#""debug""#, &input[errors[0].span.clone()]);
+/// ```
+///
+/// With the `raw_value` feature, see `validate_raw_value` to validate a
+/// serde_json `RawValue` the same way.
+pub fn validate_str(
+    schema: &Schema,
+    input: &str,
+    options: ValidateOptions,
+) -> Result<Vec<SpannedValidationError>, ValidateReaderError> {
+    let mut deserializer = serde_json::Deserializer::from_str(input);
+    let (errors, stopped) = validate_stream(schema, &mut deserializer, options)?;
+
+    // Once validation stops early, the rest of the input goes unread.
+    if !stopped {
+        deserializer
+            .end()
+            .map_err(ValidateReaderError::Deserialize)?;
+    }
+
+    if errors.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // The input is known to be valid JSON, at least as far as validation got,
+    // so the scanner only gives up after the point where validation stopped,
+    // if it stopped early. The values errors are about all come before then.
+    let mut scanner = JsonScanner::new(input);
+    let _ = scanner.value();
+
+    Ok(errors
+        .into_iter()
+        .map(|error| {
+            let path: Vec<String> = error
+                .instance_path
+                .iter()
+                .map(|token| token.clone().into_owned())
+                .collect();
+
+            // Missing values have no span of their own, so fall back to the
+            // nearest ancestor that does.
+            let span = (0..=path.len())
+                .rev()
+                .find_map(|len| scanner.spans.get(&path[..len]))
+                .cloned()
+                .unwrap_or(0..input.len());
+
+            SpannedValidationError { error, span }
+        })
+        .collect())
+}
+
+/// Validates JSON text against a schema, and returns the line and column
+/// where each error is.
+///
+/// This is [`validate_str`], with spans converted to lines and columns, for
+/// editor integrations and other tools that highlight problems in the
+/// original document.
+///
+/// ```
+/// use jtd::config::Location;
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "values": { "type": "boolean" }
+/// })).unwrap()).unwrap();
+///
+/// let input = "{\n  \"a\": true,\n  \"b\": \"yes\"\n}";
+/// let errors = jtd::validate_str_located(&schema, input, Default::default()).unwrap();
+///
+/// assert_eq!(1, errors.len());
+/// assert_eq!(Location { line: 3, column: 8 }, errors[0].start);
+/// assert_eq!(Location { line: 3, column: 13 }, errors[0].end);
+/// assert_eq!(
+///     r#"3:8: value at "/b" rejected by schema at "/values/type""#,
+///     errors[0].to_string(),
+/// );
+/// ```
+pub fn validate_str_located(
+    schema: &Schema,
+    input: &str,
+    options: ValidateOptions,
+) -> Result<Vec<LocatedValidationError>, ValidateReaderError> {
+    let errors = validate_str(schema, input, options)?;
+    if errors.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let lines = LineIndex::new(input);
+    Ok(errors
+        .into_iter()
+        .map(
+            |SpannedValidationError { error, span }| LocatedValidationError {
+                start: lines.locate(span.start),
+                end: lines.locate(span.end),
+                error,
+                span,
+            },
+        )
+        .collect())
+}
+
+/// Validates JSON text in a byte slice against a schema, without building a
+/// [`serde_json::Value`] of it.
+///
+/// This is [`validate_str`], for input that hasn't been checked to be UTF-8
+/// yet. Input that isn't UTF-8 is reported as a
+/// [`ValidateReaderError::Deserialize`] error.
+pub fn validate_slice(
+    schema: &Schema,
+    input: &[u8],
+    options: ValidateOptions,
+) -> Result<Vec<SpannedValidationError>, ValidateReaderError> {
+    let input = std::str::from_utf8(input)
+        .map_err(|err| ValidateReaderError::Deserialize(serde_json::Error::custom(err)))?;
+
+    validate_str(schema, input, options)
+}
+
+/// Validates a [`RawValue`][`serde_json::value::RawValue`] against a schema,
+/// without building a [`serde_json::Value`] of it.
+///
+/// This is [`validate_str`], for values whose parsing was deferred with
+/// [`RawValue`][`serde_json::value::RawValue`], such as the payload of an
+/// envelope deserialized into a struct. Spans are relative to the start of
+/// the raw value.
+///
+/// This function is only available with the `raw_value` feature.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde::Deserialize;
+/// use serde_json::json;
+/// use serde_json::value::RawValue;
+///
+/// #[derive(Deserialize)]
+/// struct Envelope<'a> {
+///     #[serde(borrow)]
+///     payload: &'a RawValue,
+/// }
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "elements": { "type": "string" }
+/// })).unwrap()).unwrap();
+///
+/// let input = r#"{ "payload": ["a", 2, "c"] }"#;
+/// let envelope: Envelope = serde_json::from_str(input).unwrap();
+/// let errors = jtd::validate_raw_value(&schema, envelope.payload, Default::default()).unwrap();
+///
+/// assert_eq!(1, errors.len());
+/// assert_eq!(6..7, errors[0].span);
+/// ```
+#[cfg(feature = "raw_value")]
+pub fn validate_raw_value(
+    schema: &Schema,
+    input: &serde_json::value::RawValue,
+    options: ValidateOptions,
+) -> Result<Vec<SpannedValidationError>, ValidateReaderError> {
+    validate_str(schema, input.get(), options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn spans() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "properties": {
+                    "id": { "type": "string" },
+                    "tags": { "values": { "type": "uint8" }}
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let input = r#"  { "tags": { "a": 1, "b/c": 300 }, "extra": [1, 2] }  "#;
+        let errors = validate_str(&schema, input, Default::default()).unwrap();
+        let spans: Vec<_> = errors
+            .iter()
+            .map(|error| &input[error.span.clone()])
+            .collect();
+
+        assert_eq!(
+            vec![
+                "300",
+                "[1, 2]",
+                r#"{ "tags": { "a": 1, "b/c": 300 }, "extra": [1, 2] }"#
+            ],
+            spans
+        );
+
+        // With a limit on errors, validation stops partway through the input,
+        // but the errors found before then still have spans.
+        let errors =
+            validate_str(&schema, input, ValidateOptions::new().with_max_errors(1)).unwrap();
+        assert_eq!(1, errors.len());
+        assert_eq!("300", &input[errors[0].span.clone()]);
+
+        let input = "{\n\t\"tags\": { \"é\": 256 }\n}";
+        let errors = validate_str_located(&schema, input, Default::default()).unwrap();
+        assert_eq!(
+            vec![
+                (
+                    Location {
+                        line: 2,
+                        column: 17
+                    },
+                    Location {
+                        line: 2,
+                        column: 20
+                    }
+                ),
+                (
+                    Location { line: 1, column: 1 },
+                    Location { line: 3, column: 2 }
+                ),
+            ],
+            errors
+                .iter()
+                .map(|error| (error.start, error.end))
+                .collect::<Vec<_>>()
+        );
+
+        assert!(validate_str(&schema, r#"{ "id": "a" } x"#, Default::default()).is_err());
+        assert!(validate_slice(&schema, b"{ \"id\": \"\xff\" }", Default::default()).is_err());
+        assert_eq!(
+            Ok(0),
+            validate_slice(&schema, br#"{ "id": "a", "tags": {} }"#, Default::default())
+                .map(|errors| errors.len())
+                .map_err(|err| err.to_string())
+        );
+    }
+}
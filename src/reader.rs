@@ -0,0 +1,1094 @@
+use crate::{Schema, Severity, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use jtd_core::__private::ValidateOptionsExt as _;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io;
+use thiserror::Error;
+
+/// Errors that may arise from [`validate_from_reader`] and
+/// [`validate_from_deserializer`].
+#[derive(Debug, Error)]
+pub enum ValidateReaderError<E = serde_json::Error> {
+    /// The input couldn't be read, or wasn't well-formed.
+    #[error(transparent)]
+    Deserialize(E),
+
+    /// Validating the input failed.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+}
+
+/// Validates JSON read from `reader` against a schema, without building a
+/// [`serde_json::Value`] of it first.
+///
+/// For large payloads, most of the memory [`validate()`][`crate::validate()`]
+/// needs goes to the [`Value`] being validated, rather than to validation
+/// itself. This function instead checks the input as it's parsed, and keeps
+/// no more of it in memory than it has to: the values of an object that come
+/// before its discriminator, if the object is checked against a
+/// discriminator-form schema, and the values checked against custom types
+/// from the `extensions` feature.
+///
+/// Reading from an unbuffered source, such as a [`File`][`std::fs::File`],
+/// one byte at a time is slow, so wrap it in a [`BufReader`][`io::BufReader`]
+/// first.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "elements": {
+///         "properties": {
+///             "id": { "type": "uint32" }
+///         }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let input = r#"[{ "id": 1 }, { "id": -1 }, { "id": 3, "extra": true }]"#;
+/// let errors = jtd::validate_from_reader(&schema, input.as_bytes(), Default::default()).unwrap();
+///
+/// assert_eq!(2, errors.len());
+/// assert_eq!(vec!["1", "id"], errors[0].instance_path);
+/// assert_eq!(vec!["2", "extra"], errors[1].instance_path);
+/// ```
+///
+/// # Differences from [`validate()`][`crate::validate()`]
+///
+/// This function finds the same errors as [`validate()`][`crate::validate()`]
+/// would for the same input, but not always in the same order:
+/// [`validate()`][`crate::validate()`] goes through the properties of an
+/// object in the order of the schema, and this function in the order of the
+/// input. When [`ValidateOptions::with_max_errors`] cuts validation short,
+/// the errors returned may therefore differ too.
+///
+/// The per-value limits of [`ValidateOptions`], such as
+/// [`ValidateOptions::with_max_string_len`], are checked as the input is
+/// read, so errors found before a limit is exceeded are discarded along with
+/// the rest of the input.
+///
+/// Objects with duplicate keys have each of their values checked, whereas
+/// [`Value`] keeps only the last of them.
+pub fn validate_from_reader<R: io::Read>(
+    schema: &Schema,
+    reader: R,
+    options: ValidateOptions,
+) -> Result<Vec<ValidationErrorIndicator<'static>>, ValidateReaderError> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let (errors, stopped) = validate_stream(schema, &mut deserializer, options)?;
+
+    // Once validation stops early, the rest of the input goes unread.
+    if !stopped {
+        deserializer
+            .end()
+            .map_err(ValidateReaderError::Deserialize)?;
+    }
+
+    Ok(errors)
+}
+
+/// Validates the value a [`serde::Deserializer`] produces against a schema,
+/// without building a [`serde_json::Value`] of it first.
+///
+/// This is [`validate_from_reader`] for inputs that are already behind a
+/// deserializer, such as a [`serde_json::Deserializer`] reading from a
+/// stream of several values, or a deserializer for a format other than JSON.
+/// The deserializer must be for a self-describing format. It's left
+/// positioned after the value, or where validation stopped, if it stopped
+/// early.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "type": "uint8"
+/// })).unwrap()).unwrap();
+///
+/// let mut deserializer = serde_json::Deserializer::from_str("1 300");
+/// assert!(jtd::validate_from_deserializer(&schema, &mut deserializer, Default::default())
+///     .unwrap()
+///     .is_empty());
+/// assert_eq!(
+///     1,
+///     jtd::validate_from_deserializer(&schema, &mut deserializer, Default::default())
+///         .unwrap()
+///         .len(),
+/// );
+/// ```
+pub fn validate_from_deserializer<'de, D>(
+    schema: &Schema,
+    deserializer: D,
+    options: ValidateOptions,
+) -> Result<Vec<ValidationErrorIndicator<'static>>, ValidateReaderError<D::Error>>
+where
+    D: de::Deserializer<'de>,
+{
+    Ok(validate_stream(schema, deserializer, options)?.0)
+}
+
+// Validates the value a deserializer produces. Alongside the errors, returns
+// whether validation stopped before the end of the value.
+pub(crate) fn validate_stream<'de, D>(
+    schema: &Schema,
+    deserializer: D,
+    options: ValidateOptions,
+) -> Result<(Vec<ValidationErrorIndicator<'static>>, bool), ValidateReaderError<D::Error>>
+where
+    D: de::Deserializer<'de>,
+{
+    let mut validator = Validator {
+        root: schema,
+        options,
+        instance_tokens: vec![],
+        schema_tokens: vec![],
+        depth: 1,
+        visited: 0,
+        severity: Severity::Error,
+        errors: vec![],
+        stop: None,
+    };
+
+    let result = Seed {
+        validator: &mut validator,
+        schema: Some(schema),
+        parent_tag: None,
+    }
+    .deserialize(deserializer);
+
+    match (result, validator.stop) {
+        (_, Some(Stop::MaxErrors)) => Ok((validator.errors, true)),
+        (_, Some(Stop::Failed(err))) => Err(ValidateReaderError::Validate(err)),
+        (Ok(()), None) => Ok((validator.errors, false)),
+        (Err(err), None) => Err(ValidateReaderError::Deserialize(err)),
+    }
+}
+
+// Why validation stopped before the end of the input.
+enum Stop {
+    MaxErrors,
+    Failed(ValidateError),
+}
+
+// The state of validation, shared by the seeds and visitors that go over the
+// input. It mirrors the state of validate()'s Vm, except that instance paths
+// can't borrow from an instance that's gone by the time errors are returned.
+struct Validator<'s> {
+    root: &'s Schema,
+    options: ValidateOptions,
+    instance_tokens: Vec<String>,
+    schema_tokens: Vec<&'s str>,
+    depth: usize,
+    visited: u64,
+    severity: Severity,
+    errors: Vec<ValidationErrorIndicator<'static>>,
+    stop: Option<Stop>,
+}
+
+impl<'s> Validator<'s> {
+    // Records why validation is stopping, and returns the error to abort the
+    // deserializer with.
+    fn stop<E: de::Error>(&mut self, stop: Stop) -> E {
+        self.stop = Some(stop);
+        E::custom("validation stopped")
+    }
+
+    fn fail<E: de::Error>(&mut self, err: ValidateError) -> E {
+        self.stop(Stop::Failed(err))
+    }
+
+    fn push_error<E: de::Error>(&mut self) -> Result<(), E> {
+        if self.severity < self.options.min_severity() {
+            return Ok(());
+        }
+
+        self.errors.push(ValidationErrorIndicator {
+            instance_path: self
+                .instance_tokens
+                .iter()
+                .map(|token| Cow::Owned(token.clone()))
+                .collect(),
+            schema_path: self
+                .schema_tokens
+                .iter()
+                .map(|&token| Cow::Owned(token.to_owned()))
+                .collect(),
+            severity: self.severity,
+        });
+
+        if self.options.max_errors() == self.errors.len() {
+            Err(self.stop(Stop::MaxErrors))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Pushes an error at a path relative to the current one.
+    fn push_error_at<E: de::Error>(
+        &mut self,
+        schema_tokens: &[&'s str],
+        instance_token: Option<&str>,
+    ) -> Result<(), E> {
+        let schema_len = self.schema_tokens.len();
+        let instance_len = self.instance_tokens.len();
+
+        self.schema_tokens.extend(schema_tokens);
+        self.instance_tokens
+            .extend(instance_token.map(str::to_owned));
+        let result = self.push_error();
+
+        self.schema_tokens.truncate(schema_len);
+        self.instance_tokens.truncate(instance_len);
+        result
+    }
+
+    fn check_string<E: de::Error>(&mut self, s: &str) -> Result<(), E> {
+        if exceeds(s.len(), self.options.max_string_len()) {
+            return Err(self.fail(ValidateError::StringTooLong));
+        }
+
+        Ok(())
+    }
+
+    // Checks the number of elements of an array, or keys of an object, seen
+    // so far.
+    fn check_len<E: de::Error>(&mut self, len: usize, is_array: bool) -> Result<(), E> {
+        if is_array && exceeds(len, self.options.max_array_len()) {
+            return Err(self.fail(ValidateError::ArrayTooLong));
+        }
+
+        if !is_array && exceeds(len, self.options.max_object_keys()) {
+            return Err(self.fail(ValidateError::TooManyObjectKeys));
+        }
+
+        Ok(())
+    }
+
+    // Checks the nesting of an array or object that's about to be read.
+    fn check_depth<E: de::Error>(&mut self) -> Result<(), E> {
+        if exceeds(
+            self.instance_tokens.len() + 1,
+            self.options.max_instance_depth(),
+        ) {
+            return Err(self.fail(ValidateError::MaxInstanceDepthExceeded));
+        }
+
+        Ok(())
+    }
+
+    // Follows refs from a schema to the one a value is checked against.
+    // Refs are followed before the value is read, since the value can only
+    // be read once, but their nullable and severity apply just as they would
+    // in validate(), which checks them before following the ref.
+    fn enter<E: de::Error>(&mut self, mut schema: &'s Schema) -> Result<Entered<'s>, E> {
+        if self.options.is_cancelled() {
+            return Err(self.fail(ValidateError::Cancelled));
+        }
+
+        let mut entered = Entered {
+            schema,
+            nullable: false,
+            severity: self.severity,
+            depth: self.depth,
+            schema_tokens: None,
+        };
+
+        loop {
+            entered.nullable |= schema.nullable();
+            if let Some(severity) = Severity::from_metadata(schema.metadata()) {
+                self.severity = severity;
+            }
+
+            let ref_ = match schema {
+                Schema::Ref { ref_, .. } => ref_,
+                _ => break,
+            };
+
+            schema = match self.root.definitions().get(ref_) {
+                Some(definition) => definition,
+                None => {
+                    return Err(self.fail(ValidateError::InvalidSchema(
+                        crate::SchemaValidateError::NoSuchDefinition(ref_.clone()),
+                    )))
+                }
+            };
+
+            self.depth += 1;
+            if self.depth == self.options.max_depth() {
+                return Err(self.fail(ValidateError::MaxDepthExceeded));
+            }
+
+            let tokens = std::mem::replace(&mut self.schema_tokens, vec!["definitions", ref_]);
+            entered.schema_tokens.get_or_insert(tokens);
+        }
+
+        self.visited += 1;
+        self.options.report_progress(self.visited);
+
+        entered.schema = schema;
+        Ok(entered)
+    }
+
+    // Restores the state enter() changed.
+    fn leave(&mut self, entered: Entered<'s>) {
+        if let Some(tokens) = entered.schema_tokens {
+            self.schema_tokens = tokens;
+        }
+
+        self.depth = entered.depth;
+        self.severity = entered.severity;
+    }
+
+    // Reads a value to keep, checking it against the per-value limits, given
+    // the number of arrays and objects it's nested in.
+    fn read_value<'de, D: de::Deserializer<'de>>(
+        &mut self,
+        d: D,
+        depth: usize,
+    ) -> Result<Value, D::Error> {
+        let value = Value::deserialize(d)?;
+        match self.options.check_limits(&value, depth) {
+            Ok(()) => Ok(value),
+            Err(err) => Err(self.fail(err)),
+        }
+    }
+}
+
+// A schema that refs have been followed to, along with the state to restore
+// once the value it's checked against is done with.
+struct Entered<'s> {
+    schema: &'s Schema,
+    nullable: bool,
+    severity: Severity,
+    depth: usize,
+    schema_tokens: Option<Vec<&'s str>>,
+}
+
+fn exceeds(len: usize, max: usize) -> bool {
+    max != 0 && len > max
+}
+
+// Checks a value against a schema, or just against the per-value limits if
+// the schema is None.
+struct Seed<'v, 's> {
+    validator: &'v mut Validator<'s>,
+    schema: Option<&'s Schema>,
+    parent_tag: Option<&'s str>,
+}
+
+impl<'de, 'v, 's> DeserializeSeed<'de> for Seed<'v, 's> {
+    type Value = ();
+
+    fn deserialize<D>(self, d: D) -> Result<(), D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let v = self.validator;
+        let schema = match self.schema {
+            Some(schema) => schema,
+            None => {
+                return d.deserialize_any(Visit {
+                    validator: v,
+                    schema: None,
+                    nullable: false,
+                    parent_tag: None,
+                })
+            }
+        };
+
+        let entered = v.enter(schema)?;
+        let nullable = entered.nullable;
+        let schema = entered.schema;
+
+        let result = match schema {
+            // Custom types may accept any value, so checking them takes the
+            // whole value.
+            #[cfg(feature = "extensions")]
+            Schema::Type {
+                type_: crate::Type::Custom(name),
+                ..
+            } => v.read_value(d, v.instance_tokens.len()).and_then(|value| {
+                if nullable && value.is_null() {
+                    return Ok(());
+                }
+
+                match v.options.type_extensions().check(name, &value) {
+                    Some(true) => Ok(()),
+                    Some(false) => v.push_error_at(&["type"], None),
+                    None => Err(v.fail(ValidateError::UnknownType(name.clone()))),
+                }
+            }),
+            _ => d.deserialize_any(Visit {
+                validator: v,
+                schema: Some(schema),
+                nullable,
+                parent_tag: self.parent_tag,
+            }),
+        };
+
+        v.leave(entered);
+        result
+    }
+}
+
+// Checks a value against a schema that's been resolved, so that it isn't of
+// the ref form.
+struct Visit<'v, 's> {
+    validator: &'v mut Validator<'s>,
+    schema: Option<&'s Schema>,
+    nullable: bool,
+    parent_tag: Option<&'s str>,
+}
+
+impl<'v, 's> Visit<'v, 's> {
+    // Checks a primitive value, including null.
+    fn primitive<E: de::Error>(self, value: Value) -> Result<(), E> {
+        let v = self.validator;
+        if value.is_null() && self.nullable {
+            return Ok(());
+        }
+
+        match self.schema {
+            Some(Schema::Type { type_, .. }) => match v.options.type_check(type_) {
+                Some(check) if !check(&value) => v.push_error_at(&["type"], None),
+                None if *type_ == crate::Type::Timestamp
+                    && !value.as_str().is_some_and(|s| v.options.is_timestamp(s)) =>
+                {
+                    v.push_error_at(&["type"], None)
+                }
+                _ => Ok(()),
+            },
+            Some(Schema::Enum { enum_, .. }) => match value.as_str() {
+                Some(s) if v.options.enum_contains(enum_, s) => Ok(()),
+                _ => v.push_error_at(&["enum"], None),
+            },
+            #[cfg(feature = "extensions")]
+            Some(Schema::IntEnum { enum_, .. }) => {
+                let ok = match &value {
+                    Value::Number(n) => match n.as_i64() {
+                        Some(i) => enum_.contains(&i),
+                        None => n
+                            .as_f64()
+                            .map(|f| jtd_core::__private::int_enum_contains(enum_, f))
+                            .unwrap_or(false),
+                    },
+                    _ => false,
+                };
+
+                if ok {
+                    Ok(())
+                } else {
+                    v.push_error_at(&["enum"], None)
+                }
+            }
+            _ => Visit {
+                validator: v,
+                ..self
+            }
+            .wrong_type(),
+        }
+    }
+
+    // Reports the error for a value that isn't of the kind the schema
+    // expects, exactly where validate() would.
+    fn wrong_type<E: de::Error>(self) -> Result<(), E> {
+        let token = match self.schema {
+            None | Some(Schema::Empty { .. }) | Some(Schema::Ref { .. }) => return Ok(()),
+            Some(Schema::Type { .. }) => "type",
+            Some(Schema::Enum { .. }) => "enum",
+            #[cfg(feature = "extensions")]
+            Some(Schema::IntEnum { .. }) => "enum",
+            Some(Schema::Elements { .. }) => "elements",
+            Some(Schema::Properties {
+                properties_is_present,
+                ..
+            }) => {
+                if *properties_is_present {
+                    "properties"
+                } else {
+                    "optionalProperties"
+                }
+            }
+            Some(Schema::Values { .. }) => "values",
+            Some(Schema::Discriminator { .. }) => "discriminator",
+        };
+
+        self.validator.push_error_at(&[token], None)
+    }
+
+    // Checks the entries of an object against a properties-form schema.
+    fn properties<'de, A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let v = self.validator;
+        let (properties, optional_properties, additional_properties) = match self.schema {
+            Some(Schema::Properties {
+                properties,
+                optional_properties,
+                additional_properties,
+                ..
+            }) => (properties, optional_properties, *additional_properties),
+            _ => unreachable!(),
+        };
+
+        let mut seen = BTreeSet::new();
+        let mut len = 0;
+        while let Some(key) = map.next_key::<String>()? {
+            len += 1;
+            v.check_len(len, false)?;
+            v.check_string(&key)?;
+
+            let (keyword, name, sub_schema) =
+                if let Some((name, sub_schema)) = properties.get_key_value(&key) {
+                    ("properties", name, sub_schema)
+                } else if let Some((name, sub_schema)) = optional_properties.get_key_value(&key) {
+                    ("optionalProperties", name, sub_schema)
+                } else {
+                    if !additional_properties && self.parent_tag != Some(key.as_str()) {
+                        v.push_error_at(&[], Some(&key))?;
+                    }
+
+                    map.next_value_seed(Seed {
+                        validator: v,
+                        schema: None,
+                        parent_tag: None,
+                    })?;
+                    continue;
+                };
+
+            seen.insert(name.as_str());
+            v.schema_tokens.push(keyword);
+            v.schema_tokens.push(name);
+            v.instance_tokens.push(key);
+            map.next_value_seed(Seed {
+                validator: v,
+                schema: Some(sub_schema),
+                parent_tag: None,
+            })?;
+            v.instance_tokens.pop();
+            v.schema_tokens.pop();
+            v.schema_tokens.pop();
+        }
+
+        for (name, sub_schema) in properties {
+            if seen.contains(name.as_str()) || v.options.ignore_missing_required() {
+                continue;
+            }
+
+            // The property's schema says how serious it is for the property
+            // to be missing.
+            let saved_severity = v.severity;
+            if let Some(severity) = Severity::from_metadata(sub_schema.metadata()) {
+                v.severity = severity;
+            }
+
+            let result = v.push_error_at(&["properties", name], None);
+            v.severity = saved_severity;
+            result?;
+        }
+
+        Ok(())
+    }
+
+    // Checks the entries of an object against a values-form schema.
+    fn values<'de, A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let v = self.validator;
+        let values = match self.schema {
+            Some(Schema::Values { values, .. }) => values,
+            _ => unreachable!(),
+        };
+
+        let mut len = 0;
+        while let Some(key) = map.next_key::<String>()? {
+            len += 1;
+            v.check_len(len, false)?;
+            v.check_string(&key)?;
+
+            #[cfg(feature = "extensions")]
+            if let Some(Schema::Values {
+                keys: Some(keys), ..
+            }) = self.schema
+            {
+                if let Some(keyword) = v.options.reject_key(keys, &key) {
+                    v.push_error_at(&["keys", keyword], Some(&key))?;
+                }
+            }
+
+            v.instance_tokens.push(key);
+            v.schema_tokens.push("values");
+            map.next_value_seed(Seed {
+                validator: v,
+                schema: Some(values),
+                parent_tag: None,
+            })?;
+            v.schema_tokens.pop();
+            v.instance_tokens.pop();
+        }
+
+        Ok(())
+    }
+
+    // Checks the entries of an object against a discriminator-form schema.
+    //
+    // Until the discriminator is found, the schema for the object isn't
+    // known, so the entries before it are kept. Once it's found, they're
+    // checked against the schema for the tag, followed by the rest of the
+    // object.
+    fn discriminator<'de, A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let v = self.validator;
+        let (discriminator, mapping) = match self.schema {
+            Some(Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            }) => (discriminator, mapping),
+            _ => unreachable!(),
+        };
+
+        let mut entries = vec![];
+        while let Some(key) = map.next_key::<String>()? {
+            v.check_len(entries.len() + 1, false)?;
+            v.check_string(&key)?;
+
+            let value = map.next_value_seed(ReadValue(v))?;
+            if &key != discriminator {
+                entries.push((key, value));
+                continue;
+            }
+
+            let tag = match value.as_str() {
+                Some(tag) => tag,
+                None => {
+                    v.push_error_at(&["discriminator"], Some(discriminator))?;
+                    return skip_entries(v, map, entries.len() + 1);
+                }
+            };
+
+            let (tag, sub_schema) = match mapping.get_key_value(tag) {
+                Some(entry) => entry,
+                None => {
+                    v.push_error_at(&["mapping"], Some(discriminator))?;
+                    return skip_entries(v, map, entries.len() + 1);
+                }
+            };
+
+            entries.push((key, value));
+            v.schema_tokens.push("mapping");
+            v.schema_tokens.push(tag);
+            let result = variant(v, sub_schema, discriminator, entries, map);
+            v.schema_tokens.pop();
+            v.schema_tokens.pop();
+            return result;
+        }
+
+        v.push_error_at(&["discriminator"], None)
+    }
+}
+
+// Checks an object against the schema for its tag, given the entries read
+// while looking for the tag. In valid schemas, that schema is of the
+// properties form, and the rest of the object is checked as it's read.
+// Otherwise, the rest of the object is read first.
+fn variant<'de, 's, A: MapAccess<'de>>(
+    v: &mut Validator<'s>,
+    schema: &'s Schema,
+    discriminator: &'s str,
+    entries: Vec<(String, Value)>,
+    mut map: A,
+) -> Result<(), A::Error> {
+    let entered = v.enter(schema)?;
+    let visit = Visit {
+        validator: &mut *v,
+        schema: Some(entered.schema),
+        nullable: entered.nullable,
+        parent_tag: Some(discriminator),
+    };
+
+    let result = if let Schema::Properties { .. } = entered.schema {
+        visit.properties(Chain {
+            entries: entries.into_iter(),
+            value: None,
+            rest: map,
+        })
+    } else {
+        let mut object: serde_json::Map<_, _> = entries.into_iter().collect();
+        while let Some(key) = map.next_key::<String>()? {
+            visit.validator.check_len(object.len() + 1, false)?;
+            visit.validator.check_string(&key)?;
+            let value = map.next_value_seed(ReadValue(&mut *visit.validator))?;
+            object.insert(key, value);
+        }
+
+        de::Deserializer::deserialize_any(Value::Object(object), visit).map_err(de::Error::custom)
+    };
+
+    v.leave(entered);
+    result
+}
+
+// Skips the rest of an object, given the number of its keys read so far.
+fn skip_entries<'de, A: MapAccess<'de>>(
+    v: &mut Validator<'_>,
+    mut map: A,
+    mut len: usize,
+) -> Result<(), A::Error> {
+    while let Some(key) = map.next_key::<String>()? {
+        len += 1;
+        v.check_len(len, false)?;
+        v.check_string(&key)?;
+        map.next_value_seed(Seed {
+            validator: v,
+            schema: None,
+            parent_tag: None,
+        })?;
+    }
+
+    Ok(())
+}
+
+// Reads the value of an entry of an object to keep, checking it against the
+// per-value limits.
+struct ReadValue<'v, 's>(&'v mut Validator<'s>);
+
+impl<'de, 'v, 's> DeserializeSeed<'de> for ReadValue<'v, 's> {
+    type Value = Value;
+
+    fn deserialize<D>(self, d: D) -> Result<Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let depth = self.0.instance_tokens.len() + 1;
+        self.0.read_value(d, depth)
+    }
+}
+
+// The entries of an object kept while looking for its discriminator,
+// followed by the rest of the object.
+struct Chain<I, A> {
+    entries: I,
+    value: Option<Value>,
+    rest: A,
+}
+
+impl<'de, I, A> MapAccess<'de> for Chain<I, A>
+where
+    I: Iterator<Item = (String, Value)>,
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => self.rest.next_key_seed(seed),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, A::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(value).map_err(de::Error::custom),
+            None => self.rest.next_value_seed(seed),
+        }
+    }
+}
+
+impl<'de, 'v, 's> Visitor<'de> for Visit<'v, 's> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        self.primitive(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<(), E> {
+        self.primitive(Value::Null)
+    }
+
+    fn visit_some<D>(self, d: D) -> Result<(), D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        d.deserialize_any(self)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<(), E> {
+        self.primitive(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<(), E> {
+        self.primitive(Value::from(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<(), E> {
+        self.primitive(Value::from(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<(), E> {
+        self.primitive(Value::from(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<(), E> {
+        self.validator.check_string(v)?;
+        self.primitive(Value::String(v.to_owned()))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        self.validator.check_depth()?;
+        let elements = match self.schema {
+            Some(Schema::Elements { elements, .. }) => Some(&**elements),
+            _ => None,
+        };
+
+        let v = self.validator;
+        if elements.is_none() {
+            Visit {
+                validator: &mut *v,
+                ..self
+            }
+            .wrong_type()?;
+        } else {
+            v.schema_tokens.push("elements");
+        }
+
+        let mut len = 0;
+        loop {
+            v.instance_tokens.push(len.to_string());
+            let element = seq.next_element_seed(Seed {
+                validator: &mut *v,
+                schema: elements,
+                parent_tag: None,
+            });
+            v.instance_tokens.pop();
+
+            if element?.is_none() {
+                break;
+            }
+
+            len += 1;
+            v.check_len(len, true)?;
+        }
+
+        if elements.is_some() {
+            v.schema_tokens.pop();
+        }
+
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<(), A::Error> {
+        self.validator.check_depth()?;
+        match self.schema {
+            Some(Schema::Properties { .. }) => self.properties(map),
+            Some(Schema::Values { .. }) => self.values(map),
+            Some(Schema::Discriminator { .. }) => self.discriminator(map),
+            _ => {
+                let v = self.validator;
+                Visit {
+                    validator: &mut *v,
+                    ..self
+                }
+                .wrong_type()?;
+                skip_entries(v, map, 0)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    // Checks that validate_from_reader finds the same errors as validate(),
+    // in whatever order.
+    fn assert_same_errors(schema: &Schema, instance: Value, options: ValidateOptions) {
+        let sorted = |errors: Vec<ValidationErrorIndicator>| {
+            let mut errors: Vec<_> = errors
+                .into_iter()
+                .map(|error| (error.severity, error.into_owned_paths()))
+                .collect();
+
+            errors.sort_by(|a, b| a.1.cmp(&b.1));
+            errors
+        };
+
+        let expected = sorted(crate::validate(schema, &instance, options.clone()).unwrap());
+        let actual = sorted(
+            validate_from_reader(
+                schema,
+                serde_json::to_vec(&instance).unwrap().as_slice(),
+                options,
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(expected, actual, "instance: {}", instance);
+    }
+
+    #[test]
+    fn matches_validate() {
+        let schema = schema(json!({
+            "definitions": {
+                "event": {
+                    "discriminator": "type",
+                    "mapping": {
+                        "click": {
+                            "properties": {
+                                "x": { "type": "int16" },
+                                "y": { "type": "int16", "metadata": { "severity": "warning" }}
+                            }
+                        },
+                        "key": {
+                            "properties": { "key": { "enum": ["a", "b"] }},
+                            "additionalProperties": true
+                        }
+                    }
+                }
+            },
+            "properties": {
+                "events": { "elements": { "ref": "event", "nullable": true }},
+                "tags": { "values": { "type": "timestamp" }},
+                "anything": {}
+            },
+            "optionalProperties": {
+                "count": { "type": "uint8" }
+            }
+        }));
+
+        let instances = vec![
+            json!(null),
+            json!([1, 2]),
+            json!({}),
+            json!({ "events": [], "tags": {}, "anything": [[{}]] }),
+            json!({
+                "events": [
+                    null,
+                    { "x": 1, "y": "2", "type": "click" },
+                    { "type": "click", "x": 1.5, "z": 3 },
+                    { "key": "c", "type": "key", "extra": [1] },
+                    { "x": 1, "type": "scroll" },
+                    { "x": 1, "type": 3 },
+                    { "x": 1 },
+                    "click"
+                ],
+                "tags": { "a": "2020-01-01T00:00:00Z", "b": "yesterday" },
+                "anything": { "a": [1, "b"] },
+                "count": 256,
+                "extra": true
+            }),
+        ];
+
+        for instance in instances {
+            assert_same_errors(&schema, instance.clone(), ValidateOptions::new());
+            assert_same_errors(
+                &schema,
+                instance,
+                ValidateOptions::new().with_min_severity(Severity::Error),
+            );
+        }
+    }
+
+    #[test]
+    fn options() {
+        let schema = schema(json!({ "elements": { "type": "string" }}));
+        let input = r#"[1, 2, 3, "a long string"]"#;
+
+        let errors = validate_from_reader(
+            &schema,
+            input.as_bytes(),
+            ValidateOptions::new().with_max_errors(2),
+        )
+        .unwrap();
+        assert_eq!(2, errors.len());
+
+        assert!(matches!(
+            validate_from_reader(
+                &schema,
+                input.as_bytes(),
+                ValidateOptions::new().with_max_string_len(4),
+            ),
+            Err(ValidateReaderError::Validate(ValidateError::StringTooLong)),
+        ));
+
+        assert!(matches!(
+            validate_from_reader(
+                &schema,
+                input.as_bytes(),
+                ValidateOptions::new().with_max_array_len(3),
+            ),
+            Err(ValidateReaderError::Validate(ValidateError::ArrayTooLong)),
+        ));
+
+        assert!(matches!(
+            validate_from_reader(
+                &schema,
+                "[[]]".as_bytes(),
+                ValidateOptions::new().with_max_instance_depth(1),
+            ),
+            Err(ValidateReaderError::Validate(
+                ValidateError::MaxInstanceDepthExceeded
+            )),
+        ));
+
+        let schema = self::schema(json!({
+            "properties": {
+                "a": { "type": "string" },
+                "b": { "properties": { "c": { "type": "string" }}}
+            }
+        }));
+
+        let errors = validate_from_reader(
+            &schema,
+            r#"{ "b": { "d": 1 }}"#.as_bytes(),
+            ValidateOptions::new().with_ignore_missing_required(true),
+        )
+        .unwrap();
+        assert_eq!(1, errors.len());
+        assert_eq!(vec!["b", "d"], errors[0].instance_path);
+
+        let schema = self::schema(json!({
+            "definitions": { "loop": { "ref": "loop" }},
+            "ref": "loop"
+        }));
+
+        assert!(matches!(
+            validate_from_reader(
+                &schema,
+                "null".as_bytes(),
+                ValidateOptions::new().with_max_depth(3),
+            ),
+            Err(ValidateReaderError::Validate(
+                ValidateError::MaxDepthExceeded
+            )),
+        ));
+    }
+
+    #[test]
+    fn malformed_input() {
+        let schema = schema(json!({}));
+        for input in &["", "[1, 2", "{} {}", "[1, 2,]"] {
+            assert!(matches!(
+                validate_from_reader(&schema, input.as_bytes(), ValidateOptions::new()),
+                Err(ValidateReaderError::Deserialize(_)),
+            ));
+        }
+    }
+}
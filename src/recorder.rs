@@ -0,0 +1,242 @@
+use crate::{validate, Schema, ValidateError, ValidateOptions};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Records a sample of real payloads, and how they measure up against a
+/// schema.
+///
+/// A recorder sits wherever payloads pass through a system -- a request
+/// handler, a message consumer, and so on. Every payload given to
+/// [`record`][`ContractRecorder::record`] is counted, and a sample of them is
+/// validated and written out, one JSON object per line, along with any
+/// validation errors. Over time, this gives you an executable record of what
+/// traffic actually looks like compared to the contract.
+///
+/// Each line written has this shape:
+///
+/// ```json
+/// {
+///   "instance": { "name": 3 },
+///   "errors": [{ "instancePath": ["name"], "schemaPath": ["properties", "name", "type"] }]
+/// }
+/// ```
+///
+/// Errors are written the way [`ValidationErrorIndicator`][`crate::ValidationErrorIndicator`]
+/// serializes, which is the standard form of RFC 8927, so that recordings can
+/// be compared with the output of other JSON Typedef implementations.
+///
+/// ```
+/// use jtd::{ContractRecorder, Schema};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "name": { "type": "string" }}
+/// })).unwrap()).unwrap();
+///
+/// let mut recorder = ContractRecorder::new(schema, Vec::new()).with_sample_interval(2);
+///
+/// assert_eq!(Some(0), recorder.record(&json!({ "name": "a" })).unwrap());
+/// assert_eq!(None, recorder.record(&json!({ "name": "b" })).unwrap());
+/// assert_eq!(Some(1), recorder.record(&json!({ "name": 3 })).unwrap());
+///
+/// assert_eq!(3, recorder.stats().seen);
+/// assert_eq!(2, recorder.stats().recorded);
+/// assert_eq!(1, recorder.stats().mismatched);
+///
+/// let out = String::from_utf8(recorder.into_inner()).unwrap();
+/// assert_eq!(2, out.lines().count());
+/// ```
+#[derive(Debug)]
+pub struct ContractRecorder<W> {
+    schema: Arc<Schema>,
+    writer: W,
+    options: ValidateOptions,
+    sample_interval: u64,
+    stats: RecorderStats,
+}
+
+/// Counts kept by a [`ContractRecorder`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RecorderStats {
+    /// The number of payloads passed to the recorder.
+    pub seen: u64,
+
+    /// The number of payloads that were sampled, validated, and written out.
+    pub recorded: u64,
+
+    /// The number of recorded payloads that did not satisfy the schema.
+    pub mismatched: u64,
+}
+
+/// Errors that may arise from [`ContractRecorder`].
+#[derive(Debug, Error)]
+pub enum RecordError {
+    /// Validating a payload failed.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+
+    /// A payload could not be converted to JSON.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    /// Writing out a payload failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl<W: Write> ContractRecorder<W> {
+    /// Constructs a recorder that validates against `schema`, and writes what
+    /// it records to `writer`.
+    ///
+    /// By default, every payload is recorded, and validation uses the default
+    /// [`ValidateOptions`].
+    ///
+    /// The schema may be given as an `Arc<Schema>`, to share it with other
+    /// recorders, or other parts of the program, without copying it.
+    pub fn new(schema: impl Into<Arc<Schema>>, writer: W) -> Self {
+        Self {
+            schema: schema.into(),
+            writer,
+            options: ValidateOptions::new(),
+            sample_interval: 1,
+            stats: RecorderStats::default(),
+        }
+    }
+
+    /// Sets the sampling interval.
+    ///
+    /// With an interval of `n`, the first payload and every `n`th one after it
+    /// are recorded; the rest are only counted. An interval of 0 is treated
+    /// as 1.
+    pub fn with_sample_interval(mut self, sample_interval: u64) -> Self {
+        self.sample_interval = sample_interval.max(1);
+        self
+    }
+
+    /// Sets the options used to validate recorded payloads.
+    pub fn with_validate_options(mut self, options: ValidateOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Counts a payload and, if it's sampled, validates and records it.
+    ///
+    /// Returns the number of validation errors in the payload if it was
+    /// recorded, or `None` if it was not sampled.
+    pub fn record(&mut self, instance: &Value) -> Result<Option<usize>, RecordError> {
+        if !self.take_sample() {
+            return Ok(None);
+        }
+
+        self.write(instance).map(Some)
+    }
+
+    /// Like [`record`][`ContractRecorder::record`], but for any serializable
+    /// payload.
+    ///
+    /// The payload is only converted to JSON if it's sampled.
+    pub fn record_serialize<T: Serialize>(
+        &mut self,
+        payload: &T,
+    ) -> Result<Option<usize>, RecordError> {
+        if !self.take_sample() {
+            return Ok(None);
+        }
+
+        let instance = serde_json::to_value(payload)?;
+        self.write(&instance).map(Some)
+    }
+
+    // Counts a payload, returning whether it's sampled.
+    fn take_sample(&mut self) -> bool {
+        let sampled = self.stats.seen % self.sample_interval == 0;
+        self.stats.seen += 1;
+        sampled
+    }
+
+    fn write(&mut self, instance: &Value) -> Result<usize, RecordError> {
+        let errors = validate(&self.schema, instance, self.options.clone())?;
+
+        serde_json::to_writer(
+            &mut self.writer,
+            &json!({ "instance": instance, "errors": errors }),
+        )?;
+        self.writer.write_all(b"\n")?;
+
+        self.stats.recorded += 1;
+        if !errors.is_empty() {
+            self.stats.mismatched += 1;
+        }
+
+        Ok(errors.len())
+    }
+
+    /// The counts of payloads seen, recorded, and mismatched so far.
+    pub fn stats(&self) -> RecorderStats {
+        self.stats
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<(), RecordError> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Consumes the recorder, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_samples() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "elements": { "type": "uint8" }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let mut recorder = ContractRecorder::new(schema, Vec::new()).with_sample_interval(3);
+        for i in 0..7 {
+            recorder.record_serialize(&vec![i * 100]).unwrap();
+        }
+
+        assert_eq!(
+            RecorderStats {
+                seen: 7,
+                recorded: 3,
+                mismatched: 2,
+            },
+            recorder.stats()
+        );
+
+        let lines: Vec<Value> = String::from_utf8(recorder.into_inner())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(
+            vec![
+                json!({ "instance": [0], "errors": [] }),
+                json!({
+                    "instance": [300],
+                    "errors": [{ "instancePath": ["0"], "schemaPath": ["elements", "type"] }],
+                }),
+                json!({
+                    "instance": [600],
+                    "errors": [{ "instancePath": ["0"], "schemaPath": ["elements", "type"] }],
+                }),
+            ],
+            lines
+        );
+    }
+}
@@ -0,0 +1,22 @@
+use crate::Schema;
+use jtd_core::__private::resolve_ref_with;
+
+// Follows refs from a schema, returning the schema they lead to, and whether
+// any schema along the way is nullable.
+//
+// Refs that lead nowhere, because they're to a missing definition or go
+// around in a cycle of nothing but refs, can't be checked against anything.
+// They're treated as the empty form, since any JSON is as good as anything
+// else for them.
+pub(crate) fn resolve<'a>(root: &'a Schema, schema: &'a Schema) -> (&'a Schema, bool) {
+    let mut nullable = false;
+    match resolve_ref_with(schema, root, |schema| nullable |= schema.nullable()) {
+        Ok(schema) => (schema, nullable),
+        Err(_) => (&EMPTY, true),
+    }
+}
+
+static EMPTY: Schema = Schema::Empty {
+    definitions: std::collections::BTreeMap::new(),
+    metadata: std::collections::BTreeMap::new(),
+};
@@ -0,0 +1,301 @@
+//! Render validation statistics as documents for people to read.
+//!
+//! [`Renderer`] turns [`CorpusStats`] into a standalone Markdown or HTML
+//! document, with a table of the parts of the schema that were violated,
+//! how often, and examples of the values that were rejected. The result is
+//! suitable for attaching to CI runs, or pasting into data-quality tickets.
+//!
+//! This module is only available with the `render` feature.
+//!
+//! ```
+//! use jtd::{render::Renderer, Schema};
+//! use serde_json::json;
+//!
+//! let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+//!     "properties": { "id": { "type": "string" }}
+//! })).unwrap()).unwrap();
+//!
+//! let instances = vec![json!({ "id": "a" }), json!({ "id": 1 })];
+//! let results: Vec<_> = instances
+//!     .iter()
+//!     .map(|instance| (instance, jtd::validate(&schema, instance, Default::default()).unwrap()))
+//!     .collect();
+//!
+//! let stats = jtd::stats::aggregate(&results);
+//! assert_eq!(
+//!     "# Users\n\
+//!      \n\
+//!      2 records, of which 1 (50.0%) had validation errors.\n\
+//!      \n\
+//!      | Schema path | Violations | Affected records | Examples |\n\
+//!      | --- | ---: | ---: | --- |\n\
+//!      | `/properties/id/type` | 1 | 1 (50.0%) | `1` |\n",
+//!     Renderer::new(&stats).with_title("Users").markdown(),
+//! );
+//! ```
+
+use crate::stats::{CorpusStats, PathStats};
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Renders [`CorpusStats`] as Markdown or HTML.
+///
+/// Schema paths are listed most affected records first. Examples longer than
+/// [`with_max_example_len`][`Renderer::with_max_example_len`] are cut short.
+#[derive(Clone, Debug)]
+pub struct Renderer<'a> {
+    stats: &'a CorpusStats,
+    title: String,
+    max_example_len: usize,
+}
+
+impl<'a> Renderer<'a> {
+    /// Constructs a renderer for `stats`, titled "Validation report", that
+    /// shows up to 80 characters of each example.
+    pub fn new(stats: &'a CorpusStats) -> Self {
+        Self {
+            stats,
+            title: "Validation report".to_owned(),
+            max_example_len: 80,
+        }
+    }
+
+    /// Sets the title of the document.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Sets the most characters of each example to show.
+    pub fn with_max_example_len(mut self, max_example_len: usize) -> Self {
+        self.max_example_len = max_example_len;
+        self
+    }
+
+    /// Renders a GitHub Flavored Markdown document.
+    pub fn markdown(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "# {}\n", self.title).unwrap();
+        writeln!(out, "{}", self.overview()).unwrap();
+
+        let paths = self.paths();
+        if paths.is_empty() {
+            return out;
+        }
+
+        out.push_str("\n| Schema path | Violations | Affected records | Examples |\n");
+        out.push_str("| --- | ---: | ---: | --- |\n");
+        for (path, stats) in paths {
+            let examples: Vec<_> = stats
+                .examples
+                .iter()
+                .map(|example| code_span(&self.example(example)))
+                .collect();
+
+            writeln!(
+                out,
+                "| {} | {} | {} ({:.1}%) | {} |",
+                code_span(path),
+                stats.violations,
+                stats.affected_records,
+                stats.affected_percentage,
+                examples.join("<br>"),
+            )
+            .unwrap();
+        }
+
+        out
+    }
+
+    /// Renders a standalone HTML document.
+    ///
+    /// The document has no external resources, so it can be opened from a
+    /// file or attachment as-is.
+    pub fn html(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        writeln!(out, "<title>{}</title>", escape(&self.title)).unwrap();
+        out.push_str(concat!(
+            "<style>\n",
+            "body { font-family: sans-serif; margin: 2em; }\n",
+            "table { border-collapse: collapse; }\n",
+            "th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; }\n",
+            "td.n { text-align: right; }\n",
+            "</style>\n",
+        ));
+        out.push_str("</head>\n<body>\n");
+        writeln!(out, "<h1>{}</h1>", escape(&self.title)).unwrap();
+        writeln!(out, "<p>{}</p>", escape(&self.overview())).unwrap();
+
+        let paths = self.paths();
+        if !paths.is_empty() {
+            out.push_str("<table>\n<thead>\n<tr><th>Schema path</th><th>Violations</th><th>Affected records</th><th>Examples</th></tr>\n</thead>\n<tbody>\n");
+            for (path, stats) in paths {
+                let examples: Vec<_> = stats
+                    .examples
+                    .iter()
+                    .map(|example| format!("<code>{}</code>", escape(&self.example(example))))
+                    .collect();
+
+                writeln!(
+                    out,
+                    "<tr><td><code>{}</code></td><td class=\"n\">{}</td><td class=\"n\">{} ({:.1}%)</td><td>{}</td></tr>",
+                    escape(path),
+                    stats.violations,
+                    stats.affected_records,
+                    stats.affected_percentage,
+                    examples.join("<br>"),
+                )
+                .unwrap();
+            }
+
+            out.push_str("</tbody>\n</table>\n");
+        }
+
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    fn overview(&self) -> String {
+        let records = self.stats.records;
+        let invalid = self.stats.invalid_records;
+        let noun = if records == 1 { "record" } else { "records" };
+
+        if invalid == 0 {
+            format!("{} {}, with no validation errors.", records, noun)
+        } else {
+            format!(
+                "{} {}, of which {} ({:.1}%) had validation errors.",
+                records,
+                noun,
+                invalid,
+                invalid as f64 * 100.0 / records as f64,
+            )
+        }
+    }
+
+    // The schema paths, most affected records first, and otherwise in order.
+    fn paths(&self) -> Vec<(&'a String, &'a PathStats)> {
+        let mut paths: Vec<_> = self.stats.paths.iter().collect();
+        paths.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.affected_records));
+        paths
+    }
+
+    fn example(&self, example: &Value) -> String {
+        let example = example.to_string();
+        match example.char_indices().nth(self.max_example_len) {
+            Some((end, _)) => format!("{}…", &example[..end]),
+            None => example,
+        }
+    }
+}
+
+// Formats text as a Markdown code span that's safe to put in a table cell.
+fn code_span(text: &str) -> String {
+    // A code span is delimited by a run of backticks longer than any within
+    // it, and padded with spaces if its content starts or ends with one.
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+
+    let fence = "`".repeat(longest + 1);
+    let pad = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+
+    // Pipes end table cells even within code spans, unless escaped.
+    let text = text.replace('|', "\\|");
+    format!("{}{}{}{}{}", fence, pad, text, pad, fence)
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::Aggregator;
+    use crate::Schema;
+    use serde_json::json;
+
+    fn stats() -> CorpusStats {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "properties": {
+                    "name": { "type": "string" },
+                    "tags": { "elements": { "type": "uint8" }}
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let mut aggregator = Aggregator::new();
+        for instance in &[
+            json!({ "name": "a", "tags": [] }),
+            json!({ "name": 1, "tags": [300, "a"] }),
+            json!({ "name": 2, "tags": ["long string, to be cut short"] }),
+        ] {
+            let errors = crate::validate(&schema, instance, Default::default()).unwrap();
+            aggregator.add(instance, &errors);
+        }
+
+        aggregator.finish()
+    }
+
+    #[test]
+    fn markdown() {
+        assert_eq!(
+            "# Validation report\n\
+             \n\
+             3 records, of which 2 (66.7%) had validation errors.\n\
+             \n\
+             | Schema path | Violations | Affected records | Examples |\n\
+             | --- | ---: | ---: | --- |\n\
+             | `/properties/name/type` | 2 | 2 (66.7%) | `1`<br>`2` |\n\
+             | `/properties/tags/elements/type` | 3 | 2 (66.7%) | `300`<br>`\"a\"`<br>`\"long string…` |\n",
+            Renderer::new(&stats()).with_max_example_len(12).markdown()
+        );
+
+        assert_eq!("`` `a\\|b` ``", code_span("`a|b`"));
+
+        assert_eq!(
+            "# Validation report\n\n0 records, with no validation errors.\n",
+            Renderer::new(&CorpusStats::default()).markdown()
+        );
+    }
+
+    #[test]
+    fn html() {
+        let html = Renderer::new(&stats())
+            .with_title("<Users & \"tags\">")
+            .html();
+
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.ends_with("</html>\n"));
+        assert!(html.contains("<title>&lt;Users &amp; &quot;tags&quot;&gt;</title>"));
+        assert!(html.contains(
+            "<tr><td><code>/properties/name/type</code></td><td class=\"n\">2</td>\
+             <td class=\"n\">2 (66.7%)</td><td><code>1</code><br><code>2</code></td></tr>"
+        ));
+        assert!(html.contains("<code>&quot;a&quot;</code>"));
+    }
+}
@@ -0,0 +1,521 @@
+//! Produce reports of validation results, for machines or for people.
+
+use crate::{
+    validate, InstancePath, Schema, Type, ValidateError, ValidateOptions, ValidationErrorIndicator,
+};
+use jtd_core::__private::resolve;
+use serde_json::{json, Map, Value};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use thiserror::Error;
+
+/// Writes validation results as newline-delimited JSON.
+///
+/// Each call to [`write`][`ReportWriter::write`] writes one line, describing
+/// one validated record: where it came from, whether it was valid, and its
+/// validation errors. A line looks like this (shown here across several
+/// lines):
+///
+/// ```json
+/// {
+///   "file": "users.ndjson",
+///   "line": 17,
+///   "valid": false,
+///   "errors": [{ "instancePath": "/age", "schemaPath": "/properties/age/type", "value": "43" }]
+/// }
+/// ```
+///
+/// `file` and `line` are left out if they aren't given. `value`, the part of
+/// the record that was rejected, is only included if enabled with
+/// [`with_captured_values`][`ReportWriter::with_captured_values`]. For errors
+/// about something missing, such as a required property, it's the object the
+/// thing is missing from. Paths are formatted as JSON Pointers.
+///
+/// ```
+/// use jtd::{Schema, report::ReportWriter};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "age": { "type": "uint8" }}
+/// })).unwrap()).unwrap();
+///
+/// let mut writer = ReportWriter::new(Vec::new()).with_captured_values(true);
+/// writer.validate_and_write(&schema, Some("users.ndjson"), Some(1), &json!({ "age": 43 })).unwrap();
+/// writer.validate_and_write(&schema, Some("users.ndjson"), Some(2), &json!({ "age": "43" })).unwrap();
+///
+/// let out = String::from_utf8(writer.into_inner()).unwrap();
+/// let lines: Vec<serde_json::Value> = out.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+///
+/// assert_eq!(
+///     json!({ "file": "users.ndjson", "line": 1, "valid": true, "errors": [] }),
+///     lines[0],
+/// );
+///
+/// assert_eq!(
+///     json!({
+///         "file": "users.ndjson",
+///         "line": 2,
+///         "valid": false,
+///         "errors": [{
+///             "instancePath": "/age",
+///             "schemaPath": "/properties/age/type",
+///             "value": "43",
+///         }],
+///     }),
+///     lines[1],
+/// );
+/// ```
+#[derive(Debug)]
+pub struct ReportWriter<W> {
+    writer: W,
+    options: ValidateOptions,
+    capture_values: bool,
+}
+
+/// Errors that may arise from [`ReportWriter`].
+#[derive(Debug, Error)]
+pub enum ReportError {
+    /// Validating a record failed.
+    #[error(transparent)]
+    Validate(#[from] ValidateError),
+
+    /// Writing out a record failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl<W: Write> ReportWriter<W> {
+    /// Constructs a report writer that writes to `writer`.
+    ///
+    /// By default, rejected values aren't captured, and
+    /// [`validate_and_write`][`ReportWriter::validate_and_write`] uses the
+    /// default [`ValidateOptions`].
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            options: ValidateOptions::new(),
+            capture_values: false,
+        }
+    }
+
+    /// Sets whether to include the rejected part of each record alongside its
+    /// validation errors.
+    pub fn with_captured_values(mut self, capture_values: bool) -> Self {
+        self.capture_values = capture_values;
+        self
+    }
+
+    /// Sets the options used by
+    /// [`validate_and_write`][`ReportWriter::validate_and_write`].
+    pub fn with_validate_options(mut self, options: ValidateOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Writes out the result of validating `instance`, which came from the
+    /// given file and line, if known.
+    pub fn write(
+        &mut self,
+        file: Option<&str>,
+        line: Option<u64>,
+        instance: &Value,
+        errors: &[ValidationErrorIndicator],
+    ) -> Result<(), std::io::Error> {
+        let errors: Vec<Value> = errors
+            .iter()
+            .map(|error| {
+                let instance_path = error.instance_path.to_string();
+                let mut entry = json!({
+                    "instancePath": instance_path,
+                    "schemaPath": error.schema_path.to_string(),
+                });
+
+                if self.capture_values {
+                    if let Some(value) = instance.pointer(&instance_path) {
+                        entry["value"] = value.clone();
+                    }
+                }
+
+                entry
+            })
+            .collect();
+
+        let mut record = Map::new();
+        if let Some(file) = file {
+            record.insert("file".to_owned(), file.into());
+        }
+
+        if let Some(line) = line {
+            record.insert("line".to_owned(), line.into());
+        }
+
+        record.insert("valid".to_owned(), errors.is_empty().into());
+        record.insert("errors".to_owned(), errors.into());
+
+        serde_json::to_writer(&mut self.writer, &record)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// Validates `instance` against `schema`, and writes out the result.
+    ///
+    /// Returns the number of validation errors.
+    pub fn validate_and_write(
+        &mut self,
+        schema: &Schema,
+        file: Option<&str>,
+        line: Option<u64>,
+        instance: &Value,
+    ) -> Result<usize, ReportError> {
+        let errors = validate(schema, instance, self.options.clone())?;
+        self.write(file, line, instance, &errors)?;
+        Ok(errors.len())
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.writer.flush()
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Consumes the report writer, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Summarizes validation errors for people to read.
+///
+/// Validating a batch of records against `schema` can produce many errors,
+/// most of them the same problem recurring in record after record. This
+/// function groups together errors that are the same problem at the same
+/// place, describes each group in words, and lists them most common first,
+/// separated by `"; "`, all on one line.
+///
+/// Array indices, and the keys of values-form objects, are written as `*` in
+/// the places problems are reported at, so that problems in different
+/// elements count as the same problem. Errors that appear more than once in
+/// `errors` are counted once.
+///
+/// `errors` must have come from validating against `schema`, and `schema`
+/// must be valid. If there are no errors, the summary is empty.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "elements": {
+///         "properties": {
+///             "id": { "type": "string" },
+///             "created_at": { "type": "timestamp" }
+///         }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let instance = json!([
+///     { "created_at": "2020-01-01T00:00:00Z" },
+///     { "created_at": "yesterday" },
+///     { "id": "c", "created_at": "yesterday" },
+///     { "created_at": "2020-01-01T00:00:00Z" },
+/// ]);
+///
+/// let errors = jtd::validate(&schema, &instance, Default::default()).unwrap();
+/// assert_eq!(
+///     "3 items missing required property 'id' at /*; \
+///      2 items with a value that is not an RFC 3339 timestamp at /*/created_at",
+///     jtd::report::summarize(&errors, &schema),
+/// );
+/// ```
+pub fn summarize(errors: &[ValidationErrorIndicator], schema: &Schema) -> String {
+    let mut seen = BTreeSet::new();
+    let mut groups: Vec<(String, usize)> = Vec::new();
+    let mut indices = HashMap::new();
+
+    for error in errors {
+        if !seen.insert((&error.instance_path, &error.schema_path)) {
+            continue;
+        }
+
+        let (location, problem) = describe(schema, error);
+        let summary = if location.is_empty() {
+            problem
+        } else {
+            format!("{} at {}", problem, location)
+        };
+
+        let index = *indices.entry(summary.clone()).or_insert_with(|| {
+            groups.push((summary, 0));
+            groups.len() - 1
+        });
+
+        groups[index].1 += 1;
+    }
+
+    // A stable sort, so that equally common problems stay in the order they
+    // were first seen.
+    groups.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+    groups
+        .into_iter()
+        .map(|(summary, count)| {
+            let items = if count == 1 { "item" } else { "items" };
+            format!("{} {} {}", count, items, summary)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// Describes where an error happened, with array indices and values-form keys
+// replaced by "*", and what the problem was.
+fn describe(root: &Schema, error: &ValidationErrorIndicator) -> (String, String) {
+    let mut instance_path = &error.instance_path[..];
+
+    let problem = match resolve(root, &error.schema_path) {
+        Some((Schema::Properties { .. }, [])) => {
+            pop(&mut instance_path).map(|name| format!("with unexpected property '{}'", name))
+        }
+        Some((Schema::Properties { .. }, [keyword, name])) if keyword == "properties" => {
+            Some(format!("missing required property '{}'", name))
+        }
+        Some((Schema::Type { type_, .. }, [keyword])) if keyword == "type" => {
+            Some(format!("with a value that is not {}", describe_type(type_)))
+        }
+        Some((_, [keyword])) if keyword == "enum" => {
+            Some("with a value that is not one of the enum values".to_owned())
+        }
+        Some((Schema::Elements { .. }, [keyword])) if keyword == "elements" => {
+            Some("with a value that is not an array".to_owned())
+        }
+        Some((Schema::Values { .. }, [keyword])) if keyword == "values" => {
+            Some("with a value that is not an object".to_owned())
+        }
+        Some((Schema::Properties { .. }, [keyword]))
+            if keyword == "properties" || keyword == "optionalProperties" =>
+        {
+            Some("with a value that is not an object".to_owned())
+        }
+        Some((Schema::Discriminator { discriminator, .. }, [keyword]))
+            if keyword == "discriminator" =>
+        {
+            // The error is at the discriminator if it isn't a string, or at
+            // the object if it's missing or the object isn't an object.
+            if instance_path.last().map(|t| t.as_ref()) == Some(discriminator.as_str()) {
+                pop(&mut instance_path);
+                Some(format!(
+                    "with a non-string discriminator '{}'",
+                    discriminator
+                ))
+            } else {
+                Some(format!("missing discriminator '{}'", discriminator))
+            }
+        }
+        Some((Schema::Discriminator { discriminator, .. }, [keyword])) if keyword == "mapping" => {
+            pop(&mut instance_path);
+            Some(format!(
+                "with an unknown value of discriminator '{}'",
+                discriminator
+            ))
+        }
+        _ => None,
+    };
+
+    // Errors this function doesn't understand, such as those from extension
+    // keywords, are described by where they are in the schema.
+    let problem = problem.unwrap_or_else(|| {
+        instance_path = &error.instance_path;
+        format!("failing {}", error.schema_path)
+    });
+
+    (
+        InstancePath::from(generalize(root, instance_path)).to_string(),
+        problem,
+    )
+}
+
+// Some problems are about a property of the value at fault, and are reported
+// at that value rather than at the property.
+fn pop<'p, 'a>(instance_path: &mut &'p [Cow<'a, str>]) -> Option<&'p Cow<'a, str>> {
+    let (last, rest) = instance_path.split_last()?;
+    *instance_path = rest;
+    Some(last)
+}
+
+// Replaces the tokens of an instance path that are array indices or
+// values-form keys with "*".
+//
+// This follows the instance path through the schema, rather than following
+// the schema path, because schema paths that go through a ref start over from
+// the definition.
+fn generalize<'a>(root: &Schema, instance_path: &[Cow<'a, str>]) -> Vec<Cow<'a, str>> {
+    let mut schema = Some(root);
+    instance_path
+        .iter()
+        .map(|token| {
+            schema = schema.and_then(|schema| schema.resolve_ref(root).ok());
+
+            match schema {
+                Some(Schema::Elements { elements, .. }) => {
+                    schema = Some(elements);
+                    "*".into()
+                }
+                Some(Schema::Values { values, .. }) => {
+                    schema = Some(values);
+                    "*".into()
+                }
+                Some(Schema::Properties {
+                    properties,
+                    optional_properties,
+                    ..
+                }) => {
+                    schema = properties
+                        .get(token.as_ref())
+                        .or_else(|| optional_properties.get(token.as_ref()));
+                    token.clone()
+                }
+                Some(Schema::Discriminator { mapping, .. }) => {
+                    // Without the instance, it isn't known which mapping
+                    // applies, so the first one with this property is used.
+                    schema = mapping.values().find_map(|sub_schema| match sub_schema {
+                        Schema::Properties {
+                            properties,
+                            optional_properties,
+                            ..
+                        } => properties
+                            .get(token.as_ref())
+                            .or_else(|| optional_properties.get(token.as_ref())),
+                        _ => None,
+                    });
+                    token.clone()
+                }
+                _ => {
+                    schema = None;
+                    token.clone()
+                }
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn describe_type(type_: &Type) -> Cow<'static, str> {
+    Cow::Borrowed(match type_ {
+        Type::Boolean => "a boolean",
+        Type::Int8 => "an int8",
+        Type::Uint8 => "a uint8",
+        Type::Int16 => "an int16",
+        Type::Uint16 => "a uint16",
+        Type::Int32 => "an int32",
+        Type::Uint32 => "a uint32",
+        Type::Float32 | Type::Float64 => "a number",
+        Type::String => "a string",
+        Type::Timestamp => "an RFC 3339 timestamp",
+        #[cfg(feature = "extensions")]
+        Type::Custom(name) => return Cow::Owned(format!("of type '{}'", name)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_values() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "properties": { "a/b": { "elements": { "type": "string" }}}
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let mut writer = ReportWriter::new(Vec::new()).with_captured_values(true);
+        assert_eq!(
+            2,
+            writer
+                .validate_and_write(&schema, None, None, &json!({ "a/b": ["x", 1, 2] }))
+                .unwrap()
+        );
+        assert_eq!(
+            1,
+            writer
+                .validate_and_write(&schema, None, Some(7), &json!({}))
+                .unwrap()
+        );
+
+        let lines: Vec<Value> = String::from_utf8(writer.into_inner())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(
+            vec![
+                json!({
+                    "valid": false,
+                    "errors": [
+                        { "instancePath": "/a~1b/1", "schemaPath": "/properties/a~1b/elements/type", "value": 1 },
+                        { "instancePath": "/a~1b/2", "schemaPath": "/properties/a~1b/elements/type", "value": 2 },
+                    ],
+                }),
+                json!({
+                    "line": 7,
+                    "valid": false,
+                    "errors": [{ "instancePath": "", "schemaPath": "/properties/a~1b", "value": {} }],
+                }),
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn summarizes() {
+        let schema = Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "definitions": {
+                    "tags": { "elements": { "enum": ["a", "b"] }}
+                },
+                "values": {
+                    "discriminator": "kind",
+                    "mapping": {
+                        "user": {
+                            "properties": { "tags": { "ref": "tags" }},
+                            "optionalProperties": { "age": { "type": "uint8" }}
+                        }
+                    }
+                }
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let instance = json!({
+            "alice": { "kind": "user", "tags": ["a", "c"], "age": -1 },
+            "bob": { "kind": "user", "tags": "a", "extra": 1 },
+            "carol": { "kind": "group", "tags": [] },
+            "dave": { "kind": 1 },
+            "erin": {},
+            "frank": { "kind": "user", "tags": ["c", "c"], "extra": 2 },
+            "grace": null,
+        });
+
+        let mut errors = validate(&schema, &instance, Default::default()).unwrap();
+        errors.extend(errors.clone());
+
+        assert_eq!(
+            "3 items with a value that is not one of the enum values at /*/tags/*; \
+             2 items with unexpected property 'extra' at /*; \
+             2 items missing discriminator 'kind' at /*; \
+             1 item with a value that is not a uint8 at /*/age; \
+             1 item with a value that is not an array at /*/tags; \
+             1 item with an unknown value of discriminator 'kind' at /*; \
+             1 item with a non-string discriminator 'kind' at /*",
+            summarize(&errors, &schema)
+        );
+
+        assert_eq!("", summarize(&[], &schema));
+    }
+}
@@ -0,0 +1,1159 @@
+use crate::stream::{Ctx, Object, Property, Rejected, Resolved};
+use crate::{Schema, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde::ser::{self, Impossible, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+/// A [`serde::Serializer`] that validates data against a schema as it is
+/// being serialized.
+///
+/// This is the counterpart of [`Deserializer`][`crate::Deserializer`]: it
+/// wraps any other serializer, and checks each value passed to it against a
+/// [`Schema`] before passing it along. This lets you check that the data you
+/// emit matches your contract as part of emitting it, rather than by
+/// serializing it a second time into a [`serde_json::Value`] and validating
+/// that.
+///
+/// As soon as a value is found that the schema rejects, serialization is
+/// aborted. Use [`Serializer::serialize`] to get the corresponding
+/// [`ValidationErrorIndicator`] back, in a [`SerializeError::Invalid`].
+///
+/// ```
+/// use jtd::{Schema, SerializeError};
+/// use serde_json::json;
+///
+/// #[derive(serde::Serialize)]
+/// struct User {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": {
+///         "name": { "type": "string" },
+///         "age": { "type": "uint8" }
+///     }
+/// })).unwrap()).unwrap();
+///
+/// let mut out = Vec::new();
+/// let user = User { name: "John".into(), age: 43 };
+/// jtd::Serializer::new(&mut serde_json::Serializer::new(&mut out), &schema)
+///     .serialize(&user)
+///     .unwrap();
+/// assert_eq!(br#"{"name":"John","age":43}"#, &out[..]);
+///
+/// // age is valid for the Rust type, but not for the schema.
+/// let mut out = Vec::new();
+/// let user = User { name: "John".into(), age: 300 };
+/// match jtd::Serializer::new(&mut serde_json::Serializer::new(&mut out), &schema)
+///     .serialize(&user)
+/// {
+///     Err(SerializeError::Invalid(indicator)) => {
+///         assert_eq!(vec!["age"], indicator.instance_path);
+///         assert_eq!(vec!["properties", "age", "type"], indicator.schema_path);
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+///
+/// Whatever was written before the invalid value was found is left in the
+/// wrapped serializer's output.
+///
+/// Like [`Deserializer`][`crate::Deserializer`], only the first validation
+/// error is reported. In a discriminator-form schema, the values of properties
+/// that come before the discriminator are also converted to a
+/// [`serde_json::Value`], and checked once the discriminator is found.
+pub struct Serializer<'s, S> {
+    inner: S,
+    ctx: Ctx<'s>,
+}
+
+/// Errors that may arise from [`Serializer::serialize`].
+#[derive(Debug, Error)]
+pub enum SerializeError<E> {
+    /// The output was rejected by the schema.
+    #[error(
+        "instance at {} rejected by schema at {}",
+        .0.instance_path,
+        .0.schema_path
+    )]
+    Invalid(ValidationErrorIndicator<'static>),
+
+    /// The output exceeded one of the per-value limits of the options, such
+    /// as [`ValidateOptions::with_max_string_len`].
+    #[error(transparent)]
+    Validate(ValidateError),
+
+    /// The wrapped serializer returned an error.
+    #[error(transparent)]
+    Inner(E),
+}
+
+impl<'s, S> Serializer<'s, S> {
+    /// Wraps a serializer, so that the data written to it is checked against
+    /// `schema`.
+    ///
+    /// `schema` is expected to be a root schema; any refs in it are resolved
+    /// against its definitions.
+    pub fn new(inner: S, schema: &'s Schema) -> Self {
+        Self {
+            inner,
+            ctx: Ctx::new(schema),
+        }
+    }
+
+    /// Sets the options that decide which values are valid, such as
+    /// [`ValidateOptions::with_case_insensitive_enums`] and
+    /// [`ValidateOptions::with_timestamp_validator`].
+    ///
+    /// The per-value limits, such as [`ValidateOptions::with_max_string_len`],
+    /// are checked as the output is written, and give a
+    /// [`SerializeError::Validate`] if it exceeds them. Options about which
+    /// errors are reported, such as [`ValidateOptions::with_max_errors`], have
+    /// no effect: the first error always aborts serialization.
+    pub fn with_options(self, options: ValidateOptions) -> Self {
+        self.ctx.state.borrow_mut().options = options;
+        self
+    }
+
+    /// Serializes `value`, checking the output against the schema along the
+    /// way.
+    pub fn serialize<T>(self, value: &T) -> Result<S::Ok, SerializeError<S::Error>>
+    where
+        S: ser::Serializer,
+        T: ?Sized + Serialize,
+    {
+        let state = self.ctx.state.clone();
+        value.serialize(self).map_err(|err| {
+            let mut state = state.borrow_mut();
+            match (state.error.take(), state.limit.take()) {
+                (Some(indicator), _) => SerializeError::Invalid(indicator),
+                (None, Some(limit)) => SerializeError::Validate(limit),
+                (None, None) => SerializeError::Inner(err),
+            }
+        })
+    }
+}
+
+// A value to be serialized through a validating serializer.
+struct Checked<'a, 's, T: ?Sized> {
+    value: &'a T,
+    ctx: Ctx<'s>,
+}
+
+impl<'a, 's, T> Serialize for Checked<'a, 's, T>
+where
+    T: ?Sized + Serialize,
+{
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(Serializer {
+            inner: serializer,
+            ctx: self.ctx.clone(),
+        })
+    }
+}
+
+macro_rules! serialize_int {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<S::Ok, S::Error> {
+                self.scalar(|ctx, _| ctx.check_int(v as f64))?;
+                self.inner.$method(v)
+            }
+        )*
+    };
+}
+
+impl<'s, S> Serializer<'s, S>
+where
+    S: ser::Serializer,
+{
+    fn scalar<F>(&self, check: F) -> Result<(), S::Error>
+    where
+        F: FnOnce(&Ctx<'s>, bool) -> Result<(), Rejected>,
+    {
+        let resolved = self.ctx.clone().resolve().map_err(ser::Error::custom)?;
+        let result = check(&resolved.ctx, resolved.nullable);
+        resolved.finish();
+        result.map_err(ser::Error::custom)
+    }
+
+    // Resolves the schema of a value that's the single property of an object,
+    // as externally tagged enum variants are, and enters that property.
+    fn variant(&self, variant: &'static str) -> Result<Option<VariantFrame<'s>>, S::Error> {
+        let outer = self.ctx.clone().resolve().map_err(ser::Error::custom)?;
+        let mut object = match begin_map(&outer).map_err(ser::Error::custom)? {
+            Kind::Object { object, .. } => object,
+            _ => {
+                outer.finish();
+                return Ok(None);
+            }
+        };
+
+        outer.ctx.enter_nested().map_err(ser::Error::custom)?;
+
+        let entered = enter(&mut object, variant).map_err(ser::Error::custom)?;
+        Ok(Some(VariantFrame {
+            outer,
+            object,
+            entered,
+        }))
+    }
+}
+
+impl<'s, S> ser::Serializer for Serializer<'s, S>
+where
+    S: ser::Serializer,
+{
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Compound<'s, S::SerializeSeq>;
+    type SerializeTuple = Compound<'s, S::SerializeTuple>;
+    type SerializeTupleStruct = Compound<'s, S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Compound<'s, S::SerializeTupleVariant>;
+    type SerializeMap = Compound<'s, S::SerializeMap>;
+    type SerializeStruct = Compound<'s, S::SerializeStruct>;
+    type SerializeStructVariant = Compound<'s, S::SerializeStructVariant>;
+
+    fn serialize_bool(self, v: bool) -> Result<S::Ok, S::Error> {
+        self.scalar(|ctx, _| ctx.check_bool())?;
+        self.inner.serialize_bool(v)
+    }
+
+    serialize_int! {
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+        serialize_f32(f32);
+        serialize_f64(f64);
+    }
+
+    fn serialize_char(self, v: char) -> Result<S::Ok, S::Error> {
+        self.scalar(|ctx, _| ctx.check_str(v.encode_utf8(&mut [0; 4])))?;
+        self.inner.serialize_char(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<S::Ok, S::Error> {
+        self.scalar(|ctx, _| ctx.check_str(v))?;
+        self.inner.serialize_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<S::Ok, S::Error> {
+        self.scalar(|ctx, _| ctx.check_bytes())?;
+        self.inner.serialize_bytes(v)
+    }
+
+    fn serialize_none(self) -> Result<S::Ok, S::Error> {
+        self.scalar(|ctx, nullable| ctx.check_null(nullable))?;
+        self.inner.serialize_none()
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.inner.serialize_some(&Checked {
+            value,
+            ctx: self.ctx,
+        })
+    }
+
+    fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+        self.scalar(|ctx, nullable| ctx.check_null(nullable))?;
+        self.inner.serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+        self.scalar(|ctx, nullable| ctx.check_null(nullable))?;
+        self.inner.serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<S::Ok, S::Error> {
+        self.scalar(|ctx, _| ctx.check_str(variant))?;
+        self.inner
+            .serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.inner.serialize_newtype_struct(
+            name,
+            &Checked {
+                value,
+                ctx: self.ctx,
+            },
+        )
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let frame = self.variant(variant)?;
+        let ctx = match frame {
+            Some(ref frame) => frame.entered.ctx.clone(),
+            None => self.ctx.clone().with_empty(),
+        };
+
+        let ok = self.inner.serialize_newtype_variant(
+            name,
+            variant_index,
+            variant,
+            &Checked { value, ctx },
+        )?;
+
+        if let Some(frame) = frame {
+            frame.finish().map_err(ser::Error::custom)?;
+        }
+
+        Ok(ok)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        let (resolved, kind) = self.begin(begin_seq)?;
+        let inner = self.inner.serialize_seq(len)?;
+        Ok(Compound::new(inner, resolved, kind, None))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        let (resolved, kind) = self.begin(begin_seq)?;
+        let inner = self.inner.serialize_tuple(len)?;
+        Ok(Compound::new(inner, resolved, kind, None))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        let (resolved, kind) = self.begin(begin_seq)?;
+        let inner = self.inner.serialize_tuple_struct(name, len)?;
+        Ok(Compound::new(inner, resolved, kind, None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        let frame = self.variant(variant)?;
+        let ctx = match frame {
+            Some(ref frame) => frame.entered.ctx.clone(),
+            None => self.ctx.clone().with_empty(),
+        };
+
+        let (resolved, kind) = begin(ctx, begin_seq).map_err(ser::Error::custom)?;
+        let inner = self
+            .inner
+            .serialize_tuple_variant(name, variant_index, variant, len)?;
+        Ok(Compound::new(inner, resolved, kind, frame))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        let (resolved, kind) = self.begin(begin_map)?;
+        let inner = self.inner.serialize_map(len)?;
+        Ok(Compound::new(inner, resolved, kind, None))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        let (resolved, kind) = self.begin(begin_map)?;
+        let inner = self.inner.serialize_struct(name, len)?;
+        Ok(Compound::new(inner, resolved, kind, None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        let frame = self.variant(variant)?;
+        let ctx = match frame {
+            Some(ref frame) => frame.entered.ctx.clone(),
+            None => self.ctx.clone().with_empty(),
+        };
+
+        let (resolved, kind) = begin(ctx, begin_map).map_err(ser::Error::custom)?;
+        let inner = self
+            .inner
+            .serialize_struct_variant(name, variant_index, variant, len)?;
+        Ok(Compound::new(inner, resolved, kind, frame))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+impl<'s, S> Serializer<'s, S>
+where
+    S: ser::Serializer,
+{
+    fn begin<F>(&self, f: F) -> Result<(Resolved<'s>, Kind<'s>), S::Error>
+    where
+        F: FnOnce(&Resolved<'s>) -> Result<Kind<'s>, Rejected>,
+    {
+        begin(self.ctx.clone(), f).map_err(ser::Error::custom)
+    }
+}
+
+fn begin<'s, F>(ctx: Ctx<'s>, f: F) -> Result<(Resolved<'s>, Kind<'s>), Rejected>
+where
+    F: FnOnce(&Resolved<'s>) -> Result<Kind<'s>, Rejected>,
+{
+    let resolved = ctx.resolve()?;
+    resolved.ctx.enter_nested()?;
+    let kind = f(&resolved)?;
+    Ok((resolved, kind))
+}
+
+// How the contents of a compound value are checked.
+enum Kind<'s> {
+    // Against nothing; the schema is empty. The elements of an array are
+    // still counted, for the per-value limits of the options.
+    Any {
+        len: usize,
+    },
+
+    // As elements of an array.
+    Elements {
+        ctx: Ctx<'s>,
+        index: usize,
+    },
+
+    // As properties of an object. The key of a map entry is kept until its
+    // value is serialized.
+    Object {
+        object: Object<'s>,
+        key: Option<String>,
+    },
+}
+
+fn begin_seq<'s>(resolved: &Resolved<'s>) -> Result<Kind<'s>, Rejected> {
+    match resolved.ctx.schema {
+        Schema::Empty { .. } => Ok(Kind::Any { len: 0 }),
+        Schema::Elements { elements, .. } => {
+            resolved.ctx.push_schema_token("elements");
+            Ok(Kind::Elements {
+                ctx: resolved.ctx.child(elements),
+                index: 0,
+            })
+        }
+        _ => Err(resolved.ctx.wrong_type()),
+    }
+}
+
+fn begin_map<'s>(resolved: &Resolved<'s>) -> Result<Kind<'s>, Rejected> {
+    match resolved.ctx.schema {
+        Schema::Empty { .. } if !resolved.ctx.has_limits() => Ok(Kind::Any { len: 0 }),
+        Schema::Empty { .. }
+        | Schema::Properties { .. }
+        | Schema::Values { .. }
+        | Schema::Discriminator { .. } => Ok(Kind::Object {
+            object: Object::new(resolved.ctx.clone()),
+            key: None,
+        }),
+        _ => Err(resolved.ctx.wrong_type()),
+    }
+}
+
+// A property of an object that's being serialized.
+struct Entered<'s> {
+    ctx: Ctx<'s>,
+    schema_tokens: usize,
+    instance_token: bool,
+    before_tag: bool,
+}
+
+fn enter<'s>(object: &mut Object<'s>, key: &str) -> Result<Entered<'s>, Rejected> {
+    match object.property(key)? {
+        Property::Check(tokens, sub_schema) => {
+            for token in &tokens {
+                object.ctx.push_schema_token(token);
+            }
+
+            object.ctx.push_instance_token(key);
+            Ok(Entered {
+                ctx: object.ctx.child(sub_schema),
+                schema_tokens: tokens.len(),
+                instance_token: true,
+                before_tag: false,
+            })
+        }
+        property => Ok(Entered {
+            ctx: object.ctx.clone().with_empty(),
+            schema_tokens: 0,
+            instance_token: false,
+            before_tag: matches!(property, Property::BeforeTag),
+        }),
+    }
+}
+
+impl<'s> Entered<'s> {
+    fn leave(&self, object: &Object<'s>) {
+        if self.instance_token {
+            object.ctx.pop_instance_token();
+        }
+
+        for _ in 0..self.schema_tokens {
+            object.ctx.pop_schema_token();
+        }
+    }
+}
+
+// Serializes the value of a property of an object.
+fn property<'s, T, F, R, E>(object: &mut Object<'s>, key: &str, value: &T, f: F) -> Result<R, E>
+where
+    T: ?Sized + Serialize,
+    F: FnOnce(&Checked<'_, 's, T>) -> Result<R, E>,
+    E: ser::Error,
+{
+    if let Schema::Discriminator { discriminator, .. } = object.ctx.schema {
+        if key == discriminator {
+            let tag = value.serialize(Capture { strings_only: true }).ok();
+            object.tag(tag.as_deref()).map_err(E::custom)?;
+            check_buffered(object)?;
+        }
+    }
+
+    let entered = enter(object, key).map_err(E::custom)?;
+    if entered.before_tag {
+        let buffered = value
+            .serialize(serde_json::value::Serializer)
+            .map_err(E::custom)?;
+        object.buffered.push((key.to_owned(), buffered));
+    }
+
+    let result = f(&Checked {
+        value,
+        ctx: entered.ctx.clone(),
+    });
+    entered.leave(object);
+    result
+}
+
+// Checks the values of properties that came before the tag, now that the
+// variant they belong to is known.
+fn check_buffered<E: ser::Error>(object: &mut Object<'_>) -> Result<(), E> {
+    for (key, value) in std::mem::take(&mut object.buffered) {
+        let entered = enter(object, &key).map_err(E::custom)?;
+        let result = Checked {
+            value: &value,
+            ctx: entered.ctx.clone(),
+        }
+        .serialize(serde_json::value::Serializer);
+        entered.leave(object);
+        result.map_err(E::custom)?;
+    }
+
+    Ok(())
+}
+
+// An externally tagged enum variant, being serialized as an object with one
+// property.
+struct VariantFrame<'s> {
+    outer: Resolved<'s>,
+    object: Object<'s>,
+    entered: Entered<'s>,
+}
+
+impl<'s> VariantFrame<'s> {
+    fn finish(self) -> Result<(), Rejected> {
+        self.entered.leave(&self.object);
+        self.object.end()?;
+        self.outer.ctx.leave_nested();
+        self.outer.finish();
+        Ok(())
+    }
+}
+
+/// The type returned by [`Serializer`] for serializing compound values.
+///
+/// This type is not meant to be used directly. It implements all of serde's
+/// `Serialize*` traits, so that it can be used for any kind of compound value.
+pub struct Compound<'s, C> {
+    inner: C,
+    resolved: Resolved<'s>,
+    kind: Kind<'s>,
+    variant: Option<VariantFrame<'s>>,
+}
+
+impl<'s, C> Compound<'s, C> {
+    fn new(
+        inner: C,
+        resolved: Resolved<'s>,
+        kind: Kind<'s>,
+        variant: Option<VariantFrame<'s>>,
+    ) -> Self {
+        Self {
+            inner,
+            resolved,
+            kind,
+            variant,
+        }
+    }
+
+    fn element<T, F, E>(&mut self, value: &T, f: F) -> Result<(), E>
+    where
+        T: ?Sized + Serialize,
+        F: FnOnce(&mut C, &Checked<'_, 's, T>) -> Result<(), E>,
+        E: ser::Error,
+    {
+        match self.kind {
+            Kind::Elements {
+                ref ctx,
+                ref mut index,
+            } => {
+                ctx.check_len(*index + 1, true).map_err(E::custom)?;
+                ctx.push_instance_token(&index.to_string());
+                let result = f(
+                    &mut self.inner,
+                    &Checked {
+                        value,
+                        ctx: ctx.clone(),
+                    },
+                );
+                ctx.pop_instance_token();
+
+                *index += 1;
+                result
+            }
+            _ => {
+                if let Kind::Any { ref mut len } = self.kind {
+                    *len += 1;
+                    self.resolved.ctx.check_len(*len, true).map_err(E::custom)?;
+                }
+
+                f(
+                    &mut self.inner,
+                    &Checked {
+                        value,
+                        ctx: self.resolved.ctx.clone().with_empty(),
+                    },
+                )
+            }
+        }
+    }
+
+    fn field<T, F, E>(&mut self, key: &str, value: &T, f: F) -> Result<(), E>
+    where
+        T: ?Sized + Serialize,
+        F: FnOnce(&mut C, &Checked<'_, 's, T>) -> Result<(), E>,
+        E: ser::Error,
+    {
+        let inner = &mut self.inner;
+        match self.kind {
+            Kind::Object { ref mut object, .. } => {
+                property(object, key, value, |value| f(inner, value))
+            }
+            _ => f(
+                inner,
+                &Checked {
+                    value,
+                    ctx: self.resolved.ctx.clone().with_empty(),
+                },
+            ),
+        }
+    }
+
+    fn end<E: ser::Error>(self) -> Result<C, E> {
+        match self.kind {
+            Kind::Any { .. } => {}
+            Kind::Elements { ref ctx, .. } => ctx.pop_schema_token(),
+            Kind::Object { ref object, .. } => object.end().map_err(E::custom)?,
+        }
+
+        self.resolved.ctx.leave_nested();
+        self.resolved.finish();
+        if let Some(variant) = self.variant {
+            variant.finish().map_err(E::custom)?;
+        }
+
+        Ok(self.inner)
+    }
+}
+
+macro_rules! impl_seq {
+    ($($trait:ident::$method:ident;)*) => {
+        $(
+            impl<'s, C> ser::$trait for Compound<'s, C>
+            where
+                C: ser::$trait,
+            {
+                type Ok = C::Ok;
+                type Error = C::Error;
+
+                fn $method<T>(&mut self, value: &T) -> Result<(), C::Error>
+                where
+                    T: ?Sized + Serialize,
+                {
+                    self.element(value, |inner, value| inner.$method(value))
+                }
+
+                fn end(self) -> Result<C::Ok, C::Error> {
+                    self.end()?.end()
+                }
+            }
+        )*
+    };
+}
+
+impl_seq! {
+    SerializeSeq::serialize_element;
+    SerializeTuple::serialize_element;
+    SerializeTupleStruct::serialize_field;
+    SerializeTupleVariant::serialize_field;
+}
+
+macro_rules! impl_struct {
+    ($($trait:ident;)*) => {
+        $(
+            impl<'s, C> ser::$trait for Compound<'s, C>
+            where
+                C: ser::$trait,
+            {
+                type Ok = C::Ok;
+                type Error = C::Error;
+
+                fn serialize_field<T>(
+                    &mut self,
+                    key: &'static str,
+                    value: &T,
+                ) -> Result<(), C::Error>
+                where
+                    T: ?Sized + Serialize,
+                {
+                    self.field(key, value, |inner, value| inner.serialize_field(key, value))
+                }
+
+                fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+                    self.inner.skip_field(key)
+                }
+
+                fn end(self) -> Result<C::Ok, C::Error> {
+                    self.end()?.end()
+                }
+            }
+        )*
+    };
+}
+
+impl_struct! {
+    SerializeStruct;
+    SerializeStructVariant;
+}
+
+impl<'s, C> ser::SerializeMap for Compound<'s, C>
+where
+    C: ser::SerializeMap,
+{
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), C::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if let Kind::Object { key: ref mut k, .. } = self.kind {
+            *k = key
+                .serialize(Capture {
+                    strings_only: false,
+                })
+                .ok();
+        }
+
+        self.inner.serialize_key(key)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), C::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = match self.kind {
+            Kind::Object { ref mut key, .. } => key.take().unwrap_or_default(),
+            _ => String::new(),
+        };
+
+        self.field(&key, value, |inner, value| inner.serialize_value(value))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.end()?.end()
+    }
+}
+
+// A serializer that produces the string form of keys and discriminator tags.
+// Keys may also be numbers or booleans, which serializers like serde_json
+// turn into strings; tags may only be strings.
+#[derive(Clone, Copy)]
+struct Capture {
+    strings_only: bool,
+}
+
+#[derive(Debug)]
+struct NotAString;
+
+impl fmt::Display for NotAString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("not a string")
+    }
+}
+
+impl std::error::Error for NotAString {}
+
+impl ser::Error for NotAString {
+    fn custom<T: fmt::Display>(_: T) -> Self {
+        NotAString
+    }
+}
+
+macro_rules! capture_display {
+    ($($method:ident($ty:ty);)*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<String, NotAString> {
+                if self.strings_only {
+                    Err(NotAString)
+                } else {
+                    Ok(v.to_string())
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! capture_reject {
+    ($($method:ident($($ty:ty),*);)*) => {
+        $(
+            fn $method(self, $(_: $ty),*) -> Result<String, NotAString> {
+                Err(NotAString)
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for Capture {
+    type Ok = String;
+    type Error = NotAString;
+    type SerializeSeq = Impossible<String, NotAString>;
+    type SerializeTuple = Impossible<String, NotAString>;
+    type SerializeTupleStruct = Impossible<String, NotAString>;
+    type SerializeTupleVariant = Impossible<String, NotAString>;
+    type SerializeMap = Impossible<String, NotAString>;
+    type SerializeStruct = Impossible<String, NotAString>;
+    type SerializeStructVariant = Impossible<String, NotAString>;
+
+    capture_display! {
+        serialize_bool(bool);
+        serialize_i8(i8);
+        serialize_i16(i16);
+        serialize_i32(i32);
+        serialize_i64(i64);
+        serialize_i128(i128);
+        serialize_u8(u8);
+        serialize_u16(u16);
+        serialize_u32(u32);
+        serialize_u64(u64);
+        serialize_u128(u128);
+    }
+
+    capture_reject! {
+        serialize_f32(f32);
+        serialize_f64(f64);
+        serialize_bytes(&[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(&'static str);
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, NotAString> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, NotAString> {
+        Ok(v.to_owned())
+    }
+
+    fn serialize_some<T>(self, _: &T) -> Result<String, NotAString>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(NotAString)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<String, NotAString> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<String, NotAString>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<String, NotAString>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(NotAString)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, NotAString> {
+        Err(NotAString)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, NotAString> {
+        Err(NotAString)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, NotAString> {
+        Err(NotAString)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, NotAString> {
+        Err(NotAString)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, NotAString> {
+        Err(NotAString)
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, NotAString> {
+        Err(NotAString)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, NotAString> {
+        Err(NotAString)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: serde_json::Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    fn serialize<T: Serialize>(
+        schema: &Schema,
+        value: &T,
+    ) -> Result<String, SerializeError<serde_json::Error>> {
+        let mut out = Vec::new();
+        Serializer::new(&mut serde_json::Serializer::new(&mut out), schema).serialize(value)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn agrees_with_validate() {
+        let schema = schema(json!({
+            "definitions": {
+                "point": {
+                    "properties": {
+                        "x": { "type": "int8" },
+                        "y": { "type": "float64" }
+                    }
+                }
+            },
+            "properties": {
+                "points": { "elements": { "ref": "point", "nullable": true }},
+                "tags": { "values": { "enum": ["a", "b"] }},
+                "shape": {
+                    "discriminator": "kind",
+                    "mapping": {
+                        "circle": { "properties": { "r": { "type": "uint8" }}},
+                        "square": { "optionalProperties": { "side": { "type": "uint8" }}}
+                    }
+                }
+            },
+            "optionalProperties": {
+                "at": { "type": "timestamp" }
+            }
+        }));
+
+        let instances = [
+            json!({ "points": [], "tags": {}, "shape": { "kind": "square" }}),
+            json!({ "points": [null, { "x": 1, "y": 1.5 }], "tags": { "x": "a" }, "shape": { "kind": "circle", "r": 3 }, "at": "2020-01-01T00:00:00Z" }),
+            json!({ "points": [{ "x": 128, "y": 0 }], "tags": {}, "shape": { "kind": "square" }}),
+            json!({ "points": [{ "x": 1 }], "tags": {}, "shape": { "kind": "square" }}),
+            json!({ "points": [], "tags": { "x": "c" }, "shape": { "kind": "square" }}),
+            json!({ "points": [], "tags": {}, "shape": { "kind": "triangle" }}),
+            json!({ "points": [], "tags": {}, "shape": { "kind": 3 }}),
+            json!({ "points": [], "tags": {}, "shape": {}}),
+            json!({ "points": [], "tags": {}, "shape": { "kind": "circle", "r": -1 }}),
+            json!({ "points": [], "tags": {}, "shape": { "kind": "square", "r": 1 }}),
+            json!({ "points": [], "tags": {}, "shape": { "kind": "square" }, "at": "yesterday" }),
+            json!({ "points": [], "tags": {}, "shape": { "kind": "square" }, "extra": true }),
+            json!({ "points": {}, "tags": {}, "shape": { "kind": "square" }}),
+            json!(null),
+        ];
+
+        for instance in &instances {
+            let expected = crate::validate(
+                &schema,
+                instance,
+                crate::ValidateOptions::new().with_max_errors(1),
+            )
+            .unwrap()
+            .into_iter()
+            .next();
+
+            let actual = match serialize(&schema, instance) {
+                Ok(out) => {
+                    assert_eq!(instance.to_string(), out);
+                    None
+                }
+                Err(SerializeError::Invalid(indicator)) => Some(indicator),
+                Err(SerializeError::Validate(err)) => panic!("{}", err),
+                Err(SerializeError::Inner(err)) => panic!("{}", err),
+            };
+
+            assert_eq!(expected, actual, "{}", instance);
+        }
+    }
+
+    #[test]
+    fn rust_types() {
+        #[derive(serde::Serialize)]
+        #[serde(tag = "kind", rename_all = "lowercase")]
+        enum Shape {
+            Circle { r: u8 },
+            Square { side: Option<u8> },
+        }
+
+        #[derive(serde::Serialize)]
+        enum Event {
+            Drawn(Shape),
+            Erased { at: String },
+        }
+
+        let schema = schema(json!({
+            "definitions": {
+                "shape": {
+                    "discriminator": "kind",
+                    "mapping": {
+                        "circle": { "properties": { "r": { "type": "uint8" }}},
+                        "square": { "properties": { "side": { "type": "uint8", "nullable": true }}}
+                    }
+                }
+            },
+            "optionalProperties": {
+                "Drawn": { "ref": "shape" },
+                "Erased": { "properties": { "at": { "type": "timestamp" }}}
+            }
+        }));
+
+        assert_eq!(
+            r#"{"Drawn":{"kind":"square","side":null}}"#,
+            serialize(&schema, &Event::Drawn(Shape::Square { side: None })).unwrap()
+        );
+
+        match serialize(
+            &schema,
+            &Event::Erased {
+                at: "yesterday".into(),
+            },
+        ) {
+            Err(SerializeError::Invalid(indicator)) => {
+                assert_eq!(vec!["Erased", "at"], indicator.instance_path);
+                assert_eq!(
+                    vec!["optionalProperties", "Erased", "properties", "at", "type"],
+                    indicator.schema_path
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+
+        match serialize(&schema, &Event::Drawn(Shape::Circle { r: 3 })) {
+            Ok(out) => assert_eq!(r#"{"Drawn":{"kind":"circle","r":3}}"#, out),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn tag_after_properties() {
+        let schema = schema(json!({
+            "discriminator": "t",
+            "mapping": {
+                "a": { "properties": { "b": { "type": "boolean" }}}
+            }
+        }));
+
+        assert_eq!(
+            r#"{"b":true,"t":"a"}"#,
+            serialize(&schema, &json!({ "b": true, "t": "a" })).unwrap()
+        );
+
+        for b in [json!(5), json!({ "c": 5 }), json!({}), json!([1])] {
+            match serialize(&schema, &json!({ "b": b, "t": "a" })) {
+                Err(SerializeError::Invalid(indicator)) => {
+                    assert_eq!(vec!["b"], indicator.instance_path);
+                    assert_eq!(
+                        vec!["mapping", "a", "properties", "b", "type"],
+                        indicator.schema_path
+                    );
+                }
+                other => panic!("{:?}", other),
+            }
+        }
+    }
+}
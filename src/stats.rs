@@ -0,0 +1,530 @@
+//! Summarize validation results across a corpus of records.
+//!
+//! [`aggregate`] and [`Aggregator`] summarize a corpus that has been
+//! validated in full. [`Sampler`] validates just a sample of a corpus, and
+//! estimates what the full results would be.
+
+use crate::rng::Rng;
+use crate::{validate, Schema, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Violations across a corpus, grouped by the part of the schema that was
+/// violated.
+///
+/// This serializes with camelCase field names, so it can be handed directly to
+/// dashboards and other tools.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorpusStats {
+    /// The number of records in the corpus.
+    pub records: u64,
+
+    /// The number of records with at least one validation error.
+    pub invalid_records: u64,
+
+    /// Statistics for each schema path that any record violated, keyed by
+    /// that path, formatted as a JSON Pointer.
+    pub paths: BTreeMap<String, PathStats>,
+}
+
+/// Violations of one part of a schema across a corpus.
+///
+/// See [`CorpusStats`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathStats {
+    /// The number of validation errors with this schema path.
+    pub violations: u64,
+
+    /// The number of records with at least one validation error with this
+    /// schema path.
+    pub affected_records: u64,
+
+    /// The percentage of all records in the corpus that were affected.
+    pub affected_percentage: f64,
+
+    /// A few distinct examples of values that were rejected. For errors about
+    /// something missing, such as a required property, these are the objects
+    /// the thing is missing from.
+    pub examples: Vec<Value>,
+}
+
+/// Builds up [`CorpusStats`] one record at a time.
+///
+/// This is useful for corpora too large to hold in memory at once. For
+/// smaller ones, [`aggregate`] is more convenient.
+///
+/// ```
+/// use jtd::{Schema, stats::Aggregator};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "elements": { "type": "uint8" }
+/// })).unwrap()).unwrap();
+///
+/// let mut aggregator = Aggregator::new().with_max_examples(1);
+/// for instance in &[json!([1, 300, 400]), json!([2]), json!([500])] {
+///     let errors = jtd::validate(&schema, instance, Default::default()).unwrap();
+///     aggregator.add(instance, &errors);
+/// }
+///
+/// let stats = aggregator.finish();
+/// assert_eq!(2, stats.invalid_records);
+///
+/// let path = &stats.paths["/elements/type"];
+/// assert_eq!(3, path.violations);
+/// assert_eq!(2, path.affected_records);
+/// assert_eq!(vec![json!(300)], path.examples);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Aggregator {
+    stats: CorpusStats,
+    max_examples: usize,
+}
+
+impl Default for Aggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Aggregator {
+    /// Constructs an aggregator that keeps up to 3 examples per schema path.
+    pub fn new() -> Self {
+        Self {
+            stats: CorpusStats::default(),
+            max_examples: 3,
+        }
+    }
+
+    /// Sets the maximum number of examples to keep per schema path.
+    pub fn with_max_examples(mut self, max_examples: usize) -> Self {
+        self.max_examples = max_examples;
+        self
+    }
+
+    /// Adds a record, and the validation errors it produced.
+    pub fn add(&mut self, instance: &Value, errors: &[ValidationErrorIndicator]) {
+        self.stats.records += 1;
+        if !errors.is_empty() {
+            self.stats.invalid_records += 1;
+        }
+
+        let mut affected = BTreeSet::new();
+        for error in errors {
+            let schema_path = error.schema_path.to_string();
+            let path = self.stats.paths.entry(schema_path.clone()).or_default();
+
+            path.violations += 1;
+            if affected.insert(schema_path) {
+                path.affected_records += 1;
+            }
+
+            if path.examples.len() < self.max_examples {
+                if let Some(value) = error.instance_path.get(instance) {
+                    if !path.examples.contains(value) {
+                        path.examples.push(value.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Finishes aggregating, computing percentages.
+    pub fn finish(mut self) -> CorpusStats {
+        let records = self.stats.records;
+        for path in self.stats.paths.values_mut() {
+            path.affected_percentage = if records == 0 {
+                0.0
+            } else {
+                path.affected_records as f64 * 100.0 / records as f64
+            };
+        }
+
+        self.stats
+    }
+}
+
+/// Summarizes validation results across a corpus.
+///
+/// Each result is a record, and the validation errors it produced. See
+/// [`Aggregator`] to build up the summary one record at a time instead, or to
+/// change how many examples are kept.
+///
+/// ```
+/// use jtd::Schema;
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "id": { "type": "string" }}
+/// })).unwrap()).unwrap();
+///
+/// let instances = vec![json!({ "id": "a" }), json!({ "id": 1 }), json!({}), json!({})];
+/// let results: Vec<_> = instances
+///     .iter()
+///     .map(|instance| (instance, jtd::validate(&schema, instance, Default::default()).unwrap()))
+///     .collect();
+///
+/// let stats = jtd::stats::aggregate(&results);
+/// assert_eq!(4, stats.records);
+/// assert_eq!(3, stats.invalid_records);
+/// assert_eq!(50.0, stats.paths["/properties/id"].affected_percentage);
+/// assert_eq!(25.0, stats.paths["/properties/id/type"].affected_percentage);
+/// ```
+pub fn aggregate<'a, 'v: 'a, 's, I, E>(results: I) -> CorpusStats
+where
+    I: IntoIterator<Item = &'a (&'v Value, E)>,
+    E: AsRef<[ValidationErrorIndicator<'s>]> + 'a,
+{
+    let mut aggregator = Aggregator::new();
+    for (instance, errors) in results {
+        aggregator.add(instance, errors.as_ref());
+    }
+
+    aggregator.finish()
+}
+
+/// Validates a random sample of a corpus, and estimates how often each part of
+/// the schema is violated across the whole corpus.
+///
+/// Validating every record of a very large corpus can be expensive. A sampler
+/// instead validates each record it observes with some probability, the
+/// sampling rate, and from the records it did validate, estimates the
+/// fraction of all records that violate each schema path. Each estimate comes
+/// with a confidence interval, computed with the Wilson score method.
+///
+/// Sampling is pseudo-random, but deterministic for a given seed (see
+/// [`with_seed`][`Sampler::with_seed`]), so runs can be reproduced.
+///
+/// ```
+/// use jtd::{Schema, stats::Sampler};
+/// use serde_json::json;
+///
+/// let schema = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "id": { "type": "uint32" }}
+/// })).unwrap()).unwrap();
+///
+/// let mut sampler = Sampler::new(&schema, 0.1).with_seed(7);
+/// for i in 0..10_000 {
+///     // One record in four has a string id.
+///     let instance = if i % 4 == 0 { json!({ "id": "x" }) } else { json!({ "id": i }) };
+///     sampler.observe(&instance).unwrap();
+/// }
+///
+/// let summary = sampler.finish();
+/// assert_eq!(10_000, summary.observed);
+/// assert!(summary.sampled > 800 && summary.sampled < 1200);
+///
+/// let estimate = &summary.paths["/properties/id/type"];
+/// assert!(estimate.lower < 0.25 && 0.25 < estimate.upper);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Sampler<'s> {
+    schema: &'s Schema,
+    rate: f64,
+    confidence: f64,
+    options: ValidateOptions,
+    rng: Rng,
+    observed: u64,
+    aggregator: Aggregator,
+}
+
+/// The result of validating a sample of a corpus with a [`Sampler`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleSummary {
+    /// The number of records observed.
+    pub observed: u64,
+
+    /// The number of observed records that were sampled and validated.
+    pub sampled: u64,
+
+    /// The confidence level of the intervals in the estimates.
+    pub confidence: f64,
+
+    /// The estimated fraction of all records that are invalid.
+    pub invalid: RateEstimate,
+
+    /// For each schema path violated by any sampled record, the estimated
+    /// fraction of all records that violate it. Keyed by the schema path,
+    /// formatted as a JSON Pointer.
+    pub paths: BTreeMap<String, RateEstimate>,
+
+    /// Statistics about just the sampled records.
+    pub sample: CorpusStats,
+}
+
+/// An estimated fraction of records, with a confidence interval.
+///
+/// All fields are between 0 and 1.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct RateEstimate {
+    /// The fraction observed in the sample.
+    pub rate: f64,
+
+    /// The lower bound of the confidence interval.
+    pub lower: f64,
+
+    /// The upper bound of the confidence interval.
+    pub upper: f64,
+}
+
+impl<'s> Sampler<'s> {
+    /// Constructs a sampler that validates against `schema`, sampling records
+    /// with probability `rate`.
+    ///
+    /// `rate` is clamped to be between 0 and 1. By default, the seed is 0,
+    /// intervals are at the 95% confidence level, and validation uses the
+    /// default [`ValidateOptions`].
+    pub fn new(schema: &'s Schema, rate: f64) -> Self {
+        Self {
+            schema,
+            rate: rate.clamp(0.0, 1.0),
+            confidence: 0.95,
+            options: ValidateOptions::new(),
+            rng: Rng::new(0),
+            observed: 0,
+            aggregator: Aggregator::new(),
+        }
+    }
+
+    /// Sets the seed that determines which records are sampled.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Sets the confidence level of the intervals in the estimates, such as
+    /// `0.95` or `0.99`. It must be strictly between 0 and 1.
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Sets the options used to validate sampled records.
+    pub fn with_validate_options(mut self, options: ValidateOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets how examples are kept for [`SampleSummary::sample`].
+    pub fn with_max_examples(mut self, max_examples: usize) -> Self {
+        self.aggregator = self.aggregator.with_max_examples(max_examples);
+        self
+    }
+
+    /// Observes a record, validating it if it's sampled.
+    ///
+    /// Returns whether the record was sampled.
+    pub fn observe(&mut self, instance: &Value) -> Result<bool, ValidateError> {
+        self.observed += 1;
+        if self.rng.next_f64() >= self.rate {
+            return Ok(false);
+        }
+
+        let errors = validate(self.schema, instance, self.options.clone())?;
+        self.aggregator.add(instance, &errors);
+        Ok(true)
+    }
+
+    /// Finishes sampling, and computes estimates.
+    pub fn finish(self) -> SampleSummary {
+        let sample = self.aggregator.finish();
+        let z = probit(0.5 + self.confidence / 2.0);
+
+        SampleSummary {
+            observed: self.observed,
+            sampled: sample.records,
+            confidence: self.confidence,
+            invalid: wilson(sample.invalid_records, sample.records, z),
+            paths: sample
+                .paths
+                .iter()
+                .map(|(path, stats)| {
+                    let estimate = wilson(stats.affected_records, sample.records, z);
+                    (path.clone(), estimate)
+                })
+                .collect(),
+            sample,
+        }
+    }
+}
+
+// The Wilson score interval for successes out of trials, at the given z.
+fn wilson(successes: u64, trials: u64, z: f64) -> RateEstimate {
+    if trials == 0 {
+        return RateEstimate {
+            rate: 0.0,
+            lower: 0.0,
+            upper: 1.0,
+        };
+    }
+
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = z * z;
+
+    let denominator = 1.0 + z2 / n;
+    let center = (p + z2 / (2.0 * n)) / denominator;
+    let half_width = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denominator;
+
+    RateEstimate {
+        rate: p,
+        lower: (center - half_width).max(0.0),
+        upper: (center + half_width).min(1.0),
+    }
+}
+
+// The inverse of the standard normal CDF, using Acklam's rational
+// approximation, which has a relative error below 1.2e-9.
+fn probit(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+
+    if p < 0.02425 {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - 0.02425 {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn counts_records_once_per_path() {
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({
+                "properties": { "a": { "elements": { "type": "boolean" }}}
+            }))
+            .unwrap(),
+        )
+        .unwrap();
+
+        let instances = [
+            json!({ "a": [1, 1, 2, 3, 4] }),
+            json!({ "a": [true] }),
+            json!({ "a": "x" }),
+        ];
+
+        let results: Vec<_> = instances
+            .iter()
+            .map(|i| (i, crate::validate(&schema, i, Default::default()).unwrap()))
+            .collect();
+
+        assert_eq!(
+            json!({
+                "records": 3,
+                "invalidRecords": 2,
+                "paths": {
+                    "/properties/a/elements/type": {
+                        "violations": 5,
+                        "affectedRecords": 1,
+                        "affectedPercentage": 100.0 / 3.0,
+                        "examples": [1, 2, 3],
+                    },
+                    "/properties/a/elements": {
+                        "violations": 1,
+                        "affectedRecords": 1,
+                        "affectedPercentage": 100.0 / 3.0,
+                        "examples": ["x"],
+                    },
+                },
+            }),
+            serde_json::to_value(aggregate(&results)).unwrap()
+        );
+
+        assert_eq!(CorpusStats::default(), Aggregator::new().finish());
+    }
+
+    #[test]
+    fn wilson_intervals() {
+        let z = probit(0.975);
+        assert!((z - 1.959964).abs() < 1e-6);
+        assert!((probit(0.005) + 2.575829).abs() < 1e-6);
+
+        let estimate = wilson(10, 100, z);
+        assert_eq!(0.1, estimate.rate);
+        assert!((estimate.lower - 0.05523).abs() < 1e-5);
+        assert!((estimate.upper - 0.17437).abs() < 1e-5);
+
+        let estimate = wilson(0, 10, z);
+        assert_eq!(0.0, estimate.lower);
+        assert!(estimate.upper > 0.0);
+    }
+
+    #[test]
+    fn sampling_is_reproducible() {
+        let schema = crate::Schema::from_serde_schema(
+            serde_json::from_value(json!({ "type": "boolean" })).unwrap(),
+        )
+        .unwrap();
+
+        let run = |seed| {
+            let mut sampler = Sampler::new(&schema, 0.5).with_seed(seed);
+            (0..100)
+                .map(|i| sampler.observe(&json!(i)).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(1), run(1));
+        assert_ne!(run(1), run(2));
+
+        let mut everything = Sampler::new(&schema, 2.0);
+        let mut nothing = Sampler::new(&schema, -1.0);
+        for _ in 0..10 {
+            assert!(everything.observe(&json!(1)).unwrap());
+            assert!(!nothing.observe(&json!(1)).unwrap());
+        }
+
+        let summary = everything.finish();
+        assert_eq!(1.0, summary.invalid.rate);
+        assert_eq!(1.0, summary.paths["/type"].upper);
+
+        let summary = nothing.finish();
+        assert_eq!(10, summary.observed);
+        assert_eq!(0, summary.sampled);
+        assert!(summary.paths.is_empty());
+    }
+}
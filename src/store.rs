@@ -0,0 +1,482 @@
+use crate::{FromSerdeSchemaError, Schema, SerdeSchema, Type};
+use serde::de::Error as _;
+use serde::ser::SerializeMap as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// A fingerprint of a schema's contents.
+///
+/// Two schemas have the same fingerprint exactly when they are equal. The
+/// fingerprint is the SHA-256 hash of the schema's canonical JSON form: the
+/// JSON produced by [`Schema::into_serde_schema`], with object keys sorted
+/// and no insignificant whitespace. It's written out as 64 lowercase hex
+/// digits.
+///
+/// ```
+/// use jtd::{Fingerprint, Schema};
+/// use serde_json::json;
+///
+/// let a = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "a": {}, "b": {} }
+/// })).unwrap()).unwrap();
+///
+/// let b = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "b": {}, "a": {} }
+/// })).unwrap()).unwrap();
+///
+/// assert_eq!(Fingerprint::of(&a), Fingerprint::of(&b));
+///
+/// let fingerprint = Fingerprint::of(&a);
+/// assert_eq!(fingerprint, fingerprint.to_string().parse().unwrap());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    /// Computes the fingerprint of a schema.
+    pub fn of(schema: &Schema) -> Self {
+        let canonical = serde_json::to_vec(&Canonical(schema)).expect("serialize schema");
+        Self(Sha256::digest(canonical).into())
+    }
+
+    /// The raw bytes of the fingerprint.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Indicates a string is not a valid [`Fingerprint`].
+///
+/// Fingerprints are written as exactly 64 lowercase hex digits.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("invalid fingerprint: {0:?}")]
+pub struct ParseFingerprintError(pub String);
+
+impl FromStr for Fingerprint {
+    type Err = ParseFingerprintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseFingerprintError(s.to_owned());
+        let is_hex = |b: &u8| b.is_ascii_digit() || (b'a'..=b'f').contains(b);
+        if s.len() != 64 || !s.as_bytes().iter().all(is_hex) {
+            return Err(err());
+        }
+
+        let mut bytes = [0; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).map_err(|_| err())?;
+        }
+
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for Fingerprint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Fingerprint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// A content-addressable collection of schemas.
+///
+/// Each schema in the store is keyed by its [`Fingerprint`], so storing the
+/// same schema twice keeps just one copy, and a fingerprint always refers to
+/// the same schema. Entries that are no longer needed can be removed in bulk
+/// with [`gc`][`SchemaStore::gc`].
+///
+/// Schemas are kept behind an [`Arc`], so cloning a store is cheap, and the
+/// clone shares its schemas with the original. Schemas can also be shared
+/// with the rest of a program: [`put`][`SchemaStore::put`] takes an
+/// `Arc<Schema>` as well as a [`Schema`], and [`get`][`SchemaStore::get`]
+/// returns the `Arc`.
+///
+/// A store serializes as a JSON object mapping fingerprints to schemas.
+/// Deserializing a store checks that each schema has the fingerprint it's
+/// stored under.
+///
+/// ```
+/// use jtd::{Schema, SchemaStore};
+/// use serde_json::json;
+///
+/// let user = Schema::from_serde_schema(serde_json::from_value(json!({
+///     "properties": { "name": { "type": "string" }}
+/// })).unwrap()).unwrap();
+///
+/// let mut store = SchemaStore::new();
+/// let fingerprint = store.put(user.clone());
+/// assert_eq!(fingerprint, store.put(user.clone()));
+/// assert_eq!(Some(&user), store.get(&fingerprint).map(|schema| &**schema));
+///
+/// let json = serde_json::to_string(&store).unwrap();
+/// assert_eq!(store, serde_json::from_str(&json).unwrap());
+///
+/// assert_eq!(1, store.gc(&[]));
+/// assert!(store.is_empty());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchemaStore {
+    entries: BTreeMap<Fingerprint, Arc<Schema>>,
+}
+
+/// Errors that may arise from deserializing a [`SchemaStore`].
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum SchemaStoreError {
+    /// A stored schema could not be converted from a [`SerdeSchema`].
+    #[error("invalid schema stored under {0}: {1}")]
+    InvalidSchema(Fingerprint, FromSerdeSchemaError),
+
+    /// A stored schema was stored under a fingerprint other than its own. The
+    /// first field is the fingerprint it was stored under, and the second is
+    /// its actual fingerprint.
+    #[error("schema stored under {0} has fingerprint {1}")]
+    FingerprintMismatch(Fingerprint, Fingerprint),
+}
+
+impl SchemaStore {
+    /// Constructs an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a schema to the store, returning its fingerprint.
+    ///
+    /// If the schema is already in the store, the store is unchanged.
+    pub fn put(&mut self, schema: impl Into<Arc<Schema>>) -> Fingerprint {
+        let schema = schema.into();
+        let fingerprint = Fingerprint::of(&schema);
+        self.entries.entry(fingerprint).or_insert(schema);
+        fingerprint
+    }
+
+    /// Gets the schema with the given fingerprint, if it's in the store.
+    ///
+    /// The schema can be cloned out of the store cheaply, to outlive it or to
+    /// be sent to other threads.
+    pub fn get(&self, fingerprint: &Fingerprint) -> Option<&Arc<Schema>> {
+        self.entries.get(fingerprint)
+    }
+
+    /// Whether the schema with the given fingerprint is in the store.
+    pub fn contains(&self, fingerprint: &Fingerprint) -> bool {
+        self.entries.contains_key(fingerprint)
+    }
+
+    /// Removes the schema with the given fingerprint, returning it if it was
+    /// in the store.
+    pub fn remove(&mut self, fingerprint: &Fingerprint) -> Option<Arc<Schema>> {
+        self.entries.remove(fingerprint)
+    }
+
+    /// Removes every schema whose fingerprint is not among `referenced`.
+    ///
+    /// Returns the number of schemas removed.
+    pub fn gc<'a, I>(&mut self, referenced: I) -> usize
+    where
+        I: IntoIterator<Item = &'a Fingerprint>,
+    {
+        let referenced: BTreeSet<_> = referenced.into_iter().collect();
+        let before = self.entries.len();
+        self.entries
+            .retain(|fingerprint, _| referenced.contains(fingerprint));
+
+        before - self.entries.len()
+    }
+
+    /// Iterates over the fingerprints and schemas in the store, in order of
+    /// fingerprint.
+    pub fn iter(&self) -> impl Iterator<Item = (&Fingerprint, &Arc<Schema>)> {
+        self.entries.iter()
+    }
+
+    /// The number of schemas in the store.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Constructs a store from serialized entries, checking each schema
+    /// against the fingerprint it's stored under.
+    pub fn from_serde_entries(
+        entries: BTreeMap<Fingerprint, SerdeSchema>,
+    ) -> Result<Self, SchemaStoreError> {
+        let mut store = Self::new();
+        for (fingerprint, serde_schema) in entries {
+            let schema = Schema::from_serde_schema(serde_schema)
+                .map_err(|err| SchemaStoreError::InvalidSchema(fingerprint, err))?;
+
+            let actual = Fingerprint::of(&schema);
+            if actual != fingerprint {
+                return Err(SchemaStoreError::FingerprintMismatch(fingerprint, actual));
+            }
+
+            store.entries.insert(fingerprint, schema.into());
+        }
+
+        Ok(store)
+    }
+}
+
+impl Serialize for SchemaStore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            self.entries
+                .iter()
+                .map(|(fingerprint, schema)| (fingerprint, Canonical(schema))),
+        )
+    }
+}
+
+impl<'de> Deserialize<'de> for SchemaStore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::from_serde_entries(BTreeMap::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+// A schema, serialized just as the SerdeSchema that Schema::into_serde_schema
+// turns it into, but without having to take a copy of the schema apart.
+struct Canonical<'a>(&'a Schema);
+
+// Definitions or properties, serialized as with Canonical.
+struct CanonicalMap<'a>(&'a BTreeMap<String, Schema>);
+
+impl Serialize for Canonical<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // The keywords are in the order of the fields of SerdeSchema.
+        let schema = self.0;
+        let mut map = serializer.serialize_map(None)?;
+
+        if !schema.metadata().is_empty() {
+            map.serialize_entry("metadata", schema.metadata())?;
+        }
+
+        if !schema.definitions().is_empty() {
+            map.serialize_entry("definitions", &CanonicalMap(schema.definitions()))?;
+        }
+
+        if !matches!(schema, Schema::Empty { .. }) && schema.nullable() {
+            map.serialize_entry("nullable", &true)?;
+        }
+
+        match schema {
+            Schema::Empty { .. } => {}
+            Schema::Ref { ref_, .. } => map.serialize_entry("ref", ref_)?,
+            Schema::Type { type_, .. } => map.serialize_entry("type", type_keyword(type_))?,
+            Schema::Enum { enum_, .. } => map.serialize_entry("enum", enum_)?,
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { enum_, .. } => map.serialize_entry("enum", enum_)?,
+            Schema::Elements { elements, .. } => {
+                map.serialize_entry("elements", &Canonical(elements))?
+            }
+            Schema::Properties {
+                properties,
+                optional_properties,
+                properties_is_present,
+                additional_properties,
+                ..
+            } => {
+                if *properties_is_present {
+                    map.serialize_entry("properties", &CanonicalMap(properties))?;
+                }
+
+                if !properties_is_present || !optional_properties.is_empty() {
+                    map.serialize_entry("optionalProperties", &CanonicalMap(optional_properties))?;
+                }
+
+                if *additional_properties {
+                    map.serialize_entry("additionalProperties", &true)?;
+                }
+            }
+            Schema::Values {
+                values,
+                #[cfg(feature = "extensions")]
+                keys,
+                ..
+            } => {
+                map.serialize_entry("values", &Canonical(values))?;
+
+                #[cfg(feature = "extensions")]
+                if let Some(keys) = keys {
+                    map.serialize_entry("keys", &CanonicalKeys(keys))?;
+                }
+            }
+            Schema::Discriminator {
+                discriminator,
+                mapping,
+                ..
+            } => {
+                map.serialize_entry("discriminator", discriminator)?;
+                map.serialize_entry("mapping", &CanonicalMap(mapping))?;
+            }
+        }
+
+        map.end()
+    }
+}
+
+impl Serialize for CanonicalMap<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            self.0
+                .iter()
+                .map(|(name, schema)| (name, Canonical(schema))),
+        )
+    }
+}
+
+// The keys of a values-form schema, serialized as with Canonical.
+#[cfg(feature = "extensions")]
+struct CanonicalKeys<'a>(&'a crate::Keys);
+
+#[cfg(feature = "extensions")]
+impl Serialize for CanonicalKeys<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        match self.0 {
+            crate::Keys::Enum(enum_) => map.serialize_entry("enum", enum_)?,
+            crate::Keys::String { pattern } => {
+                map.serialize_entry("type", "string")?;
+                if let Some(pattern) = pattern {
+                    map.serialize_entry("pattern", pattern.as_str())?;
+                }
+            }
+        }
+
+        map.end()
+    }
+}
+
+fn type_keyword(type_: &Type) -> &str {
+    match type_ {
+        Type::Boolean => "boolean",
+        Type::Int8 => "int8",
+        Type::Uint8 => "uint8",
+        Type::Int16 => "int16",
+        Type::Uint16 => "uint16",
+        Type::Int32 => "int32",
+        Type::Uint32 => "uint32",
+        Type::Float32 => "float32",
+        Type::Float64 => "float64",
+        Type::String => "string",
+        Type::Timestamp => "timestamp",
+        #[cfg(feature = "extensions")]
+        Type::Custom(name) => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: serde_json::Value) -> Schema {
+        Schema::from_serde_schema(serde_json::from_value(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn gc_keeps_referenced() {
+        let mut store = SchemaStore::new();
+        let a = store.put(schema(json!({ "type": "string" })));
+        let b = store.put(schema(json!({ "type": "uint8" })));
+        let c = store.put(schema(json!({ "elements": {} })));
+        assert_eq!(3, store.len());
+
+        assert_eq!(1, store.gc(&[a, c]));
+        assert!(store.contains(&a));
+        assert!(!store.contains(&b));
+        assert!(store.contains(&c));
+    }
+
+    #[test]
+    fn clones_share_schemas() {
+        let shared = Arc::new(schema(json!({ "type": "string" })));
+
+        let mut store = SchemaStore::new();
+        let a = store.put(Arc::clone(&shared));
+        let clone = store.clone();
+
+        assert!(Arc::ptr_eq(&shared, clone.get(&a).unwrap()));
+        assert!(Arc::ptr_eq(&shared, &store.remove(&a).unwrap()));
+        assert!(clone.contains(&a));
+    }
+
+    #[test]
+    fn fingerprints_canonical_form() {
+        #[derive(serde::Deserialize)]
+        struct TestCase {
+            schema: SerdeSchema,
+        }
+
+        let test_cases: BTreeMap<String, TestCase> =
+            serde_json::from_str(include_str!("../json-typedef-spec/tests/validation.json"))
+                .unwrap();
+
+        let schemas = test_cases
+            .into_values()
+            .map(|test_case| Schema::from_serde_schema(test_case.schema).unwrap());
+
+        #[cfg(feature = "extensions")]
+        let schemas = schemas.chain(std::iter::once(
+            Schema::from_serde_schema(
+                serde_json::from_value(json!({
+                    "definitions": {
+                        "a": { "values": {}, "keys": { "type": "string", "pattern": "^a" }}
+                    },
+                    "properties": {
+                        "b": { "values": { "enum": [1, 2] }, "keys": { "enum": ["x"] }},
+                        "c": { "values": {}, "keys": { "type": "string" }}
+                    }
+                }))
+                .unwrap(),
+            )
+            .unwrap(),
+        ));
+
+        for schema in schemas {
+            let canonical = serde_json::to_vec(&schema.clone().into_serde_schema()).unwrap();
+            assert_eq!(canonical, serde_json::to_vec(&Canonical(&schema)).unwrap());
+        }
+    }
+
+    #[test]
+    fn deserialize_checks_fingerprints() {
+        let mut store = SchemaStore::new();
+        let a = store.put(schema(json!({ "type": "string" })));
+
+        let tampered = json!({ a.to_string(): { "type": "uint8" }});
+        let err = serde_json::from_value::<SchemaStore>(tampered).unwrap_err();
+        assert!(err.to_string().contains("has fingerprint"));
+
+        let invalid = json!({ a.to_string(): { "type": "nope" }});
+        assert!(serde_json::from_value::<SchemaStore>(invalid).is_err());
+
+        assert!("abc".parse::<Fingerprint>().is_err());
+        assert!(a.to_string().to_uppercase().parse::<Fingerprint>().is_err());
+    }
+}
@@ -0,0 +1,554 @@
+//! Shared machinery for the validators that check data as it streams through
+//! serde, rather than as a [`serde_json::Value`].
+
+use crate::{Schema, Severity, Type, ValidateError, ValidateOptions, ValidationErrorIndicator};
+use jtd_core::__private::{resolve_ref_with, ValidateOptionsExt as _};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::rc::Rc;
+
+// A validation error, already recorded in the State, to be turned into a
+// serde error to abort with.
+pub(crate) struct Rejected(pub(crate) String);
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+pub(crate) struct State {
+    pub(crate) instance_tokens: Vec<String>,
+    pub(crate) schema_tokens: Vec<Vec<String>>,
+    pub(crate) error: Option<ValidationErrorIndicator<'static>>,
+    // The per-value limit of the options that was exceeded, if any.
+    pub(crate) limit: Option<ValidateError>,
+    pub(crate) options: ValidateOptions,
+    // The number of arrays and objects the value being checked is nested in.
+    depth: usize,
+}
+
+// Where in the schema a value being (de)serialized is to be checked.
+#[derive(Clone)]
+pub(crate) struct Ctx<'s> {
+    pub(crate) root: &'s Schema,
+    pub(crate) schema: &'s Schema,
+    pub(crate) parent_tag: Option<&'s str>,
+    pub(crate) state: Rc<RefCell<State>>,
+}
+
+// A Ctx whose refs have been resolved.
+pub(crate) struct Resolved<'s> {
+    pub(crate) ctx: Ctx<'s>,
+    pub(crate) nullable: bool,
+    ref_frames: usize,
+}
+
+impl<'s> Ctx<'s> {
+    pub(crate) fn new(schema: &'s Schema) -> Self {
+        Ctx {
+            root: schema,
+            schema,
+            parent_tag: None,
+            state: Rc::new(RefCell::new(State {
+                instance_tokens: vec![],
+                schema_tokens: vec![vec![]],
+                error: None,
+                limit: None,
+                options: ValidateOptions::new(),
+                depth: 0,
+            })),
+        }
+    }
+
+    pub(crate) fn child(&self, schema: &'s Schema) -> Self {
+        Ctx {
+            root: self.root,
+            schema,
+            parent_tag: None,
+            state: self.state.clone(),
+        }
+    }
+
+    pub(crate) fn resolve(self) -> Result<Resolved<'s>, Rejected> {
+        let mut nullable = false;
+        let mut ref_frames = 0;
+
+        let schema = resolve_ref_with(self.schema, self.root, |schema| {
+            if let Schema::Ref {
+                ref_,
+                nullable: ref_nullable,
+                ..
+            } = schema
+            {
+                nullable |= *ref_nullable;
+                self.state
+                    .borrow_mut()
+                    .schema_tokens
+                    .push(vec!["definitions".to_owned(), ref_.clone()]);
+                ref_frames += 1;
+            }
+        })
+        .map_err(|err| Rejected(err.to_string()))?;
+
+        Ok(Resolved {
+            ctx: Ctx { schema, ..self },
+            nullable,
+            ref_frames,
+        })
+    }
+
+    pub(crate) fn push_schema_token(&self, token: &str) {
+        let mut state = self.state.borrow_mut();
+        state
+            .schema_tokens
+            .last_mut()
+            .unwrap()
+            .push(token.to_owned());
+    }
+
+    pub(crate) fn pop_schema_token(&self) {
+        let mut state = self.state.borrow_mut();
+        state.schema_tokens.last_mut().unwrap().pop();
+    }
+
+    pub(crate) fn push_instance_token(&self, token: &str) {
+        self.state
+            .borrow_mut()
+            .instance_tokens
+            .push(token.to_owned());
+    }
+
+    pub(crate) fn pop_instance_token(&self) {
+        self.state.borrow_mut().instance_tokens.pop();
+    }
+
+    // Records a validation error at the current location, plus any extra
+    // schema and instance tokens, and returns a serde error to abort with.
+    pub(crate) fn error(&self, schema_tokens: &[&str], instance_token: Option<&str>) -> Rejected {
+        let mut state = self.state.borrow_mut();
+
+        let instance_path = state
+            .instance_tokens
+            .iter()
+            .map(|t| Cow::Owned(t.clone()))
+            .chain(instance_token.map(|t| Cow::Owned(t.to_owned())))
+            .collect();
+
+        let schema_path = state
+            .schema_tokens
+            .last()
+            .unwrap()
+            .iter()
+            .map(|t| Cow::Owned(t.clone()))
+            .chain(schema_tokens.iter().map(|t| Cow::Owned((*t).to_owned())))
+            .collect();
+
+        // These validators reject data as soon as it violates the schema, so
+        // they treat every violation as an error, whatever its severity.
+        let indicator = ValidationErrorIndicator {
+            instance_path,
+            schema_path,
+            severity: Severity::Error,
+        };
+
+        let err = Rejected(format!(
+            "instance at {} rejected by schema at {}",
+            indicator.instance_path, indicator.schema_path
+        ));
+
+        state.error = Some(indicator);
+        err
+    }
+
+    // Records that a per-value limit of the options was exceeded, and returns
+    // a serde error to abort with.
+    fn limit_exceeded(&self, err: ValidateError) -> Rejected {
+        let rejected = Rejected(err.to_string());
+        self.state.borrow_mut().limit = Some(err);
+        rejected
+    }
+
+    // Whether the options have any per-value limits. Without them, the parts
+    // of values that aren't checked against a schema needn't be visited.
+    pub(crate) fn has_limits(&self) -> bool {
+        self.state.borrow().options.has_limits()
+    }
+
+    // Enters an array or object, checking its nesting against the options.
+    pub(crate) fn enter_nested(&self) -> Result<(), Rejected> {
+        let mut state = self.state.borrow_mut();
+        state.depth += 1;
+
+        let max = state.options.max_instance_depth();
+        if max != 0 && state.depth > max {
+            drop(state);
+            return Err(self.limit_exceeded(ValidateError::MaxInstanceDepthExceeded));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn leave_nested(&self) {
+        self.state.borrow_mut().depth -= 1;
+    }
+
+    // Checks the number of elements of an array, or keys of an object, seen
+    // so far.
+    pub(crate) fn check_len(&self, len: usize, is_array: bool) -> Result<(), Rejected> {
+        let (max, err) = {
+            let options = &self.state.borrow().options;
+            if is_array {
+                (options.max_array_len(), ValidateError::ArrayTooLong)
+            } else {
+                (options.max_object_keys(), ValidateError::TooManyObjectKeys)
+            }
+        };
+
+        if max != 0 && len > max {
+            return Err(self.limit_exceeded(err));
+        }
+
+        Ok(())
+    }
+
+    // Checks a string, or a key of an object, against the max string length
+    // of the options.
+    fn check_str_len(&self, s: &str) -> Result<(), Rejected> {
+        let max = self.state.borrow().options.max_string_len();
+        if max != 0 && s.len() > max {
+            return Err(self.limit_exceeded(ValidateError::StringTooLong));
+        }
+
+        Ok(())
+    }
+
+    // The keyword whose schema path is reported when a value is of the wrong
+    // JSON type for this schema.
+    fn form_keyword(&self) -> &'static str {
+        match self.schema {
+            Schema::Empty { .. } | Schema::Ref { .. } => "",
+            Schema::Type { .. } => "type",
+            Schema::Enum { .. } => "enum",
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { .. } => "enum",
+            Schema::Elements { .. } => "elements",
+            Schema::Properties {
+                properties_is_present,
+                ..
+            } => {
+                if *properties_is_present {
+                    "properties"
+                } else {
+                    "optionalProperties"
+                }
+            }
+            Schema::Values { .. } => "values",
+            Schema::Discriminator { .. } => "discriminator",
+        }
+    }
+
+    pub(crate) fn wrong_type(&self) -> Rejected {
+        self.error(&[self.form_keyword()], None)
+    }
+
+    pub(crate) fn check_int(&self, val: f64) -> Result<(), Rejected> {
+        let (min, max) = match self.schema {
+            Schema::Empty { .. } => return Ok(()),
+            Schema::Type { type_, .. } => match type_ {
+                Type::Float32 | Type::Float64 => return Ok(()),
+                Type::Int8 => (-128.0, 127.0),
+                Type::Uint8 => (0.0, 255.0),
+                Type::Int16 => (-32768.0, 32767.0),
+                Type::Uint16 => (0.0, 65535.0),
+                Type::Int32 => (-2147483648.0, 2147483647.0),
+                Type::Uint32 => (0.0, 4294967295.0),
+                _ => return Err(self.wrong_type()),
+            },
+            #[cfg(feature = "extensions")]
+            Schema::IntEnum { enum_, .. } => {
+                return if jtd_core::__private::int_enum_contains(enum_, val) {
+                    Ok(())
+                } else {
+                    Err(self.wrong_type())
+                };
+            }
+            _ => return Err(self.wrong_type()),
+        };
+
+        if val.fract() != 0.0 || val < min || val > max {
+            Err(self.wrong_type())
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn check_str(&self, s: &str) -> Result<(), Rejected> {
+        self.check_str_len(s)?;
+
+        let ok = match self.schema {
+            Schema::Empty { .. } => true,
+            Schema::Type {
+                type_: Type::String,
+                ..
+            } => true,
+            Schema::Type {
+                type_: Type::Timestamp,
+                ..
+            } => self.state.borrow().options.is_timestamp(s),
+            Schema::Enum { enum_, .. } => self.state.borrow().options.enum_contains(enum_, s),
+            _ => false,
+        };
+
+        if ok {
+            Ok(())
+        } else {
+            Err(self.wrong_type())
+        }
+    }
+
+    pub(crate) fn check_bool(&self) -> Result<(), Rejected> {
+        match self.schema {
+            Schema::Empty { .. }
+            | Schema::Type {
+                type_: Type::Boolean,
+                ..
+            } => Ok(()),
+            _ => Err(self.wrong_type()),
+        }
+    }
+
+    pub(crate) fn check_null(&self, nullable: bool) -> Result<(), Rejected> {
+        if nullable || self.schema.nullable() {
+            Ok(())
+        } else {
+            Err(self.wrong_type())
+        }
+    }
+}
+
+impl<'s> Resolved<'s> {
+    pub(crate) fn finish(&self) {
+        let mut state = self.ctx.state.borrow_mut();
+        for _ in 0..self.ref_frames {
+            state.schema_tokens.pop();
+        }
+    }
+}
+
+impl<'s> Ctx<'s> {
+    pub(crate) fn check_bytes(&self) -> Result<(), Rejected> {
+        // There's no JSON equivalent to a byte string, so only the empty form
+        // accepts them.
+        match self.schema {
+            Schema::Empty { .. } => Ok(()),
+            _ => Err(self.wrong_type()),
+        }
+    }
+}
+
+// Tracks the properties of an object-like schema (properties, values, or
+// discriminator form) as an object is (de)serialized key by key.
+pub(crate) struct Object<'s> {
+    pub(crate) ctx: Ctx<'s>,
+    seen: BTreeSet<String>,
+
+    // For the discriminator form, the variant chosen by the tag, once known,
+    // and the properties seen before the tag.
+    variant: Option<(String, &'s Schema)>,
+    before_tag: Vec<String>,
+
+    // The values of properties seen before the tag, for those that keep them
+    // to check once the variant is known.
+    pub(crate) buffered: Vec<(String, Value)>,
+}
+
+// What to do with the value of a property.
+pub(crate) enum Property<'s> {
+    // Check it against a sub-schema, found at the given schema tokens.
+    Check(Vec<String>, &'s Schema),
+
+    // Check it against nothing.
+    Skip,
+
+    // It comes before the discriminator's tag, so it can't be checked yet.
+    BeforeTag,
+
+    // It's the discriminator's tag.
+    Tag,
+}
+
+impl<'s> Object<'s> {
+    pub(crate) fn new(ctx: Ctx<'s>) -> Self {
+        Self {
+            ctx,
+            seen: BTreeSet::new(),
+            variant: None,
+            before_tag: vec![],
+            buffered: vec![],
+        }
+    }
+
+    pub(crate) fn property(&mut self, key: &str) -> Result<Property<'s>, Rejected> {
+        self.seen.insert(key.to_owned());
+        self.ctx.check_str_len(key)?;
+        self.ctx.check_len(self.seen.len(), false)?;
+
+        match self.ctx.schema {
+            // Objects are only checked against empty schemas for the
+            // per-value limits of the options.
+            Schema::Empty { .. } => Ok(Property::Skip),
+            Schema::Properties { .. } => {
+                Self::properties_property(&self.ctx, self.ctx.schema, &[], self.ctx.parent_tag, key)
+            }
+            Schema::Values {
+                values,
+                #[cfg(feature = "extensions")]
+                keys,
+                ..
+            } => {
+                #[cfg(feature = "extensions")]
+                if let Some(keys) = keys {
+                    let rejected = self.ctx.state.borrow().options.reject_key(keys, key);
+                    if let Some(keyword) = rejected {
+                        return Err(self.ctx.error(&["keys", keyword], Some(key)));
+                    }
+                }
+
+                Ok(Property::Check(vec!["values".to_owned()], values))
+            }
+            Schema::Discriminator { discriminator, .. } => {
+                if key == discriminator {
+                    return Ok(Property::Tag);
+                }
+
+                match self.variant {
+                    Some((ref tag, variant)) => Self::properties_property(
+                        &self.ctx,
+                        variant,
+                        &["mapping", tag],
+                        Some(discriminator),
+                        key,
+                    ),
+                    None => {
+                        self.before_tag.push(key.to_owned());
+                        Ok(Property::BeforeTag)
+                    }
+                }
+            }
+            _ => unreachable!("object of non-object schema"),
+        }
+    }
+
+    fn properties_property(
+        ctx: &Ctx<'s>,
+        schema: &'s Schema,
+        prefix: &[&str],
+        parent_tag: Option<&str>,
+        key: &str,
+    ) -> Result<Property<'s>, Rejected> {
+        if let Schema::Properties {
+            properties,
+            optional_properties,
+            additional_properties,
+            ..
+        } = schema
+        {
+            let path = |keyword: &str| {
+                prefix
+                    .iter()
+                    .copied()
+                    .chain(vec![keyword, key])
+                    .map(|t| t.to_owned())
+                    .collect()
+            };
+
+            if parent_tag == Some(key) {
+                Ok(Property::Skip)
+            } else if let Some(sub_schema) = properties.get(key) {
+                Ok(Property::Check(path("properties"), sub_schema))
+            } else if let Some(sub_schema) = optional_properties.get(key) {
+                Ok(Property::Check(path("optionalProperties"), sub_schema))
+            } else if *additional_properties {
+                Ok(Property::Skip)
+            } else {
+                Err(ctx.error(prefix, Some(key)))
+            }
+        } else {
+            Err(Rejected("non-properties schema in mapping".to_owned()))
+        }
+    }
+
+    pub(crate) fn tag(&mut self, tag: Option<&str>) -> Result<(), Rejected> {
+        if let Schema::Discriminator {
+            discriminator,
+            mapping,
+            ..
+        } = self.ctx.schema
+        {
+            let tag = tag.ok_or_else(|| self.ctx.error(&["discriminator"], Some(discriminator)))?;
+            let variant = mapping
+                .get(tag)
+                .ok_or_else(|| self.ctx.error(&["mapping"], Some(discriminator)))?;
+
+            // Now that the variant is known, check that the properties that
+            // came before the tag belong to it.
+            for key in &self.before_tag {
+                Self::properties_property(
+                    &self.ctx,
+                    variant,
+                    &["mapping", tag],
+                    Some(discriminator),
+                    key,
+                )?;
+            }
+
+            self.variant = Some((tag.to_owned(), variant));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn end(&self) -> Result<(), Rejected> {
+        let (schema, prefix): (_, Vec<&str>) = match (self.ctx.schema, &self.variant) {
+            (Schema::Properties { .. }, _) => (self.ctx.schema, vec![]),
+            (Schema::Discriminator { .. }, Some((tag, variant))) => {
+                (*variant, vec!["mapping", tag])
+            }
+            (Schema::Discriminator { .. }, None) => {
+                return Err(self.ctx.error(&["discriminator"], None))
+            }
+            _ => return Ok(()),
+        };
+
+        if let Schema::Properties { properties, .. } = schema {
+            for name in properties.keys() {
+                if !self.seen.contains(name) {
+                    let mut tokens = prefix.clone();
+                    tokens.extend(&["properties", name]);
+                    return Err(self.ctx.error(&tokens, None));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'s> Ctx<'s> {
+    // Makes this context accept anything.
+    pub(crate) fn with_empty(self) -> Self {
+        Ctx {
+            schema: &EMPTY,
+            ..self
+        }
+    }
+}
+
+static EMPTY: Schema = Schema::Empty {
+    definitions: std::collections::BTreeMap::new(),
+    metadata: std::collections::BTreeMap::new(),
+};
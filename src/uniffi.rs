@@ -0,0 +1,228 @@
+//! Validate from Kotlin, Swift, and other languages with
+//! [UniFFI](https://mozilla.github.io/uniffi-rs/).
+//!
+//! Mobile apps often validate the same payloads as the backend they talk to.
+//! This module exposes schema parsing and validation through UniFFI, so that
+//! they can do so with the same engine, and get back the same error paths.
+//!
+//! The interface is made up of [`JtdSchema`], which parses and validates a
+//! schema once and can then validate any number of instances, the
+//! [`JtdValidationError`] records it returns, and [`JtdError`] for when
+//! something goes wrong. Schemas and instances cross the boundary as JSON
+//! text.
+//!
+//! To generate bindings, depend on this crate with the `uniffi` feature from
+//! a crate built as a `cdylib` or `staticlib`, and run `uniffi-bindgen` on
+//! the resulting library as usual.
+//!
+//! This module is only available with the `uniffi` feature.
+//!
+//! ```
+//! use jtd::uniffi::JtdSchema;
+//!
+//! let schema = JtdSchema::new(r#"{ "elements": { "type": "uint8" }}"#.into()).unwrap();
+//!
+//! let errors = schema.validate("[1, 2, 300]".into()).unwrap();
+//! assert_eq!(1, errors.len());
+//! assert_eq!(vec!["2"], errors[0].instance_path);
+//! assert_eq!(vec!["elements", "type"], errors[0].schema_path);
+//! ```
+
+use crate::{validate, Schema, SerdeSchema, Severity, ValidateOptions};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// A schema, parsed and checked to be valid, ready to validate instances.
+#[derive(Debug, ::uniffi::Object)]
+pub struct JtdSchema {
+    schema: Schema,
+}
+
+/// A validation error, as returned by [`JtdSchema::validate`].
+///
+/// This is [`ValidationErrorIndicator`][`crate::ValidationErrorIndicator`],
+/// in a form that can be passed across the FFI boundary.
+#[derive(Clone, Debug, PartialEq, Eq, ::uniffi::Record)]
+pub struct JtdValidationError {
+    /// A path to the part of the instance that was rejected.
+    pub instance_path: Vec<String>,
+
+    /// A path to the part of the schema that rejected the instance.
+    pub schema_path: Vec<String>,
+
+    /// How serious the error is.
+    pub severity: JtdSeverity,
+}
+
+/// How serious a [`JtdValidationError`] is. See [`Severity`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ::uniffi::Enum)]
+pub enum JtdSeverity {
+    /// See [`Severity::Info`].
+    Info,
+
+    /// See [`Severity::Warning`].
+    Warning,
+
+    /// See [`Severity::Error`].
+    Error,
+}
+
+/// Errors that may arise from [`JtdSchema`].
+///
+/// Only the message of each error is passed across the FFI boundary.
+#[derive(Debug, Error, ::uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum JtdError {
+    /// The schema or instance was not valid JSON, or the schema was not a
+    /// JSON representation of a schema.
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// The schema was well-formed, but not valid.
+    #[error("invalid schema: {0}")]
+    Schema(String),
+
+    /// Validating the instance failed.
+    #[error(transparent)]
+    Validate(#[from] crate::ValidateError),
+}
+
+#[::uniffi::export]
+impl JtdSchema {
+    /// Parses a schema from JSON text, and checks that it's valid.
+    #[uniffi::constructor]
+    pub fn new(schema: String) -> Result<Arc<Self>, JtdError> {
+        let serde_schema: SerdeSchema = serde_json::from_str(&schema)?;
+        let schema = Schema::from_serde_schema(serde_schema)
+            .map_err(|err| JtdError::Schema(err.to_string()))?;
+
+        schema
+            .validate()
+            .map_err(|err| JtdError::Schema(err.to_string()))?;
+
+        Ok(Arc::new(Self { schema }))
+    }
+
+    /// Validates an instance, given as JSON text, with default options.
+    pub fn validate(&self, instance: String) -> Result<Vec<JtdValidationError>, JtdError> {
+        self.validate_with_options(instance, 0, 0, JtdSeverity::Error)
+    }
+
+    /// Validates an instance, given as JSON text, with the given options.
+    ///
+    /// These are the options of [`ValidateOptions::with_max_depth`],
+    /// [`ValidateOptions::with_max_errors`], and
+    /// [`ValidateOptions::with_min_severity`]. A limit of zero means no
+    /// limit.
+    pub fn validate_with_options(
+        &self,
+        instance: String,
+        max_depth: u32,
+        max_errors: u32,
+        min_severity: JtdSeverity,
+    ) -> Result<Vec<JtdValidationError>, JtdError> {
+        let instance: serde_json::Value = serde_json::from_str(&instance)?;
+        let options = ValidateOptions::new()
+            .with_max_depth(max_depth as usize)
+            .with_max_errors(max_errors as usize)
+            .with_min_severity(match min_severity {
+                JtdSeverity::Info => Severity::Info,
+                JtdSeverity::Warning => Severity::Warning,
+                JtdSeverity::Error => Severity::Error,
+            });
+
+        Ok(validate(&self.schema, &instance, options)?
+            .into_iter()
+            .map(|error| {
+                let severity = match error.severity {
+                    Severity::Info => JtdSeverity::Info,
+                    Severity::Warning => JtdSeverity::Warning,
+                    Severity::Error => JtdSeverity::Error,
+                };
+
+                let (instance_path, schema_path) = error.into_owned_paths();
+                JtdValidationError {
+                    instance_path,
+                    schema_path,
+                    severity,
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates() {
+        let schema = JtdSchema::new(
+            r#"{
+                "properties": { "a": { "type": "string" }},
+                "optionalProperties": {
+                    "b": {
+                        "metadata": { "severityOnViolation": "warning" },
+                        "type": "uint8"
+                    }
+                }
+            }"#
+            .into(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![JtdValidationError {
+                instance_path: vec![],
+                schema_path: vec!["properties".into(), "a".into()],
+                severity: JtdSeverity::Error,
+            }],
+            schema.validate(r#"{ "b": -1 }"#.into()).unwrap()
+        );
+
+        assert_eq!(
+            vec![
+                JtdValidationError {
+                    instance_path: vec![],
+                    schema_path: vec!["properties".into(), "a".into()],
+                    severity: JtdSeverity::Error,
+                },
+                JtdValidationError {
+                    instance_path: vec!["b".into()],
+                    schema_path: vec!["optionalProperties".into(), "b".into(), "type".into()],
+                    severity: JtdSeverity::Warning,
+                },
+            ],
+            schema
+                .validate_with_options(r#"{ "b": -1 }"#.into(), 0, 0, JtdSeverity::Warning)
+                .unwrap()
+        );
+
+        assert_eq!(
+            1,
+            schema
+                .validate_with_options(r#"{ "b": -1 }"#.into(), 0, 1, JtdSeverity::Info)
+                .unwrap()
+                .len()
+        );
+
+        assert!(matches!(
+            schema.validate("{".into()),
+            Err(JtdError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_schemas() {
+        assert!(matches!(
+            JtdSchema::new("[]".into()),
+            Err(JtdError::Json(_))
+        ));
+        assert_eq!(
+            "invalid schema: no such definition: \"a\"",
+            JtdSchema::new(r#"{ "ref": "a" }"#.into())
+                .unwrap_err()
+                .to_string()
+        );
+    }
+}
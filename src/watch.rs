@@ -0,0 +1,269 @@
+use crate::{FromSerdeSchemaError, Schema, SchemaValidateError, SerdeSchema};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use thiserror::Error;
+
+/// A set of schemas, keyed by name, as loaded by [`WatchedSchemas`].
+pub type SchemaSet = BTreeMap<String, Arc<Schema>>;
+
+/// A set of schemas loaded from a directory, which reloads itself whenever
+/// the directory changes.
+///
+/// Every file in the directory whose name ends in `.jtd.json` is loaded as a
+/// schema, named after the file with that suffix removed. So `user.jtd.json`
+/// becomes the schema named `user`. Other files are ignored, as are
+/// subdirectories.
+///
+/// Each schema must parse and pass [`Schema::validate`]. When the directory
+/// changes, all of its schemas are reloaded, and if every one of them is ok,
+/// the whole set is swapped in at once. Callers holding on to a schema (or to
+/// a whole [`SchemaSet`]) from before the swap keep a consistent view of the
+/// old one. If any schema fails to load, the previous set stays active, and
+/// the error is kept for [`last_error`][`WatchedSchemas::last_error`].
+///
+/// This type is only available with the `watch` feature.
+///
+/// ```
+/// use jtd::WatchedSchemas;
+/// use serde_json::json;
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// std::fs::write(dir.path().join("user.jtd.json"), r#"{ "type": "string" }"#).unwrap();
+///
+/// let schemas = WatchedSchemas::new(dir.path()).unwrap();
+/// let user = schemas.get("user").unwrap();
+/// assert!(jtd::validate(&user, &json!("John"), Default::default()).unwrap().is_empty());
+/// ```
+pub struct WatchedSchemas {
+    shared: Arc<Shared>,
+
+    // Dropping the watcher stops it, so it's kept alive here.
+    _watcher: RecommendedWatcher,
+}
+
+struct Shared {
+    dir: PathBuf,
+    active: RwLock<Arc<SchemaSet>>,
+    last_error: Mutex<Option<Arc<WatchError>>>,
+}
+
+/// Errors that may arise from loading or watching schemas with
+/// [`WatchedSchemas`].
+///
+/// The errors that concern a particular file include its path.
+#[derive(Debug, Error)]
+pub enum WatchError {
+    /// A file or directory could not be read.
+    #[error("{}: {1}", .0.display())]
+    Io(PathBuf, #[source] std::io::Error),
+
+    /// A file did not contain a JSON Typedef schema.
+    #[error("{}: {1}", .0.display())]
+    Json(PathBuf, #[source] serde_json::Error),
+
+    /// A file's schema could not be converted from a [`SerdeSchema`].
+    #[error("{}: {1}", .0.display())]
+    Schema(PathBuf, #[source] FromSerdeSchemaError),
+
+    /// A file's schema did not pass [`Schema::validate`].
+    #[error("{}: {1}", .0.display())]
+    Invalid(PathBuf, #[source] SchemaValidateError),
+
+    /// The directory could not be watched.
+    #[error(transparent)]
+    Notify(#[from] notify::Error),
+}
+
+const SUFFIX: &str = ".jtd.json";
+
+impl WatchedSchemas {
+    /// Loads the schemas in `dir`, and starts watching it for changes.
+    ///
+    /// Unlike later reloads, which keep the previous set active on failure,
+    /// this returns an error if any schema fails to load.
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self, WatchError> {
+        let dir = dir.as_ref().to_owned();
+        let shared = Arc::new(Shared {
+            active: RwLock::new(Arc::new(load_dir(&dir)?)),
+            last_error: Mutex::new(None),
+            dir,
+        });
+
+        let handler = Arc::clone(&shared);
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+                Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+                Ok(_) => {
+                    let _ = handler.reload();
+                }
+                Err(err) => handler.set_error(err.into()),
+            })?;
+
+        watcher.watch(&shared.dir, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            shared,
+            _watcher: watcher,
+        })
+    }
+
+    /// The directory being watched.
+    pub fn dir(&self) -> &Path {
+        &self.shared.dir
+    }
+
+    /// Gets the active schema with the given name.
+    pub fn get(&self, name: &str) -> Option<Arc<Schema>> {
+        self.schemas().get(name).cloned()
+    }
+
+    /// Gets the whole set of active schemas.
+    pub fn schemas(&self) -> Arc<SchemaSet> {
+        Arc::clone(&self.shared.active.read().unwrap())
+    }
+
+    /// Reloads the schemas right away, without waiting for the directory to
+    /// change.
+    ///
+    /// This is done automatically whenever the directory changes, so it's
+    /// rarely necessary to call this directly.
+    pub fn reload(&self) -> Result<(), Arc<WatchError>> {
+        self.shared.reload()
+    }
+
+    /// The error from the most recent reload or watch failure, if any. This
+    /// is cleared by a successful reload.
+    pub fn last_error(&self) -> Option<Arc<WatchError>> {
+        self.shared.last_error.lock().unwrap().clone()
+    }
+}
+
+impl Shared {
+    fn reload(&self) -> Result<(), Arc<WatchError>> {
+        match load_dir(&self.dir) {
+            Ok(schemas) => {
+                *self.active.write().unwrap() = Arc::new(schemas);
+                *self.last_error.lock().unwrap() = None;
+                Ok(())
+            }
+            Err(err) => {
+                let err = Arc::new(err);
+                *self.last_error.lock().unwrap() = Some(Arc::clone(&err));
+                Err(err)
+            }
+        }
+    }
+
+    fn set_error(&self, err: WatchError) {
+        *self.last_error.lock().unwrap() = Some(Arc::new(err));
+    }
+}
+
+fn load_dir(dir: &Path) -> Result<SchemaSet, WatchError> {
+    let entries = fs::read_dir(dir).map_err(|err| WatchError::Io(dir.to_owned(), err))?;
+
+    let mut schemas = SchemaSet::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|err| WatchError::Io(dir.to_owned(), err))?
+            .path();
+
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.len() > SUFFIX.len() && name.ends_with(SUFFIX) => {
+                name[..name.len() - SUFFIX.len()].to_owned()
+            }
+            _ => continue,
+        };
+
+        if !path.is_file() {
+            continue;
+        }
+
+        let schema = load_file(&path)?;
+        schemas.insert(name, Arc::new(schema));
+    }
+
+    Ok(schemas)
+}
+
+fn load_file(path: &Path) -> Result<Schema, WatchError> {
+    let bytes = fs::read(path).map_err(|err| WatchError::Io(path.to_owned(), err))?;
+
+    let serde_schema: SerdeSchema =
+        serde_json::from_slice(&bytes).map_err(|err| WatchError::Json(path.to_owned(), err))?;
+
+    let schema = Schema::from_serde_schema(serde_schema)
+        .map_err(|err| WatchError::Schema(path.to_owned(), err))?;
+
+    schema
+        .validate()
+        .map_err(|err| WatchError::Invalid(path.to_owned(), err))?;
+
+    Ok(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn write(dir: &Path, name: &str, contents: &str) {
+        fs::write(dir.join(name), contents).unwrap();
+    }
+
+    #[test]
+    fn reload_keeps_previous_set_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "a.jtd.json", r#"{ "type": "string" }"#);
+        write(dir.path(), "b.jtd.json", r#"{ "elements": {} }"#);
+        write(dir.path(), "notes.json", "not a schema");
+
+        // This goes through Shared directly, since a watcher reloading in the
+        // background would race with the assertions below.
+        let shared = Shared {
+            dir: dir.path().to_owned(),
+            active: RwLock::new(Arc::new(load_dir(dir.path()).unwrap())),
+            last_error: Mutex::new(None),
+        };
+
+        let before = Arc::clone(&shared.active.read().unwrap());
+        assert_eq!(vec!["a", "b"], before.keys().collect::<Vec<_>>());
+
+        write(dir.path(), "b.jtd.json", r#"{ "ref": "nope" }"#);
+        assert!(matches!(
+            *shared.reload().unwrap_err(),
+            WatchError::Invalid(_, SchemaValidateError::NoSuchDefinition(_))
+        ));
+        assert_eq!(before, *shared.active.read().unwrap());
+        assert!(shared.last_error.lock().unwrap().is_some());
+
+        write(dir.path(), "b.jtd.json", r#"{ "values": {} }"#);
+        shared.reload().unwrap();
+        assert!(shared.last_error.lock().unwrap().is_none());
+        assert!(matches!(
+            *shared.active.read().unwrap()["b"],
+            Schema::Values { .. }
+        ));
+
+        // The old set is unaffected by the swap.
+        assert!(matches!(*before["b"], Schema::Elements { .. }));
+    }
+
+    #[test]
+    fn reloads_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let schemas = WatchedSchemas::new(dir.path()).unwrap();
+        assert!(schemas.schemas().is_empty());
+
+        write(dir.path(), "c.jtd.json", r#"{ "type": "boolean" }"#);
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while schemas.get("c").is_none() {
+            assert!(Instant::now() < deadline, "schema was not reloaded");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! The test suite of the JSON Typedef specification, from the
+//! json-typedef-spec submodule.
+
+use jtd::{Schema, SerdeSchema, ValidateOptions};
+use std::collections::{BTreeMap, HashSet};
+
+#[test]
+fn invalid_schemas() {
+    let test_cases: BTreeMap<String, serde_json::Value> = serde_json::from_str(include_str!(
+        "../json-typedef-spec/tests/invalid_schemas.json"
+    ))
+    .expect("parse invalid_schemas.json");
+
+    for (test_case_name, test_case) in test_cases {
+        if let Ok(serde_schema) = serde_json::from_value::<SerdeSchema>(test_case) {
+            if let Ok(schema) = Schema::from_serde_schema(serde_schema) {
+                if schema.validate().is_ok() {
+                    panic!(
+                        "failed to detect invalid schema: {}, got: {:?}",
+                        test_case_name, schema
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn valid_schemas() {
+    #[derive(serde::Deserialize)]
+    struct TestCase {
+        schema: serde_json::Value,
+    }
+
+    let test_cases: BTreeMap<String, TestCase> =
+        serde_json::from_str(include_str!("../json-typedef-spec/tests/validation.json"))
+            .expect("parse validation.json");
+
+    for (test_case_name, test_case) in test_cases {
+        let serde_schema =
+            serde_json::from_value::<SerdeSchema>(test_case.schema).expect(&test_case_name);
+        let schema = Schema::from_serde_schema(serde_schema).expect(&test_case_name);
+        schema.validate().expect(&test_case_name);
+    }
+}
+
+#[test]
+fn validation_spec() {
+    #[derive(serde::Deserialize, PartialEq, Debug, Eq, Hash)]
+    struct TestCaseError {
+        #[serde(rename = "instancePath")]
+        instance_path: Vec<String>,
+
+        #[serde(rename = "schemaPath")]
+        schema_path: Vec<String>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct TestCase {
+        schema: SerdeSchema,
+        instance: serde_json::Value,
+        errors: Vec<TestCaseError>,
+    }
+
+    let test_cases: BTreeMap<String, TestCase> =
+        serde_json::from_str(include_str!("../json-typedef-spec/tests/validation.json"))
+            .expect("parse validation.json");
+
+    for (test_case_name, test_case) in test_cases {
+        let schema = Schema::from_serde_schema(test_case.schema).expect(&test_case_name);
+        schema.validate().expect(&test_case_name);
+
+        let errors: HashSet<_> =
+            jtd::validate(&schema, &test_case.instance, ValidateOptions::new())
+                .expect(&test_case_name)
+                .into_iter()
+                .map(|err| err.into_owned_paths())
+                .map(|(instance_path, schema_path)| TestCaseError {
+                    instance_path,
+                    schema_path,
+                })
+                .collect();
+
+        let test_case_errors: HashSet<_> = test_case.errors.into_iter().collect();
+
+        assert_eq!(
+            test_case_errors, errors,
+            "wrong validation errors returned: {}",
+            &test_case_name
+        );
+    }
+}